@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use chrono::TimeZone;
+use chrono_tz::Tz;
+use mongodb::bson::DateTime;
+use serde::Deserialize;
+
+/// How many weeks the activity chart shows, about 3 months.
+pub const WEEKS: i64 = 13;
+
+pub const WEEK: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// The longest bar of the chart.
+const BAR_WIDTH: i64 = 10;
+
+/// How many lawsuits were opened and ruled on in each week since the start of the chart.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Activity {
+    pub opened: Vec<WeekCount>,
+    pub verdicts: Vec<WeekCount>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeekCount {
+    /// The week since the start, counting from 0.
+    #[serde(rename = "_id")]
+    pub week: i64,
+    pub count: i64,
+}
+
+impl Activity {
+    fn counts(counts: &[WeekCount]) -> Vec<i64> {
+        (0..WEEKS)
+            .map(|week| {
+                counts
+                    .iter()
+                    .filter(|count| count.week == week)
+                    .map(|count| count.count)
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// A line per week with a bar for the opened lawsuits and one for the verdicts, labeled with
+    /// the day the week starts in the time zone of the guild.
+    pub fn chart(&self, start: DateTime, tz: Tz) -> String {
+        let (opened, verdicts) = (Self::counts(&self.opened), Self::counts(&self.verdicts));
+        let max = opened.iter().chain(&verdicts).copied().max().unwrap_or(0);
+        let bar = |count: i64| match count {
+            0 => String::new(),
+            // every week with activity gets at least a sliver
+            _ => format!(
+                "{} ",
+                "█".repeat(((count * BAR_WIDTH + max - 1) / max) as usize)
+            ),
+        };
+
+        (0..WEEKS as usize)
+            .map(|week| {
+                let millis = start.timestamp_millis() + week as i64 * WEEK.as_millis() as i64;
+                let label = tz.timestamp_millis(millis).format("%d.%m.");
+                format!(
+                    "`{label}` 📂 {}{} ⚖️ {}{}",
+                    bar(opened[week]),
+                    opened[week],
+                    bar(verdicts[week]),
+                    verdicts[week]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Europe::Zurich;
+    use mongodb::bson::DateTime;
+
+    use super::{Activity, WeekCount, WEEKS};
+
+    #[test]
+    fn chart() {
+        let activity = Activity {
+            opened: vec![
+                WeekCount { week: 0, count: 4 },
+                WeekCount { week: 12, count: 1 },
+            ],
+            verdicts: vec![WeekCount { week: 0, count: 2 }],
+        };
+        // 2022-07-04 00:00 in Zurich
+        let start = DateTime::from_millis(1_656_885_600_000);
+        let chart = activity.chart(start, Zurich);
+        let lines = chart.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), WEEKS as usize);
+        assert_eq!(lines[0], "`04.07.` 📂 ██████████ 4 ⚖️ █████ 2");
+        assert_eq!(lines[1], "`11.07.` 📂 0 ⚖️ 0");
+        assert_eq!(lines[12], "`26.09.` 📂 ███ 1 ⚖️ 0");
+    }
+}
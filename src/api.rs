@@ -0,0 +1,271 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{Extension, Path},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use color_eyre::Result;
+use mongodb::bson;
+use poise::serenity_prelude::{GuildId, Http, UserId};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::{
+    dashboard,
+    dashboard::OAuthConfig,
+    keyword_watch,
+    lawsuit::{Lawsuit, LawsuitStatus},
+    maintenance::Maintenance,
+    model::{SnowflakeId, State},
+    webhook, Mongo, WrapErr,
+};
+
+#[derive(Clone)]
+struct Api {
+    mongo: Mongo,
+    http: Arc<Http>,
+    maintenance: Maintenance,
+}
+
+/// An error response with a JSON body.
+struct ApiError(StatusCode, &'static str);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<color_eyre::Report> for ApiError {
+    fn from(err: color_eyre::Report) -> Self {
+        error!(?err, "Error handling api request");
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+    }
+}
+
+type ApiResult = std::result::Result<Json<Value>, ApiError>;
+
+#[derive(Debug, Deserialize)]
+struct NewReport {
+    reporter: String,
+    accused: String,
+    reason: String,
+}
+
+/// Only the hash of the token of a guild is stored.
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Serves the HTTP API for external dashboards. Every guild has its own token, which is sent as
+/// `Authorization: Bearer <token>`. The web dashboard is served on the same address if it is
+/// configured.
+pub async fn serve(
+    addr: SocketAddr,
+    mongo: Mongo,
+    http: Arc<Http>,
+    maintenance: Maintenance,
+    oauth: Option<OAuthConfig>,
+) -> Result<()> {
+    let mut app = Router::new()
+        .route("/guilds/:guild_id/lawsuits", get(lawsuits))
+        .route("/guilds/:guild_id/prisoners", get(prisoners))
+        .route("/guilds/:guild_id/stats", get(stats))
+        .route("/guilds/:guild_id/reports", post(file_report))
+        .layer(Extension(Api {
+            mongo: mongo.clone(),
+            http: http.clone(),
+            maintenance: maintenance.clone(),
+        }));
+
+    if let Some(oauth) = oauth {
+        app = app.merge(dashboard::router(mongo, http, maintenance, oauth));
+    }
+
+    info!(%addr, "Starting api server");
+
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await
+        .wrap_err("run api server")
+}
+
+/// Loads the state of the guild if the request has its token.
+async fn authorize(api: &Api, guild_id: u64, headers: &HeaderMap) -> Result<State, ApiError> {
+    let token = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(ApiError(StatusCode::UNAUTHORIZED, "missing token"))?;
+
+    // never create a state here, unauthenticated requests must not write anything
+    let state = api
+        .mongo
+        .guild(GuildId(guild_id))
+        .find_raw_state(SnowflakeId(guild_id))
+        .await?
+        .ok_or(ApiError(StatusCode::UNAUTHORIZED, "invalid token"))?;
+    let state = bson::from_document::<State>(state).wrap_err("invalid state")?;
+    match &state.api_token_hash {
+        Some(hash) if constant_time_eq(hash.as_bytes(), hash_token(token).as_bytes()) => Ok(state),
+        _ => Err(ApiError(StatusCode::UNAUTHORIZED, "invalid token")),
+    }
+}
+
+/// Compares without returning early, so the time taken does not leak how much of the hash matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The API has no user, so it only sees the lawsuits that a member without a part in them sees.
+fn visible_lawsuits(state: &State) -> Vec<&Lawsuit> {
+    state
+        .lawsuits
+        .iter()
+        .filter(|l| l.is_visible_to(SnowflakeId(0), false))
+        .collect()
+}
+
+async fn lawsuits(
+    Extension(api): Extension<Api>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+) -> ApiResult {
+    let state = authorize(&api, guild_id, &headers).await?;
+
+    let lawsuits = visible_lawsuits(&state)
+        .into_iter()
+        .map(|l| {
+            let mut data = webhook::lawsuit_data(l);
+            data["status"] = json!(l.status);
+            data
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!(lawsuits)))
+}
+
+async fn prisoners(
+    Extension(api): Extension<Api>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+) -> ApiResult {
+    let state = authorize(&api, guild_id, &headers).await?;
+
+    let prisoners = api
+        .mongo
+        .guild(state.guild_id.into())
+        .find_prisoners(state.guild_id)
+        .await?
+        .iter()
+        .map(|entry| entry.user_id.to_string())
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!(prisoners)))
+}
+
+async fn stats(
+    Extension(api): Extension<Api>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+) -> ApiResult {
+    let state = authorize(&api, guild_id, &headers).await?;
+
+    let lawsuits = visible_lawsuits(&state);
+    let count = |status: LawsuitStatus| lawsuits.iter().filter(|l| l.status == status).count();
+    let open = lawsuits.iter().filter(|l| !l.is_closed()).count();
+    let ruled = lawsuits.iter().filter(|l| l.verdict.is_some()).count();
+    let prisoners = api
+        .mongo
+        .guild(state.guild_id.into())
+        .find_prisoners(state.guild_id)
+        .await?
+        .len();
+
+    Ok(Json(json!({
+        "lawsuits": lawsuits.len(),
+        "open": open,
+        "ruled": ruled,
+        "withdrawn": count(LawsuitStatus::Withdrawn),
+        "dismissed": count(LawsuitStatus::Dismissed),
+        "no_billed": count(LawsuitStatus::NoBilled),
+        "merged": count(LawsuitStatus::Merged),
+        "prisoners": prisoners,
+    })))
+}
+
+/// Posts a report to the review channel, where moderators can turn it into a lawsuit.
+async fn file_report(
+    Extension(api): Extension<Api>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+    Json(report): Json<NewReport>,
+) -> ApiResult {
+    if api.maintenance.is_active() {
+        return Err(ApiError(StatusCode::SERVICE_UNAVAILABLE, "maintenance"));
+    }
+    let state = authorize(&api, guild_id, &headers).await?;
+
+    let channel_id = match &state.keyword_watch {
+        Some(watch) => watch.channel_id,
+        None => {
+            return Err(ApiError(
+                StatusCode::CONFLICT,
+                "no review channel configured",
+            ))
+        }
+    };
+    let (reporter, accused) = match (report.reporter.parse(), report.accused.parse()) {
+        (Ok(reporter), Ok(accused)) => (UserId(reporter), UserId(accused)),
+        _ => return Err(ApiError(StatusCode::BAD_REQUEST, "invalid user id")),
+    };
+    if report.reason.trim().is_empty() {
+        return Err(ApiError(StatusCode::BAD_REQUEST, "missing reason"));
+    }
+
+    keyword_watch::send_report(
+        &api.http,
+        channel_id,
+        accused,
+        report.reason,
+        format!("API, gmeldet vo <@{reporter}>"),
+    )
+    .await?;
+
+    Ok(Json(json!({ "filed": true })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_lawsuits_are_hidden() {
+        let mut state = State::new(SnowflakeId(1));
+        for number in 1..=3 {
+            let mut lawsuit = Lawsuit::new(
+                SnowflakeId(2),
+                SnowflakeId(3),
+                SnowflakeId(4),
+                "Spam".to_string(),
+            );
+            lawsuit.number = number;
+            lawsuit.sealed = number == 2;
+            state.lawsuits.push(lawsuit);
+        }
+
+        let numbers = visible_lawsuits(&state)
+            .iter()
+            .map(|l| l.number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, [1, 3]);
+    }
+}
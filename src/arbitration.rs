@@ -0,0 +1,211 @@
+use color_eyre::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use poise::serenity_prelude::{ButtonStyle, CreateEmbed, GuildId, Http, UserId};
+use tracing::{info, warn};
+
+use crate::{
+    handler::Response,
+    lawsuit::{Lawsuit, Party},
+    model::SnowflakeId,
+    notification, Mongo, WrapErr,
+};
+
+/// The prefix of the custom id of the button the other party uses to submit their statement,
+/// followed by `{guild_id}:{lawsuit_id}`.
+pub const STATEMENT_ID_PREFIX: &str = "arbitration-statement:";
+
+/// The prefix of the custom id of the buttons the arbiter uses to rule,
+/// followed by `{guild_id}:{lawsuit_id}:{winner}`.
+pub const RULING_ID_PREFIX: &str = "arbitration-ruling:";
+
+/// Parses the `{guild_id}:{lawsuit_id}` part of an arbitration custom id and returns the rest.
+pub fn parse_custom_id(id: &str) -> Option<(GuildId, Uuid, &str)> {
+    let mut parts = id.splitn(3, ':');
+    let guild_id = parts.next()?.parse::<u64>().ok()?;
+    let lawsuit_id = Uuid::parse_str(parts.next()?).ok()?;
+    Some((
+        GuildId(guild_id),
+        lawsuit_id,
+        parts.next().unwrap_or_default(),
+    ))
+}
+
+/// Parses the winner from the rest of a ruling custom id.
+pub fn parse_ruling_winner(winner: &str) -> Option<Party> {
+    match winner {
+        "plaintiff" => Some(Party::Plaintiff),
+        "accused" => Some(Party::Accused),
+        _ => None,
+    }
+}
+
+fn statements_embed<'a>(embed: &'a mut CreateEmbed, lawsuit: &Lawsuit) -> &'a mut CreateEmbed {
+    embed
+        .title(format!("Schiedsverfahre #{}", lawsuit.number))
+        .field("Grund", &lawsuit.reason, false)
+        .field("Kläger", format!("<@{}>", lawsuit.plaintiff), true)
+        .field("Angeklagter", format!("<@{}>", lawsuit.accused), true);
+    for statement in &lawsuit.statements {
+        embed.field(
+            format!("Stellungnahm vom {}", statement.party),
+            &statement.text,
+            false,
+        );
+    }
+    embed
+}
+
+/// Records a new arbitration and asks the other party for their statement via DM.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn start(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    mut lawsuit: Lawsuit,
+) -> Result<Response> {
+    lawsuit.number = mongo.next_case_number(guild_id.into()).await?;
+
+    let dm = UserId::from(lawsuit.accused)
+        .create_dm_channel(http)
+        .await
+        .wrap_err("create dm channel")?
+        .send_message(http, |msg| {
+            msg.content(format!(
+                "<@{}> het di zum e Schiedsverfahre ufgforderet: {}",
+                lawsuit.plaintiff, lawsuit.reason
+            ))
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(format!("{STATEMENT_ID_PREFIX}{guild_id}:{}", lawsuit.id))
+                            .label("Stellungnahm abgeh")
+                            .style(ButtonStyle::Primary)
+                    })
+                })
+            })
+        })
+        .await;
+
+    if dm.is_err() {
+        return Ok(Response(format!(
+            "ich cha <@{}> kei DM schicke",
+            lawsuit.accused
+        )));
+    }
+
+    mongo.add_lawsuit(guild_id.into(), &lawsuit).await?;
+
+    info!(?lawsuit, "Started arbitration");
+
+    Ok(Response(format!(
+        "s'Schiedsverfahre #{} isch gstartet, <@{}> wird per DM um e Stellungnahm bete",
+        lawsuit.number, lawsuit.accused
+    )))
+}
+
+/// Sends both statements to the arbiter and asks them to rule.
+#[tracing::instrument(skip(http))]
+pub async fn request_ruling(http: &Http, guild_id: GuildId, lawsuit: &Lawsuit) -> Result<()> {
+    UserId::from(lawsuit.judge)
+        .create_dm_channel(http)
+        .await
+        .wrap_err("create dm channel")?
+        .send_message(http, |msg| {
+            msg.content("Du bisch als Schiedsrichter usgwählt worde, bitte fäll en Entscheid")
+                .embed(|embed| statements_embed(embed, lawsuit))
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        for (winner, label) in [
+                            ("plaintiff", "Für de Kläger"),
+                            ("accused", "Für de Angeklagte"),
+                        ] {
+                            row.create_button(|button| {
+                                button
+                                    .custom_id(format!(
+                                        "{RULING_ID_PREFIX}{guild_id}:{}:{winner}",
+                                        lawsuit.id
+                                    ))
+                                    .label(label)
+                                    .style(ButtonStyle::Primary)
+                            });
+                        }
+                        row
+                    })
+                })
+        })
+        .await
+        .wrap_err("send ruling request")?;
+
+    Ok(())
+}
+
+/// Records the ruling of the arbiter and tells both parties about it.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn rule(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    lawsuit: &mut Lawsuit,
+    winner: Party,
+    verdict: String,
+) -> Result<()> {
+    lawsuit.verdict = Some(verdict);
+    lawsuit.winner = Some(winner);
+    lawsuit.closed_at = Some(DateTime::now());
+
+    mongo
+        .set_lawsuit(
+            guild_id.into(),
+            lawsuit.id,
+            doc! {
+                "lawsuits.$.verdict": &lawsuit.verdict,
+                "lawsuits.$.winner": mongodb::bson::to_bson(&lawsuit.winner).wrap_err("invalid bson for winner")?,
+                "lawsuits.$.closed_at": lawsuit.closed_at,
+            },
+        )
+        .await?;
+
+    for party in [lawsuit.plaintiff, lawsuit.accused] {
+        let result = notify_party(http, party, lawsuit).await;
+        if let Err(err) = result {
+            warn!(?err, %party, "Could not send arbitration ruling to party");
+        }
+    }
+
+    let update = format!(
+        "Entscheid: {}",
+        lawsuit.verdict.as_deref().unwrap_or_default()
+    );
+    notification::notify_followers(http, lawsuit, &update).await;
+
+    info!(?lawsuit, "Ruled arbitration");
+
+    Ok(())
+}
+
+async fn notify_party(http: &Http, party: SnowflakeId, lawsuit: &Lawsuit) -> Result<()> {
+    UserId::from(party)
+        .create_dm_channel(http)
+        .await
+        .wrap_err("create dm channel")?
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                statements_embed(embed, lawsuit)
+                    .title(format!("Schiedsverfahre #{} entschiede", lawsuit.number))
+                    .field(
+                        "Entscheid",
+                        lawsuit.verdict.as_deref().unwrap_or_default(),
+                        false,
+                    );
+                if let Some(winner) = lawsuit.winner {
+                    embed.field("Gewinner", winner, true);
+                }
+                embed
+            })
+        })
+        .await
+        .wrap_err("send arbitration ruling")?;
+
+    Ok(())
+}
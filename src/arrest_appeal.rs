@@ -0,0 +1,294 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::{eyre::ContextCompat, Result};
+use mongodb::bson::{Bson, DateTime, Uuid};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, GuildId, Http, UserId};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    duration::format_date_time, economy, economy::format_amount, events, events::Change,
+    handler::Response, model::SnowflakeId, prison, scheduler, scheduler::JobKind, Mongo, WrapErr,
+};
+
+/// The prefix of the custom id of the buttons that decide an appeal against an arrest, followed
+/// by `{appeal}:{ruling}`.
+pub const ARREST_APPEAL_ID_PREFIX: &str = "arrest-appeal:";
+
+/// The longest statement, so it fits into the embed with room to spare.
+pub const STATEMENT_LIMIT: usize = 1000;
+
+/// What the judge decides about an appeal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrestRuling {
+    Uphold,
+    /// Moves the release earlier, to the time entered by the judge.
+    Reduce,
+    /// Releases the prisoner and marks the stay as wrongful.
+    Overturn,
+}
+
+impl ArrestRuling {
+    fn id(self) -> &'static str {
+        match self {
+            Self::Uphold => "uphold",
+            Self::Reduce => "reduce",
+            Self::Overturn => "overturn",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        [Self::Uphold, Self::Reduce, Self::Overturn]
+            .into_iter()
+            .find(|ruling| ruling.id() == id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrestAppealStatus {
+    Pending,
+    Upheld,
+    Reduced,
+    Overturned,
+}
+
+/// An appeal of a prisoner against their arrest ("Haftbeschwerde"), decided by the judges. Unlike
+/// the appeal of a verdict it isn't tied to a lawsuit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArrestAppeal {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    pub statement: String,
+    /// The arrest that is appealed, every arrest can only be appealed once.
+    pub arrested_at: Option<DateTime>,
+    pub filed_at: DateTime,
+    pub status: ArrestAppealStatus,
+    pub decided_by: Option<SnowflakeId>,
+}
+
+/// Parses the `{appeal}:{ruling}` part of a decision custom id.
+pub fn parse_decision_id(id: &str) -> Option<(Uuid, ArrestRuling)> {
+    let (appeal_id, ruling) = id.split_once(':')?;
+    Some((
+        Uuid::parse_str(appeal_id).ok()?,
+        ArrestRuling::from_id(ruling)?,
+    ))
+}
+
+/// Stores the appeal and posts it in the judge channel to be decided.
+#[tracing::instrument(skip(mongo, http, appeal), fields(appeal_id = %appeal.id))]
+pub async fn file(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    judge_channel: SnowflakeId,
+    appeal: &ArrestAppeal,
+) -> Result<()> {
+    mongo.add_arrest_appeal(appeal).await?;
+
+    ChannelId::from(judge_channel)
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title("Haftbeschwärde")
+                    .description(&appeal.statement)
+                    .field("Vo", format!("<@{}>", appeal.user_id), true)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    for (ruling, label, style) in [
+                        (ArrestRuling::Uphold, "Bstätige", ButtonStyle::Secondary),
+                        (ArrestRuling::Reduce, "Verchürze", ButtonStyle::Primary),
+                        (ArrestRuling::Overturn, "Ufhebe", ButtonStyle::Danger),
+                    ] {
+                        row.create_button(|button| {
+                            button
+                                .custom_id(format!(
+                                    "{ARREST_APPEAL_ID_PREFIX}{}:{}",
+                                    appeal.id,
+                                    ruling.id()
+                                ))
+                                .label(label)
+                                .style(style)
+                        });
+                    }
+                    row
+                })
+            })
+        })
+        .await
+        .wrap_err("send arrest appeal")?;
+
+    Ok(())
+}
+
+/// Decides a pending appeal. An upheld arrest stays as it is, a reduced one is released at
+/// `release_at` and an overturned one right away, with the stay marked as wrongful. Tells the
+/// prisoner via DM and logs the decision.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn decide(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    appeal_id: Uuid,
+    decided_by: SnowflakeId,
+    ruling: ArrestRuling,
+    release_at: Option<DateTime>,
+) -> Result<Result<ArrestAppeal, Response>> {
+    let appeal = match mongo.find_arrest_appeal(appeal_id).await? {
+        Some(appeal) if appeal.status == ArrestAppealStatus::Pending => appeal,
+        _ => {
+            return Ok(Err(Response(
+                "über d'Haftbeschwärde isch scho entschiede".to_string(),
+            )))
+        }
+    };
+    let (guild_id, user_id) = (appeal.guild_id, appeal.user_id);
+    let entry = mongo.find_prison_entry(guild_id, user_id).await?;
+    if ruling != ArrestRuling::Uphold && entry.is_none() {
+        return Ok(Err(Response(format!("<@{user_id}> isch nüm im Gfängnis"))));
+    }
+
+    let release_job = prison::release_job(mongo, guild_id, user_id).await?;
+    if ruling == ArrestRuling::Reduce {
+        let release_at = release_at.wrap_err("reduced appeal without release time")?;
+        if release_job
+            .as_ref()
+            .is_some_and(|job| job.due.timestamp_millis() <= release_at.timestamp_millis())
+        {
+            return Ok(Err(Response(
+                "so wird d'Haft nöd verchürzt, de Gfangeni chunnt scho früener frei".to_string(),
+            )));
+        }
+    }
+
+    let status = match ruling {
+        ArrestRuling::Uphold => ArrestAppealStatus::Upheld,
+        ArrestRuling::Reduce => ArrestAppealStatus::Reduced,
+        ArrestRuling::Overturn => ArrestAppealStatus::Overturned,
+    };
+    let appeal = match mongo
+        .decide_arrest_appeal(appeal_id, status, decided_by)
+        .await?
+    {
+        Some(appeal) => appeal,
+        None => {
+            return Ok(Err(Response(
+                "über d'Haftbeschwärde isch scho entschiede".to_string(),
+            )))
+        }
+    };
+    info!(?appeal, "Decided arrest appeal");
+
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let outcome = match (ruling, release_at) {
+        (ArrestRuling::Reduce, Some(release_at)) => {
+            let before = release_job.as_ref().map(|job| Bson::DateTime(job.due));
+            let job_id = match release_job {
+                Some(job) => {
+                    mongo.set_job_due(job.id, release_at).await?;
+                    job.id
+                }
+                None => {
+                    let kind = JobKind::Release { user_id };
+                    scheduler::schedule(mongo, guild_id, kind, release_at)
+                        .await?
+                        .id
+                }
+            };
+            // the jobs aren't part of the snapshot
+            let change = Change {
+                path: format!("jobs[{job_id}].due"),
+                before,
+                after: Some(Bson::DateTime(release_at)),
+            };
+            events::record_changes(
+                mongo,
+                guild_id,
+                Some(decided_by),
+                "Haftbeschwärde",
+                vec![change],
+            )
+            .await;
+
+            format!(
+                "verchürzt, Freilassig am {}",
+                format_date_time(release_at, state.timezone())
+            )
+        }
+        (ArrestRuling::Overturn, _) => {
+            let release = prison::release(mongo, http, GuildId::from(guild_id), user_id.into());
+            let action = "Haftbeschwärde";
+            if let Err(response) =
+                events::tracked(mongo, guild_id, Some(decided_by), action, release).await?
+            {
+                warn!(%response, ?appeal, "Could not release prisoner after overturned arrest");
+            }
+            mongo.mark_prison_stay_wrongful(guild_id, user_id).await?;
+
+            let served = entry
+                .and_then(|entry| entry.arrested_at)
+                .map(|arrested_at| {
+                    let millis =
+                        DateTime::now().timestamp_millis() - arrested_at.timestamp_millis();
+                    Duration::from_millis(millis.max(0) as u64)
+                });
+            let compensation = match served {
+                Some(served) => {
+                    let description = "Entschädigung für e ufghobeni Verhaftig";
+                    economy::compensate(mongo, guild_id, user_id, served, description).await?
+                }
+                None => None,
+            };
+            match compensation {
+                Some(amount) => format!(
+                    "ufghobe, d'Haft isch z'Unrecht gsi und wird mit {} entschädigt",
+                    format_amount(amount)
+                ),
+                None => "ufghobe, d'Haft isch z'Unrecht gsi".to_string(),
+            }
+        }
+        _ => "abglehnt, d'Haft bliibt".to_string(),
+    };
+
+    let message = format!("dini Haftbeschwärde isch {outcome}");
+    let result = match UserId::from(user_id).create_dm_channel(http).await {
+        Ok(channel) => channel.say(http, message).await.map(drop),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        warn!(?err, %user_id, "Could not tell prisoner about the decision");
+    }
+
+    if let Some(log_channel) = state.log_channel {
+        let notice =
+            format!("<@{decided_by}> hät d'Haftbeschwärde vo <@{user_id}> entschiede: {outcome}");
+        if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+            warn!(?err, "Failed to send arrest appeal notice");
+        }
+    }
+
+    Ok(Ok(appeal))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::Uuid;
+
+    use super::{parse_decision_id, ArrestRuling};
+
+    #[test]
+    fn decision_id() {
+        let id = Uuid::new();
+        assert_eq!(
+            parse_decision_id(&format!("{id}:overturn")),
+            Some((id, ArrestRuling::Overturn))
+        );
+        assert_eq!(
+            parse_decision_id(&format!("{id}:reduce")),
+            Some((id, ArrestRuling::Reduce))
+        );
+        assert_eq!(parse_decision_id(&format!("{id}:pardon")), None);
+        assert_eq!(parse_decision_id("kaputt:uphold"), None);
+    }
+}
@@ -0,0 +1,137 @@
+use std::{io::Cursor, time::Duration};
+
+use color_eyre::{eyre::eyre, Result};
+use image::{DynamicImage, ImageReader, Limits};
+use poise::serenity_prelude::CreateEmbed;
+use reqwest::redirect;
+use serde::{Deserialize, Serialize};
+
+use crate::{webhook, WrapErr};
+
+/// The longest footer Discord shows.
+pub const MAX_FOOTER_LEN: usize = 2048;
+
+/// Seals that are larger aren't downloaded.
+const SEAL_SIZE_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Seals with more pixels on a side aren't decoded.
+const SEAL_DIMENSION_LIMIT: u32 = 2048;
+
+/// Seals are scaled down to this many pixels on a side, enough for printing.
+const SEAL_RESOLUTION: u32 = 512;
+
+const SEAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The colors and seal of a guild's court, shown on its embeds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Branding {
+    pub color: u32,
+    pub footer_text: String,
+    /// The seal of the court, shown next to the footer.
+    pub icon_url: Option<String>,
+}
+
+/// Parses a color like `#1f8b4c` or `1f8b4c`.
+pub fn parse_color(color: &str) -> Option<u32> {
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Gives the embed the color and footer of the guild, if it has a branding.
+pub fn apply<'a>(embed: &'a mut CreateEmbed, branding: Option<&Branding>) -> &'a mut CreateEmbed {
+    let branding = match branding {
+        Some(branding) => branding,
+        None => return embed,
+    };
+    embed.color(branding.color).footer(|footer| {
+        footer.text(&branding.footer_text);
+        if let Some(icon_url) = &branding.icon_url {
+            footer.icon_url(icon_url);
+        }
+        footer
+    })
+}
+
+/// Downloads and decodes the seal of the court, a PNG or JPEG image. The URL gets the same checks
+/// as webhooks, so that it can't reach the network of the bot.
+#[tracing::instrument]
+pub async fn fetch_seal(url: &str) -> Result<DynamicImage> {
+    let (host, addr) = webhook::resolve(url)
+        .await
+        .map_err(|reason| eyre!("rejected seal url: {reason}"))?;
+    let client = reqwest::Client::builder()
+        .timeout(SEAL_TIMEOUT)
+        .resolve(&host, addr)
+        .redirect(redirect::Policy::none())
+        .build()
+        .wrap_err("build http client")?;
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .wrap_err("download seal")?;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.wrap_err("download seal")? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > SEAL_SIZE_LIMIT {
+            return Err(eyre!("seal is larger than {SEAL_SIZE_LIMIT} bytes"));
+        }
+    }
+
+    decode_seal(&bytes)
+}
+
+fn decode_seal(bytes: &[u8]) -> Result<DynamicImage> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .wrap_err("read seal")?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(SEAL_DIMENSION_LIMIT);
+    limits.max_image_height = Some(SEAL_DIMENSION_LIMIT);
+    reader.limits(limits);
+    let seal = reader.decode().wrap_err("decode seal")?;
+
+    if seal.width() > SEAL_RESOLUTION || seal.height() > SEAL_RESOLUTION {
+        Ok(seal.thumbnail(SEAL_RESOLUTION, SEAL_RESOLUTION))
+    } else {
+        Ok(seal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, RgbaImage};
+
+    use super::{decode_seal, parse_color, SEAL_RESOLUTION};
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#1f8b4c"), Some(0x1f8b4c));
+        assert_eq!(parse_color("FFFFFF"), Some(0xffffff));
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("grüen"), None);
+        assert_eq!(parse_color("+12345"), None);
+    }
+
+    #[test]
+    fn seals() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::new(1024, 512))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let seal = decode_seal(&png).unwrap();
+        assert_eq!(
+            (seal.width(), seal.height()),
+            (SEAL_RESOLUTION, SEAL_RESOLUTION / 2)
+        );
+        assert!(decode_seal(b"<svg></svg>").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use futures::future::join_all;
+use poise::serenity_prelude::{
+    ChannelId, GuildId, Http, HttpError, PermissionOverwrite, PermissionOverwriteType, RoleId,
+    SerenityError, UserId,
+};
+use tokio::sync::Semaphore;
+use tracing::{debug, warn};
+
+use crate::sandbox;
+
+/// How many Discord requests of bulk operations run at the same time, across all guilds. Serenity
+/// waits for the rate limit buckets itself, this only keeps large operations from filling them.
+const BULK_CONCURRENCY: usize = 4;
+
+/// How often an operation is attempted if it fails with a transient error.
+const BULK_ATTEMPTS: u32 = 3;
+
+/// How many failures a report lists, so that it fits into a message.
+const REPORT_FAILURE_LIMIT: usize = 20;
+
+/// How long to wait before the first retry. Doubles after every attempt.
+const BULK_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+static PERMITS: Semaphore = Semaphore::const_new(BULK_CONCURRENCY);
+
+/// A role or channel change that is part of a larger operation.
+#[derive(Debug, Clone)]
+pub enum BulkOp {
+    AddRole {
+        user_id: UserId,
+        role_id: RoleId,
+    },
+    RemoveRole {
+        user_id: UserId,
+        role_id: RoleId,
+    },
+    SetPermission {
+        channel_id: ChannelId,
+        overwrite: PermissionOverwrite,
+    },
+    RemovePermission {
+        channel_id: ChannelId,
+        kind: PermissionOverwriteType,
+    },
+}
+
+impl Display for BulkOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddRole { user_id, role_id } => write!(f, "<@&{role_id}> für <@{user_id}>"),
+            Self::RemoveRole { user_id, role_id } => {
+                write!(f, "<@&{role_id}> weg vo <@{user_id}>")
+            }
+            Self::SetPermission { channel_id, .. } => write!(f, "Berechtigung i <#{channel_id}>"),
+            Self::RemovePermission { channel_id, .. } => {
+                write!(f, "Berechtigung weg i <#{channel_id}>")
+            }
+        }
+    }
+}
+
+/// Discord's error code for a member that isn't in the guild anymore.
+const UNKNOWN_MEMBER: isize = 10007;
+
+/// How an operation that didn't fail ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Done,
+    /// There was nothing to do, e.g. because the member left the guild.
+    Skipped,
+}
+
+/// The results of every operation, in the order they were given.
+#[derive(Debug)]
+pub struct BulkReport {
+    pub results: Vec<(BulkOp, Result<Outcome, String>)>,
+}
+
+impl BulkReport {
+    pub fn failures(&self) -> impl Iterator<Item = (&BulkOp, &str)> {
+        self.results
+            .iter()
+            .filter_map(|(op, result)| result.as_ref().err().map(|err| (op, err.as_str())))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, result)| *result == Ok(Outcome::Skipped))
+            .count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Logs the failed operations, for callers that don't show the report.
+    pub fn log_failures(&self, context: &str) {
+        for (op, err) in self.failures() {
+            warn!(?op, err, context, "Bulk operation failed");
+        }
+    }
+}
+
+impl Display for BulkReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let failed = self.failures().count();
+        let skipped = self.skipped();
+        write!(
+            f,
+            "{} erfolgreich, {failed} fehlgschlage",
+            self.results.len() - failed - skipped
+        )?;
+        if skipped > 0 {
+            write!(f, ", {skipped} übersprunge")?;
+        }
+        for (op, err) in self.failures().take(REPORT_FAILURE_LIMIT) {
+            write!(f, "\n{op}: {err}")?;
+        }
+        if failed > REPORT_FAILURE_LIMIT {
+            write!(f, "\nund {} witeri", failed - REPORT_FAILURE_LIMIT)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the operations with limited concurrency, retrying transient failures. Failures of
+/// single operations don't stop the others. Role changes for members that left are skipped.
+#[tracing::instrument(skip(http, ops), fields(count = ops.len()))]
+pub async fn run(http: &Http, guild_id: GuildId, ops: Vec<BulkOp>) -> BulkReport {
+    if sandbox::report_channel(guild_id).is_some() {
+        let changes = ops.iter().map(ToString::to_string).collect::<Vec<_>>();
+        sandbox::simulate(http, guild_id, changes.join("\n")).await;
+        return BulkReport {
+            results: ops.into_iter().map(|op| (op, Ok(Outcome::Done))).collect(),
+        };
+    }
+
+    let results = join_all(ops.iter().map(|op| run_with_retries(http, guild_id, op))).await;
+
+    BulkReport {
+        results: ops.into_iter().zip(results).collect(),
+    }
+}
+
+async fn run_with_retries(http: &Http, guild_id: GuildId, op: &BulkOp) -> Result<Outcome, String> {
+    let mut delay = BULK_RETRY_DELAY;
+    let mut attempt = 1;
+
+    loop {
+        let result = {
+            let _permit = PERMITS.acquire().await.expect("bulk semaphore closed");
+            run_op(http, guild_id, op).await
+        };
+
+        match result {
+            Ok(()) => return Ok(Outcome::Done),
+            Err(err) if is_unknown_member(op, &err) => return Ok(Outcome::Skipped),
+            Err(err) if attempt < BULK_ATTEMPTS && is_transient(&err) => {
+                debug!(?err, ?op, attempt, "Retrying bulk operation");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(err) => return Err(describe(&err)),
+        }
+    }
+}
+
+async fn run_op(http: &Http, guild_id: GuildId, op: &BulkOp) -> Result<(), SerenityError> {
+    match op {
+        BulkOp::AddRole { user_id, role_id } => {
+            http.add_member_role(guild_id.0, user_id.0, role_id.0, None)
+                .await
+        }
+        BulkOp::RemoveRole { user_id, role_id } => {
+            http.remove_member_role(guild_id.0, user_id.0, role_id.0, None)
+                .await
+        }
+        BulkOp::SetPermission {
+            channel_id,
+            overwrite,
+        } => channel_id.create_permission(http, overwrite).await,
+        BulkOp::RemovePermission { channel_id, kind } => {
+            channel_id.delete_permission(http, *kind).await
+        }
+    }
+}
+
+/// Server errors and failed connections might work on a retry. Rate limits are already waited
+/// for by serenity.
+fn is_transient(err: &SerenityError) -> bool {
+    match err {
+        SerenityError::Http(err) => match err.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => {
+                response.status_code.is_server_error() || response.status_code.as_u16() == 429
+            }
+            HttpError::Request(_) => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_unknown_member(op: &BulkOp, err: &SerenityError) -> bool {
+    let is_role_op = matches!(op, BulkOp::AddRole { .. } | BulkOp::RemoveRole { .. });
+    let is_unknown = matches!(
+        err,
+        SerenityError::Http(err) if matches!(
+            err.as_ref(),
+            HttpError::UnsuccessfulRequest(response) if response.error.code == UNKNOWN_MEMBER
+        )
+    );
+    is_role_op && is_unknown
+}
+
+fn describe(err: &SerenityError) -> String {
+    match err {
+        SerenityError::Http(http) => match http.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => match response.status_code.as_u16() {
+                403 => "kei Berechtigung".to_string(),
+                404 => "nöd gfunde".to_string(),
+                _ => response.error.message.clone(),
+            },
+            _ => err.to_string(),
+        },
+        _ => err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_role(user: u64) -> BulkOp {
+        BulkOp::AddRole {
+            user_id: UserId(user),
+            role_id: RoleId(1),
+        }
+    }
+
+    #[test]
+    fn skipped_members_are_not_failures() {
+        let report = BulkReport {
+            results: vec![
+                (add_role(1), Ok(Outcome::Done)),
+                (add_role(2), Ok(Outcome::Skipped)),
+                (add_role(3), Err("kei Berechtigung".to_string())),
+            ],
+        };
+
+        assert!(!report.is_success());
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(
+            report.to_string(),
+            "1 erfolgreich, 1 fehlgschlage, 1 übersprunge\n<@&1> für <@3>: kei Berechtigung"
+        );
+    }
+}
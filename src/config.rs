@@ -0,0 +1,344 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use chrono_tz::Tz;
+use color_eyre::{eyre::eyre, Result};
+use mongodb::bson::Bson;
+use poise::serenity_prelude::{Cache, ChannelId, GuildId, RoleId};
+use serde::Deserialize;
+use tokio::time::{Instant, Interval};
+use tracing::{error, info, warn};
+
+use crate::{
+    duration::{parse_duration, parse_timezone},
+    handler::Response,
+    logging,
+    model::{SnowflakeId, State},
+    Mongo, WrapErr,
+};
+
+/// How often the config file is checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The longest status Discord shows.
+const MAX_PRESENCE_LEN: usize = 128;
+
+/// The settings from the JSON file at `CONFIG_FILE`, which are applied again whenever it changes,
+/// without restarting the bot. Everything is optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Replaces `RUST_LOG`, like `info,court_bot=debug`.
+    pub log_filter: Option<String>,
+    /// Commands that can't be used, by their full name like `economy fine`. Disabling a command
+    /// disables its subcommands too.
+    pub disabled_commands: Vec<String>,
+    /// The time zone of guilds that didn't set one, UTC if there is none.
+    pub default_timezone: Option<String>,
+    /// How often the background tasks run, by task, like `"scheduler": "30s"`.
+    pub intervals: HashMap<String, String>,
+    /// The status of the bot, with `{open_cases}` and `{prisoners}` for the counts of all guilds.
+    pub presence_template: Option<String>,
+    /// Shows a fixed status instead of the counts.
+    pub presence_disabled: bool,
+}
+
+static CONFIG: LazyLock<RwLock<Arc<Config>>> = LazyLock::new(Default::default);
+
+pub fn get() -> Arc<Config> {
+    CONFIG.read().expect("config lock poisoned").clone()
+}
+
+fn config_file() -> Option<PathBuf> {
+    env::var("CONFIG_FILE").ok().map(PathBuf::from)
+}
+
+/// Reads the config file and applies it. An invalid file is rejected as a whole, the previous
+/// config stays. Returns `false` if there is no `CONFIG_FILE`.
+#[tracing::instrument]
+pub async fn reload() -> Result<bool> {
+    let path = match config_file() {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .wrap_err_with(|| format!("read config file {}", path.display()))?;
+    let config: Config = serde_json::from_str(&content).wrap_err("invalid config file")?;
+    config.validate()?;
+
+    logging::set_log_filter(config.log_filter.as_deref())?;
+    info!(?config, "Loaded config");
+    *CONFIG.write().expect("config lock poisoned") = Arc::new(config);
+
+    Ok(true)
+}
+
+impl Config {
+    fn validate(&self) -> Result<()> {
+        if let Some(timezone) = &self.default_timezone {
+            parse_timezone(timezone).ok_or_else(|| eyre!("invalid time zone {timezone}"))?;
+        }
+        for (task, interval) in &self.intervals {
+            match parse_duration(interval) {
+                Some(interval) if !interval.is_zero() => {}
+                _ => return Err(eyre!("invalid interval {interval} for {task}")),
+            }
+        }
+        if let Some(template) = &self.presence_template {
+            if template.is_empty() || template.chars().count() > MAX_PRESENCE_LEN {
+                return Err(eyre!(
+                    "presence template must have 1 to {MAX_PRESENCE_LEN} characters"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn default_timezone(&self) -> Tz {
+        self.default_timezone
+            .as_deref()
+            .and_then(parse_timezone)
+            .unwrap_or(Tz::UTC)
+    }
+
+    /// Whether the command or the command it belongs to is disabled.
+    pub fn is_disabled(&self, qualified_name: &str) -> bool {
+        self.disabled_commands.iter().any(|disabled| {
+            qualified_name == disabled
+                || qualified_name
+                    .strip_prefix(disabled.as_str())
+                    .is_some_and(|rest| rest.starts_with(' '))
+        })
+    }
+
+    fn interval(&self, task: &str, default: Duration) -> Duration {
+        self.intervals
+            .get(task)
+            .and_then(|interval| parse_duration(interval))
+            .unwrap_or(default)
+    }
+}
+
+/// Reloads the config whenever the file is changed. Never returns.
+pub async fn watch_task() {
+    let path = match config_file() {
+        Some(path) => path,
+        None => return,
+    };
+    let mut last_modified = modified(&path).await;
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let modified = modified(&path).await;
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        if let Err(err) = reload().await {
+            error!(?err, "Error reloading changed config");
+        }
+    }
+}
+
+async fn modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+/// Like [`tokio::time::Interval`], but the period of the task can be changed in the config.
+pub struct TaskInterval {
+    task: &'static str,
+    default: Duration,
+    period: Duration,
+    interval: Interval,
+}
+
+impl TaskInterval {
+    pub fn new(task: &'static str, default: Duration) -> Self {
+        let period = get().interval(task, default);
+        Self {
+            task,
+            default,
+            period,
+            interval: tokio::time::interval(period),
+        }
+    }
+
+    pub async fn tick(&mut self) {
+        let period = get().interval(self.task, self.default);
+        if period != self.period {
+            info!(task = self.task, ?period, "Changed task interval");
+            self.period = period;
+            self.interval = tokio::time::interval_at(Instant::now() + period, period);
+        }
+        self.interval.tick().await;
+    }
+}
+
+/// A role or channel a guild configured, which might have been deleted since.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    CourtCategory,
+    PrisonRole,
+    JudgeRole,
+    LogChannel,
+}
+
+impl Reference {
+    fn field(self) -> &'static str {
+        match self {
+            Self::CourtCategory => "court_category",
+            Self::PrisonRole => "prison_role",
+            Self::JudgeRole => "judge_role",
+            Self::LogChannel => "log_channel",
+        }
+    }
+
+    fn id(self, state: &State) -> Option<SnowflakeId> {
+        match self {
+            Self::CourtCategory => state.court_category,
+            Self::PrisonRole => state.prison_role,
+            Self::JudgeRole => state.judge_role,
+            Self::LogChannel => state.log_channel,
+        }
+    }
+
+    fn is_role(self) -> bool {
+        matches!(self, Self::PrisonRole | Self::JudgeRole)
+    }
+
+    fn fix(self) -> &'static str {
+        match self {
+            Self::CourtCategory => {
+                "d'Kategorie für d'Gerichtsrüüm gits nüm, erstell e neui mit `/court create_category`"
+            }
+            Self::PrisonRole => {
+                "d'Gfängnisrolle gits nüm, erstell e neui mit `/prison create_role`"
+            }
+            Self::JudgeRole => "d'Richterrolle gits nüm, setz e neui mit `/court set_judge_role`",
+            Self::LogChannel => "de Log-Kanal gits nüm, setz en neue mit `/config set_log_channel`",
+        }
+    }
+}
+
+/// The references that are set but whose role or channel doesn't exist anymore.
+fn dangling(
+    state: &State,
+    references: &[Reference],
+    exists: impl Fn(Reference, SnowflakeId) -> bool,
+) -> Vec<(Reference, SnowflakeId)> {
+    references
+        .iter()
+        .filter_map(|&reference| Some((reference, reference.id(state)?)))
+        .filter(|&(reference, id)| !exists(reference, id))
+        .collect()
+}
+
+/// Checks that the roles and channels a command needs still exist before it runs. Dangling
+/// references are cleared and the response tells how to set them again. Only the settings of the
+/// whole guild are checked, not those of case types. Guilds that aren't cached are assumed to be
+/// fine.
+#[tracing::instrument(skip(mongo, cache, state), fields(guild_id = %state.guild_id))]
+pub async fn validate(
+    mongo: &Mongo,
+    cache: &Cache,
+    state: &State,
+    references: &[Reference],
+) -> Result<Result<(), Response>> {
+    let guild_id = GuildId::from(state.guild_id);
+    let dangling = dangling(state, references, |reference, id| {
+        let exists = if reference.is_role() {
+            cache.guild_field(guild_id, |guild| {
+                guild.roles.contains_key(&RoleId::from(id))
+            })
+        } else {
+            cache.guild_field(guild_id, |guild| {
+                guild.channels.contains_key(&ChannelId::from(id))
+            })
+        };
+        exists.unwrap_or(true)
+    });
+    if dangling.is_empty() {
+        return Ok(Ok(()));
+    }
+
+    let mut fixes = Vec::new();
+    for (reference, id) in dangling {
+        warn!(?reference, %id, "Cleared dangling reference");
+        // only if nobody set it again in the meantime
+        mongo
+            .restore_state_field(
+                state.guild_id,
+                reference.field(),
+                Some(id.into()),
+                Some(Bson::Null),
+            )
+            .await
+            .wrap_err("clear dangling reference")?;
+        fixes.push(reference.fix());
+    }
+
+    Ok(Err(Response(fixes.join("\n"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dangling, Config, Reference};
+    use crate::model::{SnowflakeId, State};
+
+    #[test]
+    fn dangling_references() {
+        let mut state = State::new(SnowflakeId(1));
+        state.prison_role = Some(SnowflakeId(2));
+        state.judge_role = Some(SnowflakeId(3));
+
+        let references = [
+            Reference::PrisonRole,
+            Reference::JudgeRole,
+            Reference::LogChannel,
+        ];
+        let result = dangling(&state, &references, |_, id| id == SnowflakeId(3));
+        assert_eq!(result, vec![(Reference::PrisonRole, SnowflakeId(2))]);
+    }
+
+    #[test]
+    fn disabled_commands() {
+        let config = Config {
+            disabled_commands: vec!["economy".to_string(), "prison arrest".to_string()],
+            ..Default::default()
+        };
+
+        assert!(config.is_disabled("economy"));
+        assert!(config.is_disabled("economy fine"));
+        assert!(config.is_disabled("prison arrest"));
+        assert!(!config.is_disabled("prison release"));
+        assert!(!config.is_disabled("economyx"));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let config: Config =
+            serde_json::from_str(r#"{ "intervals": { "scheduler": "soon" } }"#).unwrap();
+        assert!(config.validate().is_err());
+
+        let config: Config =
+            serde_json::from_str(r#"{ "default_timezone": "Europe/Zurich" }"#).unwrap();
+        assert!(config.validate().is_ok());
+        assert!(serde_json::from_str::<Config>(r#"{ "log_level": "debug" }"#).is_err());
+
+        let config = Config {
+            presence_template: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
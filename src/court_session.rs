@@ -0,0 +1,232 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{Datelike, Duration, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use color_eyre::Result;
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::{ChannelId, Http};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{docket, model::SnowflakeId, scheduler, scheduler::JobKind, Mongo, WrapErr};
+
+/// The hour the court day is announced on the morning of a session, unless the session starts
+/// earlier.
+const ANNOUNCEMENT_HOUR: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum Weekday {
+    #[name = "Montag"]
+    Monday,
+    #[name = "Dienstag"]
+    Tuesday,
+    #[name = "Mittwoch"]
+    Wednesday,
+    #[name = "Donnerstag"]
+    Thursday,
+    #[name = "Freitag"]
+    Friday,
+    #[name = "Samstag"]
+    Saturday,
+    #[name = "Sonntag"]
+    Sunday,
+}
+
+impl Weekday {
+    fn chrono(self) -> chrono::Weekday {
+        match self {
+            Self::Monday => chrono::Weekday::Mon,
+            Self::Tuesday => chrono::Weekday::Tue,
+            Self::Wednesday => chrono::Weekday::Wed,
+            Self::Thursday => chrono::Weekday::Thu,
+            Self::Friday => chrono::Weekday::Fri,
+            Self::Saturday => chrono::Weekday::Sat,
+            Self::Sunday => chrono::Weekday::Sun,
+        }
+    }
+}
+
+impl Display for Weekday {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Monday => "Mäntig",
+            Self::Tuesday => "Ziischtig",
+            Self::Wednesday => "Mittwuch",
+            Self::Thursday => "Dunschtig",
+            Self::Friday => "Friitig",
+            Self::Saturday => "Samschtig",
+            Self::Sunday => "Sunntig",
+        })
+    }
+}
+
+/// A court day that takes place every week at the same time, in the time zone of the guild.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourtSession {
+    pub id: Uuid,
+    pub weekday: Weekday,
+    /// Like `18:00`.
+    pub time: String,
+}
+
+impl CourtSession {
+    pub fn new(weekday: Weekday, time: NaiveTime) -> Self {
+        Self {
+            id: Uuid::new(),
+            weekday,
+            time: time.format("%H:%M").to_string(),
+        }
+    }
+
+    fn start_time(&self) -> NaiveTime {
+        parse_time(&self.time).unwrap_or_else(|| NaiveTime::from_hms(ANNOUNCEMENT_HOUR, 0, 0))
+    }
+
+    /// When the next court day is announced after the time, on the morning of the session.
+    pub fn next_announcement(&self, tz: Tz, after: DateTime) -> Option<DateTime> {
+        let start = self.start_time();
+        let announcement = start.min(NaiveTime::from_hms(ANNOUNCEMENT_HOUR, 0, 0));
+        let today = tz
+            .timestamp_millis(after.timestamp_millis())
+            .naive_local()
+            .date();
+
+        (0..=7)
+            .map(|days| today + Duration::days(days))
+            .filter(|date| date.weekday() == self.weekday.chrono())
+            .filter_map(|date| {
+                tz.from_local_datetime(&date.and_time(announcement))
+                    .earliest()
+            })
+            .map(|time| DateTime::from_millis(time.timestamp_millis()))
+            .find(|time| time.timestamp_millis() > after.timestamp_millis())
+    }
+
+    /// When the session starts on the day of the announcement.
+    fn start_after(&self, tz: Tz, announcement: DateTime) -> Option<DateTime> {
+        let date = tz
+            .timestamp_millis(announcement.timestamp_millis())
+            .naive_local()
+            .date();
+        let start = tz
+            .from_local_datetime(&date.and_time(self.start_time()))
+            .earliest()?;
+        Some(DateTime::from_millis(start.timestamp_millis()))
+    }
+}
+
+/// Parses times like `18:00`.
+pub fn parse_time(input: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(input.trim(), "%H:%M").ok()
+}
+
+/// Schedules the next announcement of the session, unless it already is.
+#[tracing::instrument(skip(mongo))]
+pub async fn schedule_next(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    session: &CourtSession,
+    tz: Tz,
+    after: DateTime,
+) -> Result<()> {
+    let due = match session.next_announcement(tz, after) {
+        Some(due) => due,
+        None => return Ok(()),
+    };
+
+    // the announcement may run again if the bot stopped in between
+    let scheduled = mongo.find_jobs(Some(guild_id)).await?.iter().any(|job| {
+        matches!(job.kind, JobKind::CourtSession { session_id, .. } if session_id == session.id)
+            && job.due == due
+    });
+    if !scheduled {
+        let kind = JobKind::CourtSession {
+            session_id: session.id,
+        };
+        scheduler::schedule(mongo, guild_id, kind, due).await?;
+    }
+
+    Ok(())
+}
+
+/// Announces the court day with the open lawsuits in the announcement channel, pings the judges
+/// and schedules the next one. Does nothing if the session was cancelled.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn announce(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    session_id: Uuid,
+    due: DateTime,
+) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let session = match state.court_sessions.iter().find(|s| s.id == session_id) {
+        Some(session) => session,
+        None => return Ok(()),
+    };
+    let tz = state.timezone();
+
+    if let Some(channel_id) = state.announcement_channel {
+        let start = session
+            .start_after(tz, due)
+            .map(|start| format!(" <t:{}:t>", start.timestamp_millis() / 1000))
+            .unwrap_or_default();
+        let ping = state
+            .judge_role
+            .map(|role| format!("<@&{role}>, "))
+            .unwrap_or_default();
+        ChannelId::from(channel_id)
+            .send_message(http, |msg| {
+                msg.content(format!("{ping}hüt{start} isch Gerichtstag"))
+                    .embed(|embed| docket::docket_embed(embed, &state))
+            })
+            .await
+            .wrap_err("send court session announcement")?;
+
+        info!(%session_id, "Announced court session");
+    }
+
+    schedule_next(mongo, guild_id, session, tz, due).await
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::{Europe::Zurich, Tz};
+    use mongodb::bson::DateTime;
+
+    use super::{parse_time, CourtSession, Weekday};
+
+    fn at(tz: Tz, date: (i32, u32, u32), hour: u32, minute: u32) -> DateTime {
+        let local = NaiveDate::from_ymd(date.0, date.1, date.2).and_hms(hour, minute, 0);
+        DateTime::from_millis(tz.from_local_datetime(&local).unwrap().timestamp_millis())
+    }
+
+    #[test]
+    fn next_announcement() {
+        // 2022-06-30 is a thursday
+        let session = CourtSession::new(Weekday::Thursday, parse_time("18:00").unwrap());
+        let morning = at(Zurich, (2022, 6, 30), 8, 0);
+
+        let before = at(Zurich, (2022, 6, 30), 7, 0);
+        assert_eq!(session.next_announcement(Zurich, before), Some(morning));
+
+        let next_week = at(Zurich, (2022, 7, 7), 8, 0);
+        assert_eq!(session.next_announcement(Zurich, morning), Some(next_week));
+
+        let friday = at(Zurich, (2022, 7, 1), 12, 0);
+        assert_eq!(session.next_announcement(Zurich, friday), Some(next_week));
+    }
+
+    #[test]
+    fn early_session() {
+        let session = CourtSession::new(Weekday::Monday, parse_time("06:30").unwrap());
+        let sunday = at(Zurich, (2022, 7, 3), 12, 0);
+        assert_eq!(
+            session.next_announcement(Zurich, sunday),
+            Some(at(Zurich, (2022, 7, 4), 6, 30))
+        );
+        assert_eq!(parse_time("25:00"), None);
+    }
+}
@@ -0,0 +1,774 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Extension, Form, Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::TimeZone;
+use color_eyre::Result;
+use poise::serenity_prelude::{ChannelId, ChannelType, GuildId, Http, RoleId, UserId};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::{
+    maintenance::Maintenance,
+    messages::{MessageKey, MessageTemplate},
+    model::{SnowflakeId, State},
+    webhook, Mongo, WrapErr,
+};
+
+const DISCORD_API: &str = "https://discord.com/api/v10";
+
+const SESSION_COOKIE: &str = "court_session";
+const OAUTH_STATE_COOKIE: &str = "court_oauth_state";
+
+/// How long someone stays logged in. The guilds they can manage are only checked on login.
+const SESSION_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Guild members with one of these permissions can use the dashboard for the guild.
+const ADMINISTRATOR: u64 = 1 << 3;
+const MANAGE_GUILD: u64 = 1 << 5;
+
+/// The Discord application used for logging in to the dashboard.
+#[derive(Debug, Clone)]
+pub struct OAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// The public address of the dashboard, like `https://court.example.com`.
+    pub base_url: String,
+}
+
+impl OAuthConfig {
+    /// Reads `DISCORD_CLIENT_ID`, `DISCORD_CLIENT_SECRET` and `DASHBOARD_URL`. The dashboard is
+    /// disabled if one of them is missing.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            client_id: env::var("DISCORD_CLIENT_ID").ok()?,
+            client_secret: env::var("DISCORD_CLIENT_SECRET").ok()?,
+            base_url: env::var("DASHBOARD_URL")
+                .ok()?
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+
+    fn redirect_uri(&self) -> String {
+        format!("{}/dashboard/callback", self.base_url)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Session {
+    user_name: String,
+    guilds: Vec<DashboardGuild>,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct DashboardGuild {
+    id: u64,
+    name: String,
+}
+
+#[derive(Clone)]
+struct Dashboard {
+    mongo: Mongo,
+    http: Arc<Http>,
+    maintenance: Maintenance,
+    oauth: OAuthConfig,
+    client: reqwest::Client,
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+}
+
+/// An error page.
+struct DashboardError(StatusCode, &'static str);
+
+impl IntoResponse for DashboardError {
+    fn into_response(self) -> Response {
+        (self.0, page("Fehler", &escape(self.1))).into_response()
+    }
+}
+
+impl From<color_eyre::Report> for DashboardError {
+    fn from(err: color_eyre::Report) -> Self {
+        error!(?err, "Error handling dashboard request");
+        DashboardError(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "es isch en Fehler passiert",
+        )
+    }
+}
+
+type PageResult = std::result::Result<Response, DashboardError>;
+
+#[derive(Debug, Deserialize)]
+struct Callback {
+    code: String,
+    state: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscordUser {
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PartialGuild {
+    id: String,
+    name: String,
+    permissions: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Settings {
+    court_category: String,
+    judge_role: String,
+    prison_role: String,
+    announcement_channel: String,
+    case_ping_role: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageForm {
+    key: MessageKey,
+    template: String,
+}
+
+/// The routes of the dashboard, served under `/dashboard`.
+pub fn router(
+    mongo: Mongo,
+    http: Arc<Http>,
+    maintenance: Maintenance,
+    oauth: OAuthConfig,
+) -> Router {
+    Router::new()
+        .route("/dashboard", get(index))
+        .route("/dashboard/login", get(login))
+        .route("/dashboard/callback", get(callback))
+        .route("/dashboard/logout", post(logout))
+        .route("/dashboard/guilds/:guild_id", get(guild))
+        .route("/dashboard/guilds/:guild_id/settings", post(save_settings))
+        .route("/dashboard/guilds/:guild_id/messages", post(save_message))
+        .route("/dashboard/guilds/:guild_id/lawsuits/:number", get(lawsuit))
+        .layer(Extension(Dashboard {
+            mongo,
+            http,
+            maintenance,
+            oauth,
+            client: reqwest::Client::new(),
+            sessions: Default::default(),
+        }))
+}
+
+fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn set_cookie(name: &str, value: &str, max_age: u64) -> String {
+    format!("{name}={value}; Path=/dashboard; Max-Age={max_age}; HttpOnly; Secure; SameSite=Lax")
+}
+
+impl Dashboard {
+    /// Settings can't be changed while the database might be migrated.
+    fn check_writable(&self) -> Result<(), DashboardError> {
+        if self.maintenance.is_active() {
+            return Err(DashboardError(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "de Bot wird grad gwartet, probiers spöter nomal",
+            ));
+        }
+        Ok(())
+    }
+
+    fn session(&self, headers: &HeaderMap) -> Option<Session> {
+        let id = cookie(headers, SESSION_COOKIE)?;
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.expires_at > Instant::now());
+        sessions.get(id).cloned()
+    }
+
+    /// Loads the state of the guild if the logged in user may manage it.
+    async fn authorize(
+        &self,
+        headers: &HeaderMap,
+        guild_id: u64,
+    ) -> std::result::Result<State, DashboardError> {
+        let session = self.session(headers).ok_or(DashboardError(
+            StatusCode::UNAUTHORIZED,
+            "du bisch nöd aagmeldet",
+        ))?;
+        if !session.guilds.iter().any(|guild| guild.id == guild_id) {
+            return Err(DashboardError(
+                StatusCode::FORBIDDEN,
+                "du chasch dä Server nöd verwalte",
+            ));
+        }
+        Ok(self
+            .mongo
+            .guild(GuildId(guild_id))
+            .find_or_insert_state(SnowflakeId(guild_id))
+            .await?)
+    }
+
+    /// Exchanges the code for a token and looks up the guilds the user may manage.
+    async fn create_session(&self, code: &str) -> Result<Session> {
+        let token = self
+            .client
+            .post(format!("{DISCORD_API}/oauth2/token"))
+            .form(&[
+                ("client_id", self.oauth.client_id.as_str()),
+                ("client_secret", self.oauth.client_secret.as_str()),
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.oauth.redirect_uri()),
+            ])
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .wrap_err("request oauth token")?
+            .json::<TokenResponse>()
+            .await
+            .wrap_err("read oauth token")?
+            .access_token;
+
+        let user = self
+            .client
+            .get(format!("{DISCORD_API}/users/@me"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .wrap_err("request user")?
+            .json::<DiscordUser>()
+            .await
+            .wrap_err("read user")?;
+
+        let guilds = self
+            .client
+            .get(format!("{DISCORD_API}/users/@me/guilds"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .wrap_err("request guilds")?
+            .json::<Vec<PartialGuild>>()
+            .await
+            .wrap_err("read guilds")?;
+
+        let mut managed = Vec::new();
+        for guild in guilds {
+            let permissions = guild.permissions.parse::<u64>().unwrap_or_default();
+            let id = match guild.id.parse::<u64>() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+            // only guilds where the bot is used
+            if permissions & (ADMINISTRATOR | MANAGE_GUILD) != 0
+                && self
+                    .mongo
+                    .guild(GuildId(id))
+                    .find_raw_state(SnowflakeId(id))
+                    .await?
+                    .is_some()
+            {
+                managed.push(DashboardGuild {
+                    id,
+                    name: guild.name,
+                });
+            }
+        }
+
+        Ok(Session {
+            user_name: user.username,
+            guilds: managed,
+            expires_at: Instant::now() + SESSION_DURATION,
+        })
+    }
+}
+
+async fn index(Extension(dashboard): Extension<Dashboard>, headers: HeaderMap) -> Response {
+    let session = match dashboard.session(&headers) {
+        Some(session) => session,
+        None => return Redirect::to("/dashboard/login").into_response(),
+    };
+
+    let mut body = format!(
+        "<p>aagmeldet als {}</p>\
+         <form method=\"post\" action=\"/dashboard/logout\"><button>abmelde</button></form>\
+         <h2>Server</h2><ul>",
+        escape(&session.user_name)
+    );
+    for guild in &session.guilds {
+        let _ = write!(
+            body,
+            "<li><a href=\"/dashboard/guilds/{}\">{}</a></li>",
+            guild.id,
+            escape(&guild.name)
+        );
+    }
+    body.push_str("</ul>");
+    if session.guilds.is_empty() {
+        body.push_str("<p>du verwaltisch kein Server, wo de Bot drin isch</p>");
+    }
+
+    page("Gericht", &body).into_response()
+}
+
+async fn login(Extension(dashboard): Extension<Dashboard>) -> Response {
+    let state = webhook::generate_secret();
+    let url = format!(
+        "https://discord.com/oauth2/authorize?response_type=code&scope=identify%20guilds\
+         &client_id={}&redirect_uri={}&state={state}",
+        dashboard.oauth.client_id,
+        urlencode(&dashboard.oauth.redirect_uri()),
+    );
+
+    (
+        [(
+            header::SET_COOKIE,
+            set_cookie(OAUTH_STATE_COOKIE, &state, 600),
+        )],
+        Redirect::to(&url),
+    )
+        .into_response()
+}
+
+async fn callback(
+    Extension(dashboard): Extension<Dashboard>,
+    Query(callback): Query<Callback>,
+    headers: HeaderMap,
+) -> PageResult {
+    if cookie(&headers, OAUTH_STATE_COOKIE) != Some(callback.state.as_str()) {
+        return Err(DashboardError(
+            StatusCode::BAD_REQUEST,
+            "d'Aamäldig isch abgloffe, probier's nomal",
+        ));
+    }
+
+    let session = dashboard.create_session(&callback.code).await?;
+    info!(user = %session.user_name, "Logged in to dashboard");
+
+    let id = webhook::generate_secret();
+    dashboard
+        .sessions
+        .lock()
+        .unwrap()
+        .insert(id.clone(), session);
+
+    Ok((
+        [
+            (
+                header::SET_COOKIE,
+                set_cookie(SESSION_COOKIE, &id, SESSION_DURATION.as_secs()),
+            ),
+            (header::SET_COOKIE, set_cookie(OAUTH_STATE_COOKIE, "", 0)),
+        ],
+        Redirect::to("/dashboard"),
+    )
+        .into_response())
+}
+
+async fn logout(Extension(dashboard): Extension<Dashboard>, headers: HeaderMap) -> Response {
+    if let Some(id) = cookie(&headers, SESSION_COOKIE) {
+        dashboard.sessions.lock().unwrap().remove(id);
+    }
+
+    (
+        [(header::SET_COOKIE, set_cookie(SESSION_COOKIE, "", 0))],
+        Redirect::to("/dashboard/login"),
+    )
+        .into_response()
+}
+
+async fn guild(
+    Extension(dashboard): Extension<Dashboard>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+) -> PageResult {
+    let state = dashboard.authorize(&headers, guild_id).await?;
+
+    let channels = GuildId(guild_id)
+        .channels(&dashboard.http)
+        .await
+        .wrap_err("fetch channels")?;
+    let roles = GuildId(guild_id)
+        .roles(&dashboard.http)
+        .await
+        .wrap_err("fetch roles")?;
+
+    let options_of = |kind: ChannelType| {
+        let mut options = channels
+            .values()
+            .filter(|channel| channel.kind == kind)
+            .map(|channel| (channel.id.0, channel.name.clone()))
+            .collect::<Vec<_>>();
+        options.sort_by(|a, b| a.1.cmp(&b.1));
+        options
+    };
+    let categories = options_of(ChannelType::Category);
+    let text_channels = options_of(ChannelType::Text);
+    let mut role_options = roles
+        .values()
+        .map(|role| (role.id.0, role.name.clone()))
+        .collect::<Vec<_>>();
+    role_options.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let open = state.lawsuits.iter().filter(|l| !l.is_closed()).count();
+    let ruled = state
+        .lawsuits
+        .iter()
+        .filter(|l| l.verdict.is_some())
+        .count();
+    let prisoners = dashboard
+        .mongo
+        .guild(state.guild_id.into())
+        .find_prisoners(state.guild_id)
+        .await?
+        .len();
+
+    let mut body = format!(
+        "<p><a href=\"/dashboard\">zrugg</a></p>\
+         <h2>Statistik</h2>\
+         <p>{} Prozess, {open} offe, {ruled} mit Urteil, {prisoners} im Gfängnis</p>\
+         <h2>Iistellige</h2>\
+         <form method=\"post\" action=\"/dashboard/guilds/{guild_id}/settings\">",
+        state.lawsuits.len()
+    );
+    select(
+        &mut body,
+        "court_category",
+        "Kategorie für Gerichtsräum",
+        &categories,
+        state.court_category,
+    );
+    select(
+        &mut body,
+        "judge_role",
+        "Richterrolle",
+        &role_options,
+        state.judge_role,
+    );
+    select(
+        &mut body,
+        "prison_role",
+        "Gfängnisrolle",
+        &role_options,
+        state.prison_role,
+    );
+    select(
+        &mut body,
+        "announcement_channel",
+        "Kanal für Urteil",
+        &text_channels,
+        state.announcement_channel,
+    );
+    select(
+        &mut body,
+        "case_ping_role",
+        "Rolle für neui Fäll",
+        &role_options,
+        state.case_ping_role,
+    );
+    body.push_str("<button>speichere</button></form><h2>Nachrichte</h2>");
+
+    for key in MessageKey::ALL {
+        let template = state
+            .messages
+            .iter()
+            .find(|message| message.key == key)
+            .map(|message| message.template.as_str())
+            .unwrap_or_default();
+        let key_value = serde_json::to_value(key).wrap_err("serialize message key")?;
+        let _ = write!(
+            body,
+            "<form method=\"post\" action=\"/dashboard/guilds/{guild_id}/messages\">\
+             <input type=\"hidden\" name=\"key\" value=\"{}\">\
+             <label>{key} <input name=\"template\" value=\"{}\" placeholder=\"{}\"></label>\
+             <button>speichere</button></form>",
+            key_value.as_str().unwrap_or_default(),
+            escape(template),
+            escape(key.default_template()),
+        );
+    }
+
+    body.push_str("<h2>Prozess</h2><ul>");
+    for lawsuit in state.lawsuits.iter().rev().filter(|l| !l.sealed) {
+        let _ = write!(
+            body,
+            "<li><a href=\"/dashboard/guilds/{guild_id}/lawsuits/{}\">#{}</a> {} ({})</li>",
+            lawsuit.number,
+            lawsuit.number,
+            escape(&lawsuit.reason),
+            lawsuit.status_name(),
+        );
+    }
+    body.push_str("</ul>");
+
+    Ok(page("Server", &body).into_response())
+}
+
+async fn lawsuit(
+    Extension(dashboard): Extension<Dashboard>,
+    Path((guild_id, number)): Path<(u64, u64)>,
+    headers: HeaderMap,
+) -> PageResult {
+    let state = dashboard.authorize(&headers, guild_id).await?;
+
+    let lawsuit = state
+        .lawsuits
+        .iter()
+        .find(|l| l.number == number && !l.sealed)
+        .ok_or(DashboardError(
+            StatusCode::NOT_FOUND,
+            "de Prozess git's nöd",
+        ))?;
+
+    let http = &dashboard.http;
+    let plaintiff = if lawsuit.anonymous {
+        "Anonym".to_string()
+    } else {
+        user_name(http, lawsuit.plaintiff).await
+    };
+    let accused = user_name(http, lawsuit.accused).await;
+    let judge = user_name(http, lawsuit.judge).await;
+
+    let mut body = format!(
+        "<p><a href=\"/dashboard/guilds/{guild_id}\">zrugg</a></p>\
+         <h2>Prozess #{number}</h2>\
+         <p>Status: {}</p>\
+         <p>Grund: {}</p>\
+         <p>Kläger: {}</p>\
+         <p>Angeklagter: {}</p>\
+         <p>Richter: {}</p>",
+        lawsuit.status_name(),
+        escape(&lawsuit.reason),
+        escape(&plaintiff),
+        escape(&accused),
+        escape(&judge),
+    );
+    if let Some(verdict) = &lawsuit.verdict {
+        let _ = write!(body, "<p>Urteil: {}</p>", escape(verdict));
+    }
+
+    body.push_str("<h3>Stellungnahme</h3><ul>");
+    for statement in &lawsuit.statements {
+        let _ = write!(
+            body,
+            "<li>{}: {}</li>",
+            statement.party,
+            escape(&statement.text)
+        );
+    }
+    body.push_str("</ul><h3>Verlauf</h3><ul>");
+    let mut names = HashMap::<SnowflakeId, String>::new();
+    for entry in &lawsuit.history {
+        let actor = match names.get(&entry.actor) {
+            Some(name) => name.clone(),
+            None => {
+                let name = user_name(http, entry.actor).await;
+                names.insert(entry.actor, name.clone());
+                name
+            }
+        };
+        let _ = write!(
+            body,
+            "<li>{}, {}: {}</li>",
+            state
+                .timezone()
+                .timestamp_millis(entry.timestamp.timestamp_millis())
+                .format("%d.%m.%Y %H:%M"),
+            escape(&actor),
+            escape(&entry.description)
+        );
+    }
+    body.push_str("</ul>");
+
+    Ok(page(&format!("Prozess #{number}"), &body).into_response())
+}
+
+async fn save_settings(
+    Extension(dashboard): Extension<Dashboard>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+    Form(settings): Form<Settings>,
+) -> PageResult {
+    dashboard.check_writable()?;
+    let state = dashboard.authorize(&headers, guild_id).await?;
+    let guild_id = state.guild_id;
+    let mongo = dashboard.mongo.guild(guild_id.into());
+
+    // the form might be stale or edited by hand, so every id has to exist in the guild
+    let channels = GuildId::from(guild_id)
+        .channels(&dashboard.http)
+        .await
+        .wrap_err("fetch channels")?;
+    let roles = GuildId::from(guild_id)
+        .roles(&dashboard.http)
+        .await
+        .wrap_err("fetch roles")?;
+
+    let channel = |value: &str, kind: ChannelType| {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        match value
+            .parse::<u64>()
+            .ok()
+            .and_then(|id| channels.get(&ChannelId(id)))
+        {
+            Some(channel) if channel.kind == kind => Ok(Some(SnowflakeId::from(channel.id))),
+            _ => Err(DashboardError(
+                StatusCode::BAD_REQUEST,
+                "en Kanal gits nöd uf dem Server, lad d'Siite nomal",
+            )),
+        }
+    };
+    let role = |value: &str| {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        match value
+            .parse::<u64>()
+            .ok()
+            .and_then(|id| roles.get(&RoleId(id)))
+        {
+            Some(role) => Ok(Some(SnowflakeId::from(role.id))),
+            None => Err(DashboardError(
+                StatusCode::BAD_REQUEST,
+                "e Rolle gits nöd uf dem Server, lad d'Siite nomal",
+            )),
+        }
+    };
+
+    let court_category = channel(&settings.court_category, ChannelType::Category)?;
+    let judge_role = role(&settings.judge_role)?;
+    let prison_role = role(&settings.prison_role)?;
+    let announcement_channel = channel(&settings.announcement_channel, ChannelType::Text)?;
+    let case_ping_role = role(&settings.case_ping_role)?;
+
+    if let Some(category) = court_category {
+        mongo.set_court_category(guild_id, category).await?;
+    }
+    if let Some(role) = judge_role {
+        mongo.set_judge_role(guild_id, role).await?;
+    }
+    if let Some(role) = prison_role {
+        mongo.set_prison_role(guild_id, role).await?;
+    }
+    mongo
+        .set_announcement_channel(guild_id, announcement_channel)
+        .await?;
+    mongo.set_case_ping_role(guild_id, case_ping_role).await?;
+
+    info!(%guild_id, "Saved settings in dashboard");
+
+    Ok(Redirect::to(&format!("/dashboard/guilds/{guild_id}")).into_response())
+}
+
+async fn save_message(
+    Extension(dashboard): Extension<Dashboard>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+    Form(form): Form<MessageForm>,
+) -> PageResult {
+    dashboard.check_writable()?;
+    let state = dashboard.authorize(&headers, guild_id).await?;
+
+    let template = form.template.trim();
+    let template = (!template.is_empty()).then(|| MessageTemplate {
+        key: form.key,
+        template: template.to_string(),
+    });
+    dashboard
+        .mongo
+        .guild(state.guild_id.into())
+        .set_message_template(state.guild_id, form.key, template.as_ref())
+        .await?;
+
+    Ok(Redirect::to(&format!("/dashboard/guilds/{guild_id}")).into_response())
+}
+
+async fn user_name(http: &Http, id: SnowflakeId) -> String {
+    match UserId::from(id).to_user(http).await {
+        Ok(user) => user.tag(),
+        Err(_) => id.to_string(),
+    }
+}
+
+fn select(
+    body: &mut String,
+    name: &str,
+    label: &str,
+    options: &[(u64, String)],
+    selected: Option<SnowflakeId>,
+) {
+    let _ = write!(
+        body,
+        "<p><label>{label} <select name=\"{name}\"><option value=\"\">-</option>"
+    );
+    for (id, option) in options {
+        let attribute = if selected.map(|s| s.0) == Some(*id) {
+            " selected"
+        } else {
+            ""
+        };
+        let _ = write!(
+            body,
+            "<option value=\"{id}\"{attribute}>{}</option>",
+            escape(option)
+        );
+    }
+    body.push_str("</select></label></p>");
+}
+
+fn page(title: &str, body: &str) -> Html<String> {
+    Html(format!(
+        "<!DOCTYPE html><html lang=\"de-CH\"><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{}</title>\
+         <style>body {{ font-family: sans-serif; max-width: 50rem; margin: auto; padding: 1rem; }}</style>\
+         </head><body><h1>{}</h1>{body}</body></html>",
+        escape(title),
+        escape(title)
+    ))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn urlencode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
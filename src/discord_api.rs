@@ -0,0 +1,92 @@
+use std::future::Future;
+
+use color_eyre::Result;
+use poise::serenity_prelude::{ChannelId, GuildId, Http, HttpError, RoleId, SerenityError, UserId};
+
+use crate::WrapErr;
+
+/// The Discord requests of the logic that can run without Discord, implemented by [`Http`] and a
+/// fake in tests. Requests always go to the API, since the cache might not have seen a change yet.
+pub trait DiscordApi: Send + Sync {
+    /// The roles of the member, or `None` if the user isn't in the guild.
+    fn member_roles(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> impl Future<Output = Result<Option<Vec<RoleId>>>> + Send;
+
+    fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn remove_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn send_message(
+        &self,
+        channel_id: ChannelId,
+        content: String,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl DiscordApi for Http {
+    async fn member_roles(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Option<Vec<RoleId>>> {
+        match self.get_member(guild_id.0, user_id.0).await {
+            Ok(member) => Ok(Some(member.roles)),
+            Err(err) if is_not_found(&err) => Ok(None),
+            Err(err) => Err(err).wrap_err("fetch member"),
+        }
+    }
+
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        Http::add_member_role(self, guild_id.0, user_id.0, role_id.0, None)
+            .await
+            .wrap_err("add member role")
+    }
+
+    async fn remove_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        Http::remove_member_role(self, guild_id.0, user_id.0, role_id.0, None)
+            .await
+            .wrap_err("remove member role")
+    }
+
+    async fn send_message(&self, channel_id: ChannelId, content: String) -> Result<()> {
+        channel_id
+            .say(self, content)
+            .await
+            .wrap_err("send message")?;
+        Ok(())
+    }
+}
+
+/// Whether Discord answered with 404, e.g. for an unknown member or message.
+pub(crate) fn is_not_found(err: &SerenityError) -> bool {
+    matches!(
+        err,
+        SerenityError::Http(err) if matches!(
+            err.as_ref(),
+            HttpError::UnsuccessfulRequest(response) if response.status_code.as_u16() == 404
+        )
+    )
+}
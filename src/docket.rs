@@ -0,0 +1,228 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono_tz::Tz;
+use color_eyre::Result;
+use poise::serenity_prelude::{ChannelId, CreateEmbed, GuildId, Http, MessageId, Timestamp};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    branding,
+    config::TaskInterval,
+    discord_api,
+    duration::format_date_time,
+    lawsuit::Lawsuit,
+    maintenance::Maintenance,
+    model::{SnowflakeId, State},
+    sandbox, Mongo, WrapErr,
+};
+
+/// How often all dockets are refreshed, in case a change didn't refresh them.
+const DOCKET_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+/// How many lawsuits fit on the docket.
+const DOCKET_LIMIT: usize = 25;
+
+/// Discord rejects embeds with a longer description.
+const DESCRIPTION_LIMIT: usize = 4096;
+
+/// The pinned message that lists all open lawsuits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Docket {
+    pub channel_id: SnowflakeId,
+    /// Not set until the message was sent for the first time.
+    pub message_id: Option<SnowflakeId>,
+}
+
+fn docket_line(lawsuit: &Lawsuit, tz: Tz) -> String {
+    let deadline = lawsuit
+        .decision_deadline
+        .map(|deadline| format!(", Urteil bis <t:{}:R>", deadline.timestamp_millis() / 1000))
+        .unwrap_or_default();
+    let hearing = lawsuit
+        .next_hearing()
+        .map(|hearing| format!(", Verhandlig {}", format_date_time(hearing.time, tz)))
+        .unwrap_or_default();
+    format!(
+        "{}\nRichter: <@{}>{deadline}{hearing}",
+        lawsuit.summary(),
+        lawsuit.judge
+    )
+}
+
+pub fn docket_embed<'a>(embed: &'a mut CreateEmbed, state: &State) -> &'a mut CreateEmbed {
+    let lawsuits = state
+        .lawsuits
+        .iter()
+        .filter(|l| !l.is_closed() && !l.arbitration && !l.sealed)
+        .collect::<Vec<_>>();
+
+    let description = if lawsuits.is_empty() {
+        "Es laufe kei Prozess".to_string()
+    } else {
+        let lines = lawsuits
+            .iter()
+            .map(|l| docket_line(l, state.timezone()))
+            .collect::<Vec<_>>();
+        truncated_description(&lines, DOCKET_LIMIT, DESCRIPTION_LIMIT)
+    };
+
+    // the footer is left to the branding of the guild
+    branding::apply(embed, state.branding.as_ref())
+        .title("Offeni Prozess")
+        .description(description)
+        .timestamp(Timestamp::now())
+}
+
+/// Joins as many lines as fit into `max_chars` and at most `max_lines`, and mentions how many
+/// were left out.
+fn truncated_description(lines: &[String], max_lines: usize, max_chars: usize) -> String {
+    let suffix = |rest: usize| format!("\n\n… und {rest} witeri");
+    // reserve room for the longest possible suffix so it always fits
+    let budget = max_chars.saturating_sub(suffix(lines.len()).chars().count());
+
+    let mut description = String::new();
+    let mut len = 0;
+    let mut taken = 0;
+    for line in lines.iter().take(max_lines) {
+        let separator = if taken == 0 { 0 } else { 2 };
+        let line_len = line.chars().count();
+        let fits_all = taken + 1 == lines.len() && len + separator + line_len <= max_chars;
+        if len + separator + line_len > budget && !fits_all {
+            break;
+        }
+        if separator > 0 {
+            description.push_str("\n\n");
+        }
+        description.push_str(line);
+        len += separator + line_len;
+        taken += 1;
+    }
+
+    if taken < lines.len() {
+        description.push_str(&suffix(lines.len() - taken));
+    }
+    description
+}
+
+/// Updates the docket of the guild, or sends and pins a new one if the old message is gone.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn refresh(mongo: &Mongo, http: &Http, guild_id: GuildId) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id.into()).await?;
+    refresh_state(mongo, http, &state).await
+}
+
+async fn refresh_state(mongo: &Mongo, http: &Http, state: &State) -> Result<()> {
+    let docket = match &state.docket {
+        Some(docket) => docket,
+        None => return Ok(()),
+    };
+    let channel_id = ChannelId::from(docket.channel_id);
+
+    if let Some(message_id) = docket.message_id {
+        let result = channel_id
+            .edit_message(http, MessageId::from(message_id), |msg| {
+                msg.embed(|embed| docket_embed(embed, state))
+            })
+            .await;
+        match result {
+            Ok(_) => return Ok(()),
+            // only a deleted message is replaced, anything else would pile up new dockets
+            Err(err) if discord_api::is_not_found(&err) => {
+                warn!(?err, "Docket message is gone, sending a new one")
+            }
+            Err(err) => return Err(err).wrap_err("edit docket"),
+        }
+    }
+
+    let message = channel_id
+        .send_message(http, |msg| msg.embed(|embed| docket_embed(embed, state)))
+        .await
+        .wrap_err("send docket")?;
+    if let Err(err) = message.pin(http).await {
+        warn!(?err, "Failed to pin docket");
+    }
+
+    mongo
+        .set_docket(
+            state.guild_id,
+            Some(&Docket {
+                channel_id: docket.channel_id,
+                message_id: Some(message.id.into()),
+            }),
+        )
+        .await?;
+
+    info!(guild_id = %state.guild_id, "Sent new docket");
+
+    Ok(())
+}
+
+/// Periodically refreshes the dockets of all guilds. Never returns.
+pub async fn docket_task(mongo: Mongo, http: Arc<Http>, maintenance: Maintenance) {
+    let mut interval = TaskInterval::new("docket", DOCKET_REFRESH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if maintenance.is_active() {
+            continue;
+        }
+
+        if let Err(err) = refresh_all(&mongo, &http).await {
+            error!(?err, "Error refreshing dockets");
+        }
+    }
+}
+
+#[tracing::instrument(skip(mongo, http))]
+pub async fn refresh_all(mongo: &Mongo, http: &Http) -> Result<()> {
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        for state in mongo.find_states_with_docket().await? {
+            if sandbox::report_channel(state.guild_id.into()).is_some() {
+                continue;
+            }
+            if let Err(err) = refresh_state(mongo, http, &state).await {
+                error!(?err, guild_id = %state.guild_id, "Error refreshing docket");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn description_fits_into_an_embed() {
+        let lines = (0..10)
+            .map(|i| format!("{i}").repeat(1000))
+            .collect::<Vec<_>>();
+
+        let description = truncated_description(&lines, DOCKET_LIMIT, DESCRIPTION_LIMIT);
+
+        assert!(description.chars().count() <= DESCRIPTION_LIMIT);
+        assert!(description.starts_with(&lines[0]));
+        assert!(description.ends_with("… und 6 witeri"));
+    }
+
+    #[test]
+    fn description_mentions_lawsuits_over_the_limit() {
+        let lines = (0..30).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        let description = truncated_description(&lines, DOCKET_LIMIT, DESCRIPTION_LIMIT);
+
+        assert!(description.starts_with("0\n\n1\n\n"));
+        assert!(description.ends_with("24\n\n… und 5 witeri"));
+    }
+
+    #[test]
+    fn description_without_truncation() {
+        let lines = vec!["a".to_string(), "b".to_string()];
+
+        assert_eq!(truncated_description(&lines, 25, 4096), "a\n\nb");
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use mongodb::bson::DateTime;
+
+/// Parses durations like `30m`, `12h`, `3d` or `1w`. Multiple parts can be combined, like `1d12h`.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut total = 0_u64;
+    let mut number = String::new();
+
+    for char in input.trim().chars() {
+        if char.is_ascii_digit() {
+            number.push(char);
+            continue;
+        }
+
+        let unit = match char.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 60 * 60 * 24,
+            'w' => 60 * 60 * 24 * 7,
+            _ => return None,
+        };
+        let value = number.parse::<u64>().ok()?;
+        total = total.checked_add(value.checked_mul(unit)?)?;
+        number.clear();
+    }
+
+    if !number.is_empty() || total == 0 {
+        return None;
+    }
+
+    Some(Duration::from_secs(total))
+}
+
+/// Formats a duration in the largest fitting units, like `1d 12h`.
+pub fn format_duration(duration: Duration) -> String {
+    let mut secs = duration.as_secs();
+    let mut parts = Vec::new();
+
+    for (unit, name) in [(60 * 60 * 24, "d"), (60 * 60, "h"), (60, "m"), (1, "s")] {
+        if secs >= unit {
+            parts.push(format!("{}{name}", secs / unit));
+            secs %= unit;
+        }
+    }
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Parses time zone names like `Europe/Zurich`.
+pub fn parse_timezone(input: &str) -> Option<Tz> {
+    input.trim().parse().ok()
+}
+
+fn from_local(date_time: NaiveDateTime, tz: Tz) -> Option<DateTime> {
+    let date_time = tz.from_local_datetime(&date_time).earliest()?;
+    Some(DateTime::from_millis(date_time.timestamp_millis()))
+}
+
+/// Parses dates like `2022-06-30` or `30.06.2022` as midnight in the time zone.
+pub fn parse_date(input: &str, tz: Tz) -> Option<DateTime> {
+    let input = input.trim();
+    let date = NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(input, "%d.%m.%Y"))
+        .ok()?;
+
+    from_local(date.and_hms(0, 0, 0), tz)
+}
+
+/// Parses dates with a time like `2022-06-30 18:00` or `30.06.2022 18:00` in the time zone.
+pub fn parse_date_time(input: &str, tz: Tz) -> Option<DateTime> {
+    let input = input.trim();
+    let date_time = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(input, "%d.%m.%Y %H:%M"))
+        .ok()?;
+
+    from_local(date_time, tz)
+}
+
+/// Formats a date as Discord timestamp, which everyone sees in their own time zone, followed by
+/// the date in the time zone of the guild, like `<t:1656547200:d> (30.06.2022)`.
+pub fn format_date(time: DateTime, tz: Tz) -> String {
+    let local = tz.timestamp_millis(time.timestamp_millis());
+    format!(
+        "<t:{}:d> ({})",
+        time.timestamp_millis() / 1000,
+        local.format("%d.%m.%Y")
+    )
+}
+
+/// Like [`format_date`], but with the time, like `<t:1656604800:f> (30.06.2022 18:00 CEST)`.
+pub fn format_date_time(time: DateTime, tz: Tz) -> String {
+    let local = tz.timestamp_millis(time.timestamp_millis());
+    format!(
+        "<t:{}:f> ({})",
+        time.timestamp_millis() / 1000,
+        local.format("%d.%m.%Y %H:%M %Z")
+    )
+}
@@ -0,0 +1,602 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use color_eyre::Result;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, DateTime, Uuid};
+use poise::serenity_prelude::{GuildId, Http, RoleId};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::{
+    config::TaskInterval,
+    maintenance::Maintenance,
+    model::SnowflakeId,
+    sandbox,
+    scheduler::{self, JobKind},
+    Mongo, WrapErr,
+};
+
+/// How often the economy task checks whether salaries or taxes are due.
+const ECONOMY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the income of debtors is garnished.
+pub const GARNISHMENT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    pub balance: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Grant,
+    FilingFee,
+    FilingFeeRefund,
+    Damages,
+    Garnishment,
+    Salary,
+    Fine,
+    /// Paid back when a fine is undone.
+    FineRefund,
+    /// Paid back when the lawsuit is reopened.
+    DamagesRefund,
+    Tax,
+    /// Paid by the treasury for time that was wrongfully spent in prison.
+    Compensation,
+}
+
+impl Display for TransactionKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Grant => "Gutschrift",
+            Self::FilingFee => "Gerichtsgebühr",
+            Self::FilingFeeRefund => "Rückerstattung Gerichtsgebühr",
+            Self::Damages => "Schadenersatz",
+            Self::Garnishment => "Lohnpfändung",
+            Self::Salary => "Lohn",
+            Self::Fine => "Busse",
+            Self::FineRefund => "Rückerstattung Busse",
+            Self::DamagesRefund => "Rückerstattung Schadenersatz",
+            Self::Tax => "Steuer",
+            Self::Compensation => "Entschädigung",
+        })
+    }
+}
+
+/// A single entry in the ledger. Positive amounts are income, negative amounts are expenses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    pub amount: i64,
+    pub kind: TransactionKind,
+    pub description: String,
+    pub timestamp: DateTime,
+}
+
+/// An amount someone was ordered to pay but could not cover with their balance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Debt {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub debtor: SnowflakeId,
+    pub creditor: SnowflakeId,
+    /// The amount that is still outstanding.
+    pub amount: i64,
+    pub reason: String,
+    pub lawsuit_id: Option<Uuid>,
+    pub created_at: DateTime,
+    /// The end of the income window that was last garnished for the debt.
+    #[serde(default)]
+    pub garnished_until: Option<DateTime>,
+}
+
+/// The role that members with unpaid debts over the threshold get when they rejoin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtorRole {
+    pub threshold: i64,
+    /// Members only get a reminder if there is no role.
+    pub role_id: Option<SnowflakeId>,
+}
+
+/// A periodic payment from the treasury to everyone with a role.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Salary {
+    pub role_id: SnowflakeId,
+    pub amount: i64,
+    pub interval_secs: i64,
+    pub last_paid: DateTime,
+}
+
+/// A periodic tax on all positive balances that flows into the treasury.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tax {
+    pub percent: u8,
+    pub interval_secs: i64,
+    pub last_collected: DateTime,
+}
+
+/// The treasury of a guild is stored as an account with the id of the guild.
+pub fn treasury_account(guild_id: SnowflakeId) -> SnowflakeId {
+    guild_id
+}
+
+pub fn format_amount(amount: i64) -> String {
+    format!("{amount} Fr.")
+}
+
+/// Formats a transaction as a single line for ledger listings.
+pub fn format_transaction(transaction: &Transaction) -> String {
+    let sign = if transaction.amount >= 0 { "+" } else { "" };
+    format!(
+        "<t:{}:d> `{sign}{}` {} – {}",
+        transaction.timestamp.timestamp_millis() / 1000,
+        format_amount(transaction.amount),
+        transaction.kind,
+        transaction.description,
+    )
+}
+
+/// Garnishes the income the debtor earned since the last run for their oldest debts and schedules
+/// the next run. Stops once the debtor has no outstanding debts anymore.
+#[tracing::instrument(skip(mongo))]
+pub async fn garnish(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    debtor: SnowflakeId,
+    due: DateTime,
+) -> Result<()> {
+    let debts = mongo.find_debts(guild_id, debtor).await?;
+    let since = match garnishment_start(&debts) {
+        Some(since) => since,
+        None => return Ok(()),
+    };
+
+    // the job may run again if the bot stopped in between, the window was already garnished then
+    if since < due {
+        // mark the window as garnished first, we'd rather skip a deduction than deduct twice
+        mongo.set_garnished_until(guild_id, debtor, due).await?;
+
+        let state = mongo.find_or_insert_state(guild_id).await?;
+        // the window still moves on while garnishment is off, so that the income is never taken later
+        if let Some(percent) = state.garnishment_percent {
+            let income = mongo
+                .find_transactions_between(guild_id, debtor, since, due)
+                .await?;
+            let budget = garnishable_income(&income).saturating_mul(i64::from(percent)) / 100;
+            garnish_debts(mongo, guild_id, debtor, &debts, budget).await?;
+        }
+    }
+
+    // reschedule even if the debts are paid now, the next run stops if there are none left
+    let next =
+        DateTime::from_millis(due.timestamp_millis() + GARNISHMENT_INTERVAL.as_millis() as i64);
+    let scheduled = mongo.find_jobs(Some(guild_id)).await?.iter().any(|job| {
+        matches!(job.kind, JobKind::Garnishment { user_id } if user_id == debtor) && job.due == next
+    });
+    if !scheduled {
+        scheduler::schedule(
+            mongo,
+            guild_id,
+            JobKind::Garnishment { user_id: debtor },
+            next,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Pays the debts oldest first from the budget until it or the balance of the debtor runs out.
+async fn garnish_debts(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    debtor: SnowflakeId,
+    debts: &[Debt],
+    mut budget: i64,
+) -> Result<()> {
+    for debt in debts {
+        if budget <= 0 {
+            break;
+        }
+
+        let part = debt.amount.min(budget);
+        let paid = mongo
+            .debit_up_to(
+                guild_id,
+                debtor,
+                part,
+                TransactionKind::Garnishment,
+                &debt.reason,
+            )
+            .await?;
+        if paid == 0 {
+            break;
+        }
+
+        mongo
+            .reduce_debt(debt.id, paid)
+            .await
+            .wrap_err("reduce garnished debt")?;
+        mongo
+            .credit(
+                guild_id,
+                debt.creditor,
+                paid,
+                TransactionKind::Garnishment,
+                &debt.reason,
+            )
+            .await
+            .wrap_err("pay garnished amount to creditor")?;
+
+        info!(debt_id = %debt.id, %paid, "Garnished income for debt");
+
+        // the balance ran out
+        if paid < part {
+            break;
+        }
+        budget -= paid;
+    }
+
+    Ok(())
+}
+
+/// Starts garnishing the income of someone who got a new debt. A debtor only ever has a single
+/// garnishment job for all their debts, which keeps rescheduling itself while they have any.
+#[tracing::instrument(skip(mongo))]
+pub async fn start_garnishment(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    debtor: SnowflakeId,
+) -> Result<()> {
+    let scheduled = mongo.find_jobs(Some(guild_id)).await?.iter().any(|job| {
+        matches!(job.kind, JobKind::Garnishment { user_id } if user_id == debtor) && !job.failed
+    });
+    if !scheduled {
+        let due = DateTime::from_millis(
+            DateTime::now().timestamp_millis() + GARNISHMENT_INTERVAL.as_millis() as i64,
+        );
+        scheduler::schedule(
+            mongo,
+            guild_id,
+            JobKind::Garnishment { user_id: debtor },
+            due,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Where the next garnishment window of the debts starts, or `None` if there are no debts.
+/// Debts that were added after the last run are garnished from the end of that run on.
+fn garnishment_start(debts: &[Debt]) -> Option<DateTime> {
+    debts
+        .iter()
+        .filter_map(|debt| debt.garnished_until)
+        .max()
+        .or_else(|| debts.iter().map(|debt| debt.created_at).min())
+}
+
+/// The income that can be garnished. Garnished amounts that were paid to the debtor as a creditor
+/// don't count, they were income from garnishment already.
+fn garnishable_income(transactions: &[Transaction]) -> i64 {
+    transactions
+        .iter()
+        .filter(|transaction| transaction.kind != TransactionKind::Garnishment)
+        .map(|transaction| transaction.amount.max(0))
+        .fold(0, i64::saturating_add)
+}
+
+/// The compensation for the full hours that were spent in prison. Saturates instead of
+/// overflowing for rates that are set very high.
+pub fn compensation_amount(rate_per_hour: i64, served: Duration) -> i64 {
+    let hours = i64::try_from(served.as_secs() / (60 * 60)).unwrap_or(i64::MAX);
+    rate_per_hour.saturating_mul(hours)
+}
+
+/// Pays the member from the treasury for the time they were wrongfully in prison, if the guild
+/// pays compensation. Returns the paid amount.
+#[tracing::instrument(skip(mongo))]
+pub async fn compensate(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    user_id: SnowflakeId,
+    served: Duration,
+    description: &str,
+) -> Result<Option<i64>> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let amount = match state.compensation_rate {
+        Some(rate) => compensation_amount(rate, served),
+        None => return Ok(None),
+    };
+    if amount <= 0 {
+        return Ok(None);
+    }
+
+    mongo
+        .credit(
+            guild_id,
+            user_id,
+            amount,
+            TransactionKind::Compensation,
+            description,
+        )
+        .await?;
+    // the treasury is allowed to go into deficit
+    mongo
+        .credit(
+            guild_id,
+            treasury_account(guild_id),
+            -amount,
+            TransactionKind::Compensation,
+            description,
+        )
+        .await?;
+
+    info!(%guild_id, %user_id, %amount, "Paid compensation");
+
+    Ok(Some(amount))
+}
+
+/// Periodically pays out all due salaries and collects due taxes. Never returns.
+pub async fn economy_task(mongo: Mongo, http: Arc<Http>, maintenance: Maintenance) {
+    let mut interval = TaskInterval::new("economy", ECONOMY_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if maintenance.is_active() {
+            continue;
+        }
+
+        if let Err(err) = pay_due_salaries(&mongo, &http).await {
+            error!(?err, "Error paying salaries");
+        }
+
+        if let Err(err) = collect_due_taxes(&mongo).await {
+            error!(?err, "Error collecting taxes");
+        }
+    }
+}
+
+#[tracing::instrument(skip(mongo))]
+pub async fn collect_due_taxes(mongo: &Mongo) -> Result<()> {
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        let now = DateTime::now();
+
+        for state in mongo.find_states_with_tax().await? {
+            if sandbox::report_channel(state.guild_id.into()).is_some() {
+                continue;
+            }
+            let tax = match state.tax {
+                Some(tax) => tax,
+                None => continue,
+            };
+
+            let due = tax.last_collected.timestamp_millis() + tax.interval_secs * 1000;
+            if due > now.timestamp_millis() {
+                continue;
+            }
+
+            mongo.set_tax_collected(state.guild_id, now).await?;
+
+            if let Err(err) = collect_tax(mongo, state.guild_id, &tax).await {
+                error!(?err, guild_id = %state.guild_id, "Error collecting tax");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn collect_tax(mongo: &Mongo, guild_id: SnowflakeId, tax: &Tax) -> Result<()> {
+    let treasury = treasury_account(guild_id);
+    let mut total = 0;
+
+    for account in mongo.find_accounts(guild_id).await? {
+        if account.user_id == treasury {
+            continue;
+        }
+
+        let amount = account.balance * i64::from(tax.percent) / 100;
+        if amount <= 0 {
+            continue;
+        }
+
+        let paid = mongo
+            .debit_up_to(
+                guild_id,
+                account.user_id,
+                amount,
+                TransactionKind::Tax,
+                "Steuer",
+            )
+            .await?;
+        total += paid;
+    }
+
+    if total > 0 {
+        mongo
+            .credit(guild_id, treasury, total, TransactionKind::Tax, "Steuer")
+            .await?;
+    }
+
+    info!(%guild_id, %total, "Collected tax");
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(mongo, http))]
+pub async fn pay_due_salaries(mongo: &Mongo, http: &Http) -> Result<()> {
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        let now = DateTime::now();
+
+        for state in mongo.find_states_with_salaries().await? {
+            if sandbox::report_channel(state.guild_id.into()).is_some() {
+                continue;
+            }
+            for salary in state.salaries {
+                let due = salary.last_paid.timestamp_millis() + salary.interval_secs * 1000;
+                if due > now.timestamp_millis() {
+                    continue;
+                }
+
+                // mark it as paid first, we'd rather skip a payment than pay twice
+                mongo
+                    .set_salary_paid(state.guild_id, salary.role_id, now)
+                    .await?;
+
+                if let Err(err) = pay_salary(mongo, http, state.guild_id, &salary).await {
+                    error!(?err, guild_id = %state.guild_id, role_id = %salary.role_id, "Error paying salary");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn pay_salary(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    salary: &Salary,
+) -> Result<()> {
+    let role_id = RoleId::from(salary.role_id);
+    let description = format!("Lohn für <@&{}>", salary.role_id);
+
+    let members = GuildId::from(guild_id)
+        .members_iter(http)
+        .try_filter(|member| futures::future::ready(member.roles.contains(&role_id)))
+        .try_collect::<Vec<_>>()
+        .await
+        .wrap_err("fetch members")?;
+
+    for member in &members {
+        mongo
+            .credit(
+                guild_id,
+                member.user.id.into(),
+                salary.amount,
+                TransactionKind::Salary,
+                &description,
+            )
+            .await?;
+    }
+
+    // the treasury is allowed to go into deficit
+    let total = salary.amount * members.len() as i64;
+    if total > 0 {
+        mongo
+            .credit(
+                guild_id,
+                treasury_account(guild_id),
+                -total,
+                TransactionKind::Salary,
+                &description,
+            )
+            .await?;
+    }
+
+    info!(%guild_id, role_id = %salary.role_id, members = members.len(), "Paid salary");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mongodb::bson::{DateTime, Uuid};
+
+    use super::{
+        compensation_amount, garnishable_income, garnishment_start, Debt, Transaction,
+        TransactionKind,
+    };
+    use crate::model::SnowflakeId;
+
+    fn debt(created_at: i64, garnished_until: Option<i64>) -> Debt {
+        Debt {
+            id: Uuid::new(),
+            guild_id: SnowflakeId(1),
+            debtor: SnowflakeId(2),
+            creditor: SnowflakeId(3),
+            amount: 100,
+            reason: "Schadenersatz".to_string(),
+            lawsuit_id: None,
+            created_at: DateTime::from_millis(created_at),
+            garnished_until: garnished_until.map(DateTime::from_millis),
+        }
+    }
+
+    fn transaction(amount: i64, kind: TransactionKind) -> Transaction {
+        Transaction {
+            id: Uuid::new(),
+            guild_id: SnowflakeId(1),
+            user_id: SnowflakeId(2),
+            amount,
+            kind,
+            description: String::new(),
+            timestamp: DateTime::now(),
+        }
+    }
+
+    #[test]
+    fn compensation() {
+        let hour = 60 * 60;
+        assert_eq!(compensation_amount(10, Duration::from_secs(3 * hour)), 30);
+        assert_eq!(compensation_amount(10, Duration::from_secs(hour - 1)), 0);
+        assert_eq!(
+            compensation_amount(10, Duration::from_secs(2 * hour + 59)),
+            20
+        );
+        assert_eq!(
+            compensation_amount(i64::MAX, Duration::from_secs(2 * hour)),
+            i64::MAX
+        );
+        assert_eq!(compensation_amount(1_000_000, Duration::MAX), i64::MAX);
+    }
+
+    #[test]
+    fn garnishment_window_continues_after_the_last_run() {
+        assert_eq!(garnishment_start(&[]), None);
+        assert_eq!(
+            garnishment_start(&[debt(20, None), debt(10, None)]),
+            Some(DateTime::from_millis(10))
+        );
+        // a debt added after the last run doesn't garnish the same income again
+        assert_eq!(
+            garnishment_start(&[debt(10, Some(50)), debt(60, None)]),
+            Some(DateTime::from_millis(50))
+        );
+    }
+
+    #[test]
+    fn only_income_is_garnished() {
+        let transactions = [
+            transaction(100, TransactionKind::Salary),
+            transaction(-30, TransactionKind::Fine),
+            transaction(20, TransactionKind::Damages),
+            transaction(50, TransactionKind::Garnishment),
+        ];
+        assert_eq!(garnishable_income(&transactions), 120);
+        assert_eq!(
+            garnishable_income(&[
+                transaction(i64::MAX, TransactionKind::Grant),
+                transaction(1, TransactionKind::Grant),
+            ]),
+            i64::MAX
+        );
+    }
+}
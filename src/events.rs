@@ -0,0 +1,432 @@
+use std::{
+    fmt::{Display, Formatter},
+    future::Future,
+};
+
+use color_eyre::Result;
+use mongodb::bson::{self, doc, spec::BinarySubtype, Bson, DateTime, Document, Uuid};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{handler, logging::Invocation, model::SnowflakeId, Context, Mongo};
+
+/// A state-changing operation with the changes it made to the data of the guild. Events are only
+/// appended, never changed, except for an undo, which is stored before it's applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    /// `None` for background tasks.
+    pub actor: Option<SnowflakeId>,
+    /// What caused the changes, like `/prison arrest`.
+    pub action: String,
+    pub changes: Vec<Change>,
+    pub timestamp: DateTime,
+    /// The event that this one reverses, set by `/admin undo`.
+    #[serde(default)]
+    pub undoes: Option<Uuid>,
+}
+
+/// A changed value in the snapshot of a guild. The path is made of field names separated by `.`,
+/// elements of lists are addressed by their id like `state.lawsuits[<id>]`. A missing value means
+/// that the field or element didn't exist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub path: String,
+    pub before: Option<Bson>,
+    pub after: Option<Bson>,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.before, &self.after) {
+            (None, _) => write!(f, "+{}", self.path),
+            (_, None) => write!(f, "-{}", self.path),
+            (Some(Bson::Document(_) | Bson::Array(_)), _)
+            | (_, Some(Bson::Document(_) | Bson::Array(_))) => write!(f, "~{}", self.path),
+            (Some(before), Some(after)) => write!(f, "{}: {before} → {after}", self.path),
+        }
+    }
+}
+
+/// The data of the guild that events track: the state document and the prison entries.
+#[tracing::instrument(skip(mongo))]
+pub async fn snapshot(mongo: &Mongo, guild_id: SnowflakeId) -> Result<Document> {
+    let mut state = mongo.find_raw_state(guild_id).await?.unwrap_or_default();
+    state.remove("_id");
+    let prisoners = mongo
+        .find_prisoners(guild_id)
+        .await?
+        .iter()
+        .map(bson::to_bson)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(doc! { "state": state, "prison": prisoners })
+}
+
+/// Compares the snapshot with the current data and stores an event if anything changed. Failures
+/// are only logged, the operation already happened.
+pub async fn record(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    actor: Option<SnowflakeId>,
+    action: impl Into<String>,
+    before: &Document,
+) {
+    match snapshot(mongo, guild_id).await {
+        Ok(after) => {
+            let changes = diff(before, &after);
+            record_changes(mongo, guild_id, actor, action, changes).await;
+        }
+        Err(err) => warn!(?err, %guild_id, "Failed to take snapshot for event"),
+    }
+}
+
+/// Stores an event with changes that are known without comparing snapshots, like changes to data
+/// that isn't part of the snapshot. Nothing is stored if there are no changes.
+pub async fn record_changes(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    actor: Option<SnowflakeId>,
+    action: impl Into<String>,
+    changes: Vec<Change>,
+) {
+    if changes.is_empty() {
+        return;
+    }
+
+    let event = Event {
+        id: Uuid::new(),
+        guild_id,
+        actor,
+        action: action.into(),
+        changes,
+        timestamp: DateTime::now(),
+        undoes: None,
+    };
+    if let Err(err) = mongo.add_event(&event).await {
+        warn!(?err, %guild_id, "Failed to record event");
+    }
+}
+
+/// Takes the snapshot that the command is compared to when it's finished.
+pub async fn start_command(ctx: Context<'_>) {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let before = match snapshot(&handler::guild_mongo(ctx), guild_id.into()).await {
+        Ok(before) => before,
+        Err(err) => {
+            warn!(?err, %guild_id, "Failed to take snapshot before command");
+            return;
+        }
+    };
+    if let Some(mut invocation) = ctx.invocation_data::<Invocation>().await {
+        invocation.state_before = Some(before);
+    }
+}
+
+/// Records what the command changed, whether it succeeded or not.
+pub async fn finish_command(ctx: Context<'_>) {
+    let (guild_id, before) = match (ctx.guild_id(), take_state_before(ctx).await) {
+        (Some(guild_id), Some(before)) => (guild_id, before),
+        _ => return,
+    };
+
+    let action = format!("/{}", ctx.command().qualified_name);
+    let actor = Some(ctx.author().id.into());
+    record(
+        &handler::guild_mongo(ctx),
+        guild_id.into(),
+        actor,
+        action,
+        &before,
+    )
+    .await;
+}
+
+/// Stops the command from being recorded when it's finished, for commands that record their own
+/// event.
+pub async fn skip_command(ctx: Context<'_>) {
+    take_state_before(ctx).await;
+}
+
+async fn take_state_before(ctx: Context<'_>) -> Option<Document> {
+    ctx.invocation_data::<Invocation>()
+        .await?
+        .state_before
+        .take()
+}
+
+/// Runs the operation and records its changes as an event.
+pub async fn tracked<T>(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    actor: Option<SnowflakeId>,
+    action: impl Into<String>,
+    operation: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let before = snapshot(mongo, guild_id).await?;
+    let result = operation.await;
+    // failed operations might have changed something before failing
+    record(mongo, guild_id, actor, action, &before).await;
+    result
+}
+
+/// The changes from one snapshot to the other.
+pub fn diff(before: &Document, after: &Document) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_documents("", before, after, 0, &mut changes);
+    changes
+}
+
+/// Goes into the sections of the snapshot and the fields of the state, but not deeper.
+const DIFF_DEPTH: usize = 2;
+
+fn diff_documents(
+    path: &str,
+    before: &Document,
+    after: &Document,
+    depth: usize,
+    changes: &mut Vec<Change>,
+) {
+    let removed = before.keys().filter(|key| !after.contains_key(*key));
+    for key in after.keys().chain(removed) {
+        let path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{path}.{key}")
+        };
+        diff_values(path, before.get(key), after.get(key), depth + 1, changes);
+    }
+}
+
+fn diff_values(
+    path: String,
+    before: Option<&Bson>,
+    after: Option<&Bson>,
+    depth: usize,
+    changes: &mut Vec<Change>,
+) {
+    if before == after {
+        return;
+    }
+
+    match (before, after) {
+        (Some(Bson::Document(before)), Some(Bson::Document(after))) if depth < DIFF_DEPTH => {
+            diff_documents(&path, before, after, depth, changes);
+        }
+        (Some(Bson::Array(before)), Some(Bson::Array(after)))
+            if is_keyed(before) && is_keyed(after) =>
+        {
+            let removed = before
+                .iter()
+                .filter(|element| find_element(after, &element_key(element)).is_none());
+            for element in after.iter().chain(removed) {
+                let key = element_key(element);
+                changes.extend(element_change(
+                    format!("{path}[{}]", key.as_deref().unwrap_or_default()),
+                    find_element(before, &key),
+                    find_element(after, &key),
+                ));
+            }
+        }
+        _ => changes.push(Change {
+            path,
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+    }
+}
+
+fn element_change(path: String, before: Option<&Bson>, after: Option<&Bson>) -> Option<Change> {
+    (before != after).then(|| Change {
+        path,
+        before: before.cloned(),
+        after: after.cloned(),
+    })
+}
+
+/// Lists of lawsuits, court rooms, prisoners and similar are compared by element, identified by
+/// the first of these fields.
+const KEY_FIELDS: [&str; 3] = ["id", "user_id", "channel_id"];
+
+fn element_key(element: &Bson) -> Option<String> {
+    let document = element.as_document()?;
+    let value = KEY_FIELDS.iter().find_map(|field| document.get(field))?;
+    Some(match value {
+        Bson::String(value) => value.clone(),
+        Bson::Binary(binary) if binary.subtype == BinarySubtype::Uuid => {
+            Uuid::from_bytes(binary.bytes.clone().try_into().ok()?).to_string()
+        }
+        value => value.to_string(),
+    })
+}
+
+fn is_keyed(elements: &[Bson]) -> bool {
+    let mut keys = Vec::with_capacity(elements.len());
+    for element in elements {
+        match element_key(element) {
+            Some(key) if !keys.contains(&key) => keys.push(key),
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn find_element<'a>(elements: &'a [Bson], key: &Option<String>) -> Option<&'a Bson> {
+    elements.iter().find(|element| element_key(element) == *key)
+}
+
+/// Undoes the changes of the events, which must be ordered from newest to oldest, to get the
+/// snapshot from before the oldest one. Changes to data outside of the snapshot are skipped.
+pub fn revert(snapshot: &mut Document, events: &[Event]) {
+    for event in events {
+        for change in event.changes.iter().rev() {
+            let root = change.path.split(['.', '[']).next().unwrap_or_default();
+            if snapshot.contains_key(root) {
+                set_path(snapshot, &change.path, change.before.clone());
+            }
+        }
+    }
+}
+
+/// Sets or removes the value at the path of a change.
+fn set_path(document: &mut Document, path: &str, value: Option<Bson>) {
+    let (parents, last) = match path.rsplit_once('.') {
+        Some((parents, last)) => (Some(parents), last),
+        None => (None, path),
+    };
+
+    let mut target = document;
+    for segment in parents.into_iter().flat_map(|parents| parents.split('.')) {
+        if !target.contains_key(segment) {
+            target.insert(segment, Document::new());
+        }
+        target = match target.get_document_mut(segment) {
+            Ok(target) => target,
+            Err(_) => return,
+        };
+    }
+
+    match last.split_once('[') {
+        Some((field, key)) => {
+            let key = Some(key.trim_end_matches(']').to_string());
+            if !target.contains_key(field) {
+                target.insert(field, Bson::Array(Vec::new()));
+            }
+            let elements = match target.get_array_mut(field) {
+                Ok(elements) => elements,
+                Err(_) => return,
+            };
+            let position = elements
+                .iter()
+                .position(|element| element_key(element) == key);
+            match (position, value) {
+                (Some(position), Some(value)) => elements[position] = value,
+                (Some(position), None) => {
+                    elements.remove(position);
+                }
+                (None, Some(value)) => elements.push(value),
+                (None, None) => {}
+            }
+        }
+        None => match value {
+            Some(value) => {
+                target.insert(last, value);
+            }
+            None => {
+                target.remove(last);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{doc, Bson, DateTime, Uuid};
+
+    use super::{diff, revert, Change, Event};
+    use crate::model::SnowflakeId;
+
+    fn event(changes: Vec<Change>) -> Event {
+        Event {
+            id: Uuid::new(),
+            guild_id: SnowflakeId(1),
+            actor: None,
+            action: "test".to_string(),
+            changes,
+            timestamp: DateTime::now(),
+            undoes: None,
+        }
+    }
+
+    #[test]
+    fn diff_fields() {
+        let before = doc! { "state": { "case_counter": 1, "judge_role": "2" }, "prison": [] };
+        let after = doc! { "state": { "case_counter": 2, "tax": { "percent": 5 } }, "prison": [] };
+
+        let changes = diff(&before, &after);
+
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    path: "state.case_counter".to_string(),
+                    before: Some(Bson::Int32(1)),
+                    after: Some(Bson::Int32(2)),
+                },
+                Change {
+                    path: "state.tax".to_string(),
+                    before: None,
+                    after: Some(Bson::Document(doc! { "percent": 5 })),
+                },
+                Change {
+                    path: "state.judge_role".to_string(),
+                    before: Some(Bson::String("2".to_string())),
+                    after: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_list_elements() {
+        let before = doc! { "state": {}, "prison": [{ "user_id": "1" }, { "user_id": "2" }] };
+        let after = doc! { "state": {}, "prison": [{ "user_id": "2" }, { "user_id": "3" }] };
+
+        let paths = diff(&before, &after)
+            .into_iter()
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec!["+prison[3]", "-prison[1]"]);
+    }
+
+    #[test]
+    fn revert_restores_snapshot() {
+        let first = doc! {
+            "state": { "case_counter": 1, "lawsuits": [{ "id": "a", "reason": "x" }] },
+            "prison": [{ "user_id": "1" }],
+        };
+        let second = doc! {
+            "state": { "case_counter": 2, "lawsuits": [{ "id": "a", "reason": "y" }, { "id": "b" }] },
+            "prison": [],
+        };
+        let third = doc! {
+            "state": { "case_counter": 2, "lawsuits": [{ "id": "b" }], "log_channel": "5" },
+            "prison": [{ "user_id": "2" }],
+        };
+
+        let events = [event(diff(&second, &third)), event(diff(&first, &second))];
+        let mut snapshot = third.clone();
+        revert(&mut snapshot, &events[..1]);
+        // reverted list elements can end up in another order
+        assert_eq!(diff(&snapshot, &second), vec![]);
+
+        revert(&mut snapshot, &events[1..]);
+        assert_eq!(diff(&snapshot, &first), vec![]);
+    }
+}
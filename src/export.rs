@@ -0,0 +1,214 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::TimeZone;
+use color_eyre::Result;
+use mongodb::bson::DateTime;
+
+use crate::{economy::TransactionKind, model::State, Mongo};
+
+/// The records that can be exported as CSV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum ExportKind {
+    #[name = "Gefängnis"]
+    Prison,
+    #[name = "Bussen"]
+    Fines,
+    #[name = "Prozesse"]
+    Cases,
+}
+
+impl ExportKind {
+    pub fn columns(self) -> &'static [&'static str] {
+        match self {
+            Self::Prison => &["user_id", "arrested_at", "left_at"],
+            Self::Fines => &["user_id", "amount", "description", "timestamp"],
+            Self::Cases => &[
+                "number",
+                "status",
+                "case_type",
+                "plaintiff",
+                "accused",
+                "judge",
+                "reason",
+                "verdict",
+                "winner",
+                "damages",
+                "created_at",
+            ],
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Prison => "prison",
+            Self::Fines => "fines",
+            Self::Cases => "cases",
+        }
+    }
+}
+
+impl Display for ExportKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Prison => "Gefängnis",
+            Self::Fines => "Bussen",
+            Self::Cases => "Prozesse",
+        })
+    }
+}
+
+/// Selects the columns by name, in the order they are given. All columns are used if there are
+/// none. Returns `None` if one of them doesn't exist.
+pub fn parse_columns(kind: ExportKind, input: &str) -> Option<Vec<usize>> {
+    let all = kind.columns();
+    let selected = input
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            all.iter()
+                .position(|column| column.eq_ignore_ascii_case(name))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    if selected.is_empty() {
+        Some((0..all.len()).collect())
+    } else {
+        Some(selected)
+    }
+}
+
+/// Builds the CSV with the columns, only including records in the time range. The end is
+/// exclusive.
+#[tracing::instrument(skip(mongo, state))]
+pub async fn export(
+    mongo: &Mongo,
+    state: &State,
+    kind: ExportKind,
+    columns: &[usize],
+    from: Option<DateTime>,
+    to: Option<DateTime>,
+) -> Result<String> {
+    let tz = state.timezone();
+    let date = |time: Option<DateTime>| {
+        time.map(|time| {
+            tz.timestamp_millis(time.timestamp_millis())
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+    };
+    let in_range = |time: Option<DateTime>| match time {
+        Some(time) => from.is_none_or(|from| time >= from) && to.is_none_or(|to| time < to),
+        None => from.is_none() && to.is_none(),
+    };
+
+    let rows: Vec<Vec<String>> = match kind {
+        ExportKind::Prison => mongo
+            .find_prisoners(state.guild_id)
+            .await?
+            .into_iter()
+            .filter(|entry| in_range(entry.arrested_at))
+            .map(|entry| {
+                vec![
+                    entry.user_id.to_string(),
+                    date(entry.arrested_at),
+                    date(entry.left_at),
+                ]
+            })
+            .collect(),
+        ExportKind::Fines => mongo
+            .find_transactions_of_kind(state.guild_id, TransactionKind::Fine, from, to)
+            .await?
+            .into_iter()
+            // the treasury receives every fine, only the payments are listed
+            .filter(|transaction| transaction.amount < 0)
+            .map(|transaction| {
+                vec![
+                    transaction.user_id.to_string(),
+                    (-transaction.amount).to_string(),
+                    transaction.description,
+                    date(Some(transaction.timestamp)),
+                ]
+            })
+            .collect(),
+        ExportKind::Cases => state
+            .lawsuits
+            .iter()
+            .filter(|l| !l.sealed && in_range(l.created_at))
+            .map(|l| {
+                vec![
+                    l.number.to_string(),
+                    l.status_name().to_string(),
+                    l.case_type.map(|t| t.to_string()).unwrap_or_default(),
+                    if l.anonymous {
+                        String::new()
+                    } else {
+                        l.plaintiff.to_string()
+                    },
+                    l.accused.to_string(),
+                    l.judge.to_string(),
+                    l.reason.clone(),
+                    l.verdict.clone().unwrap_or_default(),
+                    l.winner.map(|w| w.to_string()).unwrap_or_default(),
+                    l.damages.map(|d| d.to_string()).unwrap_or_default(),
+                    date(l.created_at),
+                ]
+            })
+            .collect(),
+    };
+
+    let header = columns.iter().map(|&i| kind.columns()[i].to_string());
+    let mut csv = csv_line(header);
+    for row in rows {
+        csv.push_str(&csv_line(columns.iter().map(|&i| row[i].clone())));
+    }
+
+    Ok(csv)
+}
+
+fn csv_line(fields: impl Iterator<Item = String>) -> String {
+    let mut line = fields
+        .map(|field| {
+            // spreadsheets run cells starting with these as formulas, numbers are left alone
+            let field = if field.starts_with(['=', '+', '-', '@']) && field.parse::<f64>().is_err()
+            {
+                format!("'{field}")
+            } else {
+                field
+            };
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formulas_are_escaped() {
+        let fields = [
+            "=HYPERLINK(\"x\")",
+            "+1+1",
+            "@SUM(A1)",
+            "-5",
+            "-",
+            "normal, text",
+        ];
+
+        let line = csv_line(fields.iter().map(ToString::to_string));
+
+        assert_eq!(
+            line,
+            "\"'=HYPERLINK(\"\"x\"\")\",'+1+1,'@SUM(A1),-5,'-,\"normal, text\"\r\n"
+        );
+    }
+}
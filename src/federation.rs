@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use color_eyre::Result;
+use poise::serenity_prelude::{ChannelId, GuildId, Http, UserId};
+use tracing::warn;
+
+use crate::{
+    handler::Response,
+    lawsuit::{Lawsuit, Party},
+    model::{SnowflakeId, State},
+    Mongo,
+};
+
+/// Whether the guild shares its prison records with the partner. Both guilds have to add each
+/// other, a guild alone can't see the records of another one.
+pub fn is_mutual(state: &State, partner: &State) -> bool {
+    state.federation.contains(&partner.guild_id) && partner.federation.contains(&state.guild_id)
+}
+
+/// The states of the guilds that share their prison records with the guild. `mongo` is the
+/// database of all guilds, not the one of the guild.
+#[tracing::instrument(skip(mongo, state))]
+pub async fn partners(mongo: &Mongo, state: &State) -> Result<Vec<State>> {
+    let mut partners = vec![];
+    for &partner_id in &state.federation {
+        let partner = mongo
+            .for_guild(GuildId::from(partner_id))
+            .find_or_insert_state(partner_id)
+            .await?;
+        if is_mutual(state, &partner) {
+            partners.push(partner);
+        }
+    }
+    Ok(partners)
+}
+
+/// Arrests the user in the partner guild as well and tells its log channel where the arrest came
+/// from.
+#[tracing::instrument(skip(mongo, http, partner))]
+pub async fn extradite(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    from: &str,
+    partner: &State,
+    user_id: UserId,
+) -> Result<Result<(), Response>> {
+    let guild_id = GuildId::from(partner.guild_id);
+    let result = crate::prison::arrest(&mongo.for_guild(guild_id), http, guild_id, user_id).await?;
+
+    if let (Ok(()), Some(log_channel)) = (&result, partner.log_channel) {
+        let notice = format!("<@{user_id}> isch uf Aafrag vo {from} au da igsperrt worde");
+        if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+            warn!(?err, "Failed to send extradition notice");
+        }
+    }
+
+    Ok(result)
+}
+
+/// A line about the user's history in a partner guild for `/record`, or `None` if there is
+/// nothing to tell. Sealed lawsuits stay private to the partner.
+pub fn history(
+    guild_name: &str,
+    user_id: SnowflakeId,
+    in_prison: bool,
+    prison_stays: u64,
+    lawsuits: &[Lawsuit],
+) -> Option<String> {
+    let accused = lawsuits
+        .iter()
+        .filter(|l| l.accused == user_id && !l.sealed)
+        .collect::<Vec<_>>();
+    let lost = accused
+        .iter()
+        .filter(|l| l.winner == Some(Party::Plaintiff))
+        .count();
+
+    let mut parts = vec![];
+    if in_prison {
+        parts.push("sitzt im Gfängnis".to_string());
+    }
+    if prison_stays > 0 {
+        parts.push(format!("{prison_stays} mal im Gfängnis gsi"));
+    }
+    if !accused.is_empty() {
+        parts.push(format!(
+            "{} mal aklagt, {lost} mal verurteilt",
+            accused.len()
+        ));
+    }
+
+    match parts.is_empty() {
+        true => None,
+        false => Some(format!("{guild_name}: {}", parts.join(", "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{history, is_mutual};
+    use crate::{
+        lawsuit::{Lawsuit, Party},
+        model::{SnowflakeId, State},
+    };
+
+    #[test]
+    fn mutual() {
+        let mut a = State::new(SnowflakeId(1));
+        let mut b = State::new(SnowflakeId(2));
+        assert!(!is_mutual(&a, &b));
+
+        a.federation.push(SnowflakeId(2));
+        assert!(!is_mutual(&a, &b));
+        assert!(!is_mutual(&b, &a));
+
+        b.federation.push(SnowflakeId(1));
+        assert!(is_mutual(&a, &b));
+        assert!(is_mutual(&b, &a));
+    }
+
+    #[test]
+    fn history_line() {
+        let user = SnowflakeId(3);
+        let lawsuit = |sealed, winner| {
+            let mut lawsuit =
+                Lawsuit::new(SnowflakeId(1), user, SnowflakeId(2), "gmein".to_string());
+            lawsuit.sealed = sealed;
+            lawsuit.winner = winner;
+            lawsuit
+        };
+        let lawsuits = [
+            lawsuit(false, Some(Party::Plaintiff)),
+            lawsuit(false, None),
+            lawsuit(true, Some(Party::Plaintiff)),
+        ];
+
+        assert_eq!(history("Nachbar", user, false, 0, &[]), None);
+        assert_eq!(
+            history("Nachbar", user, true, 2, &lawsuits).as_deref(),
+            Some(
+                "Nachbar: sitzt im Gfängnis, 2 mal im Gfängnis gsi, 2 mal aklagt, 1 mal verurteilt"
+            )
+        );
+        assert_eq!(
+            history("Nachbar", SnowflakeId(9), false, 0, &lawsuits),
+            None
+        );
+    }
+}
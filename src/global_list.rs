@@ -0,0 +1,86 @@
+use std::{env, sync::Arc};
+
+use color_eyre::Result;
+use mongodb::bson::DateTime;
+use poise::serenity_prelude::{ChannelId, Http, Member};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{model::SnowflakeId, Mongo};
+
+/// A member on the global list of offenders. The list is shared by all guilds and only bot owners
+/// can change it. Guilds that use it only get told when someone on it joins, nobody is punished
+/// automatically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalListEntry {
+    pub user_id: SnowflakeId,
+    pub reason: String,
+    pub added_by: SnowflakeId,
+    pub added_at: DateTime,
+}
+
+/// Where flagged members can appeal their entry, from `GLOBAL_LIST_APPEAL_CONTACT`.
+fn appeal_contact() -> Option<String> {
+    env::var("GLOBAL_LIST_APPEAL_CONTACT")
+        .ok()
+        .filter(|contact| !contact.is_empty())
+}
+
+/// The notice for the log channel when someone on the list joins.
+pub fn flag_message(entry: &GlobalListEntry, appeal_contact: Option<&str>) -> String {
+    let appeal = match appeal_contact {
+        Some(contact) => format!("Iisprache gäge de Iitrag: {contact}"),
+        None => "Iisprache gäge de Iitrag: bi de Betreiber vom Bot".to_string(),
+    };
+    format!(
+        "⚠️ <@{}> staht uf de globale Liste: {}\n{appeal}",
+        entry.user_id, entry.reason
+    )
+}
+
+/// Flags the member in the log channel of the guild if they are on the global list. `mongo` is
+/// the database of the guild.
+#[tracing::instrument(skip(mongo, http, member), fields(member = %member.user.id))]
+pub async fn check(mongo: &Mongo, http: &Arc<Http>, member: &Member) -> Result<()> {
+    let state = mongo.find_or_insert_state(member.guild_id.into()).await?;
+    let log_channel = match state.log_channel {
+        Some(log_channel) if state.global_list => log_channel,
+        _ => return Ok(()),
+    };
+
+    let entry = match mongo.find_global_list_entry(member.user.id.into()).await? {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+
+    info!(guild_id = %member.guild_id, "Member on the global list joined");
+    let notice = flag_message(&entry, appeal_contact().as_deref());
+    if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+        warn!(?err, "Failed to send global list notice");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::DateTime;
+
+    use super::{flag_message, GlobalListEntry};
+    use crate::model::SnowflakeId;
+
+    #[test]
+    fn flag() {
+        let entry = GlobalListEntry {
+            user_id: SnowflakeId(1),
+            reason: "Spam uf 5 Server".to_string(),
+            added_by: SnowflakeId(2),
+            added_at: DateTime::now(),
+        };
+        assert_eq!(
+            flag_message(&entry, Some("appeals@example.com")),
+            "⚠️ <@1> staht uf de globale Liste: Spam uf 5 Server\nIisprache gäge de Iitrag: appeals@example.com"
+        );
+        assert!(flag_message(&entry, None).ends_with("bi de Betreiber vom Bot"));
+    }
+}
@@ -0,0 +1,420 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use color_eyre::{eyre::ContextCompat, Result};
+use mongodb::bson::{doc, DateTime, Uuid};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, GuildId, Http, MessageId};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    branding,
+    config::TaskInterval,
+    handler::Response,
+    lawsuit,
+    lawsuit::{Lawsuit, LawsuitCtx, LawsuitStatus},
+    maintenance::Maintenance,
+    model::{SnowflakeId, State},
+    sandbox, Mongo, WrapErr,
+};
+
+/// The prefix of the custom id of the vote buttons, followed by `{lawsuit_id}:{indict}`.
+pub const VOTE_ID_PREFIX: &str = "grand-jury:";
+
+/// How often the grand jury task checks for expired votes.
+const GRAND_JURY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The settings of the grand jury that decides whether criminal lawsuits go to trial.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrandJury {
+    pub role_id: SnowflakeId,
+    /// The channel where the votes are held.
+    pub channel_id: SnowflakeId,
+    /// How many votes for an indictment are needed.
+    pub threshold: u32,
+    pub deadline_secs: i64,
+}
+
+/// The vote of the grand jury on a pending lawsuit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proceeding {
+    pub deadline: DateTime,
+    pub votes: Vec<Vote>,
+    pub indicted: bool,
+    pub channel_id: SnowflakeId,
+    pub message_id: SnowflakeId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vote {
+    pub user_id: SnowflakeId,
+    pub indict: bool,
+}
+
+impl Proceeding {
+    pub fn tally(&self) -> (usize, usize) {
+        let indict = self.votes.iter().filter(|vote| vote.indict).count();
+        (indict, self.votes.len() - indict)
+    }
+}
+
+/// The state of a vote after someone voted.
+#[derive(Debug, Clone, Copy)]
+pub struct Tally {
+    pub indict: usize,
+    pub no_bill: usize,
+    pub threshold: u32,
+    pub indicted: bool,
+}
+
+impl Display for Tally {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            indict, no_bill, ..
+        } = self;
+        if self.indicted {
+            write!(
+                f,
+                "D'Grand Jury hät Aklag erhobe ({indict} für, {no_bill} gege)"
+            )
+        } else {
+            write!(
+                f,
+                "Stimme: {indict} für d'Aklag, {no_bill} dagege, es bruucht {}",
+                self.threshold
+            )
+        }
+    }
+}
+
+/// Parses the `{lawsuit_id}:{indict}` part of a vote custom id.
+pub fn parse_vote_id(id: &str) -> Option<(Uuid, bool)> {
+    let (lawsuit_id, indict) = id.split_once(':')?;
+    Some((Uuid::parse_str(lawsuit_id).ok()?, indict.parse().ok()?))
+}
+
+/// Records the lawsuit as pending and asks the grand jury to vote on it.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn submit(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    mut lawsuit: Lawsuit,
+    config: &GrandJury,
+) -> Result<Response> {
+    let deadline =
+        DateTime::from_millis(DateTime::now().timestamp_millis() + config.deadline_secs * 1000);
+    let deadline_secs = deadline.timestamp_millis() / 1000;
+    let branding = mongo.find_or_insert_state(guild_id.into()).await?.branding;
+
+    let message = ChannelId::from(config.channel_id)
+        .send_message(http, |msg| {
+            msg.content(format!(
+                "<@&{}>, es git en neue Fall zum entscheide",
+                config.role_id
+            ))
+            .embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("Aklag #{}", lawsuit.number))
+                    .field("Grund", &lawsuit.reason, false)
+                    .field("Kläger", lawsuit.plaintiff_mention(), true)
+                    .field("Angeklagter", format!("<@{}>", lawsuit.accused), true)
+                    .field("Abstimmig bis", format!("<t:{deadline_secs}:R>"), true)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(format!("{VOTE_ID_PREFIX}{}:true", lawsuit.id))
+                            .label("Aklag erhebe")
+                            .style(ButtonStyle::Danger)
+                    })
+                    .create_button(|button| {
+                        button
+                            .custom_id(format!("{VOTE_ID_PREFIX}{}:false", lawsuit.id))
+                            .label("Kei Aklag")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await
+        .wrap_err("send grand jury vote")?;
+
+    lawsuit.status = LawsuitStatus::PendingIndictment;
+    lawsuit.grand_jury = Some(Proceeding {
+        deadline,
+        votes: vec![],
+        indicted: false,
+        channel_id: config.channel_id,
+        message_id: message.id.into(),
+    });
+
+    mongo.add_lawsuit(guild_id.into(), &lawsuit).await?;
+
+    info!(?lawsuit, "Submitted lawsuit to grand jury");
+
+    Ok(Response(format!(
+        "de Fall #{} isch a d'Grand Jury gange, si entscheidet bis <t:{deadline_secs}:R>",
+        lawsuit.number
+    )))
+}
+
+/// Records the vote of a grand juror and indicts the accused once enough votes are reached.
+/// Returns the tally after the vote.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn vote(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    lawsuit_id: Uuid,
+    vote: Vote,
+) -> Result<Result<Tally, Response>> {
+    let state = mongo.find_or_insert_state(guild_id.into()).await?;
+
+    let config = match &state.grand_jury {
+        Some(config) => config.clone(),
+        None => return Ok(Err(Response("es git kei Grand Jury meh".to_string()))),
+    };
+    let proceeding = state
+        .lawsuits
+        .iter()
+        .find(|l| l.id == lawsuit_id && l.status == LawsuitStatus::PendingIndictment)
+        .and_then(|l| l.grand_jury.as_ref());
+
+    match proceeding {
+        Some(proceeding)
+            if !proceeding.indicted
+                && proceeding.deadline.timestamp_millis() > DateTime::now().timestamp_millis() => {}
+        _ => return Ok(Err(Response("d'Abstimmig isch vorbi".to_string()))),
+    }
+
+    mongo
+        .set_grand_jury_vote(guild_id.into(), lawsuit_id, &vote)
+        .await?;
+
+    let state = mongo.find_or_insert_state(guild_id.into()).await?;
+    let lawsuit = state
+        .lawsuits
+        .iter()
+        .find(|l| l.id == lawsuit_id)
+        .wrap_err("lawsuit not found after voting")?;
+    let (indict, no_bill) = lawsuit
+        .grand_jury
+        .as_ref()
+        .map(Proceeding::tally)
+        .unwrap_or_default();
+
+    let tally = Tally {
+        indict,
+        no_bill,
+        threshold: config.threshold,
+        indicted: indict >= config.threshold as usize,
+    };
+
+    // votes arriving together can all see the threshold reached, only the first one indicts
+    if tally.indicted && mongo.claim_indictment(guild_id.into(), lawsuit_id).await? {
+        indict_lawsuit(mongo, http, guild_id, &state, lawsuit.clone()).await?;
+    }
+
+    Ok(Ok(tally))
+}
+
+async fn indict_lawsuit(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    state: &State,
+    mut lawsuit: Lawsuit,
+) -> Result<()> {
+    if let Some(proceeding) = &mut lawsuit.grand_jury {
+        proceeding.indicted = true;
+    }
+
+    info!(lawsuit_id = %lawsuit.id, "Grand jury indicted");
+
+    if let Err(response) = open_court(mongo, http, guild_id, state, lawsuit.clone()).await? {
+        if let Some(proceeding) = &lawsuit.grand_jury {
+            ChannelId::from(proceeding.channel_id)
+                .say(
+                    http,
+                    format!("{response}\nde Fall wird ufgmacht, sobald en Gerichtsraum frei isch"),
+                )
+                .await
+                .wrap_err("send no room message")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens a courtroom for an indicted lawsuit. The vote and the grand jury task can both get here
+/// for the same lawsuit, only the one that moves it out of [`LawsuitStatus::PendingIndictment`]
+/// opens it, and moves it back if there is no room.
+async fn open_court(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    state: &State,
+    lawsuit: Lawsuit,
+) -> Result<Result<(), Response>> {
+    let claimed = mongo
+        .claim_lawsuit_status(
+            guild_id.into(),
+            lawsuit.id,
+            LawsuitStatus::PendingIndictment,
+            LawsuitStatus::Opening,
+        )
+        .await?;
+    if !claimed {
+        return Ok(Ok(()));
+    }
+
+    let mut lawsuit_ctx = LawsuitCtx {
+        lawsuit,
+        mongo_client: mongo.clone(),
+        http: http.clone(),
+        guild_id,
+    };
+
+    let opened = lawsuit_ctx.find_room_and_open(state).await;
+    if !matches!(opened, Ok(Ok(_))) {
+        mongo
+            .claim_lawsuit_status(
+                guild_id.into(),
+                lawsuit_ctx.lawsuit.id,
+                LawsuitStatus::Opening,
+                LawsuitStatus::PendingIndictment,
+            )
+            .await?;
+    }
+
+    match opened? {
+        Ok(room) => {
+            lawsuit_ctx.lawsuit.status = LawsuitStatus::Active;
+            let proceeding = lawsuit_ctx.lawsuit.grand_jury.clone();
+            let response = lawsuit_ctx.start(room);
+
+            if let Some(proceeding) = proceeding {
+                ChannelId::from(proceeding.channel_id)
+                    .say(http, response.to_string())
+                    .await
+                    .wrap_err("send indictment message")?;
+            }
+            Ok(Ok(()))
+        }
+        Err(response) => Ok(Err(response)),
+    }
+}
+
+/// Periodically no-bills lawsuits whose vote expired and opens courtrooms for indicted lawsuits
+/// that are still waiting for a free room. Never returns.
+pub async fn grand_jury_task(mongo: Mongo, http: Arc<Http>, maintenance: Maintenance) {
+    let mut interval = TaskInterval::new("grand_jury", GRAND_JURY_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if maintenance.is_active() {
+            continue;
+        }
+
+        if let Err(err) = process_pending_indictments(&mongo, &http).await {
+            error!(?err, "Error processing pending indictments");
+        }
+    }
+}
+
+#[tracing::instrument(skip(mongo, http))]
+pub async fn process_pending_indictments(mongo: &Mongo, http: &Arc<Http>) -> Result<()> {
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        let now = DateTime::now().timestamp_millis();
+
+        for state in mongo.find_states_with_pending_indictments().await? {
+            let guild_id = GuildId::from(state.guild_id);
+            if sandbox::report_channel(guild_id).is_some() {
+                continue;
+            }
+
+            for lawsuit in state
+                .lawsuits
+                .iter()
+                .filter(|l| l.status == LawsuitStatus::PendingIndictment)
+            {
+                let proceeding = match &lawsuit.grand_jury {
+                    Some(proceeding) => proceeding,
+                    None => continue,
+                };
+
+                if proceeding.indicted {
+                    let result = open_court(mongo, http, guild_id, &state, lawsuit.clone()).await;
+                    match result {
+                        // only one lawsuit can take a free room per run
+                        Ok(Ok(())) => break,
+                        Ok(Err(_)) => {}
+                        Err(err) => error!(?err, lawsuit_id = %lawsuit.id, "Error opening court"),
+                    }
+                } else if proceeding.deadline.timestamp_millis() <= now {
+                    no_bill(mongo, http, state.guild_id, lawsuit, proceeding).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Closes the lawsuit without an indictment and refunds the filing fee, since it never went to
+/// trial.
+async fn no_bill(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit: &Lawsuit,
+    proceeding: &Proceeding,
+) -> Result<()> {
+    let claimed = mongo
+        .claim_lawsuit_status(
+            guild_id,
+            lawsuit.id,
+            LawsuitStatus::PendingIndictment,
+            LawsuitStatus::NoBilled,
+        )
+        .await?;
+    if !claimed {
+        return Ok(());
+    }
+
+    lawsuit::refund_filing_fee(mongo, guild_id.into(), lawsuit).await?;
+    mongo
+        .set_lawsuit(
+            guild_id,
+            lawsuit.id,
+            doc! {
+                "lawsuits.$.closed_at": DateTime::now(),
+                "lawsuits.$.filing_fee": None::<i64>,
+            },
+        )
+        .await?;
+
+    let (indict, no_bill) = proceeding.tally();
+    let result = ChannelId::from(proceeding.channel_id)
+        .edit_message(http, MessageId::from(proceeding.message_id), |msg| {
+            msg.content(format!(
+                "D'Grand Jury hät kei Aklag erhobe ({indict} für, {no_bill} gege)"
+            ))
+            .components(|c| c)
+        })
+        .await;
+    if let Err(err) = result {
+        warn!(?err, lawsuit_id = %lawsuit.id, "Failed to update grand jury vote message");
+    }
+
+    info!(lawsuit_id = %lawsuit.id, "Grand jury returned no bill");
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::fmt::{Display, Formatter};
+
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::model::SnowflakeId;
+
+/// Whether a guild is on the allowlist or the denylist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessRule {
+    #[name = "Erlaubt"]
+    Allow,
+    #[name = "Gesperrt"]
+    Deny,
+}
+
+impl Display for AccessRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Allow => "erlaubt",
+            Self::Deny => "gsperrt",
+        })
+    }
+}
+
+/// An entry of the allowlist or denylist of the guilds the bot may operate in. The lists are
+/// shared by all guilds and only bot owners can change them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildAccess {
+    pub guild_id: SnowflakeId,
+    pub rule: AccessRule,
+    pub added_at: DateTime,
+}
+
+/// Whether the bot may operate in the guild. Denied guilds never are. Once there is an allowed
+/// guild, only allowed guilds are.
+pub fn is_permitted(entries: &[GuildAccess], guild_id: SnowflakeId) -> bool {
+    let rule = entries
+        .iter()
+        .find(|entry| entry.guild_id == guild_id)
+        .map(|entry| entry.rule);
+    match rule {
+        Some(AccessRule::Allow) => true,
+        Some(AccessRule::Deny) => false,
+        None => !entries.iter().any(|entry| entry.rule == AccessRule::Allow),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::DateTime;
+
+    use super::{is_permitted, AccessRule, GuildAccess};
+    use crate::model::SnowflakeId;
+
+    fn entry(guild_id: u64, rule: AccessRule) -> GuildAccess {
+        GuildAccess {
+            guild_id: SnowflakeId(guild_id),
+            rule,
+            added_at: DateTime::now(),
+        }
+    }
+
+    #[test]
+    fn denylist() {
+        let entries = [entry(1, AccessRule::Deny)];
+        assert!(!is_permitted(&entries, SnowflakeId(1)));
+        assert!(is_permitted(&entries, SnowflakeId(2)));
+        assert!(is_permitted(&[], SnowflakeId(2)));
+    }
+
+    #[test]
+    fn allowlist() {
+        let entries = [entry(1, AccessRule::Allow), entry(2, AccessRule::Deny)];
+        assert!(is_permitted(&entries, SnowflakeId(1)));
+        assert!(!is_permitted(&entries, SnowflakeId(2)));
+        assert!(!is_permitted(&entries, SnowflakeId(3)));
+    }
+}
@@ -1,13 +1,81 @@
-use std::fmt::{Debug, Display, Formatter};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display, Formatter},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
-use color_eyre::{eyre::ContextCompat, Result};
+use color_eyre::{
+    eyre::{eyre, ContextCompat},
+    Result,
+};
+use futures::StreamExt;
 use mongodb::bson::Uuid;
-use poise::{serenity::model::prelude::*, serenity_prelude as serenity, Event};
-use tracing::{debug, error, info};
+use poise::{serenity::model::prelude::*, serenity_prelude as serenity, Event, Modal};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    lawsuit::{Lawsuit, LawsuitCtx},
-    model::SnowflakeId,
+    activity, api, arbitration, arrest_appeal,
+    arrest_appeal::{ArrestAppeal, ArrestAppealStatus, ArrestRuling},
+    branding,
+    branding::Branding,
+    bulk,
+    bulk::BulkOp,
+    config::Reference,
+    court_session,
+    court_session::{CourtSession, Weekday},
+    docket,
+    docket::Docket,
+    duration::{
+        format_date_time, format_duration, parse_date, parse_date_time, parse_duration,
+        parse_timezone,
+    },
+    economy::{format_amount, format_transaction, TransactionKind},
+    events, export,
+    export::ExportKind,
+    federation, global_list,
+    global_list::GlobalListEntry,
+    grand_jury,
+    grand_jury::GrandJury,
+    history_import, intake,
+    intake::IntakeAnswer,
+    keyword_watch,
+    keyword_watch::KeywordWatch,
+    lawsuit::{
+        conflict_of_interest, pick_judge, CaseType, CommunityService, Deadline, Hearing, Lawsuit,
+        LawsuitCtx, LawsuitStatus, LeaveAction, Party, Phase, PrisonSentence, Ruling, Statement,
+        FAST_TRACK_DEADLINE, STATEMENT_ID_PREFIX,
+    },
+    leaderboard,
+    leaderboard::{Period, PrisonRanking, LEADERBOARD_SIZE},
+    maintenance::Maintenance,
+    messages::{MessageKey, MessageTemplate},
+    model::{ReplyVisibility, SnowflakeId, State},
+    moderation,
+    moderation::ModerationKind,
+    notification, opinion_poll, ordeal,
+    ordeal::{Hand, OrdealKind},
+    petition,
+    petition::{Petition, PetitionStatus},
+    prison::{PrisonRestriction, PrisonVoice},
+    rejoin_checks,
+    retention::{Purge, Retention},
+    sandbox, scheduler,
+    scheduler::JobKind,
+    signature,
+    signature::Verification,
+    subpoena, undo,
+    undo::RecordedFine,
+    verdict_template,
+    verdict_template::VerdictTemplate,
+    visitation,
+    visitation::{Visit, VisitStatus, VisitationConfig},
+    warrant,
+    warrant::Warrant,
+    webhook,
+    webhook::{Webhook, WebhookEvent},
+    work_release,
+    work_release::{WorkTask, WorkTaskStatus},
     Context, Mongo, Report, WrapErr,
 };
 
@@ -15,6 +83,106 @@ pub struct Handler {
     pub dev_guild_id: Option<GuildId>,
     pub set_global_commands: bool,
     pub mongo: Mongo,
+    pub started_at: Instant,
+    /// The reply settings of every guild, loaded before each command because the reply callback
+    /// can't access the database.
+    pub reply_settings: Mutex<HashMap<GuildId, ReplySettings>>,
+    /// Guilds whose commands are traced in detail, set with `/debug verbose`.
+    pub verbose_guilds: Mutex<HashSet<GuildId>>,
+    /// The keyword watch of every guild, so that messages don't need a database lookup.
+    pub keyword_watches: Mutex<HashMap<GuildId, Option<KeywordWatch>>>,
+    /// Whether the operator enabled keyword watches, which need the privileged message content
+    /// intent.
+    pub keyword_watch_enabled: bool,
+    pub maintenance: Maintenance,
+}
+
+/// The database for the guild of the command, the shadow database if it is in sandbox mode.
+pub fn guild_mongo(ctx: Context<'_>) -> Mongo {
+    match ctx.guild_id() {
+        Some(guild_id) => ctx.data().mongo.for_guild(guild_id),
+        None => ctx.data().mongo.clone(),
+    }
+}
+
+/// Whether replies in a guild are only visible to whoever used the command.
+#[derive(Debug, Clone, Default)]
+pub struct ReplySettings {
+    pub ephemeral: Option<bool>,
+    pub commands: Vec<ReplyVisibility>,
+}
+
+/// Commands whose replies are always public, no matter the settings.
+const ALWAYS_PUBLIC: [&str; 2] = ["lawsuit close", "lawsuit bulk close"];
+
+/// Commands whose replies are always private, no matter the settings, because they show a secret.
+const ALWAYS_PRIVATE: [&str; 2] = ["config api_token", "config webhook add"];
+
+/// Loads the reply settings of the guild the command is used in.
+pub async fn load_reply_settings(ctx: Context<'_>) -> Result<()> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    let state = guild_mongo(ctx)
+        .find_or_insert_state(guild_id.into())
+        .await?;
+
+    let settings = ReplySettings {
+        ephemeral: state.ephemeral_replies,
+        commands: state.reply_visibility,
+    };
+    ctx.data()
+        .reply_settings
+        .lock()
+        .expect("reply settings lock poisoned")
+        .insert(guild_id, settings);
+
+    Ok(())
+}
+
+/// Makes the reply ephemeral or public according to the settings of the guild. Commands that are
+/// ephemeral by default because they show sensitive data only become public with a setting for
+/// that specific command.
+pub fn apply_reply_settings(ctx: Context<'_>, reply: &mut poise::CreateReply<'_>) {
+    let command = ctx.command();
+    if ALWAYS_PUBLIC.contains(&command.qualified_name.as_str()) {
+        return;
+    }
+    if ALWAYS_PRIVATE.contains(&command.qualified_name.as_str()) {
+        reply.ephemeral = true;
+        return;
+    }
+    // the command explicitly made this reply private, like when revealing an anonymous plaintiff
+    if reply.ephemeral && !command.ephemeral {
+        return;
+    }
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let reply_settings = ctx
+        .data()
+        .reply_settings
+        .lock()
+        .expect("reply settings lock poisoned");
+    let settings = match reply_settings.get(&guild_id) {
+        Some(settings) => settings,
+        None => return,
+    };
+
+    let for_command = settings
+        .commands
+        .iter()
+        .find(|visibility| visibility.command == command.qualified_name)
+        .map(|visibility| visibility.ephemeral);
+
+    match (for_command, settings.ephemeral) {
+        (Some(ephemeral), _) => reply.ephemeral = ephemeral,
+        (None, Some(ephemeral)) if !command.ephemeral => reply.ephemeral = ephemeral,
+        _ => {}
+    }
 }
 
 impl Debug for Handler {
@@ -23,6 +191,7 @@ impl Debug for Handler {
     }
 }
 
+#[derive(Debug)]
 pub struct Response(pub String);
 
 impl Display for Response {
@@ -31,409 +200,10795 @@ impl Display for Response {
     }
 }
 
+/// Replies with the message of the guild for the key.
+async fn say_message(ctx: Context<'_>, key: MessageKey, args: &[(&str, String)]) -> Result<()> {
+    let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+    let state = guild_mongo(ctx)
+        .find_or_insert_state(guild_id.into())
+        .await?;
+
+    let message = state.message(key, args);
+    match &state.branding {
+        Some(branding) => {
+            ctx.send(|reply| {
+                reply.embed(|embed| branding::apply(embed, Some(branding)).description(message))
+            })
+            .await?;
+        }
+        None => {
+            ctx.say(message).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the member is a judge: admins and members with the judge role.
+fn is_judge_member(state: &crate::model::State, member: &Member) -> bool {
+    let admin = member
+        .permissions
+        .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD));
+    let judge = state
+        .judge_role_for(None)
+        .is_some_and(|role| member.roles.contains(&role.into()));
+    admin || judge
+}
+
+/// Whether the member runs the prison: admins and members with the warden role.
+fn is_warden(state: &crate::model::State, member: &Member) -> bool {
+    let admin = member
+        .permissions
+        .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD));
+    let warden = state
+        .warden_role
+        .is_some_and(|role| member.roles.contains(&role.into()));
+    admin || warden
+}
+
+/// The check of the prison commands that wardens can use.
+pub async fn warden_check(ctx: Context<'_>) -> Result<bool> {
+    let (guild_id, member) = match (ctx.guild_id(), ctx) {
+        (Some(guild_id), Context::Application(app_ctx)) => match app_ctx.interaction.member() {
+            Some(member) => (guild_id, member),
+            None => return Ok(false),
+        },
+        _ => return Ok(false),
+    };
+    let state = guild_mongo(ctx)
+        .find_or_insert_state(guild_id.into())
+        .await?;
+    Ok(is_warden(&state, member))
+}
+
+/// Whether whoever used the command can manage the guild, which makes them an admin of the court.
+fn is_admin(ctx: Context<'_>) -> bool {
+    match ctx {
+        Context::Application(ctx) => ctx
+            .interaction
+            .member()
+            .and_then(|member| member.permissions)
+            .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD)),
+        Context::Prefix(_) => false,
+    }
+}
+
+/// The branding of the guild of the command, if it has one.
+async fn guild_branding(ctx: Context<'_>) -> Result<Option<Branding>> {
+    match ctx.guild_id() {
+        Some(guild_id) => Ok(guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?
+            .branding),
+        None => Ok(None),
+    }
+}
+
+/// The name of the guild if the bot is in it, otherwise its id.
+fn guild_name(ctx: Context<'_>, guild_id: SnowflakeId) -> String {
+    GuildId::from(guild_id)
+        .name(ctx.discord())
+        .unwrap_or_else(|| guild_id.to_string())
+}
+
+/// Shows the places of a leaderboard, or the message if nobody is on it.
+async fn send_leaderboard(
+    ctx: Context<'_>,
+    title: String,
+    places: impl IntoIterator<Item = String>,
+    empty: &str,
+) -> Result<()> {
+    let lines = places
+        .into_iter()
+        .take(LEADERBOARD_SIZE)
+        .enumerate()
+        .map(|(place, line)| format!("{}. {line}", place + 1))
+        .collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        ctx.say(empty).await?;
+        return Ok(());
+    }
+
+    let branding = guild_branding(ctx).await?;
+    ctx.send(|reply| {
+        reply.embed(|embed| {
+            branding::apply(embed, branding.as_ref())
+                .title(title)
+                .description(lines.join("\n"))
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Checks that the roles and channels of the guild that the command needs still exist, see
+/// [`crate::config::validate`]. Returns whether the command can run.
+async fn validate_references(
+    ctx: Context<'_>,
+    state: &crate::model::State,
+    references: &[Reference],
+) -> Result<bool> {
+    let result =
+        crate::config::validate(&guild_mongo(ctx), &ctx.discord().cache, state, references).await?;
+    match result {
+        Ok(()) => Ok(true),
+        Err(response) => {
+            ctx.say(response.to_string()).await?;
+            Ok(false)
+        }
+    }
+}
+
+/// How long someone has to answer a confirmation question.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Verdict documents are a few kilobytes, anything much larger isn't one.
+const VERIFY_SIZE_LIMIT: u64 = 1024 * 1024;
+
+/// How long the parties have to pick their hands in a rock paper scissors ordeal.
+const DUEL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How long someone has to submit a modal after opening it.
+const MODAL_TIMEOUT: Duration = Duration::from_secs(60 * 15);
+
+/// The largest file `/admin import_history` accepts, in bytes.
+const IMPORT_SIZE_LIMIT: u64 = 8 * 1024 * 1024;
+
+/// How many commands and guilds `/admin usage` lists.
+const USAGE_LIST_LIMIT: usize = 15;
+
+#[derive(Debug, poise::Modal)]
+#[name = "Stellungnahm"]
+struct StatementModal {
+    #[name = "Dini Stellungnahm"]
+    #[paragraph]
+    #[max_length = 1000]
+    statement: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Urteil"]
+struct VerdictModal {
+    #[name = "Urteil"]
+    #[paragraph]
+    #[max_length = 1000]
+    verdict: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Entscheid"]
+struct RulingModal {
+    #[name = "Begründig"]
+    #[paragraph]
+    #[max_length = 1000]
+    verdict: String,
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Haft verchürze"]
+struct ReductionModal {
+    #[name = "Restlichi Haft ab jetzt, z.B. 2d 12h"]
+    #[max_length = 50]
+    duration: String,
+}
+
+/// Parses a comma separated list of tags.
+fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn matches_filter(lawsuit: &Lawsuit, case_type: Option<CaseType>, tag: Option<&str>) -> bool {
+    case_type.is_none_or(|case_type| lawsuit.case_type == Some(case_type))
+        && tag.is_none_or(|tag| lawsuit.has_tag(tag))
+}
+
 impl Handler {
+    /// Leaves the guild if the bot may not operate there. Otherwise, creates the state of a guild
+    /// the bot was just added to and tells the inviter how to set up the bot.
+    async fn handle_guild_create(
+        &self,
+        ctx: &serenity::Context,
+        guild: &Guild,
+        is_new: bool,
+    ) -> Result<()> {
+        let access = self.mongo.find_guild_access().await?;
+        if !crate::guild_access::is_permitted(&access, guild.id.into()) {
+            info!(guild_id = %guild.id, name = %guild.name, "Leaving guild that isn't permitted");
+            guild
+                .id
+                .leave(&ctx.http)
+                .await
+                .wrap_err("leave guild that isn't permitted")?;
+            return Ok(());
+        }
+
+        self.mongo.create_guild_indexes(guild.id).await?;
+
+        // the bot also receives this event for every guild on startup
+        if !is_new {
+            return Ok(());
+        }
+
+        let _ = self
+            .mongo
+            .guild(guild.id)
+            .find_or_insert_state(guild.id.into())
+            .await?;
+
+        info!(guild_id = %guild.id, name = %guild.name, members = guild.member_count, "Joined new guild");
+
+        let inviter = match find_inviter(ctx, guild.id).await {
+            Ok(inviter) => inviter,
+            Err(err) => {
+                debug!(?err, "Could not read the audit log to find the inviter");
+                None
+            }
+        };
+
+        if let Some(inviter) = inviter {
+            let result = inviter
+                .create_dm_channel(&ctx.http)
+                .await
+                .wrap_err("create dm channel")?
+                .say(&ctx.http, quick_start_message(&guild.name))
+                .await;
+            match result {
+                Ok(_) => return Ok(()),
+                Err(err) => debug!(?err, %inviter, "Could not send quick start message to inviter"),
+            }
+        }
+
+        if let Some(channel_id) = guild.system_channel_id {
+            channel_id
+                .say(&ctx.http, quick_start_message(&guild.name))
+                .await
+                .wrap_err("send quick start message to system channel")?;
+        } else {
+            info!(guild_id = %guild.id, "Nowhere to send the quick start message");
+        }
+
+        Ok(())
+    }
+
     async fn handle_guild_member_join(
         &self,
         ctx: &serenity::Context,
         member: &Member,
     ) -> Result<()> {
-        let guild_id = member.guild_id;
-        let user_id = member.user.id;
-        let state = self.mongo.find_or_insert_state(guild_id.into()).await?;
-
         debug!(member = ?member.user.id, "New member joined");
 
-        if let Some(role_id) = state.prison_role {
-            if self
-                .mongo
-                .find_prison_entry(guild_id.into(), user_id.into())
-                .await?
-                .is_some()
-            {
-                info!("New member was in prison, giving them the prison role");
+        let mongo = self.mongo.for_guild(member.guild_id);
+        rejoin_checks::run(&mongo, ctx, member).await?;
+        global_list::check(&mongo, &ctx.http, member).await
+    }
 
-                member
-                    .clone()
-                    .add_role(&ctx.http, role_id)
-                    .await
-                    .wrap_err("add role to member in prison")?;
+    /// Reports messages that contain a trigger word of the guild's keyword watch.
+    async fn handle_message(&self, ctx: &serenity::Context, message: &Message) -> Result<()> {
+        let guild_id = match message.guild_id {
+            Some(guild_id) if self.keyword_watch_enabled && !message.author.bot => guild_id,
+            _ => return Ok(()),
+        };
+
+        let cached = self
+            .keyword_watches
+            .lock()
+            .expect("keyword watches lock poisoned")
+            .get(&guild_id)
+            .cloned();
+        let watch = match cached {
+            Some(watch) => watch,
+            None => {
+                let state = self
+                    .mongo
+                    .for_guild(guild_id)
+                    .find_or_insert_state(guild_id.into())
+                    .await?;
+                self.keyword_watches
+                    .lock()
+                    .expect("keyword watches lock poisoned")
+                    .insert(guild_id, state.keyword_watch.clone());
+                state.keyword_watch
+            }
+        };
+
+        if let Some(watch) = watch {
+            if let Some(word) = watch.matching_word(&message.content) {
+                keyword_watch::report(&ctx.http, &watch, message, word).await?;
             }
         }
 
         Ok(())
     }
-}
 
-pub mod lawsuit {
-    use color_eyre::eyre::eyre;
+    async fn handle_guild_ban(
+        &self,
+        ctx: &serenity::Context,
+        guild_id: GuildId,
+        user: &User,
+    ) -> Result<()> {
+        let mongo = self.mongo.for_guild(guild_id);
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
 
-    use super::*;
+        if state.record_moderation {
+            moderation::record(
+                &mongo,
+                &ctx.http,
+                guild_id,
+                user.id,
+                ModerationKind::Ban,
+                None,
+            )
+            .await?;
+        }
 
-    #[poise::command(
-        slash_command,
-        guild_only,
-        subcommands("create", "set_category", "close", "clear")
-    )]
-    pub async fn lawsuit(_: Context<'_>) -> Result<()> {
-        unreachable!()
+        Ok(())
     }
 
-    /// Einen neuen Gerichtsprozess erstellen
-    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-    async fn create(
-        ctx: Context<'_>,
-        #[description = "Der Kläger"] plaintiff: User,
-        #[description = "Der Angeklagte"] accused: User,
-        #[description = "Der Richter"] judge: User,
-        #[description = "Der Grund für die Klage"] reason: String,
-        #[description = "Der Anwalt des Klägers"] plaintiff_lawyer: Option<User>,
-        #[description = "Der Anwalt des Angeklagten"] accused_lawyer: Option<User>,
+    /// Records timeouts. Other member updates are ignored.
+    async fn handle_guild_member_update(
+        &self,
+        ctx: &serenity::Context,
+        member: &Member,
     ) -> Result<()> {
-        lawsuit_create_impl(
-            ctx,
-            plaintiff,
-            accused,
-            judge,
-            reason,
-            plaintiff_lawyer,
-            accused_lawyer,
+        let until = match member.communication_disabled_until {
+            Some(until) if until.unix_timestamp() > Timestamp::now().unix_timestamp() => until,
+            _ => return Ok(()),
+        };
+
+        let mongo = self.mongo.for_guild(member.guild_id);
+        let state = mongo.find_or_insert_state(member.guild_id.into()).await?;
+        if !state.record_moderation {
+            return Ok(());
+        }
+
+        // the member can be updated again while the timeout is still running
+        let already_recorded = mongo
+            .find_moderation_actions(
+                member.guild_id.into(),
+                member.user.id.into(),
+                Some(ModerationKind::Timeout),
+            )
+            .await?
+            .iter()
+            .any(|action| {
+                action.until.map(|until| until.timestamp_millis() / 1000)
+                    == Some(until.unix_timestamp())
+            });
+        if already_recorded {
+            return Ok(());
+        }
+
+        moderation::record(
+            &mongo,
+            &ctx.http,
+            member.guild_id,
+            member.user.id,
+            ModerationKind::Timeout,
+            Some(until),
         )
         .await
-        .wrap_err("lawsuit_create")
     }
 
-    /// Die Rolle für Gefangene setzen
-    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-    async fn set_category(
-        ctx: Context<'_>,
-        #[description = "Die Kategorie"] category: Channel,
+    /// Warns the log channel if the prison role was moved to where the bot can't give it anymore.
+    async fn handle_guild_role_update(
+        &self,
+        ctx: &serenity::Context,
+        old: Option<&Role>,
+        role: &Role,
     ) -> Result<()> {
-        lawsuit_set_category_impl(ctx, category)
+        let state = self
+            .mongo
+            .for_guild(role.guild_id)
+            .find_or_insert_state(role.guild_id.into())
+            .await?;
+        if state.prison_role != Some(role.id.into()) {
+            return Ok(());
+        }
+        let log_channel = match state.log_channel {
+            Some(channel) => ChannelId::from(channel),
+            None => return Ok(()),
+        };
+
+        let bot_position = crate::prison::highest_role_position(
+            &ctx.http,
+            role.guild_id,
+            ctx.cache.current_user_id(),
+        )
+        .await?;
+        let out_of_reach =
+            |role: &Role| crate::prison::is_out_of_reach(role.position, bot_position);
+        // only warn once, not on every change while it's still too high
+        if !out_of_reach(role) || old.is_some_and(out_of_reach) {
+            return Ok(());
+        }
+
+        warn!(guild_id = %role.guild_id, role_id = %role.id, "Prison role moved out of reach");
+
+        log_channel
+            .say(
+                &ctx.http,
+                format!(
+                    "d'Gfängnisrolle <@&{}> isch jetzt über de höchste Rolle vom Bot, so chan ich \
+                     niemer meh iisperre. Bitte verschieb d'Rolle wieder drunter",
+                    role.id
+                ),
+            )
             .await
-            .wrap_err("lawsuit_set_category")
+            .wrap_err("send prison role warning")?;
+
+        Ok(())
+    }
+
+    /// Annotates the open lawsuits and the prison entry of a member who left, so the data
+    /// doesn't silently go stale.
+    async fn handle_guild_member_removal(
+        &self,
+        ctx: &serenity::Context,
+        guild_id: GuildId,
+        user: &User,
+    ) -> Result<()> {
+        let user_id = SnowflakeId::from(user.id);
+        let mongo = self.mongo.for_guild(guild_id);
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+
+        debug!(member = ?user.id, "Member left");
+
+        if state.record_moderation {
+            moderation::record(
+                &mongo,
+                &ctx.http,
+                guild_id,
+                user.id,
+                ModerationKind::Kick,
+                None,
+            )
+            .await?;
+        }
+
+        if mongo
+            .find_prison_entry(guild_id.into(), user_id)
+            .await?
+            .is_some()
+        {
+            info!(%user_id, "Prisoner left the guild");
+            mongo
+                .set_prisoner_left(
+                    guild_id.into(),
+                    user_id,
+                    Some(mongodb::bson::DateTime::now()),
+                )
+                .await?;
+        }
+
+        let involved = state
+            .lawsuits
+            .iter()
+            .filter(|l| !l.is_closed() && l.involved().contains(&user_id));
+        for lawsuit in involved {
+            let mut lawsuit_ctx = LawsuitCtx {
+                lawsuit: lawsuit.clone(),
+                mongo_client: mongo.clone(),
+                http: ctx.http.clone(),
+                guild_id,
+            };
+            let result = lawsuit_ctx
+                .member_left(user_id, ctx.cache.current_user_id().into(), &state)
+                .await;
+            if let Err(err) = result {
+                error!(?err, lawsuit_id = %lawsuit.id, "Failed to handle member leaving lawsuit");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_interaction(
+        &self,
+        ctx: &serenity::Context,
+        interaction: &Interaction,
+    ) -> Result<()> {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return Ok(()),
+        };
+        let custom_id = component.data.custom_id.as_str();
+
+        // buttons that belong to a running command are handled by its collector
+        let own = COMPONENT_PREFIXES
+            .iter()
+            .any(|prefix| custom_id.starts_with(prefix));
+        if let (true, Some(message)) = (own, self.maintenance.message()) {
+            return respond_ephemeral(ctx, component, &message).await;
+        }
+
+        if let Some(lawsuit_id) = custom_id.strip_prefix(STATEMENT_ID_PREFIX) {
+            self.handle_statement_button(ctx, component, lawsuit_id)
+                .await
+        } else if let Some(id) = custom_id.strip_prefix(arbitration::STATEMENT_ID_PREFIX) {
+            self.handle_arbitration_statement_button(ctx, component, id)
+                .await
+        } else if let Some(id) = custom_id.strip_prefix(arbitration::RULING_ID_PREFIX) {
+            self.handle_arbitration_ruling_button(ctx, component, id)
+                .await
+        } else if let Some(id) = custom_id.strip_prefix(grand_jury::VOTE_ID_PREFIX) {
+            self.handle_grand_jury_vote_button(ctx, component, id).await
+        } else if let Some(id) = custom_id.strip_prefix(opinion_poll::VOTE_ID_PREFIX) {
+            self.handle_opinion_vote_button(ctx, component, id).await
+        } else if let Some(id) = custom_id.strip_prefix(keyword_watch::REPORT_ID_PREFIX) {
+            self.handle_keyword_report_button(ctx, component, id).await
+        } else if let Some(id) = custom_id.strip_prefix(subpoena::SUBPOENA_ID_PREFIX) {
+            self.handle_subpoena_button(ctx, component, id).await
+        } else if let Some(id) = custom_id.strip_prefix(visitation::VISIT_ID_PREFIX) {
+            self.handle_visit_button(ctx, component, id).await
+        } else if let Some(id) = custom_id.strip_prefix(petition::PETITION_ID_PREFIX) {
+            self.handle_petition_button(ctx, component, id).await
+        } else if let Some(id) = custom_id.strip_prefix(work_release::TASK_ID_PREFIX) {
+            self.handle_task_button(ctx, component, id).await
+        } else if let Some(id) = custom_id.strip_prefix(arrest_appeal::ARREST_APPEAL_ID_PREFIX) {
+            self.handle_arrest_appeal_button(ctx, component, id).await
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn handle_statement_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        lawsuit_id: &str,
+    ) -> Result<()> {
+        let lawsuit_id = Uuid::parse_str(lawsuit_id).wrap_err("invalid lawsuit id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let lawsuit = state.lawsuits.iter().find(|l| l.id == lawsuit_id);
+
+        if let Some(error) = accused_statement_error(lawsuit, component.user.id) {
+            return respond_ephemeral(ctx, component, error).await;
+        }
+
+        let (modal, submit) = match collect_modal::<StatementModal>(ctx, component).await? {
+            Some(modal) => modal,
+            None => return Ok(()),
+        };
+        let statement = Statement {
+            party: Party::Accused,
+            text: modal.statement,
+        };
+
+        mongo
+            .add_lawsuit_statement(guild_id.into(), lawsuit_id, &statement)
+            .await?;
+        if let Some(lawsuit) = lawsuit {
+            let update = format!("Stellungnahm vom {} igreicht", statement.party);
+            notification::notify_followers(&ctx.http, lawsuit, &update).await;
+        }
+
+        submit
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| {
+                        data.content(format!(
+                            "Stellungnahm vom {} <@{}>:\n{}",
+                            statement.party, component.user.id, statement.text
+                        ))
+                        .components(|c| c)
+                    })
+            })
+            .await
+            .wrap_err("send statement")?;
+
+        info!(%lawsuit_id, "Added statement to lawsuit");
+
+        Ok(())
+    }
+
+    async fn handle_arbitration_statement_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (guild_id, lawsuit_id, _) =
+            arbitration::parse_custom_id(id).wrap_err("invalid arbitration id")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let lawsuit = state.lawsuits.iter().find(|l| l.id == lawsuit_id);
+
+        if let Some(error) = accused_statement_error(lawsuit, component.user.id) {
+            return respond_ephemeral(ctx, component, error).await;
+        }
+        let mut lawsuit = lawsuit.wrap_err("lawsuit not found")?.clone();
+
+        let (modal, submit) = match collect_modal::<StatementModal>(ctx, component).await? {
+            Some(modal) => modal,
+            None => return Ok(()),
+        };
+        let statement = Statement {
+            party: Party::Accused,
+            text: modal.statement,
+        };
+
+        mongo
+            .add_lawsuit_statement(guild_id.into(), lawsuit_id, &statement)
+            .await?;
+        let update = format!("Stellungnahm vom {} igreicht", statement.party);
+        notification::notify_followers(&ctx.http, &lawsuit, &update).await;
+        lawsuit.statements.push(statement);
+
+        arbitration::request_ruling(&ctx.http, guild_id, &lawsuit).await?;
+
+        submit
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| {
+                        data.content("dini Stellungnahm isch an de Schiedsrichter gange")
+                            .components(|c| c)
+                    })
+            })
+            .await
+            .wrap_err("send statement confirmation")?;
+
+        info!(%lawsuit_id, "Added statement to arbitration");
+
+        Ok(())
+    }
+
+    async fn handle_arbitration_ruling_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (guild_id, lawsuit_id, winner) =
+            arbitration::parse_custom_id(id).wrap_err("invalid arbitration id")?;
+        let winner = arbitration::parse_ruling_winner(winner).wrap_err("invalid winner")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let lawsuit = state.lawsuits.iter().find(|l| l.id == lawsuit_id);
+
+        let mut lawsuit = match lawsuit {
+            Some(lawsuit) if lawsuit.is_closed() => {
+                return respond_ephemeral(ctx, component, "de fall isch scho entschiede").await;
+            }
+            Some(lawsuit) if lawsuit.judge != component.user.id.into() => {
+                return respond_ephemeral(ctx, component, "du bisch nöd de Schiedsrichter").await;
+            }
+            Some(lawsuit) => lawsuit.clone(),
+            None => return respond_ephemeral(ctx, component, "de fall gits nöd").await,
+        };
+
+        let (modal, submit) = match collect_modal::<RulingModal>(ctx, component).await? {
+            Some(modal) => modal,
+            None => return Ok(()),
+        };
+
+        arbitration::rule(
+            &mongo,
+            &ctx.http,
+            guild_id,
+            &mut lawsuit,
+            winner,
+            modal.verdict,
+        )
+        .await?;
+
+        submit
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| {
+                        data.content(format!("du häsch für de {winner} entschiede"))
+                            .components(|c| c)
+                    })
+            })
+            .await
+            .wrap_err("send ruling confirmation")?;
+
+        Ok(())
+    }
+
+    async fn handle_grand_jury_vote_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (lawsuit_id, indict) = grand_jury::parse_vote_id(id).wrap_err("invalid vote id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let member = component.member.as_ref().wrap_err("member not found")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let is_juror = state
+            .grand_jury
+            .as_ref()
+            .is_some_and(|config| member.roles.contains(&config.role_id.into()));
+        if !is_juror {
+            return respond_ephemeral(ctx, component, "du bisch nöd i de Grand Jury").await;
+        }
+
+        let vote = grand_jury::Vote {
+            user_id: component.user.id.into(),
+            indict,
+        };
+        let result = grand_jury::vote(&mongo, &ctx.http, guild_id, lawsuit_id, vote).await?;
+
+        match result {
+            Ok(tally) => {
+                component
+                    .create_interaction_response(&ctx.http, |res| {
+                        res.kind(InteractionResponseType::UpdateMessage)
+                            .interaction_response_data(|data| {
+                                data.content(tally.to_string());
+                                if tally.indicted {
+                                    data.components(|c| c);
+                                }
+                                data
+                            })
+                    })
+                    .await
+                    .wrap_err("update grand jury vote")?;
+            }
+            Err(response) => {
+                respond_ephemeral(ctx, component, &response.to_string()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_opinion_vote_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (lawsuit_id, approve) = opinion_poll::parse_vote_id(id).wrap_err("invalid vote id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let vote = opinion_poll::OpinionVote {
+            user_id: component.user.id.into(),
+            approve,
+        };
+        let result = opinion_poll::vote(&mongo, guild_id.into(), lawsuit_id, vote).await?;
+
+        let response = match result {
+            Ok(()) => "dini Stimm isch zellt, s'Resultat gits in 24 Stund".to_string(),
+            Err(response) => response.to_string(),
+        };
+        respond_ephemeral(ctx, component, &response).await
+    }
+
+    /// Records the answer of a witness to their subpoena. The buttons are in DMs, so the guild is
+    /// part of the id.
+    async fn handle_subpoena_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (guild_id, lawsuit_id, subpoena_id, accept) =
+            subpoena::parse_id(id).wrap_err("invalid subpoena id")?;
+
+        let result = subpoena::answer(
+            &self.mongo.for_guild(guild_id),
+            &ctx.http,
+            guild_id,
+            lawsuit_id,
+            subpoena_id,
+            component.user.id,
+            accept,
+        )
+        .await?;
+
+        match result {
+            Ok(()) => {
+                let content = if accept {
+                    "du häsch d'Vorladig agnoh"
+                } else {
+                    "du häsch d'Vorladig abglehnt, de Richter wird informiert"
+                };
+                component
+                    .create_interaction_response(&ctx.http, |res| {
+                        res.kind(InteractionResponseType::UpdateMessage)
+                            .interaction_response_data(|data| {
+                                data.content(content).components(|c| c)
+                            })
+                    })
+                    .await
+                    .wrap_err("update subpoena")?;
+            }
+            Err(response) => {
+                respond_ephemeral(ctx, component, &response.to_string()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Files a lawsuit for a reported message, with whoever pressed the button as the plaintiff,
+    /// or discards the report.
+    async fn handle_keyword_report_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (file, accused) = keyword_watch::parse_report_id(id).wrap_err("invalid report id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let member = component.member.as_ref().wrap_err("member not found")?;
+
+        let is_moderator = member
+            .permissions
+            .is_some_and(|p| p.contains(Permissions::MANAGE_MESSAGES));
+        if !is_moderator {
+            return respond_ephemeral(ctx, component, "du häsch kei recht für da!").await;
+        }
+
+        let content = if file {
+            let reason = component
+                .message
+                .embeds
+                .first()
+                .and_then(|embed| embed.description.clone())
+                .wrap_err("report without description")?;
+            match self
+                .file_keyword_lawsuit(ctx, guild_id, component.user.id, accused, reason)
+                .await?
+            {
+                Ok(response) => format!("{response} (vo <@{}>)", component.user.id),
+                Err(response) => return respond_ephemeral(ctx, component, &response).await,
+            }
+        } else {
+            format!("Verworfe vo <@{}>", component.user.id)
+        };
+
+        component
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| data.content(content).components(|c| c))
+            })
+            .await
+            .wrap_err("update keyword report")?;
+
+        Ok(())
+    }
+
+    async fn handle_visit_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (visit_id, approve) = visitation::parse_decision_id(id).wrap_err("invalid visit id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let member = component.member.as_ref().wrap_err("member not found")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !is_warden(&state, member) {
+            return respond_ephemeral(ctx, component, "du häsch kei recht für da!").await;
+        }
+
+        let decided_by = component.user.id.into();
+        let visit = visitation::decide(&mongo, &ctx.http, visit_id, decided_by, approve).await?;
+        if visit.is_none() {
+            return respond_ephemeral(ctx, component, "über de Bsuech isch scho entschiede").await;
+        }
+
+        let decision = match approve {
+            true => "Erlaubt",
+            false => "Abglehnt",
+        };
+        let content = format!(
+            "{}\n{decision} vo <@{}>",
+            component.message.content, component.user.id
+        );
+        component
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| data.content(content).components(|c| c))
+            })
+            .await
+            .wrap_err("update visit request")?;
+
+        Ok(())
+    }
+
+    async fn handle_petition_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (petition_id, grant) =
+            petition::parse_decision_id(id).wrap_err("invalid petition id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let member = component.member.as_ref().wrap_err("member not found")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !is_warden(&state, member) {
+            return respond_ephemeral(ctx, component, "du häsch kei recht für da!").await;
+        }
+
+        let decided_by = component.user.id.into();
+        let petition = petition::decide(&mongo, &ctx.http, petition_id, decided_by, grant).await?;
+        if petition.is_none() {
+            return respond_ephemeral(ctx, component, "über s'Gsuech isch scho entschiede").await;
+        }
+
+        let decision = match grant {
+            true => "Guetgheisse",
+            false => "Abglehnt",
+        };
+        let content = format!("{decision} vo <@{}>", component.user.id);
+        component
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| data.content(content).components(|c| c))
+            })
+            .await
+            .wrap_err("update petition")?;
+
+        Ok(())
+    }
+
+    async fn handle_task_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (task_id, confirm) = work_release::parse_decision_id(id).wrap_err("invalid task id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let member = component.member.as_ref().wrap_err("member not found")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !is_warden(&state, member) {
+            return respond_ephemeral(ctx, component, "du häsch kei recht für da!").await;
+        }
+
+        let decided_by = component.user.id.into();
+        let task = work_release::decide(
+            &mongo,
+            &ctx.http,
+            guild_id.into(),
+            task_id,
+            decided_by,
+            confirm,
+        )
+        .await?;
+        if task.is_none() {
+            return respond_ephemeral(ctx, component, "über d'Arbeit isch scho entschiede").await;
+        }
+
+        let decision = match confirm {
+            true => "Bestätigt",
+            false => "Abglehnt",
+        };
+        let content = format!("{decision} vo <@{}>", component.user.id);
+        component
+            .create_interaction_response(&ctx.http, |res| {
+                res.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| data.content(content).components(|c| c))
+            })
+            .await
+            .wrap_err("update finished task")?;
+
+        Ok(())
+    }
+
+    async fn handle_arrest_appeal_button(
+        &self,
+        ctx: &serenity::Context,
+        component: &serenity::MessageComponentInteraction,
+        id: &str,
+    ) -> Result<()> {
+        let (appeal_id, ruling) =
+            arrest_appeal::parse_decision_id(id).wrap_err("invalid arrest appeal id")?;
+        let guild_id = component.guild_id.wrap_err("guild_id not found")?;
+        let member = component.member.as_ref().wrap_err("member not found")?;
+        let mongo = self.mongo.for_guild(guild_id);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !is_judge_member(&state, member) {
+            return respond_ephemeral(
+                ctx,
+                component,
+                "nur Richter chönd über Haftbeschwärde entscheide",
+            )
+            .await;
+        }
+
+        // the reduced sentence is entered in a modal, which then gets the response
+        let (release_at, submit) = if ruling == ArrestRuling::Reduce {
+            let (modal, submit) = match collect_modal::<ReductionModal>(ctx, component).await? {
+                Some(modal) => modal,
+                None => return Ok(()),
+            };
+            match parse_duration(&modal.duration) {
+                Some(duration) => {
+                    let release_at = mongodb::bson::DateTime::from_millis(
+                        mongodb::bson::DateTime::now().timestamp_millis()
+                            + duration.as_millis() as i64,
+                    );
+                    (Some(release_at), Some(submit))
+                }
+                None => {
+                    submit
+                        .create_interaction_response(&ctx.http, |res| {
+                            res.kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|data| {
+                                    data.content(
+                                        "das isch kei gültigi dauer, probier öppis wie `2d 12h`",
+                                    )
+                                    .ephemeral(true)
+                                })
+                        })
+                        .await
+                        .wrap_err("send invalid duration response")?;
+                    return Ok(());
+                }
+            }
+        } else {
+            (None, None)
+        };
+
+        let decided_by = component.user.id.into();
+        let result =
+            arrest_appeal::decide(&mongo, &ctx.http, appeal_id, decided_by, ruling, release_at)
+                .await?;
+        let content = match result {
+            Ok(appeal) => {
+                let decision = match appeal.status {
+                    ArrestAppealStatus::Reduced => "Verchürzt",
+                    ArrestAppealStatus::Overturned => "Ufghobe",
+                    ArrestAppealStatus::Upheld | ArrestAppealStatus::Pending => "Bstätigt",
+                };
+                format!("{decision} vo <@{}>", component.user.id)
+            }
+            Err(response) => {
+                return match submit {
+                    Some(submit) => submit
+                        .create_interaction_response(&ctx.http, |res| {
+                            res.kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|data| {
+                                    data.content(response.to_string()).ephemeral(true)
+                                })
+                        })
+                        .await
+                        .wrap_err("send arrest appeal response"),
+                    None => respond_ephemeral(ctx, component, &response.to_string()).await,
+                };
+            }
+        };
+
+        match submit {
+            Some(submit) => submit
+                .create_interaction_response(&ctx.http, |res| {
+                    res.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|data| data.content(content).components(|c| c))
+                })
+                .await
+                .wrap_err("update arrest appeal")?,
+            None => component
+                .create_interaction_response(&ctx.http, |res| {
+                    res.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|data| data.content(content).components(|c| c))
+                })
+                .await
+                .wrap_err("update arrest appeal")?,
+        }
+
+        Ok(())
+    }
+
+    async fn file_keyword_lawsuit(
+        &self,
+        ctx: &serenity::Context,
+        guild_id: GuildId,
+        plaintiff: UserId,
+        accused: UserId,
+        reason: String,
+    ) -> Result<Result<Response, String>> {
+        let mongo = self.mongo.for_guild(guild_id);
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+
+        let judge_role = match state.judge_role_for(Some(CaseType::Criminal)) {
+            Some(role) => role,
+            None => return Ok(Err("es isch kei Richterrolle gsetzt".to_string())),
+        };
+
+        let mut lawsuit = Lawsuit {
+            case_type: Some(CaseType::Criminal),
+            ..Lawsuit::new(plaintiff.into(), accused.into(), SnowflakeId(0), reason)
+        };
+        let judge = pick_judge(&ctx.http, guild_id, judge_role, &state.lawsuits, &lawsuit).await?;
+        match judge {
+            Some(judge) => lawsuit.judge = judge.into(),
+            None => return Ok(Err("es git kein verfüegbare richter".to_string())),
+        }
+
+        let lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo,
+            http: ctx.http.clone(),
+            guild_id,
+        };
+
+        let response = lawsuit_ctx
+            .initialize()
+            .await
+            .wrap_err("initialize lawsuit")?;
+
+        Ok(Ok(response))
+    }
+}
+
+/// Asks the author of the command to confirm something with buttons.
+/// Returns `false` if they cancel or don't answer in time.
+async fn confirm(ctx: Context<'_>, question: String) -> Result<bool> {
+    let message = ctx
+        .send(|reply| {
+            reply.content(question).components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id("confirm")
+                            .label("Bestätige")
+                            .style(serenity::ButtonStyle::Danger)
+                    })
+                    .create_button(|button| {
+                        button
+                            .custom_id("cancel")
+                            .label("Abbreche")
+                            .style(serenity::ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await?
+        .message()
+        .await?;
+
+    let interaction = message
+        .await_component_interaction(&ctx.discord().shard)
+        .author_id(ctx.author().id)
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+
+    let confirmed = match interaction {
+        Some(interaction) => {
+            let confirmed = interaction.data.custom_id == "confirm";
+            interaction
+                .create_interaction_response(ctx.discord(), |res| {
+                    res.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|data| data.components(|c| c))
+                })
+                .await
+                .wrap_err("acknowledge confirmation")?;
+            confirmed
+        }
+        None => false,
+    };
+
+    Ok(confirmed)
+}
+
+/// Checks whether the user may submit the statement of the accused for a lawsuit.
+fn accused_statement_error(lawsuit: Option<&Lawsuit>, user_id: UserId) -> Option<&'static str> {
+    match lawsuit {
+        Some(lawsuit) if lawsuit.is_closed() => Some("de prozess isch scho abgschlosse"),
+        Some(lawsuit) if lawsuit.is_locked(Phase::Statements, mongodb::bson::DateTime::now()) => {
+            Some("d'Frist für d'Stellungnahm isch abgloffe")
+        }
+        Some(lawsuit) if lawsuit.accused != user_id.into() => {
+            Some("nur de Angeklagti chan e Stellungnahm abgeh")
+        }
+        Some(lawsuit) if lawsuit.statements.iter().any(|s| s.party == Party::Accused) => {
+            Some("du häsch scho e Stellungnahm abgeh")
+        }
+        Some(_) => None,
+        None => Some("de prozess gits nöd"),
+    }
+}
+
+async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+    content: &str,
+) -> Result<()> {
+    component
+        .create_interaction_response(&ctx.http, |res| {
+            res.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| data.content(content).ephemeral(true))
+        })
+        .await
+        .wrap_err("send ephemeral response")
+}
+
+/// The custom id of a modal shown in response to the interaction, so that its submission isn't
+/// mixed up with another modal of the same user.
+fn modal_id(interaction_id: serenity::InteractionId) -> String {
+    format!("modal:{interaction_id}")
+}
+
+/// Replaces the custom id of a derived modal, which is always the same.
+fn scope_modal(response: &mut serenity::CreateInteractionResponse, custom_id: &str) {
+    if let Some(serde_json::Value::Object(data)) = response.0.get_mut("data") {
+        data.insert("custom_id".to_string(), custom_id.into());
+    }
+}
+
+/// Shows a modal in response to a component and waits for it to be submitted.
+/// The submit interaction still needs to be responded to.
+async fn collect_modal<M: Modal>(
+    ctx: &serenity::Context,
+    component: &serenity::MessageComponentInteraction,
+) -> Result<Option<(M, std::sync::Arc<serenity::ModalSubmitInteraction>)>> {
+    let custom_id = modal_id(component.id);
+    component
+        .create_interaction_response(&ctx.http, |res| {
+            *res = M::create(None);
+            scope_modal(res, &custom_id);
+            res
+        })
+        .await
+        .wrap_err("send modal")?;
+
+    let submit = serenity::CollectModalInteraction::new(&ctx.shard)
+        .author_id(component.user.id)
+        .filter(move |submit| submit.data.custom_id == custom_id)
+        .timeout(MODAL_TIMEOUT)
+        .await;
+
+    match submit {
+        Some(submit) => {
+            let modal = M::parse(submit.data.clone()).map_err(|err| eyre!(err))?;
+            Ok(Some((modal, submit)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Shows a modal as the first response to the command and waits for it to be submitted, like
+/// [`Modal::execute`] but only for the modal of this interaction. Returns `None` if it wasn't
+/// submitted in time.
+async fn execute_modal<M: Modal>(ctx: Context<'_>, defaults: Option<M>) -> Result<Option<M>> {
+    let application_context = match ctx {
+        Context::Application(ctx) => ctx,
+        Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+    };
+    let interaction = application_context.interaction.unwrap();
+
+    let custom_id = modal_id(interaction.id);
+    interaction
+        .create_interaction_response(&ctx.discord().http, |res| {
+            *res = M::create(defaults);
+            scope_modal(res, &custom_id);
+            res
+        })
+        .await
+        .wrap_err("send modal")?;
+    application_context
+        .has_sent_initial_response
+        .store(true, std::sync::atomic::Ordering::SeqCst);
+
+    let submit = serenity::CollectModalInteraction::new(&ctx.discord().shard)
+        .author_id(interaction.user.id)
+        .filter(move |submit| submit.data.custom_id == custom_id)
+        .timeout(MODAL_TIMEOUT)
+        .await;
+    let submit = match submit {
+        Some(submit) => submit,
+        None => return Ok(None),
+    };
+
+    // closes the modal for the user
+    submit
+        .create_interaction_response(&ctx.discord().http, |res| {
+            res.kind(serenity::InteractionResponseType::DeferredUpdateMessage)
+        })
+        .await
+        .wrap_err("acknowledge modal")?;
+
+    let modal = M::parse(submit.data.clone()).map_err(|err| eyre!(err))?;
+    Ok(Some(modal))
+}
+
+/// Asks the questions of the intake form in a modal, which has to be the first response to the
+/// command. Returns `None` if the modal wasn't submitted in time.
+async fn collect_intake(
+    ctx: Context<'_>,
+    questions: &[String],
+) -> Result<Option<Vec<IntakeAnswer>>> {
+    let application_context = match ctx {
+        Context::Application(ctx) => ctx,
+        Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+    };
+    let interaction = application_context.interaction.unwrap();
+
+    let custom_id = modal_id(interaction.id);
+    interaction
+        .create_interaction_response(&ctx.discord().http, |res| {
+            *res = intake::modal(&custom_id, questions);
+            res
+        })
+        .await
+        .wrap_err("send intake modal")?;
+    application_context
+        .has_sent_initial_response
+        .store(true, std::sync::atomic::Ordering::SeqCst);
+
+    let submit = serenity::CollectModalInteraction::new(&ctx.discord().shard)
+        .author_id(interaction.user.id)
+        .filter(move |submit| submit.data.custom_id == custom_id)
+        .timeout(MODAL_TIMEOUT)
+        .await;
+    let submit = match submit {
+        Some(submit) => submit,
+        None => return Ok(None),
+    };
+
+    // closes the modal for the user
+    submit
+        .create_interaction_response(&ctx.discord().http, |res| {
+            res.kind(serenity::InteractionResponseType::DeferredUpdateMessage)
+        })
+        .await
+        .wrap_err("acknowledge intake modal")?;
+
+    Ok(Some(intake::parse(questions, submit.data.clone())))
+}
+
+async fn autocomplete_verdict_template(ctx: Context<'_>, partial: String) -> Vec<String> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return vec![],
+    };
+    match guild_mongo(ctx).find_or_insert_state(guild_id.into()).await {
+        Ok(state) => verdict_template::matching(&state.verdict_templates, &partial),
+        Err(err) => {
+            error!(?err, "Failed to load verdict templates");
+            vec![]
+        }
+    }
+}
+
+pub mod lawsuit {
+    use mongodb::{bson, bson::doc};
+
+    use super::*;
+
+    /// The maximum amount of lawsuits shown in a listing.
+    const LIST_LIMIT: usize = 20;
+
+    /// How many reminders someone can have at the same time in a guild.
+    const REMINDER_LIMIT: usize = 25;
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Gericht",
+        subcommands(
+            "create",
+            "smallclaim",
+            "settings",
+            "close",
+            "withdraw",
+            "dismiss",
+            "recuse",
+            "reassign_judge",
+            "lawyer",
+            "amend",
+            "move_case",
+            "reopen",
+            "merge",
+            "tag",
+            "list",
+            "info",
+            "notify",
+            "seal",
+            "hearing",
+            "deadline",
+            "detention",
+            "bulk",
+            "clear",
+            "ordeal",
+            "verdict"
+        )
+    )]
+    pub async fn lawsuit(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Einen neuen Gerichtsprozess erstellen
+    #[allow(clippy::too_many_arguments)]
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn create(
+        ctx: Context<'_>,
+        #[description = "Der Kläger"] plaintiff: User,
+        #[description = "Der Angeklagte"] accused: User,
+        #[description = "Der Richter"] judge: User,
+        #[description = "Der Grund für die Klage"] reason: String,
+        #[description = "Der Anwalt des Klägers"] plaintiff_lawyer: Option<User>,
+        #[description = "Der Anwalt des Angeklagten"] accused_lawyer: Option<User>,
+        #[description = "Die Art des Prozesses"] case_type: Option<CaseType>,
+        #[description = "Tags, mit Komma getrennt"] tags: Option<String>,
+        #[description = "Wann der Vorfall passiert ist, zum Beispiel 30.06.2022"]
+        incident_date: Option<String>,
+        #[description = "Die Verjährung ignorieren, nur für den Richter"]
+        override_limitation: Option<bool>,
+        #[description = "Den Kläger öffentlich nicht anzeigen"] anonymous: Option<bool>,
+        #[description = "Bei einer Berufung die Nummer des angefochtenen Prozesses"]
+        appeal_of: Option<u64>,
+    ) -> Result<()> {
+        lawsuit_create_impl(
+            ctx,
+            plaintiff,
+            accused,
+            judge,
+            reason,
+            plaintiff_lawyer,
+            accused_lawyer,
+            case_type,
+            tags,
+            incident_date,
+            override_limitation.unwrap_or(false),
+            anonymous.unwrap_or(false),
+            appeal_of,
+        )
+        .await
+        .wrap_err("lawsuit_create")
+    }
+
+    /// Eine Bagatellklage im Schnellverfahren einreichen
+    #[poise::command(slash_command, guild_only)]
+    async fn smallclaim(
+        ctx: Context<'_>,
+        #[description = "Der Angeklagte"] accused: User,
+        #[description = "Der Grund für die Klage"] reason: String,
+        #[description = "Der geforderte Betrag"]
+        #[min = 1]
+        amount: i64,
+    ) -> Result<()> {
+        lawsuit_smallclaim_impl(ctx, accused, reason, amount)
+            .await
+            .wrap_err("lawsuit_smallclaim")
+    }
+
+    /// Die eigene Klage in diesem Kanal zurückziehen
+    #[poise::command(slash_command, guild_only)]
+    async fn withdraw(ctx: Context<'_>) -> Result<()> {
+        lawsuit_terminate_impl(ctx, LawsuitStatus::Withdrawn, None)
+            .await
+            .wrap_err("lawsuit_withdraw")
+    }
+
+    /// Den Prozess in diesem Kanal ohne Urteil abweisen
+    #[poise::command(slash_command, guild_only)]
+    async fn dismiss(
+        ctx: Context<'_>,
+        #[description = "Warum die Klage abgewiesen wird"] reason: String,
+    ) -> Result<()> {
+        lawsuit_terminate_impl(ctx, LawsuitStatus::Dismissed, Some(reason))
+            .await
+            .wrap_err("lawsuit_dismiss")
+    }
+
+    /// Als Richter in diesem Prozess in den Ausstand treten
+    #[poise::command(slash_command, guild_only)]
+    async fn recuse(ctx: Context<'_>) -> Result<()> {
+        lawsuit_recuse_impl(ctx).await.wrap_err("lawsuit_recuse")
+    }
+
+    /// Den Prozess in diesem Kanal einem anderen Richter übergeben
+    #[poise::command(slash_command, guild_only)]
+    async fn reassign_judge(
+        ctx: Context<'_>,
+        #[description = "Der neue Richter"] judge: User,
+    ) -> Result<()> {
+        lawsuit_reassign_judge_impl(ctx, judge)
+            .await
+            .wrap_err("lawsuit_reassign_judge")
+    }
+
+    #[poise::command(slash_command, guild_only, subcommands("lawyer_set"))]
+    async fn lawyer(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Den Anwalt einer Partei in diesem Prozess ändern
+    #[poise::command(slash_command, guild_only, rename = "set")]
+    async fn lawyer_set(
+        ctx: Context<'_>,
+        #[description = "Die Partei"] side: Party,
+        #[description = "Der neue Anwalt, leer lassen um den Anwalt zu entfernen"] user: Option<
+            User,
+        >,
+    ) -> Result<()> {
+        lawsuit_lawyer_set_impl(ctx, side, user)
+            .await
+            .wrap_err("lawsuit_lawyer_set")
+    }
+
+    #[poise::command(slash_command, guild_only, subcommands("deadline_set"))]
+    async fn deadline(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands(
+            "settings_category",
+            "settings_filing_fee",
+            "settings_debt_block",
+            "settings_appeal_window"
+        )
+    )]
+    async fn settings(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands(
+            "notify_follow",
+            "notify_unfollow",
+            "notify_remind",
+            "notify_reminders"
+        )
+    )]
+    async fn notify(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(slash_command, guild_only, subcommands("seal_set", "seal_lift"))]
+    async fn seal(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("hearing_schedule", "hearing_subpoena")
+    )]
+    async fn hearing(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("detention_start", "detention_review", "detention_end")
+    )]
+    async fn detention(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(slash_command, guild_only, subcommands("bulk_close", "bulk_dismiss"))]
+    async fn bulk(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("verdict_export", "verdict_verify")
+    )]
+    async fn verdict(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Eine Frist für einen Abschnitt eines Prozesses setzen
+    #[poise::command(slash_command, guild_only, rename = "set")]
+    async fn deadline_set(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Der Abschnitt, der nach der Frist gesperrt wird"] phase: Phase,
+        #[description = "Das Ende der Frist, z.B. 30.06.2022 18:00"] time: String,
+    ) -> Result<()> {
+        lawsuit_deadline_set_impl(ctx, case, phase, time)
+            .await
+            .wrap_err("lawsuit_deadline_set")
+    }
+
+    /// Einen Zeugen zu einer Verhandlung im Prozess in diesem Kanal vorladen
+    #[poise::command(slash_command, guild_only, rename = "subpoena")]
+    async fn hearing_subpoena(
+        ctx: Context<'_>,
+        #[description = "Der Zeuge"] user: User,
+        #[description = "Die Verhandlung, z.B. 30.06.2022 18:00"] date: String,
+    ) -> Result<()> {
+        lawsuit_subpoena_impl(ctx, user, date)
+            .await
+            .wrap_err("lawsuit_subpoena")
+    }
+
+    /// Sich per DM an etwas zu einem Prozess erinnern lassen
+    #[poise::command(slash_command, guild_only, rename = "remind")]
+    async fn notify_remind(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Wann, z.B. 2h oder 30.06.2022 18:00"] when: String,
+        #[description = "Woran erinnert werden soll"] note: String,
+    ) -> Result<()> {
+        lawsuit_remind_impl(ctx, case, when, note)
+            .await
+            .wrap_err("lawsuit_remind")
+    }
+
+    /// Die eigenen Erinnerungen zu Prozessen anzeigen oder eine löschen
+    #[poise::command(slash_command, guild_only, rename = "reminders")]
+    async fn notify_reminders(
+        ctx: Context<'_>,
+        #[description = "Die Nummer der Erinnerung, die gelöscht werden soll"]
+        #[min = 1]
+        cancel: Option<usize>,
+    ) -> Result<()> {
+        lawsuit_reminders_impl(ctx, cancel)
+            .await
+            .wrap_err("lawsuit_reminders")
+    }
+
+    /// Das Urteil eines Prozesses als PDF zum Ausdrucken herunterladen
+    #[poise::command(slash_command, guild_only, rename = "export")]
+    async fn verdict_export(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_export_impl(ctx, case)
+            .await
+            .wrap_err("lawsuit_export")
+    }
+
+    /// Prüfen, ob ein Urteil als PDF vom Bot stammt und nicht verändert wurde
+    #[poise::command(slash_command, guild_only, rename = "verify")]
+    async fn verdict_verify(
+        ctx: Context<'_>,
+        #[description = "Das PDF von /lawsuit verdict export"] file: serenity::Attachment,
+    ) -> Result<()> {
+        lawsuit_verify_impl(ctx, file)
+            .await
+            .wrap_err("lawsuit_verify")
+    }
+
+    /// Eine festgefahrene Bagatellklage durch ein Gottesurteil entscheiden
+    #[poise::command(slash_command, guild_only)]
+    async fn ordeal(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Wie entschieden wird"] kind: OrdealKind,
+    ) -> Result<()> {
+        lawsuit_ordeal_impl(ctx, case, kind)
+            .await
+            .wrap_err("lawsuit_ordeal")
+    }
+
+    /// Den Angeklagten bis zum Urteil in Untersuchungshaft nehmen
+    #[poise::command(slash_command, guild_only, rename = "start")]
+    async fn detention_start(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_detention_impl(ctx, case, DetentionAction::Detain)
+            .await
+            .wrap_err("lawsuit_detain")
+    }
+
+    /// Eine Haftprüfung nach einigen Tagen Untersuchungshaft planen
+    #[poise::command(slash_command, guild_only, rename = "review")]
+    async fn detention_review(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Nach wie vielen Tagen die Haft geprüft wird"]
+        #[min = 1]
+        days: u32,
+    ) -> Result<()> {
+        lawsuit_detention_impl(ctx, case, DetentionAction::Review(days))
+            .await
+            .wrap_err("lawsuit_detention_review")
+    }
+
+    /// Den Angeklagten aus der Untersuchungshaft entlassen
+    #[poise::command(slash_command, guild_only, rename = "end")]
+    async fn detention_end(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_detention_impl(ctx, case, DetentionAction::End)
+            .await
+            .wrap_err("lawsuit_detention_end")
+    }
+
+    /// Den Grund einer Klage ändern
+    #[poise::command(slash_command, guild_only)]
+    async fn amend(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Der neue Grund für die Klage"] new_reason: String,
+    ) -> Result<()> {
+        lawsuit_amend_impl(ctx, case, new_reason)
+            .await
+            .wrap_err("lawsuit_amend")
+    }
+
+    /// Einen laufenden Prozess in einen anderen Gerichtsraum verschieben
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "move",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn move_case(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Der neue Gerichtsraum"] channel: Channel,
+    ) -> Result<()> {
+        lawsuit_move_impl(ctx, case, channel)
+            .await
+            .wrap_err("lawsuit_move")
+    }
+
+    /// Einen abgeschlossenen Prozess wieder eröffnen
+    #[poise::command(slash_command, guild_only)]
+    async fn reopen(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Warum der Prozess wieder eröffnet wird"] reason: String,
+    ) -> Result<()> {
+        lawsuit_reopen_impl(ctx, case, reason)
+            .await
+            .wrap_err("lawsuit_reopen")
+    }
+
+    /// Eine doppelte Klage in einen anderen laufenden Prozess überführen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn merge(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses, der weitergeführt wird"] primary: u64,
+        #[description = "Die Nummer der doppelten Klage"] duplicate: u64,
+    ) -> Result<()> {
+        lawsuit_merge_impl(ctx, primary, duplicate)
+            .await
+            .wrap_err("lawsuit_merge")
+    }
+
+    /// Die Kategorie für Gerichtsräume setzen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        rename = "category"
+    )]
+    async fn settings_category(
+        ctx: Context<'_>,
+        #[description = "Die Kategorie"] category: Channel,
+        #[description = "Nur für diese Art von Prozessen"] case_type: Option<CaseType>,
+    ) -> Result<()> {
+        lawsuit_set_category_impl(ctx, category, case_type)
+            .await
+            .wrap_err("lawsuit_set_category")
+    }
+
+    /// Die Gerichtsgebühr für neue Prozesse setzen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        rename = "filing_fee"
+    )]
+    async fn settings_filing_fee(
+        ctx: Context<'_>,
+        #[description = "Die Gebühr, 0 für keine Gebühr"]
+        #[min = 0]
+        amount: i64,
+    ) -> Result<()> {
+        lawsuit_set_filing_fee_impl(ctx, amount)
+            .await
+            .wrap_err("lawsuit_set_filing_fee")
+    }
+
+    /// Festlegen, ob Personen mit Schulden Klagen einreichen dürfen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        rename = "debt_block"
+    )]
+    async fn settings_debt_block(
+        ctx: Context<'_>,
+        #[description = "Ob Schulden das Einreichen von Klagen verhindern"] enabled: bool,
+    ) -> Result<()> {
+        lawsuit_set_debt_block_impl(ctx, enabled)
+            .await
+            .wrap_err("lawsuit_set_debt_block")
+    }
+
+    /// Festlegen, wie lange ein Urteil angefochten werden kann, bevor es vollstreckt wird
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        rename = "appeal_window"
+    )]
+    async fn settings_appeal_window(
+        ctx: Context<'_>,
+        #[description = "Die Frist, z.B. `2d`, leer um Urteile sofort zu vollstrecken"]
+        duration: Option<String>,
+    ) -> Result<()> {
+        lawsuit_set_appeal_window_impl(ctx, duration)
+            .await
+            .wrap_err("lawsuit_set_appeal_window")
+    }
+
+    /// Den Gerichtsprozess abschliessen und ein Urteil fällen
+    #[allow(clippy::too_many_arguments)]
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn close(
+        ctx: Context<'_>,
+        #[description = "Das Urteil, sonst wird es in einem Formular abgefragt"] verdict: Option<
+            String,
+        >,
+        #[description = "Eine Urteilsvorlage, die im Formular noch angepasst werden kann"]
+        #[autocomplete = "autocomplete_verdict_template"]
+        template: Option<String>,
+        #[description = "Wer den Prozess gewonnen hat"] winner: Option<Party>,
+        #[description = "Schadenersatz, den der Verlierer dem Gewinner zahlen muss"]
+        #[min = 1]
+        damages: Option<i64>,
+        #[description = "Stunden gemeinnützige Arbeit für den Angeklagten"]
+        #[min = 1]
+        community_service: Option<u32>,
+        #[description = "Frist für die gemeinnützige Arbeit, standardmässig 7d"]
+        service_deadline: Option<String>,
+        #[description = "Gefängnisstrafe für den Angeklagten, z.B. 3d"] prison: Option<String>,
+        #[description = "Bewährungsfrist, während der die Gefängnisstrafe ausgesetzt ist, z.B. 30d"]
+        probation: Option<String>,
+    ) -> Result<()> {
+        lawsuit_close_impl(
+            ctx,
+            verdict,
+            template,
+            winner,
+            damages,
+            community_service,
+            service_deadline,
+            prison,
+            probation,
+        )
+        .await
+        .wrap_err("lawsuit_close")
+    }
+
+    /// Die Art und Tags des Gerichtsprozesses in diesem Kanal ändern
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn tag(
+        ctx: Context<'_>,
+        #[description = "Die neue Art des Prozesses"] case_type: Option<CaseType>,
+        #[description = "Tags zum Hinzufügen, mit Komma getrennt"] add: Option<String>,
+        #[description = "Tags zum Entfernen, mit Komma getrennt"] remove: Option<String>,
+    ) -> Result<()> {
+        lawsuit_tag_impl(ctx, case_type, add, remove)
+            .await
+            .wrap_err("lawsuit_tag")
+    }
+
+    /// Gerichtsprozesse auflisten
+    #[poise::command(slash_command, guild_only)]
+    async fn list(
+        ctx: Context<'_>,
+        #[description = "Nur Prozesse dieser Art"] case_type: Option<CaseType>,
+        #[description = "Nur Prozesse mit diesem Tag"] tag: Option<String>,
+        #[description = "Nur offene Prozesse"] open_only: Option<bool>,
+    ) -> Result<()> {
+        lawsuit_list_impl(ctx, case_type, tag, open_only.unwrap_or(false))
+            .await
+            .wrap_err("lawsuit_list")
+    }
+
+    /// Details zu einem Gerichtsprozess anzeigen
+    #[poise::command(slash_command, guild_only)]
+    async fn info(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_info_impl(ctx, case).await.wrap_err("lawsuit_info")
+    }
+
+    /// Bei Änderungen an einem Prozess eine Direktnachricht bekommen
+    #[poise::command(slash_command, guild_only, rename = "follow")]
+    async fn notify_follow(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_follow_impl(ctx, case, true)
+            .await
+            .wrap_err("lawsuit_follow")
+    }
+
+    /// Keine Direktnachrichten mehr zu einem Prozess bekommen
+    #[poise::command(slash_command, guild_only, rename = "unfollow")]
+    async fn notify_unfollow(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_follow_impl(ctx, case, false)
+            .await
+            .wrap_err("lawsuit_unfollow")
+    }
+
+    /// Einen Prozess versiegeln, damit ihn nur der Richter und die Admins sehen
+    #[poise::command(slash_command, guild_only, rename = "set")]
+    async fn seal_set(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_seal_impl(ctx, case, true)
+            .await
+            .wrap_err("lawsuit_seal")
+    }
+
+    /// Die Versiegelung eines Prozesses aufheben
+    #[poise::command(slash_command, guild_only, rename = "lift")]
+    async fn seal_lift(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+    ) -> Result<()> {
+        lawsuit_seal_impl(ctx, case, false)
+            .await
+            .wrap_err("lawsuit_unseal")
+    }
+
+    /// Eine Verhandlung für einen laufenden Prozess ansetzen
+    #[poise::command(slash_command, guild_only, rename = "schedule")]
+    async fn hearing_schedule(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Prozesses"] case: u64,
+        #[description = "Wann die Verhandlung ist, zum Beispiel 30.06.2022 18:00"] time: String,
+        #[description = "Worum es in der Verhandlung geht"] description: Option<String>,
+    ) -> Result<()> {
+        lawsuit_schedule_hearing_impl(ctx, case, time, description)
+            .await
+            .wrap_err("lawsuit_schedule_hearing")
+    }
+
+    /// Viele laufende Prozesse auf einmal mit einem Urteil abschliessen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        rename = "close"
+    )]
+    async fn bulk_close(
+        ctx: Context<'_>,
+        #[description = "Das Urteil für alle Prozesse"] verdict: Option<String>,
+        #[description = "Nur Prozesse, die älter sind, zum Beispiel 60d"] older_than: Option<
+            String,
+        >,
+        #[description = "Nur Prozesse dieses Richters"] judge: Option<User>,
+        #[description = "Nur Prozesse dieser Art"] case_type: Option<CaseType>,
+        #[description = "Nur Prozesse mit diesem Tag"] tag: Option<String>,
+    ) -> Result<()> {
+        let action = BulkAction::Close(verdict.unwrap_or_else(|| "Eingestellt".to_string()));
+        lawsuit_bulk_impl(ctx, action, older_than, judge, case_type, tag)
+            .await
+            .wrap_err("lawsuit_bulk_close")
+    }
+
+    /// Viele laufende Prozesse auf einmal abweisen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        rename = "dismiss"
+    )]
+    async fn bulk_dismiss(
+        ctx: Context<'_>,
+        #[description = "Warum die Klagen abgewiesen werden"] reason: String,
+        #[description = "Nur Prozesse, die älter sind, zum Beispiel 60d"] older_than: Option<
+            String,
+        >,
+        #[description = "Nur Prozesse dieses Richters"] judge: Option<User>,
+        #[description = "Nur Prozesse dieser Art"] case_type: Option<CaseType>,
+        #[description = "Nur Prozesse mit diesem Tag"] tag: Option<String>,
+    ) -> Result<()> {
+        let action = BulkAction::Dismiss(reason);
+        lawsuit_bulk_impl(ctx, action, older_than, judge, case_type, tag)
+            .await
+            .wrap_err("lawsuit_bulk_dismiss")
+    }
+
+    /// Alle Rechtsprozessdaten löschen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn clear(ctx: Context<'_>) -> Result<()> {
+        lawsuit_clear_impl(ctx).await.wrap_err("lawsuit_clear")
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_create_impl(
+        ctx: Context<'_>,
+        plaintiff: User,
+        accused: User,
+        judge: User,
+        reason: String,
+        plaintiff_lawyer: Option<User>,
+        accused_lawyer: Option<User>,
+        case_type: Option<CaseType>,
+        tags: Option<String>,
+        incident_date: Option<String>,
+        override_limitation: bool,
+        anonymous: bool,
+        appeal_of: Option<u64>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        // the modal has to be the first response
+        let intake = if state.intake_form.is_empty() {
+            vec![]
+        } else {
+            match collect_intake(ctx, &state.intake_form).await? {
+                Some(intake) => intake,
+                None => return Ok(()),
+            }
+        };
+        // a public reply would show who filed the lawsuit
+        if anonymous && state.intake_form.is_empty() {
+            ctx.defer_ephemeral().await?;
+        }
+
+        let references = [
+            Reference::CourtCategory,
+            Reference::JudgeRole,
+            Reference::LogChannel,
+        ];
+        if !validate_references(ctx, &state, &references).await? {
+            return Ok(());
+        }
+
+        if let Some(number) = appeal_of {
+            if !state.lawsuits.iter().any(|l| l.number == number) {
+                ctx.say(format!("de Prozess #{number} gits nöd")).await?;
+                return Ok(());
+            }
+        }
+        let case_type = if appeal_of.is_some() {
+            Some(CaseType::Appeal)
+        } else {
+            case_type
+        };
+
+        let incident_date = incident_date
+            .as_deref()
+            .map(|date| parse_date(date, state.timezone()));
+        let incident_date = match incident_date {
+            Some(Some(date)) => Some(date),
+            Some(None) => {
+                ctx.say("das isch kei gültigs datum, probier öppis wie `30.06.2022`")
+                    .await?;
+                return Ok(());
+            }
+            None => None,
+        };
+
+        if let Some(incident_date) = incident_date {
+            if let Some(limitation) = state.limitation_for(case_type) {
+                let age =
+                    bson::DateTime::now().timestamp_millis() - incident_date.timestamp_millis();
+                let expired = age > limitation.as_millis() as i64;
+
+                if expired && !(override_limitation && ctx.author().id == judge.id) {
+                    ctx.say(format!(
+                        "de Vorfall isch verjährt, d'Verjährigsfrist isch {}. nur de Richter chan das übersteue",
+                        format_duration(limitation)
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+            }
+        }
+
+        let lawsuit = Lawsuit {
+            plaintiff_lawyer: plaintiff_lawyer.map(|user| user.id.into()),
+            accused_lawyer: accused_lawyer.map(|user| user.id.into()),
+            case_type,
+            tags: tags.as_deref().map(parse_tags).unwrap_or_default(),
+            incident_date,
+            anonymous,
+            appeal_of,
+            intake,
+            ..Lawsuit::new(
+                plaintiff.id.into(),
+                accused.id.into(),
+                judge.id.into(),
+                reason,
+            )
+        };
+
+        if let Some(conflict) = conflict_of_interest(&state.lawsuits, &lawsuit, lawsuit.judge) {
+            let question = format!("Interessekonflikt: {conflict}. Trotzdem als Richter zuewiese?");
+            if !confirm(ctx, question).await? {
+                ctx.say("ich ha de Prozess nöd erstellt").await?;
+                return Ok(());
+            }
+        }
+
+        let lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: guild_mongo(ctx),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+
+        let response = lawsuit_ctx
+            .initialize()
+            .await
+            .wrap_err("initialize lawsuit")?;
+
+        ctx.send(|reply| reply.content(response.to_string()).ephemeral(anonymous))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_smallclaim_impl(
+        ctx: Context<'_>,
+        accused: User,
+        reason: String,
+        amount: i64,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let plaintiff = ctx.author().id;
+
+        // the modal has to be the first response
+        let statement = match execute_modal::<StatementModal>(ctx, None).await? {
+            Some(statement) => statement,
+            None => return Ok(()),
+        };
+
+        if accused.id == plaintiff {
+            ctx.say("du chasch di nöd selber verklage").await?;
+            return Ok(());
+        }
+
+        let mongo_client = &guild_mongo(ctx);
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let judge_role = match state.judge_role_for(Some(CaseType::SmallClaims)) {
+            Some(role) => role,
+            None => {
+                say_message(ctx, MessageKey::NoJudgeRole, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        let decision_deadline = bson::DateTime::from_millis(
+            bson::DateTime::now().timestamp_millis() + FAST_TRACK_DEADLINE.as_millis() as i64,
+        );
+
+        let mut lawsuit = Lawsuit {
+            fast_track: true,
+            case_type: Some(CaseType::SmallClaims),
+            claim_amount: Some(amount),
+            decision_deadline: Some(decision_deadline),
+            statements: vec![Statement {
+                party: Party::Plaintiff,
+                text: statement.statement,
+            }],
+            ..Lawsuit::new(plaintiff.into(), accused.id.into(), SnowflakeId(0), reason)
+        };
+
+        let judge = pick_judge(
+            &ctx.discord().http,
+            guild_id,
+            judge_role,
+            &state.lawsuits,
+            &lawsuit,
+        )
+        .await?;
+        match judge {
+            Some(judge) => lawsuit.judge = judge.into(),
+            None => {
+                ctx.say("es git kein verfüegbare richter").await?;
+                return Ok(());
+            }
+        }
+
+        let lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+
+        let response = lawsuit_ctx
+            .initialize()
+            .await
+            .wrap_err("initialize lawsuit")?;
+
+        ctx.say(response.to_string()).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_set_category_impl(
+        ctx: Context<'_>,
+        category: Channel,
+        case_type: Option<CaseType>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        match category.category() {
+            Some(category) => {
+                let id = category.id;
+                let mongo = &guild_mongo(ctx);
+                match case_type {
+                    Some(case_type) => {
+                        mongo
+                            .update_case_type_config(guild_id.into(), case_type, |config| {
+                                config.court_category = Some(id.into())
+                            })
+                            .await?
+                    }
+                    None => mongo.set_court_category(guild_id.into(), id.into()).await?,
+                }
+                say_message(ctx, MessageKey::Saved, &[]).await?;
+            }
+            None => {
+                ctx.say("Das ist keine Kategorie!").await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_set_filing_fee_impl(ctx: Context<'_>, amount: i64) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let fee = if amount > 0 { Some(amount) } else { None };
+        guild_mongo(ctx)
+            .set_filing_fee(guild_id.into(), fee)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_set_debt_block_impl(ctx: Context<'_>, enabled: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_debt_blocks_filings(guild_id.into(), enabled)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_set_appeal_window_impl(
+        ctx: Context<'_>,
+        duration: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let window = match duration.as_deref().map(parse_duration) {
+            Some(Some(window)) => Some(window.as_secs() as i64),
+            Some(None) => {
+                say_message(
+                    ctx,
+                    MessageKey::InvalidDeadline,
+                    &[("example", "`2d`".to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+            None => None,
+        };
+        guild_mongo(ctx)
+            .set_appeal_window(guild_id.into(), window)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_close_impl(
+        ctx: Context<'_>,
+        verdict: Option<String>,
+        template: Option<String>,
+        winner: Option<Party>,
+        damages: Option<i64>,
+        community_service: Option<u32>,
+        service_deadline: Option<String>,
+        prison: Option<String>,
+        probation: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let parse = |duration: Option<String>| match duration.as_deref().map(parse_duration) {
+            Some(None) => Err(()),
+            Some(duration) => Ok(duration),
+            None => Ok(None),
+        };
+        let prison = match (parse(prison), parse(probation)) {
+            (Ok(Some(duration)), Ok(probation)) => Some(PrisonSentence {
+                duration,
+                probation,
+            }),
+            (Ok(None), Ok(Some(_))) => {
+                ctx.say("e Bewährig git's nur für e Gfängnisstraf").await?;
+                return Ok(());
+            }
+            (Ok(None), Ok(None)) => None,
+            _ => {
+                ctx.say("das isch kei gültigi dauer, probier öppis wie `3d`")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let community_service = match community_service {
+            Some(hours) => {
+                let deadline = service_deadline.as_deref().unwrap_or("7d");
+                match parse_duration(deadline) {
+                    Some(deadline) => Some(CommunityService { hours, deadline }),
+                    None => {
+                        say_message(
+                            ctx,
+                            MessageKey::InvalidDeadline,
+                            &[("example", "`7d`".to_string())],
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+
+        let member = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?;
+
+        let permission_override = member
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let room_id = ctx.channel_id();
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client
+            .find_or_insert_state(guild_id.into())
+            .await
+            .wrap_err("find guild for verdict")?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.court_room == room_id.into() && !l.is_closed());
+
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == room_id.into());
+        let room = match room {
+            Some(room) => room.clone(),
+            None => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        let template = match template {
+            Some(name) => match state.verdict_templates.iter().find(|t| t.name == name) {
+                Some(template) => Some(template),
+                None => {
+                    ctx.say(format!("es git kei Urteilsvorlag «{name}»"))
+                        .await?;
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+        // the modal has to be the first response
+        let verdict = match verdict {
+            Some(verdict) => verdict,
+            None => {
+                let defaults = template.map(|template| VerdictModal {
+                    verdict: template.fill(&lawsuit),
+                });
+                match execute_modal(ctx, defaults).await? {
+                    Some(VerdictModal { verdict }) => verdict,
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+
+        let response = lawsuit_ctx
+            .rule_verdict(
+                permission_override,
+                member.user.id,
+                Ruling {
+                    verdict,
+                    winner,
+                    damages,
+                    community_service,
+                    prison,
+                },
+                room,
+            )
+            .await?;
+
+        if let Err(response) = response {
+            ctx.say(response.to_string()).await?;
+            return Ok(());
+        }
+
+        ctx.say("ich han en dir abschlosse").await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_terminate_impl(
+        ctx: Context<'_>,
+        status: LawsuitStatus,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let room_id = ctx.channel_id();
+        let mongo_client = &guild_mongo(ctx);
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+        let permission_override = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.court_room == room_id.into() && !l.is_closed());
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == room_id.into());
+
+        let (lawsuit, room) = match (lawsuit, room) {
+            (Some(lawsuit), Some(room)) => (lawsuit.clone(), room.clone()),
+            _ => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        let author = ctx.author().id.into();
+        let allowed = match status {
+            LawsuitStatus::Withdrawn => lawsuit.plaintiff == author,
+            _ => lawsuit.judge == author,
+        };
+        if !allowed && !permission_override {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        lawsuit_ctx.terminate(status, author, reason, &room).await?;
+
+        ctx.say("ich han en dir abschlosse").await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_recuse_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let room_id = ctx.channel_id();
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.court_room == room_id.into() && !l.is_closed());
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == room_id.into());
+
+        let (lawsuit, room) = match (lawsuit, room) {
+            (Some(lawsuit), Some(room)) => (lawsuit.clone(), room.clone()),
+            _ => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() {
+            ctx.say("nur de Richter chan in Usstand träte").await?;
+            return Ok(());
+        }
+
+        let judge_role = match state.judge_role_for(lawsuit.case_type) {
+            Some(role) => role,
+            None => {
+                ctx.say("es git kei richterrolle, en admin muess en neue Richter bestimme")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let new_judge = pick_judge(
+            &ctx.discord().http,
+            guild_id,
+            judge_role,
+            &state.lawsuits,
+            &lawsuit,
+        )
+        .await?;
+        let new_judge = match new_judge {
+            Some(judge) => judge,
+            None => {
+                ctx.say("es git kein andere verfüegbare richter").await?;
+                return Ok(());
+            }
+        };
+
+        let old_judge = lawsuit.judge;
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        lawsuit_ctx.change_judge(new_judge.into(), &room).await?;
+        lawsuit_ctx
+            .add_history(
+                old_judge,
+                format!("<@{old_judge}> isch in Usstand trete, neue Richter <@{new_judge}>"),
+            )
+            .await?;
+
+        ctx.say(format!(
+            "<@{old_judge}> isch in Usstand trete, de neui Richter isch <@{new_judge}>"
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_reassign_judge_impl(ctx: Context<'_>, judge: User) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let room_id = ctx.channel_id();
+        let mongo_client = &guild_mongo(ctx);
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+        let permission_override = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.court_room == room_id.into() && !l.is_closed());
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == room_id.into());
+
+        let (lawsuit, room) = match (lawsuit, room) {
+            (Some(lawsuit), Some(room)) => (lawsuit.clone(), room.clone()),
+            _ => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() && !permission_override {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+        if lawsuit.judge == judge.id.into() {
+            ctx.say("das isch scho de Richter").await?;
+            return Ok(());
+        }
+
+        if let Some(conflict) = conflict_of_interest(&state.lawsuits, &lawsuit, judge.id.into()) {
+            let question = format!("Interessekonflikt: {conflict}. Trotzdem als Richter zuewiese?");
+            if !confirm(ctx, question).await? {
+                ctx.say("de Richter isch de gliich blibe").await?;
+                return Ok(());
+            }
+        }
+
+        let old_judge = lawsuit.judge;
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        lawsuit_ctx.change_judge(judge.id.into(), &room).await?;
+        lawsuit_ctx
+            .add_history(
+                ctx.author().id.into(),
+                format!("Prozess vo <@{old_judge}> a <@{}> übergeh", judge.id),
+            )
+            .await?;
+
+        let lawsuit = &lawsuit_ctx.lawsuit;
+        let handover = format!(
+            "De Prozess #{} isch vo <@{old_judge}> a <@{}> übergeh worde",
+            lawsuit.number, judge.id
+        );
+
+        // the old judge can't see the court room anymore
+        let dm = UserId::from(old_judge)
+            .create_dm_channel(ctx.discord())
+            .await
+            .wrap_err("create dm channel")?
+            .say(ctx.discord(), &handover)
+            .await;
+        if let Err(err) = dm {
+            info!(?err, "Could not notify old judge about handover");
+        }
+
+        ctx.say(format!(
+            "{handover}. {} <@{}>",
+            lawsuit.plaintiff_mention(),
+            lawsuit.accused
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_lawyer_set_impl(
+        ctx: Context<'_>,
+        side: Party,
+        user: Option<User>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let room_id = ctx.channel_id();
+        let mongo_client = &guild_mongo(ctx);
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+        let permission_override = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.court_room == room_id.into() && !l.is_closed());
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == room_id.into());
+
+        let (lawsuit, room) = match (lawsuit, room) {
+            (Some(lawsuit), Some(room)) => (lawsuit.clone(), room.clone()),
+            _ => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        let author = ctx.author().id.into();
+        if lawsuit.party(side) != author && lawsuit.judge != author && !permission_override {
+            ctx.say("nur d'Partei sälber oder de Richter chönd de Anwalt ändere")
+                .await?;
+            return Ok(());
+        }
+        if lawsuit.judge != author
+            && !permission_override
+            && lawsuit.is_locked(Phase::Lawyers, bson::DateTime::now())
+        {
+            ctx.say("d'Frist für Anwaltswechsel isch abgloffe").await?;
+            return Ok(());
+        }
+
+        let new_lawyer = user.map(|user| SnowflakeId::from(user.id));
+        if new_lawyer.is_some_and(|lawyer| {
+            lawyer == lawsuit.judge || lawyer == lawsuit.party(side.opponent())
+        }) {
+            ctx.say("de Richter oder d'Gegepartei chönd nöd Anwalt si")
+                .await?;
+            return Ok(());
+        }
+
+        let old_lawyer = lawsuit.lawyer(side);
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        lawsuit_ctx.change_lawyer(side, new_lawyer, &room).await?;
+
+        let mention = |lawyer: Option<SnowflakeId>| {
+            lawyer
+                .map(|lawyer| format!("<@{lawyer}>"))
+                .unwrap_or_else(|| "kein Anwalt".to_string())
+        };
+        let description = format!(
+            "Anwalt vom {side} gwechslet vo {} zu {}",
+            mention(old_lawyer),
+            mention(new_lawyer)
+        );
+        lawsuit_ctx.add_history(author, description.clone()).await?;
+
+        ctx.say(description).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_amend_impl(ctx: Context<'_>, case: u64, new_reason: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = match state.lawsuits.iter().find(|l| l.number == case) {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::LawsuitNotFound,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let author = ctx.author().id.into();
+        if lawsuit.plaintiff != author && lawsuit.judge != author {
+            ctx.say("nur de Kläger oder de Richter chönd d'Klag ändere")
+                .await?;
+            return Ok(());
+        }
+        if lawsuit.is_closed() {
+            ctx.say("de Prozess isch scho abgschlosse").await?;
+            return Ok(());
+        }
+        if lawsuit.judge != author && lawsuit.is_locked(Phase::Amendments, bson::DateTime::now()) {
+            ctx.say("d'Frist für Klageänderige isch abgloffe").await?;
+            return Ok(());
+        }
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        lawsuit_ctx.amend(author, new_reason).await?;
+
+        ctx.say(format!("de Grund vom Prozess #{case} isch gänderet"))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_move_impl(ctx: Context<'_>, case: u64, channel: Channel) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let channel = match channel.guild() {
+            Some(channel) if channel.kind == ChannelType::Text => channel,
+            _ => {
+                ctx.say("das isch kein textkanal").await?;
+                return Ok(());
+            }
+        };
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && !l.is_closed() && l.court_room != SnowflakeId(0));
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::NoOngoingLawsuit,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.court_room == channel.id.into() {
+            ctx.say("de Prozess isch scho i dem kanal").await?;
+            return Ok(());
+        }
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == channel.id.into());
+        let room = match room {
+            Some(room) if room.ongoing_lawsuit => {
+                ctx.say("i dem gerichtsraum lauft scho en prozess").await?;
+                return Ok(());
+            }
+            Some(room) => room.clone(),
+            None => lawsuit_ctx.register_room(&channel).await?,
+        };
+
+        let report = match lawsuit_ctx.move_to(ctx.author().id.into(), room).await? {
+            Ok(report) => report,
+            Err(Response(response)) => {
+                ctx.say(response).await?;
+                return Ok(());
+            }
+        };
+
+        let mut response = format!("de Prozess #{case} isch jetzt i <#{}>", channel.id);
+        if !report.is_success() {
+            response.push_str(&format!("\n\nRolle: {report}"));
+        }
+        ctx.say(response).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_merge_impl(ctx: Context<'_>, primary: u64, duplicate: u64) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        if primary == duplicate {
+            ctx.say("en Prozess cha nöd mit sich selber zämegführt werde")
+                .await?;
+            return Ok(());
+        }
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let find_ongoing = |case: u64| {
+            let lawsuit = state.lawsuits.iter().find(|l| {
+                l.number == case
+                    && !l.is_closed()
+                    && l.status == LawsuitStatus::Active
+                    && !l.arbitration
+            })?;
+            let room = state
+                .court_rooms
+                .iter()
+                .find(|r| r.channel_id == lawsuit.court_room)?;
+            Some((lawsuit.clone(), room.clone()))
+        };
+
+        let ((primary_lawsuit, room), (duplicate_lawsuit, duplicate_room)) =
+            match (find_ongoing(primary), find_ongoing(duplicate)) {
+                (Some(primary), Some(duplicate)) => (primary, duplicate),
+                (None, _) => {
+                    say_message(
+                        ctx,
+                        MessageKey::NoOngoingLawsuit,
+                        &[("case", primary.to_string())],
+                    )
+                    .await?;
+                    return Ok(());
+                }
+                (_, None) => {
+                    say_message(
+                        ctx,
+                        MessageKey::NoOngoingLawsuit,
+                        &[("case", duplicate.to_string())],
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            };
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit: primary_lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        let duplicate_ctx = LawsuitCtx {
+            lawsuit: duplicate_lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+
+        lawsuit_ctx
+            .merge(
+                ctx.author().id.into(),
+                duplicate_ctx,
+                &room,
+                &duplicate_room,
+            )
+            .await?;
+
+        ctx.say(format!(
+            "de Prozess #{duplicate} isch jetzt en Teil vom Prozess #{primary}"
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_reopen_impl(ctx: Context<'_>, case: u64, reason: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+        let permission_override = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = state.lawsuits.iter().find(|l| {
+            l.number == case
+                && !l.arbitration
+                && (l.verdict.is_some()
+                    || matches!(
+                        l.status,
+                        LawsuitStatus::Withdrawn | LawsuitStatus::Dismissed
+                    ))
+        });
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                ctx.say(format!("es git kein abgschlossene Prozess #{case}"))
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() && !permission_override {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        let lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        let response = lawsuit_ctx.reopen(ctx.author().id.into(), reason).await?;
+
+        ctx.say(response.to_string()).await?;
+
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    enum BulkAction {
+        /// Close with this verdict.
+        Close(String),
+        /// Dismiss with this reason.
+        Dismiss(String),
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_bulk_impl(
+        ctx: Context<'_>,
+        action: BulkAction,
+        older_than: Option<String>,
+        judge: Option<User>,
+        case_type: Option<CaseType>,
+        tag: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let cutoff = match older_than.as_deref().map(parse_duration) {
+            Some(Some(age)) => Some(bson::DateTime::from_millis(
+                bson::DateTime::now().timestamp_millis() - age.as_millis() as i64,
+            )),
+            Some(None) => {
+                ctx.say("das isch kei gültigi duur, probier öppis wie `60d`")
+                    .await?;
+                return Ok(());
+            }
+            None => None,
+        };
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuits = state
+            .lawsuits
+            .iter()
+            .filter(|l| !l.is_closed() && l.status == LawsuitStatus::Active && !l.arbitration)
+            .filter(|l| matches_filter(l, case_type, tag.as_deref()))
+            .filter(|l| cutoff.is_none_or(|cutoff| l.created_before(cutoff)))
+            .filter(|l| {
+                judge
+                    .as_ref()
+                    .is_none_or(|judge| l.judge == judge.id.into())
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if lawsuits.is_empty() {
+            ctx.say("es git kei passendi prozess").await?;
+            return Ok(());
+        }
+
+        let lines = lawsuits
+            .iter()
+            .take(LIST_LIMIT)
+            .map(|l| l.summary())
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                embed.title("Vorschau").description(lines);
+                if lawsuits.len() > LIST_LIMIT {
+                    embed.footer(|footer| {
+                        footer.text(format!("und {} meh", lawsuits.len() - LIST_LIMIT))
+                    });
+                }
+                embed
+            })
+        })
+        .await?;
+
+        let verb = match action {
+            BulkAction::Close(_) => "abschliesse",
+            BulkAction::Dismiss(_) => "abwiese",
+        };
+        let question = format!("Söll ich die {} Prozess {verb}?", lawsuits.len());
+        if !confirm(ctx, question).await? {
+            ctx.say("ich han nüt gmacht").await?;
+            return Ok(());
+        }
+
+        let author = ctx.author().id;
+        let mut report = Vec::new();
+
+        for lawsuit in lawsuits {
+            let number = lawsuit.number;
+            let room = state
+                .court_rooms
+                .iter()
+                .find(|r| r.channel_id == lawsuit.court_room)
+                .cloned();
+            let room = match room {
+                Some(room) => room,
+                None => {
+                    report.push(format!("#{number}: kein Gerichtsraum"));
+                    continue;
+                }
+            };
+
+            let mut lawsuit_ctx = LawsuitCtx {
+                lawsuit,
+                mongo_client: mongo_client.clone(),
+                http: ctx.discord().http.clone(),
+                guild_id,
+            };
+
+            let result = match &action {
+                BulkAction::Close(verdict) => {
+                    let ruling = Ruling {
+                        verdict: verdict.clone(),
+                        winner: None,
+                        damages: None,
+                        community_service: None,
+                        prison: None,
+                    };
+                    lawsuit_ctx.rule_verdict(true, author, ruling, room).await
+                }
+                BulkAction::Dismiss(reason) => lawsuit_ctx
+                    .terminate(
+                        LawsuitStatus::Dismissed,
+                        author.into(),
+                        Some(reason.clone()),
+                        &room,
+                    )
+                    .await
+                    .map(Ok),
+            };
+
+            report.push(match result {
+                Ok(Ok(())) => format!("#{number}: ✅"),
+                Ok(Err(response)) => format!("#{number}: {response}"),
+                Err(err) => {
+                    error!(?err, %number, "Error in bulk action");
+                    format!("#{number}: Fehler")
+                }
+            });
+        }
+
+        let lines = report
+            .iter()
+            .take(LIST_LIMIT)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                embed.title("Resultat").description(lines);
+                if report.len() > LIST_LIMIT {
+                    embed.footer(|footer| {
+                        footer.text(format!("und {} meh", report.len() - LIST_LIMIT))
+                    });
+                }
+                embed
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_tag_impl(
+        ctx: Context<'_>,
+        case_type: Option<CaseType>,
+        add: Option<String>,
+        remove: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let room_id = ctx.channel_id();
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.court_room == room_id.into() && !l.is_closed());
+
+        let mut lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        if case_type.is_some() {
+            lawsuit.case_type = case_type;
+        }
+        for tag in add.as_deref().map(parse_tags).unwrap_or_default() {
+            if !lawsuit.has_tag(&tag) {
+                lawsuit.tags.push(tag);
+            }
+        }
+        for tag in remove.as_deref().map(parse_tags).unwrap_or_default() {
+            lawsuit.tags.retain(|t| !t.eq_ignore_ascii_case(&tag));
+        }
+
+        mongo_client
+            .set_lawsuit(
+                guild_id.into(),
+                lawsuit.id,
+                doc! {
+                    "lawsuits.$.case_type": bson::to_bson(&lawsuit.case_type).wrap_err("invalid bson for case type")?,
+                    "lawsuits.$.tags": &lawsuit.tags,
+                },
+            )
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_list_impl(
+        ctx: Context<'_>,
+        case_type: Option<CaseType>,
+        tag: Option<String>,
+        open_only: bool,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let (user_id, admin) = (ctx.author().id.into(), is_admin(ctx));
+        let lawsuits = state
+            .lawsuits
+            .iter()
+            .rev()
+            .filter(|l| l.is_visible_to(user_id, admin))
+            .filter(|l| matches_filter(l, case_type, tag.as_deref()))
+            .filter(|l| !open_only || !l.is_closed())
+            .collect::<Vec<_>>();
+
+        if lawsuits.is_empty() {
+            ctx.say("es git kei passendi prozess").await?;
+            return Ok(());
+        }
+
+        let lines = lawsuits
+            .iter()
+            .take(LIST_LIMIT)
+            .map(|l| l.summary())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title("Prozess")
+                    .description(lines);
+                if lawsuits.len() > LIST_LIMIT {
+                    embed.footer(|footer| {
+                        footer.text(format!("und {} meh", lawsuits.len() - LIST_LIMIT))
+                    });
+                }
+                embed
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_info_impl(ctx: Context<'_>, case: u64) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+        let permission_override = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let author_id = ctx.author().id.into();
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && l.is_visible_to(author_id, permission_override));
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit,
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::LawsuitNotFound,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        // only the judge and admins may know who filed anonymously
+        let reveal =
+            lawsuit.anonymous && (permission_override || lawsuit.judge == ctx.author().id.into());
+        let plaintiff = if reveal {
+            format!("<@{}> (anonym)", lawsuit.plaintiff)
+        } else {
+            lawsuit.plaintiff_mention()
+        };
+        let lawyer = |lawyer: Option<SnowflakeId>| match lawyer {
+            Some(lawyer) => format!("<@{lawyer}>"),
+            None => "Keinen".to_string(),
+        };
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply
+                .embed(|embed| {
+                    branding::apply(embed, branding.as_ref())
+                        .title(format!("Prozess #{}", lawsuit.number))
+                        .description(lawsuit.summary())
+                        .field("Grund", &lawsuit.reason, false)
+                        .field("Kläger", plaintiff, true)
+                        .field("Anwalt des Klägers", lawyer(lawsuit.plaintiff_lawyer), true)
+                        .field("Angeklagter", format!("<@{}>", lawsuit.accused), true)
+                        .field(
+                            "Anwalt des Angeklagten",
+                            lawyer(lawsuit.accused_lawyer),
+                            true,
+                        )
+                        .field("Richter", format!("<@{}>", lawsuit.judge), true);
+                    if lawsuit.court_room != SnowflakeId(0) {
+                        embed.field("Gerichtsraum", format!("<#{}>", lawsuit.court_room), true);
+                    }
+                    for answer in &lawsuit.intake {
+                        embed.field(&answer.question, &answer.answer, false);
+                    }
+                    if !lawsuit.subpoenas.is_empty() {
+                        let subpoenas = lawsuit
+                            .subpoenas
+                            .iter()
+                            .map(|s| format!("<@{}>: {}", s.user_id, s.answer()))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        embed.field("Vorladige", subpoenas, false);
+                    }
+                    if let Some(verdict) = &lawsuit.verdict {
+                        embed.field("Urteil", verdict, false);
+                    }
+                    if let Some(reason) = &lawsuit.termination_reason {
+                        embed.field("Begründung", reason, false);
+                    }
+                    embed
+                })
+                .ephemeral(reveal)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_follow_impl(ctx: Context<'_>, case: u64, follow: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let author = ctx.author().id.into();
+        let admin = is_admin(ctx);
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && l.is_visible_to(author, admin));
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit,
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::LawsuitNotFound,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let following = lawsuit.followers.contains(&author);
+
+        if follow {
+            if following {
+                ctx.say(format!("du folgsch em Prozess #{case} scho"))
+                    .await?;
+                return Ok(());
+            }
+            if lawsuit.is_closed() {
+                ctx.say(format!("de Prozess #{case} isch scho abgschlosse"))
+                    .await?;
+                return Ok(());
+            }
+            mongo_client
+                .add_lawsuit_follower(guild_id.into(), lawsuit.id, author)
+                .await?;
+            ctx.say(format!(
+                "du chunsch jetzt e DM, wenn sich am Prozess #{case} öppis änderet"
+            ))
+            .await?;
+        } else {
+            if !following {
+                ctx.say(format!("du folgsch em Prozess #{case} nöd"))
+                    .await?;
+                return Ok(());
+            }
+            mongo_client
+                .remove_lawsuit_follower(guild_id.into(), lawsuit.id, author)
+                .await?;
+            ctx.say(format!("du folgsch em Prozess #{case} nüm"))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_seal_impl(ctx: Context<'_>, case: u64, sealed: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+        let permission_override = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let lawsuit = match state.lawsuits.iter().find(|l| l.number == case) {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::LawsuitNotFound,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() && !permission_override {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        mongo_client
+            .set_lawsuit(
+                guild_id.into(),
+                lawsuit.id,
+                doc! { "lawsuits.$.sealed": sealed },
+            )
+            .await?;
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        let description = if sealed {
+            "Prozess versiglet"
+        } else {
+            "Prozess entsiglet"
+        };
+        lawsuit_ctx
+            .add_history(ctx.author().id.into(), description.to_string())
+            .await?;
+
+        info!(case, sealed, "Changed seal of lawsuit");
+        if let Some(log_channel) = state.log_channel {
+            let action = if sealed { "versiglet" } else { "entsiglet" };
+            let notice = format!("<@{}> hät de Prozess #{case} {action}", ctx.author().id);
+            if let Err(err) = ChannelId::from(log_channel)
+                .say(&ctx.discord().http, notice)
+                .await
+            {
+                warn!(?err, "Failed to send seal notice");
+            }
+        }
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_schedule_hearing_impl(
+        ctx: Context<'_>,
+        case: u64,
+        time: String,
+        description: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let application_context = match ctx {
+            Context::Application(ctx) => ctx,
+            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+        };
+        let permission_override = application_context
+            .interaction
+            .member()
+            .wrap_err("member not found")?
+            .permissions
+            .map(|p| p.contains(Permissions::MANAGE_GUILD))
+            .unwrap_or(false);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let tz = state.timezone();
+
+        let time = match parse_date_time(&time, tz) {
+            Some(time) if time.timestamp_millis() > bson::DateTime::now().timestamp_millis() => {
+                time
+            }
+            Some(_) => {
+                ctx.say("d'Verhandlig muess i de Zuekunft sii").await?;
+                return Ok(());
+            }
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::InvalidTime,
+                    &[("example", "`30.06.2022 18:00`".to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && !l.is_closed() && !l.arbitration);
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::NoOngoingLawsuit,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() && !permission_override {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        lawsuit_ctx
+            .schedule_hearing(ctx.author().id.into(), Hearing { time, description })
+            .await?;
+
+        ctx.say(format!(
+            "d'Verhandlig isch am {} agsetzt",
+            format_date_time(time, tz)
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_deadline_set_impl(
+        ctx: Context<'_>,
+        case: u64,
+        phase: Phase,
+        time: String,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let tz = state.timezone();
+
+        let due = match parse_date_time(&time, tz) {
+            Some(due) if due.timestamp_millis() > bson::DateTime::now().timestamp_millis() => due,
+            Some(_) => {
+                ctx.say("d'Frist muess i de Zuekunft sii").await?;
+                return Ok(());
+            }
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::InvalidTime,
+                    &[("example", "`30.06.2022 18:00`".to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && !l.is_closed() && !l.arbitration);
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::NoOngoingLawsuit,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() && !is_admin(ctx) {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        lawsuit_ctx
+            .set_deadline(ctx.author().id.into(), Deadline { phase, due })
+            .await?;
+
+        ctx.say(format!(
+            "d'Frist für {phase} lauft bis am {}",
+            format_date_time(due, tz)
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_subpoena_impl(ctx: Context<'_>, user: User, date: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let tz = state.timezone();
+
+        let hearing = match parse_date_time(&date, tz) {
+            Some(hearing)
+                if hearing.timestamp_millis() > bson::DateTime::now().timestamp_millis() =>
+            {
+                hearing
+            }
+            Some(_) => {
+                ctx.say("d'Verhandlig muess i de Zuekunft sii").await?;
+                return Ok(());
+            }
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::InvalidTime,
+                    &[("example", "`30.06.2022 18:00`".to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let room_id = ctx.channel_id();
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.court_room == room_id.into() && !l.is_closed());
+        let mut lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() && !is_admin(ctx) {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        let result = subpoena::summon(
+            mongo_client,
+            &ctx.discord().http,
+            guild_id,
+            &mut lawsuit,
+            user.id,
+            hearing,
+        )
+        .await?;
+
+        match result {
+            Ok(()) => {
+                ctx.say(format!(
+                    "<@{}> isch uf de {} vorglade",
+                    user.id,
+                    format_date_time(hearing, tz)
+                ))
+                .await?;
+            }
+            Err(response) => {
+                ctx.say(response.to_string()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The reminders of the author in the guild, the next one first.
+    async fn own_reminders(ctx: Context<'_>, mongo: &Mongo) -> Result<Vec<scheduler::Job>> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let author = SnowflakeId::from(ctx.author().id);
+
+        let jobs = mongo.find_jobs(Some(guild_id.into())).await?;
+        Ok(jobs
+            .into_iter()
+            .filter(|job| {
+                !job.failed
+                    && matches!(job.kind, JobKind::CaseReminder { user_id, .. } if user_id == author)
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_remind_impl(
+        ctx: Context<'_>,
+        case: u64,
+        when: String,
+        note: String,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let tz = state.timezone();
+
+        let now = bson::DateTime::now().timestamp_millis();
+        let due = match parse_duration(&when) {
+            Some(duration) => Some(bson::DateTime::from_millis(
+                now + duration.as_millis() as i64,
+            )),
+            None => parse_date_time(&when, tz),
+        };
+        let due = match due {
+            Some(due) if due.timestamp_millis() > now => due,
+            Some(_) => {
+                ctx.say("d'Erinnerig muess i de Zuekunft sii").await?;
+                return Ok(());
+            }
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::InvalidTime,
+                    &[("example", "`2h` oder `30.06.2022 18:00`".to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && !l.is_closed());
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit,
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::NoOngoingLawsuit,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let author = SnowflakeId::from(ctx.author().id);
+        let involved = lawsuit.judge == author
+            || lawsuit.plaintiff_lawyer == Some(author)
+            || lawsuit.accused_lawyer == Some(author);
+        if !involved {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        if own_reminders(ctx, mongo_client).await?.len() >= REMINDER_LIMIT {
+            ctx.say(format!(
+                "du häsch scho {REMINDER_LIMIT} Erinnerige, lösch zerst eini mit `/lawsuit notify reminders`"
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let kind = JobKind::CaseReminder {
+            lawsuit_id: lawsuit.id,
+            user_id: author,
+            note,
+        };
+        scheduler::schedule(mongo_client, guild_id.into(), kind, due).await?;
+
+        ctx.say(format!(
+            "ich erinnere di am {} per DM a de Prozess #{case}",
+            format_date_time(due, tz)
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_reminders_impl(ctx: Context<'_>, cancel: Option<usize>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let reminders = own_reminders(ctx, mongo_client).await?;
+
+        if let Some(number) = cancel {
+            match number.checked_sub(1).and_then(|index| reminders.get(index)) {
+                Some(reminder) => {
+                    mongo_client.delete_job(reminder.id).await?;
+                    ctx.say("d'Erinnerig isch glöscht").await?;
+                }
+                None => {
+                    ctx.say(format!("es git kei Erinnerig {number}")).await?;
+                }
+            }
+            return Ok(());
+        }
+
+        if reminders.is_empty() {
+            ctx.say("du häsch kei Erinnerige, setz eini mit `/lawsuit notify remind`")
+                .await?;
+            return Ok(());
+        }
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let tz = state.timezone();
+        let list = reminders
+            .iter()
+            .enumerate()
+            .filter_map(|(index, reminder)| match &reminder.kind {
+                JobKind::CaseReminder {
+                    lawsuit_id, note, ..
+                } => {
+                    let case = state
+                        .lawsuits
+                        .iter()
+                        .find(|l| l.id == *lawsuit_id)
+                        .map(|l| format!("#{}", l.number))
+                        .unwrap_or_else(|| "?".to_string());
+                    Some(format!(
+                        "{}. {} (Prozess {case}): {note}",
+                        index + 1,
+                        format_date_time(reminder.due, tz)
+                    ))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.say(list).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_export_impl(ctx: Context<'_>, case: u64) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && l.is_visible_to(ctx.author().id.into(), is_admin(ctx)));
+        let lawsuit = match lawsuit {
+            Some(lawsuit) if lawsuit.verdict.is_some() => lawsuit,
+            Some(_) => {
+                ctx.say(format!("de Prozess #{case} hät no keis Urteil"))
+                    .await?;
+                return Ok(());
+            }
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::LawsuitNotFound,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        // a sealed verdict must not end up in the channel
+        if lawsuit.sealed {
+            ctx.defer_ephemeral().await?;
+        } else {
+            ctx.defer().await?;
+        }
+
+        let mut names = HashMap::new();
+        for user_id in lawsuit.participants() {
+            match UserId::from(user_id).to_user(ctx.discord()).await {
+                Ok(user) => {
+                    names.insert(user_id, user.name);
+                }
+                Err(err) => warn!(?err, %user_id, "Could not fetch user for verdict record"),
+            }
+        }
+        let court = match &state.branding {
+            Some(branding) => branding.footer_text.clone(),
+            None => ctx
+                .guild()
+                .map(|guild| format!("Gericht von {}", guild.name))
+                .unwrap_or_else(|| "Gericht".to_string()),
+        };
+
+        let seal = match state.branding.as_ref().and_then(|b| b.icon_url.as_ref()) {
+            Some(url) => match branding::fetch_seal(url).await {
+                Ok(seal) => Some(seal),
+                Err(err) => {
+                    warn!(?err, "Could not load seal for verdict record");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let mut document =
+            crate::pdf::verdict_record(lawsuit, &court, seal, &names, state.timezone());
+        if let Some(signed) = signature::sign(guild_id.into(), lawsuit) {
+            signed.embed(&mut document);
+        }
+        let pdf = document.finish();
+
+        ctx.send(|reply| {
+            reply
+                .content(format!("Urteil vom Prozess #{case}"))
+                .ephemeral(lawsuit.sealed)
+                .attachment(serenity::AttachmentType::Bytes {
+                    data: pdf.into(),
+                    filename: format!("urteil-{case}.pdf"),
+                })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_verify_impl(ctx: Context<'_>, file: serenity::Attachment) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        if file.size > VERIFY_SIZE_LIMIT {
+            ctx.say("d'Datei isch z'gross für es Urteil").await?;
+            return Ok(());
+        }
+
+        ctx.defer().await?;
+
+        let pdf = file.download().await.wrap_err("download verdict file")?;
+        let verdict = match signature::verify(&pdf) {
+            Verification::Valid(verdict) => verdict,
+            Verification::Forged => {
+                ctx.say("❌ das Urteil isch gfälscht oder veränderet worde")
+                    .await?;
+                return Ok(());
+            }
+            Verification::Unsigned => {
+                ctx.say("i dere Datei isch kei signierts Urteil").await?;
+                return Ok(());
+            }
+            Verification::NoKey => {
+                ctx.say("de Bot cha kei Urteil signiere, de Betreiber hät kein Schlüssel gsetzt")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let origin = if verdict.guild_id == guild_id.into() {
+            String::new()
+        } else {
+            format!(" vomene andere Server ({})", verdict.guild_id)
+        };
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title(format!(
+                        "✅ Echts Urteil vom Prozess #{}{origin}",
+                        verdict.number
+                    ))
+                    .field(
+                        "Kläger",
+                        verdict
+                            .plaintiff
+                            .map(|plaintiff| format!("<@{plaintiff}>"))
+                            .unwrap_or_else(|| "Anonym".to_string()),
+                        true,
+                    )
+                    .field("Angeklagter", format!("<@{}>", verdict.accused), true)
+                    .field("Richter", format!("<@{}>", verdict.judge), true)
+                    .field("Grund", &verdict.reason, false)
+                    .field("Urteil", &verdict.verdict, false)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_ordeal_impl(ctx: Context<'_>, case: u64, kind: OrdealKind) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        if !state.ordeals {
+            ctx.say(
+                "Gottesurteil sind uf dem Server nöd iigschaltet, das gaht mit `/config ordeals`",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && !l.is_closed() && !l.arbitration);
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::NoOngoingLawsuit,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        if lawsuit.case_type != Some(CaseType::SmallClaims) {
+            ctx.say("nur Bagatellsache chönd dur es Gottesurteil entschiede werde")
+                .await?;
+            return Ok(());
+        }
+        if lawsuit.judge != ctx.author().id.into() && !is_admin(ctx) {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+        let room = match state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == lawsuit.court_room)
+        {
+            Some(room) => room.clone(),
+            None => {
+                say_message(ctx, MessageKey::NoActiveLawsuit, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        let (winner, course) = match kind {
+            OrdealKind::Dice => {
+                let (winner, rolls) = ordeal::roll_dice(&mut rand::thread_rng());
+                let course = rolls
+                    .iter()
+                    .map(|(plaintiff, accused)| format!("🎲 {plaintiff} gege {accused}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (winner, course)
+            }
+            OrdealKind::RockPaperScissors => match rock_paper_scissors(ctx, &lawsuit).await? {
+                Some(result) => result,
+                None => {
+                    ctx.say("kei Partei hät gwählt, s'Duell isch abbroche")
+                        .await?;
+                    return Ok(());
+                }
+            },
+        };
+
+        let verdict = ordeal::verdict(kind, winner, &course);
+        let damages = match winner {
+            Party::Plaintiff => lawsuit.claim_amount,
+            Party::Accused => None,
+        };
+        let lawsuit_id = lawsuit.id;
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        let response = lawsuit_ctx
+            .rule_verdict(
+                is_admin(ctx),
+                ctx.author().id,
+                Ruling {
+                    verdict: verdict.clone(),
+                    winner: Some(winner),
+                    damages,
+                    community_service: None,
+                    prison: None,
+                },
+                room,
+            )
+            .await?;
+        if let Err(response) = response {
+            ctx.say(response.to_string()).await?;
+            return Ok(());
+        }
+
+        mongo_client
+            .set_lawsuit(
+                guild_id.into(),
+                lawsuit_id,
+                doc! { "lawsuits.$.ordeal": bson::to_bson(&kind).wrap_err("invalid bson for ordeal")? },
+            )
+            .await?;
+
+        ctx.say(verdict).await?;
+
+        Ok(())
+    }
+
+    /// Lets the plaintiff and the accused pick their hands with buttons until one of them wins.
+    /// If only one of them picked in time, they win, if neither did, there is no winner.
+    async fn rock_paper_scissors(
+        ctx: Context<'_>,
+        lawsuit: &Lawsuit,
+    ) -> Result<Option<(Party, String)>> {
+        let message = ctx
+            .send(|reply| {
+                reply
+                    .content(format!(
+                        "<@{}> und <@{}>, wählet Schere, Stei oder Papier",
+                        lawsuit.plaintiff, lawsuit.accused
+                    ))
+                    .components(|c| {
+                        c.create_action_row(|row| {
+                            for hand in Hand::ALL {
+                                row.create_button(|button| {
+                                    button
+                                        .custom_id(format!(
+                                            "{}{}",
+                                            ordeal::HAND_ID_PREFIX,
+                                            hand.id()
+                                        ))
+                                        .label(hand.to_string())
+                                        .emoji(hand.emoji())
+                                        .style(serenity::ButtonStyle::Primary)
+                                });
+                            }
+                            row
+                        })
+                    })
+            })
+            .await?
+            .message()
+            .await?;
+
+        let mut interactions = message
+            .await_component_interactions(&ctx.discord().shard)
+            .timeout(DUEL_TIMEOUT)
+            .build();
+        let (mut plaintiff_hand, mut accused_hand) = (None, None);
+        let mut rounds = Vec::new();
+        let mut winner = None;
+
+        while let Some(interaction) = interactions.next().await {
+            let hand = interaction
+                .data
+                .custom_id
+                .strip_prefix(ordeal::HAND_ID_PREFIX)
+                .and_then(Hand::from_id);
+            let hand = match hand {
+                Some(hand) => hand,
+                None => continue,
+            };
+            let user_id = SnowflakeId::from(interaction.user.id);
+            let slot = if user_id == lawsuit.plaintiff {
+                &mut plaintiff_hand
+            } else if user_id == lawsuit.accused {
+                &mut accused_hand
+            } else {
+                respond_ephemeral(
+                    ctx.discord(),
+                    &interaction,
+                    "nur de Kläger und de Angeklagti duelliered sich",
+                )
+                .await?;
+                continue;
+            };
+            if slot.is_some() {
+                respond_ephemeral(ctx.discord(), &interaction, "du häsch scho gwählt").await?;
+                continue;
+            }
+            *slot = Some(hand);
+            respond_ephemeral(
+                ctx.discord(),
+                &interaction,
+                &format!("du häsch {hand} gwählt"),
+            )
+            .await?;
+
+            if let (Some(plaintiff), Some(accused)) = (plaintiff_hand, accused_hand) {
+                rounds.push(format!("{plaintiff} gege {accused}"));
+                match ordeal::duel_winner(plaintiff, accused) {
+                    Some(party) => {
+                        winner = Some(party);
+                        break;
+                    }
+                    None => {
+                        (plaintiff_hand, accused_hand) = (None, None);
+                        message
+                            .channel_id
+                            .say(&ctx.discord().http, "unentschiede, nomal wähle!")
+                            .await?;
+                    }
+                }
+            }
+        }
+        interactions.stop();
+
+        message
+            .channel_id
+            .edit_message(&ctx.discord().http, message.id, |msg| msg.components(|c| c))
+            .await
+            .wrap_err("remove duel buttons")?;
+
+        if let Some(winner) = winner {
+            return Ok(Some((winner, rounds.join(", "))));
+        }
+        let forfeit = match (plaintiff_hand, accused_hand) {
+            (Some(_), None) => Some(Party::Plaintiff),
+            (None, Some(_)) => Some(Party::Accused),
+            _ => None,
+        };
+        Ok(forfeit.map(|winner| {
+            rounds.push("de Gegner hät nöd gwählt".to_string());
+            (winner, rounds.join(", "))
+        }))
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum DetentionAction {
+        Detain,
+        /// In how many days.
+        Review(u32),
+        End,
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_detention_impl(
+        ctx: Context<'_>,
+        case: u64,
+        action: DetentionAction,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let lawsuit = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == case && !l.is_closed() && !l.arbitration);
+        let lawsuit = match lawsuit {
+            Some(lawsuit) => lawsuit.clone(),
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::NoOngoingLawsuit,
+                    &[("case", case.to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if lawsuit.judge != ctx.author().id.into() && !is_admin(ctx) {
+            say_message(ctx, MessageKey::NoPermission, &[]).await?;
+            return Ok(());
+        }
+
+        let mut lawsuit_ctx = LawsuitCtx {
+            lawsuit,
+            mongo_client: mongo_client.clone(),
+            http: ctx.discord().http.clone(),
+            guild_id,
+        };
+        let actor = ctx.author().id.into();
+        let response = match action {
+            DetentionAction::Detain => lawsuit_ctx.detain(actor).await?,
+            DetentionAction::Review(days) => {
+                let after = Duration::from_secs(u64::from(days) * 24 * 60 * 60);
+                match lawsuit_ctx.schedule_detention_review(actor, after).await? {
+                    Ok(due) => {
+                        ctx.say(format!(
+                            "d'Haftprüefig isch am {}",
+                            format_date_time(due, state.timezone())
+                        ))
+                        .await?;
+                        return Ok(());
+                    }
+                    Err(response) => Err(response),
+                }
+            }
+            DetentionAction::End => {
+                if lawsuit_ctx.lawsuit.detained_at.is_none() {
+                    ctx.say(format!(
+                        "<@{}> isch nöd in Untersuechigshaft",
+                        lawsuit_ctx.lawsuit.accused
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                lawsuit_ctx.end_detention(actor).await?;
+                Ok(())
+            }
+        };
+
+        match response {
+            Ok(()) => say_message(ctx, MessageKey::Saved, &[]).await?,
+            Err(response) => {
+                ctx.say(response.to_string()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawsuit_clear_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx).delete_guild(guild_id.into()).await?;
+        ctx.say("alles weg").await?;
+        Ok(())
+    }
+}
+
+pub mod court {
+    use mongodb::bson;
+
+    use super::*;
+
+    /// How far ahead the calendar shows hearings.
+    const CALENDAR_RANGE: Duration = Duration::from_secs(60 * 60 * 24 * 14);
+
+    /// The maximum amount of hearings shown in the calendar.
+    const CALENDAR_LIMIT: usize = 20;
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Gericht",
+        subcommands(
+            "stats",
+            "set_judge_role",
+            "set_grand_jury",
+            "set_limitation",
+            "set_docket_channel",
+            "subscribe",
+            "calendar",
+            "sync_rooms",
+            "create_category",
+            "room_permissions",
+            "form",
+            "verdict_template",
+            "session",
+            "most_sued",
+            "leaderboard_opt_out",
+            "search"
+        )
+    )]
+    pub async fn court(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(slash_command, guild_only, subcommands("overview", "activity"))]
+    async fn stats(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Statistiken über die Gerichtsprozesse anzeigen
+    #[poise::command(slash_command, guild_only)]
+    async fn overview(
+        ctx: Context<'_>,
+        #[description = "Nur Prozesse dieser Art"] case_type: Option<CaseType>,
+        #[description = "Nur Prozesse mit diesem Tag"] tag: Option<String>,
+    ) -> Result<()> {
+        court_stats_impl(ctx, case_type, tag)
+            .await
+            .wrap_err("court_stats")
+    }
+
+    /// Neue Prozesse und Urteile pro Woche der letzten drei Monate anzeigen
+    #[poise::command(slash_command, guild_only)]
+    async fn activity(ctx: Context<'_>) -> Result<()> {
+        court_stats_activity_impl(ctx)
+            .await
+            .wrap_err("court_stats_activity")
+    }
+
+    /// Die Begründungen, Urteile und Aussagen aller Prozesse durchsuchen
+    #[poise::command(slash_command, guild_only)]
+    async fn search(
+        ctx: Context<'_>,
+        #[description = "Die Wörter, die alle vorkommen müssen"] query: String,
+    ) -> Result<()> {
+        court_search_impl(ctx, query).await.wrap_err("court_search")
+    }
+
+    /// Die Personen anzeigen, die am häufigsten angeklagt wurden
+    #[poise::command(slash_command, guild_only)]
+    async fn most_sued(ctx: Context<'_>) -> Result<()> {
+        court_most_sued_impl(ctx).await.wrap_err("court_most_sued")
+    }
+
+    /// Auf den Ranglisten nicht mehr erscheinen, oder wieder
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn leaderboard_opt_out(
+        ctx: Context<'_>,
+        #[description = "Ob du auf den Ranglisten versteckt wirst"] hidden: bool,
+    ) -> Result<()> {
+        court_leaderboard_opt_out_impl(ctx, hidden)
+            .await
+            .wrap_err("court_leaderboard_opt_out")
+    }
+
+    /// Die Rolle setzen, die Richter haben müssen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_judge_role(
+        ctx: Context<'_>,
+        #[description = "Die Rolle"] role: Role,
+        #[description = "Nur für diese Art von Prozessen"] case_type: Option<CaseType>,
+    ) -> Result<()> {
+        court_set_judge_role_impl(ctx, role, case_type)
+            .await
+            .wrap_err("court_set_judge_role")
+    }
+
+    /// Strafprozesse zuerst von einer Grand Jury entscheiden lassen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_grand_jury(
+        ctx: Context<'_>,
+        #[description = "Wie viele Stimmen es für eine Anklage braucht, 0 um auszuschalten"]
+        #[min = 0]
+        threshold: u32,
+        #[description = "Die Rolle der Geschworenen"] role: Option<Role>,
+        #[description = "Der Kanal für die Abstimmungen"] channel: Option<Channel>,
+        #[description = "Wie lange abgestimmt wird, zum Beispiel 2d"] deadline: Option<String>,
+    ) -> Result<()> {
+        court_set_grand_jury_impl(ctx, threshold, role, channel, deadline)
+            .await
+            .wrap_err("court_set_grand_jury")
+    }
+
+    /// Die Verhandlungen der nächsten zwei Wochen anzeigen
+    #[poise::command(slash_command, guild_only)]
+    async fn calendar(ctx: Context<'_>) -> Result<()> {
+        court_calendar_impl(ctx).await.wrap_err("court_calendar")
+    }
+
+    /// Bei neuen Prozessen erwähnt werden, oder nicht mehr
+    #[poise::command(slash_command, guild_only)]
+    async fn subscribe(ctx: Context<'_>) -> Result<()> {
+        court_subscribe_impl(ctx).await.wrap_err("court_subscribe")
+    }
+
+    /// Den Kanal setzen, in dem eine Liste aller offenen Prozesse angepinnt wird
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_docket_channel(
+        ctx: Context<'_>,
+        #[description = "Der Kanal, leer lassen um auszuschalten"] channel: Option<Channel>,
+    ) -> Result<()> {
+        court_set_docket_channel_impl(ctx, channel)
+            .await
+            .wrap_err("court_set_docket_channel")
+    }
+
+    /// Die Verjährungsfrist für eine Art von Prozessen setzen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_limitation(
+        ctx: Context<'_>,
+        #[description = "Die Art des Prozesses"] case_type: CaseType,
+        #[description = "Die Frist, zum Beispiel 4w, 0 um sie auszuschalten"] duration: String,
+    ) -> Result<()> {
+        court_set_limitation_impl(ctx, case_type, duration)
+            .await
+            .wrap_err("court_set_limitation")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_stats_impl(
+        ctx: Context<'_>,
+        case_type: Option<CaseType>,
+        tag: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let (user_id, admin) = (ctx.author().id.into(), is_admin(ctx));
+        let lawsuits = state
+            .lawsuits
+            .iter()
+            .filter(|l| l.is_visible_to(user_id, admin))
+            .filter(|l| matches_filter(l, case_type, tag.as_deref()))
+            .collect::<Vec<_>>();
+
+        let open = lawsuits.iter().filter(|l| !l.is_closed()).count();
+        let with_status = |status| lawsuits.iter().filter(|l| l.status == status).count();
+        let with_verdict = lawsuits.iter().filter(|l| l.verdict.is_some()).count();
+        let won_by = |party| lawsuits.iter().filter(|l| l.winner == Some(party)).count();
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title("Gerichtsstatistik")
+                    .field("Total", lawsuits.len(), true)
+                    .field("Offen", open, true)
+                    .field("Abgeschlossen", lawsuits.len() - open, true)
+                    .field("Mit Urteil", with_verdict, true)
+                    .field("Zurückgezogen", with_status(LawsuitStatus::Withdrawn), true)
+                    .field("Abgewiesen", with_status(LawsuitStatus::Dismissed), true)
+                    .field("Gewonnen vom Kläger", won_by(Party::Plaintiff), true)
+                    .field("Gewonnen vom Angeklagten", won_by(Party::Accused), true)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_search_impl(ctx: Context<'_>, query: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let (user_id, admin) = (ctx.author().id.into(), is_admin(ctx));
+        let hits = crate::search::search(
+            state
+                .lawsuits
+                .iter()
+                .filter(|l| l.is_visible_to(user_id, admin)),
+            &query,
+        );
+
+        if hits.is_empty() {
+            ctx.say("kei Prozess passt zu dere Suechi").await?;
+            return Ok(());
+        }
+
+        let lines = hits
+            .iter()
+            .take(crate::search::MAX_RESULTS)
+            .map(|hit| format!("{}\n> {}", hit.lawsuit.summary(), hit.snippet))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("{} Träffer für «{query}»", hits.len()))
+                    .description(lines)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_most_sued_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let (user_id, admin) = (ctx.author().id.into(), is_admin(ctx));
+        let counts = leaderboard::most_sued(
+            state
+                .lawsuits
+                .iter()
+                .filter(|l| l.is_visible_to(user_id, admin)),
+        );
+
+        send_leaderboard(
+            ctx,
+            "Am meiste verklagt".to_string(),
+            counts
+                .iter()
+                .filter(|(accused, _)| !state.leaderboard_opt_outs.contains(accused))
+                .map(|(accused, count)| format!("<@{accused}> – {count} Prozess")),
+            "es isch no niemert verklagt worde",
+        )
+        .await
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_leaderboard_opt_out_impl(ctx: Context<'_>, hidden: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_leaderboard_opt_out(guild_id.into(), ctx.author().id.into(), hidden)
+            .await?;
+
+        if hidden {
+            ctx.say("du erschiinsch nüm uf de Ranglischte").await?;
+        } else {
+            ctx.say("du erschiinsch wieder uf de Ranglischte").await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_stats_activity_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let since = mongodb::bson::DateTime::from_millis(
+            mongodb::bson::DateTime::now().timestamp_millis()
+                - activity::WEEKS * activity::WEEK.as_millis() as i64,
+        );
+        let activity = mongo_client
+            .lawsuit_activity(guild_id.into(), since, is_admin(ctx))
+            .await?;
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title("Gerichtsaktivität")
+                    .description(format!(
+                        "📂 neui Prozess, ⚖️ Urteil, pro Wuche\n\n{}",
+                        activity.chart(since, state.timezone())
+                    ))
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_set_judge_role_impl(
+        ctx: Context<'_>,
+        role: Role,
+        case_type: Option<CaseType>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+
+        match case_type {
+            Some(case_type) => {
+                mongo
+                    .update_case_type_config(guild_id.into(), case_type, |config| {
+                        config.judge_role = Some(role.id.into())
+                    })
+                    .await?
+            }
+            None => {
+                mongo
+                    .set_judge_role(guild_id.into(), role.id.into())
+                    .await?
+            }
+        }
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_set_limitation_impl(
+        ctx: Context<'_>,
+        case_type: CaseType,
+        duration: String,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let limitation = if duration.trim() == "0" {
+            None
+        } else {
+            match parse_duration(&duration) {
+                Some(duration) => Some(duration.as_secs() as i64),
+                None => {
+                    say_message(
+                        ctx,
+                        MessageKey::InvalidDeadline,
+                        &[("example", "`4w`".to_string())],
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            }
+        };
+
+        guild_mongo(ctx)
+            .update_case_type_config(guild_id.into(), case_type, |config| {
+                config.limitation_secs = limitation
+            })
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_set_grand_jury_impl(
+        ctx: Context<'_>,
+        threshold: u32,
+        role: Option<Role>,
+        channel: Option<Channel>,
+        deadline: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let grand_jury = if threshold > 0 {
+            let (role, channel) = match (role, channel) {
+                (Some(role), Some(channel)) => (role, channel),
+                _ => {
+                    ctx.say("du muesch e rolle und en kanal für d'Grand Jury ahgeh")
+                        .await?;
+                    return Ok(());
+                }
+            };
+            let deadline = match parse_duration(deadline.as_deref().unwrap_or("2d")) {
+                Some(deadline) => deadline,
+                None => {
+                    say_message(
+                        ctx,
+                        MessageKey::InvalidDeadline,
+                        &[("example", "`2d`".to_string())],
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            };
+
+            Some(GrandJury {
+                role_id: role.id.into(),
+                channel_id: channel.id().into(),
+                threshold,
+                deadline_secs: deadline.as_secs() as i64,
+            })
+        } else {
+            None
+        };
+
+        guild_mongo(ctx)
+            .set_grand_jury(guild_id.into(), grand_jury.as_ref())
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_calendar_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let now = bson::DateTime::now().timestamp_millis();
+        let until = now + CALENDAR_RANGE.as_millis() as i64;
+
+        let (user_id, admin) = (ctx.author().id.into(), is_admin(ctx));
+        let mut hearings = state
+            .lawsuits
+            .iter()
+            .filter(|l| !l.is_closed() && l.is_visible_to(user_id, admin))
+            .flat_map(|l| l.hearings.iter().map(move |hearing| (l, hearing)))
+            .filter(|(_, hearing)| (now..until).contains(&hearing.time.timestamp_millis()))
+            .collect::<Vec<_>>();
+        hearings.sort_by_key(|(_, hearing)| hearing.time.timestamp_millis());
+
+        if hearings.is_empty() {
+            ctx.say("i de nächste zwei Wuche git's kei Verhandlige")
+                .await?;
+            return Ok(());
+        }
+
+        let lines = hearings
+            .iter()
+            .take(CALENDAR_LIMIT)
+            .map(|(lawsuit, hearing)| {
+                let description = hearing
+                    .description
+                    .as_deref()
+                    .map(|description| format!(": {description}"))
+                    .unwrap_or_default();
+                format!(
+                    "{} **#{}** {}{description}",
+                    format_date_time(hearing.time, state.timezone()),
+                    lawsuit.number,
+                    lawsuit.reason
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title("Verhandlige")
+                    .description(lines);
+                if hearings.len() > CALENDAR_LIMIT {
+                    embed.footer(|footer| {
+                        footer.text(format!("und {} meh", hearings.len() - CALENDAR_LIMIT))
+                    });
+                }
+                embed
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_subscribe_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+        let role_id = match state.case_ping_role {
+            Some(role_id) => RoleId::from(role_id),
+            None => {
+                ctx.say("es git kei Rolle für neui Prozess").await?;
+                return Ok(());
+            }
+        };
+
+        let member = guild_id
+            .member(ctx.discord(), ctx.author().id)
+            .await
+            .wrap_err("fetch member")?;
+
+        if member.roles.contains(&role_id) {
+            sandbox::remove_member_role(&ctx.discord().http, guild_id, member.user.id, role_id)
+                .await
+                .wrap_err("remove case ping role")?;
+            ctx.say("du wirsch nüm bi neue Prozess erwähnt").await?;
+        } else {
+            sandbox::add_member_role(&ctx.discord().http, guild_id, member.user.id, role_id)
+                .await
+                .wrap_err("add case ping role")?;
+            ctx.say("du wirsch jetzt bi neue Prozess erwähnt").await?;
+        }
+
+        Ok(())
+    }
+
+    /// Eine Kategorie für Gerichtsräume erstellen, in der nur Richter und Beteiligte schreiben dürfen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        required_bot_permissions = "MANAGE_CHANNELS | MANAGE_ROLES"
+    )]
+    async fn create_category(
+        ctx: Context<'_>,
+        #[description = "Der Name der Kategorie, standardmässig \"Gericht\""] name: Option<String>,
+        #[description = "Nur für diese Art von Prozessen"] case_type: Option<CaseType>,
+    ) -> Result<()> {
+        court_create_category_impl(ctx, name, case_type)
+            .await
+            .wrap_err("court_create_category")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_create_category_impl(
+        ctx: Context<'_>,
+        name: Option<String>,
+        case_type: Option<CaseType>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let category = crate::lawsuit::create_court_category(
+            &guild_mongo(ctx),
+            &ctx.discord().http,
+            guild_id,
+            ctx.discord().cache.current_user_id(),
+            name.unwrap_or_else(|| "Gericht".to_string()),
+            case_type,
+        )
+        .await?;
+
+        match category {
+            Some(category) => {
+                ctx.say(format!(
+                    "d'Kategorie <#{category}> isch erstellt, neui Gerichtsräum chömed det ane"
+                ))
+                .await?;
+            }
+            None => {
+                say_message(ctx, MessageKey::Saved, &[]).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands(
+            "room_permissions_set",
+            "room_permissions_reader",
+            "room_permissions_show",
+            "room_permissions_reset"
+        )
+    )]
+    async fn room_permissions(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Festlegen, was Zuschauer und Beteiligte in neuen Gerichtsräumen dürfen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "set",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn room_permissions_set(
+        ctx: Context<'_>,
+        #[description = "Ob Zuschauer auf Nachrichten reagieren dürfen"]
+        spectator_reactions: Option<bool>,
+        #[description = "Ob die Beteiligten Threads erstellen dürfen"] threads: Option<bool>,
+    ) -> Result<()> {
+        court_room_permissions_set_impl(ctx, spectator_reactions, threads)
+            .await
+            .wrap_err("court_room_permissions_set")
+    }
+
+    /// Eine Rolle die Gerichtsräume lesen lassen, oder es ihr wieder wegnehmen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "reader",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn room_permissions_reader(
+        ctx: Context<'_>,
+        #[description = "Die Rolle"] role: Role,
+    ) -> Result<()> {
+        court_room_permissions_reader_impl(ctx, role)
+            .await
+            .wrap_err("court_room_permissions_reader")
+    }
+
+    /// Die Berechtigungen für neue Gerichtsräume anzeigen
+    #[poise::command(slash_command, guild_only, rename = "show")]
+    async fn room_permissions_show(ctx: Context<'_>) -> Result<()> {
+        court_room_permissions_show_impl(ctx)
+            .await
+            .wrap_err("court_room_permissions_show")
+    }
+
+    /// Die Berechtigungen für neue Gerichtsräume zurücksetzen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "reset",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn room_permissions_reset(ctx: Context<'_>) -> Result<()> {
+        court_room_permissions_reset_impl(ctx)
+            .await
+            .wrap_err("court_room_permissions_reset")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_room_permissions_set_impl(
+        ctx: Context<'_>,
+        spectator_reactions: Option<bool>,
+        threads: Option<bool>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let mut permissions = state.room_permissions.unwrap_or_default();
+        if let Some(spectator_reactions) = spectator_reactions {
+            permissions.spectator_reactions = spectator_reactions;
+        }
+        if let Some(threads) = threads {
+            permissions.threads = threads;
+        }
+        mongo
+            .set_room_permissions(guild_id.into(), Some(&permissions))
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_room_permissions_reader_impl(ctx: Context<'_>, role: Role) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let mut permissions = state.room_permissions.unwrap_or_default();
+        let role_id = SnowflakeId::from(role.id);
+        let added = if permissions.readers.contains(&role_id) {
+            permissions.readers.retain(|&reader| reader != role_id);
+            false
+        } else {
+            permissions.readers.push(role_id);
+            true
+        };
+        mongo
+            .set_room_permissions(guild_id.into(), Some(&permissions))
+            .await?;
+
+        let response = match (added, permissions.readers.is_empty()) {
+            (true, _) => format!("<@&{role_id}> chan jetzt neui Gerichtsräum lese"),
+            (false, false) => format!("<@&{role_id}> chan neui Gerichtsräum nüm lese"),
+            (false, true) => format!(
+                "<@&{role_id}> isch kei Leserolle meh, jetzt chan wieder jede neui Gerichtsräum lese"
+            ),
+        };
+        ctx.say(response).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_room_permissions_show_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+        let permissions = state.room_permissions.unwrap_or_default();
+
+        let readers = if permissions.readers.is_empty() {
+            "alli".to_string()
+        } else {
+            permissions
+                .readers
+                .iter()
+                .map(|role| format!("<@&{role}>"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let yes_no = |allowed: bool| if allowed { "ja" } else { "nei" };
+
+        ctx.say(format!(
+            "Lese: {readers}\nZueschauer dörfed reagiere: {}\nBeteiligti dörfed Threads erstelle: {}\n\
+             Gilt für neui Gerichtsräum, `/court sync_rooms` wändets uf di bestehende a",
+            yes_no(permissions.spectator_reactions),
+            yes_no(permissions.threads)
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_room_permissions_reset_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_room_permissions(guild_id.into(), None)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("form_add", "form_remove", "form_show")
+    )]
+    async fn form(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Eine Frage hinzufügen, die beim Einreichen einer Klage beantwortet werden muss
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "add",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn form_add(
+        ctx: Context<'_>,
+        #[description = "Die Frage"] question: String,
+    ) -> Result<()> {
+        court_form_add_impl(ctx, question)
+            .await
+            .wrap_err("court_form_add")
+    }
+
+    /// Eine Frage des Klageformulars entfernen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "remove",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn form_remove(
+        ctx: Context<'_>,
+        #[description = "Die Nummer der Frage"]
+        #[min = 1]
+        number: usize,
+    ) -> Result<()> {
+        court_form_remove_impl(ctx, number)
+            .await
+            .wrap_err("court_form_remove")
+    }
+
+    /// Die Fragen des Klageformulars anzeigen
+    #[poise::command(slash_command, guild_only, rename = "show")]
+    async fn form_show(ctx: Context<'_>) -> Result<()> {
+        court_form_show_impl(ctx).await.wrap_err("court_form_show")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_form_add_impl(ctx: Context<'_>, question: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let question = question.trim().to_string();
+        if question.is_empty() || question.chars().count() > intake::MAX_QUESTION_LEN {
+            ctx.say(format!(
+                "e Frag muess zwüsche 1 und {} Zeiche lang si",
+                intake::MAX_QUESTION_LEN
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let mut questions = mongo
+            .find_or_insert_state(guild_id.into())
+            .await?
+            .intake_form;
+        if questions.len() >= intake::MAX_QUESTIONS {
+            ctx.say(format!(
+                "es git scho {} Frage, meh passed nöd in es Formular",
+                intake::MAX_QUESTIONS
+            ))
+            .await?;
+            return Ok(());
+        }
+        questions.push(question);
+        mongo.set_intake_form(guild_id.into(), &questions).await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_form_remove_impl(ctx: Context<'_>, number: usize) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let mut questions = mongo
+            .find_or_insert_state(guild_id.into())
+            .await?
+            .intake_form;
+        if number == 0 || number > questions.len() {
+            ctx.say(format!("es git kei Frag {number}")).await?;
+            return Ok(());
+        }
+        questions.remove(number - 1);
+        mongo.set_intake_form(guild_id.into(), &questions).await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_form_show_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        if state.intake_form.is_empty() {
+            ctx.say("es git kei Klagformular, füeg mit `/court form add` Frage hinzue")
+                .await?;
+            return Ok(());
+        }
+
+        let questions = state
+            .intake_form
+            .iter()
+            .enumerate()
+            .map(|(index, question)| format!("{}. {question}", index + 1))
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.say(questions).await?;
+
+        Ok(())
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands(
+            "verdict_template_add",
+            "verdict_template_remove",
+            "verdict_template_list"
+        )
+    )]
+    async fn verdict_template(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Eine Urteilsvorlage für /lawsuit close hinzufügen oder ersetzen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "add",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn verdict_template_add(
+        ctx: Context<'_>,
+        #[description = "Der Name der Vorlage, z.B. Schuldig – Beleidigung §3"] name: String,
+        #[description = "Das Urteil, mit {accused}, {plaintiff}, {law} und {number} als Platzhalter"]
+        text: String,
+        #[description = "Das Gesetz, das für {law} eingesetzt wird"] law: Option<String>,
+    ) -> Result<()> {
+        court_verdict_template_add_impl(ctx, name, text, law)
+            .await
+            .wrap_err("court_verdict_template_add")
+    }
+
+    /// Eine Urteilsvorlage entfernen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "remove",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn verdict_template_remove(
+        ctx: Context<'_>,
+        #[description = "Der Name der Vorlage"]
+        #[autocomplete = "autocomplete_verdict_template"]
+        name: String,
+    ) -> Result<()> {
+        court_verdict_template_remove_impl(ctx, name)
+            .await
+            .wrap_err("court_verdict_template_remove")
+    }
+
+    /// Die Urteilsvorlagen anzeigen
+    #[poise::command(slash_command, guild_only, rename = "list")]
+    async fn verdict_template_list(ctx: Context<'_>) -> Result<()> {
+        court_verdict_template_list_impl(ctx)
+            .await
+            .wrap_err("court_verdict_template_list")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_verdict_template_add_impl(
+        ctx: Context<'_>,
+        name: String,
+        text: String,
+        law: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        // autocomplete choices and the verdict modal can't be longer
+        if name.chars().count() > 100 || text.chars().count() > 1000 {
+            ctx.say("de Name dörf höchstens 100 und s'Urteil höchstens 1000 Zeiche lang si")
+                .await?;
+            return Ok(());
+        }
+
+        let template = VerdictTemplate {
+            name: name.trim().to_string(),
+            text,
+            law,
+        };
+        guild_mongo(ctx)
+            .set_verdict_template(guild_id.into(), &template.name, Some(&template))
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_verdict_template_remove_impl(ctx: Context<'_>, name: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !state.verdict_templates.iter().any(|t| t.name == name) {
+            ctx.say(format!("es git kei Urteilsvorlag «{name}»"))
+                .await?;
+            return Ok(());
+        }
+        mongo
+            .set_verdict_template(guild_id.into(), &name, None)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_verdict_template_list_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        if state.verdict_templates.is_empty() {
+            ctx.say(
+                "es git kei Urteilsvorlage, füeg mit `/court verdict_template add` eini hinzue",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let templates = state
+            .verdict_templates
+            .iter()
+            .map(|template| match &template.law {
+                Some(law) => format!("**{}** ({law}): {}", template.name, template.text),
+                None => format!("**{}**: {}", template.name, template.text),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let placeholders = verdict_template::PLACEHOLDERS
+            .iter()
+            .map(|name| format!("`{{{name}}}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        ctx.say(format!("{templates}\n\nPlatzhalter: {placeholders}"))
+            .await?;
+
+        Ok(())
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("session_schedule", "session_list", "session_cancel")
+    )]
+    async fn session(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Einen wöchentlichen Gerichtstag planen, der am Morgen angekündigt wird
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "schedule",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn session_schedule(
+        ctx: Context<'_>,
+        #[description = "Der Wochentag"] weekday: Weekday,
+        #[description = "Der Beginn, z.B. 18:00"] time: String,
+    ) -> Result<()> {
+        court_session_schedule_impl(ctx, weekday, time)
+            .await
+            .wrap_err("court_session_schedule")
+    }
+
+    /// Die geplanten Gerichtstage anzeigen
+    #[poise::command(slash_command, guild_only, rename = "list")]
+    async fn session_list(ctx: Context<'_>) -> Result<()> {
+        court_session_list_impl(ctx)
+            .await
+            .wrap_err("court_session_list")
+    }
+
+    /// Einen geplanten Gerichtstag absagen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "cancel",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn session_cancel(
+        ctx: Context<'_>,
+        #[description = "Die Nummer des Gerichtstags"]
+        #[min = 1]
+        number: usize,
+    ) -> Result<()> {
+        court_session_cancel_impl(ctx, number)
+            .await
+            .wrap_err("court_session_cancel")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_session_schedule_impl(
+        ctx: Context<'_>,
+        weekday: Weekday,
+        time: String,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let time = match court_session::parse_time(&time) {
+            Some(time) => time,
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::InvalidTime,
+                    &[("example", "`18:00`".to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if state.announcement_channel.is_none() {
+            ctx.say("d'Gerichtstäg werded im Aakündigungskanal aakündigt, setz en zerst mit `/config set_announcement_channel`")
+                .await?;
+            return Ok(());
+        }
+
+        let session = CourtSession::new(weekday, time);
+        mongo.add_court_session(guild_id.into(), &session).await?;
+        court_session::schedule_next(
+            &mongo,
+            guild_id.into(),
+            &session,
+            state.timezone(),
+            mongodb::bson::DateTime::now(),
+        )
+        .await?;
+
+        ctx.say(format!(
+            "jede {weekday} um {} isch jetzt Gerichtstag, am Morge wird er aakündigt",
+            session.time
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_session_list_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        if state.court_sessions.is_empty() {
+            ctx.say("es sind kei Gerichtstäg planet, plan eine mit `/court session schedule`")
+                .await?;
+            return Ok(());
+        }
+
+        let sessions = state
+            .court_sessions
+            .iter()
+            .enumerate()
+            .map(|(index, session)| {
+                format!("{}. {} um {}", index + 1, session.weekday, session.time)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.say(sessions).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_session_cancel_impl(ctx: Context<'_>, number: usize) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let session = match number
+            .checked_sub(1)
+            .and_then(|index| state.court_sessions.get(index))
+        {
+            Some(session) => session,
+            None => {
+                ctx.say(format!("es git kein Gerichtstag {number}")).await?;
+                return Ok(());
+            }
+        };
+
+        mongo
+            .remove_court_session(guild_id.into(), session.id)
+            .await?;
+        let jobs = mongo.find_jobs(Some(guild_id.into())).await?;
+        for job in jobs {
+            if matches!(job.kind, JobKind::CourtSession { session_id } if session_id == session.id)
+            {
+                mongo.delete_job(job.id).await?;
+            }
+        }
+
+        ctx.say(format!(
+            "de Gerichtstag am {} um {} isch abgseit",
+            session.weekday, session.time
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Die Berechtigungen der Gerichtsräume und die Rollen der Beteiligten neu setzen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn sync_rooms(ctx: Context<'_>) -> Result<()> {
+        court_sync_rooms_impl(ctx)
+            .await
+            .wrap_err("court_sync_rooms")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_sync_rooms_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        if state.court_rooms.is_empty() {
+            ctx.say("es git no kei Gerichtsräum").await?;
+            return Ok(());
+        }
+
+        ctx.defer().await?;
+
+        let permissions = state.room_permissions.clone().unwrap_or_default();
+        let bot_id = ctx.discord().cache.current_user_id();
+        let mut ops = Vec::new();
+        for room in &state.court_rooms {
+            ops.extend(
+                permissions
+                    .overwrites(guild_id, room.role_id.into(), bot_id)
+                    .into_iter()
+                    .map(|overwrite| BulkOp::SetPermission {
+                        channel_id: room.channel_id.into(),
+                        overwrite,
+                    }),
+            );
+
+            let lawsuit = state.lawsuits.iter().find(|l| {
+                l.court_room == room.channel_id
+                    && l.status == LawsuitStatus::Active
+                    && !l.is_closed()
+            });
+            if let Some(lawsuit) = lawsuit {
+                ops.extend(
+                    lawsuit
+                        .participants()
+                        .into_iter()
+                        .map(|user| BulkOp::AddRole {
+                            user_id: user.into(),
+                            role_id: room.role_id.into(),
+                        }),
+                );
+            }
+        }
+
+        let report = bulk::run(&ctx.discord().http, guild_id, ops).await;
+
+        info!(failures = report.failures().count(), "Synced court rooms");
+
+        if report.is_success() {
+            ctx.say("alli Gerichtsräum sind wieder in Ordnig").await?;
+        } else {
+            ctx.say(report.to_string()).await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn court_set_docket_channel_impl(
+        ctx: Context<'_>,
+        channel: Option<Channel>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let docket = channel.map(|channel| Docket {
+            channel_id: channel.id().into(),
+            message_id: None,
+        });
+
+        mongo_client
+            .set_docket(guild_id.into(), docket.as_ref())
+            .await?;
+        docket::refresh(mongo_client, &ctx.discord().http, guild_id).await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+}
+
+pub mod prison {
+    use super::*;
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Gefängnis",
+        subcommands(
+            "set_role",
+            "create_role",
+            "arrest",
+            "release",
+            "leaderboard",
+            "warrant",
+            "visit",
+            "petition",
+            "voice",
+            "task",
+            "appeal"
+        )
+    )]
+    pub async fn prison(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Die Rolle für Gefangene setzen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_role(ctx: Context<'_>, #[description = "Die Rolle"] role: Role) -> Result<()> {
+        prison_set_role_impl(ctx, role)
+            .await
+            .wrap_err("prison_set_role")
+    }
+
+    /// Die Rolle für Gefangene erstellen und direkt unter der Rolle vom Bot einordnen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "MANAGE_GUILD",
+        required_bot_permissions = "MANAGE_ROLES"
+    )]
+    async fn create_role(
+        ctx: Context<'_>,
+        #[description = "Der Name der Rolle, standardmässig \"Gefangene\""] name: Option<String>,
+        #[description = "Die Farbe als Hex-Farbe, z.B. #808080"] color: Option<String>,
+        #[description = "Was die Rolle in allen Kanälen nicht darf, standardmässig Schreiben und Sprechen"]
+        restriction: Option<PrisonRestriction>,
+    ) -> Result<()> {
+        prison_create_role_impl(ctx, name, color, restriction)
+            .await
+            .wrap_err("prison_create_role")
+    }
+
+    /// Jemanden einsperren
+    #[poise::command(slash_command, guild_only, check = "warden_check")]
+    async fn arrest(
+        ctx: Context<'_>,
+        #[description = "Die Person zum einsperren"] user: User,
+        #[description = "Nach dieser Zeit freilassen, z.B. 2d 12h"] duration: Option<String>,
+        #[description = "Der Grund, steht in der Nachricht an den Verhafteten"] reason: Option<
+            String,
+        >,
+    ) -> Result<()> {
+        prison_arrest_impl(ctx, user, duration, reason)
+            .await
+            .wrap_err("prison_arrest")
+    }
+
+    /// Einen Gefangenen freilassen
+    #[poise::command(slash_command, guild_only, check = "warden_check")]
+    async fn release(
+        ctx: Context<'_>,
+        #[description = "Die Person zum freilassen"] user: User,
+    ) -> Result<()> {
+        prison_release_impl(ctx, user)
+            .await
+            .wrap_err("prison_release")
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("warrant_issue", "warrant_resolve")
+    )]
+    async fn warrant(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Eine Fahndung ausschreiben, die auch den verbundenen Servern gemeldet wird
+    #[poise::command(slash_command, guild_only, rename = "issue")]
+    async fn warrant_issue(
+        ctx: Context<'_>,
+        #[description = "Die gesuchte Person"] user: User,
+        #[description = "Der Grund für die Fahndung"] reason: String,
+    ) -> Result<()> {
+        prison_warrant_issue_impl(ctx, user, reason)
+            .await
+            .wrap_err("prison_warrant_issue")
+    }
+
+    /// Eine Fahndung aufheben und ihre Meldungen überall löschen
+    #[poise::command(slash_command, guild_only, rename = "resolve")]
+    async fn warrant_resolve(
+        ctx: Context<'_>,
+        #[description = "Die gesuchte Person"] user: User,
+    ) -> Result<()> {
+        prison_warrant_resolve_impl(ctx, user)
+            .await
+            .wrap_err("prison_warrant_resolve")
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("visit_request", "visit_configure")
+    )]
+    async fn visit(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Einen Besuch bei einem Gefangenen beantragen
+    #[poise::command(slash_command, guild_only, ephemeral, rename = "request")]
+    async fn visit_request(
+        ctx: Context<'_>,
+        #[description = "Der Gefangene"] prisoner: User,
+        #[description = "Wann der Besuch beginnt, z.B. 30.06.2022 18:00"] time: String,
+    ) -> Result<()> {
+        prison_visit_request_impl(ctx, prisoner, time)
+            .await
+            .wrap_err("prison_visit_request")
+    }
+
+    /// Den Kanal und die Dauer für Besuche festlegen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "configure",
+        check = "warden_check"
+    )]
+    async fn visit_configure(
+        ctx: Context<'_>,
+        #[description = "Der Kanal des Gefängnisses, leer lassen um auszuschalten"] channel: Option<
+            Channel,
+        >,
+        #[description = "Wie viele Minuten ein Besuch dauert, standardmässig 30"]
+        #[min = 5]
+        #[max = 1440]
+        minutes: Option<u32>,
+    ) -> Result<()> {
+        prison_visit_configure_impl(ctx, channel, minutes)
+            .await
+            .wrap_err("prison_visit_configure")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_visit_request_impl(
+        ctx: Context<'_>,
+        prisoner: User,
+        time: String,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let config = match &state.visitation {
+            Some(config) => config,
+            None => {
+                ctx.say("uf dem Server chönd Gfangeni nöd bsuecht werde")
+                    .await?;
+                return Ok(());
+            }
+        };
+        if !validate_references(ctx, &state, &[Reference::LogChannel]).await? {
+            return Ok(());
+        }
+        if mongo
+            .find_prison_entry(guild_id.into(), prisoner.id.into())
+            .await?
+            .is_none()
+        {
+            ctx.say(format!("<@{}> isch nöd im Gfängnis", prisoner.id))
+                .await?;
+            return Ok(());
+        }
+
+        let tz = state.timezone();
+        let start = match parse_date_time(&time, tz) {
+            Some(start)
+                if start.timestamp_millis() > mongodb::bson::DateTime::now().timestamp_millis() =>
+            {
+                start
+            }
+            Some(_) => {
+                ctx.say("de Bsuech muess i de Zuekunft sii").await?;
+                return Ok(());
+            }
+            None => {
+                say_message(
+                    ctx,
+                    MessageKey::InvalidTime,
+                    &[("example", "`30.06.2022 18:00`".to_string())],
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let visit = Visit {
+            id: mongodb::bson::Uuid::new(),
+            guild_id: guild_id.into(),
+            prisoner: prisoner.id.into(),
+            visitor: ctx.author().id.into(),
+            channel_id: config.channel_id,
+            start,
+            minutes: config.minutes,
+            status: VisitStatus::Requested,
+            decided_by: None,
+        };
+        let log_channel = state.log_channel.wrap_err("log channel not found")?;
+        visitation::request(mongo, &ctx.discord().http, log_channel, &visit, tz).await?;
+
+        ctx.say("de Bsuech isch beantragt, du bechunnsch e Nachricht sobald entschiede isch")
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_visit_configure_impl(
+        ctx: Context<'_>,
+        channel: Option<Channel>,
+        minutes: Option<u32>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let config = channel.map(|channel| VisitationConfig {
+            channel_id: channel.id().into(),
+            minutes: minutes.unwrap_or(visitation::DEFAULT_VISIT_MINUTES),
+        });
+        guild_mongo(ctx)
+            .set_visitation(guild_id.into(), config.as_ref())
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    /// Gefangene aus den Sprachkanälen aussperren
+    #[poise::command(slash_command, guild_only, check = "warden_check")]
+    async fn voice(
+        ctx: Context<'_>,
+        #[description = "Ob Gefangene den Sprachkanälen nicht beitreten können"] enabled: bool,
+        #[description = "Der einzige Sprachkanal, dem Gefangene beitreten können"] cell: Option<
+            Channel,
+        >,
+    ) -> Result<()> {
+        prison_voice_impl(ctx, enabled, cell)
+            .await
+            .wrap_err("prison_voice")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_voice_impl(
+        ctx: Context<'_>,
+        enabled: bool,
+        cell: Option<Channel>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let voice = enabled.then(|| PrisonVoice {
+            cell_channel: cell.map(|cell| cell.id().into()),
+        });
+        guild_mongo(ctx)
+            .set_prison_voice(guild_id.into(), voice.as_ref())
+            .await?;
+
+        ctx.say("gspeicheret, das gilt für alli wo ab jetzt verhaftet oder freiglah werded")
+            .await?;
+
+        Ok(())
+    }
+
+    /// Ein Gesuch oder eine Beschwerde an die Aufseher richten
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn petition(
+        ctx: Context<'_>,
+        #[description = "Was du den Aufsehern sagen willst"] text: String,
+    ) -> Result<()> {
+        prison_petition_impl(ctx, text)
+            .await
+            .wrap_err("prison_petition")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_petition_impl(ctx: Context<'_>, text: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+        let user_id = ctx.author().id.into();
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let warden_channel = match state.warden_channel {
+            Some(channel) => channel,
+            None => {
+                ctx.say("es git kein Kanal für Gsuech vo Gfangene").await?;
+                return Ok(());
+            }
+        };
+        if mongo
+            .find_prison_entry(guild_id.into(), user_id)
+            .await?
+            .is_none()
+        {
+            ctx.say("nur Gfangeni chönd es Gsuech stelle").await?;
+            return Ok(());
+        }
+        if text.chars().count() > petition::PETITION_LIMIT {
+            ctx.say(format!(
+                "es Gsuech dörf höchstens {} Zeiche lang sii",
+                petition::PETITION_LIMIT
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let now = mongodb::bson::DateTime::now();
+        let last = mongo.find_last_petition(guild_id.into(), user_id).await?;
+        if let Some(next) = petition::next_petition(last.as_ref(), now) {
+            ctx.say(format!(
+                "du chasch s'nächste Gsuech erst am {} stelle",
+                format_date_time(next, state.timezone())
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let petition = Petition {
+            id: mongodb::bson::Uuid::new(),
+            guild_id: guild_id.into(),
+            user_id,
+            text,
+            filed_at: now,
+            status: PetitionStatus::Pending,
+            decided_by: None,
+        };
+        petition::file(mongo, &ctx.discord().http, warden_channel, &petition).await?;
+
+        ctx.say("dis Gsuech isch a d'Ufseher gschickt worde, du bechunnsch d'Antwort per DM")
+            .await?;
+
+        Ok(())
+    }
+
+    /// Beschwerde gegen die eigene Verhaftung bei den Richtern einlegen
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn appeal(
+        ctx: Context<'_>,
+        #[description = "Warum die Verhaftung falsch ist"] statement: String,
+    ) -> Result<()> {
+        prison_appeal_impl(ctx, statement)
+            .await
+            .wrap_err("prison_appeal")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_appeal_impl(ctx: Context<'_>, statement: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+        let user_id = ctx.author().id.into();
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let judge_channel = match state.judge_channel {
+            Some(channel) => channel,
+            None => {
+                ctx.say("es git kein Kanal für Haftbeschwärde").await?;
+                return Ok(());
+            }
+        };
+        let entry = match mongo.find_prison_entry(guild_id.into(), user_id).await? {
+            Some(entry) => entry,
+            None => {
+                ctx.say("nur Gfangeni chönd e Haftbeschwärde iilegge")
+                    .await?;
+                return Ok(());
+            }
+        };
+        if statement.chars().count() > arrest_appeal::STATEMENT_LIMIT {
+            ctx.say(format!(
+                "e Haftbeschwärde dörf höchstens {} Zeiche lang sii",
+                arrest_appeal::STATEMENT_LIMIT
+            ))
+            .await?;
+            return Ok(());
+        }
+        if mongo
+            .find_arrest_appeal_for(guild_id.into(), user_id, entry.arrested_at)
+            .await?
+            .is_some()
+        {
+            ctx.say("gäge die Verhaftig häsch scho e Haftbeschwärde iiglegt")
+                .await?;
+            return Ok(());
+        }
+
+        let appeal = ArrestAppeal {
+            id: mongodb::bson::Uuid::new(),
+            guild_id: guild_id.into(),
+            user_id,
+            statement,
+            arrested_at: entry.arrested_at,
+            filed_at: mongodb::bson::DateTime::now(),
+            status: ArrestAppealStatus::Pending,
+            decided_by: None,
+        };
+        arrest_appeal::file(mongo, &ctx.discord().http, judge_channel, &appeal).await?;
+
+        ctx.say("dini Haftbeschwärde isch a d'Richter gschickt worde, du bechunnsch de Entscheid per DM")
+            .await?;
+
+        Ok(())
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("task_add", "task_list", "task_claim", "task_complete")
+    )]
+    async fn task(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Eine Arbeit ausschreiben, die die Strafe eines Gefangenen verkürzt
+    #[poise::command(slash_command, guild_only, rename = "add", check = "warden_check")]
+    async fn task_add(
+        ctx: Context<'_>,
+        #[description = "Was zu tun ist"] description: String,
+        #[description = "Um wie viele Stunden die Strafe verkürzt wird"]
+        #[min = 1]
+        #[max = 720]
+        hours_credit: u32,
+    ) -> Result<()> {
+        prison_task_add_impl(ctx, description, hours_credit)
+            .await
+            .wrap_err("prison_task_add")
+    }
+
+    /// Die ausgeschriebenen Arbeiten anzeigen
+    #[poise::command(slash_command, guild_only, rename = "list")]
+    async fn task_list(ctx: Context<'_>) -> Result<()> {
+        prison_task_list_impl(ctx)
+            .await
+            .wrap_err("prison_task_list")
+    }
+
+    /// Eine Arbeit übernehmen
+    #[poise::command(slash_command, guild_only, ephemeral, rename = "claim")]
+    async fn task_claim(
+        ctx: Context<'_>,
+        #[description = "Die Nummer der Arbeit"]
+        #[min = 1]
+        number: usize,
+    ) -> Result<()> {
+        prison_task_claim_impl(ctx, number)
+            .await
+            .wrap_err("prison_task_claim")
+    }
+
+    /// Die übernommene Arbeit den Aufsehern als erledigt melden
+    #[poise::command(slash_command, guild_only, ephemeral, rename = "complete")]
+    async fn task_complete(ctx: Context<'_>) -> Result<()> {
+        prison_task_complete_impl(ctx)
+            .await
+            .wrap_err("prison_task_complete")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_task_add_impl(
+        ctx: Context<'_>,
+        description: String,
+        hours_credit: u32,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if state.warden_channel.is_none() {
+            ctx.say("erledigti Arbeite werded im Kanal für d'Ufseher bestätigt, setz en zerst mit `/config set_warden_channel`")
+                .await?;
+            return Ok(());
+        }
+        if description.chars().count() > work_release::TASK_DESCRIPTION_LIMIT {
+            ctx.say(format!(
+                "d'Beschriibig dörf höchstens {} Zeiche lang sii",
+                work_release::TASK_DESCRIPTION_LIMIT
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let task = WorkTask {
+            id: mongodb::bson::Uuid::new(),
+            description,
+            hours_credit,
+            created_by: ctx.author().id.into(),
+            claimed_by: None,
+            status: WorkTaskStatus::Open,
+        };
+        mongo.add_work_task(guild_id.into(), &task).await?;
+
+        ctx.say(format!(
+            "d'Arbeit isch usgschriebe, sie verchürzt d'Strof um {hours_credit} Stunde"
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_task_list_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        if state.work_tasks.is_empty() {
+            ctx.say("es sind kei Arbeite usgschriebe").await?;
+            return Ok(());
+        }
+
+        let tasks = state
+            .work_tasks
+            .iter()
+            .enumerate()
+            .map(|(index, task)| {
+                let status = match (task.status, task.claimed_by) {
+                    (WorkTaskStatus::Open, _) | (_, None) => "frei".to_string(),
+                    (WorkTaskStatus::Claimed, Some(user)) => format!("übernoh vo <@{user}>"),
+                    (WorkTaskStatus::Submitted, Some(user)) => {
+                        format!("vo <@{user}> erledigt, wartet uf Bestätigung")
+                    }
+                };
+                format!(
+                    "{}. {} ({} Stunde), {status}",
+                    index + 1,
+                    task.description,
+                    task.hours_credit
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.say(tasks).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_task_claim_impl(ctx: Context<'_>, number: usize) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+        let user_id = ctx.author().id.into();
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if mongo
+            .find_prison_entry(guild_id.into(), user_id)
+            .await?
+            .is_none()
+        {
+            ctx.say("nur Gfangeni chönd Arbeite übernäh").await?;
+            return Ok(());
+        }
+        if crate::prison::release_job(&mongo, guild_id.into(), user_id)
+            .await?
+            .is_none()
+        {
+            ctx.say("du häsch kei Freilassigsziit wo chönnt verchürzt werde")
+                .await?;
+            return Ok(());
+        }
+        if state
+            .work_tasks
+            .iter()
+            .any(|task| task.claimed_by == Some(user_id))
+        {
+            ctx.say("du häsch scho e Arbeit, mach die zerst fertig")
+                .await?;
+            return Ok(());
+        }
+
+        let task = match number
+            .checked_sub(1)
+            .and_then(|index| state.work_tasks.get(index))
+        {
+            Some(task) => task,
+            None => {
+                ctx.say(format!("es git kei Arbeit {number}")).await?;
+                return Ok(());
+            }
+        };
+        if !mongo
+            .claim_work_task(guild_id.into(), task.id, user_id)
+            .await?
+        {
+            ctx.say("die Arbeit het scho öpper anders übernoh").await?;
+            return Ok(());
+        }
+
+        ctx.say(format!(
+            "du häsch d'Arbeit übernoh, meld sie mit `/prison task complete` wenn sie erledigt isch:\n> {}",
+            task.description
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_task_complete_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+        let user_id = ctx.author().id.into();
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let task =
+            match state.work_tasks.iter().find(|task| {
+                task.claimed_by == Some(user_id) && task.status == WorkTaskStatus::Claimed
+            }) {
+                Some(task) => task,
+                None => {
+                    ctx.say("du häsch kei Arbeit übernoh, lueg mit `/prison task list` was es git")
+                        .await?;
+                    return Ok(());
+                }
+            };
+        let warden_channel = match state.warden_channel {
+            Some(channel) => channel,
+            None => {
+                ctx.say("es git kein Kanal für d'Ufseher").await?;
+                return Ok(());
+            }
+        };
+
+        if !mongo
+            .set_work_task_status(
+                guild_id.into(),
+                task.id,
+                WorkTaskStatus::Claimed,
+                WorkTaskStatus::Submitted,
+            )
+            .await?
+        {
+            ctx.say("d'Arbeit isch scho gmeldet").await?;
+            return Ok(());
+        }
+        work_release::submit(&ctx.discord().http, warden_channel, task, user_id).await?;
+
+        ctx.say("d'Arbeit isch de Ufseher gmeldet, du bechunnsch d'Antwort per DM")
+            .await?;
+
+        Ok(())
+    }
+
+    /// Whether the author is a judge of the guild. Admins count as judges.
+    async fn is_judge(ctx: Context<'_>, state: &State) -> Result<bool> {
+        if is_admin(ctx) {
+            return Ok(true);
+        }
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let judge_role = match state.judge_role_for(None) {
+            Some(judge_role) => RoleId::from(judge_role),
+            None => return Ok(false),
+        };
+        let member = guild_id
+            .member(ctx.discord(), ctx.author().id)
+            .await
+            .wrap_err("fetch member")?;
+        Ok(member.roles.contains(&judge_role))
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_warrant_issue_impl(ctx: Context<'_>, user: User, reason: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !is_judge(ctx, &state).await? {
+            ctx.say("nur Richter chönd Fahndige usschriebe").await?;
+            return Ok(());
+        }
+        if mongo
+            .find_warrant(guild_id.into(), user.id.into())
+            .await?
+            .is_some()
+        {
+            ctx.say(format!("noch <@{}> wird scho gfahndet", user.id))
+                .await?;
+            return Ok(());
+        }
+
+        let partners = federation::partners(&ctx.data().mongo, &state).await?;
+        let channels = std::iter::once(&state)
+            .chain(&partners)
+            .filter_map(|state| state.log_channel)
+            .collect::<Vec<_>>();
+        let text = warrant::notice_text(user.id.into(), &reason, &guild_name(ctx, state.guild_id));
+        let notices = warrant::post(&ctx.discord().http, &text, &channels).await;
+
+        let warrant = Warrant {
+            guild_id: guild_id.into(),
+            user_id: user.id.into(),
+            reason,
+            issued_by: ctx.author().id.into(),
+            issued_at: mongodb::bson::DateTime::now(),
+            notices,
+        };
+        mongo.add_warrant(&warrant).await?;
+        info!(user_id = %user.id, "Issued warrant");
+
+        ctx.say(format!(
+            "d'Fahndig noch <@{}> isch i {} Kanäl gmeldet worde",
+            user.id,
+            warrant.notices.len()
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_warrant_resolve_impl(ctx: Context<'_>, user: User) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !is_judge(ctx, &state).await? {
+            ctx.say("nur Richter chönd Fahndige ufhebe").await?;
+            return Ok(());
+        }
+
+        match mongo
+            .remove_warrant(guild_id.into(), user.id.into())
+            .await?
+        {
+            Some(warrant) => {
+                warrant::clear(&ctx.discord().http, &warrant).await;
+                info!(user_id = %user.id, "Resolved warrant");
+                ctx.say(format!("d'Fahndig noch <@{}> isch ufghobe", user.id))
+                    .await?;
+            }
+            None => {
+                ctx.say(format!("noch <@{}> wird nöd gfahndet", user.id))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_set_role_impl(ctx: Context<'_>, role: Role) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        guild_mongo(ctx)
+            .set_prison_role(guild_id.into(), role.id.into())
+            .await?;
+
+        let bot_position = crate::prison::highest_role_position(
+            &ctx.discord().http,
+            guild_id,
+            ctx.discord().cache.current_user_id(),
+        )
+        .await?;
+        if crate::prison::is_out_of_reach(role.position, bot_position) {
+            ctx.say(format!(
+                "gspeicheret, aber <@&{}> isch nöd under de höchste Rolle vom Bot, so chan ich \
+                 niemer iisperre. Bitte verschieb d'Rolle drunter",
+                role.id
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_create_role_impl(
+        ctx: Context<'_>,
+        name: Option<String>,
+        color: Option<String>,
+        restriction: Option<PrisonRestriction>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let color = match color.as_deref().map(branding::parse_color) {
+            Some(Some(color)) => Some(color),
+            Some(None) => {
+                ctx.say("d'Farb muess e Hex-Farb wie #808080 sii").await?;
+                return Ok(());
+            }
+            None => None,
+        };
+
+        ctx.defer().await?;
+
+        let (role_id, report) = crate::prison::create_role(
+            &guild_mongo(ctx),
+            &ctx.discord().http,
+            guild_id,
+            ctx.discord().cache.current_user_id(),
+            name.unwrap_or_else(|| "Gefangene".to_string()),
+            color,
+            restriction.unwrap_or(PrisonRestriction::Full),
+        )
+        .await?;
+
+        if report.is_success() {
+            ctx.say(format!("d'Gfängnisrolle <@&{role_id}> isch erstellt"))
+                .await?;
+        } else {
+            ctx.say(format!(
+                "d'Gfängnisrolle <@&{role_id}> isch erstellt, aber nöd alli Kanäl händ klappt: {report}"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_arrest_impl(
+        ctx: Context<'_>,
+        user: User,
+        duration: Option<String>,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let duration = match duration.as_deref().map(parse_duration) {
+            Some(Some(duration)) => Some(duration),
+            Some(None) => {
+                ctx.say("das isch kei gültigi dauer, probier öppis wie `2d 12h`")
+                    .await?;
+                return Ok(());
+            }
+            None => None,
+        };
+
+        let mongo = &guild_mongo(ctx);
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        if !validate_references(ctx, &state, &[Reference::PrisonRole, Reference::LogChannel])
+            .await?
+        {
+            return Ok(());
+        }
+
+        let result = crate::prison::arrest(mongo, &ctx.discord().http, guild_id, user.id).await?;
+
+        match result {
+            Ok(()) => {
+                if let Some(duration) = duration {
+                    let due = mongodb::bson::DateTime::from_millis(
+                        mongodb::bson::DateTime::now().timestamp_millis()
+                            + duration.as_millis() as i64,
+                    );
+                    let job = JobKind::Release {
+                        user_id: user.id.into(),
+                    };
+                    scheduler::schedule(mongo, guild_id.into(), job, due).await?;
+                }
+                let args = [("user", format!("<@{}>", user.id))];
+                say_message(ctx, MessageKey::Arrested, &args).await?;
+                crate::prison::notify_arrest(
+                    mongo,
+                    &ctx.discord().http,
+                    guild_id.into(),
+                    user.id.into(),
+                    reason.as_deref(),
+                    duration,
+                    None,
+                )
+                .await?;
+                offer_extradition(ctx, &state, &user).await?;
+            }
+            Err(response) => {
+                ctx.say(response.to_string()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Offers to arrest the user in the guilds that share their prison records as well.
+    async fn offer_extradition(ctx: Context<'_>, state: &State, user: &User) -> Result<()> {
+        let mongo = &ctx.data().mongo;
+        let partners = federation::partners(mongo, state).await?;
+        if partners.is_empty() {
+            return Ok(());
+        }
+
+        let names = partners
+            .iter()
+            .map(|partner| guild_name(ctx, partner.guild_id))
+            .collect::<Vec<_>>();
+        let question = format!(
+            "söll <@{}> au uf {} verhaftet werde?",
+            user.id,
+            names.join(", ")
+        );
+        if !confirm(ctx, question).await? {
+            return Ok(());
+        }
+
+        let from = guild_name(ctx, state.guild_id);
+        let mut lines = vec![];
+        for (partner, name) in partners.iter().zip(&names) {
+            let result =
+                federation::extradite(mongo, &ctx.discord().http, &from, partner, user.id).await?;
+            lines.push(match result {
+                Ok(()) => format!("{name}: verhaftet"),
+                Err(response) => format!("{name}: {response}"),
+            });
+        }
+        ctx.say(lines.join("\n")).await?;
+
+        Ok(())
+    }
+
+    /// Die Gefangenen mit der längsten Haft oder den meisten Verhaftungen anzeigen
+    #[poise::command(slash_command, guild_only)]
+    async fn leaderboard(
+        ctx: Context<'_>,
+        #[description = "Wonach sortiert wird, standardmässig die Haft"] ranking: Option<
+            PrisonRanking,
+        >,
+    ) -> Result<()> {
+        prison_leaderboard_impl(ctx, ranking.unwrap_or(PrisonRanking::TimeServed))
+            .await
+            .wrap_err("prison_leaderboard")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_leaderboard_impl(ctx: Context<'_>, ranking: PrisonRanking) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let records = mongo_client
+            .prison_leaderboard(
+                guild_id.into(),
+                ranking,
+                &state.leaderboard_opt_outs,
+                LEADERBOARD_SIZE as i64,
+            )
+            .await?;
+
+        send_leaderboard(
+            ctx,
+            "Gfängnisrangliste".to_string(),
+            records.iter().map(ToString::to_string),
+            "es isch no niemert us em Gfängnis entlah worde",
+        )
+        .await
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn prison_release_impl(ctx: Context<'_>, user: User) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+        if !validate_references(ctx, &state, &[Reference::PrisonRole, Reference::LogChannel])
+            .await?
+        {
+            return Ok(());
+        }
+
+        let result =
+            crate::prison::release(&guild_mongo(ctx), &ctx.discord().http, guild_id, user.id)
+                .await?;
+
+        match result {
+            Ok(()) => {
+                let args = [("user", format!("<@{}>", user.id))];
+                say_message(ctx, MessageKey::Released, &args).await?;
+            }
+            Err(response) => {
+                ctx.say(response.to_string()).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub mod economy {
+    use std::time::Duration;
+
+    use mongodb::bson;
+
+    use super::*;
+    use crate::economy::{start_garnishment, treasury_account, Debt, DebtorRole, Salary, Tax};
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Wirtschaft",
+        subcommands(
+            "balance",
+            "history",
+            "grant",
+            "fine",
+            "treasury",
+            "set_garnishment",
+            "set_compensation",
+            "set_tax",
+            "set_debtor_role",
+            "salary"
+        )
+    )]
+    pub async fn economy(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("salary_set", "salary_remove", "salary_list")
+    )]
+    async fn salary(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Einen regelmässigen Lohn aus der Staatskasse für eine Rolle festlegen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "set",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn salary_set(
+        ctx: Context<'_>,
+        #[description = "Die Rolle"] role: Role,
+        #[description = "Der Betrag pro Auszahlung"]
+        #[min = 1]
+        amount: i64,
+        #[description = "Wie oft der Lohn ausgezahlt wird, zum Beispiel 1d oder 1w"]
+        interval: String,
+    ) -> Result<()> {
+        economy_salary_set_impl(ctx, role, amount, interval)
+            .await
+            .wrap_err("economy_salary_set")
+    }
+
+    /// Den Lohn einer Rolle entfernen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "remove",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn salary_remove(
+        ctx: Context<'_>,
+        #[description = "Die Rolle"] role: Role,
+    ) -> Result<()> {
+        economy_salary_remove_impl(ctx, role)
+            .await
+            .wrap_err("economy_salary_remove")
+    }
+
+    /// Alle Löhne anzeigen
+    #[poise::command(slash_command, guild_only, rename = "list")]
+    async fn salary_list(ctx: Context<'_>) -> Result<()> {
+        economy_salary_list_impl(ctx)
+            .await
+            .wrap_err("economy_salary_list")
+    }
+
+    /// Den Kontostand anzeigen
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn balance(
+        ctx: Context<'_>,
+        #[description = "Die Person, standardmässig du selbst"] user: Option<User>,
+    ) -> Result<()> {
+        economy_balance_impl(ctx, user)
+            .await
+            .wrap_err("economy_balance")
+    }
+
+    /// Die letzten Buchungen anzeigen
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn history(
+        ctx: Context<'_>,
+        #[description = "Die Person, standardmässig du selbst"] user: Option<User>,
+    ) -> Result<()> {
+        economy_history_impl(ctx, user)
+            .await
+            .wrap_err("economy_history")
+    }
+
+    /// Jemandem Geld gutschreiben
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn grant(
+        ctx: Context<'_>,
+        #[description = "Die Person"] user: User,
+        #[description = "Der Betrag"]
+        #[min = 1]
+        amount: i64,
+        #[description = "Der Grund"] reason: Option<String>,
+    ) -> Result<()> {
+        economy_grant_impl(ctx, user, amount, reason)
+            .await
+            .wrap_err("economy_grant")
+    }
+
+    /// Jemandem eine Busse geben, die in die Staatskasse fliesst
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn fine(
+        ctx: Context<'_>,
+        #[description = "Die Person"] user: User,
+        #[description = "Der Betrag"]
+        #[min = 1]
+        amount: i64,
+        #[description = "Der Grund"] reason: String,
+    ) -> Result<()> {
+        economy_fine_impl(ctx, user, amount, reason)
+            .await
+            .wrap_err("economy_fine")
+    }
+
+    /// Den Stand der Staatskasse anzeigen
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn treasury(ctx: Context<'_>) -> Result<()> {
+        economy_treasury_impl(ctx)
+            .await
+            .wrap_err("economy_treasury")
+    }
+
+    /// Eine regelmässige Steuer auf alle Guthaben festlegen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_tax(
+        ctx: Context<'_>,
+        #[description = "Der Steuersatz in Prozent, 0 um die Steuer auszuschalten"]
+        #[min = 0]
+        #[max = 100]
+        percent: u8,
+        #[description = "Wie oft die Steuer erhoben wird, zum Beispiel 1w"] interval: Option<
+            String,
+        >,
+    ) -> Result<()> {
+        economy_set_tax_impl(ctx, percent, interval)
+            .await
+            .wrap_err("economy_set_tax")
+    }
+
+    /// Den Anteil des Einkommens setzen, der für Schulden gepfändet wird
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_garnishment(
+        ctx: Context<'_>,
+        #[description = "Der Anteil in Prozent, 0 um die Lohnpfändung auszuschalten"]
+        #[min = 0]
+        #[max = 100]
+        percent: u8,
+    ) -> Result<()> {
+        economy_set_garnishment_impl(ctx, percent)
+            .await
+            .wrap_err("economy_set_garnishment")
+    }
+
+    /// Setzen, wie viel die Staatskasse pro Stunde ungerechtfertigter Haft bezahlt
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_compensation(
+        ctx: Context<'_>,
+        #[description = "Der Betrag pro Stunde, 0 um die Entschädigung auszuschalten"]
+        #[min = 0]
+        #[max = 1000000]
+        rate: i64,
+    ) -> Result<()> {
+        economy_set_compensation_impl(ctx, rate)
+            .await
+            .wrap_err("economy_set_compensation")
+    }
+
+    /// Eine Rolle setzen, die Leute mit hohen Schulden beim Wiederbeitritt bekommen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_debtor_role(
+        ctx: Context<'_>,
+        #[description = "Ab welchen Schulden, 0 um auszuschalten"]
+        #[min = 0]
+        threshold: i64,
+        #[description = "Die Rolle, sonst gibt es nur eine Erinnerung"] role: Option<Role>,
+    ) -> Result<()> {
+        economy_set_debtor_role_impl(ctx, threshold, role)
+            .await
+            .wrap_err("economy_set_debtor_role")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_balance_impl(ctx: Context<'_>, user: Option<User>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let user = user.unwrap_or_else(|| ctx.author().clone());
+
+        let mongo = &guild_mongo(ctx);
+        let account = mongo.find_account(guild_id.into(), user.id.into()).await?;
+        let debts = mongo.find_debts(guild_id.into(), user.id.into()).await?;
+        let debt = debts.iter().map(|debt| debt.amount).sum::<i64>();
+
+        let mut message = format!("<@{}> hät {}", user.id, format_amount(account.balance));
+        if debt > 0 {
+            message.push_str(&format!(" und {} Schulde", format_amount(debt)));
+        }
+
+        ctx.say(message).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_history_impl(ctx: Context<'_>, user: Option<User>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let user = user.unwrap_or_else(|| ctx.author().clone());
+
+        let transactions = guild_mongo(ctx)
+            .find_transactions(guild_id.into(), user.id.into(), 10)
+            .await?;
+
+        if transactions.is_empty() {
+            ctx.say("da häts no kei buchige").await?;
+            return Ok(());
+        }
+
+        let lines = transactions
+            .iter()
+            .map(format_transaction)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("Buchungen vo {}", user.name))
+                    .description(lines)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_grant_impl(
+        ctx: Context<'_>,
+        user: User,
+        amount: i64,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let balance = guild_mongo(ctx)
+            .credit(
+                guild_id.into(),
+                user.id.into(),
+                amount,
+                TransactionKind::Grant,
+                reason.as_deref().unwrap_or("Gutschrift"),
+            )
+            .await?;
+
+        ctx.say(format!(
+            "isch überwiese, <@{}> hät jetzt {}",
+            user.id,
+            format_amount(balance)
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_salary_set_impl(
+        ctx: Context<'_>,
+        role: Role,
+        amount: i64,
+        interval: String,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let interval = match parse_duration(&interval) {
+            Some(interval) => interval,
+            None => {
+                ctx.say("das isch kei gültigi duur, probier öppis wie `1d` oder `1w`")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let salary = Salary {
+            role_id: role.id.into(),
+            amount,
+            interval_secs: interval.as_secs() as i64,
+            last_paid: bson::DateTime::now(),
+        };
+
+        guild_mongo(ctx)
+            .set_salary(guild_id.into(), &salary)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_salary_remove_impl(ctx: Context<'_>, role: Role) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .remove_salary(guild_id.into(), role.id.into())
+            .await?;
+        ctx.say("isch weg").await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_salary_list_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        if state.salaries.is_empty() {
+            ctx.say("es git no kei Löhn").await?;
+            return Ok(());
+        }
+
+        let lines = state
+            .salaries
+            .iter()
+            .map(|salary| {
+                format!(
+                    "<@&{}>: {} all {}",
+                    salary.role_id,
+                    format_amount(salary.amount),
+                    format_duration(Duration::from_secs(salary.interval_secs as u64))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title("Löhn")
+                    .description(lines)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_fine_impl(
+        ctx: Context<'_>,
+        user: User,
+        amount: i64,
+        reason: String,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?.into();
+        let mongo = &guild_mongo(ctx);
+        let treasury = treasury_account(guild_id);
+
+        let paid = mongo
+            .debit_up_to(
+                guild_id,
+                user.id.into(),
+                amount,
+                TransactionKind::Fine,
+                &reason,
+            )
+            .await?;
+
+        if paid > 0 {
+            mongo
+                .credit(guild_id, treasury, paid, TransactionKind::Fine, &reason)
+                .await?;
+        }
+
+        let debt = if paid < amount {
+            let debt = Debt {
+                id: Uuid::new(),
+                guild_id,
+                debtor: user.id.into(),
+                creditor: treasury,
+                amount: amount - paid,
+                reason,
+                lawsuit_id: None,
+                created_at: bson::DateTime::now(),
+                garnished_until: None,
+            };
+            mongo.add_debt(&debt).await?;
+            start_garnishment(mongo, guild_id, debt.debtor).await?;
+            Some(debt)
+        } else {
+            None
+        };
+
+        let fine = RecordedFine {
+            id: Uuid::new(),
+            user_id: user.id.into(),
+            amount,
+            debt_id: debt.as_ref().map(|debt| debt.id),
+        };
+        undo::record_fine(mongo, guild_id, ctx.author().id.into(), &fine).await?;
+
+        match debt {
+            Some(debt) => {
+                ctx.say(format!(
+                    "<@{}> hät {} zahlt und schuldet de Staatskasse no {}",
+                    user.id,
+                    format_amount(paid),
+                    format_amount(debt.amount)
+                ))
+                .await?;
+            }
+            None => {
+                ctx.say(format!(
+                    "<@{}> hät d'Busse vo {} zahlt",
+                    user.id,
+                    format_amount(amount)
+                ))
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_treasury_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?.into();
+        let mongo = &guild_mongo(ctx);
+        let treasury = treasury_account(guild_id);
+
+        let account = mongo.find_account(guild_id, treasury).await?;
+        let transactions = mongo.find_transactions(guild_id, treasury, 10).await?;
+
+        let lines = if transactions.is_empty() {
+            "no kei buchige".to_string()
+        } else {
+            transactions
+                .iter()
+                .map(format_transaction)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title("Staatskasse")
+                    .field("Stand", format_amount(account.balance), false)
+                    .field("Letzti Buchige", lines, false)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_set_tax_impl(
+        ctx: Context<'_>,
+        percent: u8,
+        interval: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let tax = if percent > 0 {
+            let interval = match interval.as_deref().and_then(parse_duration) {
+                Some(interval) => interval,
+                None => {
+                    ctx.say("du muesch e gültigi duur ahgeh, probier öppis wie `1w`")
+                        .await?;
+                    return Ok(());
+                }
+            };
+
+            Some(Tax {
+                percent,
+                interval_secs: interval.as_secs() as i64,
+                last_collected: bson::DateTime::now(),
+            })
+        } else {
+            None
+        };
+
+        guild_mongo(ctx)
+            .set_tax(guild_id.into(), tax.as_ref())
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_set_debtor_role_impl(
+        ctx: Context<'_>,
+        threshold: i64,
+        role: Option<Role>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let debtor_role = if threshold > 0 {
+            Some(DebtorRole {
+                threshold,
+                role_id: role.map(|role| role.id.into()),
+            })
+        } else {
+            None
+        };
+        guild_mongo(ctx)
+            .set_debtor_role(guild_id.into(), debtor_role.as_ref())
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_set_compensation_impl(ctx: Context<'_>, rate: i64) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let rate = if rate > 0 { Some(rate) } else { None };
+        guild_mongo(ctx)
+            .set_compensation_rate(guild_id.into(), rate)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn economy_set_garnishment_impl(ctx: Context<'_>, percent: u8) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let percent = if percent > 0 { Some(percent) } else { None };
+        guild_mongo(ctx)
+            .set_garnishment_percent(guild_id.into(), percent)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+}
+
+pub mod debt {
+    use super::*;
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Wirtschaft",
+        subcommands("status")
+    )]
+    pub async fn debt(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Die offenen Schulden von jemandem anzeigen
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn status(
+        ctx: Context<'_>,
+        #[description = "Die Person, standardmässig du selbst"] user: Option<User>,
+    ) -> Result<()> {
+        debt_status_impl(ctx, user).await.wrap_err("debt_status")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn debt_status_impl(ctx: Context<'_>, user: Option<User>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let user = user.unwrap_or_else(|| ctx.author().clone());
+
+        let debts = guild_mongo(ctx)
+            .find_debts(guild_id.into(), user.id.into())
+            .await?;
+
+        if debts.is_empty() {
+            ctx.say(format!("<@{}> hät kei Schulde", user.id)).await?;
+            return Ok(());
+        }
+
+        let total = debts.iter().map(|debt| debt.amount).sum::<i64>();
+        let lines = debts
+            .iter()
+            .map(|debt| {
+                format!(
+                    "<t:{}:d> `{}` a <@{}> – {}",
+                    debt.created_at.timestamp_millis() / 1000,
+                    format_amount(debt.amount),
+                    debt.creditor,
+                    debt.reason
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("Schulde vo {}", user.name))
+                    .description(lines)
+                    .field("Total", format_amount(total), false)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub mod record {
+    use super::*;
+
+    /// The maximum amount of entries shown per section.
+    const RECORD_LIMIT: usize = 10;
+
+    /// Das Strafregister von jemandem anzeigen
+    #[poise::command(slash_command, guild_only, ephemeral, category = "Gericht")]
+    pub async fn record(
+        ctx: Context<'_>,
+        #[description = "Die Person, sonst du selbst"] user: Option<User>,
+    ) -> Result<()> {
+        record_impl(ctx, user).await.wrap_err("record")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn record_impl(ctx: Context<'_>, user: Option<User>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let user = user.unwrap_or_else(|| ctx.author().clone());
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let (author_id, admin) = (ctx.author().id.into(), is_admin(ctx));
+        let lawsuits = state
+            .lawsuits
+            .iter()
+            .rev()
+            .filter(|l| l.accused == user.id.into() && l.is_visible_to(author_id, admin))
+            .take(RECORD_LIMIT)
+            .map(|l| match &l.verdict {
+                Some(verdict) => format!("{}\nUrteil: {verdict}", l.summary()),
+                None => l.summary(),
+            })
+            .collect::<Vec<_>>();
+        let in_prison = mongo_client
+            .find_prison_entry(guild_id.into(), user.id.into())
+            .await?
+            .is_some();
+        let wrongful = mongo_client
+            .count_wrongful_prison_stays(guild_id.into(), user.id.into())
+            .await?;
+        let moderation = mongo_client
+            .find_moderation_actions(guild_id.into(), user.id.into(), None)
+            .await?
+            .iter()
+            .take(RECORD_LIMIT)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        let mut federated = vec![];
+        for partner in federation::partners(&ctx.data().mongo, &state).await? {
+            let partner_mongo = ctx.data().mongo.for_guild(partner.guild_id.into());
+            let in_prison = partner_mongo
+                .find_prison_entry(partner.guild_id, user.id.into())
+                .await?
+                .is_some();
+            let stays = partner_mongo
+                .count_prison_stays(partner.guild_id, user.id.into())
+                .await?;
+            federated.extend(federation::history(
+                &guild_name(ctx, partner.guild_id),
+                user.id.into(),
+                in_prison,
+                stays,
+                &partner.lawsuits,
+            ));
+        }
+
+        let mut sections = vec![];
+        if in_prison {
+            sections.push("**Sitzt im Gfängnis**".to_string());
+        }
+        if wrongful > 0 {
+            sections.push(format!(
+                "**Z'Unrecht igsperrt**\n{wrongful} mal, d'Verhaftig isch uf Beschwärde ufghobe worde"
+            ));
+        }
+        if !lawsuits.is_empty() {
+            sections.push(format!("**Prozess**\n{}", lawsuits.join("\n")));
+        }
+        if !moderation.is_empty() {
+            sections.push(format!("**Moderation**\n{}", moderation.join("\n")));
+        }
+        if !federated.is_empty() {
+            sections.push(format!("**Anderi Server**\n{}", federated.join("\n")));
+        }
+        if sections.is_empty() {
+            ctx.say(format!("<@{}> hät es suubers Strafregister", user.id))
+                .await?;
+            return Ok(());
+        }
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("Strafregister vo {}", user.name))
+                    .description(sections.join("\n\n"))
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub mod lawyer {
+    use super::*;
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Gericht",
+        subcommands("leaderboard")
+    )]
+    pub async fn lawyer(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Die Anwälte mit den meisten gewonnenen Prozessen anzeigen
+    #[poise::command(slash_command, guild_only)]
+    async fn leaderboard(
+        ctx: Context<'_>,
+        #[description = "Welche Prozesse zählen, standardmässig alle"] period: Option<Period>,
+    ) -> Result<()> {
+        lawyer_leaderboard_impl(ctx, period.unwrap_or(Period::AllTime))
+            .await
+            .wrap_err("lawyer_leaderboard")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn lawyer_leaderboard_impl(ctx: Context<'_>, period: Period) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        let (user_id, admin) = (ctx.author().id.into(), is_admin(ctx));
+        let (tz, now) = (state.timezone(), mongodb::bson::DateTime::now());
+        let records = leaderboard::lawyer_records(
+            state
+                .lawsuits
+                .iter()
+                .filter(|l| l.is_closed() && l.is_visible_to(user_id, admin))
+                .filter(|l| period.contains(tz, now, l.closed_at)),
+        );
+
+        send_leaderboard(
+            ctx,
+            format!("Bescht Anwält {period}"),
+            records
+                .iter()
+                .filter(|record| !state.leaderboard_opt_outs.contains(&record.lawyer))
+                .map(ToString::to_string),
+            &format!("{period} hät no kein Anwalt en Prozess abgschlosse"),
+        )
+        .await
+    }
+}
+
+pub mod service {
+    use super::*;
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Gefängnis",
+        subcommands("log", "status")
+    )]
+    pub async fn service(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Geleistete Stunden gemeinnütziger Arbeit eintragen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn log(
+        ctx: Context<'_>,
+        #[description = "Die Person"] user: User,
+        #[description = "Die geleisteten Stunden"]
+        #[min = 1]
+        hours: u32,
+    ) -> Result<()> {
+        service_log_impl(ctx, user, hours)
+            .await
+            .wrap_err("service_log")
+    }
+
+    /// Die offene gemeinnützige Arbeit von jemandem anzeigen
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn status(
+        ctx: Context<'_>,
+        #[description = "Die Person, standardmässig du selbst"] user: Option<User>,
+    ) -> Result<()> {
+        service_status_impl(ctx, user)
+            .await
+            .wrap_err("service_status")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn service_log_impl(ctx: Context<'_>, user: User, hours: u32) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+
+        let obligations = mongo
+            .find_service_obligations(guild_id.into(), user.id.into())
+            .await?;
+
+        if obligations.is_empty() {
+            ctx.say(format!(
+                "<@{}> muess kei gemeinnützigi Arbeit leiste",
+                user.id
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        // the hours go towards the obligation with the earliest deadline first
+        let mut remaining = hours;
+        for obligation in &obligations {
+            if remaining == 0 {
+                break;
+            }
+            let open = obligation.hours_required - obligation.hours_done;
+            let logged = open.min(remaining);
+            remaining -= logged;
+
+            if logged == open {
+                mongo.delete_service_obligation(obligation.id).await?;
+            } else {
+                mongo
+                    .set_service_hours_done(obligation.id, obligation.hours_done + logged)
+                    .await?;
+            }
+        }
+
+        let open = obligations
+            .iter()
+            .map(|obligation| obligation.hours_required - obligation.hours_done)
+            .sum::<u32>()
+            .saturating_sub(hours);
+
+        if open == 0 {
+            ctx.say(format!(
+                "<@{}> hät all gemeinnützigi Arbeit erledigt",
+                user.id
+            ))
+            .await?;
+        } else {
+            ctx.say(format!(
+                "isch itreit, <@{}> muess no {open} Stunde leiste",
+                user.id
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn service_status_impl(ctx: Context<'_>, user: Option<User>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let user = user.unwrap_or_else(|| ctx.author().clone());
+        let mongo_client = &guild_mongo(ctx);
+
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+        let obligations = mongo_client
+            .find_service_obligations(guild_id.into(), user.id.into())
+            .await?;
+
+        if obligations.is_empty() {
+            ctx.say(format!(
+                "<@{}> muess kei gemeinnützigi Arbeit leiste",
+                user.id
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let lines = obligations
+            .iter()
+            .map(|obligation| {
+                format!(
+                    "{}/{} Stunde bis {} – {}",
+                    obligation.hours_done,
+                    obligation.hours_required,
+                    format_date_time(obligation.deadline, state.timezone()),
+                    obligation.reason
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let branding = guild_branding(ctx).await?;
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("Gemeinnützigi Arbeit vo {}", user.name))
+                    .description(lines)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub mod arbitrate {
+    use super::*;
+
+    /// Einen kleinen Streit ohne Gerichtsraum von einem Schiedsrichter entscheiden lassen
+    #[poise::command(slash_command, guild_only, category = "Gericht")]
+    pub async fn arbitrate(
+        ctx: Context<'_>,
+        #[description = "Die andere Partei"] other_party: User,
+        #[description = "Worum es geht"] issue: String,
+    ) -> Result<()> {
+        arbitrate_impl(ctx, other_party, issue)
+            .await
+            .wrap_err("arbitrate")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn arbitrate_impl(ctx: Context<'_>, other_party: User, issue: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let plaintiff = ctx.author().id;
+
+        // the modal has to be the first response
+        let statement = match execute_modal::<StatementModal>(ctx, None).await? {
+            Some(statement) => statement,
+            None => return Ok(()),
+        };
+
+        if other_party.id == plaintiff {
+            ctx.say("du chasch nöd mit dir selber stritte").await?;
+            return Ok(());
+        }
+
+        let mongo_client = &guild_mongo(ctx);
+        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
+
+        let judge_role = match state.judge_role_for(None) {
+            Some(role) => role,
+            None => {
+                say_message(ctx, MessageKey::NoJudgeRole, &[]).await?;
+                return Ok(());
+            }
+        };
+
+        let mut lawsuit = Lawsuit {
+            arbitration: true,
+            statements: vec![Statement {
+                party: Party::Plaintiff,
+                text: statement.statement,
+            }],
+            ..Lawsuit::new(
+                plaintiff.into(),
+                other_party.id.into(),
+                SnowflakeId(0),
+                issue,
+            )
+        };
+
+        let arbiter = pick_judge(
+            &ctx.discord().http,
+            guild_id,
+            judge_role,
+            &state.lawsuits,
+            &lawsuit,
+        )
+        .await?;
+        match arbiter {
+            Some(arbiter) => lawsuit.judge = arbiter.into(),
+            None => {
+                ctx.say("es git kein verfüegbare schiedsrichter").await?;
+                return Ok(());
+            }
+        }
+
+        let response =
+            arbitration::start(mongo_client, &ctx.discord().http, guild_id, lawsuit).await?;
+
+        ctx.say(response.to_string()).await?;
+
+        Ok(())
+    }
+}
+
+/// The prefixes of the custom ids of the buttons that [`Handler::handle_interaction`] handles.
+const COMPONENT_PREFIXES: [&str; 11] = [
+    STATEMENT_ID_PREFIX,
+    arbitration::STATEMENT_ID_PREFIX,
+    arbitration::RULING_ID_PREFIX,
+    grand_jury::VOTE_ID_PREFIX,
+    opinion_poll::VOTE_ID_PREFIX,
+    keyword_watch::REPORT_ID_PREFIX,
+    subpoena::SUBPOENA_ID_PREFIX,
+    visitation::VISIT_ID_PREFIX,
+    petition::PETITION_ID_PREFIX,
+    work_release::TASK_ID_PREFIX,
+    arrest_appeal::ARREST_APPEAL_ID_PREFIX,
+];
+
+pub async fn listener(
+    ctx: &serenity::Context,
+    event: &Event<'_>,
+    _: poise::FrameworkContext<'_, Handler, Report>,
+    data: &Handler,
+) -> Result<()> {
+    // nothing is written while the database might be migrated, buttons tell why they don't work
+    if data.maintenance.is_active() && !matches!(event, Event::InteractionCreate { .. }) {
+        return Ok(());
+    }
+
+    match event {
+        Event::GuildCreate { guild, is_new } => {
+            if let Err(err) = data.handle_guild_create(ctx, guild, *is_new).await {
+                error!(?err, "An error occurred in guild_create handler");
+            }
+        }
+        Event::GuildMemberAddition { new_member } => {
+            if let Err(err) = data.handle_guild_member_join(ctx, new_member).await {
+                error!(?err, "An error occurred in guild_member_addition handler");
+            }
+        }
+        Event::GuildMemberRemoval { guild_id, user, .. } => {
+            if let Err(err) = data.handle_guild_member_removal(ctx, *guild_id, user).await {
+                error!(?err, "An error occurred in guild_member_removal handler");
+            }
+        }
+        Event::GuildBanAddition {
+            guild_id,
+            banned_user,
+        } => {
+            if let Err(err) = data.handle_guild_ban(ctx, *guild_id, banned_user).await {
+                error!(?err, "An error occurred in guild_ban_addition handler");
+            }
+        }
+        Event::GuildRoleUpdate {
+            old_data_if_available,
+            new,
+        } => {
+            let result = data
+                .handle_guild_role_update(ctx, old_data_if_available.as_ref(), new)
+                .await;
+            if let Err(err) = result {
+                error!(?err, "An error occurred in guild_role_update handler");
+            }
+        }
+        Event::GuildMemberUpdate { new, .. } => {
+            if let Err(err) = data.handle_guild_member_update(ctx, new).await {
+                error!(?err, "An error occurred in guild_member_update handler");
+            }
+        }
+        Event::Message { new_message } => {
+            if let Err(err) = data.handle_message(ctx, new_message).await {
+                error!(?err, "An error occurred in message handler");
+            }
+        }
+        Event::InteractionCreate { interaction } => {
+            if let Err(err) = data.handle_interaction(ctx, interaction).await {
+                error!(?err, "An error occurred in interaction_create handler");
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Finds the user who added the bot to the guild using the audit log. Needs the permission to view
+/// the audit log.
+async fn find_inviter(ctx: &serenity::Context, guild_id: GuildId) -> Result<Option<UserId>> {
+    let bot_id = ctx.cache.current_user_id();
+    let audit_logs = guild_id
+        .audit_logs(
+            &ctx.http,
+            Some(Action::Member(MemberAction::BotAdd).num()),
+            None,
+            None,
+            Some(10),
+        )
+        .await
+        .wrap_err("get audit logs")?;
+
+    Ok(audit_logs
+        .entries
+        .iter()
+        .find(|entry| entry.target_id == Some(bot_id.0))
+        .map(|entry| entry.user_id))
+}
+
+fn quick_start_message(guild_name: &str) -> String {
+    format!(
+        "Merci, dass du mich uf **{guild_name}** iiglade häsch! So gahts los:\n\
+         • `/court set_judge_role` – d'Rolle vo de Richter\n\
+         • `/court create_category` – e Kategorie für d'Gerichtsrüüm erstelle, \
+         oder e bestehendi mit `/lawsuit settings category` neh\n\
+         • `/prison create_role` – e Rolle für Gfangeni erstelle, \
+         oder e bestehendi mit `/prison set_role` neh\n\
+         • `/config timezone` – d'Zitzone vom Server\n\
+         Mit `/help` gsehsch alli Befehl."
+    )
+}
+
+/// All slash commands of the bot.
+pub fn commands() -> Vec<poise::Command<Handler, Report>> {
+    vec![
+        lawsuit::lawsuit(),
+        court::court(),
+        prison::prison(),
+        economy::economy(),
+        debt::debt(),
+        lawyer::lawyer(),
+        record::record(),
+        service::service(),
+        arbitrate::arbitrate(),
+        config::config(),
+        help::help(),
+        about::about(),
+        admin::admin(),
+        owner::owner(),
+        debug::debug(),
+    ]
+}
+
+/// Only lets owners use commands during maintenance.
+pub fn maintenance_check(ctx: Context<'_>) -> bool {
+    !ctx.data().maintenance.is_active() || ctx.framework().options.owners.contains(&ctx.author().id)
+}
+
+/// Whether the command isn't disabled in the config.
+pub fn enabled_check(ctx: Context<'_>) -> bool {
+    !crate::config::get().is_disabled(&ctx.command().qualified_name)
+}
+
+/// Logs the options of commands used in guilds with verbose tracing.
+pub fn trace_verbose(ctx: Context<'_>, stage: &str) {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+    let verbose = ctx
+        .data()
+        .verbose_guilds
+        .lock()
+        .expect("verbose guilds lock poisoned")
+        .contains(&guild_id);
+
+    if let (true, Context::Application(app_ctx)) = (verbose, ctx) {
+        info!(
+            %guild_id,
+            command = %ctx.command().qualified_name,
+            author = %ctx.author().id,
+            channel_id = %ctx.channel_id(),
+            options = ?app_ctx.interaction.data().options,
+            stage,
+            "Verbose command trace"
+        );
+    }
+}
+
+pub async fn error_handler(error: poise::FrameworkError<'_, Handler, Report>) {
+    match error {
+        poise::FrameworkError::MissingUserPermissions { ctx, .. } => {
+            let _ = ctx.say("du häsch kei recht für da!").await;
+        }
+        poise::FrameworkError::NotAnOwner { ctx } => {
+            let _ = ctx.say("das dörf nur de Bsitzer vom Bot").await;
+        }
+        poise::FrameworkError::CommandCheckFailed { ctx, error: None } => {
+            let message = match ctx.data().maintenance.message() {
+                Some(message) => Some(message),
+                None if !enabled_check(ctx) => {
+                    Some("dä Befehl isch momentan deaktiviert".to_string())
+                }
+                // the check of the command itself failed
+                None => Some("du häsch kei recht für da!".to_string()),
+            };
+            if let Some(message) = message {
+                let _ = ctx
+                    .send(|reply| reply.content(message).ephemeral(true))
+                    .await;
+            }
+        }
+        poise::FrameworkError::GuildOnly { ctx } => {
+            let _ = ctx
+                .say("du chasch de command nur uf emene serve nutze!")
+                .await;
+        }
+        poise::FrameworkError::Command { error, ctx } => {
+            let correlation_id = crate::logging::correlation_id(ctx).await;
+            let command = &ctx.command().qualified_name;
+
+            sentry::with_scope(
+                |scope| {
+                    scope.set_tag("command", command);
+                    scope.set_tag("correlation_id", &correlation_id);
+                    if let Some(guild_id) = ctx.guild_id() {
+                        scope.set_tag("guild_id", guild_id);
+                    }
+                    scope.set_user(Some(sentry::User {
+                        id: Some(ctx.author().id.to_string()),
+                        username: Some(ctx.author().tag()),
+                        ..Default::default()
+                    }));
+                },
+                || {
+                    let source: &(dyn std::error::Error + 'static) = error.as_ref();
+                    error!(
+                        error = source,
+                        report = ?error,
+                        %correlation_id,
+                        guild_id = ?ctx.guild_id(),
+                        user_id = %ctx.author().id,
+                        %command,
+                        "Error during command execution"
+                    );
+                },
+            );
+
+            crate::usage::record(ctx, false).await;
+            crate::events::finish_command(ctx).await;
+
+            let _ = ctx
+                .say(format!(
+                    "es isch en Fehler passiert, Fehler-ID `{correlation_id}`"
+                ))
+                .await;
+        }
+        err => {
+            error!(?err, "Error during command execution");
+        }
+    }
+}
+
+pub mod config {
+    use super::*;
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        category = "Verwaltung",
+        subcommands(
+            "set_announcement_channel",
+            "set_case_ping_role",
+            "set_warden_role",
+            "set_warden_channel",
+            "set_judge_channel",
+            "timezone",
+            "message",
+            "ephemeral",
+            "member_leave",
+            "keywords",
+            "record_moderation",
+            "verdict_polls",
+            "ordeals",
+            "set_log_channel",
+            "webhook",
+            "api_token",
+            "sandbox",
+            "retention",
+            "branding",
+            "federation",
+            "global_list",
+            "arrest_notice"
+        )
+    )]
+    pub async fn config(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Den Kanal setzen, in dem Urteile veröffentlicht werden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_announcement_channel(
+        ctx: Context<'_>,
+        #[description = "Der Kanal, leer lassen um auszuschalten"] channel: Option<Channel>,
+    ) -> Result<()> {
+        config_set_announcement_channel_impl(ctx, channel)
+            .await
+            .wrap_err("config_set_announcement_channel")
+    }
+
+    /// Die Rolle setzen, die bei neuen Prozessen erwähnt wird
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_case_ping_role(
+        ctx: Context<'_>,
+        #[description = "Die Rolle, leer lassen um auszuschalten"] role: Option<Role>,
+    ) -> Result<()> {
+        config_set_case_ping_role_impl(ctx, role)
+            .await
+            .wrap_err("config_set_case_ping_role")
+    }
+
+    /// Die Rolle setzen, die Gefangene verwalten darf, ohne Admin zu sein
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_warden_role(
+        ctx: Context<'_>,
+        #[description = "Die Rolle, leer lassen um auszuschalten"] role: Option<Role>,
+    ) -> Result<()> {
+        config_set_warden_role_impl(ctx, role)
+            .await
+            .wrap_err("config_set_warden_role")
+    }
+
+    /// Den Kanal setzen, in dem die Richter über Haftbeschwerden entscheiden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_judge_channel(
+        ctx: Context<'_>,
+        #[description = "Der Kanal, leer lassen um auszuschalten"] channel: Option<Channel>,
+    ) -> Result<()> {
+        config_set_judge_channel_impl(ctx, channel)
+            .await
+            .wrap_err("config_set_judge_channel")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_set_judge_channel_impl(
+        ctx: Context<'_>,
+        channel: Option<Channel>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_judge_channel(guild_id.into(), channel.map(|channel| channel.id().into()))
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    /// Den Kanal setzen, in dem die Aufseher die Gesuche der Gefangenen entscheiden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_warden_channel(
+        ctx: Context<'_>,
+        #[description = "Der Kanal, leer lassen um auszuschalten"] channel: Option<Channel>,
+    ) -> Result<()> {
+        config_set_warden_channel_impl(ctx, channel)
+            .await
+            .wrap_err("config_set_warden_channel")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_set_warden_channel_impl(
+        ctx: Context<'_>,
+        channel: Option<Channel>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_warden_channel(guild_id.into(), channel.map(|channel| channel.id().into()))
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_set_warden_role_impl(ctx: Context<'_>, role: Option<Role>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_warden_role(guild_id.into(), role.map(|role| role.id.into()))
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_set_case_ping_role_impl(ctx: Context<'_>, role: Option<Role>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_case_ping_role(guild_id.into(), role.map(|role| role.id.into()))
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("branding_set", "branding_reset")
+    )]
+    async fn branding(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Die Farbe und Fusszeile der Nachrichten des Gerichts festlegen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "set",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn branding_set(
+        ctx: Context<'_>,
+        #[description = "Die Farbe, z.B. #1f8b4c"] color: String,
+        #[description = "Der Text der Fusszeile"] footer_text: String,
+        #[description = "Der Link zum Siegel des Gerichts"] icon_url: Option<String>,
+    ) -> Result<()> {
+        config_branding_set_impl(ctx, color, footer_text, icon_url)
+            .await
+            .wrap_err("config_branding_set")
+    }
+
+    /// Die Farbe und Fusszeile der Nachrichten des Gerichts entfernen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "reset",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn branding_reset(ctx: Context<'_>) -> Result<()> {
+        config_branding_reset_impl(ctx)
+            .await
+            .wrap_err("config_branding_reset")
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("federation_add", "federation_remove", "federation_list")
+    )]
+    async fn federation(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Die Gefängnisdaten mit einem anderen Server teilen, wenn dieser das auch tut
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "add",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn federation_add(
+        ctx: Context<'_>,
+        #[description = "Die ID des anderen Servers"] server: String,
+    ) -> Result<()> {
+        config_federation_impl(ctx, server, true)
+            .await
+            .wrap_err("config_federation_add")
+    }
+
+    /// Die Gefängnisdaten nicht mehr mit einem anderen Server teilen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "remove",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn federation_remove(
+        ctx: Context<'_>,
+        #[description = "Die ID des anderen Servers"] server: String,
+    ) -> Result<()> {
+        config_federation_impl(ctx, server, false)
+            .await
+            .wrap_err("config_federation_remove")
+    }
+
+    /// Die Server anzeigen, mit denen die Gefängnisdaten geteilt werden
+    #[poise::command(slash_command, guild_only, rename = "list")]
+    async fn federation_list(ctx: Context<'_>) -> Result<()> {
+        config_federation_list_impl(ctx)
+            .await
+            .wrap_err("config_federation_list")
+    }
+
+    #[poise::command(slash_command, guild_only, subcommands("message_set", "message_reset"))]
+    async fn message(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Eine Antwort des Bots durch einen eigenen Text ersetzen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "set",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn message_set(
+        ctx: Context<'_>,
+        #[description = "Die Antwort"] key: MessageKey,
+        #[description = "Der neue Text, Platzhalter wie {user} werden ersetzt"] template: String,
+    ) -> Result<()> {
+        config_message_set_impl(ctx, key, Some(template))
+            .await
+            .wrap_err("config_message_set")
+    }
+
+    /// Eine Antwort des Bots auf den Standardtext zurücksetzen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "reset",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn message_reset(
+        ctx: Context<'_>,
+        #[description = "Die Antwort"] key: MessageKey,
+    ) -> Result<()> {
+        config_message_set_impl(ctx, key, None)
+            .await
+            .wrap_err("config_message_reset")
+    }
+
+    /// Einstellen, ob die Antworten des Bots nur für den Benutzer des Befehls sichtbar sind
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn ephemeral(
+        ctx: Context<'_>,
+        #[description = "Ob die Antworten privat sind, leer lassen um zurückzusetzen"]
+        ephemeral: Option<bool>,
+        #[description = "Nur für diesen Befehl, zum Beispiel economy balance"] command: Option<
+            String,
+        >,
+    ) -> Result<()> {
+        config_ephemeral_impl(ctx, ephemeral, command)
+            .await
+            .wrap_err("config_ephemeral")
+    }
+
+    /// Einstellen, was mit einem Prozess passiert, wenn der Kläger oder Angeklagte den Server verlässt
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn member_leave(
+        ctx: Context<'_>,
+        #[description = "Was mit dem Prozess passiert"] action: LeaveAction,
+    ) -> Result<()> {
+        config_member_leave_impl(ctx, action)
+            .await
+            .wrap_err("config_member_leave")
+    }
+
+    /// Den Kanal setzen, in dem der Bot Probleme meldet
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn set_log_channel(
+        ctx: Context<'_>,
+        #[description = "Der Kanal, leer lassen um auszuschalten"] channel: Option<Channel>,
+    ) -> Result<()> {
+        config_set_log_channel_impl(ctx, channel)
+            .await
+            .wrap_err("config_set_log_channel")
+    }
+
+    /// Einen neuen Zugangsschlüssel für die HTTP-Schnittstelle erstellen oder ihn entfernen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        ephemeral,
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn api_token(
+        ctx: Context<'_>,
+        #[description = "Ob die Schnittstelle für diesen Server erreichbar ist"] enabled: bool,
+    ) -> Result<()> {
+        config_api_token_impl(ctx, enabled)
+            .await
+            .wrap_err("config_api_token")
+    }
+
+    /// Befehle proben, ohne dass Rollen oder Kanäle wirklich geändert werden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn sandbox(
+        ctx: Context<'_>,
+        #[description = "Ob der Sandbox-Modus aktiv ist"] enabled: bool,
+        #[description = "Wo simulierte Änderungen gemeldet werden, sonst dieser Kanal"]
+        channel: Option<Channel>,
+    ) -> Result<()> {
+        config_sandbox_impl(ctx, enabled, channel)
+            .await
+            .wrap_err("config_sandbox")
+    }
+
+    /// Alti Date nach einer bestimmten Anzahl Tagen automatisch löschen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn retention(
+        ctx: Context<'_>,
+        #[description = "Nach wie vielen Tagen die Protokolle abgeschlossener Prozesse gelöscht werden"]
+        #[min = 1]
+        transcripts_days: Option<u32>,
+        #[description = "Nach wie vielen Tagen Gefangene gelöscht werden, die den Server verlassen haben"]
+        #[min = 1]
+        prisoners_days: Option<u32>,
+        #[description = "Nach wie vielen Tagen abgeschlossene Prozesse ganz gelöscht werden"]
+        #[min = 1]
+        closed_cases_days: Option<u32>,
+    ) -> Result<()> {
+        config_retention_impl(ctx, transcripts_days, prisoners_days, closed_cases_days)
+            .await
+            .wrap_err("config_retention")
+    }
+
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("webhook_add", "webhook_remove", "webhook_list")
+    )]
+    async fn webhook(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Einen Webhook hinzufügen, der bei Ereignissen im Gericht aufgerufen wird
+    #[poise::command(
+        slash_command,
+        guild_only,
+        ephemeral,
+        rename = "add",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn webhook_add(
+        ctx: Context<'_>,
+        #[description = "Die öffentliche https-URL, an die die Ereignisse gesendet werden"]
+        url: String,
+        #[description = "Die Ereignisse mit Komma getrennt, zum Beispiel verdict,arrest. Sonst alle"]
+        events: Option<String>,
+    ) -> Result<()> {
+        config_webhook_add_impl(ctx, url, events)
+            .await
+            .wrap_err("config_webhook_add")
+    }
+
+    /// Einen Webhook entfernen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        rename = "remove",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn webhook_remove(
+        ctx: Context<'_>,
+        #[description = "Die URL des Webhooks"] url: String,
+    ) -> Result<()> {
+        config_webhook_remove_impl(ctx, url)
+            .await
+            .wrap_err("config_webhook_remove")
+    }
+
+    /// Alle Webhooks anzeigen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        ephemeral,
+        rename = "list",
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn webhook_list(ctx: Context<'_>) -> Result<()> {
+        config_webhook_list_impl(ctx)
+            .await
+            .wrap_err("config_webhook_list")
+    }
+
+    /// Banns, Kicks und Timeouts von Discord im Strafregister festhalten
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn record_moderation(
+        ctx: Context<'_>,
+        #[description = "Ob die Moderation festgehalten wird"] enabled: bool,
+    ) -> Result<()> {
+        config_record_moderation_impl(ctx, enabled)
+            .await
+            .wrap_err("config_record_moderation")
+    }
+
+    /// Mitglieder auf der globalen Liste beim Beitritt im Log-Kanal melden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn global_list(
+        ctx: Context<'_>,
+        #[description = "Ob Mitglieder auf der globalen Liste gemeldet werden"] enabled: bool,
+    ) -> Result<()> {
+        config_global_list_impl(ctx, enabled)
+            .await
+            .wrap_err("config_global_list")
+    }
+
+    /// Verhafteten eine Nachricht mit Grund, Dauer und Prozess schicken
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn arrest_notice(
+        ctx: Context<'_>,
+        #[description = "Ob Verhaftete die Nachricht bekommen, der Text ist mit /config message anpassbar"]
+        enabled: bool,
+    ) -> Result<()> {
+        config_arrest_notice_impl(ctx, enabled)
+            .await
+            .wrap_err("config_arrest_notice")
+    }
+
+    /// Zuschauer über veröffentlichte Urteile abstimmen lassen
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn verdict_polls(
+        ctx: Context<'_>,
+        #[description = "Ob unter Urteilen abgestimmt werden kann"] enabled: bool,
+    ) -> Result<()> {
+        config_verdict_polls_impl(ctx, enabled)
+            .await
+            .wrap_err("config_verdict_polls")
+    }
+
+    /// Richtern erlauben, Bagatellklagen durch ein Gottesurteil zu entscheiden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn ordeals(
+        ctx: Context<'_>,
+        #[description = "Ob /lawsuit ordeal benutzt werden kann"] enabled: bool,
+    ) -> Result<()> {
+        config_ordeals_impl(ctx, enabled)
+            .await
+            .wrap_err("config_ordeals")
+    }
+
+    /// Nachrichten mit bestimmten Wörtern zur Überprüfung melden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn keywords(
+        ctx: Context<'_>,
+        #[description = "Die Wörter, mit Komma getrennt, leer lassen um auszuschalten"]
+        words: Option<String>,
+        #[description = "Der Kanal, in dem die Meldungen überprüft werden"] channel: Option<
+            Channel,
+        >,
+    ) -> Result<()> {
+        config_keywords_impl(ctx, words, channel)
+            .await
+            .wrap_err("config_keywords")
+    }
+
+    /// Die Zeitzone setzen, in der Daten angezeigt und eingegeben werden
+    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+    async fn timezone(
+        ctx: Context<'_>,
+        #[description = "Die Zeitzone, zum Beispiel Europe/Zurich"] timezone: String,
+    ) -> Result<()> {
+        config_timezone_impl(ctx, timezone)
+            .await
+            .wrap_err("config_timezone")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_message_set_impl(
+        ctx: Context<'_>,
+        key: MessageKey,
+        template: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        if let Some(template) = &template {
+            let unknown = template
+                .split('{')
+                .skip(1)
+                .filter_map(|rest| rest.split_once('}'))
+                .map(|(name, _)| name)
+                .find(|name| !key.placeholders().contains(name));
+            if let Some(name) = unknown {
+                let known = match key.placeholders() {
+                    [] => "kei".to_string(),
+                    placeholders => placeholders
+                        .iter()
+                        .map(|name| format!("`{{{name}}}`"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                };
+                ctx.say(format!(
+                    "de Platzhalter `{{{name}}}` gits nöd, für die Antwort gits {known}"
+                ))
+                .await?;
+                return Ok(());
+            }
+        }
+
+        let template = template.map(|template| MessageTemplate { key, template });
+        guild_mongo(ctx)
+            .set_message_template(guild_id.into(), key, template.as_ref())
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    /// Whether a command or subcommand at any depth has the full name.
+    fn command_exists(commands: &[poise::Command<Handler, Report>], qualified_name: &str) -> bool {
+        commands.iter().any(|command| {
+            command.qualified_name == qualified_name
+                || command_exists(&command.subcommands, qualified_name)
+        })
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_ephemeral_impl(
+        ctx: Context<'_>,
+        ephemeral: Option<bool>,
+        command: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo_client = &guild_mongo(ctx);
+
+        match command {
+            Some(command) => {
+                let command = command
+                    .trim_start_matches('/')
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if ALWAYS_PUBLIC.contains(&command.as_str()) {
+                    ctx.say(format!("d'Antworte vo `/{command}` sind immer öffentlich"))
+                        .await?;
+                    return Ok(());
+                }
+                if ALWAYS_PRIVATE.contains(&command.as_str()) {
+                    ctx.say(format!("d'Antworte vo `/{command}` sind immer privat"))
+                        .await?;
+                    return Ok(());
+                }
+                if !command_exists(&ctx.framework().options.commands, &command) {
+                    ctx.say(format!("de Befehl `/{command}` gits nöd")).await?;
+                    return Ok(());
+                }
+                mongo_client
+                    .set_reply_visibility(guild_id.into(), &command, ephemeral)
+                    .await?;
+            }
+            None => {
+                mongo_client
+                    .set_ephemeral_replies(guild_id.into(), ephemeral)
+                    .await?;
+            }
+        }
+
+        load_reply_settings(ctx).await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_member_leave_impl(ctx: Context<'_>, action: LeaveAction) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_member_leave_action(guild_id.into(), action)
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_set_log_channel_impl(ctx: Context<'_>, channel: Option<Channel>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_log_channel(guild_id.into(), channel.map(|channel| channel.id().into()))
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_api_token_impl(ctx: Context<'_>, enabled: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        // the api always reads the real data
+        if !enabled {
+            ctx.data()
+                .mongo
+                .set_api_token_hash(guild_id.into(), None)
+                .await?;
+            say_message(ctx, MessageKey::Saved, &[]).await?;
+            return Ok(());
+        }
+
+        let token = webhook::generate_secret();
+        ctx.data()
+            .mongo
+            .set_api_token_hash(guild_id.into(), Some(&api::hash_token(&token)))
+            .await?;
+
+        info!("Created api token");
+
+        ctx.say(format!(
+            "de neu Schlüssel isch erstellt, en alte gilt nüme. Er wird nur jetzt azeigt, \
+             schick en im Header `Authorization: Bearer <Schlüssel>` mit:\n||{token}||"
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_sandbox_impl(
+        ctx: Context<'_>,
+        enabled: bool,
+        channel: Option<Channel>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        // the flag is stored in the real data, whether the guild is in sandbox mode or not
+        let mongo = ctx.data().mongo.guild(guild_id);
+
+        if enabled {
+            let channel_id = channel.map_or(ctx.channel_id(), |channel| channel.id());
+            mongo
+                .enable_sandbox(guild_id.into(), channel_id.into())
+                .await?;
+            sandbox::set(guild_id, Some(channel_id));
+            info!(%channel_id, "Enabled sandbox mode");
+
+            ctx.say(format!(
+                "de Sandbox-Modus isch aktiv. Befehl bruched e Kopie vo de Date, Rolle und Kanäl \
+                 werded nur in <#{channel_id}> gmeldet"
+            ))
+            .await?;
+        } else {
+            mongo.disable_sandbox(guild_id.into()).await?;
+            sandbox::set(guild_id, None);
+            info!("Disabled sandbox mode");
+
+            ctx.say("de Sandbox-Modus isch us, d'Kopie vo de Date isch glöscht")
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_branding_set_impl(
+        ctx: Context<'_>,
+        color: String,
+        footer_text: String,
+        icon_url: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let color = match branding::parse_color(&color) {
+            Some(color) => color,
+            None => {
+                ctx.say("d'Farb muess e Hex-Farb wie #1f8b4c sii").await?;
+                return Ok(());
+            }
+        };
+        if footer_text.chars().count() > branding::MAX_FOOTER_LEN {
+            ctx.say(format!(
+                "d'Fusszeile dörf höchstens {} Zeiche ha",
+                branding::MAX_FOOTER_LEN
+            ))
+            .await?;
+            return Ok(());
+        }
+        if let Some(icon_url) = &icon_url {
+            if !icon_url.starts_with("https://") {
+                ctx.say("de Link zum Siegel muess mit https:// aafange")
+                    .await?;
+                return Ok(());
+            }
+        }
+
+        let branding = Branding {
+            color,
+            footer_text,
+            icon_url,
+        };
+        guild_mongo(ctx)
+            .set_branding(guild_id.into(), Some(&branding))
+            .await?;
+        info!(?branding, "Set branding");
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_branding_reset_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx).set_branding(guild_id.into(), None).await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_retention_impl(
+        ctx: Context<'_>,
+        transcripts_days: Option<u32>,
+        prisoners_days: Option<u32>,
+        closed_cases_days: Option<u32>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        // a new dry run is made whenever the retention changes
+        let retention = Retention {
+            transcripts_days,
+            prisoners_days,
+            closed_cases_days,
+            dry_run_at: None,
+        };
+        if !retention.is_enabled() {
+            mongo.set_retention(guild_id.into(), None).await?;
+            ctx.say("d'Date werded wieder für immer bhalte").await?;
+            return Ok(());
+        }
+        mongo
+            .set_retention(guild_id.into(), Some(&retention))
+            .await?;
+
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let prisoners = mongo.find_prisoners(guild_id.into()).await?;
+        let purge = Purge::plan(
+            &retention,
+            &state.lawsuits,
+            &prisoners,
+            mongodb::bson::DateTime::now(),
+        );
+
+        info!(?retention, "Set retention");
+
+        ctx.say(format!(
+            "gspeicheret. Vor em erste Lösche chunnt en Testlauf i de Log-Kanal, im Moment \
+             würd das glöscht:\n{purge}"
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_webhook_add_impl(
+        ctx: Context<'_>,
+        url: String,
+        events: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        if let Err(reason) = webhook::resolve(&url).await {
+            ctx.say(reason).await?;
+            return Ok(());
+        }
+        let events = match WebhookEvent::parse_list(events.as_deref().unwrap_or_default()) {
+            Some(events) => events,
+            None => {
+                let names = WebhookEvent::ALL.map(WebhookEvent::name).join(", ");
+                ctx.say(format!("das sind kei gültigi Ereignis, es git: {names}"))
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let webhook = Webhook {
+            id: Uuid::new(),
+            url,
+            secret: webhook::generate_secret(),
+            events,
+        };
+        guild_mongo(ctx)
+            .add_webhook(guild_id.into(), &webhook)
+            .await?;
+
+        info!(url = %webhook.url, "Added webhook");
+
+        ctx.say(format!(
+            "de Webhook isch hinzuegfüegt. D'Nutzlast wird mit HMAC-SHA256 und dem Geheimnis \
+             signiert, d'Signatur staht im Header `X-Court-Signature`:\n||{}||",
+            webhook.secret
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_webhook_remove_impl(ctx: Context<'_>, url: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .remove_webhook(guild_id.into(), &url)
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_webhook_list_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+
+        if state.webhooks.is_empty() {
+            ctx.say("es git kei Webhooks").await?;
+            return Ok(());
+        }
+
+        let lines = state
+            .webhooks
+            .iter()
+            .map(|webhook| {
+                let events = webhook
+                    .events
+                    .iter()
+                    .map(|event| event.name())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("`{}` – {events}", webhook.url)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.say(lines).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_global_list_impl(ctx: Context<'_>, enabled: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = guild_mongo(ctx);
+
+        if enabled {
+            let state = mongo.find_or_insert_state(guild_id.into()).await?;
+            if !validate_references(ctx, &state, &[Reference::LogChannel]).await? {
+                return Ok(());
+            }
+        }
+        mongo.set_global_list(guild_id.into(), enabled).await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_arrest_notice_impl(ctx: Context<'_>, enabled: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_arrest_notice(guild_id.into(), enabled)
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_record_moderation_impl(ctx: Context<'_>, enabled: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_record_moderation(guild_id.into(), enabled)
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_verdict_polls_impl(ctx: Context<'_>, enabled: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_verdict_polls(guild_id.into(), enabled)
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_ordeals_impl(ctx: Context<'_>, enabled: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_ordeals(guild_id.into(), enabled)
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_federation_impl(ctx: Context<'_>, server: String, partner: bool) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let partner_id = match server.trim().parse::<u64>() {
+            Ok(id) if id != guild_id.0 => GuildId(id),
+            Ok(_) => {
+                ctx.say("das isch dä Server sälber").await?;
+                return Ok(());
+            }
+            Err(_) => {
+                ctx.say("das isch kei gültigi Server-ID").await?;
+                return Ok(());
+            }
+        };
+        if partner && ctx.discord().cache.guild(partner_id).is_none() {
+            ctx.say("dä Bot isch nöd uf dem Server").await?;
+            return Ok(());
+        }
+
+        guild_mongo(ctx)
+            .set_federation_partner(guild_id.into(), partner_id.into(), partner)
+            .await?;
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_federation_list_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let state = guild_mongo(ctx)
+            .find_or_insert_state(guild_id.into())
+            .await?;
+        if state.federation.is_empty() {
+            ctx.say("es werded kei Gfängnisdate mit andere Server teilt")
+                .await?;
+            return Ok(());
+        }
+
+        let partners = federation::partners(&ctx.data().mongo, &state).await?;
+        let lines = state
+            .federation
+            .iter()
+            .map(|&partner_id| {
+                let status = match partners.iter().any(|p| p.guild_id == partner_id) {
+                    true => "teilt",
+                    false => "wartet uf dä ander Server",
+                };
+                format!(
+                    "{} (`{partner_id}`) – {status}",
+                    guild_name(ctx, partner_id)
+                )
+            })
+            .collect::<Vec<_>>();
+        ctx.say(lines.join("\n")).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_keywords_impl(
+        ctx: Context<'_>,
+        words: Option<String>,
+        channel: Option<Channel>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let words = words
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+
+        let keyword_watch = match (words.is_empty(), channel) {
+            (true, _) => None,
+            (false, _) if !ctx.data().keyword_watch_enabled => {
+                ctx.say("Stichwörter sind uf dem Bot nöd aktiviert, de Betriiber muess `KEYWORD_WATCH` setze")
+                    .await?;
+                return Ok(());
+            }
+            (false, Some(channel)) => Some(KeywordWatch {
+                words,
+                channel_id: channel.id().into(),
+            }),
+            (false, None) => {
+                ctx.say("du muesch en Kanal für d'Meldige aagäh").await?;
+                return Ok(());
+            }
+        };
+
+        guild_mongo(ctx)
+            .set_keyword_watch(guild_id.into(), keyword_watch.as_ref())
+            .await?;
+        ctx.data()
+            .keyword_watches
+            .lock()
+            .expect("keyword watches lock poisoned")
+            .insert(guild_id, keyword_watch);
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_timezone_impl(ctx: Context<'_>, timezone: String) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        let tz = match parse_timezone(&timezone) {
+            Some(tz) => tz,
+            None => {
+                ctx.say("die zitzone kenn ich nöd, probier öppis wie `Europe/Zurich`")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        guild_mongo(ctx)
+            .set_timezone(guild_id.into(), tz.name())
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn config_set_announcement_channel_impl(
+        ctx: Context<'_>,
+        channel: Option<Channel>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        guild_mongo(ctx)
+            .set_announcement_channel(guild_id.into(), channel.map(|channel| channel.id().into()))
+            .await?;
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+}
+
+pub mod help {
+    use super::*;
+
+    type Command = poise::Command<Handler, Report>;
+
+    /// The order in which the categories are shown. Commands without a category come last.
+    const CATEGORIES: [&str; 4] = ["Gericht", "Gefängnis", "Wirtschaft", "Verwaltung"];
+
+    /// Discord rejects embed fields with a longer value.
+    pub(super) const FIELD_LIMIT: usize = 1024;
+
+    /// Hilfe zu allen Befehlen oder zu einem bestimmten Befehl anzeigen
+    #[poise::command(slash_command)]
+    pub async fn help(
+        ctx: Context<'_>,
+        #[description = "Der Befehl, zum Beispiel lawsuit create"] command: Option<String>,
+    ) -> Result<()> {
+        help_impl(ctx, command).await.wrap_err("help")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn help_impl(ctx: Context<'_>, command: Option<String>) -> Result<()> {
+        let commands = &ctx.framework().options.commands;
+
+        let path = match &command {
+            Some(command) => command
+                .trim_start_matches('/')
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            None => return overview(ctx, commands).await,
+        };
+
+        let command = match find_command(commands, &path) {
+            Some(command) => command,
+            None => {
+                ctx.say(format!("de Befehl `/{}` gits nöd", path.join(" ")))
+                    .await?;
+                return Ok(());
+            }
+        };
+        let name = format!("/{}", path.join(" "));
+
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                embed.title(&name);
+                if let Some(help) = command.inline_help {
+                    embed.description(help);
+                }
+                if command.subcommands.is_empty() {
+                    embed.field("Verwendung", format!("`{}`", usage(&name, command)), false);
+                    let parameters = command
+                        .parameters
+                        .iter()
+                        .map(|parameter| {
+                            let optional = if parameter.required {
+                                ""
+                            } else {
+                                " (optional)"
+                            };
+                            format!(
+                                "`{}`{optional}: {}",
+                                parameter.name,
+                                parameter.description.unwrap_or_default()
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    for (title, value) in field_chunks("Parameter", &parameters) {
+                        embed.field(title, value, false);
+                    }
+                } else {
+                    let subcommands = command
+                        .subcommands
+                        .iter()
+                        .map(|subcommand| {
+                            format!(
+                                "`{name} {}` – {}",
+                                subcommand.name,
+                                subcommand.inline_help.unwrap_or_default()
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    for (title, value) in field_chunks("Befehle", &subcommands) {
+                        embed.field(title, value, false);
+                    }
+                }
+                embed.field("Berechtigung", permissions(command), false)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn overview(ctx: Context<'_>, commands: &[Command]) -> Result<()> {
+        let category_index = |command: &Command| {
+            command
+                .category
+                .and_then(|category| CATEGORIES.iter().position(|c| *c == category))
+                .unwrap_or(CATEGORIES.len())
+        };
+
+        let mut commands = commands
+            .iter()
+            .filter(|command| !command.hide_in_help)
+            .collect::<Vec<_>>();
+        commands.sort_by_key(|command| category_index(command));
+
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                embed
+                    .title("Hilfe")
+                    .description("Mit `/help <befehl>` gits meh Infos zu eme Befehl");
+                for category in CATEGORIES.iter().copied().chain(["Anderes"]) {
+                    let lines = commands
+                        .iter()
+                        .filter(|command| {
+                            CATEGORIES
+                                .get(category_index(command))
+                                .unwrap_or(&"Anderes")
+                                == &category
+                        })
+                        .map(|command| {
+                            if command.subcommands.is_empty() {
+                                format!(
+                                    "`/{}` – {}",
+                                    command.name,
+                                    command.inline_help.unwrap_or_default()
+                                )
+                            } else {
+                                let subcommands = command
+                                    .subcommands
+                                    .iter()
+                                    .map(|subcommand| subcommand.name)
+                                    .collect::<Vec<_>>();
+                                format!("`/{}` {}", command.name, subcommands.join(", "))
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    for (title, value) in field_chunks(category, &lines) {
+                        embed.field(title, value, false);
+                    }
+                }
+                embed
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Splits the lines into as many fields as needed to stay under the limit of a field. The
+    /// title is only on the first one. A line that is too long on its own is cut.
+    pub(super) fn field_chunks(title: &str, lines: &[String]) -> Vec<(String, String)> {
+        let mut fields = Vec::<(String, String)>::new();
+        let mut value = String::new();
+        for line in lines {
+            let line = if line.chars().count() > FIELD_LIMIT {
+                let mut cut = line.chars().take(FIELD_LIMIT - 1).collect::<String>();
+                cut.push('…');
+                cut
+            } else {
+                line.clone()
+            };
+            if !value.is_empty() && value.chars().count() + 1 + line.chars().count() > FIELD_LIMIT {
+                let title = if fields.is_empty() { title } else { "\u{200b}" };
+                fields.push((title.to_string(), std::mem::take(&mut value)));
+            }
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(&line);
+        }
+        if !value.is_empty() {
+            let title = if fields.is_empty() { title } else { "\u{200b}" };
+            fields.push((title.to_string(), value));
+        }
+        fields
+    }
+
+    fn find_command<'a>(commands: &'a [Command], path: &[&str]) -> Option<&'a Command> {
+        let (name, rest) = path.split_first()?;
+        let command = commands.iter().find(|command| command.name == *name)?;
+        if rest.is_empty() {
+            Some(command)
+        } else {
+            find_command(&command.subcommands, rest)
+        }
+    }
+
+    /// An example invocation, with the optional parameters in brackets.
+    fn usage(name: &str, command: &Command) -> String {
+        command
+            .parameters
+            .iter()
+            .fold(name.to_string(), |usage, parameter| {
+                if parameter.required {
+                    format!("{usage} {}:…", parameter.name)
+                } else {
+                    format!("{usage} [{}:…]", parameter.name)
+                }
+            })
+    }
+
+    fn permissions(command: &Command) -> String {
+        if command.required_permissions.is_empty() {
+            "Alli".to_string()
+        } else {
+            command
+                .required_permissions
+                .get_permission_names()
+                .join(", ")
+        }
+    }
+}
+
+pub mod about {
+    use super::*;
+
+    /// Informationen über den Bot anzeigen
+    #[poise::command(slash_command, ephemeral)]
+    pub async fn about(ctx: Context<'_>) -> Result<()> {
+        about_impl(ctx).await.wrap_err("about")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn about_impl(ctx: Context<'_>) -> Result<()> {
+        let mongo_client = &guild_mongo(ctx);
+
+        let uptime = Duration::from_secs(ctx.data().started_at.elapsed().as_secs());
+        let guilds = ctx.discord().cache.guild_count();
+
+        let latency = {
+            let shard_manager = ctx.framework().shard_manager.lock().await;
+            let runners = shard_manager.runners.lock().await;
+            runners
+                .get(&serenity::ShardId(ctx.discord().shard_id))
+                .and_then(|runner| runner.latency)
+        };
+        let latency = match latency {
+            Some(latency) => format!("{}ms", latency.as_millis()),
+            None => "unbekannt".to_string(),
+        };
+
+        let ping = mongo_client.ping().await?;
+        let open_lawsuits = mongo_client.count_open_lawsuits().await?;
+        let prisoners = mongo_client.count_prisoners().await?;
+
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                embed
+                    .title("Court Bot")
+                    .field("Version", env!("CARGO_PKG_VERSION"), true)
+                    .field("Commit", env!("GIT_COMMIT"), true)
+                    .field("Online sit", format_duration(uptime), true)
+                    .field("Latenz", latency, true)
+                    .field("Datenbank", format!("{}ms", ping.as_millis()), true)
+                    .field("Server", guilds, true)
+                    .field("Offeni Prozess", open_lawsuits, true)
+                    .field("Gfangeni", prisoners, true)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub mod admin {
+    use poise::serenity_prelude::ApplicationCommand;
+
+    use super::*;
+
+    /// Where the application commands are registered.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+    pub enum SyncScope {
+        #[name = "Global"]
+        Global,
+        #[name = "Dieser Server"]
+        Guild,
+        #[name = "Server-Kopien entfernen"]
+        ClearGuild,
+    }
+
+    /// The subcommands check their own permissions, `export_csv` is for the staff of the guild.
+    #[poise::command(
+        slash_command,
+        hide_in_help,
+        category = "Verwaltung",
+        subcommands(
+            "sync",
+            "maintenance",
+            "export_csv",
+            "import_history",
+            "usage",
+            "undo",
+            "reload_config"
+        )
+    )]
+    pub async fn admin(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Die Befehle bei Discord neu registrieren
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn sync(
+        ctx: Context<'_>,
+        #[description = "Global, oder als Kopie auf diesem Server (ohne Server der Dev-Server)"]
+        scope: SyncScope,
+    ) -> Result<()> {
+        sync_impl(ctx, scope).await.wrap_err("admin_sync")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn sync_impl(ctx: Context<'_>, scope: SyncScope) -> Result<()> {
+        let http = &ctx.discord().http;
+        let commands = &ctx.framework().options.commands;
+        let create_commands = poise::builtins::create_application_commands(commands);
+
+        let (old, new) = match scope {
+            SyncScope::Global => {
+                let old = ApplicationCommand::get_global_application_commands(http)
+                    .await
+                    .wrap_err("get global commands")?;
+                let new = ApplicationCommand::set_global_application_commands(http, |b| {
+                    *b = create_commands;
+                    b
+                })
+                .await
+                .wrap_err("set global commands")?;
+                (old, new)
+            }
+            SyncScope::Guild | SyncScope::ClearGuild => {
+                let guild_id = match ctx.guild_id().or(ctx.data().dev_guild_id) {
+                    Some(guild_id) => guild_id,
+                    None => {
+                        ctx.say("es git kei Server zum de Befehl registriere")
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                let old = guild_id
+                    .get_application_commands(http)
+                    .await
+                    .wrap_err("get guild commands")?;
+                let new = guild_id
+                    .set_application_commands(http, |b| {
+                        if scope == SyncScope::Guild {
+                            *b = create_commands;
+                        }
+                        b
+                    })
+                    .await
+                    .wrap_err("set guild commands")?;
+                (old, new)
+            }
+        };
+
+        info!(?scope, commands = new.len(), "Synced application commands");
+
+        ctx.say(sync_report(&old, &new)).await?;
+
+        Ok(())
+    }
+
+    /// Den Wartungsmodus ein- oder ausschalten
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn maintenance(
+        ctx: Context<'_>,
+        #[description = "Ob der Wartungsmodus an ist"] enabled: bool,
+        #[description = "Die Nachricht für die Benutzer"] message: Option<String>,
+    ) -> Result<()> {
+        maintenance_impl(ctx, enabled, message)
+            .await
+            .wrap_err("admin_maintenance")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn maintenance_impl(
+        ctx: Context<'_>,
+        enabled: bool,
+        message: Option<String>,
+    ) -> Result<()> {
+        let maintenance = &ctx.data().maintenance;
+
+        if enabled {
+            maintenance.enable(message);
+            info!("Enabled maintenance mode");
+            ctx.say("de Wartigsmodus isch ii, d'Hintergrundufgabe started kei neui Durchläuf meh")
+                .await?;
+        } else {
+            maintenance.disable();
+            info!("Disabled maintenance mode");
+            ctx.say("de Wartigsmodus isch us").await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gefängnis-, Bussen- oder Prozessdaten als CSV-Datei exportieren
+    #[poise::command(
+        slash_command,
+        guild_only,
+        ephemeral,
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn export_csv(
+        ctx: Context<'_>,
+        #[description = "Welche Daten"] kind: ExportKind,
+        #[description = "Spalten mit Komma getrennt, leer lassen für alle"] columns: Option<String>,
+        #[description = "Ab diesem Datum, z.B. 2022-06-01"] from: Option<String>,
+        #[description = "Bis und mit diesem Datum"] to: Option<String>,
+    ) -> Result<()> {
+        export_csv_impl(ctx, kind, columns, from, to)
+            .await
+            .wrap_err("admin_export_csv")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn export_csv_impl(
+        ctx: Context<'_>,
+        kind: ExportKind,
+        columns: Option<String>,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+        let state = mongo.find_or_insert_state(guild_id.into()).await?;
+        let tz = state.timezone();
+
+        let columns = match export::parse_columns(kind, columns.as_deref().unwrap_or_default()) {
+            Some(columns) => columns,
+            None => {
+                ctx.say(format!(
+                    "die Spalte git's nöd, es git: {}",
+                    kind.columns().join(", ")
+                ))
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let mut range = [None, None];
+        for (i, input) in [from, to].iter().enumerate() {
+            if let Some(input) = input {
+                match parse_date(input, tz) {
+                    Some(date) => range[i] = Some(date),
+                    None => {
+                        ctx.say(format!("`{input}` isch kes gültigs Datum")).await?;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        let [from, to] = range;
+        // the end date is included
+        let to =
+            to.map(|to| mongodb::bson::DateTime::from_millis(to.timestamp_millis() + 86_400_000));
+
+        let csv = export::export(mongo, &state, kind, &columns, from, to).await?;
+
+        info!(?kind, "Exported csv");
+
+        ctx.send(|reply| {
+            reply
+                .content(format!("Export vo {kind}"))
+                .attachment(serenity::AttachmentType::Bytes {
+                    data: csv.into_bytes().into(),
+                    filename: format!("{}-{guild_id}.csv", kind.file_name()),
+                })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Verwarnungen, Mutes, Kicks und Banns aus dem Export eines anderen Bots übernehmen
+    #[poise::command(
+        slash_command,
+        guild_only,
+        ephemeral,
+        required_permissions = "MANAGE_GUILD"
+    )]
+    async fn import_history(
+        ctx: Context<'_>,
+        #[description = "CSV- oder JSON-Export, z.B. von Dyno oder Carl-bot"]
+        file: serenity::Attachment,
+    ) -> Result<()> {
+        import_history_impl(ctx, file)
+            .await
+            .wrap_err("admin_import_history")
+    }
+
+    #[tracing::instrument(skip(ctx, file), fields(file = %file.filename))]
+    async fn import_history_impl(ctx: Context<'_>, file: serenity::Attachment) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+
+        if file.size > IMPORT_SIZE_LIMIT {
+            ctx.say("d'Datei isch z'gross").await?;
+            return Ok(());
+        }
+
+        ctx.defer_ephemeral().await?;
+
+        let content = file.download().await.wrap_err("download import file")?;
+        let content = match String::from_utf8(content) {
+            Ok(content) => content,
+            Err(_) => {
+                ctx.say("d'Datei isch kei Text").await?;
+                return Ok(());
+            }
+        };
+
+        let result =
+            history_import::import(&guild_mongo(ctx), guild_id.into(), &file.filename, &content)
+                .await?;
+        let summary = match result {
+            Ok(summary) => summary,
+            Err(response) => {
+                ctx.say(response.to_string()).await?;
+                return Ok(());
+            }
+        };
+
+        ctx.say(format!(
+            "{} Iiträg importiert, {} sind scho importiert gsi, {} übersprunge (unbekannti Aktion oder kei Benutzer)",
+            summary.imported, summary.duplicates, summary.skipped
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Die eigene letzte Verhaftung, Freilassung, Busse oder Rollen-Einstellung rückgängig machen
+    #[poise::command(slash_command, guild_only, ephemeral)]
+    async fn undo(ctx: Context<'_>) -> Result<()> {
+        undo_impl(ctx).await.wrap_err("admin_undo")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn undo_impl(ctx: Context<'_>) -> Result<()> {
+        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        let mongo = &guild_mongo(ctx);
+        // the undo records its own event that points to the reversed one
+        events::skip_command(ctx).await;
+
+        let (event, reversal) =
+            match undo::find_last(mongo, guild_id.into(), ctx.author().id.into()).await? {
+                Some(found) => found,
+                None => {
+                    ctx.say(format!(
+                        "du häsch i de letzte {} Minute nüt gmacht, wo mer chan rückgängig mache",
+                        undo::UNDO_WINDOW.as_secs() / 60
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+            };
+
+        let question = format!(
+            "{reversal} vo <t:{}:R> rückgängig mache?",
+            event.timestamp.timestamp_millis() / 1000
+        );
+        if !confirm(ctx, question).await? {
+            ctx.say("abbroche").await?;
+            return Ok(());
+        }
+
+        let http = &ctx.discord().http;
+        let actor = ctx.author().id.into();
+        let changes = match undo::undo(mongo, http, guild_id, actor, &event, &reversal).await? {
+            Ok(changes) => changes,
+            Err(response) => {
+                ctx.say(response.to_string()).await?;
+                return Ok(());
+            }
+        };
+        if changes.is_empty() {
+            ctx.say(
+                "das isch sithär scho wieder gänderet worde, es git nüt meh rückgängig z'mache",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        ctx.say(format!("{reversal} isch rückgängig gmacht"))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Die Konfigurationsdatei neu laden
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn reload_config(ctx: Context<'_>) -> Result<()> {
+        reload_config_impl(ctx)
+            .await
+            .wrap_err("admin_reload_config")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn reload_config_impl(ctx: Context<'_>) -> Result<()> {
+        match crate::config::reload().await {
+            Ok(true) => {
+                ctx.say("d'Konfiguration isch neu glade").await?;
+            }
+            Ok(false) => {
+                ctx.say("es isch kei `CONFIG_FILE` gsetzt").await?;
+            }
+            Err(err) => {
+                error!(?err, "Failed to reload config");
+                ctx.say(format!(
+                    "d'Konfiguration isch ungültig, di alt blibt: {err:#}"
+                ))
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Zeigen, welche Befehle wie oft genutzt werden
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn usage(
+        ctx: Context<'_>,
+        #[description = "Die letzten so viele Tage, standardmässig 7"]
+        #[min = 1]
+        #[max = 30]
+        days: Option<u32>,
+    ) -> Result<()> {
+        usage_impl(ctx, days).await.wrap_err("admin_usage")
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn usage_impl(ctx: Context<'_>, days: Option<u32>) -> Result<()> {
+        let mongo = &ctx.data().mongo;
+        let days = days.unwrap_or(7);
+        let since = mongodb::bson::DateTime::from_millis(
+            mongodb::bson::DateTime::now().timestamp_millis()
+                - i64::from(days) * 24 * 60 * 60 * 1000,
+        );
+
+        let commands = mongo.command_usage_stats(since).await?;
+        let guilds = mongo.guild_activity(since, USAGE_LIST_LIMIT as i64).await?;
+
+        if commands.is_empty() {
+            ctx.say(format!(
+                "i de letzte {days} Täg isch kei Befehl brucht worde"
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let total = commands.iter().map(|stats| stats.count).sum::<i64>();
+        let failures = commands.iter().map(|stats| stats.failures).sum::<i64>();
+
+        let command_lines = commands
+            .iter()
+            .take(USAGE_LIST_LIMIT)
+            .map(|stats| {
+                format!(
+                    "`/{}`: {}×, {:.1}% Fehler, {:.0} ms",
+                    stats.command,
+                    stats.count,
+                    stats.error_rate(),
+                    stats.avg_latency_ms
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let guild_lines = guilds
+            .iter()
+            .map(|activity| {
+                let name = match activity.guild_id {
+                    Some(guild_id) => GuildId::from(guild_id)
+                        .name(ctx.discord())
+                        .unwrap_or_else(|| guild_id.to_string()),
+                    None => "Direktnachrichte".to_string(),
+                };
+                format!(
+                    "{name}: {} Befehl vo {} Benutzer",
+                    activity.count, activity.users
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        ctx.say(format!(
+            "**Letzti {days} Täg**: {total} Befehl, {failures} fehlgschlage\n\n\
+            **Befehl**\n{command_lines}\n\n**Server**\n{guild_lines}"
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lists the added, removed and changed commands. Discord increments the version of a
+    /// command whenever it changes.
+    fn sync_report(old: &[ApplicationCommand], new: &[ApplicationCommand]) -> String {
+        let format_names = |commands: Vec<&ApplicationCommand>| {
+            commands
+                .iter()
+                .map(|command| format!("`/{}`", command.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let find_old = |command: &ApplicationCommand| old.iter().find(|c| c.name == command.name);
+
+        let added = new.iter().filter(|c| find_old(c).is_none()).collect();
+        let removed = old
+            .iter()
+            .filter(|c| !new.iter().any(|n| n.name == c.name))
+            .collect();
+        let changed = new
+            .iter()
+            .filter(|c| find_old(c).is_some_and(|old| old.version != c.version))
+            .collect::<Vec<_>>();
+
+        let mut lines = vec![];
+        for (label, commands) in [("Neu", added), ("Entfernt", removed), ("Gänderet", changed)] {
+            if !commands.is_empty() {
+                lines.push(format!("{label}: {}", format_names(commands)));
+            }
+        }
+
+        if lines.is_empty() {
+            format!("alli {} Befehl sind scho aktuell", new.len())
+        } else {
+            format!("{} Befehl registriert\n{}", new.len(), lines.join("\n"))
+        }
+    }
+}
+
+pub mod owner {
+    use super::*;
+    use crate::{
+        guild_access,
+        guild_access::{AccessRule, GuildAccess},
+    };
+
+    /// How many guilds `/owner guilds` lists.
+    const GUILD_LIST_LIMIT: usize = 25;
+
+    /// How many days of command usage count as activity.
+    const ACTIVITY_DAYS: i64 = 30;
+
+    #[poise::command(
+        slash_command,
+        owners_only,
+        hide_in_help,
+        category = "Verwaltung",
+        subcommands("guilds", "leave", "stats", "access", "globallist")
+    )]
+    pub async fn owner(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Die Server des Bots nach Aktivität auflisten
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn guilds(ctx: Context<'_>) -> Result<()> {
+        owner_guilds_impl(ctx).await.wrap_err("owner_guilds")
+    }
+
+    /// Einen Server verlassen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn leave(
+        ctx: Context<'_>,
+        #[description = "Die ID des Servers"] guild_id: String,
+    ) -> Result<()> {
+        owner_leave_impl(ctx, guild_id)
+            .await
+            .wrap_err("owner_leave")
+    }
+
+    /// Statistiken über alle Server anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn stats(ctx: Context<'_>) -> Result<()> {
+        owner_stats_impl(ctx).await.wrap_err("owner_stats")
+    }
+
+    #[poise::command(
+        slash_command,
+        owners_only,
+        subcommands("access_set", "access_reset", "access_list")
+    )]
+    async fn access(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Einen Server erlauben oder sperren
+    #[poise::command(slash_command, owners_only, ephemeral, rename = "set")]
+    async fn access_set(
+        ctx: Context<'_>,
+        #[description = "Die ID des Servers"] guild_id: String,
+        #[description = "Ob der Bot auf dem Server erlaubt oder gesperrt ist"] rule: AccessRule,
+    ) -> Result<()> {
+        owner_access_set_impl(ctx, guild_id, Some(rule))
+            .await
+            .wrap_err("owner_access_set")
+    }
+
+    /// Einen Server von der Liste der erlaubten und gesperrten Server entfernen
+    #[poise::command(slash_command, owners_only, ephemeral, rename = "reset")]
+    async fn access_reset(
+        ctx: Context<'_>,
+        #[description = "Die ID des Servers"] guild_id: String,
+    ) -> Result<()> {
+        owner_access_set_impl(ctx, guild_id, None)
+            .await
+            .wrap_err("owner_access_reset")
+    }
+
+    /// Die erlaubten und gesperrten Server anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral, rename = "list")]
+    async fn access_list(ctx: Context<'_>) -> Result<()> {
+        owner_access_list_impl(ctx)
+            .await
+            .wrap_err("owner_access_list")
+    }
+
+    #[poise::command(
+        slash_command,
+        owners_only,
+        subcommands("globallist_add", "globallist_remove", "globallist_list")
+    )]
+    async fn globallist(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Jemanden auf die globale Liste setzen, die Server beim Beitritt warnt
+    #[poise::command(slash_command, owners_only, ephemeral, rename = "add")]
+    async fn globallist_add(
+        ctx: Context<'_>,
+        #[description = "Die Person"] user: User,
+        #[description = "Der Grund, den die Server sehen"] reason: String,
+    ) -> Result<()> {
+        owner_globallist_add_impl(ctx, user, reason)
+            .await
+            .wrap_err("owner_globallist_add")
+    }
+
+    /// Jemanden von der globalen Liste entfernen
+    #[poise::command(slash_command, owners_only, ephemeral, rename = "remove")]
+    async fn globallist_remove(
+        ctx: Context<'_>,
+        #[description = "Die Person"] user: User,
+    ) -> Result<()> {
+        owner_globallist_remove_impl(ctx, user)
+            .await
+            .wrap_err("owner_globallist_remove")
+    }
+
+    /// Die globale Liste anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral, rename = "list")]
+    async fn globallist_list(ctx: Context<'_>) -> Result<()> {
+        owner_globallist_list_impl(ctx)
+            .await
+            .wrap_err("owner_globallist_list")
+    }
+
+    async fn parse_guild_id(ctx: Context<'_>, guild_id: &str) -> Result<Option<GuildId>> {
+        let guild_id = guild_id.trim().parse().ok().map(GuildId);
+        if guild_id.is_none() {
+            ctx.say("das isch kei gültige Server-ID").await?;
+        }
+        Ok(guild_id)
+    }
+
+    fn guild_name(ctx: Context<'_>, guild_id: GuildId) -> String {
+        guild_id
+            .name(ctx.discord())
+            .unwrap_or_else(|| guild_id.to_string())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_guilds_impl(ctx: Context<'_>) -> Result<()> {
+        let cache = &ctx.discord().cache;
+        let guild_ids = cache.guilds();
+        let since = mongodb::bson::DateTime::from_millis(
+            mongodb::bson::DateTime::now().timestamp_millis() - ACTIVITY_DAYS * 24 * 60 * 60 * 1000,
+        );
+        let activity = ctx
+            .data()
+            .mongo
+            .guild_activity(since, guild_ids.len() as i64 + 1)
+            .await?;
+        let commands = |guild_id: GuildId| {
+            activity
+                .iter()
+                .find(|activity| activity.guild_id == Some(guild_id.into()))
+                .map_or(0, |activity| activity.count)
+        };
+
+        let mut guilds = guild_ids
+            .iter()
+            .map(|&guild_id| (guild_id, commands(guild_id)))
+            .collect::<Vec<_>>();
+        guilds.sort_by_key(|&(_, commands)| std::cmp::Reverse(commands));
+
+        let mut lines = guilds
+            .iter()
+            .take(GUILD_LIST_LIMIT)
+            .map(|&(guild_id, commands)| {
+                let members = cache
+                    .guild_field(guild_id, |guild| guild.member_count)
+                    .unwrap_or_default();
+                format!(
+                    "{} (`{guild_id}`): {commands} Befehl, {members} Mitglieder",
+                    guild_name(ctx, guild_id)
+                )
+            })
+            .collect::<Vec<_>>();
+        if guilds.len() > GUILD_LIST_LIMIT {
+            lines.push(format!("und {} witeri", guilds.len() - GUILD_LIST_LIMIT));
+        }
+
+        ctx.say(format!(
+            "**{} Server**, Befehl i de letzte {ACTIVITY_DAYS} Täg\n{}",
+            guilds.len(),
+            lines.join("\n")
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_leave_impl(ctx: Context<'_>, guild_id: String) -> Result<()> {
+        let guild_id = match parse_guild_id(ctx, &guild_id).await? {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+
+        let name = guild_name(ctx, guild_id);
+        if !confirm(ctx, format!("söll ich de Server {name} würkli verlah?")).await? {
+            return Ok(());
+        }
+
+        guild_id
+            .leave(&ctx.discord().http)
+            .await
+            .wrap_err("leave guild")?;
+        info!(%guild_id, %name, "Left guild on owner request");
+        ctx.say(format!("ich ha de Server {name} verlah")).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_stats_impl(ctx: Context<'_>) -> Result<()> {
+        let mongo = &ctx.data().mongo;
+        let cache = &ctx.discord().cache;
+
+        let guild_ids = cache.guilds();
+        let members = guild_ids
+            .iter()
+            .filter_map(|&guild_id| cache.guild_field(guild_id, |guild| guild.member_count))
+            .sum::<u64>();
+        let since = mongodb::bson::DateTime::from_millis(
+            mongodb::bson::DateTime::now().timestamp_millis() - ACTIVITY_DAYS * 24 * 60 * 60 * 1000,
+        );
+        let activity = mongo
+            .guild_activity(since, guild_ids.len() as i64 + 1)
+            .await?;
+        let active = activity
+            .iter()
+            .filter(|activity| activity.guild_id.is_some())
+            .count();
+        let commands = activity.iter().map(|activity| activity.count).sum::<i64>();
+        let open_lawsuits = mongo.count_open_lawsuits().await?;
+        let prisoners = mongo.count_prisoners().await?;
+        let access = mongo.find_guild_access().await?;
+        let denied = access
+            .iter()
+            .filter(|entry| entry.rule == AccessRule::Deny)
+            .count();
+
+        ctx.send(|reply| {
+            reply.embed(|embed| {
+                embed
+                    .title("Alli Server")
+                    .field("Server", guild_ids.len(), true)
+                    .field("Mitglieder", members, true)
+                    .field(format!("Aktiv i {ACTIVITY_DAYS} Täg"), active, true)
+                    .field(format!("Befehl i {ACTIVITY_DAYS} Täg"), commands, true)
+                    .field("Offeni Prozess", open_lawsuits, true)
+                    .field("Gfangeni", prisoners, true)
+                    .field("Gsperrti Server", denied, true)
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_access_set_impl(
+        ctx: Context<'_>,
+        guild_id: String,
+        rule: Option<AccessRule>,
+    ) -> Result<()> {
+        let guild_id = match parse_guild_id(ctx, &guild_id).await? {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+        let mongo = &ctx.data().mongo;
+
+        mongo.set_guild_access(guild_id.into(), rule).await?;
+        info!(%guild_id, ?rule, "Changed guild access");
+
+        // other guilds that aren't permitted anymore are left once they are loaded again
+        let access = mongo.find_guild_access().await?;
+        let present = ctx.discord().cache.guilds().contains(&guild_id);
+        if present && !guild_access::is_permitted(&access, guild_id.into()) {
+            let name = guild_name(ctx, guild_id);
+            guild_id
+                .leave(&ctx.discord().http)
+                .await
+                .wrap_err("leave denied guild")?;
+            info!(%guild_id, %name, "Left denied guild");
+            ctx.say(format!("gspeicheret, ich ha de Server {name} verlah"))
+                .await?;
+            return Ok(());
+        }
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_access_list_impl(ctx: Context<'_>) -> Result<()> {
+        let access = ctx.data().mongo.find_guild_access().await?;
+
+        if access.is_empty() {
+            ctx.say("es sind kei Server erlaubt oder gsperrt, de Bot darf uf jede Server")
+                .await?;
+            return Ok(());
+        }
+
+        let line = |entry: &GuildAccess| {
+            let guild_id = GuildId::from(entry.guild_id);
+            format!(
+                "{} (`{guild_id}`): {}",
+                guild_name(ctx, guild_id),
+                entry.rule
+            )
+        };
+        let mut message = access.iter().map(line).collect::<Vec<_>>().join("\n");
+        if access.iter().any(|entry| entry.rule == AccessRule::Allow) {
+            message.push_str("\nAlli andere Server sind gsperrt");
+        }
+        ctx.say(message).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_globallist_add_impl(ctx: Context<'_>, user: User, reason: String) -> Result<()> {
+        let entry = GlobalListEntry {
+            user_id: user.id.into(),
+            reason,
+            added_by: ctx.author().id.into(),
+            added_at: mongodb::bson::DateTime::now(),
+        };
+        ctx.data().mongo.add_global_list_entry(&entry).await?;
+        info!(user_id = %user.id, "Added user to the global list");
+
+        say_message(ctx, MessageKey::Saved, &[]).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_globallist_remove_impl(ctx: Context<'_>, user: User) -> Result<()> {
+        let removed = ctx
+            .data()
+            .mongo
+            .remove_global_list_entry(user.id.into())
+            .await?;
+
+        if removed {
+            info!(user_id = %user.id, "Removed user from the global list");
+            say_message(ctx, MessageKey::Saved, &[]).await?;
+        } else {
+            ctx.say(format!("<@{}> staht nöd uf de globale Liste", user.id))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(ctx))]
+    async fn owner_globallist_list_impl(ctx: Context<'_>) -> Result<()> {
+        let entries = ctx.data().mongo.find_global_list().await?;
+
+        if entries.is_empty() {
+            ctx.say("d'globali Liste isch leer").await?;
+            return Ok(());
+        }
+
+        let message = entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "<@{}> (`{}`): {}",
+                    entry.user_id, entry.user_id, entry.reason
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.say(message).await?;
+
+        Ok(())
+    }
+}
+
+pub mod debug {
+    use chrono_tz::Tz;
+    use mongodb::bson::{self, Bson};
+
+    use super::*;
+    use crate::{docket, economy, events, grand_jury, lawsuit, service};
+
+    /// How many jobs `/debug jobs` shows.
+    const JOBS_LIMIT: usize = 15;
+
+    /// How many events `/debug events` shows, and how many changes per event.
+    const EVENTS_LIMIT: i64 = 15;
+    const EVENT_CHANGES_LIMIT: usize = 5;
+
+    /// A background sweep that can be run without waiting for its interval.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+    pub enum Sweep {
+        #[name = "Löhne"]
+        Salaries,
+        #[name = "Steuern"]
+        Taxes,
+        #[name = "Gemeinnützige Arbeit"]
+        Service,
+        #[name = "Schnellverfahren"]
+        FastTrack,
+        #[name = "Grand Jury"]
+        GrandJury,
+        #[name = "Gerichtsübersicht"]
+        Docket,
+        #[name = "Geplante Aufträge"]
+        Jobs,
+        #[name = "Datenaufbewahrung"]
+        Retention,
+    }
+
+    #[poise::command(
+        slash_command,
+        owners_only,
+        hide_in_help,
+        category = "Verwaltung",
+        subcommands(
+            "state",
+            "invalidate_cache",
+            "sweep",
+            "verbose",
+            "jobs",
+            "events",
+            "event",
+            "replay"
+        )
+    )]
+    pub async fn debug(_: Context<'_>) -> Result<()> {
+        unreachable!()
+    }
+
+    /// Das gespeicherte Dokument eines Servers anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn state(
+        ctx: Context<'_>,
+        #[description = "Die ID des Servers, sonst dieser Server"] guild_id: Option<String>,
+    ) -> Result<()> {
+        debug_state_impl(ctx, guild_id)
+            .await
+            .wrap_err("debug_state")
+    }
+
+    /// Die zwischengespeicherten Einstellungen verwerfen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn invalidate_cache(ctx: Context<'_>) -> Result<()> {
+        debug_invalidate_cache_impl(ctx)
+            .await
+            .wrap_err("debug_invalidate_cache")
+    }
+
+    /// Eine Hintergrundaufgabe sofort ausführen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn sweep(ctx: Context<'_>, #[description = "Die Aufgabe"] sweep: Sweep) -> Result<()> {
+        debug_sweep_impl(ctx, sweep).await.wrap_err("debug_sweep")
+    }
+
+    /// Ausführliches Tracing für einen Server ein- oder ausschalten
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn verbose(
+        ctx: Context<'_>,
+        #[description = "Ob alle Befehle ausführlich geloggt werden"] enabled: bool,
+        #[description = "Die ID des Servers, sonst dieser Server"] guild_id: Option<String>,
+    ) -> Result<()> {
+        debug_verbose_impl(ctx, enabled, guild_id)
+            .await
+            .wrap_err("debug_verbose")
+    }
+
+    /// Die geplanten Aufträge anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn jobs(
+        ctx: Context<'_>,
+        #[description = "Die ID des Servers, sonst alle Server"] guild_id: Option<String>,
+    ) -> Result<()> {
+        debug_jobs_impl(ctx, guild_id).await.wrap_err("debug_jobs")
     }
 
-    /// Den Gerichtsprozess abschliessen und ein Urteil fällen
-    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-    async fn close(ctx: Context<'_>, #[description = "Das Urteil"] verdict: String) -> Result<()> {
-        lawsuit_close_impl(ctx, verdict)
+    /// Die letzten Änderungen an den Daten eines Servers anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn events(
+        ctx: Context<'_>,
+        #[description = "Die ID des Servers, sonst dieser Server"] guild_id: Option<String>,
+        #[description = "Nur Änderungen an dem Pfad, z.B. state.prison_role"] path: Option<String>,
+    ) -> Result<()> {
+        debug_events_impl(ctx, guild_id, path)
             .await
-            .wrap_err("lawsuit_close")
+            .wrap_err("debug_events")
     }
 
-    /// Alle Rechtsprozessdaten löschen
-    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-    async fn clear(ctx: Context<'_>) -> Result<()> {
-        lawsuit_clear_impl(ctx).await.wrap_err("lawsuit_clear")
+    /// Eine Änderung mit allen Werten vorher und nachher anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn event(
+        ctx: Context<'_>,
+        #[description = "Die ID der Änderung"] id: String,
+    ) -> Result<()> {
+        debug_event_impl(ctx, id).await.wrap_err("debug_event")
     }
 
-    #[tracing::instrument(skip(ctx))]
-    async fn lawsuit_create_impl(
+    /// Die Daten eines Servers zu einem früheren Zeitpunkt wiederherstellen und anzeigen
+    #[poise::command(slash_command, owners_only, ephemeral)]
+    async fn replay(
         ctx: Context<'_>,
-        plaintiff: User,
-        accused: User,
-        judge: User,
-        reason: String,
-        plaintiff_lawyer: Option<User>,
-        accused_lawyer: Option<User>,
+        #[description = "Der Zeitpunkt, z.B. 30.06.2022 18:00"] time: String,
+        #[description = "Die ID des Servers, sonst dieser Server"] guild_id: Option<String>,
     ) -> Result<()> {
-        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+        debug_replay_impl(ctx, time, guild_id)
+            .await
+            .wrap_err("debug_replay")
+    }
 
-        let lawsuit = Lawsuit {
-            id: Uuid::new(),
-            plaintiff: plaintiff.id.into(),
-            accused: accused.id.into(),
-            judge: judge.id.into(),
-            plaintiff_lawyer: plaintiff_lawyer.map(|user| user.id.into()),
-            accused_lawyer: accused_lawyer.map(|user| user.id.into()),
-            reason: reason.to_owned(),
-            verdict: None,
-            court_room: SnowflakeId(0),
+    /// The guild with the id, or the current guild if no id was given. Replies if there is none.
+    async fn target_guild(ctx: Context<'_>, guild_id: Option<String>) -> Result<Option<GuildId>> {
+        let guild_id = match guild_id {
+            Some(guild_id) => guild_id.trim().parse().ok().map(GuildId),
+            None => ctx.guild_id(),
         };
+        if guild_id.is_none() {
+            ctx.say("das isch kei gültige Server-ID").await?;
+        }
+        Ok(guild_id)
+    }
 
-        let lawsuit_ctx = LawsuitCtx {
-            lawsuit,
-            mongo_client: ctx.data().mongo.clone(),
-            http: ctx.discord().http.clone(),
-            guild_id,
+    #[tracing::instrument(skip(ctx))]
+    async fn debug_state_impl(ctx: Context<'_>, guild_id: Option<String>) -> Result<()> {
+        let guild_id = match target_guild(ctx, guild_id).await? {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
         };
 
-        let response = lawsuit_ctx
-            .initialize()
-            .await
-            .wrap_err("initialize lawsuit")?;
+        let mongo = ctx.data().mongo.guild(guild_id);
+        let state = match mongo.find_raw_state(guild_id.into()).await? {
+            Some(state) => state,
+            None => {
+                ctx.say(format!("de Server {guild_id} hät no kei Dokument"))
+                    .await?;
+                return Ok(());
+            }
+        };
+        let json = serde_json::to_string_pretty(&Bson::Document(state).into_relaxed_extjson())
+            .wrap_err("format state as json")?;
 
-        ctx.say(response.to_string()).await?;
+        ctx.send(|reply| {
+            reply
+                .content(format!("Dokument vom Server {guild_id}"))
+                .attachment(serenity::AttachmentType::Bytes {
+                    data: json.into_bytes().into(),
+                    filename: format!("state-{guild_id}.json"),
+                })
+        })
+        .await?;
 
         Ok(())
     }
 
     #[tracing::instrument(skip(ctx))]
-    async fn lawsuit_set_category_impl(ctx: Context<'_>, category: Channel) -> Result<()> {
-        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+    async fn debug_jobs_impl(ctx: Context<'_>, guild_id: Option<String>) -> Result<()> {
+        let guild_id = match guild_id {
+            Some(guild_id) => match guild_id.trim().parse() {
+                Ok(guild_id) => Some(SnowflakeId(guild_id)),
+                Err(_) => {
+                    ctx.say("das isch kei gültige Server-ID").await?;
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
 
-        match category.category() {
-            Some(category) => {
-                let id = category.id;
-                ctx.data()
-                    .mongo
-                    .set_court_category(guild_id.into(), id.into())
-                    .await?;
-                ctx.say("isch gsetzt").await?;
+        let mut jobs = Vec::new();
+        match guild_id {
+            Some(guild_id) => {
+                let mongo = ctx.data().mongo.guild(guild_id.into());
+                jobs = mongo.find_jobs(Some(guild_id)).await?;
             }
             None => {
-                ctx.say("Das ist keine Kategorie!").await?;
+                for mongo in ctx.data().mongo.namespaces().await? {
+                    jobs.extend(mongo.find_jobs(None).await?);
+                }
+                jobs.sort_by_key(|job| job.due);
             }
         }
+        if jobs.is_empty() {
+            ctx.say("es sind kei Uufträg plant").await?;
+            return Ok(());
+        }
+
+        let lines = jobs
+            .iter()
+            .take(JOBS_LIMIT)
+            .map(|job| {
+                let status = if job.failed {
+                    "fehlgschlage"
+                } else if job.locked_until.is_some() {
+                    "lauft"
+                } else {
+                    "wartet"
+                };
+                let mut line = format!(
+                    "`{}` {} im Server {}, <t:{}:R>, {status}, {} Versüech",
+                    job.id,
+                    job.kind,
+                    job.guild_id,
+                    job.due.timestamp_millis() / 1000,
+                    job.attempts
+                );
+                if let Some(error) = &job.last_error {
+                    line.push_str(&format!("\n> {error}"));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let more = jobs.len().saturating_sub(JOBS_LIMIT);
+        let more = if more > 0 {
+            format!("\nund {more} witeri")
+        } else {
+            String::new()
+        };
+
+        ctx.say(format!("{lines}{more}")).await?;
 
         Ok(())
     }
 
     #[tracing::instrument(skip(ctx))]
-    async fn lawsuit_close_impl(ctx: Context<'_>, verdict: String) -> Result<()> {
-        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
-
-        let application_context = match ctx {
-            Context::Application(ctx) => ctx,
-            Context::Prefix(_) => return Err(eyre!("wrong context, cannot happen!")),
+    async fn debug_events_impl(
+        ctx: Context<'_>,
+        guild_id: Option<String>,
+        path: Option<String>,
+    ) -> Result<()> {
+        let guild_id = match target_guild(ctx, guild_id).await? {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
         };
 
-        let member = application_context
-            .interaction
-            .member()
-            .wrap_err("member not found")?;
-
-        let permission_override = member
-            .permissions
-            .map(|p| p.contains(Permissions::MANAGE_GUILD))
-            .unwrap_or(false);
+        // events of guilds in sandbox mode are in the shadow database, like the rest of their data
+        let events = ctx
+            .data()
+            .mongo
+            .for_guild(guild_id)
+            .find_events(guild_id.into(), path.as_deref(), EVENTS_LIMIT)
+            .await?;
+        if events.is_empty() {
+            ctx.say("es sind kei Änderige gspeicheret").await?;
+            return Ok(());
+        }
 
-        let room_id = ctx.channel_id();
-        let mongo_client = &ctx.data().mongo;
+        let lines = events
+            .iter()
+            .map(|event| {
+                let actor = match event.actor {
+                    Some(actor) => format!("<@{actor}>"),
+                    None => "em Bot".to_string(),
+                };
+                let mut changes = event
+                    .changes
+                    .iter()
+                    .take(EVENT_CHANGES_LIMIT)
+                    .map(|change| format!("`{change}`"))
+                    .collect::<Vec<_>>();
+                let more = event.changes.len().saturating_sub(EVENT_CHANGES_LIMIT);
+                if more > 0 {
+                    changes.push(format!("und {more} witeri"));
+                }
+                format!(
+                    "`{}` <t:{}:R> {} vo {actor}\n> {}",
+                    event.id,
+                    event.timestamp.timestamp_millis() / 1000,
+                    event.action,
+                    changes.join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
 
-        let state = mongo_client
-            .find_or_insert_state(guild_id.into())
-            .await
-            .wrap_err("find guild for verdict")?;
+        ctx.say(lines).await?;
 
-        let lawsuit = state
-            .lawsuits
-            .iter()
-            .find(|l| l.court_room == room_id.into() && l.verdict.is_none());
+        Ok(())
+    }
 
-        let lawsuit = match lawsuit {
-            Some(lawsuit) => lawsuit.clone(),
-            None => {
-                ctx.say("i dem channel lauft kein aktive prozess!").await?;
+    #[tracing::instrument(skip(ctx))]
+    async fn debug_event_impl(ctx: Context<'_>, id: String) -> Result<()> {
+        let id = match Uuid::parse_str(id.trim()) {
+            Ok(id) => id,
+            Err(_) => {
+                ctx.say("das isch kei gültigi ID").await?;
                 return Ok(());
             }
         };
 
-        let room = state
-            .court_rooms
-            .iter()
-            .find(|r| r.channel_id == room_id.into());
-        let room = match room {
-            Some(room) => room.clone(),
+        // the event might be in the database of any guild or its shadow database, so look in all
+        let mut event = None;
+        for mongo in ctx.data().mongo.namespaces().await? {
+            event = match mongo.find_event(id).await? {
+                Some(event) => Some(event),
+                None => mongo.sandbox().find_event(id).await?,
+            };
+            if event.is_some() {
+                break;
+            }
+        }
+        let event = match event {
+            Some(event) => event,
             None => {
-                ctx.say("i dem channel lauft kein aktive prozess!").await?;
+                ctx.say(format!("d'Änderig `{id}` gits nöd")).await?;
                 return Ok(());
             }
         };
 
-        let mut lawsuit_ctx = LawsuitCtx {
-            lawsuit,
-            mongo_client: mongo_client.clone(),
-            http: ctx.discord().http.clone(),
-            guild_id,
-        };
-
-        let response = lawsuit_ctx
-            .rule_verdict(
-                permission_override,
-                member.user.id,
-                verdict.to_string(),
-                room,
-            )
-            .await?;
-
-        if let Err(response) = response {
-            ctx.say(response.to_string()).await?;
-            return Ok(());
-        }
+        let json = serde_json::to_string_pretty(
+            &bson::to_bson(&event)
+                .wrap_err("serialize event")?
+                .into_relaxed_extjson(),
+        )
+        .wrap_err("format event as json")?;
 
-        ctx.say("ich han en dir abschlosse").await?;
+        ctx.send(|reply| {
+            reply
+                .content(format!(
+                    "{} im Server {}, <t:{}:f>",
+                    event.action,
+                    event.guild_id,
+                    event.timestamp.timestamp_millis() / 1000
+                ))
+                .attachment(serenity::AttachmentType::Bytes {
+                    data: json.into_bytes().into(),
+                    filename: format!("event-{id}.json"),
+                })
+        })
+        .await?;
 
         Ok(())
     }
 
     #[tracing::instrument(skip(ctx))]
-    async fn lawsuit_clear_impl(ctx: Context<'_>) -> Result<()> {
-        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+    async fn debug_replay_impl(
+        ctx: Context<'_>,
+        time: String,
+        guild_id: Option<String>,
+    ) -> Result<()> {
+        let guild_id = match target_guild(ctx, guild_id).await? {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+        let mongo = ctx.data().mongo.for_guild(guild_id);
 
-        ctx.data().mongo.delete_guild(guild_id.into()).await?;
-        ctx.say("alles weg").await?;
-        Ok(())
-    }
-}
+        let mut snapshot = events::snapshot(&mongo, guild_id.into()).await?;
+        let tz = snapshot
+            .get_document("state")
+            .ok()
+            .and_then(|state| state.get_str("timezone").ok())
+            .and_then(parse_timezone)
+            .unwrap_or(Tz::UTC);
 
-pub mod prison {
-    use super::*;
-    #[poise::command(
-        slash_command,
-        guild_only,
-        subcommands("set_role", "arrest", "release")
-    )]
-    pub async fn prison(_: Context<'_>) -> Result<()> {
-        unreachable!()
-    }
+        let time = match parse_date_time(&time, tz) {
+            Some(time) => time,
+            None => {
+                ctx.say("das isch kei gültigi ziit, probier öppis wie `30.06.2022 18:00`")
+                    .await?;
+                return Ok(());
+            }
+        };
 
-    /// Die Rolle für Gefangene setzen
-    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-    async fn set_role(ctx: Context<'_>, #[description = "Die Rolle"] role: Role) -> Result<()> {
-        prison_set_role_impl(ctx, role)
-            .await
-            .wrap_err("prison_set_role")
-    }
+        let events = mongo.find_events_since(guild_id.into(), time).await?;
+        events::revert(&mut snapshot, &events);
 
-    /// Jemanden einsperren
-    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-    async fn arrest(
-        ctx: Context<'_>,
-        #[description = "Die Person zum einsperren"] user: User,
-    ) -> Result<()> {
-        prison_arrest_impl(ctx, user)
-            .await
-            .wrap_err("prison_arrest")
-    }
+        info!(%guild_id, reverted = events.len(), "Replayed guild data");
 
-    /// Einen Gefangenen freilassen
-    #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-    async fn release(
-        ctx: Context<'_>,
-        #[description = "Die Person zum freilassen"] user: User,
-    ) -> Result<()> {
-        prison_release_impl(ctx, user)
-            .await
-            .wrap_err("prison_release")
+        let json = serde_json::to_string_pretty(&Bson::Document(snapshot).into_relaxed_extjson())
+            .wrap_err("format snapshot as json")?;
+
+        ctx.send(|reply| {
+            reply
+                .content(format!(
+                    "Date vom Server {guild_id} am {}, {} Änderige zruggnah",
+                    format_date_time(time, tz),
+                    events.len()
+                ))
+                .attachment(serenity::AttachmentType::Bytes {
+                    data: json.into_bytes().into(),
+                    filename: format!("replay-{guild_id}.json"),
+                })
+        })
+        .await?;
+
+        Ok(())
     }
 
     #[tracing::instrument(skip(ctx))]
-    async fn prison_set_role_impl(ctx: Context<'_>, role: Role) -> Result<()> {
+    async fn debug_invalidate_cache_impl(ctx: Context<'_>) -> Result<()> {
+        let cleared = {
+            let mut reply_settings = ctx
+                .data()
+                .reply_settings
+                .lock()
+                .expect("reply settings lock poisoned");
+            let cleared = reply_settings.len();
+            reply_settings.clear();
+            cleared
+        };
         ctx.data()
-            .mongo
-            .set_prison_role(
-                ctx.guild_id().wrap_err("guild_id not found")?.into(),
-                role.id.into(),
-            )
-            .await?;
+            .keyword_watches
+            .lock()
+            .expect("keyword watches lock poisoned")
+            .clear();
 
-        ctx.say("isch gsetzt").await.wrap_err("reply")?;
+        info!(cleared, "Invalidated reply settings cache");
+
+        ctx.say(format!("{cleared} Server us em Cache glöscht"))
+            .await?;
 
         Ok(())
     }
 
     #[tracing::instrument(skip(ctx))]
-    async fn prison_arrest_impl(ctx: Context<'_>, user: User) -> Result<()> {
-        let mongo_client = &ctx.data().mongo;
-        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
+    async fn debug_sweep_impl(ctx: Context<'_>, sweep: Sweep) -> Result<()> {
+        let mongo = &ctx.data().mongo;
         let http = &ctx.discord().http;
 
-        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
-        let role = state.prison_role;
+        let result = match sweep {
+            Sweep::Salaries => economy::pay_due_salaries(mongo, http).await,
+            Sweep::Taxes => economy::collect_due_taxes(mongo).await,
+            Sweep::Service => service::escalate_overdue_service(mongo, http).await,
+            Sweep::FastTrack => lawsuit::remind_fast_track_judges(mongo, http).await,
+            Sweep::GrandJury => grand_jury::process_pending_indictments(mongo, http).await,
+            Sweep::Docket => docket::refresh_all(mongo, http).await,
+            Sweep::Jobs => scheduler::run_due_jobs(mongo, http).await,
+            Sweep::Retention => crate::retention::purge_all(mongo, http).await,
+        };
 
-        let role = match role {
-            Some(role) => role,
-            None => {
-                ctx.say("du mosch zerst e rolle setze mit /prison set_role")
+        match result {
+            Ok(()) => {
+                info!(?sweep, "Ran sweep manually");
+                ctx.say("d'Uufgab isch durchgloffe").await?;
+            }
+            Err(err) => {
+                error!(?err, ?sweep, "Manual sweep failed");
+                ctx.say(format!("d'Uufgab isch fehlgschlage: {err}"))
                     .await?;
-                return Ok(());
             }
-        };
-
-        mongo_client
-            .add_to_prison(guild_id.into(), user.id.into())
-            .await?;
-
-        guild_id
-            .member(http, user.id)
-            .await
-            .wrap_err("fetching guild member")?
-            .add_role(http, role)
-            .await
-            .wrap_err("add guild member role")?;
-
-        ctx.say("isch igsperrt").await?;
+        }
 
         Ok(())
     }
 
     #[tracing::instrument(skip(ctx))]
-    async fn prison_release_impl(ctx: Context<'_>, user: User) -> Result<()> {
-        let mongo_client = &ctx.data().mongo;
-        let guild_id = ctx.guild_id().wrap_err("guild_id not found")?;
-        let http = &ctx.discord().http;
-
-        let state = mongo_client.find_or_insert_state(guild_id.into()).await?;
-        let role = state.prison_role;
-
-        let role = match role {
-            Some(role) => role,
-            None => {
-                ctx.say("du mosch zerst e rolle setze mit /prison set_role")
-                    .await?;
-                return Ok(());
-            }
+    async fn debug_verbose_impl(
+        ctx: Context<'_>,
+        enabled: bool,
+        guild_id: Option<String>,
+    ) -> Result<()> {
+        let guild_id = match target_guild(ctx, guild_id).await? {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
         };
 
-        mongo_client
-            .remove_from_prison(guild_id.into(), user.id.into())
-            .await?;
+        {
+            let mut verbose_guilds = ctx
+                .data()
+                .verbose_guilds
+                .lock()
+                .expect("verbose guilds lock poisoned");
+            if enabled {
+                verbose_guilds.insert(guild_id);
+            } else {
+                verbose_guilds.remove(&guild_id);
+            }
+        }
 
-        guild_id
-            .member(http, user.id)
-            .await
-            .wrap_err("fetching guild member")?
-            .remove_role(http, role)
-            .await
-            .wrap_err("remove guild member role")?;
+        info!(%guild_id, enabled, "Changed verbose tracing");
 
-        ctx.say("d'freiheit wartet").await?;
+        let state = if enabled { "ii" } else { "us" };
+        ctx.say(format!("ausführlichs Tracing für {guild_id} isch {state}"))
+            .await?;
 
         Ok(())
     }
 }
 
-pub async fn listener(
-    ctx: &serenity::Context,
-    event: &Event<'_>,
-    _: poise::FrameworkContext<'_, Handler, Report>,
-    data: &Handler,
-) -> Result<()> {
-    #[allow(clippy::single_match)]
-    match event {
-        Event::GuildMemberAddition { new_member } => {
-            if let Err(err) = data.handle_guild_member_join(ctx, new_member).await {
-                error!(?err, "An error occurred in guild_member_addition handler");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Discord rejects the registration of commands with more than 25 options per level.
+    #[test]
+    fn commands_have_at_most_25_subcommands() {
+        fn check(commands: &[poise::Command<Handler, Report>], path: &str) {
+            assert!(
+                commands.len() <= 25,
+                "`{path}` has {} subcommands",
+                commands.len()
+            );
+            for command in commands {
+                check(&command.subcommands, &format!("{path} {}", command.name));
             }
         }
-        _ => {}
+        check(&commands(), "");
     }
-    Ok(())
-}
 
-pub async fn error_handler(error: poise::FrameworkError<'_, Handler, Report>) {
-    match error {
-        poise::FrameworkError::MissingUserPermissions { ctx, .. } => {
-            let _ = ctx.say("du häsch kei recht für da!").await;
-        }
-        poise::FrameworkError::GuildOnly { ctx } => {
-            let _ = ctx
-                .say("du chasch de command nur uf emene serve nutze!")
-                .await;
+    #[test]
+    fn fixed_visibilities_name_existing_commands() {
+        // the qualified names are only set by the framework, so they are built here
+        fn names(commands: &[poise::Command<Handler, Report>], prefix: &str) -> Vec<String> {
+            commands
+                .iter()
+                .flat_map(|command| {
+                    let name = format!("{prefix}{}", command.name);
+                    let mut names = names(&command.subcommands, &format!("{name} "));
+                    names.push(name);
+                    names
+                })
+                .collect()
         }
-        err => {
-            error!(?err, "Error during command execution");
+        let names = names(&commands(), "");
+
+        for command in ALWAYS_PUBLIC.iter().chain(&ALWAYS_PRIVATE) {
+            assert!(
+                names.contains(&command.to_string()),
+                "`/{command}` doesn't exist"
+            );
         }
     }
+
+    #[test]
+    fn modals_are_scoped_to_their_interaction() {
+        let mut first = StatementModal::create(None);
+        scope_modal(&mut first, &modal_id(serenity::InteractionId(1)));
+        let mut second = StatementModal::create(None);
+        scope_modal(&mut second, &modal_id(serenity::InteractionId(2)));
+
+        let custom_id = |response: &serenity::CreateInteractionResponse| {
+            response.0["data"]["custom_id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(custom_id(&first), "modal:1");
+        assert_eq!(custom_id(&second), "modal:2");
+        assert_eq!(first.0["data"]["title"], "Stellungnahm");
+    }
+
+    #[test]
+    fn long_lists_are_split_into_fields() {
+        let lines = (0..40)
+            .map(|i| format!("`/lawsuit {i}` – {}", "x".repeat(60)))
+            .collect::<Vec<_>>();
+
+        let fields = help::field_chunks("Befehle", &lines);
+
+        assert!(fields.len() > 1);
+        assert_eq!(fields[0].0, "Befehle");
+        assert!(fields[1..].iter().all(|(title, _)| title == "\u{200b}"));
+        assert!(fields
+            .iter()
+            .all(|(_, value)| value.chars().count() <= help::FIELD_LIMIT));
+        let joined = fields
+            .iter()
+            .map(|(_, value)| value.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(joined, lines.join("\n"));
+    }
+
+    #[test]
+    fn overlong_lines_are_cut() {
+        let fields = help::field_chunks("Parameter", &["y".repeat(2000)]);
+
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].1.chars().count(), help::FIELD_LIMIT);
+        assert!(fields[0].1.ends_with('…'));
+        assert!(help::field_chunks("Parameter", &[]).is_empty());
+    }
 }
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use color_eyre::{eyre::eyre, Result};
+use mongodb::bson::{DateTime, Uuid};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    handler::Response,
+    model::SnowflakeId,
+    moderation::{ModerationAction, ModerationKind},
+    Mongo, WrapErr,
+};
+
+/// The names other bots use for the columns of their exports, compared in lowercase.
+const USER_COLUMNS: &[&str] = &[
+    "user_id",
+    "userid",
+    "user",
+    "target_id",
+    "target",
+    "member_id",
+];
+const KIND_COLUMNS: &[&str] = &["type", "action", "kind", "case_type", "punishment"];
+const MODERATOR_COLUMNS: &[&str] = &[
+    "moderator_id",
+    "mod_id",
+    "moderator",
+    "responsible_moderator",
+];
+const REASON_COLUMNS: &[&str] = &["reason", "note"];
+const TIMESTAMP_COLUMNS: &[&str] = &["timestamp", "created_at", "date", "time"];
+const UNTIL_COLUMNS: &[&str] = &["expires_at", "expires", "until", "end"];
+
+/// The outcome of an import.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Already imported before.
+    pub duplicates: usize,
+    /// Entries with an unknown action, like unbans or notes, or without a user.
+    pub skipped: usize,
+}
+
+/// Imports warns, mutes, kicks and bans from the CSV or JSON export of another moderation bot,
+/// like Dyno or Carl-bot, into the criminal record.
+#[tracing::instrument(skip(mongo, content))]
+pub async fn import(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    file_name: &str,
+    content: &str,
+) -> Result<Result<ImportSummary, Response>> {
+    let rows = if file_name.to_lowercase().ends_with(".json") {
+        match parse_json(content) {
+            Ok(rows) => rows,
+            Err(err) => {
+                info!(?err, "Invalid import file");
+                return Ok(Err(Response("d'Datei isch kei gültigs JSON".to_string())));
+            }
+        }
+    } else {
+        parse_csv(content)
+    };
+
+    let existing = mongo
+        .find_guild_moderation_actions(guild_id)
+        .await?
+        .into_iter()
+        .map(|action| (action.user_id, action.kind, action.timestamp))
+        .collect::<HashSet<_>>();
+
+    let mut summary = ImportSummary::default();
+    let mut actions = Vec::new();
+    for row in &rows {
+        match to_action(guild_id, row) {
+            Some(action) if existing.contains(&(action.user_id, action.kind, action.timestamp)) => {
+                summary.duplicates += 1
+            }
+            Some(action) => actions.push(action),
+            None => summary.skipped += 1,
+        }
+    }
+
+    summary.imported = actions.len();
+    mongo.add_moderation_actions(&actions).await?;
+
+    info!(?summary, "Imported moderation history");
+
+    Ok(Ok(summary))
+}
+
+fn to_action(guild_id: SnowflakeId, row: &HashMap<String, String>) -> Option<ModerationAction> {
+    let field = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| row.get(*name))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+
+    let kind = match field(KIND_COLUMNS)?.to_lowercase().as_str() {
+        "warn" | "warning" => ModerationKind::Warn,
+        "mute" | "tempmute" | "timeout" => ModerationKind::Timeout,
+        "kick" => ModerationKind::Kick,
+        "ban" | "tempban" | "softban" | "hackban" | "forceban" => ModerationKind::Ban,
+        _ => return None,
+    };
+
+    Some(ModerationAction {
+        id: Uuid::new(),
+        guild_id,
+        user_id: parse_id(field(USER_COLUMNS)?)?,
+        kind,
+        moderator: field(MODERATOR_COLUMNS).and_then(parse_id),
+        reason: field(REASON_COLUMNS).map(str::to_string),
+        until: field(UNTIL_COLUMNS).and_then(parse_timestamp),
+        timestamp: field(TIMESTAMP_COLUMNS)
+            .and_then(parse_timestamp)
+            .unwrap_or_else(DateTime::now),
+        imported: true,
+    })
+}
+
+/// Exports often contain the name next to the id, like `name#0001 (123456789)`.
+fn parse_id(value: &str) -> Option<SnowflakeId> {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| part.len() >= 15)
+        .find_map(|part| part.parse().ok())
+        .map(SnowflakeId)
+}
+
+/// Accepts unix timestamps in seconds or milliseconds, RFC 3339 and `2022-06-30 18:00:00` in UTC.
+fn parse_timestamp(value: &str) -> Option<DateTime> {
+    if let Ok(number) = value.parse::<f64>() {
+        let millis = if number > 1e12 {
+            number
+        } else {
+            number * 1000.0
+        };
+        return Some(DateTime::from_millis(millis as i64));
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(DateTime::from_millis(time.timestamp_millis()));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+        .ok()
+        .map(|time| DateTime::from_millis(time.timestamp_millis()))
+}
+
+/// Reads an array of objects, or an object containing one.
+fn parse_json(content: &str) -> Result<Vec<HashMap<String, String>>> {
+    let value = serde_json::from_str::<Value>(content).wrap_err("invalid json")?;
+
+    let entries = match value {
+        Value::Array(entries) => entries,
+        Value::Object(object) => object
+            .into_iter()
+            .find_map(|(_, value)| match value {
+                Value::Array(entries) => Some(entries),
+                _ => None,
+            })
+            .ok_or_else(|| eyre!("no list of entries in json"))?,
+        _ => return Err(eyre!("no list of entries in json")),
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Value::Object(object) => Some(
+                object
+                    .into_iter()
+                    .filter_map(|(key, value)| {
+                        let value = match value {
+                            Value::String(value) => value,
+                            Value::Number(number) => number.to_string(),
+                            // like `"user": { "id": "...", "name": "..." }`
+                            Value::Object(object) => match object.get("id") {
+                                Some(Value::String(id)) => id.clone(),
+                                Some(Value::Number(id)) => id.to_string(),
+                                _ => return None,
+                            },
+                            _ => return None,
+                        };
+                        Some((key.to_lowercase(), value))
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Reads a CSV file with a header row.
+fn parse_csv(content: &str) -> Vec<HashMap<String, String>> {
+    let mut records = csv_records(content).into_iter();
+    let header = match records.next() {
+        Some(header) => header
+            .into_iter()
+            .map(|name| name.trim().to_lowercase().replace(' ', "_"))
+            .collect::<Vec<_>>(),
+        None => return vec![],
+    };
+
+    records
+        .map(|record| header.iter().cloned().zip(record).collect())
+        .collect()
+}
+
+fn csv_records(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
@@ -0,0 +1,91 @@
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+
+/// A modal can't have more text inputs.
+pub const MAX_QUESTIONS: usize = 5;
+
+/// The longest label of a text input in a modal.
+pub const MAX_QUESTION_LEN: usize = 45;
+
+/// The longest answer to a question.
+const MAX_ANSWER_LEN: u64 = 1000;
+
+/// What the plaintiff answered to a question of the intake form when filing the lawsuit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntakeAnswer {
+    pub question: String,
+    pub answer: String,
+}
+
+/// The modal that asks the questions of the intake form, all of them required.
+pub fn modal(
+    custom_id: &str,
+    questions: &[String],
+) -> serenity::CreateInteractionResponse<'static> {
+    let mut response = serenity::CreateInteractionResponse::default();
+    response.kind(serenity::InteractionResponseType::Modal);
+    response.interaction_response_data(|data| {
+        data.custom_id(custom_id)
+            .title("Klag iireiche")
+            .components(|components| {
+                for (index, question) in questions.iter().enumerate() {
+                    components.create_action_row(|row| {
+                        row.create_input_text(|input| {
+                            input
+                                .custom_id(index.to_string())
+                                .label(question)
+                                .style(serenity::InputTextStyle::Paragraph)
+                                .required(true)
+                                .max_length(MAX_ANSWER_LEN)
+                        })
+                    });
+                }
+                components
+            })
+    });
+    response
+}
+
+/// Reads the answers out of the submitted modal. Questions without an answer are left out.
+pub fn parse(
+    questions: &[String],
+    mut data: serenity::ModalSubmitInteractionData,
+) -> Vec<IntakeAnswer> {
+    let answers = (0..questions.len())
+        .map(|index| poise::find_modal_text(&mut data, &index.to_string()))
+        .collect::<Vec<_>>();
+    answered(questions, answers)
+}
+
+fn answered(questions: &[String], answers: Vec<Option<String>>) -> Vec<IntakeAnswer> {
+    questions
+        .iter()
+        .zip(answers)
+        .filter_map(|(question, answer)| {
+            Some(IntakeAnswer {
+                question: question.clone(),
+                answer: answer?.trim().to_string(),
+            })
+        })
+        .filter(|answer| !answer.answer.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{answered, IntakeAnswer};
+
+    #[test]
+    fn unanswered_questions() {
+        let questions = vec!["Was isch passiert?".to_string(), "Zeuge?".to_string()];
+        let answers = answered(&questions, vec![Some(" alles ".to_string()), None]);
+        assert_eq!(
+            answers,
+            vec![IntakeAnswer {
+                question: "Was isch passiert?".to_string(),
+                answer: "alles".to_string(),
+            }]
+        );
+        assert!(answered(&questions, vec![Some("  ".to_string()), None]).is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use color_eyre::Result;
+use poise::serenity_prelude::{ButtonStyle, ChannelId, Http, Message, UserId};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{model::SnowflakeId, WrapErr};
+
+/// The prefix of the custom id of the report buttons, followed by `{file}:{accused}`.
+pub const REPORT_ID_PREFIX: &str = "keyword-report:";
+
+/// The maximum length of the message content that is quoted in a report.
+const QUOTE_LIMIT: usize = 1000;
+
+/// Messages containing one of the words are reported in the review channel, where moderators can
+/// turn them into a lawsuit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordWatch {
+    /// Lowercase trigger words.
+    pub words: Vec<String>,
+    pub channel_id: SnowflakeId,
+}
+
+impl KeywordWatch {
+    pub fn matching_word(&self, content: &str) -> Option<&str> {
+        let content = content.to_lowercase();
+        self.words
+            .iter()
+            .find(|word| content.contains(word.as_str()))
+            .map(String::as_str)
+    }
+}
+
+/// Parses the `{file}:{accused}` part of a report custom id.
+pub fn parse_report_id(id: &str) -> Option<(bool, UserId)> {
+    let (file, accused) = id.split_once(':')?;
+    Some((file.parse().ok()?, UserId(accused.parse().ok()?)))
+}
+
+/// Reports the message in the review channel.
+#[tracing::instrument(skip(http, watch, message), fields(message_id = %message.id))]
+pub async fn report(
+    http: &Http,
+    watch: &KeywordWatch,
+    message: &Message,
+    word: &str,
+) -> Result<()> {
+    let mut quote = message
+        .content
+        .chars()
+        .take(QUOTE_LIMIT)
+        .collect::<String>();
+    if quote.len() < message.content.len() {
+        quote.push('…');
+    }
+    let link = message.link();
+    let reason = format!("Verstoss gege `{word}` ({link}): {quote}");
+
+    send_report(
+        http,
+        watch.channel_id,
+        message.author.id,
+        reason,
+        format!("Stichwort in <#{}>", message.channel_id),
+    )
+    .await?;
+
+    info!(accused = %message.author.id, word, "Reported message with trigger word");
+
+    Ok(())
+}
+
+/// Posts a report to the review channel with buttons to file a lawsuit or discard the report.
+/// The description of the embed is used as the reason of the lawsuit.
+pub async fn send_report(
+    http: &Http,
+    channel_id: SnowflakeId,
+    accused: UserId,
+    reason: String,
+    origin: String,
+) -> Result<()> {
+    ChannelId::from(channel_id)
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title("Verdacht uf en Verstoss")
+                    .description(reason)
+                    .field("Verdächtigte", format!("<@{accused}>"), true)
+                    .field("Quelle", origin, true)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(format!("{REPORT_ID_PREFIX}true:{accused}"))
+                            .label("Aklag erhebe")
+                            .style(ButtonStyle::Danger)
+                    })
+                    .create_button(|button| {
+                        button
+                            .custom_id(format!("{REPORT_ID_PREFIX}false:{accused}"))
+                            .label("Verwerfe")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await
+        .wrap_err("send report")?;
+
+    Ok(())
+}
@@ -1,23 +1,197 @@
-use std::sync::Arc;
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
 
+use chrono_tz::Tz;
 use color_eyre::Result;
-use mongodb::bson::{doc, Uuid};
+use futures::TryStreamExt;
+use mongodb::{
+    bson,
+    bson::{doc, Uuid},
+};
 use poise::{
     serenity::model::prelude::*,
-    serenity_prelude::{CreateMessage, Http},
+    serenity_prelude::{ButtonStyle, CreateMessage, Http},
 };
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
+    branding,
+    branding::Branding,
+    bulk,
+    bulk::{BulkOp, BulkReport},
+    config::TaskInterval,
+    discord_api::DiscordApi,
+    docket,
+    duration::{format_date, format_date_time, format_duration},
+    economy,
+    economy::{format_amount, Debt, TransactionKind},
+    grand_jury,
+    grand_jury::Proceeding,
     handler::Response,
-    model::{CourtRoom, SnowflakeId},
+    intake::IntakeAnswer,
+    maintenance::Maintenance,
+    model::{CourtRoom, PrisonEntry, SnowflakeId, State},
+    notification, opinion_poll,
+    opinion_poll::OpinionPoll,
+    ordeal::OrdealKind,
+    prison, probation, sandbox,
+    sandbox::SimulatedDiscord,
+    scheduler,
+    scheduler::JobKind,
+    service::ServiceObligation,
+    storage::Storage,
+    subpoena::Subpoena,
+    webhook,
+    webhook::WebhookEvent,
     Mongo, WrapErr,
 };
 
+/// The prefix of the custom id of statement buttons and modals, followed by the lawsuit id.
+pub const STATEMENT_ID_PREFIX: &str = "lawsuit-statement:";
+
+/// How long the judge has to rule on a fast track lawsuit.
+pub const FAST_TRACK_DEADLINE: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// How long before the deadline the judge gets reminded.
+const FAST_TRACK_REMINDER: Duration = Duration::from_secs(60 * 60 * 2);
+
+/// How often the fast track task checks the deadlines.
+const FAST_TRACK_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long before a deadline of a phase the parties get reminded.
+const DEADLINE_REMINDER: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// How long the accused has for community service if the verdict doesn't say.
+const DEFAULT_SERVICE_DEADLINE: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum Party {
+    #[name = "Kläger"]
+    Plaintiff,
+    #[name = "Angeklagter"]
+    Accused,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseType {
+    #[name = "Strafrecht"]
+    Criminal,
+    #[name = "Zivilrecht"]
+    Civil,
+    #[name = "Berufung"]
+    Appeal,
+    #[name = "Bagatellsache"]
+    SmallClaims,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LawsuitStatus {
+    /// The lawsuit has a court room, or had one if there is a verdict.
+    #[default]
+    Active,
+    /// The grand jury hasn't decided about an indictment yet.
+    PendingIndictment,
+    /// Indicted, a court room is being opened.
+    Opening,
+    /// The grand jury decided against an indictment.
+    NoBilled,
+    /// The plaintiff dropped the lawsuit.
+    Withdrawn,
+    /// The judge threw the lawsuit out without a verdict.
+    Dismissed,
+    /// The lawsuit was a duplicate and continues as part of another lawsuit.
+    Merged,
+}
+
+/// What happens to an open lawsuit when the plaintiff or the accused leaves the guild.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaveAction {
+    /// Only record it in the history and tell the judge.
+    #[default]
+    #[name = "Nur vermerken"]
+    Annotate,
+    /// Pause the lawsuit until the member rejoins.
+    #[name = "Pausieren"]
+    Pause,
+    #[name = "Abweisen"]
+    Dismiss,
+}
+
+impl Display for CaseType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Criminal => "Strafrecht",
+            Self::Civil => "Zivilrecht",
+            Self::Appeal => "Berufung",
+            Self::SmallClaims => "Bagatellsache",
+        })
+    }
+}
+
+impl Party {
+    pub fn opponent(self) -> Self {
+        match self {
+            Self::Plaintiff => Self::Accused,
+            Self::Accused => Self::Plaintiff,
+        }
+    }
+}
+
+/// A step of a lawsuit that the judge can give a deadline. Once it's over, the actions of the
+/// phase are locked for the parties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    #[name = "Stellungnahme"]
+    Statements,
+    #[name = "Klageänderung"]
+    Amendments,
+    #[name = "Anwaltswechsel"]
+    Lawyers,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Statements => "Stellungnahme",
+            Self::Amendments => "Klageänderung",
+            Self::Lawyers => "Anwaltswechsel",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deadline {
+    pub phase: Phase,
+    pub due: bson::DateTime,
+}
+
+impl Display for Party {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Plaintiff => "Kläger",
+            Self::Accused => "Angeklagter",
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Lawsuit {
     pub id: Uuid,
+    /// The human readable case number, unique per guild.
+    #[serde(default)]
+    pub number: u64,
     pub plaintiff: SnowflakeId,
     pub accused: SnowflakeId,
     pub plaintiff_lawyer: Option<SnowflakeId>,
@@ -26,6 +200,446 @@ pub struct Lawsuit {
     pub reason: String,
     pub verdict: Option<String>,
     pub court_room: SnowflakeId,
+    #[serde(default)]
+    pub winner: Option<Party>,
+    /// The filing fee the plaintiff paid when creating the lawsuit.
+    #[serde(default)]
+    pub filing_fee: Option<i64>,
+    /// Damages the losing party has to pay to the winner.
+    #[serde(default)]
+    pub damages: Option<i64>,
+    /// Hours of community service the accused was sentenced to.
+    #[serde(default)]
+    pub community_service: Option<u32>,
+    /// How long the accused was sentenced to prison.
+    #[serde(default)]
+    pub prison_secs: Option<u64>,
+    /// How long the prison sentence is suspended on probation.
+    #[serde(default)]
+    pub probation_secs: Option<u64>,
+    /// How long the accused was already in prison before the verdict. It's credited to the prison
+    /// sentence.
+    #[serde(default)]
+    pub time_served_secs: Option<u64>,
+    /// When the accused was detained pending trial. Cleared once the detention ends.
+    #[serde(default)]
+    pub detained_at: Option<bson::DateTime>,
+    /// When the detention is reviewed next.
+    #[serde(default)]
+    pub detention_review: Option<bson::DateTime>,
+    #[serde(default)]
+    pub case_type: Option<CaseType>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Small claims that are handled without lawyers and with a decision deadline.
+    #[serde(default)]
+    pub fast_track: bool,
+    /// The amount of money the plaintiff is claiming.
+    #[serde(default)]
+    pub claim_amount: Option<i64>,
+    #[serde(default)]
+    pub decision_deadline: Option<bson::DateTime>,
+    /// How many deadline reminders were already sent to the judge.
+    #[serde(default)]
+    pub reminders_sent: u8,
+    #[serde(default)]
+    pub statements: Vec<Statement>,
+    /// Arbitrations are ruled on via DM and don't have a court room.
+    #[serde(default)]
+    pub arbitration: bool,
+    #[serde(default)]
+    pub status: LawsuitStatus,
+    #[serde(default)]
+    pub grand_jury: Option<Proceeding>,
+    /// When the incident the lawsuit is about happened, as stated by the plaintiff.
+    #[serde(default)]
+    pub incident_date: Option<bson::DateTime>,
+    /// Changes made to the lawsuit after it was created.
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    /// Why the lawsuit was withdrawn or dismissed.
+    #[serde(default)]
+    pub termination_reason: Option<String>,
+    /// Lawsuits from before this was recorded don't have it.
+    #[serde(default)]
+    pub created_at: Option<bson::DateTime>,
+    /// People from merged lawsuits who are neither plaintiff, accused, their lawyers or the judge.
+    #[serde(default)]
+    pub additional_parties: Vec<SnowflakeId>,
+    /// The case number of the lawsuit this one was merged into.
+    #[serde(default)]
+    pub merged_into: Option<u64>,
+    /// Anonymous plaintiffs are hidden in public embeds and don't get access to the court room.
+    /// Only the judge and admins can look them up with `/lawsuit info`.
+    #[serde(default)]
+    pub anonymous: bool,
+    /// Members who get a DM when something about the lawsuit changes.
+    #[serde(default)]
+    pub followers: Vec<SnowflakeId>,
+    /// Sealed lawsuits are not announced publicly.
+    #[serde(default)]
+    pub sealed: bool,
+    #[serde(default)]
+    pub hearings: Vec<Hearing>,
+    /// Paused because the plaintiff or the accused left the guild.
+    #[serde(default)]
+    pub paused: bool,
+    /// The case number of the lawsuit this appeal is against.
+    #[serde(default)]
+    pub appeal_of: Option<u64>,
+    /// When the verdict was given or the lawsuit ended otherwise. Lawsuits closed before this was
+    /// recorded don't have it.
+    #[serde(default)]
+    pub closed_at: Option<bson::DateTime>,
+    /// At most one per phase.
+    #[serde(default)]
+    pub deadlines: Vec<Deadline>,
+    /// The witnesses that were summoned to a hearing.
+    #[serde(default)]
+    pub subpoenas: Vec<Subpoena>,
+    /// Until when the verdict can be appealed. Set while the verdict waits to be executed.
+    #[serde(default)]
+    pub appealable_until: Option<bson::DateTime>,
+    /// When the verdict was executed, so that reopening the lawsuit reverses it. Verdicts
+    /// executed before this was recorded don't have it.
+    #[serde(default)]
+    pub executed_at: Option<bson::DateTime>,
+    /// How long the accused has for the community service once the verdict is executed.
+    #[serde(default)]
+    pub community_service_secs: Option<u64>,
+    /// The answers to the intake form of the guild.
+    #[serde(default)]
+    pub intake: Vec<IntakeAnswer>,
+    /// The vote of the spectators on the verdict, if the guild has them.
+    #[serde(default)]
+    pub opinion_poll: Option<OpinionPoll>,
+    /// Set if the verdict was decided by an ordeal instead of the judge.
+    #[serde(default)]
+    pub ordeal: Option<OrdealKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hearing {
+    pub time: bson::DateTime,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: bson::DateTime,
+    /// Who made the change.
+    pub actor: SnowflakeId,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    pub party: Party,
+    pub text: String,
+}
+
+/// Everything the judge decided when closing a lawsuit.
+#[derive(Debug, Clone)]
+pub struct Ruling {
+    pub verdict: String,
+    pub winner: Option<Party>,
+    pub damages: Option<i64>,
+    pub community_service: Option<CommunityService>,
+    pub prison: Option<PrisonSentence>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CommunityService {
+    pub hours: u32,
+    /// How long the accused has to complete the hours before being arrested.
+    pub deadline: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PrisonSentence {
+    pub duration: Duration,
+    /// If set, the sentence is suspended and only activated by a conviction or arrest within it.
+    pub probation: Option<Duration>,
+}
+
+impl Lawsuit {
+    pub fn new(
+        plaintiff: SnowflakeId,
+        accused: SnowflakeId,
+        judge: SnowflakeId,
+        reason: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new(),
+            number: 0,
+            plaintiff,
+            accused,
+            plaintiff_lawyer: None,
+            accused_lawyer: None,
+            judge,
+            reason,
+            verdict: None,
+            court_room: SnowflakeId(0),
+            winner: None,
+            filing_fee: None,
+            damages: None,
+            community_service: None,
+            prison_secs: None,
+            probation_secs: None,
+            time_served_secs: None,
+            detained_at: None,
+            detention_review: None,
+            case_type: None,
+            tags: vec![],
+            fast_track: false,
+            claim_amount: None,
+            decision_deadline: None,
+            reminders_sent: 0,
+            statements: vec![],
+            arbitration: false,
+            status: LawsuitStatus::Active,
+            grand_jury: None,
+            incident_date: None,
+            history: vec![],
+            termination_reason: None,
+            created_at: Some(bson::DateTime::now()),
+            additional_parties: vec![],
+            merged_into: None,
+            anonymous: false,
+            followers: vec![],
+            sealed: false,
+            hearings: vec![],
+            paused: false,
+            appeal_of: None,
+            closed_at: None,
+            deadlines: vec![],
+            subpoenas: vec![],
+            appealable_until: None,
+            executed_at: None,
+            community_service_secs: None,
+            intake: vec![],
+            opinion_poll: None,
+            ordeal: None,
+        }
+    }
+
+    pub fn deadline(&self, phase: Phase) -> Option<&Deadline> {
+        self.deadlines
+            .iter()
+            .find(|deadline| deadline.phase == phase)
+    }
+
+    /// Whether the deadline of the phase is over, so that the parties can't act in it anymore.
+    pub fn is_locked(&self, phase: Phase, now: bson::DateTime) -> bool {
+        self.deadline(phase)
+            .is_some_and(|deadline| deadline.due <= now)
+    }
+
+    pub fn party(&self, party: Party) -> SnowflakeId {
+        match party {
+            Party::Plaintiff => self.plaintiff,
+            Party::Accused => self.accused,
+        }
+    }
+
+    /// How the plaintiff is shown in public, which hides them if they filed anonymously.
+    pub fn plaintiff_mention(&self) -> String {
+        if self.anonymous {
+            "Anonym".to_string()
+        } else {
+            format!("<@{}>", self.plaintiff)
+        }
+    }
+
+    /// When the lawsuit was closed. For lawsuits closed before that was recorded, the time of the
+    /// last change is used instead.
+    pub fn closed_time(&self) -> Option<bson::DateTime> {
+        if !self.is_closed() {
+            return None;
+        }
+        self.closed_at.or_else(|| {
+            self.history
+                .iter()
+                .map(|entry| entry.timestamp)
+                .chain(self.created_at)
+                .max()
+        })
+    }
+
+    /// Whether there is an open appeal against the lawsuit.
+    pub fn is_under_appeal(&self, lawsuits: &[Lawsuit]) -> bool {
+        lawsuits
+            .iter()
+            .any(|appeal| appeal.appeal_of == Some(self.number) && !appeal.is_closed())
+    }
+
+    /// The prison sentence of the verdict, like `3d uf Bewährig (30d)`, or with the credited time
+    /// served, like `3d - 1d Untersuechigshaft = 2d`.
+    pub fn prison_sentence(&self) -> Option<String> {
+        let sentence = format_duration(Duration::from_secs(self.prison_secs?));
+        Some(match (self.probation_secs, self.time_served_secs) {
+            (Some(probation), _) => format!(
+                "{sentence} uf Bewährig ({})",
+                format_duration(Duration::from_secs(probation))
+            ),
+            (None, Some(served)) => {
+                let remaining = self.remaining_prison_secs().unwrap_or_default();
+                let remaining = match remaining {
+                    0 => "abgsässe".to_string(),
+                    _ => format_duration(Duration::from_secs(remaining)),
+                };
+                format!(
+                    "{sentence} - {} Untersuechigshaft = {remaining}",
+                    format_duration(Duration::from_secs(served))
+                )
+            }
+            (None, None) => sentence,
+        })
+    }
+
+    /// The prison sentence minus the time served.
+    pub fn remaining_prison_secs(&self) -> Option<u64> {
+        let served = self.time_served_secs.unwrap_or_default();
+        Some(self.prison_secs?.saturating_sub(served))
+    }
+
+    /// How long the accused has been in prison since the lawsuit was filed, if they are still there.
+    pub fn time_served(&self, entry: Option<&PrisonEntry>, now: bson::DateTime) -> Option<u64> {
+        let arrested_at = entry?.arrested_at?;
+        let start = match self.created_at {
+            Some(created_at) => arrested_at.max(created_at),
+            None => arrested_at,
+        };
+        let secs = (now.timestamp_millis() - start.timestamp_millis()) / 1000;
+        (secs > 0).then_some(secs as u64)
+    }
+
+    /// Whether the verdict still waits to be executed and no appeal against it is open.
+    pub fn awaits_execution(&self, lawsuits: &[Lawsuit]) -> bool {
+        self.appealable_until.is_some() && !self.is_under_appeal(lawsuits)
+    }
+
+    /// Whether the user may see the lawsuit in lists and statistics. Sealed lawsuits are only
+    /// visible to their judge and admins.
+    pub fn is_visible_to(&self, user_id: SnowflakeId, admin: bool) -> bool {
+        !self.sealed || admin || self.judge == user_id
+    }
+
+    /// Whether the lawsuit is over, either with a verdict or because it never went to trial.
+    pub fn is_closed(&self) -> bool {
+        self.verdict.is_some()
+            || matches!(
+                self.status,
+                LawsuitStatus::NoBilled
+                    | LawsuitStatus::Withdrawn
+                    | LawsuitStatus::Dismissed
+                    | LawsuitStatus::Merged
+            )
+    }
+
+    /// Everyone who has access to the court room. A plaintiff who filed anonymously isn't one of
+    /// them, so that nobody learns who they are from the room's members.
+    pub fn participants(&self) -> Vec<SnowflakeId> {
+        [
+            Some(self.plaintiff).filter(|_| !self.anonymous),
+            self.plaintiff_lawyer,
+            Some(self.accused),
+            self.accused_lawyer,
+            Some(self.judge),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.additional_parties.iter().copied())
+        .collect()
+    }
+
+    /// Everyone involved in the lawsuit, including a plaintiff who filed anonymously.
+    pub fn involved(&self) -> Vec<SnowflakeId> {
+        let mut involved = self.participants();
+        if self.anonymous {
+            involved.insert(0, self.plaintiff);
+        }
+        involved
+    }
+
+    pub fn lawyer(&self, party: Party) -> Option<SnowflakeId> {
+        match party {
+            Party::Plaintiff => self.plaintiff_lawyer,
+            Party::Accused => self.accused_lawyer,
+        }
+    }
+
+    /// Whether the lawsuit was created before the given time. Lawsuits without a creation date
+    /// are always considered older.
+    pub fn created_before(&self, time: bson::DateTime) -> bool {
+        self.created_at
+            .is_none_or(|created_at| created_at.timestamp_millis() < time.timestamp_millis())
+    }
+
+    /// The earliest hearing that is still in the future.
+    pub fn next_hearing(&self) -> Option<&Hearing> {
+        let now = bson::DateTime::now().timestamp_millis();
+        self.hearings
+            .iter()
+            .filter(|hearing| hearing.time.timestamp_millis() > now)
+            .min_by_key(|hearing| hearing.time.timestamp_millis())
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            LawsuitStatus::PendingIndictment => "Anklage ausstehend",
+            LawsuitStatus::Opening => "wird eröffnet",
+            LawsuitStatus::NoBilled => "nicht angeklagt",
+            LawsuitStatus::Withdrawn => "zurückgezogen",
+            LawsuitStatus::Dismissed => "abgewiesen",
+            LawsuitStatus::Merged => "zusammengeführt",
+            LawsuitStatus::Active if self.appealable_until.is_some() => "anfechtbar",
+            LawsuitStatus::Active if self.verdict.is_some() => "abgeschlossen",
+            LawsuitStatus::Active if self.paused => "pausiert",
+            LawsuitStatus::Active => "offen",
+        }
+    }
+
+    /// Formats the lawsuit as a single line for listings.
+    pub fn summary(&self) -> String {
+        let status = self.status_name();
+        let case_type = self
+            .case_type
+            .map(|case_type| format!(" [{case_type}]"))
+            .unwrap_or_default();
+        let arbitration = if self.arbitration {
+            " [Schiedsverfahre]"
+        } else {
+            ""
+        };
+        let merged_into = self
+            .merged_into
+            .map(|number| format!(" → #{number}"))
+            .unwrap_or_default();
+
+        format!(
+            "**#{}**{case_type}{arbitration} {} gegen <@{}> – {} ({status}){merged_into}",
+            self.number,
+            self.plaintiff_mention(),
+            self.accused,
+            self.reason
+        )
+    }
+}
+
+/// How a lawsuit got to the setup of its court room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setup {
+    /// Newly filed, the filing fee is refunded if the setup fails.
+    Filed,
+    /// Filed before, like after an indictment.
+    Started,
+    /// Closed before and reopened, which isn't announced again.
+    Reopened,
 }
 
 pub struct LawsuitCtx {
@@ -42,30 +656,110 @@ impl LawsuitCtx {
             .find_or_insert_state(self.guild_id.into())
             .await?;
 
-        let free_room = state
-            .court_rooms
-            .iter()
-            .find(|r| !r.ongoing_lawsuit)
-            .cloned();
+        let filed = file(
+            &self.mongo_client,
+            self.http.as_ref(),
+            self.guild_id,
+            &state,
+            &mut self.lawsuit,
+        )
+        .await?;
+        if let Err(response) = filed {
+            return Ok(response);
+        }
+
+        if self.lawsuit.case_type == Some(CaseType::Criminal) {
+            if let Some(grand_jury) = &state.grand_jury {
+                return grand_jury::submit(
+                    &self.mongo_client,
+                    &self.http,
+                    self.guild_id,
+                    self.lawsuit,
+                    grand_jury,
+                )
+                .await;
+            }
+        }
+
+        match self.find_room_and_open(&state).await {
+            Ok(Ok(room)) => Ok(self.spawn_setup(room, Setup::Filed)),
+            Ok(Err(response)) => {
+                self.refund_filing_fee().await?;
+                Ok(response)
+            }
+            Err(err) => {
+                if let Err(err) = self.refund_filing_fee().await {
+                    error!(?err, "Failed to refund filing fee");
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Sets up the lawsuit in the court room in the background.
+    pub fn start(self, room: CourtRoom) -> Response {
+        self.spawn_setup(room, Setup::Started)
+    }
+
+    /// Like [`LawsuitCtx::start`], but what happens around the setup depends on how the lawsuit
+    /// got there.
+    fn spawn_setup(mut self, room: CourtRoom, kind: Setup) -> Response {
+        let channel_id = room.channel_id;
+        self.lawsuit.court_room = channel_id;
+
+        tokio::spawn(async move {
+            if let Err(err) = self.setup(room, kind).await {
+                error!(?err, "Error setting up lawsuit");
+                if kind == Setup::Filed {
+                    if let Err(err) = self.withdraw_filing_fee().await {
+                        error!(?err, "Failed to refund filing fee");
+                    }
+                }
+            }
+        });
 
-        let room = match (free_room, &state.court_category) {
+        Response(format!("ha eine ufgmacht im channel <#{}>", channel_id))
+    }
+
+    pub async fn find_room_and_open(&self, state: &State) -> Result<Result<CourtRoom, Response>> {
+        let category = state.court_category_for(self.lawsuit.case_type);
+        let free_room = claim_free_room(
+            &self.mongo_client,
+            self.guild_id,
+            state,
+            self.lawsuit.case_type,
+        )
+        .await?;
+
+        let room = match (free_room, &category) {
             (Some(room), _) => room,
             (None, Some(category)) => {
                 // create room
 
                 let result = self
-                    .create_room(state.court_rooms.len(), *category)
+                    .create_room(state, *category)
                     .await
                     .wrap_err("create new room")?;
 
-                match result {
-                    Err(res) => return Ok(res),
+                let room = match result {
+                    Err(res) => return Ok(Err(res)),
                     Ok(room) => room,
+                };
+                if !self
+                    .mongo_client
+                    .claim_court_room(self.guild_id.into(), room.channel_id)
+                    .await?
+                {
+                    return Ok(Err(Response(
+                        "grad isch kei Gerichtsruum frei, probier's nomal".to_string(),
+                    )));
                 }
+                room
             }
-            (None, None) => return Ok(Response(
-                "Zuerst eine Kategorie für die Gerichtsräume festlegen mit `/lawsuit set_category`".to_string(),
-            )),
+            (None, None) => return Ok(Err(Response(
+                "Zuerst eine Kategorie für die Gerichtsräume festlegen mit `/lawsuit settings category`"
+                    .to_string(),
+            ))),
         };
 
         let result = self
@@ -73,26 +767,38 @@ impl LawsuitCtx {
             .await
             .wrap_err("send process open message")?;
 
-        if let Err(response) = result {
-            return Ok(response);
+        if result.is_err() {
+            self.mongo_client
+                .set_court_room(
+                    self.guild_id.into(),
+                    room.channel_id,
+                    doc! { "court_rooms.$.ongoing_lawsuit": false },
+                )
+                .await?;
         }
 
-        let channel_id = room.channel_id;
-        self.lawsuit.court_room = channel_id;
+        Ok(result.map(|()| room))
+    }
 
-        tokio::spawn(async move {
-            if let Err(err) = self.setup(room).await {
-                error!(?err, "Error setting up lawsuit");
-            }
-        });
+    async fn refund_filing_fee(&self) -> Result<()> {
+        refund_filing_fee(&self.mongo_client, self.guild_id, &self.lawsuit).await
+    }
 
-        Ok(Response(format!(
-            "ha eine ufgmacht im channel <#{}>",
-            channel_id
-        )))
+    /// Refunds the filing fee of a lawsuit that might already be saved, and forgets it so that a
+    /// later verdict doesn't refund it again.
+    async fn withdraw_filing_fee(&mut self) -> Result<()> {
+        self.refund_filing_fee().await?;
+        self.lawsuit.filing_fee = None;
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.filing_fee": None::<i64> },
+            )
+            .await
     }
 
-    async fn setup(&self, room: CourtRoom) -> Result<()> {
+    async fn setup(&self, room: CourtRoom, kind: Setup) -> Result<()> {
         let Self {
             mongo_client,
             http,
@@ -101,110 +807,1620 @@ impl LawsuitCtx {
         } = self;
         let guild_id = *guild_id;
 
-        mongo_client.add_lawsuit(guild_id.into(), lawsuit).await?;
-        mongo_client
-            .set_court_room(
-                guild_id.into(),
-                room.channel_id,
-                doc! { "court_rooms.$.ongoing_lawsuit": true },
-            )
-            .await?;
+        match sandbox::report_channel(guild_id) {
+            Some(_) => {
+                let discord = SimulatedDiscord::new(http);
+                admit(mongo_client, &discord, guild_id, lawsuit, &room).await?;
+            }
+            None => admit(mongo_client, http.as_ref(), guild_id, lawsuit, &room).await?,
+        }
 
-        async fn assign_role(
-            user: SnowflakeId,
-            http: &Http,
-            guild_id: GuildId,
-            role_id: SnowflakeId,
-        ) -> Result<()> {
-            let mut member = guild_id.member(http, user).await.wrap_err("fetch member")?;
-            member
-                .add_role(http, role_id)
+        self.set_room_topic(room.channel_id).await?;
+
+        if lawsuit.fast_track {
+            ChannelId::from(room.channel_id)
+                .send_message(http, |msg| {
+                    msg.content(format!(
+                        "<@{}>, du chasch dini Stellungnahm zu dere Klag abgeh",
+                        lawsuit.accused
+                    ))
+                    .components(|c| {
+                        c.create_action_row(|row| {
+                            row.create_button(|button| {
+                                button
+                                    .custom_id(format!("{STATEMENT_ID_PREFIX}{}", lawsuit.id))
+                                    .label("Stellungnahm abgeh")
+                                    .style(ButtonStyle::Primary)
+                            })
+                        })
+                    })
+                })
                 .await
-                .wrap_err("add role to member")?;
+                .wrap_err("send statement request")?;
+        }
 
-            Ok(())
+        info!(?lawsuit, "Created lawsuit");
+
+        self.refresh_docket().await;
+        // a reopened lawsuit isn't new
+        if kind == Setup::Reopened {
+            return Ok(());
         }
-        assign_role(lawsuit.accused, http, guild_id, room.role_id).await?;
-        if let Some(accused_lawyer) = lawsuit.accused_lawyer {
-            assign_role(accused_lawyer, http, guild_id, room.role_id).await?;
+        if let Err(err) = self.announce_new_case().await {
+            error!(?err, "Failed to announce new lawsuit");
         }
-        assign_role(lawsuit.plaintiff, http, guild_id, room.role_id).await?;
-        if let Some(plaintiff_lawyer) = lawsuit.plaintiff_lawyer {
-            assign_role(plaintiff_lawyer, http, guild_id, room.role_id).await?;
+        if let Err(err) = self.dispatch_webhooks(WebhookEvent::LawsuitCreated).await {
+            error!(?err, "Failed to dispatch lawsuit webhooks");
         }
-        assign_role(lawsuit.judge, http, guild_id, room.role_id).await?;
 
-        info!(?lawsuit, "Created lawsuit");
+        Ok(())
+    }
+
+    /// Refreshes the docket after a change. A stale docket is fixed by the next refresh, so
+    /// failing here isn't worth failing the change.
+    async fn refresh_docket(&self) {
+        let result = docket::refresh(&self.mongo_client, &self.http, self.guild_id).await;
+        if let Err(err) = result {
+            error!(?err, "Failed to refresh docket");
+        }
+    }
+
+    async fn set_room_topic(&self, channel_id: SnowflakeId) -> Result<()> {
+        let topic = format!("Prozess #{}: {}", self.lawsuit.number, self.lawsuit.reason);
+
+        ChannelId::from(channel_id)
+            .edit(&self.http, |channel| channel.topic(topic))
+            .await
+            .wrap_err("set court room topic")?;
 
         Ok(())
     }
 
-    pub async fn rule_verdict(
+    /// Changes the reason of the lawsuit, keeping the old one in the history.
+    pub async fn amend(&mut self, actor: SnowflakeId, reason: String) -> Result<()> {
+        let old_reason = std::mem::replace(&mut self.lawsuit.reason, reason);
+
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.reason": &self.lawsuit.reason },
+            )
+            .await?;
+        self.add_history(actor, format!("Grund gänderet, vorher: {old_reason}"))
+            .await?;
+
+        if self.lawsuit.court_room != SnowflakeId(0) && !self.lawsuit.is_closed() {
+            self.set_room_topic(self.lawsuit.court_room).await?;
+        }
+
+        info!(lawsuit_id = %self.lawsuit.id, "Amended lawsuit");
+
+        Ok(())
+    }
+
+    /// Moves the lawsuit into another court room. The old room may not exist anymore. The role
+    /// changes are done before the lawsuit points to the new room, the ones that failed are
+    /// returned.
+    pub async fn move_to(
         &mut self,
-        permission_override: bool,
-        user_id: UserId,
-        verdict: String,
+        actor: SnowflakeId,
         room: CourtRoom,
-    ) -> Result<Result<(), Response>> {
-        if self.lawsuit.judge != user_id.into() && !permission_override {
-            return Ok(Err(Response("du häsch kei recht für da!".to_string())));
-        }
+    ) -> Result<Result<BulkReport, Response>> {
+        let old_channel = self.lawsuit.court_room;
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        let old_room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == old_channel);
 
-        self.lawsuit.verdict = Some(verdict);
-        let lawsuit = &self.lawsuit;
+        if !self
+            .mongo_client
+            .claim_court_room(self.guild_id.into(), room.channel_id)
+            .await?
+        {
+            return Ok(Err(Response(
+                "i dem gerichtsraum lauft scho en prozess".to_string(),
+            )));
+        }
 
-        async fn remove_role(
-            user: SnowflakeId,
-            http: &Http,
-            guild_id: GuildId,
-            role_id: SnowflakeId,
-        ) -> Result<()> {
-            let mut member = guild_id.member(http, user).await.wrap_err("fetch member")?;
-            member
-                .remove_role(http, role_id)
-                .await
-                .wrap_err("remove role from member")?;
+        let participants = self.lawsuit.participants();
+        let mut ops = participants
+            .iter()
+            .map(|user| BulkOp::AddRole {
+                user_id: (*user).into(),
+                role_id: room.role_id.into(),
+            })
+            .collect::<Vec<_>>();
+        if let Some(old_room) = old_room {
+            ops.extend(participants.iter().map(|user| BulkOp::RemoveRole {
+                user_id: (*user).into(),
+                role_id: old_room.role_id.into(),
+            }));
+        }
+        let report = bulk::run(&self.http, self.guild_id, ops).await;
+        report.log_failures("move court room roles");
 
-            Ok(())
+        if let Err(err) = self.set_room_topic(room.channel_id).await {
+            error!(?err, "Failed to set topic of new court room");
         }
 
-        let http = &self.http;
-        let guild_id = self.guild_id;
+        let old_channel_exists = ChannelId::from(old_channel)
+            .to_channel(&self.http)
+            .await
+            .is_ok();
 
-        tokio::try_join!(
-            self.mongo_client.set_court_room(
-                self.guild_id.into(),
-                lawsuit.court_room,
-                doc! { "court_rooms.$.ongoing_lawsuit": false },
-            ),
-            self.mongo_client.set_lawsuit(
+        self.mongo_client
+            .set_lawsuit(
                 self.guild_id.into(),
-                lawsuit.id,
-                doc! { "lawsuits.$.verdict": &lawsuit.verdict },
+                self.lawsuit.id,
+                doc! { "lawsuits.$.court_room": room.channel_id },
+            )
+            .await?;
+        self.lawsuit.court_room = room.channel_id;
+
+        if old_room.is_some() {
+            if old_channel_exists {
+                self.mongo_client
+                    .set_court_room(
+                        self.guild_id.into(),
+                        old_channel,
+                        doc! { "court_rooms.$.ongoing_lawsuit": false },
+                    )
+                    .await?;
+            } else {
+                self.mongo_client
+                    .delete_court_room(self.guild_id.into(), old_channel)
+                    .await?;
+            }
+        }
+
+        self.add_history(
+            actor,
+            format!("Vo <#{old_channel}> nach <#{}> verschobe", room.channel_id),
+        )
+        .await?;
+
+        if old_channel_exists {
+            ChannelId::from(old_channel)
+                .say(
+                    &self.http,
+                    format!(
+                        "De Prozess #{} isch nach <#{}> verschobe worde",
+                        self.lawsuit.number, room.channel_id
+                    ),
+                )
+                .await
+                .wrap_err("send pointer message")?;
+        }
+
+        ChannelId::from(room.channel_id)
+            .say(
+                &self.http,
+                format!(
+                    "De Prozess #{} isch vo <#{old_channel}> hierhär verschobe worde",
+                    self.lawsuit.number
+                ),
+            )
+            .await
+            .wrap_err("send moved message")?;
+
+        info!(lawsuit_id = %self.lawsuit.id, %old_channel, new_channel = %room.channel_id, failures = report.failures().count(), "Moved lawsuit");
+
+        Ok(Ok(report))
+    }
+
+    /// Registers an existing channel as a court room with a new role.
+    pub async fn register_room(&self, channel: &GuildChannel) -> Result<CourtRoom> {
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        let role_name = format!("Gerichtsprozess {}", state.court_rooms.len() + 1);
+
+        // the @everyone role has the id of the guild
+        if sandbox::simulate(
+            &self.http,
+            self.guild_id,
+            format!(
+                "Rolle `{role_name}` erstellt und <#{}> als Gerichtsraum igrichtet",
+                channel.id
             ),
-            remove_role(lawsuit.accused, http, guild_id, room.role_id),
-            remove_role(lawsuit.plaintiff, http, guild_id, room.role_id),
-            remove_role(lawsuit.judge, http, guild_id, room.role_id),
-        )?;
+        )
+        .await
+        {
+            let room = CourtRoom {
+                channel_id: channel.id.into(),
+                ongoing_lawsuit: false,
+                role_id: self.guild_id.into(),
+                category: channel.parent_id.map(Into::into),
+            };
+            self.mongo_client
+                .add_court_room(self.guild_id.into(), &room)
+                .await
+                .wrap_err("add court room to database")?;
+            return Ok(room);
+        }
+
+        let role = self
+            .guild_id
+            .create_role(&self.http, |role| {
+                role.name(role_name).permissions(Permissions::empty())
+            })
+            .await
+            .wrap_err("create role")?;
+
+        channel
+            .create_permission(
+                &self.http,
+                &PermissionOverwrite {
+                    allow: Permissions::SEND_MESSAGES,
+                    deny: Permissions::empty(),
+                    kind: PermissionOverwriteType::Role(role.id),
+                },
+            )
+            .await
+            .wrap_err("add permission overwrite")?;
+
+        let room = CourtRoom {
+            channel_id: channel.id.into(),
+            ongoing_lawsuit: false,
+            role_id: role.id.into(),
+            category: channel.parent_id.map(Into::into),
+        };
+
+        self.mongo_client
+            .add_court_room(self.guild_id.into(), &room)
+            .await
+            .wrap_err("add court room to database")?;
+
+        info!(guild_id = %self.guild_id, channel_id = %channel.id, "Registered court room");
+
+        Ok(room)
+    }
+
+    /// Schedules a hearing and announces it in the court room.
+    pub async fn schedule_hearing(&mut self, actor: SnowflakeId, hearing: Hearing) -> Result<()> {
+        self.mongo_client
+            .add_lawsuit_hearing(self.guild_id.into(), self.lawsuit.id, &hearing)
+            .await?;
+
+        let time = format_date_time(hearing.time, self.timezone().await?);
+        let description = match &hearing.description {
+            Some(description) => format!("Verhandlig am {time} agsetzt: {description}"),
+            None => format!("Verhandlig am {time} agsetzt"),
+        };
+        self.lawsuit.hearings.push(hearing);
+
+        if self.lawsuit.court_room != SnowflakeId(0) {
+            ChannelId::from(self.lawsuit.court_room)
+                .say(&self.http, &description)
+                .await
+                .wrap_err("send hearing message")?;
+        }
+        self.add_history(actor, description).await?;
+
+        info!(lawsuit_id = %self.lawsuit.id, "Scheduled hearing");
+
+        Ok(())
+    }
+
+    /// Sets the deadline of a phase, replacing an earlier one, and schedules the reminder for the
+    /// parties and the lock at the deadline.
+    pub async fn set_deadline(&mut self, actor: SnowflakeId, deadline: Deadline) -> Result<()> {
+        self.lawsuit.deadlines.retain(|d| d.phase != deadline.phase);
+        self.lawsuit.deadlines.push(deadline.clone());
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.deadlines": bson::to_bson(&self.lawsuit.deadlines).wrap_err("invalid bson for deadlines")? },
+            )
+            .await?;
+
+        let now = bson::DateTime::now().timestamp_millis();
+        let remind_at = deadline.due.timestamp_millis() - DEADLINE_REMINDER.as_millis() as i64;
+        let (lawsuit_id, phase, due) = (self.lawsuit.id, deadline.phase, deadline.due);
+        let guild_id = self.guild_id.into();
+        if remind_at > now {
+            let job = JobKind::DeadlineReminder {
+                lawsuit_id,
+                phase,
+                due,
+            };
+            let remind_at = bson::DateTime::from_millis(remind_at);
+            scheduler::schedule(&self.mongo_client, guild_id, job, remind_at).await?;
+        }
+        let job = JobKind::DeadlineExpired {
+            lawsuit_id,
+            phase,
+            due,
+        };
+        scheduler::schedule(&self.mongo_client, guild_id, job, due).await?;
+
+        let description = format!(
+            "Frist für {} bis {} gsetzt",
+            deadline.phase,
+            format_date_time(deadline.due, self.timezone().await?)
+        );
+        if self.lawsuit.court_room != SnowflakeId(0) {
+            ChannelId::from(self.lawsuit.court_room)
+                .say(&self.http, &description)
+                .await
+                .wrap_err("send deadline message")?;
+        }
+        self.add_history(actor, description).await?;
+
+        info!(lawsuit_id = %self.lawsuit.id, phase = ?deadline.phase, "Set deadline");
+
+        Ok(())
+    }
+
+    /// Imprisons the accused until the trial.
+    pub async fn detain(&mut self, actor: SnowflakeId) -> Result<Result<(), Response>> {
+        if self.lawsuit.detained_at.is_some() {
+            return Ok(Err(Response(format!(
+                "<@{}> isch scho in Untersuechigshaft",
+                self.lawsuit.accused
+            ))));
+        }
+
+        let result = prison::arrest(
+            &self.mongo_client,
+            &self.http,
+            self.guild_id,
+            self.lawsuit.accused.into(),
+        )
+        .await?;
+        // someone who is already in prison is detained without a new arrest
+        if let Err(response) = result {
+            let imprisoned = self
+                .mongo_client
+                .find_prison_entry(self.guild_id.into(), self.lawsuit.accused)
+                .await?
+                .is_some();
+            if !imprisoned {
+                return Ok(Err(response));
+            }
+        }
+
+        prison::notify_arrest(
+            &self.mongo_client,
+            &self.http,
+            self.guild_id.into(),
+            self.lawsuit.accused,
+            Some(&self.lawsuit.reason),
+            None,
+            Some(self.lawsuit.number),
+        )
+        .await?;
+
+        let now = bson::DateTime::now();
+        self.lawsuit.detained_at = Some(now);
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.detained_at": now },
+            )
+            .await?;
+
+        let description = format!("<@{}> in Untersuechigshaft gnoh", self.lawsuit.accused);
+        self.say_in_court_room(&description).await?;
+        self.add_history(actor, description).await?;
+
+        info!(lawsuit_id = %self.lawsuit.id, "Detained accused");
+
+        Ok(Ok(()))
+    }
+
+    /// Schedules a review of the detention, where the judge is reminded to decide whether the
+    /// accused stays detained.
+    pub async fn schedule_detention_review(
+        &mut self,
+        actor: SnowflakeId,
+        after: Duration,
+    ) -> Result<Result<bson::DateTime, Response>> {
+        if self.lawsuit.detained_at.is_none() {
+            return Ok(Err(Response(format!(
+                "<@{}> isch nöd in Untersuechigshaft",
+                self.lawsuit.accused
+            ))));
+        }
+
+        let due = bson::DateTime::from_millis(
+            bson::DateTime::now().timestamp_millis() + after.as_millis() as i64,
+        );
+        self.lawsuit.detention_review = Some(due);
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.detention_review": due },
+            )
+            .await?;
+
+        let job = JobKind::DetentionReview {
+            lawsuit_id: self.lawsuit.id,
+            due,
+        };
+        scheduler::schedule(&self.mongo_client, self.guild_id.into(), job, due).await?;
+
+        let description = format!(
+            "Haftprüefig am {}",
+            format_date_time(due, self.timezone().await?)
+        );
+        self.add_history(actor, description).await?;
+
+        info!(lawsuit_id = %self.lawsuit.id, "Scheduled detention review");
+
+        Ok(Ok(due))
+    }
+
+    /// Releases the accused from the detention, if they are detained.
+    pub async fn end_detention(&mut self, actor: SnowflakeId) -> Result<()> {
+        if self.lawsuit.detained_at.is_none() {
+            return Ok(());
+        }
+
+        let result = prison::release(
+            &self.mongo_client,
+            &self.http,
+            self.guild_id,
+            self.lawsuit.accused.into(),
+        )
+        .await?;
+        if let Err(response) = result {
+            warn!(%response, lawsuit_id = %self.lawsuit.id, "Could not release detained accused");
+        }
+        self.clear_detention().await?;
+
+        let description = format!("<@{}> us de Untersuechigshaft entlah", self.lawsuit.accused);
+        self.say_in_court_room(&description).await?;
+        self.add_history(actor, description).await?;
+
+        info!(lawsuit_id = %self.lawsuit.id, "Ended detention");
+
+        Ok(())
+    }
+
+    async fn clear_detention(&mut self) -> Result<()> {
+        if self.lawsuit.detained_at.is_none() {
+            return Ok(());
+        }
+
+        self.lawsuit.detained_at = None;
+        self.lawsuit.detention_review = None;
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! {
+                    "lawsuits.$.detained_at": None::<bson::DateTime>,
+                    "lawsuits.$.detention_review": None::<bson::DateTime>,
+                },
+            )
+            .await
+    }
+
+    async fn say_in_court_room(&self, message: &str) -> Result<()> {
+        if self.lawsuit.court_room != SnowflakeId(0) {
+            ChannelId::from(self.lawsuit.court_room)
+                .say(&self.http, message)
+                .await
+                .wrap_err("send court room message")?;
+        }
+        Ok(())
+    }
+
+    async fn timezone(&self) -> Result<Tz> {
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        Ok(state.timezone())
+    }
+
+    async fn branding(&self) -> Result<Option<Branding>> {
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        Ok(state.branding)
+    }
+
+    /// Records a change to the lawsuit in its history and tells the followers about it.
+    pub async fn add_history(&mut self, actor: SnowflakeId, description: String) -> Result<()> {
+        let entry = HistoryEntry {
+            timestamp: bson::DateTime::now(),
+            actor,
+            description,
+        };
+
+        self.mongo_client
+            .add_lawsuit_history(self.guild_id.into(), self.lawsuit.id, &entry)
+            .await?;
+        notification::notify_followers(&self.http, &self.lawsuit, &entry.description).await;
+        self.lawsuit.history.push(entry);
+        self.refresh_docket().await;
+
+        Ok(())
+    }
+
+    /// Records that someone involved in the lawsuit left the guild and tells the judge. If the
+    /// plaintiff or the accused left, the lawsuit is also paused or dismissed, depending on the
+    /// settings of the guild.
+    pub async fn member_left(
+        &mut self,
+        user: SnowflakeId,
+        actor: SnowflakeId,
+        state: &State,
+    ) -> Result<()> {
+        let lawsuit = &self.lawsuit;
+        let is_party = user == lawsuit.plaintiff || user == lawsuit.accused;
+        let who = if lawsuit.anonymous && user == lawsuit.plaintiff {
+            // leaving shouldn't reveal who the anonymous plaintiff is
+            "de anonymi Kläger".to_string()
+        } else if user == lawsuit.plaintiff {
+            format!("<@{user}> (Kläger)")
+        } else if user == lawsuit.accused {
+            format!("<@{user}> (Angeklagte)")
+        } else if user == lawsuit.judge {
+            format!("<@{user}> (Richter)")
+        } else if [lawsuit.plaintiff_lawyer, lawsuit.accused_lawyer].contains(&Some(user)) {
+            format!("<@{user}> (Awalt)")
+        } else {
+            format!("<@{user}>")
+        };
+
+        self.add_history(actor, format!("{who} hät de Server verlah"))
+            .await?;
+
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == self.lawsuit.court_room && r.ongoing_lawsuit)
+            .filter(|_| self.lawsuit.status == LawsuitStatus::Active && !self.lawsuit.arbitration)
+            .cloned();
+
+        let consequence = match (is_party, state.member_leave_action, &room) {
+            (true, LeaveAction::Pause, _) if !self.lawsuit.paused => {
+                self.mongo_client
+                    .set_lawsuit(
+                        self.guild_id.into(),
+                        self.lawsuit.id,
+                        doc! { "lawsuits.$.paused": true },
+                    )
+                    .await?;
+                self.lawsuit.paused = true;
+                self.add_history(actor, "Prozess pausiert".to_string())
+                    .await?;
+                ", de Prozess isch pausiert bis d'Person zrugg chunnt"
+            }
+            (true, LeaveAction::Dismiss, Some(room)) => {
+                self.terminate(
+                    LawsuitStatus::Dismissed,
+                    actor,
+                    Some(format!("{who} hät de Server verlah")),
+                    room,
+                )
+                .await?;
+                ", drum isch de Prozess abgwise worde"
+            }
+            _ => "",
+        };
+
+        let message = format!(
+            "<@{}>, {who} im Prozess #{} hät de Server verlah{consequence}",
+            self.lawsuit.judge, self.lawsuit.number
+        );
+        let result = if let Some(room) = &room {
+            ChannelId::from(room.channel_id)
+                .say(&self.http, message)
+                .await
+                .map(drop)
+        } else if user != self.lawsuit.judge {
+            match UserId::from(self.lawsuit.judge)
+                .create_dm_channel(&self.http)
+                .await
+            {
+                Ok(channel) => channel.say(&self.http, message).await.map(drop),
+                Err(err) => Err(err),
+            }
+        } else {
+            Ok(())
+        };
+        if let Err(err) = result {
+            error!(?err, lawsuit_id = %self.lawsuit.id, "Failed to tell the judge about the member leaving");
+        }
+
+        info!(lawsuit_id = %self.lawsuit.id, %user, action = ?state.member_leave_action, "Member of lawsuit left");
+
+        Ok(())
+    }
+
+    /// Continues the lawsuit after a party that left rejoined the guild.
+    pub async fn resume(&mut self, actor: SnowflakeId) -> Result<()> {
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.paused": false },
+            )
+            .await?;
+        self.lawsuit.paused = false;
+        self.add_history(actor, "Prozess gaht wiiter".to_string())
+            .await?;
+
+        info!(lawsuit_id = %self.lawsuit.id, "Resumed lawsuit");
+
+        Ok(())
+    }
+
+    /// Hands the lawsuit to a different judge and moves the court room access to them.
+    pub async fn change_judge(&mut self, new_judge: SnowflakeId, room: &CourtRoom) -> Result<()> {
+        let old_judge = self.lawsuit.judge;
+
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.judge": new_judge },
+            )
+            .await?;
+        self.lawsuit.judge = new_judge;
+
+        let result =
+            sandbox::remove_member_role(&self.http, self.guild_id, old_judge, room.role_id).await;
+        if let Err(err) = result {
+            error!(?err, "Failed to remove court room role from old judge");
+        }
+
+        sandbox::add_member_role(&self.http, self.guild_id, new_judge, room.role_id)
+            .await
+            .wrap_err("add court room role to new judge")?;
+
+        info!(lawsuit_id = %self.lawsuit.id, %old_judge, %new_judge, "Changed judge");
+
+        Ok(())
+    }
+
+    /// Reopens a closed lawsuit in its old court room if it's free, or any other free room.
+    /// Damages and sentences that were already carried out are not undone.
+    pub async fn reopen(mut self, actor: SnowflakeId, reason: String) -> Result<Response> {
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+
+        // claimed before anything changes, so that two reopens can't both get it
+        let old_room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == self.lawsuit.court_room)
+            .cloned();
+        let old_room = match old_room {
+            Some(room)
+                if self
+                    .mongo_client
+                    .claim_court_room(self.guild_id.into(), room.channel_id)
+                    .await? =>
+            {
+                Some(room)
+            }
+            _ => None,
+        };
+
+        let closed = self.lawsuit.clone();
+        self.lawsuit.verdict = None;
+        self.lawsuit.winner = None;
+        self.lawsuit.damages = None;
+        self.lawsuit.community_service = None;
+        self.lawsuit.community_service_secs = None;
+        self.lawsuit.prison_secs = None;
+        self.lawsuit.probation_secs = None;
+        self.lawsuit.time_served_secs = None;
+        self.lawsuit.appealable_until = None;
+        self.lawsuit.status = LawsuitStatus::Active;
+        self.lawsuit.termination_reason = None;
+        self.lawsuit.closed_at = None;
+        self.lawsuit.executed_at = None;
+
+        let room = match old_room {
+            Some(room) => {
+                let result = self
+                    .send_process_open_message(&self.http, self.guild_id, &room)
+                    .await?;
+                if result.is_err() {
+                    self.mongo_client
+                        .set_court_room_ongoing(self.guild_id.into(), room.channel_id, false)
+                        .await?;
+                }
+                result.map(|()| room)
+            }
+            None => self.find_room_and_open(&state).await?,
+        };
+
+        match room {
+            Ok(room) => {
+                let remaining = match closed.executed_at {
+                    Some(_) => self.reverse_execution(&closed).await?,
+                    None => vec![],
+                };
+                self.add_history(actor, format!("Prozess wieder ufgmacht: {reason}"))
+                    .await?;
+                info!(lawsuit_id = %self.lawsuit.id, ?remaining, "Reopened lawsuit");
+                if remaining.is_empty() {
+                    return Ok(self.spawn_setup(room, Setup::Reopened));
+                }
+
+                // recorded, so that the judge can take care of it by hand
+                let remaining = format!("Nöd rückgängig gmacht: {}", remaining.join(", "));
+                self.add_history(actor, remaining.clone()).await?;
+                let response = self.spawn_setup(room, Setup::Reopened);
+                Ok(Response(format!("{response}\n{remaining}")))
+            }
+            Err(response) => Ok(response),
+        }
+    }
+
+    /// Undoes what the executed verdict did, before the lawsuit is reopened: the damages go back,
+    /// and the debts, community service and probation from it are dropped. Returns what couldn't
+    /// be undone, like money the winner already spent or a sentence that was served.
+    async fn reverse_execution(&self, lawsuit: &Lawsuit) -> Result<Vec<String>> {
+        let guild_id = self.guild_id.into();
+        let mut remaining = Vec::new();
+
+        if let (Some(winner), Some(damages)) = (lawsuit.winner, lawsuit.damages) {
+            let winner_id = lawsuit.party(winner);
+            let loser_id = lawsuit.party(winner.opponent());
+
+            // what wasn't paid yet is owed as a debt
+            let mut owed = 0;
+            for debt in self.mongo_client.find_debts(guild_id, loser_id).await? {
+                if debt.lawsuit_id == Some(lawsuit.id) {
+                    self.mongo_client.reduce_debt(debt.id, debt.amount).await?;
+                    owed += debt.amount;
+                }
+            }
+
+            let paid = damages.saturating_sub(owed).max(0);
+            let returned = self
+                .mongo_client
+                .debit_up_to(
+                    guild_id,
+                    winner_id,
+                    paid,
+                    TransactionKind::DamagesRefund,
+                    &lawsuit.reason,
+                )
+                .await
+                .wrap_err("debit damages refund")?;
+            if returned > 0 {
+                self.mongo_client
+                    .credit(
+                        guild_id,
+                        loser_id,
+                        returned,
+                        TransactionKind::DamagesRefund,
+                        &lawsuit.reason,
+                    )
+                    .await
+                    .wrap_err("credit damages refund")?;
+            }
+            if returned < paid {
+                remaining.push(format!(
+                    "{} Schadenersatz vo <@{winner_id}>",
+                    format_amount(paid - returned)
+                ));
+            }
+        }
+
+        let obligations = self
+            .mongo_client
+            .find_service_obligations(guild_id, lawsuit.accused)
+            .await?;
+        for obligation in obligations {
+            if obligation.lawsuit_id == Some(lawsuit.id) {
+                self.mongo_client
+                    .delete_service_obligation(obligation.id)
+                    .await?;
+            }
+        }
+
+        // a probation whose sentence wasn't activated yet never will be
+        self.mongo_client
+            .delete_lawsuit_probations(lawsuit.id)
+            .await?;
+
+        // whether they are in prison for this or something else can't be told apart
+        let in_prison = self
+            .mongo_client
+            .find_prison_entry(guild_id, lawsuit.accused)
+            .await?
+            .is_some();
+        if lawsuit.prison_secs.is_some() && in_prison {
+            remaining.push(format!(
+                "d'Gfängnisstraf vo <@{}>, mit `/prison release` freilah",
+                lawsuit.accused
+            ));
+        }
+
+        info!(lawsuit_id = %lawsuit.id, ?remaining, "Reversed executed verdict");
+
+        Ok(remaining)
+    }
+
+    /// Ends the lawsuit without a verdict, with [`LawsuitStatus::Withdrawn`],
+    /// [`LawsuitStatus::Dismissed`] or [`LawsuitStatus::Merged`], and frees the court room.
+    pub async fn terminate(
+        &mut self,
+        status: LawsuitStatus,
+        actor: SnowflakeId,
+        reason: Option<String>,
+        room: &CourtRoom,
+    ) -> Result<()> {
+        self.lawsuit.status = status;
+        self.lawsuit.termination_reason = reason;
+        self.lawsuit.closed_at = Some(bson::DateTime::now());
+
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! {
+                    "lawsuits.$.status": bson::to_bson(&status).wrap_err("invalid bson for status")?,
+                    "lawsuits.$.termination_reason": &self.lawsuit.termination_reason,
+                    "lawsuits.$.closed_at": self.lawsuit.closed_at,
+                },
+            )
+            .await?;
+        self.mongo_client
+            .set_court_room(
+                self.guild_id.into(),
+                room.channel_id,
+                doc! { "court_rooms.$.ongoing_lawsuit": false },
+            )
+            .await?;
+
+        let ops = self
+            .lawsuit
+            .participants()
+            .into_iter()
+            .map(|user| BulkOp::RemoveRole {
+                user_id: user.into(),
+                role_id: room.role_id.into(),
+            })
+            .collect();
+        bulk::run(&self.http, self.guild_id, ops)
+            .await
+            .log_failures("remove court room role");
+
+        let title = match status {
+            LawsuitStatus::Withdrawn => "zurückgezogen",
+            LawsuitStatus::Merged => "zämegführt",
+            _ => "abgewiesen",
+        };
+        self.add_history(actor, format!("Prozess {title}")).await?;
+
+        let branding = self.branding().await?;
+        let lawsuit = &self.lawsuit;
+        ChannelId::from(room.channel_id)
+            .send_message(&self.http, |msg| {
+                msg.embed(|embed| {
+                    branding::apply(embed, branding.as_ref())
+                        .title(format!("Prozess #{} {title}", lawsuit.number))
+                        .field("Grund", &lawsuit.reason, false);
+                    if let Some(reason) = &lawsuit.termination_reason {
+                        embed.field("Begründung", reason, false);
+                    }
+                    embed
+                })
+            })
+            .await
+            .wrap_err("send termination message")?;
+
+        info!(lawsuit_id = %lawsuit.id, ?status, "Terminated lawsuit");
+
+        if matches!(status, LawsuitStatus::Dismissed | LawsuitStatus::Withdrawn) {
+            self.end_detention(actor).await?;
+        }
+        self.settle_appeal().await?;
+
+        Ok(())
+    }
+
+    /// Takes over the parties, statements, tags and history of a duplicate lawsuit and closes the
+    /// duplicate with a pointer to this one, which frees its court room.
+    pub async fn merge(
+        &mut self,
+        actor: SnowflakeId,
+        mut duplicate: LawsuitCtx,
+        room: &CourtRoom,
+        duplicate_room: &CourtRoom,
+    ) -> Result<()> {
+        let mut new_parties = Vec::new();
+        for user in duplicate.lawsuit.participants() {
+            if user != duplicate.lawsuit.judge
+                && !self.lawsuit.involved().contains(&user)
+                && !new_parties.contains(&user)
+            {
+                new_parties.push(user);
+            }
+        }
+
+        let new_tags = duplicate
+            .lawsuit
+            .tags
+            .iter()
+            .filter(|tag| !self.lawsuit.has_tag(tag))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // only appended, so that concurrent changes to this lawsuit are kept
+        self.mongo_client
+            .append_to_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                &new_parties,
+                &duplicate.lawsuit.statements,
+                &new_tags,
+                &duplicate.lawsuit.history,
+            )
+            .await?;
+
+        self.lawsuit.additional_parties.extend(&new_parties);
+        self.lawsuit
+            .statements
+            .extend(duplicate.lawsuit.statements.iter().cloned());
+        self.lawsuit.tags.extend(new_tags);
+        self.lawsuit
+            .history
+            .extend(duplicate.lawsuit.history.iter().cloned());
+        self.add_history(
+            actor,
+            format!(
+                "Fall #{} ufgnoh: {}",
+                duplicate.lawsuit.number, duplicate.lawsuit.reason
+            ),
+        )
+        .await?;
+
+        let ops = new_parties
+            .iter()
+            .map(|user| BulkOp::AddRole {
+                user_id: (*user).into(),
+                role_id: room.role_id.into(),
+            })
+            .collect();
+        bulk::run(&self.http, self.guild_id, ops)
+            .await
+            .log_failures("add court room role to merged party");
+
+        duplicate.lawsuit.merged_into = Some(self.lawsuit.number);
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                duplicate.lawsuit.id,
+                doc! { "lawsuits.$.merged_into": self.lawsuit.number as i64 },
+            )
+            .await?;
+        duplicate
+            .terminate(
+                LawsuitStatus::Merged,
+                actor,
+                Some(format!(
+                    "wiitergfüehrt als Prozess #{} i <#{}>",
+                    self.lawsuit.number, room.channel_id
+                )),
+                duplicate_room,
+            )
+            .await?;
+
+        info!(lawsuit_id = %self.lawsuit.id, duplicate_id = %duplicate.lawsuit.id, "Merged lawsuits");
+
+        Ok(())
+    }
+
+    /// Sets or removes the lawyer of a party and moves the court room access to them.
+    pub async fn change_lawyer(
+        &mut self,
+        party: Party,
+        new_lawyer: Option<SnowflakeId>,
+        room: &CourtRoom,
+    ) -> Result<()> {
+        let old_lawyer = self.lawsuit.lawyer(party);
+        let field = match party {
+            Party::Plaintiff => "lawsuits.$.plaintiff_lawyer",
+            Party::Accused => "lawsuits.$.accused_lawyer",
+        };
+
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { field: new_lawyer },
+            )
+            .await?;
+        match party {
+            Party::Plaintiff => self.lawsuit.plaintiff_lawyer = new_lawyer,
+            Party::Accused => self.lawsuit.accused_lawyer = new_lawyer,
+        }
+
+        if let Some(old_lawyer) = old_lawyer {
+            let result =
+                sandbox::remove_member_role(&self.http, self.guild_id, old_lawyer, room.role_id)
+                    .await;
+            if let Err(err) = result {
+                error!(?err, "Failed to remove court room role from old lawyer");
+            }
+        }
+
+        if let Some(new_lawyer) = new_lawyer {
+            sandbox::add_member_role(&self.http, self.guild_id, new_lawyer, room.role_id)
+                .await
+                .wrap_err("add court room role to new lawyer")?;
+        }
+
+        info!(lawsuit_id = %self.lawsuit.id, ?old_lawyer, ?new_lawyer, %party, "Changed lawyer");
+
+        Ok(())
+    }
+
+    pub async fn rule_verdict(
+        &mut self,
+        permission_override: bool,
+        user_id: UserId,
+        ruling: Ruling,
+        room: CourtRoom,
+    ) -> Result<Result<(), Response>> {
+        if self.lawsuit.judge != user_id.into() && !permission_override {
+            return Ok(Err(Response("du häsch kei recht für da!".to_string())));
+        }
+
+        let Ruling {
+            verdict,
+            winner,
+            damages,
+            community_service,
+            prison,
+        } = ruling;
+
+        if damages.is_some() && winner.is_none() {
+            return Ok(Err(Response(
+                "für en Schadenersatz muesch ahgeh wer gwunne hät".to_string(),
+            )));
+        }
+
+        self.lawsuit.verdict = Some(verdict);
+        self.lawsuit.winner = winner;
+        self.lawsuit.damages = damages;
+        self.lawsuit.community_service = community_service.map(|service| service.hours);
+        self.lawsuit.community_service_secs =
+            community_service.map(|service| service.deadline.as_secs());
+        self.lawsuit.prison_secs = prison.map(|prison| prison.duration.as_secs());
+        self.lawsuit.probation_secs = prison
+            .and_then(|prison| prison.probation)
+            .map(|probation| probation.as_secs());
+        self.lawsuit.time_served_secs = None;
+        if prison.is_some() && self.lawsuit.probation_secs.is_none() {
+            let entry = self
+                .mongo_client
+                .find_prison_entry(self.guild_id.into(), self.lawsuit.accused)
+                .await?;
+            self.lawsuit.time_served_secs = self
+                .lawsuit
+                .time_served(entry.as_ref(), bson::DateTime::now());
+        }
+        self.lawsuit.closed_at = Some(bson::DateTime::now());
+        let lawsuit = &self.lawsuit;
+
+        let http = &self.http;
+        let guild_id = self.guild_id;
+
+        match sandbox::report_channel(guild_id) {
+            Some(_) => {
+                let discord = SimulatedDiscord::new(http);
+                close(&self.mongo_client, &discord, guild_id, lawsuit, &room).await?;
+            }
+            None => close(&self.mongo_client, http.as_ref(), guild_id, lawsuit, &room).await?,
+        }
+
+        let response = self
+            .send_process_close_message(http, guild_id, &room)
+            .await?;
+
+        let update = format!("Urteil: {}", lawsuit.verdict.as_deref().unwrap_or_default());
+        notification::notify_followers(http, lawsuit, &update).await;
+        self.refresh_docket().await;
+
+        info!(?lawsuit, "Closed lawsuit");
+
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        // a prison sentence takes over from the detention, everyone else is free to go
+        if self.lawsuit.prison_secs.is_some() && self.lawsuit.probation_secs.is_none() {
+            self.clear_detention().await?;
+        } else {
+            self.end_detention(self.lawsuit.judge).await?;
+        }
+        match state.appeal_window_secs {
+            Some(window) => self.await_appeals(window).await?,
+            None => self.execute_verdict().await?,
+        }
+        self.settle_appeal().await?;
+
+        if let Err(response) = response {
+            return Ok(Err(response));
+        }
+
+        Ok(Ok(()))
+    }
+
+    /// Keeps the verdict appealable for the window and schedules its execution after it.
+    async fn await_appeals(&mut self, window_secs: i64) -> Result<()> {
+        let until = bson::DateTime::from_millis(
+            bson::DateTime::now().timestamp_millis() + window_secs * 1000,
+        );
+        self.lawsuit.appealable_until = Some(until);
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                self.lawsuit.id,
+                doc! { "lawsuits.$.appealable_until": until },
+            )
+            .await?;
+
+        let job = JobKind::FinalizeVerdict {
+            lawsuit_id: self.lawsuit.id,
+        };
+        scheduler::schedule(&self.mongo_client, self.guild_id.into(), job, until).await?;
+
+        let description = format!(
+            "S'Urteil cha bis <t:{}:f> mit enere Berufig agfochte werde",
+            until.timestamp_millis() / 1000
+        );
+        if self.lawsuit.court_room != SnowflakeId(0) {
+            ChannelId::from(self.lawsuit.court_room)
+                .say(&self.http, &description)
+                .await
+                .wrap_err("send appeal window message")?;
+        }
+        self.add_history(self.lawsuit.judge, description).await?;
+
+        Ok(())
+    }
+
+    /// Executes the sentences of the verdict and publishes it.
+    async fn execute_verdict(&self) -> Result<()> {
+        let lawsuit = &self.lawsuit;
+
+        // a refunded fee is forgotten, so that a reopened lawsuit doesn't refund it again
+        let refunded = lawsuit.winner == Some(Party::Plaintiff);
+        if refunded {
+            self.refund_filing_fee().await?;
+        }
+        let filing_fee = lawsuit.filing_fee.filter(|_| !refunded);
+        self.mongo_client
+            .set_lawsuit(
+                self.guild_id.into(),
+                lawsuit.id,
+                doc! {
+                    "lawsuits.$.executed_at": bson::DateTime::now(),
+                    "lawsuits.$.filing_fee": filing_fee,
+                },
+            )
+            .await?;
+
+        if let (Some(winner), Some(damages)) = (lawsuit.winner, lawsuit.damages) {
+            self.pay_damages(winner, damages).await?;
+        }
+
+        if let Some(hours) = lawsuit.community_service {
+            let deadline = Duration::from_secs(
+                lawsuit
+                    .community_service_secs
+                    .unwrap_or(DEFAULT_SERVICE_DEADLINE.as_secs()),
+            );
+            let obligation = ServiceObligation {
+                id: Uuid::new(),
+                guild_id: self.guild_id.into(),
+                user_id: lawsuit.accused,
+                hours_required: hours,
+                hours_done: 0,
+                deadline: bson::DateTime::from_millis(
+                    bson::DateTime::now().timestamp_millis() + deadline.as_millis() as i64,
+                ),
+                reason: lawsuit.reason.clone(),
+                lawsuit_id: Some(lawsuit.id),
+            };
+            self.mongo_client
+                .add_service_obligation(&obligation)
+                .await
+                .wrap_err("add community service")?;
+        }
+
+        if lawsuit.winner == Some(Party::Plaintiff) {
+            probation::violated(&self.mongo_client, self.guild_id.into(), lawsuit.accused).await?;
+        }
+
+        if let Some(sentence) = lawsuit.prison_secs.map(Duration::from_secs) {
+            match lawsuit.probation_secs.map(Duration::from_secs) {
+                Some(probation) => {
+                    probation::start(
+                        &self.mongo_client,
+                        self.guild_id.into(),
+                        lawsuit.accused,
+                        lawsuit.id,
+                        sentence,
+                        probation,
+                    )
+                    .await?;
+                }
+                None => {
+                    let remaining = lawsuit.remaining_prison_secs().unwrap_or_default();
+                    self.imprison_accused(Duration::from_secs(remaining))
+                        .await?
+                }
+            }
+        }
+
+        if let Err(err) = self.announce_verdict().await {
+            error!(?err, "Failed to announce verdict");
+        }
+        if let Err(err) = self.dispatch_webhooks(WebhookEvent::Verdict).await {
+            error!(?err, "Failed to dispatch verdict webhooks");
+        }
+
+        info!(lawsuit_id = %lawsuit.id, "Executed verdict");
+
+        Ok(())
+    }
+
+    /// Once an appeal is over, executes the appealed verdict, or sets it aside if the appellant
+    /// won. Does nothing while other appeals against it are still open.
+    async fn settle_appeal(&self) -> Result<()> {
+        let number = match self.lawsuit.appeal_of {
+            Some(number) => number,
+            None => return Ok(()),
+        };
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        let appealed = state
+            .lawsuits
+            .iter()
+            .find(|l| l.number == number && l.awaits_execution(&state.lawsuits));
+        let appealed = match appealed {
+            Some(appealed) => appealed.clone(),
+            None => return Ok(()),
+        };
+
+        let mut appealed_ctx = LawsuitCtx {
+            lawsuit: appealed,
+            mongo_client: self.mongo_client.clone(),
+            http: self.http.clone(),
+            guild_id: self.guild_id,
+        };
+
+        if self.lawsuit.verdict.is_some() && self.lawsuit.winner == Some(Party::Plaintiff) {
+            appealed_ctx.lawsuit.appealable_until = None;
+            self.mongo_client
+                .set_lawsuit(
+                    self.guild_id.into(),
+                    appealed_ctx.lawsuit.id,
+                    doc! { "lawsuits.$.appealable_until": None::<bson::DateTime> },
+                )
+                .await?;
+            appealed_ctx
+                .add_history(
+                    self.lawsuit.judge,
+                    format!("Urteil dur d'Berufig #{} ufghobe", self.lawsuit.number),
+                )
+                .await?;
+            appealed_ctx
+                .compensate_detention(self.lawsuit.judge)
+                .await?;
+            info!(lawsuit_id = %appealed_ctx.lawsuit.id, "Set aside appealed verdict");
+            return Ok(());
+        }
+
+        // the window might not be over yet
+        let job = JobKind::FinalizeVerdict {
+            lawsuit_id: appealed_ctx.lawsuit.id,
+        };
+        let due = appealed_ctx
+            .lawsuit
+            .appealable_until
+            .map_or(bson::DateTime::now(), |until| {
+                until.max(bson::DateTime::now())
+            });
+        scheduler::schedule(&self.mongo_client, self.guild_id.into(), job, due).await?;
+
+        Ok(())
+    }
+
+    /// Pings the case ping role in the announcement channel about a new public lawsuit.
+    async fn announce_new_case(&self) -> Result<()> {
+        if self.lawsuit.sealed || self.lawsuit.arbitration {
+            return Ok(());
+        }
+
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        let (channel_id, role_id) = match (state.announcement_channel, state.case_ping_role) {
+            (Some(channel_id), Some(role_id)) => (channel_id, role_id),
+            _ => return Ok(()),
+        };
+
+        let lawsuit = &self.lawsuit;
+        ChannelId::from(channel_id)
+            .send_message(&self.http, |msg| {
+                msg.content(format!("<@&{role_id}>, es git en neue Prozess"))
+                    .embed(|embed| {
+                        branding::apply(embed, state.branding.as_ref())
+                            .title(format!("Prozess #{}", lawsuit.number))
+                            .field("Grund", &lawsuit.reason, false)
+                            .field("Kläger", lawsuit.plaintiff_mention(), true)
+                            .field("Angeklagter", format!("<@{}>", lawsuit.accused), true)
+                            .field("Richter", format!("<@{}>", lawsuit.judge), true)
+                    })
+            })
+            .await
+            .wrap_err("send new lawsuit announcement")?;
+
+        Ok(())
+    }
+
+    /// Sends the event to the webhooks of the guild, unless the lawsuit is sealed.
+    async fn dispatch_webhooks(&self, event: WebhookEvent) -> Result<()> {
+        if self.lawsuit.sealed || self.lawsuit.arbitration {
+            return Ok(());
+        }
+
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        webhook::dispatch(
+            &self.http,
+            &state,
+            event,
+            webhook::lawsuit_data(&self.lawsuit),
+        );
+
+        Ok(())
+    }
+
+    /// Publishes the verdict in the announcement channel, unless the lawsuit is sealed. Spectators
+    /// can vote on it if the guild has verdict polls.
+    async fn announce_verdict(&self) -> Result<()> {
+        if self.lawsuit.sealed {
+            return Ok(());
+        }
+
+        let state = self
+            .mongo_client
+            .find_or_insert_state(self.guild_id.into())
+            .await?;
+        let channel_id = match state.announcement_channel {
+            Some(channel_id) => channel_id,
+            None => return Ok(()),
+        };
+
+        let lawsuit = &self.lawsuit;
+        let message = ChannelId::from(channel_id)
+            .send_message(&self.http, |msg| {
+                if state.verdict_polls {
+                    msg.components(|c| opinion_poll::buttons(c, lawsuit.id));
+                }
+                msg.embed(|embed| {
+                    branding::apply(embed, state.branding.as_ref())
+                        .title(format!("Urteil im Prozess #{}", lawsuit.number))
+                        .field("Grund", &lawsuit.reason, false)
+                        .field("Kläger", lawsuit.plaintiff_mention(), true)
+                        .field("Angeklagter", format!("<@{}>", lawsuit.accused), true)
+                        .field("Richter", format!("<@{}>", lawsuit.judge), true)
+                        .field(
+                            "Urteil",
+                            lawsuit.verdict.as_deref().unwrap_or_default(),
+                            false,
+                        );
+                    if let Some(winner) = lawsuit.winner {
+                        embed.field("Gewinner", winner, true);
+                    }
+                    if let Some(damages) = lawsuit.damages {
+                        embed.field("Schadenersatz", format_amount(damages), true);
+                    }
+                    if let Some(hours) = lawsuit.community_service {
+                        embed.field("Gemeinnützige Arbeit", format!("{hours} Stunden"), true);
+                    }
+                    if let Some(prison) = lawsuit.prison_sentence() {
+                        embed.field("Gefängnis", prison, true);
+                    }
+                    embed
+                })
+            })
+            .await
+            .wrap_err("send verdict announcement")?;
+
+        if state.verdict_polls {
+            opinion_poll::open(
+                &self.mongo_client,
+                self.guild_id.into(),
+                lawsuit.id,
+                channel_id,
+                message.id.into(),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Compensates the accused for the pre-trial detention of a conviction that was set aside.
+    async fn compensate_detention(&mut self, actor: SnowflakeId) -> Result<()> {
+        let served = match self.lawsuit.time_served_secs {
+            Some(secs) if self.lawsuit.winner == Some(Party::Plaintiff) => {
+                Duration::from_secs(secs)
+            }
+            _ => return Ok(()),
+        };
+
+        let accused = self.lawsuit.accused;
+        let description = format!(
+            "Entschädigung für d'Haft im Prozess #{}",
+            self.lawsuit.number
+        );
+        let amount = match economy::compensate(
+            &self.mongo_client,
+            self.guild_id.into(),
+            accused,
+            served,
+            &description,
+        )
+        .await?
+        {
+            Some(amount) => amount,
+            None => return Ok(()),
+        };
+
+        self.add_history(
+            actor,
+            format!(
+                "<@{accused}> mit {} für {} Haft entschädigt",
+                format_amount(amount),
+                format_duration(served)
+            ),
+        )
+        .await?;
+        info!(lawsuit_id = %self.lawsuit.id, %amount, "Compensated wrongful detention");
+
+        Ok(())
+    }
+
+    /// Imprisons the accused for the rest of the sentence and schedules the release. Someone who
+    /// is already in prison before the trial just stays there.
+    async fn imprison_accused(&self, sentence: Duration) -> Result<()> {
+        let user_id = self.lawsuit.accused;
+
+        // the time served covers the whole sentence
+        if sentence.is_zero() {
+            let result = prison::release(
+                &self.mongo_client,
+                &self.http,
+                self.guild_id,
+                user_id.into(),
+            )
+            .await?;
+            if let Err(response) = result {
+                warn!(%response, lawsuit_id = %self.lawsuit.id, "Could not release accused");
+            }
+            return Ok(());
+        }
+
+        let result = prison::arrest(
+            &self.mongo_client,
+            &self.http,
+            self.guild_id,
+            user_id.into(),
+        )
+        .await?;
+        if let Err(response) = result {
+            let imprisoned = self
+                .mongo_client
+                .find_prison_entry(self.guild_id.into(), user_id)
+                .await?
+                .is_some();
+            if !imprisoned {
+                warn!(%response, lawsuit_id = %self.lawsuit.id, "Could not imprison accused");
+                return Ok(());
+            }
+        }
+
+        let due = bson::DateTime::from_millis(
+            bson::DateTime::now().timestamp_millis() + sentence.as_millis() as i64,
+        );
+        scheduler::schedule(
+            &self.mongo_client,
+            self.guild_id.into(),
+            JobKind::Release { user_id },
+            due,
+        )
+        .await?;
+
+        prison::notify_arrest(
+            &self.mongo_client,
+            &self.http,
+            self.guild_id.into(),
+            user_id,
+            Some(&self.lawsuit.reason),
+            Some(sentence),
+            Some(self.lawsuit.number),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Transfers the damages from the loser to the winner. Whatever the loser can't pay is recorded as a debt.
+    async fn pay_damages(&self, winner: Party, damages: i64) -> Result<()> {
+        let lawsuit = &self.lawsuit;
+        let guild_id = self.guild_id.into();
+        let winner_id = lawsuit.party(winner);
+        let loser_id = lawsuit.party(winner.opponent());
+
+        let paid = self
+            .mongo_client
+            .debit_up_to(
+                guild_id,
+                loser_id,
+                damages,
+                TransactionKind::Damages,
+                &lawsuit.reason,
+            )
+            .await
+            .wrap_err("debit damages")?;
 
-        if let Some(accused_lawyer) = lawsuit.accused_lawyer {
-            remove_role(accused_lawyer, http, guild_id, room.role_id).await?;
-        }
-        if let Some(plaintiff_lawyer) = lawsuit.plaintiff_lawyer {
-            remove_role(plaintiff_lawyer, http, guild_id, room.role_id).await?;
+        if paid > 0 {
+            self.mongo_client
+                .credit(
+                    guild_id,
+                    winner_id,
+                    paid,
+                    TransactionKind::Damages,
+                    &lawsuit.reason,
+                )
+                .await
+                .wrap_err("credit damages")?;
         }
 
-        let response = self
-            .send_process_close_message(http, guild_id, &room)
-            .await?;
-
-        info!(?lawsuit, "Closed lawsuit");
-
-        if let Err(response) = response {
-            return Ok(Err(response));
+        if paid < damages {
+            let debt = Debt {
+                id: Uuid::new(),
+                guild_id,
+                debtor: loser_id,
+                creditor: winner_id,
+                amount: damages - paid,
+                reason: lawsuit.reason.clone(),
+                lawsuit_id: Some(lawsuit.id),
+                created_at: bson::DateTime::now(),
+                garnished_until: None,
+            };
+            self.mongo_client.add_debt(&debt).await?;
+            economy::start_garnishment(&self.mongo_client, guild_id, loser_id).await?;
+            info!(?debt, "Loser could not pay all damages");
         }
 
-        Ok(Ok(()))
+        Ok(())
     }
 
     async fn send_process_open_message(
@@ -213,13 +2429,15 @@ impl LawsuitCtx {
         guild_id: GuildId,
         room: &CourtRoom,
     ) -> Result<Result<(), Response>> {
+        let tz = self.timezone().await?;
+        let branding = self.branding().await?;
         self.send_court_message(http, guild_id, room, |msg| {
             msg.embed(|embed| {
                 let lawsuit = &self.lawsuit;
-                embed
-                    .title("Prozess")
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("Prozess #{}", lawsuit.number))
                     .field("Grund", &lawsuit.reason, false)
-                    .field("Kläger", format!("<@{}>", lawsuit.plaintiff), true)
+                    .field("Kläger", lawsuit.plaintiff_mention(), true)
                     .field(
                         "Anwalt des Klägers",
                         match &lawsuit.plaintiff_lawyer {
@@ -237,7 +2455,37 @@ impl LawsuitCtx {
                         },
                         true,
                     )
-                    .field("Richter", format!("<@{}>", lawsuit.judge), true)
+                    .field("Richter", format!("<@{}>", lawsuit.judge), true);
+                if let Some(incident_date) = lawsuit.incident_date {
+                    embed.field("Vorfall", format_date(incident_date, tz), true);
+                }
+                if let Some(claim_amount) = lawsuit.claim_amount {
+                    embed.field("Forderung", format_amount(claim_amount), true);
+                }
+                if let Some(deadline) = lawsuit.decision_deadline {
+                    embed.field(
+                        "Urteil bis",
+                        format!("<t:{}:R>", deadline.timestamp_millis() / 1000),
+                        true,
+                    );
+                }
+                for answer in &lawsuit.intake {
+                    embed.field(&answer.question, &answer.answer, false);
+                }
+                for statement in &lawsuit.statements {
+                    embed.field(
+                        format!("Stellungnahm vom {}", statement.party),
+                        &statement.text,
+                        false,
+                    );
+                }
+                if let Some(case_type) = lawsuit.case_type {
+                    embed.field("Art", case_type, true);
+                }
+                if !lawsuit.tags.is_empty() {
+                    embed.field("Tags", lawsuit.tags.join(", "), true);
+                }
+                embed
             })
         })
         .await
@@ -249,13 +2497,14 @@ impl LawsuitCtx {
         guild_id: GuildId,
         room: &CourtRoom,
     ) -> Result<Result<(), Response>> {
+        let branding = self.branding().await?;
         self.send_court_message(http, guild_id, room, |msg| {
             msg.embed(|embed| {
                 let lawsuit = &self.lawsuit;
-                embed
-                    .title("Prozess abgeschlossen")
+                branding::apply(embed, branding.as_ref())
+                    .title(format!("Prozess #{} abgeschlossen", lawsuit.number))
                     .field("Grund", &lawsuit.reason, false)
-                    .field("Kläger", format!("<@{}>", lawsuit.plaintiff), true)
+                    .field("Kläger", lawsuit.plaintiff_mention(), true)
                     .field(
                         "Anwalt des Klägers",
                         match &lawsuit.plaintiff_lawyer {
@@ -278,7 +2527,20 @@ impl LawsuitCtx {
                         "Urteil",
                         lawsuit.verdict.clone().expect("no verdict found!"),
                         true,
-                    )
+                    );
+                if let Some(winner) = lawsuit.winner {
+                    embed.field("Gewinner", winner, true);
+                }
+                if let Some(damages) = lawsuit.damages {
+                    embed.field("Schadenersatz", format_amount(damages), true);
+                }
+                if let Some(hours) = lawsuit.community_service {
+                    embed.field("Gemeinnützige Arbeit", format!("{hours} Stunden"), true);
+                }
+                if let Some(prison) = lawsuit.prison_sentence() {
+                    embed.field("Gefängnis", prison, true);
+                }
+                embed
             })
         })
         .await
@@ -324,13 +2586,34 @@ impl LawsuitCtx {
 
     async fn create_room(
         &self,
-        room_len: usize,
+        state: &State,
         category_id: SnowflakeId,
     ) -> Result<Result<CourtRoom, Response>> {
-        let room_number = room_len + 1;
+        let room_number = state.court_rooms.len() + 1;
         let room_name = format!("gerichtsraum-{room_number}");
         let role_name = format!("Gerichtsprozess {room_number}");
 
+        // the report channel stands in for the room, so only one simulated lawsuit can be open
+        if let Some(report_channel) = sandbox::report_channel(self.guild_id) {
+            sandbox::simulate(
+                &self.http,
+                self.guild_id,
+                format!("Kanal `{room_name}` und Rolle `{role_name}` erstellt"),
+            )
+            .await;
+            let room = CourtRoom {
+                channel_id: report_channel.into(),
+                ongoing_lawsuit: false,
+                role_id: self.guild_id.into(),
+                category: Some(category_id),
+            };
+            self.mongo_client
+                .add_court_room(self.guild_id.into(), &room)
+                .await
+                .wrap_err("add court room to database")?;
+            return Ok(Ok(room));
+        }
+
         let guild = self
             .guild_id
             .to_partial_guild(&self.http)
@@ -365,16 +2648,23 @@ impl LawsuitCtx {
                 channel.id
             }
             None => {
+                let bot_id = self
+                    .http
+                    .get_current_user()
+                    .await
+                    .wrap_err("fetch current user")?
+                    .id;
+                let permissions = state
+                    .room_permissions
+                    .clone()
+                    .unwrap_or_default()
+                    .overwrites(self.guild_id, role_id, bot_id);
                 guild
                     .create_channel(&self.http, |channel| {
                         channel
                             .name(room_name)
                             .category(category_id)
-                            .permissions(vec![PermissionOverwrite {
-                                allow: Permissions::SEND_MESSAGES,
-                                deny: Permissions::empty(),
-                                kind: PermissionOverwriteType::Role(role_id),
-                            }])
+                            .permissions(permissions)
                     })
                     .await
                     .wrap_err("create channel")?
@@ -386,6 +2676,7 @@ impl LawsuitCtx {
             channel_id: channel_id.into(),
             ongoing_lawsuit: false,
             role_id: role_id.into(),
+            category: Some(category_id),
         };
 
         self.mongo_client
@@ -398,3 +2689,949 @@ impl LawsuitCtx {
         Ok(Ok(room))
     }
 }
+
+/// The part of [`LawsuitCtx::initialize`] that checks whether the lawsuit can be filed and charges
+/// the filing fee. Gives the lawsuit its number.
+pub async fn file(
+    storage: &impl Storage,
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    state: &State,
+    lawsuit: &mut Lawsuit,
+) -> Result<Result<(), Response>> {
+    if let Some(judge_role) = state.judge_role_for(lawsuit.case_type) {
+        let roles = discord
+            .member_roles(guild_id, lawsuit.judge.into())
+            .await
+            .wrap_err("fetch judge roles")?
+            .unwrap_or_default();
+
+        if !roles.contains(&judge_role.into()) {
+            return Ok(Err(Response(format!(
+                "de Richter muess d'Rolle <@&{judge_role}> ha"
+            ))));
+        }
+    }
+
+    if state.debt_blocks_filings {
+        let debts = storage
+            .find_debts(guild_id.into(), lawsuit.plaintiff)
+            .await?;
+
+        if !debts.is_empty() {
+            let total = debts.iter().map(|debt| debt.amount).sum();
+            return Ok(Err(Response(format!(
+                "de Kläger hät no {} Schulde und dörf kei neui Klag ireiche",
+                format_amount(total)
+            ))));
+        }
+    }
+
+    lawsuit.number = storage.next_case_number(guild_id.into()).await?;
+
+    if let Some(fee) = state.filing_fee {
+        let result = storage
+            .try_debit(
+                guild_id.into(),
+                lawsuit.plaintiff,
+                fee,
+                TransactionKind::FilingFee,
+                &lawsuit.reason,
+            )
+            .await
+            .wrap_err("pay filing fee")?;
+
+        if let Err(shortfall) = result {
+            return Ok(Err(Response(format!(
+                "de Kläger cha d'Gerichtsgebühr vo {} nöd zahle, es fehled no {}",
+                format_amount(fee),
+                format_amount(shortfall)
+            ))));
+        }
+
+        storage
+            .credit(
+                guild_id.into(),
+                economy::treasury_account(guild_id.into()),
+                fee,
+                TransactionKind::FilingFee,
+                &lawsuit.reason,
+            )
+            .await
+            .wrap_err("pay filing fee into treasury")?;
+
+        lawsuit.filing_fee = Some(fee);
+    }
+
+    Ok(Ok(()))
+}
+
+/// Pays the filing fee of the lawsuit back to the plaintiff from the treasury, if there was one.
+pub async fn refund_filing_fee(
+    storage: &impl Storage,
+    guild_id: GuildId,
+    lawsuit: &Lawsuit,
+) -> Result<()> {
+    if let Some(fee) = lawsuit.filing_fee {
+        storage
+            .credit(
+                guild_id.into(),
+                lawsuit.plaintiff,
+                fee,
+                TransactionKind::FilingFeeRefund,
+                &lawsuit.reason,
+            )
+            .await
+            .wrap_err("refund filing fee")?;
+        storage
+            .credit(
+                guild_id.into(),
+                economy::treasury_account(guild_id.into()),
+                -fee,
+                TransactionKind::FilingFeeRefund,
+                &lawsuit.reason,
+            )
+            .await
+            .wrap_err("refund filing fee from treasury")?;
+    }
+    Ok(())
+}
+
+/// Claims a free court room of the category of the lawsuit. Another lawsuit might take a room that
+/// was free when the state was loaded, so a room is only returned if claiming it succeeded.
+pub async fn claim_free_room(
+    storage: &impl Storage,
+    guild_id: GuildId,
+    state: &State,
+    case_type: Option<CaseType>,
+) -> Result<Option<CourtRoom>> {
+    let category = state.court_category_for(case_type);
+
+    for room in state
+        .court_rooms
+        .iter()
+        .filter(|r| !r.ongoing_lawsuit && r.category.or(state.court_category) == category)
+    {
+        if storage
+            .claim_court_room(guild_id.into(), room.channel_id)
+            .await?
+        {
+            return Ok(Some(room.clone()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The part of [`LawsuitCtx::start`] that saves the lawsuit and gives the participants access to
+/// its court room.
+pub async fn admit(
+    storage: &impl Storage,
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    lawsuit: &Lawsuit,
+    room: &CourtRoom,
+) -> Result<()> {
+    storage.save_lawsuit(guild_id.into(), lawsuit).await?;
+    storage
+        .set_court_room_ongoing(guild_id.into(), room.channel_id, true)
+        .await?;
+
+    for user in lawsuit.participants() {
+        discord
+            .add_member_role(guild_id, user.into(), room.role_id.into())
+            .await
+            .wrap_err("add court room role to participant")?;
+    }
+
+    Ok(())
+}
+
+/// The part of [`LawsuitCtx::rule_verdict`] that saves the verdict, frees the court room and takes
+/// the access to it from the participants.
+pub async fn close(
+    storage: &impl Storage,
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    lawsuit: &Lawsuit,
+    room: &CourtRoom,
+) -> Result<()> {
+    tokio::try_join!(
+        storage.set_court_room_ongoing(guild_id.into(), lawsuit.court_room, false),
+        storage.set_verdict(guild_id.into(), lawsuit),
+    )?;
+
+    // members who left can't lose the role, so failures are only logged
+    for user in lawsuit.participants() {
+        let result = discord
+            .remove_member_role(guild_id, user.into(), room.role_id.into())
+            .await;
+        if let Err(err) = result {
+            warn!(?err, %user, "Failed to remove court room role after verdict");
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns why someone can't be the judge of the lawsuit, if they have a conflict of interest.
+/// `lawsuits` are all lawsuits of the guild.
+pub fn conflict_of_interest(
+    lawsuits: &[Lawsuit],
+    lawsuit: &Lawsuit,
+    judge: SnowflakeId,
+) -> Option<String> {
+    if judge == lawsuit.plaintiff || judge == lawsuit.accused {
+        return Some(format!("<@{judge}> isch Partei i dem Prozess"));
+    }
+    if Some(judge) == lawsuit.plaintiff_lawyer || Some(judge) == lawsuit.accused_lawyer {
+        return Some(format!("<@{judge}> isch Anwalt i dem Prozess"));
+    }
+
+    let parties = [lawsuit.plaintiff, lawsuit.accused];
+    let other_case = lawsuits.iter().find(|other| {
+        other.id != lawsuit.id
+            && !other.is_closed()
+            && ((other.plaintiff == judge && parties.contains(&other.accused))
+                || (other.accused == judge && parties.contains(&other.plaintiff)))
+    });
+
+    other_case.map(|other| {
+        format!(
+            "<@{judge}> hät en laufende Prozess (#{}) gege e Partei",
+            other.number
+        )
+    })
+}
+
+/// Picks a random member with the judge role who isn't the current judge of the lawsuit
+/// and has no conflict of interest.
+#[tracing::instrument(skip(http, lawsuits))]
+pub async fn pick_judge(
+    http: &Http,
+    guild_id: GuildId,
+    judge_role: SnowflakeId,
+    lawsuits: &[Lawsuit],
+    lawsuit: &Lawsuit,
+) -> Result<Option<UserId>> {
+    let judge_role = RoleId::from(judge_role);
+
+    let judges = guild_id
+        .members_iter(http)
+        .try_filter(|member| {
+            let id = member.user.id.into();
+            futures::future::ready(
+                member.roles.contains(&judge_role)
+                    && id != lawsuit.judge
+                    && conflict_of_interest(lawsuits, lawsuit, id).is_none(),
+            )
+        })
+        .map_ok(|member| member.user.id)
+        .try_collect::<Vec<_>>()
+        .await
+        .wrap_err("fetch members")?;
+
+    Ok(judges.choose(&mut rand::thread_rng()).copied())
+}
+
+/// Periodically reminds judges of fast track lawsuits about their deadline. Never returns.
+/// Creates a category for court rooms where @everyone can't write, but the judges can, and stores
+/// it. The participants can write in the room of their lawsuit through its role. `None` in sandbox
+/// mode, where the category is only simulated.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn create_court_category(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    bot_id: UserId,
+    name: String,
+    case_type: Option<CaseType>,
+) -> Result<Option<ChannelId>> {
+    if sandbox::simulate(http, guild_id, format!("Kategorie `{name}` erstellt")).await {
+        return Ok(None);
+    }
+
+    let state = mongo.find_or_insert_state(guild_id.into()).await?;
+
+    let write = Permissions::SEND_MESSAGES | Permissions::SEND_MESSAGES_IN_THREADS;
+    // the @everyone role has the id of the guild
+    let mut permissions = vec![
+        PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: write | Permissions::CREATE_PUBLIC_THREADS | Permissions::CREATE_PRIVATE_THREADS,
+            kind: PermissionOverwriteType::Role(RoleId(guild_id.0)),
+        },
+        PermissionOverwrite {
+            allow: write | Permissions::VIEW_CHANNEL,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(bot_id),
+        },
+    ];
+    if let Some(judge_role) = state.judge_role_for(case_type) {
+        permissions.push(PermissionOverwrite {
+            allow: write,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(judge_role.into()),
+        });
+    }
+
+    let category = guild_id
+        .create_channel(http, |channel| {
+            channel
+                .name(name)
+                .kind(ChannelType::Category)
+                .permissions(permissions)
+        })
+        .await
+        .wrap_err("create court category")?;
+
+    match case_type {
+        Some(case_type) => {
+            mongo
+                .update_case_type_config(guild_id.into(), case_type, |config| {
+                    config.court_category = Some(category.id.into())
+                })
+                .await?
+        }
+        None => {
+            mongo
+                .set_court_category(guild_id.into(), category.id.into())
+                .await?
+        }
+    }
+
+    info!(category_id = %category.id, "Created court category");
+
+    Ok(Some(category.id))
+}
+
+pub async fn fast_track_task(mongo: Mongo, http: Arc<Http>, maintenance: Maintenance) {
+    let mut interval = TaskInterval::new("fast_track", FAST_TRACK_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if maintenance.is_active() {
+            continue;
+        }
+
+        if let Err(err) = remind_fast_track_judges(&mongo, &http).await {
+            error!(?err, "Error reminding fast track judges");
+        }
+    }
+}
+
+#[tracing::instrument(skip(mongo, http))]
+pub async fn remind_fast_track_judges(mongo: &Mongo, http: &Http) -> Result<()> {
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        let now = bson::DateTime::now().timestamp_millis();
+
+        for state in mongo.find_states_with_open_fast_track().await? {
+            if sandbox::report_channel(state.guild_id.into()).is_some() {
+                continue;
+            }
+            for lawsuit in state
+                .lawsuits
+                .iter()
+                .filter(|l| l.fast_track && !l.is_closed() && !l.paused)
+            {
+                let deadline = match lawsuit.decision_deadline {
+                    Some(deadline) => deadline.timestamp_millis(),
+                    None => continue,
+                };
+
+                let message = if now >= deadline && lawsuit.reminders_sent < 2 {
+                    format!(
+                        "<@{}>, d'Frist für es Urteil i dem Schnellverfahre isch abgloffe!",
+                        lawsuit.judge
+                    )
+                } else if now >= deadline - FAST_TRACK_REMINDER.as_millis() as i64
+                    && lawsuit.reminders_sent < 1
+                {
+                    format!(
+                        "<@{}>, du muesch bis <t:{}:R> es Urteil fälle",
+                        lawsuit.judge,
+                        deadline / 1000
+                    )
+                } else {
+                    continue;
+                };
+
+                let reminders_sent = if now >= deadline { 2 } else { 1 };
+                mongo
+                    .set_lawsuit(
+                        state.guild_id,
+                        lawsuit.id,
+                        doc! { "lawsuits.$.reminders_sent": reminders_sent },
+                    )
+                    .await?;
+
+                if let Err(err) = ChannelId::from(lawsuit.court_room).say(http, message).await {
+                    error!(?err, lawsuit_id = %lawsuit.id, "Failed to send fast track reminder");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reminds the parties in the court room that the deadline of the phase is near.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn remind_deadline(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    phase: Phase,
+    due: bson::DateTime,
+) -> Result<()> {
+    let lawsuit = match find_with_deadline(mongo, guild_id, lawsuit_id, phase, due).await? {
+        Some(lawsuit) => lawsuit,
+        None => return Ok(()),
+    };
+
+    let mentions = [
+        lawsuit.plaintiff_mention(),
+        format!("<@{}>", lawsuit.accused),
+    ]
+    .into_iter()
+    .chain(
+        [lawsuit.plaintiff_lawyer, lawsuit.accused_lawyer]
+            .into_iter()
+            .flatten()
+            .map(|lawyer| format!("<@{lawyer}>")),
+    )
+    .collect::<Vec<_>>()
+    .join(" ");
+    ChannelId::from(lawsuit.court_room)
+        .say(
+            http,
+            format!(
+                "{mentions}, d'Frist für {phase} lauft <t:{}:R> ab",
+                due.timestamp_millis() / 1000
+            ),
+        )
+        .await
+        .wrap_err("send deadline reminder")?;
+
+    Ok(())
+}
+
+/// Announces that the deadline of the phase is over, and pings the judge if it was the last one.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn expire_deadline(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    phase: Phase,
+    due: bson::DateTime,
+) -> Result<()> {
+    let lawsuit = match find_with_deadline(mongo, guild_id, lawsuit_id, phase, due).await? {
+        Some(lawsuit) => lawsuit,
+        None => return Ok(()),
+    };
+
+    let mut message = format!("d'Frist für {phase} isch abgloffe");
+    if lawsuit.deadlines.iter().all(|deadline| deadline.due <= due) {
+        message.push_str(&format!(
+            "\n<@{}>, alli Friste sind abgloffe, de Prozess isch parat für es Urteil",
+            lawsuit.judge
+        ));
+    }
+    ChannelId::from(lawsuit.court_room)
+        .say(http, message)
+        .await
+        .wrap_err("send deadline expiry")?;
+
+    info!(%lawsuit_id, ?phase, "Deadline expired");
+
+    Ok(())
+}
+
+/// Reminds the judge in the court room to review the detention of the accused.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn review_detention(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    due: bson::DateTime,
+) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    // the detention ended or the review was moved in the meantime
+    let lawsuit = state.lawsuits.iter().find(|lawsuit| {
+        lawsuit.id == lawsuit_id && !lawsuit.is_closed() && lawsuit.detention_review == Some(due)
+    });
+    let (lawsuit, detained_at) = match lawsuit {
+        Some(lawsuit) => match lawsuit.detained_at {
+            Some(detained_at) => (lawsuit, detained_at),
+            None => return Ok(()),
+        },
+        None => return Ok(()),
+    };
+
+    let channel = match (lawsuit.court_room, state.log_channel) {
+        (room, _) if room != SnowflakeId(0) => room,
+        (_, Some(log_channel)) => log_channel,
+        _ => return Ok(()),
+    };
+    let message = format!(
+        "<@{}>, <@{}> isch sit <t:{}:R> in Untersuechigshaft für de Prozess #{}. \
+         Es isch Ziit für e Haftprüefig, mit `/lawsuit detention end` chasch d'Haft beende",
+        lawsuit.judge,
+        lawsuit.accused,
+        detained_at.timestamp_millis() / 1000,
+        lawsuit.number
+    );
+    ChannelId::from(channel)
+        .say(http, message)
+        .await
+        .wrap_err("send detention review")?;
+
+    info!(%lawsuit_id, "Reviewed detention");
+
+    Ok(())
+}
+
+/// Executes the verdict once the appeal window is over. Does nothing while it's under appeal, the
+/// appeal executes or sets it aside once it's closed.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn finalize_verdict(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let lawsuit = match state.lawsuits.iter().find(|l| l.id == lawsuit_id) {
+        Some(lawsuit) if lawsuit.awaits_execution(&state.lawsuits) => lawsuit.clone(),
+        _ => return Ok(()),
+    };
+
+    let mut lawsuit_ctx = LawsuitCtx {
+        lawsuit,
+        mongo_client: mongo.clone(),
+        http: http.clone(),
+        guild_id: guild_id.into(),
+    };
+    lawsuit_ctx.lawsuit.appealable_until = None;
+    mongo
+        .set_lawsuit(
+            guild_id,
+            lawsuit_id,
+            doc! { "lawsuits.$.appealable_until": None::<bson::DateTime> },
+        )
+        .await?;
+
+    let judge = lawsuit_ctx.lawsuit.judge;
+    lawsuit_ctx
+        .add_history(judge, "S'Urteil isch rechtskräftig".to_string())
+        .await?;
+    lawsuit_ctx.execute_verdict().await?;
+
+    Ok(())
+}
+
+/// The lawsuit if it's still running and the deadline of the phase wasn't changed since the job
+/// was scheduled.
+async fn find_with_deadline(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    phase: Phase,
+    due: bson::DateTime,
+) -> Result<Option<Lawsuit>> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    Ok(state.lawsuits.into_iter().find(|lawsuit| {
+        lawsuit.id == lawsuit_id
+            && !lawsuit.is_closed()
+            && lawsuit.court_room != SnowflakeId(0)
+            && lawsuit
+                .deadline(phase)
+                .is_some_and(|deadline| deadline.due == due)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{DateTime, Uuid};
+    use poise::serenity_prelude::{GuildId, RoleId, UserId};
+
+    use super::{
+        admit, claim_free_room, close, file, refund_filing_fee, Deadline, Lawsuit, LawsuitStatus,
+        Party, Phase,
+    };
+    use crate::{
+        economy::{Debt, TransactionKind},
+        model::{CourtRoom, PrisonEntry, SnowflakeId, State},
+        testing::{FakeDiscord, MemoryStorage},
+    };
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    const GUILD: GuildId = GuildId(1);
+    const PLAINTIFF: UserId = UserId(2);
+    const ACCUSED: UserId = UserId(3);
+    const JUDGE: UserId = UserId(4);
+    const JUDGE_ROLE: RoleId = RoleId(5);
+    const ROOM_CHANNEL: SnowflakeId = SnowflakeId(6);
+    const ROOM_ROLE: RoleId = RoleId(7);
+    const FEE: i64 = 10;
+
+    fn court() -> (MemoryStorage, FakeDiscord) {
+        let mut state = State::new(GUILD.into());
+        state.judge_role = Some(JUDGE_ROLE.into());
+        state.filing_fee = Some(FEE);
+        state.court_rooms.push(CourtRoom {
+            channel_id: ROOM_CHANNEL,
+            ongoing_lawsuit: false,
+            role_id: ROOM_ROLE.into(),
+            category: None,
+        });
+        let storage = MemoryStorage::with_state(state);
+        storage
+            .accounts
+            .lock()
+            .unwrap()
+            .insert((GUILD.into(), PLAINTIFF.into()), 50);
+
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, PLAINTIFF, &[]);
+        discord.add_member(GUILD, ACCUSED, &[]);
+        discord.add_member(GUILD, JUDGE, &[JUDGE_ROLE]);
+
+        (storage, discord)
+    }
+
+    fn new_lawsuit() -> Lawsuit {
+        Lawsuit::new(
+            PLAINTIFF.into(),
+            ACCUSED.into(),
+            JUDGE.into(),
+            "Diebstahl".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn create_open_and_close() {
+        let (storage, discord) = court();
+        let state = storage.state(GUILD);
+        let mut lawsuit = new_lawsuit();
+
+        file(&storage, &discord, GUILD, &state, &mut lawsuit)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(lawsuit.number, 1);
+        assert_eq!(lawsuit.filing_fee, Some(FEE));
+        assert_eq!(storage.balance(GUILD, PLAINTIFF.into()), 50 - FEE);
+        assert_eq!(storage.balance(GUILD, GUILD.into()), FEE);
+
+        let room = claim_free_room(&storage, GUILD, &state, None)
+            .await
+            .unwrap()
+            .unwrap();
+        lawsuit.court_room = room.channel_id;
+        admit(&storage, &discord, GUILD, &lawsuit, &room)
+            .await
+            .unwrap();
+
+        let state = storage.state(GUILD);
+        assert!(state.court_rooms[0].ongoing_lawsuit);
+        assert!(state.lawsuits.iter().any(|l| l.id == lawsuit.id));
+        for user in [PLAINTIFF, ACCUSED, JUDGE] {
+            assert!(discord.has_role(GUILD, user, ROOM_ROLE));
+        }
+
+        lawsuit.verdict = Some("schuldig".to_string());
+        lawsuit.winner = Some(Party::Plaintiff);
+        lawsuit.closed_at = Some(DateTime::now());
+        close(&storage, &discord, GUILD, &lawsuit, &room)
+            .await
+            .unwrap();
+
+        let state = storage.state(GUILD);
+        assert!(!state.court_rooms[0].ongoing_lawsuit);
+        let saved = state.lawsuits.iter().find(|l| l.id == lawsuit.id).unwrap();
+        assert_eq!(saved.verdict.as_deref(), Some("schuldig"));
+        assert!(saved.is_closed());
+        for user in [PLAINTIFF, ACCUSED, JUDGE] {
+            assert!(!discord.has_role(GUILD, user, ROOM_ROLE));
+        }
+    }
+
+    #[tokio::test]
+    async fn anonymous_plaintiff_stays_out_of_the_room() {
+        let (storage, discord) = court();
+        let state = storage.state(GUILD);
+        let mut lawsuit = Lawsuit {
+            anonymous: true,
+            ..new_lawsuit()
+        };
+
+        file(&storage, &discord, GUILD, &state, &mut lawsuit)
+            .await
+            .unwrap()
+            .unwrap();
+        let room = claim_free_room(&storage, GUILD, &state, None)
+            .await
+            .unwrap()
+            .unwrap();
+        admit(&storage, &discord, GUILD, &lawsuit, &room)
+            .await
+            .unwrap();
+
+        assert!(!discord.has_role(GUILD, PLAINTIFF, ROOM_ROLE));
+        assert!(discord.has_role(GUILD, ACCUSED, ROOM_ROLE));
+    }
+
+    #[tokio::test]
+    async fn judge_needs_judge_role() {
+        let (storage, discord) = court();
+        discord.add_member(GUILD, JUDGE, &[]);
+        let state = storage.state(GUILD);
+        let mut lawsuit = new_lawsuit();
+
+        let result = file(&storage, &discord, GUILD, &state, &mut lawsuit)
+            .await
+            .unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(storage.balance(GUILD, PLAINTIFF.into()), 50);
+        assert_eq!(storage.state(GUILD).case_counter, 0);
+    }
+
+    #[tokio::test]
+    async fn plaintiff_cannot_pay_fee() {
+        let (storage, discord) = court();
+        storage.accounts.lock().unwrap().clear();
+        let state = storage.state(GUILD);
+        let mut lawsuit = new_lawsuit();
+
+        let result = file(&storage, &discord, GUILD, &state, &mut lawsuit)
+            .await
+            .unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(lawsuit.filing_fee, None);
+        assert!(storage.transactions.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn debts_block_filing_before_the_fee() {
+        let (storage, discord) = court();
+        storage
+            .states
+            .lock()
+            .unwrap()
+            .get_mut(&GUILD.into())
+            .unwrap()
+            .debt_blocks_filings = true;
+        storage.debts.lock().unwrap().push(Debt {
+            id: Uuid::new(),
+            guild_id: GUILD.into(),
+            debtor: PLAINTIFF.into(),
+            creditor: ACCUSED.into(),
+            amount: 5,
+            reason: "Schadenersatz".to_string(),
+            lawsuit_id: None,
+            created_at: DateTime::now(),
+            garnished_until: None,
+        });
+        let state = storage.state(GUILD);
+        let mut lawsuit = new_lawsuit();
+
+        let result = file(&storage, &discord, GUILD, &state, &mut lawsuit)
+            .await
+            .unwrap();
+
+        assert!(result.is_err());
+        assert!(storage.transactions.lock().unwrap().is_empty());
+        assert_eq!(storage.balance(GUILD, PLAINTIFF.into()), 50);
+    }
+
+    #[tokio::test]
+    async fn taken_room_is_not_claimed_twice() {
+        let (storage, _) = court();
+        let state = storage.state(GUILD);
+
+        let first = claim_free_room(&storage, GUILD, &state, None)
+            .await
+            .unwrap();
+        // the second lawsuit still sees the room as free in its state
+        let second = claim_free_room(&storage, GUILD, &state, None)
+            .await
+            .unwrap();
+
+        assert!(first.is_some());
+        assert!(second.is_none());
+    }
+
+    #[tokio::test]
+    async fn filing_fee_refund() {
+        let (storage, discord) = court();
+        let state = storage.state(GUILD);
+        let mut lawsuit = new_lawsuit();
+
+        file(&storage, &discord, GUILD, &state, &mut lawsuit)
+            .await
+            .unwrap()
+            .unwrap();
+        refund_filing_fee(&storage, GUILD, &lawsuit).await.unwrap();
+
+        assert_eq!(storage.balance(GUILD, PLAINTIFF.into()), 50);
+        assert_eq!(storage.balance(GUILD, GUILD.into()), 0);
+        let kinds = storage
+            .transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|transaction| transaction.2)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                TransactionKind::FilingFee,
+                TransactionKind::FilingFee,
+                TransactionKind::FilingFeeRefund,
+                TransactionKind::FilingFeeRefund,
+            ]
+        );
+    }
+
+    #[test]
+    fn appeals_pause_execution() {
+        let lawsuit = |number, appeal_of, closed| Lawsuit {
+            number,
+            appeal_of,
+            status: if closed {
+                LawsuitStatus::Withdrawn
+            } else {
+                LawsuitStatus::Active
+            },
+            ..Lawsuit::new(
+                SnowflakeId(1),
+                SnowflakeId(2),
+                SnowflakeId(3),
+                "test".to_string(),
+            )
+        };
+        let verdict = Lawsuit {
+            appealable_until: Some(DateTime::now()),
+            ..lawsuit(1, None, false)
+        };
+
+        assert!(verdict.awaits_execution(&[]));
+        assert!(!verdict.awaits_execution(&[lawsuit(2, Some(1), false)]));
+        assert!(verdict.awaits_execution(&[lawsuit(2, Some(1), true)]));
+        assert!(!lawsuit(1, None, false).awaits_execution(&[]));
+    }
+
+    #[test]
+    fn suspended_prison_sentence() {
+        let lawsuit = Lawsuit {
+            prison_secs: Some(3 * 24 * 60 * 60),
+            ..Lawsuit::new(
+                SnowflakeId(1),
+                SnowflakeId(2),
+                SnowflakeId(3),
+                "test".to_string(),
+            )
+        };
+        assert_eq!(lawsuit.prison_sentence().as_deref(), Some("3d"));
+
+        let suspended = Lawsuit {
+            probation_secs: Some(30 * 24 * 60 * 60),
+            ..lawsuit
+        };
+        assert_eq!(
+            suspended.prison_sentence().as_deref(),
+            Some("3d uf Bewährig (30d)")
+        );
+    }
+
+    #[test]
+    fn time_served_is_credited() {
+        let now = DateTime::now();
+        let days_ago =
+            |days: i64| DateTime::from_millis(now.timestamp_millis() - days * DAY * 1000);
+        let lawsuit = Lawsuit {
+            prison_secs: Some(3 * DAY as u64),
+            created_at: Some(days_ago(2)),
+            ..Lawsuit::new(
+                SnowflakeId(1),
+                SnowflakeId(2),
+                SnowflakeId(3),
+                "test".to_string(),
+            )
+        };
+        let entry = |arrested_at| PrisonEntry {
+            guild_id: SnowflakeId(1),
+            user_id: SnowflakeId(2),
+            arrested_at: Some(arrested_at),
+            left_at: None,
+        };
+
+        // only the time since the filing counts
+        let served = lawsuit.time_served(Some(&entry(days_ago(5))), now);
+        assert_eq!(served, Some(2 * DAY as u64));
+        assert_eq!(lawsuit.time_served(None, now), None);
+
+        let credited = Lawsuit {
+            time_served_secs: served,
+            ..lawsuit
+        };
+        assert_eq!(credited.remaining_prison_secs(), Some(DAY as u64));
+        assert_eq!(
+            credited.prison_sentence().as_deref(),
+            Some("3d - 2d Untersuechigshaft = 1d")
+        );
+    }
+
+    #[test]
+    fn deadlines_lock_their_phase() {
+        let now = DateTime::now();
+        let mut lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "test".to_string(),
+        );
+        lawsuit.deadlines = vec![
+            Deadline {
+                phase: Phase::Statements,
+                due: DateTime::from_millis(now.timestamp_millis() - 1000),
+            },
+            Deadline {
+                phase: Phase::Lawyers,
+                due: DateTime::from_millis(now.timestamp_millis() + 1000),
+            },
+        ];
+
+        assert!(lawsuit.is_locked(Phase::Statements, now));
+        assert!(!lawsuit.is_locked(Phase::Lawyers, now));
+        assert!(!lawsuit.is_locked(Phase::Amendments, now));
+    }
+
+    #[test]
+    fn anonymous_plaintiff_is_involved_but_not_a_participant() {
+        let mut lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "test".to_string(),
+        );
+        lawsuit.plaintiff_lawyer = Some(SnowflakeId(4));
+        assert_eq!(
+            lawsuit.participants(),
+            [1, 4, 2, 3].map(SnowflakeId).to_vec()
+        );
+
+        lawsuit.anonymous = true;
+        assert_eq!(lawsuit.participants(), [4, 2, 3].map(SnowflakeId).to_vec());
+        assert_eq!(lawsuit.involved(), [1, 4, 2, 3].map(SnowflakeId).to_vec());
+    }
+}
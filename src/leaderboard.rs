@@ -0,0 +1,241 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use chrono::{Datelike, TimeZone};
+use chrono_tz::Tz;
+use mongodb::bson::DateTime;
+use serde::Deserialize;
+
+use crate::{
+    duration::format_duration,
+    lawsuit::{Lawsuit, LawsuitStatus, Party},
+    model::SnowflakeId,
+};
+
+/// How many places a leaderboard shows.
+pub const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum Period {
+    #[name = "Dieser Monat"]
+    Month,
+    #[name = "Gesamt"]
+    AllTime,
+}
+
+impl Period {
+    /// Whether something that happened at the time counts for the period. Months are calendar
+    /// months in the time zone of the guild. Without a time it only counts for all time.
+    pub fn contains(self, tz: Tz, now: DateTime, time: Option<DateTime>) -> bool {
+        match (self, time) {
+            (Self::AllTime, _) => true,
+            (Self::Month, None) => false,
+            (Self::Month, Some(time)) => {
+                let month = |time: DateTime| {
+                    let local = tz.timestamp_millis(time.timestamp_millis());
+                    (local.year(), local.month())
+                };
+                month(time) == month(now)
+            }
+        }
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Month => "dä Monet",
+            Self::AllTime => "vo allne Zyte",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum PrisonRanking {
+    #[name = "Längste Haft"]
+    TimeServed,
+    #[name = "Meiste Verhaftungen"]
+    Arrests,
+}
+
+/// The finished stays in prison of a member.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrisonRecord {
+    #[serde(rename = "_id")]
+    pub user_id: SnowflakeId,
+    pub arrests: i64,
+    pub served_ms: i64,
+}
+
+impl Display for PrisonRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let served = Duration::from_millis(self.served_ms.max(0) as u64);
+        write!(
+            f,
+            "<@{}> – {} mal verhaftet, {} gsässe",
+            self.user_id,
+            self.arrests,
+            format_duration(served)
+        )
+    }
+}
+
+/// How the closed lawsuits went for a lawyer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LawyerRecord {
+    pub lawyer: SnowflakeId,
+    pub won: u32,
+    pub lost: u32,
+    /// Withdrawn lawsuits, which is how the parties settle.
+    pub settled: u32,
+}
+
+impl Display for LawyerRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<@{}> – {} gwunne, {} verlore, {} vergliche",
+            self.lawyer, self.won, self.lost, self.settled
+        )
+    }
+}
+
+/// The records of the lawyers who represented a side in the closed lawsuits, the most wins first.
+/// The outcome is attributed to the lawyers the sides had when the lawsuit was closed.
+pub fn lawyer_records<'a>(lawsuits: impl IntoIterator<Item = &'a Lawsuit>) -> Vec<LawyerRecord> {
+    let mut records = HashMap::<SnowflakeId, LawyerRecord>::new();
+
+    for lawsuit in lawsuits {
+        for side in [Party::Plaintiff, Party::Accused] {
+            let lawyer = match lawsuit.lawyer(side) {
+                Some(lawyer) => lawyer,
+                None => continue,
+            };
+            let record = records.entry(lawyer).or_insert(LawyerRecord {
+                lawyer,
+                won: 0,
+                lost: 0,
+                settled: 0,
+            });
+            match lawsuit.winner {
+                Some(winner) if winner == side => record.won += 1,
+                Some(_) => record.lost += 1,
+                None if lawsuit.status == LawsuitStatus::Withdrawn => record.settled += 1,
+                None => {}
+            }
+        }
+    }
+
+    let mut records = records
+        .into_values()
+        .filter(|record| record.won + record.lost + record.settled > 0)
+        .collect::<Vec<_>>();
+    records.sort_by_key(|record| {
+        (
+            std::cmp::Reverse(record.won),
+            record.lost,
+            std::cmp::Reverse(record.settled),
+            record.lawyer.0,
+        )
+    });
+    records
+}
+
+/// How often each member was accused, the most first. Merged lawsuits only count once, for the
+/// lawsuit they were merged into.
+pub fn most_sued<'a>(lawsuits: impl IntoIterator<Item = &'a Lawsuit>) -> Vec<(SnowflakeId, u32)> {
+    let mut counts = HashMap::<SnowflakeId, u32>::new();
+    for lawsuit in lawsuits {
+        if lawsuit.status != LawsuitStatus::Merged {
+            *counts.entry(lawsuit.accused).or_default() += 1;
+        }
+    }
+
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by_key(|&(user_id, count)| (std::cmp::Reverse(count), user_id.0));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Zurich;
+    use mongodb::bson::DateTime;
+
+    use super::{lawyer_records, most_sued, Period};
+    use crate::{
+        lawsuit::{Lawsuit, LawsuitStatus, Party},
+        model::SnowflakeId,
+    };
+
+    fn lawsuit(plaintiff_lawyer: u64, accused_lawyer: Option<u64>) -> Lawsuit {
+        let mut lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "gmein".to_string(),
+        );
+        lawsuit.plaintiff_lawyer = Some(SnowflakeId(plaintiff_lawyer));
+        lawsuit.accused_lawyer = accused_lawyer.map(SnowflakeId);
+        lawsuit
+    }
+
+    #[test]
+    fn lawyers() {
+        let mut won = lawsuit(10, Some(11));
+        won.winner = Some(Party::Plaintiff);
+        let mut lost = lawsuit(10, None);
+        lost.winner = Some(Party::Accused);
+        let mut settled = lawsuit(11, None);
+        settled.status = LawsuitStatus::Withdrawn;
+        let dismissed = lawsuit(12, None);
+
+        let records = lawyer_records(&[won, lost, settled, dismissed]);
+        let summary = records
+            .iter()
+            .map(|r| (r.lawyer.0, r.won, r.lost, r.settled))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![(10, 1, 1, 0), (11, 0, 1, 1)]);
+    }
+
+    #[test]
+    fn sued() {
+        let accused = |id, status| {
+            let mut lawsuit = lawsuit(10, None);
+            lawsuit.accused = SnowflakeId(id);
+            lawsuit.status = status;
+            lawsuit
+        };
+        let lawsuits = [
+            accused(5, LawsuitStatus::Active),
+            accused(4, LawsuitStatus::Withdrawn),
+            accused(5, LawsuitStatus::Dismissed),
+            accused(4, LawsuitStatus::Merged),
+        ];
+        assert_eq!(
+            most_sued(&lawsuits),
+            vec![(SnowflakeId(5), 2), (SnowflakeId(4), 1)]
+        );
+    }
+
+    #[test]
+    fn month() {
+        let at = |month, day| {
+            DateTime::from_millis(
+                Zurich
+                    .ymd(2022, month, day)
+                    .and_hms(0, 30, 0)
+                    .timestamp_millis(),
+            )
+        };
+        let now = at(7, 15);
+        // still june in UTC
+        assert!(Period::Month.contains(Zurich, now, Some(at(7, 1))));
+        assert!(!Period::Month.contains(Zurich, now, Some(at(6, 30))));
+        assert!(!Period::Month.contains(Zurich, now, None));
+        assert!(Period::AllTime.contains(Zurich, now, None));
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, env, path::PathBuf, sync::OnceLock, time::Instant};
+
+use color_eyre::{Report, Result};
+use mongodb::bson::Document;
+use poise::{ApplicationContext, BoxFuture, Command, FrameworkError};
+use rand::Rng;
+use tracing::{info, info_span, Instrument};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    fmt::writer::BoxMakeWriter, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter,
+    Registry,
+};
+
+use crate::{handler::Handler, Context, WrapErr};
+
+/// How the logs are written, set with `LOG_FORMAT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One line per event.
+    Text,
+    /// A tree of the spans, for development.
+    Pretty,
+    /// One JSON object per event, including the fields of all spans.
+    Json,
+}
+
+impl LogFormat {
+    /// `PRETTY` is still supported from before there was `LOG_FORMAT`.
+    pub fn from_env() -> Self {
+        match env::var("LOG_FORMAT").as_deref() {
+            Ok("json") => Self::Json,
+            Ok("pretty") => Self::Pretty,
+            Ok(_) => Self::Text,
+            Err(_) if env::var("PRETTY").is_ok() => Self::Pretty,
+            Err(_) => Self::Text,
+        }
+    }
+}
+
+/// Changes the log filter while the bot is running.
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Replaces the log filter, with the syntax of `RUST_LOG`. `None` goes back to `RUST_LOG`.
+pub fn set_log_filter(filter: Option<&str>) -> Result<()> {
+    let filter = match filter {
+        Some(filter) => EnvFilter::try_new(filter).wrap_err("invalid log filter")?,
+        None => EnvFilter::from_default_env(),
+    };
+    if let Some(handle) = FILTER_HANDLE.get() {
+        handle.reload(filter).wrap_err("reload log filter")?;
+    }
+    Ok(())
+}
+
+/// Sets up the global subscriber. If `LOG_DIR` is set, the logs are written to a file in it
+/// instead of stdout, starting a new file every day. The returned guard must be kept until the
+/// end, it flushes the file when dropped.
+pub fn setup_tracing(format: LogFormat) -> Option<WorkerGuard> {
+    // errors become sentry events, other logs are attached to them. does nothing without sentry
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+    let _ = FILTER_HANDLE.set(handle);
+    let registry = Registry::default()
+        .with(filter)
+        .with(tracing_error::ErrorLayer::default())
+        .with(sentry_tracing::layer());
+
+    let log_dir = env::var("LOG_DIR").ok().map(PathBuf::from);
+    let (writer, guard) = match &log_dir {
+        Some(dir) => {
+            let appender = tracing_appender::rolling::daily(dir, "court-bot.log");
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (BoxMakeWriter::new(writer), Some(guard))
+        }
+        None => (BoxMakeWriter::new(std::io::stdout), None),
+    };
+
+    match format {
+        LogFormat::Pretty => {
+            let tree_layer = tracing_tree::HierarchicalLayer::new(2)
+                .with_targets(true)
+                .with_bracketed_fields(true);
+
+            registry.with(tree_layer).init();
+        }
+        LogFormat::Text => {
+            let fmt_layer = tracing_subscriber::fmt::layer()
+                .with_level(true)
+                .with_timer(tracing_subscriber::fmt::time::time())
+                .with_ansi(log_dir.is_none())
+                .with_thread_names(true)
+                .with_writer(writer);
+
+            registry.with(fmt_layer).init();
+        }
+        LogFormat::Json => {
+            let json_layer = tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_thread_names(true)
+                .with_writer(writer);
+
+            registry.with(json_layer).init();
+        }
+    }
+
+    if let Some(dir) = log_dir {
+        info!(dir = %dir.display(), "Writing logs to files");
+    }
+
+    guard
+}
+
+/// Reports errors and panics to Sentry, or any compatible service, if `SENTRY_DSN` is set. Must be
+/// called after the panic hook of color-eyre is installed so that both run. The returned guard
+/// must be kept until the end, it sends the remaining events when dropped.
+pub fn setup_sentry() -> Option<sentry::ClientInitGuard> {
+    let dsn = env::var("SENTRY_DSN").ok()?;
+
+    let guard = sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            environment: env::var("SENTRY_ENVIRONMENT").ok().map(Into::into),
+            ..Default::default()
+        },
+    ));
+
+    Some(guard)
+}
+
+/// Identifies the log lines of a single command invocation, set in the pre-command hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorrelationId(pub u32);
+
+impl CorrelationId {
+    pub fn random() -> Self {
+        Self(rand::thread_rng().gen())
+    }
+}
+
+impl std::fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+/// Stored as the invocation data of every command by the pre-command hook.
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    pub correlation_id: CorrelationId,
+    pub started_at: Instant,
+    /// The data of the guild before the command, compared afterwards to record an event.
+    pub state_before: Option<Document>,
+}
+
+impl Invocation {
+    pub fn start() -> Self {
+        Self {
+            correlation_id: CorrelationId::random(),
+            started_at: Instant::now(),
+            state_before: None,
+        }
+    }
+}
+
+/// The correlation id of the invocation, or `-` if the pre-command hook didn't run yet, like for
+/// failed checks.
+pub async fn correlation_id(ctx: Context<'_>) -> String {
+    match ctx.invocation_data::<Invocation>().await {
+        Some(invocation) => invocation.correlation_id.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+type SlashAction = for<'a> fn(
+    ApplicationContext<'a, Handler, Report>,
+) -> BoxFuture<'a, Result<(), FrameworkError<'a, Handler, Report>>>;
+
+/// The actions of the commands by qualified name, replaced by [`instrumented_action`].
+static SLASH_ACTIONS: OnceLock<HashMap<String, SlashAction>> = OnceLock::new();
+
+/// Runs every command in a span with the command, guild, channel, user and correlation id, so
+/// that all logs of the command have them. poise has no hook that wraps the command itself, so the
+/// actions are replaced with one that looks up the real action by name.
+pub fn instrument_commands(
+    mut commands: Vec<Command<Handler, Report>>,
+) -> Vec<Command<Handler, Report>> {
+    let mut actions = HashMap::new();
+    replace_actions(&mut commands, &mut actions);
+    if SLASH_ACTIONS.set(actions).is_err() {
+        panic!("commands were already instrumented");
+    }
+    commands
+}
+
+fn replace_actions(
+    commands: &mut [Command<Handler, Report>],
+    actions: &mut HashMap<String, SlashAction>,
+) {
+    for command in commands {
+        if let Some(action) = command.slash_action.replace(instrumented_action) {
+            actions.insert(command.qualified_name.clone(), action);
+        }
+        replace_actions(&mut command.subcommands, actions);
+    }
+}
+
+fn instrumented_action(
+    ctx: ApplicationContext<'_, Handler, Report>,
+) -> BoxFuture<'_, Result<(), FrameworkError<'_, Handler, Report>>> {
+    Box::pin(async move {
+        let action = SLASH_ACTIONS
+            .get()
+            .and_then(|actions| actions.get(&ctx.command.qualified_name))
+            .expect("action of instrumented command not found");
+
+        let context = Context::Application(ctx);
+        let correlation_id = correlation_id(context).await;
+        let span = info_span!(
+            "command",
+            command = %ctx.command.qualified_name,
+            guild_id = ?context.guild_id(),
+            channel_id = %context.channel_id(),
+            user_id = %context.author().id,
+            %correlation_id,
+        );
+
+        action(ctx).instrument(span).await
+    })
+}
@@ -1,20 +1,78 @@
 extern crate core;
 
+mod activity;
+mod api;
+mod arbitration;
+mod arrest_appeal;
+mod branding;
+mod bulk;
+mod config;
+mod court_session;
+mod dashboard;
+mod discord_api;
+mod docket;
+mod duration;
+mod economy;
+mod events;
+mod export;
+mod federation;
+mod global_list;
+mod grand_jury;
+mod guild_access;
 mod handler;
+mod history_import;
+mod intake;
+mod keyword_watch;
 mod lawsuit;
+mod leaderboard;
+mod logging;
+mod maintenance;
+mod messages;
 mod model;
+mod moderation;
+mod notification;
+mod opinion_poll;
+mod ordeal;
+mod pdf;
+mod petition;
+mod presence;
+mod prison;
+mod probation;
+mod rejoin_checks;
+mod retention;
+mod room_permissions;
+mod sandbox;
+mod scheduler;
+mod search;
+mod service;
+mod signature;
+mod storage;
+mod subpoena;
+#[cfg(test)]
+mod testing;
+mod undo;
+mod usage;
+mod verdict_template;
+mod visitation;
+mod warrant;
+mod webhook;
+mod work_release;
 
-use std::env;
+use std::{env, net::SocketAddr, time::Instant};
 
 use color_eyre::{eyre::WrapErr, Report, Result};
 use poise::{
     serenity_prelude as serenity,
-    serenity_prelude::{Activity, GatewayIntents, GuildId},
+    serenity_prelude::{GatewayIntents, GuildId},
 };
 use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
 
-use crate::{handler::Handler, model::Mongo};
+use crate::{
+    handler::Handler,
+    logging::{Invocation, LogFormat},
+    maintenance::Maintenance,
+    model::Mongo,
+};
 
 type Context<'a> = poise::Context<'a, Handler, Report>;
 
@@ -24,12 +82,15 @@ async fn main() -> Result<()> {
 
     let _ = dotenv::dotenv();
 
-    let pretty = env::var("PRETTY").is_ok();
-
-    setup_tracing(pretty);
+    let _sentry_guard = logging::setup_sentry();
+    let _log_guard = logging::setup_tracing(LogFormat::from_env());
 
     info!("Starting up...");
 
+    if config::reload().await? {
+        tokio::spawn(config::watch_task());
+    }
+
     let mongo_uri = env::var("MONGO_URI").wrap_err("MONGO_URI not found in the environment")?;
     let db_name = env::var("DB_NAME").unwrap_or_else(|_| "court-bot".to_string());
 
@@ -38,10 +99,14 @@ async fn main() -> Result<()> {
     let password = env::var("MONGO_INITDB_ROOT_PASSWORD")
         .wrap_err("MONGO_INITDB_ROOT_PASSWORD not found in the environment")?;
 
-    let mongo = Mongo::connect(&mongo_uri, &db_name, username, password).await?;
+    let per_guild = env::var("DB_PER_GUILD").is_ok();
+
+    let mongo = Mongo::connect(&mongo_uri, &db_name, username, password, per_guild).await?;
 
     info!("Connected to mongodb");
 
+    sandbox::load(&mongo).await?;
+
     let token = env::var("DISCORD_TOKEN").wrap_err("DISCORD_TOKEN not found in environment")?;
     let dev_guild_id = if env::var("DEV").is_ok() {
         Some(GuildId(
@@ -56,14 +121,85 @@ async fn main() -> Result<()> {
 
     let set_global_commands = env::var("SET_GLOBAL").is_ok();
 
+    // reading messages needs a privileged intent, which is only requested when it's used
+    let keyword_watch_enabled = env::var("KEYWORD_WATCH").is_ok();
+    let mut intents = GatewayIntents::non_privileged() | GatewayIntents::GUILD_MEMBERS;
+    if keyword_watch_enabled {
+        intents |= GatewayIntents::MESSAGE_CONTENT;
+    }
+
+    let api_addr = match env::var("API_ADDR") {
+        Ok(addr) => Some(
+            addr.parse::<SocketAddr>()
+                .wrap_err("API_ADDR must be an address like 0.0.0.0:8080")?,
+        ),
+        Err(_) => None,
+    };
+
     poise::Framework::build()
         .token(token)
         .user_data_setup(move |ctx, ready, framework| {
             Box::pin(async move {
+                let maintenance = Maintenance::default();
+
+                tokio::spawn(economy::economy_task(
+                    mongo.clone(),
+                    ctx.http.clone(),
+                    maintenance.clone(),
+                ));
+                tokio::spawn(service::service_task(
+                    mongo.clone(),
+                    ctx.http.clone(),
+                    maintenance.clone(),
+                ));
+                tokio::spawn(lawsuit::fast_track_task(
+                    mongo.clone(),
+                    ctx.http.clone(),
+                    maintenance.clone(),
+                ));
+                tokio::spawn(grand_jury::grand_jury_task(
+                    mongo.clone(),
+                    ctx.http.clone(),
+                    maintenance.clone(),
+                ));
+                tokio::spawn(docket::docket_task(
+                    mongo.clone(),
+                    ctx.http.clone(),
+                    maintenance.clone(),
+                ));
+                tokio::spawn(scheduler::scheduler_task(
+                    mongo.clone(),
+                    ctx.http.clone(),
+                    maintenance.clone(),
+                ));
+                tokio::spawn(retention::retention_task(
+                    mongo.clone(),
+                    ctx.http.clone(),
+                    maintenance.clone(),
+                ));
+                tokio::spawn(presence::presence_task(ctx.clone(), mongo.clone()));
+
+                if let Some(addr) = api_addr {
+                    let (mongo, http) = (mongo.clone(), ctx.http.clone());
+                    let maintenance = maintenance.clone();
+                    let oauth = dashboard::OAuthConfig::from_env();
+                    tokio::spawn(async move {
+                        if let Err(err) = api::serve(addr, mongo, http, maintenance, oauth).await {
+                            error!(?err, "Api server stopped");
+                        }
+                    });
+                }
+
                 let data = Handler {
                     dev_guild_id,
                     set_global_commands,
                     mongo,
+                    started_at: Instant::now(),
+                    reply_settings: Default::default(),
+                    verbose_guilds: Default::default(),
+                    keyword_watches: Default::default(),
+                    keyword_watch_enabled,
+                    maintenance,
                 };
 
                 let commands = &framework.options().commands;
@@ -97,20 +233,23 @@ async fn main() -> Result<()> {
                     }
                 }
 
-                ctx.set_activity(Activity::playing("für Recht und Ordnung sorgen"))
-                    .await;
-
                 info!(name = %ready.user.name, "Bot is connected!");
 
                 Ok(data)
             })
         })
         .options(poise::FrameworkOptions {
-            commands: vec![
-                handler::lawsuit::lawsuit(),
-                handler::prison::prison(),
-                hello(),
-            ],
+            commands: logging::instrument_commands({
+                let mut commands = handler::commands();
+                commands.push(hello());
+                commands
+            }),
+            reply_callback: Some(handler::apply_reply_settings),
+            command_check: Some(|ctx| {
+                Box::pin(async move {
+                    Ok(handler::maintenance_check(ctx) && handler::enabled_check(ctx))
+                })
+            }),
             on_error: |err| Box::pin(async { handler::error_handler(err).await }),
             listener: |ctx, event, ctx2, data| {
                 Box::pin(async move { handler::listener(ctx, event, ctx2, data).await })
@@ -123,12 +262,30 @@ async fn main() -> Result<()> {
                         .await
                         .unwrap_or_else(|| "<unknown>".to_owned());
                     let author = ctx.author().tag();
+                    let user_id = ctx.author().id;
+                    let guild_id = ctx.guild_id();
+                    let invocation = Invocation::start();
+                    let correlation_id = invocation.correlation_id;
+                    ctx.set_invocation_data(invocation).await;
+
+                    events::start_command(ctx).await;
+
+                    if let Err(err) = handler::load_reply_settings(ctx).await {
+                        error!(?err, "Failed to load reply settings");
+                    }
+                    handler::trace_verbose(ctx, "start");
 
                     match ctx {
                         Context::Application(ctx) => {
-                            let command_name = &ctx.interaction.data().name;
-
-                            info!(?author, ?channel_name, ?command_name, "Command called");
+                            info!(
+                                %correlation_id,
+                                ?guild_id,
+                                %user_id,
+                                command = %ctx.command.qualified_name,
+                                ?author,
+                                ?channel_name,
+                                "Command called"
+                            );
                         }
                         Context::Prefix(_) => {
                             tracing::warn!("Prefix command called!");
@@ -137,9 +294,18 @@ async fn main() -> Result<()> {
                     }
                 })
             },
+            post_command: |ctx| {
+                Box::pin(async move {
+                    handler::trace_verbose(ctx, "end");
+                    let correlation_id = logging::correlation_id(ctx).await;
+                    info!(%correlation_id, "Command finished");
+                    usage::record(ctx, true).await;
+                    events::finish_command(ctx).await;
+                })
+            },
             ..Default::default()
         })
-        .intents(GatewayIntents::non_privileged() | GatewayIntents::GUILD_MEMBERS)
+        .intents(intents)
         .run()
         .await
         .wrap_err("failed to create discord client")?;
@@ -152,25 +318,3 @@ async fn hello(ctx: Context<'_>) -> Result<()> {
     ctx.say("hoi!").await?;
     Ok(())
 }
-
-fn setup_tracing(pretty: bool) {
-    let registry = Registry::default()
-        .with(EnvFilter::from_default_env())
-        .with(tracing_error::ErrorLayer::default());
-
-    if pretty {
-        let tree_layer = tracing_tree::HierarchicalLayer::new(2)
-            .with_targets(true)
-            .with_bracketed_fields(true);
-
-        registry.with(tree_layer).init();
-    } else {
-        let fmt_layer = tracing_subscriber::fmt::layer()
-            .with_level(true)
-            .with_timer(tracing_subscriber::fmt::time::time())
-            .with_ansi(true)
-            .with_thread_names(true);
-
-        registry.with(fmt_layer).init();
-    };
-}
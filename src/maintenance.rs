@@ -0,0 +1,30 @@
+use std::sync::{Arc, RwLock};
+
+/// The message shown when no message was given with `/admin maintenance`.
+const DEFAULT_MESSAGE: &str = "de Bot wird grad gwartet, probiers spöter nomal";
+
+/// Whether the bot is in maintenance mode. While it is, only owners can use commands, buttons and
+/// gateway events are ignored, the API refuses writes and the background tasks don't start new
+/// runs, so the database can be migrated safely.
+#[derive(Debug, Clone, Default)]
+pub struct Maintenance(Arc<RwLock<Option<String>>>);
+
+impl Maintenance {
+    pub fn enable(&self, message: Option<String>) {
+        *self.0.write().expect("maintenance lock poisoned") =
+            Some(message.unwrap_or_else(|| DEFAULT_MESSAGE.to_string()));
+    }
+
+    pub fn disable(&self) {
+        *self.0.write().expect("maintenance lock poisoned") = None;
+    }
+
+    /// The notice shown to users, if maintenance mode is on.
+    pub fn message(&self) -> Option<String> {
+        self.0.read().expect("maintenance lock poisoned").clone()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.message().is_some()
+    }
+}
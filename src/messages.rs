@@ -0,0 +1,169 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// A user facing message that guilds can replace with their own template using
+/// `/config message set`. These are the replies that many commands share, replies that only one
+/// command gives keep their text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKey {
+    #[name = "Einstellung gespeichert"]
+    Saved,
+    #[name = "Keine Berechtigung"]
+    NoPermission,
+    #[name = "Kein Prozess im Kanal"]
+    NoActiveLawsuit,
+    #[name = "Verhaftet"]
+    Arrested,
+    #[name = "Freigelassen"]
+    Released,
+    #[name = "Nachricht an Verhaftete"]
+    ArrestNotice,
+    #[name = "Prozess nicht gefunden"]
+    LawsuitNotFound,
+    #[name = "Kein laufender Prozess"]
+    NoOngoingLawsuit,
+    #[name = "Ungültige Zeit"]
+    InvalidTime,
+    #[name = "Ungültige Frist"]
+    InvalidDeadline,
+    #[name = "Keine Richterrolle"]
+    NoJudgeRole,
+}
+
+impl MessageKey {
+    pub const ALL: [MessageKey; 11] = [
+        Self::Saved,
+        Self::NoPermission,
+        Self::NoActiveLawsuit,
+        Self::Arrested,
+        Self::Released,
+        Self::ArrestNotice,
+        Self::LawsuitNotFound,
+        Self::NoOngoingLawsuit,
+        Self::InvalidTime,
+        Self::InvalidDeadline,
+        Self::NoJudgeRole,
+    ];
+
+    pub fn default_template(self) -> &'static str {
+        match self {
+            Self::Saved => "isch gsetzt",
+            Self::NoPermission => "du häsch kei recht für da!",
+            Self::NoActiveLawsuit => "i dem channel lauft kein aktive prozess!",
+            Self::Arrested => "isch igsperrt",
+            Self::Released => "d'freiheit wartet",
+            Self::ArrestNotice => {
+                "du bisch igsperrt worde\nGrund: {reason}\nDauer: {duration}\nFall: {case}\n\
+                 mit `/prison petition` chasch di a d'Ufseher wende, zum Bispil für e früeneri Freilassig"
+            }
+            Self::LawsuitNotFound => "es git kein Prozess #{case}",
+            Self::NoOngoingLawsuit => "es git kein laufende Prozess #{case}",
+            Self::InvalidTime => "das isch kei gültigi ziit, probier öppis wie {example}",
+            Self::InvalidDeadline => "das isch kei gültigi frist, probier öppis wie {example}",
+            Self::NoJudgeRole => "du mosch zerst e richterrolle setze mit /court set_judge_role",
+        }
+    }
+
+    /// The placeholders that are replaced in the template, written as `{name}`.
+    pub fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            Self::Arrested | Self::Released => &["user"],
+            Self::ArrestNotice => &["user", "reason", "duration", "case"],
+            Self::LawsuitNotFound | Self::NoOngoingLawsuit => &["case"],
+            Self::InvalidTime | Self::InvalidDeadline => &["example"],
+            Self::Saved | Self::NoPermission | Self::NoActiveLawsuit | Self::NoJudgeRole => &[],
+        }
+    }
+}
+
+impl Display for MessageKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Saved => "Einstellung gespeichert",
+            Self::NoPermission => "Keine Berechtigung",
+            Self::NoActiveLawsuit => "Kein Prozess im Kanal",
+            Self::Arrested => "Verhaftet",
+            Self::Released => "Freigelassen",
+            Self::ArrestNotice => "Nachricht an Verhaftete",
+            Self::LawsuitNotFound => "Prozess nicht gefunden",
+            Self::NoOngoingLawsuit => "Kein laufender Prozess",
+            Self::InvalidTime => "Ungültige Zeit",
+            Self::InvalidDeadline => "Ungültige Frist",
+            Self::NoJudgeRole => "Keine Richterrolle",
+        })
+    }
+}
+
+/// A template a guild uses instead of the default message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageTemplate {
+    pub key: MessageKey,
+    pub template: String,
+}
+
+/// Replaces the `{name}` placeholders in the template with their values in a single pass, so that
+/// placeholders in the values stay as they are. Unknown placeholders are kept.
+pub fn render(template: &str, args: &[(&str, String)]) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                message.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, MessageKey};
+
+    #[test]
+    fn placeholders_are_replaced_once() {
+        let args = [
+            ("user", "{reason}".to_string()),
+            ("reason", "Spam".to_string()),
+        ];
+        assert_eq!(
+            render("{user} wegen {reason}", &args),
+            "{reason} wegen Spam"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        let args = [("case", "3".to_string())];
+        assert_eq!(render("#{case} {other} {", &args), "#3 {other} {");
+        assert_eq!(render("{{case}}", &args), "{3}");
+    }
+
+    #[test]
+    fn defaults_only_use_their_placeholders() {
+        for key in MessageKey::ALL {
+            let args = key
+                .placeholders()
+                .iter()
+                .map(|name| (*name, String::new()))
+                .collect::<Vec<_>>();
+            let message = render(key.default_template(), &args);
+            assert!(!message.contains('{'), "{key} has an unknown placeholder");
+        }
+    }
+}
@@ -2,20 +2,66 @@ use std::{
     fmt::{Display, Formatter},
     num::ParseIntError,
     str::FromStr,
+    time::{Duration, Instant},
 };
 
-use color_eyre::Result;
+use chrono_tz::Tz;
+use color_eyre::{
+    eyre::{eyre, ContextCompat},
+    Result,
+};
+use futures::TryStreamExt;
 use mongodb::{
     bson,
-    bson::{doc, Bson, Uuid},
-    options::{ClientOptions, Credential, IndexOptions, UpdateOptions},
+    bson::{doc, Bson, Document, Uuid},
+    error::{ErrorKind, WriteError, WriteFailure},
+    options::{
+        ClientOptions, Credential, FindOneAndUpdateOptions, FindOneOptions, FindOptions,
+        IndexOptions, ReplaceOptions, ReturnDocument, UpdateOptions,
+    },
     Client, Collection, Database, IndexModel,
 };
-use poise::serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use poise::serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
-use crate::{lawsuit::Lawsuit, WrapErr};
+use crate::{
+    activity::{Activity, WEEK},
+    arrest_appeal::{ArrestAppeal, ArrestAppealStatus},
+    branding::Branding,
+    config,
+    court_session::CourtSession,
+    docket::Docket,
+    duration::parse_timezone,
+    economy::{Account, Debt, DebtorRole, Salary, Tax, Transaction, TransactionKind},
+    events::{Change, Event},
+    global_list::GlobalListEntry,
+    grand_jury::{GrandJury, Vote},
+    guild_access::{AccessRule, GuildAccess},
+    keyword_watch::KeywordWatch,
+    lawsuit::{CaseType, LeaveAction},
+    lawsuit::{Hearing, HistoryEntry, Lawsuit, LawsuitStatus, Statement},
+    leaderboard::{PrisonRanking, PrisonRecord},
+    messages,
+    messages::{MessageKey, MessageTemplate},
+    moderation::{ModerationAction, ModerationKind},
+    opinion_poll::OpinionVote,
+    petition::{Petition, PetitionStatus},
+    prison::PrisonVoice,
+    probation::Probation,
+    retention::Retention,
+    room_permissions::RoomPermissions,
+    sandbox,
+    scheduler::Job,
+    service::ServiceObligation,
+    usage::{CommandStats, CommandUsage, GuildActivity, USAGE_RETENTION},
+    verdict_template::VerdictTemplate,
+    visitation::{Visit, VisitStatus, VisitationConfig},
+    warrant::Warrant,
+    webhook::Webhook,
+    work_release::{WorkTask, WorkTaskStatus},
+    WrapErr,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -84,7 +130,7 @@ macro_rules! from_snowflake {
     };
 }
 
-from_snowflake!(GuildId, RoleId, ChannelId, UserId);
+from_snowflake!(GuildId, RoleId, ChannelId, UserId, MessageId);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct State {
@@ -93,6 +139,249 @@ pub struct State {
     pub court_category: Option<SnowflakeId>,
     pub court_rooms: Vec<CourtRoom>,
     pub prison_role: Option<SnowflakeId>,
+    #[serde(default)]
+    pub filing_fee: Option<i64>,
+    /// Whether people with outstanding debts are prevented from filing lawsuits.
+    #[serde(default)]
+    pub debt_blocks_filings: bool,
+    /// How long a verdict can be appealed before it's executed. Verdicts are executed right away
+    /// if none is set.
+    #[serde(default)]
+    pub appeal_window_secs: Option<i64>,
+    /// The percentage of income that is withheld to pay off debts.
+    #[serde(default)]
+    pub garnishment_percent: Option<u8>,
+    /// What the treasury pays per hour of wrongful imprisonment.
+    #[serde(default)]
+    pub compensation_rate: Option<i64>,
+    #[serde(default)]
+    pub salaries: Vec<Salary>,
+    #[serde(default)]
+    pub tax: Option<Tax>,
+    /// The number of the last created lawsuit.
+    #[serde(default)]
+    pub case_counter: u64,
+    #[serde(default)]
+    pub judge_role: Option<SnowflakeId>,
+    /// Settings that override the defaults for specific case types.
+    #[serde(default)]
+    pub case_types: Vec<CaseTypeConfig>,
+    /// If set, criminal lawsuits first need to be indicted by the grand jury.
+    #[serde(default)]
+    pub grand_jury: Option<GrandJury>,
+    #[serde(default)]
+    pub docket: Option<Docket>,
+    /// Where verdicts and new lawsuits are published.
+    #[serde(default)]
+    pub announcement_channel: Option<SnowflakeId>,
+    /// The role members can give themselves to get pinged about new lawsuits.
+    #[serde(default)]
+    pub case_ping_role: Option<SnowflakeId>,
+    /// Members with this role can run the prison without being admins.
+    #[serde(default)]
+    pub warden_role: Option<SnowflakeId>,
+    /// Where the petitions of prisoners are posted for the wardens.
+    #[serde(default)]
+    pub warden_channel: Option<SnowflakeId>,
+    /// Where the judges decide the appeals of prisoners against their arrest.
+    #[serde(default)]
+    pub judge_channel: Option<SnowflakeId>,
+    /// Whether prisoners are kept out of the voice channels.
+    #[serde(default)]
+    pub prison_voice: Option<PrisonVoice>,
+    /// Whether arrested members get a DM with the reason, the duration and the case, using the
+    /// `ArrestNotice` message.
+    #[serde(default)]
+    pub arrest_notice: bool,
+    /// The name of the time zone dates are shown and entered in, like `Europe/Zurich`.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Templates that replace the default messages.
+    #[serde(default)]
+    pub messages: Vec<MessageTemplate>,
+    /// Whether replies are only visible to whoever used the command, unless overridden for the
+    /// command.
+    #[serde(default)]
+    pub ephemeral_replies: Option<bool>,
+    #[serde(default)]
+    pub reply_visibility: Vec<ReplyVisibility>,
+    #[serde(default)]
+    pub member_leave_action: LeaveAction,
+    #[serde(default)]
+    pub debtor_role: Option<DebtorRole>,
+    #[serde(default)]
+    pub keyword_watch: Option<KeywordWatch>,
+    /// Whether bans, kicks and timeouts done with Discord are recorded in the criminal record.
+    #[serde(default)]
+    pub record_moderation: bool,
+    /// The channel for notices about problems, like failed webhook deliveries.
+    #[serde(default)]
+    pub log_channel: Option<SnowflakeId>,
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// The SHA-256 hash of the token for the HTTP API.
+    #[serde(default)]
+    pub api_token_hash: Option<String>,
+    /// If set, the guild is in sandbox mode and simulated changes are reported to this channel.
+    /// Only stored in the real database.
+    #[serde(default)]
+    pub sandbox_channel: Option<SnowflakeId>,
+    #[serde(default)]
+    pub retention: Option<Retention>,
+    /// The color and footer of the embeds of the guild.
+    #[serde(default)]
+    pub branding: Option<Branding>,
+    /// Who can see and use new court rooms, the default if none is set.
+    #[serde(default)]
+    pub room_permissions: Option<RoomPermissions>,
+    /// The questions the plaintiff has to answer when filing a lawsuit.
+    #[serde(default)]
+    pub intake_form: Vec<String>,
+    #[serde(default)]
+    pub verdict_templates: Vec<VerdictTemplate>,
+    /// The recurring court days.
+    #[serde(default)]
+    pub court_sessions: Vec<CourtSession>,
+    /// Whether spectators can vote on announced verdicts.
+    #[serde(default)]
+    pub verdict_polls: bool,
+    /// Whether judges can decide small claims with `/lawsuit ordeal`.
+    #[serde(default)]
+    pub ordeals: bool,
+    /// Members who don't want to appear on leaderboards.
+    #[serde(default)]
+    pub leaderboard_opt_outs: Vec<SnowflakeId>,
+    /// The guilds this guild shares its prison records with, if they added this guild as well.
+    #[serde(default)]
+    pub federation: Vec<SnowflakeId>,
+    /// Whether members on the global list are flagged in the log channel when they join.
+    #[serde(default)]
+    pub global_list: bool,
+    /// Where prisoners can be visited, visits can't be requested without it.
+    #[serde(default)]
+    pub visitation: Option<VisitationConfig>,
+    /// The tasks prisoners can do to be released earlier.
+    #[serde(default)]
+    pub work_tasks: Vec<WorkTask>,
+}
+
+/// Whether the replies to a command are only visible to whoever used it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyVisibility {
+    /// The full name of the command, like `economy balance`.
+    pub command: String,
+    pub ephemeral: bool,
+}
+
+impl State {
+    /// The state of a guild that didn't configure anything yet.
+    pub fn new(guild_id: SnowflakeId) -> Self {
+        Self {
+            guild_id,
+            lawsuits: vec![],
+            court_category: None,
+            court_rooms: vec![],
+            prison_role: None,
+            filing_fee: None,
+            debt_blocks_filings: false,
+            appeal_window_secs: None,
+            garnishment_percent: None,
+            compensation_rate: None,
+            salaries: vec![],
+            tax: None,
+            case_counter: 0,
+            judge_role: None,
+            case_types: vec![],
+            grand_jury: None,
+            docket: None,
+            announcement_channel: None,
+            case_ping_role: None,
+            warden_role: None,
+            warden_channel: None,
+            judge_channel: None,
+            prison_voice: None,
+            arrest_notice: false,
+            timezone: None,
+            messages: vec![],
+            ephemeral_replies: None,
+            reply_visibility: vec![],
+            member_leave_action: LeaveAction::default(),
+            debtor_role: None,
+            keyword_watch: None,
+            record_moderation: false,
+            log_channel: None,
+            webhooks: vec![],
+            api_token_hash: None,
+            sandbox_channel: None,
+            retention: None,
+            branding: None,
+            room_permissions: None,
+            intake_form: vec![],
+            verdict_templates: vec![],
+            court_sessions: vec![],
+            verdict_polls: false,
+            ordeals: false,
+            leaderboard_opt_outs: vec![],
+            federation: vec![],
+            global_list: false,
+            visitation: None,
+            work_tasks: vec![],
+        }
+    }
+
+    fn case_type_config(&self, case_type: Option<CaseType>) -> Option<&CaseTypeConfig> {
+        let case_type = case_type?;
+        self.case_types
+            .iter()
+            .find(|config| config.case_type == case_type)
+    }
+
+    pub fn court_category_for(&self, case_type: Option<CaseType>) -> Option<SnowflakeId> {
+        self.case_type_config(case_type)
+            .and_then(|config| config.court_category)
+            .or(self.court_category)
+    }
+
+    pub fn judge_role_for(&self, case_type: Option<CaseType>) -> Option<SnowflakeId> {
+        self.case_type_config(case_type)
+            .and_then(|config| config.judge_role)
+            .or(self.judge_role)
+    }
+
+    /// The time zone of the guild, the default of the config if none is set.
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+            .as_deref()
+            .and_then(parse_timezone)
+            .unwrap_or_else(|| config::get().default_timezone())
+    }
+
+    /// The message for the key, using the template of the guild if it has one.
+    pub fn message(&self, key: MessageKey, args: &[(&str, String)]) -> String {
+        let template = self
+            .messages
+            .iter()
+            .find(|template| template.key == key)
+            .map_or(key.default_template(), |template| &template.template);
+        messages::render(template, args)
+    }
+
+    /// How old an incident may be for a lawsuit of this type to be filed.
+    pub fn limitation_for(&self, case_type: Option<CaseType>) -> Option<Duration> {
+        self.case_type_config(case_type)
+            .and_then(|config| config.limitation_secs)
+            .map(|secs| Duration::from_secs(secs as u64))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseTypeConfig {
+    pub case_type: CaseType,
+    pub court_category: Option<SnowflakeId>,
+    pub judge_role: Option<SnowflakeId>,
+    /// The statute of limitations.
+    #[serde(default)]
+    pub limitation_secs: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,17 +389,53 @@ pub struct CourtRoom {
     pub channel_id: SnowflakeId,
     pub ongoing_lawsuit: bool,
     pub role_id: SnowflakeId,
+    /// The category the room was created in. Rooms without one belong to the default category.
+    #[serde(default)]
+    pub category: Option<SnowflakeId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrisonEntry {
     pub guild_id: SnowflakeId,
     pub user_id: SnowflakeId,
+    #[serde(default)]
+    pub arrested_at: Option<bson::DateTime>,
+    /// When the prisoner left the guild. The prison role is given back if they rejoin.
+    #[serde(default)]
+    pub left_at: Option<bson::DateTime>,
+}
+
+/// A finished stay in prison, recorded when the prisoner is released.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrisonStay {
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    /// Missing if the arrest was before this was recorded.
+    pub arrested_at: Option<bson::DateTime>,
+    pub released_at: bson::DateTime,
+    /// Set when the arrest was overturned on appeal.
+    #[serde(default)]
+    pub wrongful: bool,
 }
 
+/// How often a read-modify-write update is retried if the document changed in between.
+const CONFLICT_RETRIES: usize = 5;
+
 #[derive(Clone)]
 pub struct Mongo {
+    client: Client,
+    /// The name of the shared database, also the prefix of the databases of the guilds in
+    /// per-guild mode.
+    db_name: String,
+    /// Whether every guild has its own database, `{db_name}-{guild_id}`.
+    per_guild: bool,
     db: Database,
+    /// Where the commands of guilds in sandbox mode read and write, a copy of their data at the
+    /// time sandbox mode was enabled.
+    sandbox_db: Database,
+    /// The data that isn't owned by a single guild, like the command usage and the guild access
+    /// lists.
+    shared_db: Database,
 }
 
 impl Mongo {
@@ -120,6 +445,7 @@ impl Mongo {
         db_name: &str,
         username: String,
         password: String,
+        per_guild: bool,
     ) -> Result<Self> {
         let mut client_options = ClientOptions::parse(uri)
             .await
@@ -134,35 +460,380 @@ impl Mongo {
         let client = Client::with_options(client_options).wrap_err("failed to create client")?;
 
         let db = client.database(db_name);
-        let mongo = Self { db };
+        let sandbox_db = client.database(&format!("{db_name}-sandbox"));
+        let mongo = Self {
+            client,
+            db_name: db_name.to_string(),
+            per_guild,
+            shared_db: db.clone(),
+            db,
+            sandbox_db,
+        };
 
-        info!("Creating indexes");
+        info!(per_guild, "Creating indexes");
 
         mongo
-            .state_coll()
+            .usage_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "timestamp": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("command_usage.timestamp".to_string())
+                            .expire_after(USAGE_RETENTION)
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create command usage index")?;
+
+        for namespace in mongo.namespaces().await? {
+            namespace.create_indexes().await?;
+        }
+
+        Ok(mongo)
+    }
+
+    /// Creates the indexes of the database of the guild, which doesn't exist before the guild has
+    /// any data. Does nothing unless every guild has its own database.
+    #[tracing::instrument(skip(self))]
+    pub async fn create_guild_indexes(&self, guild_id: GuildId) -> Result<()> {
+        if !self.per_guild {
+            return Ok(());
+        }
+        self.guild(guild_id).create_indexes().await
+    }
+
+    async fn create_indexes(&self) -> Result<()> {
+        self.state_coll()
             .create_index(
                 IndexModel::builder()
                     .keys(doc! { "guild_id": 1 })
-                    .options(IndexOptions::builder().name("state.guild_id".to_string()).build())
+                    .options(
+                        IndexOptions::builder()
+                            .name("state.guild_id".to_string())
+                            .build(),
+                    )
                     .build(),
                 None,
             )
             .await
             .wrap_err("create state index")?;
 
-        mongo
-            .prison_coll()
+        self.prison_coll()
             .create_index(
                 IndexModel::builder()
                     .keys(doc! { "guild_id": 1, "user_id": 1 })
-                    .options(IndexOptions::builder().name("prison.guild_id_user_id".to_string()).build())
+                    .options(
+                        IndexOptions::builder()
+                            .name("prison.guild_id_user_id".to_string())
+                            .build(),
+                    )
                     .build(),
                 None,
             )
             .await
             .wrap_err("create state index")?;
 
-        Ok(mongo)
+        self.account_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "user_id": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("accounts.guild_id_user_id".to_string())
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create accounts index")?;
+
+        self.ledger_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "user_id": 1, "timestamp": -1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("ledger.guild_id_user_id_timestamp".to_string())
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create ledger index")?;
+
+        self.debt_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "debtor": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("debts.guild_id_debtor".to_string())
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create debts index")?;
+
+        self.service_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "user_id": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("community_service.guild_id_user_id".to_string())
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create community service index")?;
+
+        self.moderation_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "user_id": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("moderation.guild_id_user_id".to_string())
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create moderation index")?;
+
+        self.prison_history_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "user_id": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("prison_history.guild_id_user_id".to_string())
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create prison history index")?;
+
+        self.warrant_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "user_id": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("warrants.guild_id_user_id".to_string())
+                            .unique(true)
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create warrant index")?;
+
+        self.job_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "due": 1 })
+                    .options(IndexOptions::builder().name("jobs.due".to_string()).build())
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create jobs index")?;
+
+        self.event_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "guild_id": 1, "timestamp": -1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("events.guild_id_timestamp".to_string())
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create events index")?;
+
+        // only one undo per event, the others are null
+        self.event_coll()
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "undoes": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name("events.undoes".to_string())
+                            .unique(true)
+                            .partial_filter_expression(doc! { "undoes": { "$type": "binData" } })
+                            .build(),
+                    )
+                    .build(),
+                None,
+            )
+            .await
+            .wrap_err("create events undo index")?;
+
+        Ok(())
+    }
+
+    /// The database with the data of the guild, the shared database unless every guild has its
+    /// own. Ignores sandbox mode.
+    pub fn guild(&self, guild_id: GuildId) -> Mongo {
+        if !self.per_guild {
+            return self.clone();
+        }
+        let name = format!("{}-{guild_id}", self.db_name);
+        Mongo {
+            db: self.client.database(&name),
+            sandbox_db: self.client.database(&format!("{name}-sandbox")),
+            ..self.clone()
+        }
+    }
+
+    /// The database for the guild, the shadow database if it is in sandbox mode.
+    pub fn for_guild(&self, guild_id: GuildId) -> Mongo {
+        let mongo = self.guild(guild_id);
+        match sandbox::report_channel(guild_id) {
+            Some(_) => mongo.sandbox(),
+            None => mongo,
+        }
+    }
+
+    /// The shadow database of all guilds in sandbox mode.
+    pub fn sandbox(&self) -> Mongo {
+        Mongo {
+            db: self.sandbox_db.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// The databases with guild data. In per-guild mode, that's the database of every guild that
+    /// has data, otherwise just the shared one.
+    #[tracing::instrument(skip(self))]
+    pub async fn namespaces(&self) -> Result<Vec<Mongo>> {
+        if !self.per_guild {
+            return Ok(vec![self.clone()]);
+        }
+        let prefix = format!("{}-", self.db_name);
+        let names = self
+            .client
+            .list_database_names(None, None)
+            .await
+            .wrap_err("list databases")?;
+
+        // the shadow databases end in `-sandbox` and are skipped
+        Ok(names
+            .iter()
+            .filter_map(|name| name.strip_prefix(&prefix)?.parse::<u64>().ok())
+            .map(|guild_id| self.guild(GuildId(guild_id)))
+            .collect())
+    }
+
+    /// The guilds in sandbox mode with their report channel.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_sandboxes(&self) -> Result<Vec<(SnowflakeId, SnowflakeId)>> {
+        let cursor = self
+            .state_coll()
+            .find(doc! { "sandbox_channel": { "$ne": null } }, None)
+            .await
+            .wrap_err("find sandboxes")?;
+        let states: Vec<State> = cursor.try_collect().await.wrap_err("collect sandboxes")?;
+
+        Ok(states
+            .into_iter()
+            .filter_map(|state| Some((state.guild_id, state.sandbox_channel?)))
+            .collect())
+    }
+
+    /// Replaces the data of the guild in the shadow database with a copy of the real data and
+    /// stores the report channel. Must be called on the real database.
+    #[tracing::instrument(skip(self))]
+    pub async fn enable_sandbox(
+        &self,
+        guild_id: SnowflakeId,
+        report_channel: SnowflakeId,
+    ) -> Result<()> {
+        let state = self.find_or_insert_state(guild_id).await?;
+        let prisoners = self.find_prisoners(guild_id).await?;
+
+        self.clear_sandbox(guild_id).await?;
+        self.sandbox_db
+            .collection::<State>("state")
+            .insert_one(&state, None)
+            .await
+            .wrap_err("copy state to sandbox")?;
+        if !prisoners.is_empty() {
+            self.sandbox_db
+                .collection::<PrisonEntry>("prison")
+                .insert_many(&prisoners, None)
+                .await
+                .wrap_err("copy prisoners to sandbox")?;
+        }
+
+        self.state_coll()
+            .update_one(
+                doc! { "guild_id": guild_id },
+                doc! { "$set": { "sandbox_channel": report_channel } },
+                None,
+            )
+            .await
+            .wrap_err("set sandbox channel")?;
+        Ok(())
+    }
+
+    /// Deletes the data of the guild in the shadow database and leaves sandbox mode. Must be called
+    /// on the real database.
+    #[tracing::instrument(skip(self))]
+    pub async fn disable_sandbox(&self, guild_id: SnowflakeId) -> Result<()> {
+        self.clear_sandbox(guild_id).await?;
+        self.state_coll()
+            .update_one(
+                doc! { "guild_id": guild_id },
+                doc! { "$set": { "sandbox_channel": null } },
+                None,
+            )
+            .await
+            .wrap_err("unset sandbox channel")?;
+        Ok(())
+    }
+
+    async fn clear_sandbox(&self, guild_id: SnowflakeId) -> Result<()> {
+        let collections = self
+            .sandbox_db
+            .list_collection_names(None)
+            .await
+            .wrap_err("list sandbox collections")?;
+        for name in collections {
+            self.sandbox_db
+                .collection::<Document>(&name)
+                .delete_many(doc! { "guild_id": guild_id }, None)
+                .await
+                .wrap_err("clear sandbox collection")?;
+        }
+        Ok(())
+    }
+
+    /// Finds the state document as it is stored, including fields that the bot doesn't know about.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_raw_state(&self, guild_id: SnowflakeId) -> Result<Option<Document>> {
+        let coll = self.db.collection::<Document>("state");
+        coll.find_one(doc! {"guild_id": &guild_id  }, None)
+            .await
+            .wrap_err("find raw state")
     }
 
     #[tracing::instrument(skip(self))]
@@ -186,13 +857,7 @@ impl Mongo {
 
     #[tracing::instrument(skip(self))]
     pub async fn new_state(&self, guild_id: SnowflakeId) -> Result<State> {
-        let state = State {
-            guild_id,
-            lawsuits: vec![],
-            court_category: None,
-            court_rooms: vec![],
-            prison_role: None,
-        };
+        let state = State::new(guild_id);
 
         let coll = self.db.collection::<State>("state");
         coll.insert_one(&state, None)
@@ -219,6 +884,100 @@ impl Mongo {
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
+    pub async fn set_judge_role(
+        &self,
+        guild_id: SnowflakeId,
+        judge_role: SnowflakeId,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "judge_role": judge_role } },
+            None,
+        )
+        .await
+        .wrap_err("update judge role")?;
+        Ok(())
+    }
+
+    /// Updates the settings of a case type, creating them if they don't exist yet.
+    #[tracing::instrument(skip(self, update))]
+    pub async fn update_case_type_config(
+        &self,
+        guild_id: SnowflakeId,
+        case_type: CaseType,
+        update: impl Fn(&mut CaseTypeConfig),
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        for _ in 0..CONFLICT_RETRIES {
+            let state = self.find_or_insert_state(guild_id).await?;
+            // only written if nobody changed the list since it was read, documents from before
+            // case types existed don't have the field
+            let unchanged = if state.case_types.is_empty() {
+                doc! { "guild_id": &guild_id, "$or": [
+                    { "case_types": { "$exists": false } },
+                    { "case_types": { "$size": 0 } },
+                ] }
+            } else {
+                let old =
+                    bson::to_bson(&state.case_types).wrap_err("invalid bson for case types")?;
+                doc! { "guild_id": &guild_id, "case_types": old }
+            };
+            let mut case_types = state.case_types;
+
+            let index = match case_types.iter().position(|c| c.case_type == case_type) {
+                Some(index) => index,
+                None => {
+                    case_types.push(CaseTypeConfig {
+                        case_type,
+                        court_category: None,
+                        judge_role: None,
+                        limitation_secs: None,
+                    });
+                    case_types.len() - 1
+                }
+            };
+            update(&mut case_types[index]);
+
+            let result = coll
+                .update_one(
+                    unchanged,
+                    doc! { "$set": { "case_types": bson::to_bson(&case_types).wrap_err("invalid bson for case types")? } },
+                    None,
+                )
+                .await
+                .wrap_err("update case types")?;
+
+            if result.matched_count == 1 {
+                return Ok(());
+            }
+            info!(%guild_id, "Case types changed concurrently, retrying");
+        }
+
+        Err(eyre!("case types kept changing while updating them"))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn next_case_number(&self, guild_id: SnowflakeId) -> Result<u64> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        let state = coll
+            .find_one_and_update(
+                doc! { "guild_id": &guild_id  },
+                doc! { "$inc": { "case_counter": 1_i64 } },
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .wrap_err("increment case counter")?
+            .wrap_err("state not found")?;
+        Ok(state.case_counter)
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn set_prison_role(
         &self,
@@ -267,108 +1026,2916 @@ impl Mongo {
         Ok(())
     }
 
-    #[tracing::instrument(skip(self, value))]
-    pub async fn set_court_room(
-        &self,
-        guild_id: SnowflakeId,
-        channel_id: SnowflakeId,
-        value: impl Into<Bson>,
-    ) -> Result<()> {
-        let _ = self.find_or_insert_state(guild_id).await?;
+    /// Replaces the lawsuit with the same id, or adds it if it doesn't exist yet.
+    #[tracing::instrument(skip(self))]
+    pub async fn save_lawsuit(&self, guild_id: SnowflakeId, lawsuit: &Lawsuit) -> Result<()> {
         let coll = self.state_coll();
 
-        coll.update_one(
-            doc! { "guild_id": &guild_id, "court_rooms.channel_id": channel_id  },
-            doc! { "$set": value.into() },
-            None,
-        )
-        .await
-        .wrap_err("set courtroom")?;
+        let result = coll
+            .update_one(
+                doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit.id },
+                doc! { "$set": { "lawsuits.$": bson::to_bson(lawsuit).wrap_err("invalid bson for lawsuit")? } },
+                None,
+            )
+            .await
+            .wrap_err("replace lawsuit")?;
+
+        if result.matched_count == 0 {
+            self.add_lawsuit(guild_id, lawsuit).await?;
+        }
+
         Ok(())
     }
 
-    #[tracing::instrument(skip(self, value))]
-    pub async fn set_lawsuit(
+    /// Adds the entries to the lists of the lawsuit.
+    #[tracing::instrument(skip(self, statements, history))]
+    pub async fn append_to_lawsuit(
         &self,
         guild_id: SnowflakeId,
         lawsuit_id: Uuid,
-        value: impl Into<Bson>,
+        parties: &[SnowflakeId],
+        statements: &[Statement],
+        tags: &[String],
+        history: &[HistoryEntry],
     ) -> Result<()> {
-        let _ = self.find_or_insert_state(guild_id).await?;
         let coll = self.state_coll();
 
         coll.update_one(
-            doc! { "guild_id": &guild_id, "lawsuit.id": lawsuit_id  },
-            doc! { "$set": value.into() },
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id },
+            doc! { "$push": {
+                "lawsuits.$.additional_parties": { "$each": bson::to_bson(parties).wrap_err("invalid bson for parties")? },
+                "lawsuits.$.statements": { "$each": bson::to_bson(statements).wrap_err("invalid bson for statements")? },
+                "lawsuits.$.tags": { "$each": tags },
+                "lawsuits.$.history": { "$each": bson::to_bson(history).wrap_err("invalid bson for history")? },
+            } },
             None,
         )
         .await
-        .wrap_err("set courtroom")?;
+        .wrap_err("append to lawsuit")?;
+
         Ok(())
     }
 
+    /// Marks the room as used if it is still free. Returns whether it was free.
     #[tracing::instrument(skip(self))]
-    pub async fn delete_guild(&self, guild_id: SnowflakeId) -> Result<()> {
+    pub async fn claim_court_room(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+    ) -> Result<bool> {
         let coll = self.state_coll();
 
-        coll.delete_one(doc! { "guild_id": &guild_id }, None)
+        let result = coll
+            .update_one(
+                doc! {
+                    "guild_id": &guild_id,
+                    "court_rooms": { "$elemMatch": { "channel_id": channel_id, "ongoing_lawsuit": false } },
+                },
+                doc! { "$set": { "court_rooms.$.ongoing_lawsuit": true } },
+                None,
+            )
             .await
-            .wrap_err("delete guild")?;
-        Ok(())
+            .wrap_err("claim court room")?;
+
+        Ok(result.modified_count == 1)
     }
 
     #[tracing::instrument(skip(self))]
-    pub async fn add_to_prison(&self, guild_id: SnowflakeId, user_id: SnowflakeId) -> Result<()> {
-        let coll = self.prison_coll();
-
+    pub async fn delete_court_room(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+    ) -> Result<()> {
+        let coll = self.state_coll();
         coll.update_one(
-            doc! { "guild_id": guild_id, "user_id": user_id },
+            doc! { "guild_id": &guild_id },
+            doc! { "$pull": { "court_rooms": { "channel_id": channel_id } } },
+            None,
+        )
+        .await
+        .wrap_err("delete court room")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, value))]
+    pub async fn set_court_room(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+        value: impl Into<Bson>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "court_rooms.channel_id": channel_id  },
+            doc! { "$set": value.into() },
+            None,
+        )
+        .await
+        .wrap_err("set courtroom")?;
+        Ok(())
+    }
+
+    /// Marks whether a lawsuit is going on in the room.
+    pub async fn set_court_room_ongoing(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+        ongoing: bool,
+    ) -> Result<()> {
+        self.set_court_room(
+            guild_id,
+            channel_id,
+            doc! { "court_rooms.$.ongoing_lawsuit": ongoing },
+        )
+        .await
+    }
+
+    /// Saves the verdict and the sentences of the lawsuit, and when it was closed.
+    #[tracing::instrument(skip(self, lawsuit), fields(lawsuit_id = %lawsuit.id))]
+    pub async fn set_verdict(&self, guild_id: SnowflakeId, lawsuit: &Lawsuit) -> Result<()> {
+        self.set_lawsuit(
+            guild_id,
+            lawsuit.id,
             doc! {
-                "$setOnInsert": {
-                    "guild_id": guild_id, "user_id": user_id,
-                }
+                "lawsuits.$.verdict": &lawsuit.verdict,
+                "lawsuits.$.winner": bson::to_bson(&lawsuit.winner).wrap_err("invalid bson for winner")?,
+                "lawsuits.$.damages": lawsuit.damages,
+                "lawsuits.$.community_service": lawsuit.community_service,
+                "lawsuits.$.community_service_secs": lawsuit.community_service_secs.map(|secs| secs as i64),
+                "lawsuits.$.prison_secs": lawsuit.prison_secs.map(|secs| secs as i64),
+                "lawsuits.$.probation_secs": lawsuit.probation_secs.map(|secs| secs as i64),
+                "lawsuits.$.time_served_secs": lawsuit.time_served_secs.map(|secs| secs as i64),
+                "lawsuits.$.closed_at": lawsuit.closed_at,
             },
-            UpdateOptions::builder().upsert(true).build(),
         )
         .await
-        .wrap_err("add to prison collection")?;
+    }
+
+    #[tracing::instrument(skip(self, value))]
+    pub async fn set_lawsuit(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        value: impl Into<Bson>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$set": value.into() },
+            None,
+        )
+        .await
+        .wrap_err("set lawsuit")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_lawsuit_statement(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        statement: &Statement,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$push": { "lawsuits.$.statements": bson::to_bson(statement).wrap_err("invalid bson for statement")? } },
+            None,
+        )
+        .await
+        .wrap_err("add lawsuit statement")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_lawsuit_hearing(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        hearing: &Hearing,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$push": { "lawsuits.$.hearings": bson::to_bson(hearing).wrap_err("invalid bson for hearing")? } },
+            None,
+        )
+        .await
+        .wrap_err("add lawsuit hearing")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_lawsuit_follower(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        user_id: SnowflakeId,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$addToSet": { "lawsuits.$.followers": user_id } },
+            None,
+        )
+        .await
+        .wrap_err("add lawsuit follower")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_lawsuit_follower(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        user_id: SnowflakeId,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$pull": { "lawsuits.$.followers": user_id } },
+            None,
+        )
+        .await
+        .wrap_err("remove lawsuit follower")?;
+        Ok(())
+    }
+
+    /// Replaces an earlier vote of the same juror.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_grand_jury_vote(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        vote: &Vote,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$pull": { "lawsuits.$.grand_jury.votes": { "user_id": vote.user_id } } },
+            None,
+        )
+        .await
+        .wrap_err("remove old grand jury vote")?;
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$push": { "lawsuits.$.grand_jury.votes": bson::to_bson(vote).wrap_err("invalid bson for vote")? } },
+            None,
+        )
+        .await
+        .wrap_err("add grand jury vote")?;
+        Ok(())
+    }
+
+    /// Marks the lawsuit as indicted by the grand jury if it isn't yet. Returns whether it wasn't,
+    /// so that only one of several votes reaching the threshold at the same time indicts.
+    #[tracing::instrument(skip(self))]
+    pub async fn claim_indictment(&self, guild_id: SnowflakeId, lawsuit_id: Uuid) -> Result<bool> {
+        let coll = self.state_coll();
+
+        let result = coll
+            .update_one(
+                doc! {
+                    "guild_id": &guild_id,
+                    "lawsuits": { "$elemMatch": { "id": lawsuit_id, "grand_jury.indicted": false } },
+                },
+                doc! { "$set": { "lawsuits.$.grand_jury.indicted": true } },
+                None,
+            )
+            .await
+            .wrap_err("claim indictment")?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    /// Changes the status of the lawsuit if it still has the expected one. Returns whether it did,
+    /// so that only one of several concurrent transitions goes through.
+    #[tracing::instrument(skip(self))]
+    pub async fn claim_lawsuit_status(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        from: LawsuitStatus,
+        to: LawsuitStatus,
+    ) -> Result<bool> {
+        let coll = self.state_coll();
+        let from = bson::to_bson(&from).wrap_err("invalid bson for status")?;
+        let to = bson::to_bson(&to).wrap_err("invalid bson for status")?;
+
+        let result = coll
+            .update_one(
+                doc! {
+                    "guild_id": &guild_id,
+                    "lawsuits": { "$elemMatch": { "id": lawsuit_id, "status": from } },
+                },
+                doc! { "$set": { "lawsuits.$.status": to } },
+                None,
+            )
+            .await
+            .wrap_err("claim lawsuit status")?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_opinion_vote(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        vote: &OpinionVote,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$pull": { "lawsuits.$.opinion_poll.votes": { "user_id": vote.user_id } } },
+            None,
+        )
+        .await
+        .wrap_err("remove old opinion vote")?;
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$push": { "lawsuits.$.opinion_poll.votes": bson::to_bson(vote).wrap_err("invalid bson for vote")? } },
+            None,
+        )
+        .await
+        .wrap_err("add opinion vote")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_states_with_pending_indictments(&self) -> Result<Vec<State>> {
+        let coll = self.state_coll();
+
+        let cursor = coll
+            .find(doc! { "lawsuits.status": "pending_indictment" }, None)
+            .await
+            .wrap_err("find states with pending indictments")?;
+
+        cursor.try_collect().await.wrap_err("collect states")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_grand_jury(
+        &self,
+        guild_id: SnowflakeId,
+        grand_jury: Option<&GrandJury>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "grand_jury": bson::to_bson(&grand_jury).wrap_err("invalid bson for grand jury")? } },
+            None,
+        )
+        .await
+        .wrap_err("update grand jury")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_visitation(
+        &self,
+        guild_id: SnowflakeId,
+        visitation: Option<&VisitationConfig>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "visitation": bson::to_bson(&visitation).wrap_err("invalid bson for visitation")? } },
+            None,
+        )
+        .await
+        .wrap_err("update visitation")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_branding(
+        &self,
+        guild_id: SnowflakeId,
+        branding: Option<&Branding>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "branding": bson::to_bson(&branding).wrap_err("invalid bson for branding")? } },
+            None,
+        )
+        .await
+        .wrap_err("update branding")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_room_permissions(
+        &self,
+        guild_id: SnowflakeId,
+        permissions: Option<&RoomPermissions>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "room_permissions": bson::to_bson(&permissions).wrap_err("invalid bson for room permissions")? } },
+            None,
+        )
+        .await
+        .wrap_err("update room permissions")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_intake_form(&self, guild_id: SnowflakeId, questions: &[String]) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "intake_form": questions } },
+            None,
+        )
+        .await
+        .wrap_err("update intake form")?;
+        Ok(())
+    }
+
+    /// Replaces the verdict template with the name, or removes it if there is no new one.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_verdict_template(
+        &self,
+        guild_id: SnowflakeId,
+        name: &str,
+        template: Option<&VerdictTemplate>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$pull": { "verdict_templates": { "name": name } } },
+            None,
+        )
+        .await
+        .wrap_err("remove old verdict template")?;
+
+        if let Some(template) = template {
+            coll.update_one(
+                doc! { "guild_id": &guild_id },
+                doc! { "$push": { "verdict_templates": bson::to_bson(template).wrap_err("invalid bson for verdict template")? } },
+                None,
+            )
+            .await
+            .wrap_err("add verdict template")?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_retention(
+        &self,
+        guild_id: SnowflakeId,
+        retention: Option<&Retention>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "retention": bson::to_bson(&retention).wrap_err("invalid bson for retention")? } },
+            None,
+        )
+        .await
+        .wrap_err("update retention")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_retention_dry_run(
+        &self,
+        guild_id: SnowflakeId,
+        dry_run_at: bson::DateTime,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "retention": { "$ne": null } },
+            doc! { "$set": { "retention.dry_run_at": dry_run_at } },
+            None,
+        )
+        .await
+        .wrap_err("update retention dry run")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_states_with_retention(&self) -> Result<Vec<State>> {
+        let coll = self.state_coll();
+
+        let cursor = coll
+            .find(doc! { "retention": { "$ne": null } }, None)
+            .await
+            .wrap_err("find states with retention")?;
+
+        cursor.try_collect().await.wrap_err("collect states")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_lawsuits(&self, guild_id: SnowflakeId, lawsuit_ids: &[Uuid]) -> Result<()> {
+        if lawsuit_ids.is_empty() {
+            return Ok(());
+        }
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$pull": { "lawsuits": { "id": { "$in": lawsuit_ids } } } },
+            None,
+        )
+        .await
+        .wrap_err("delete lawsuits")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_debtor_role(
+        &self,
+        guild_id: SnowflakeId,
+        debtor_role: Option<&DebtorRole>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "debtor_role": bson::to_bson(&debtor_role).wrap_err("invalid bson for debtor role")? } },
+            None,
+        )
+        .await
+        .wrap_err("update debtor role")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_keyword_watch(
+        &self,
+        guild_id: SnowflakeId,
+        keyword_watch: Option<&KeywordWatch>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "keyword_watch": bson::to_bson(&keyword_watch).wrap_err("invalid bson for keyword watch")? } },
+            None,
+        )
+        .await
+        .wrap_err("update keyword watch")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_docket(&self, guild_id: SnowflakeId, docket: Option<&Docket>) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "docket": bson::to_bson(&docket).wrap_err("invalid bson for docket")? } },
+            None,
+        )
+        .await
+        .wrap_err("update docket")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_announcement_channel(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: Option<SnowflakeId>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "announcement_channel": channel_id } },
+            None,
+        )
+        .await
+        .wrap_err("update announcement channel")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_warden_role(
+        &self,
+        guild_id: SnowflakeId,
+        role_id: Option<SnowflakeId>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "warden_role": role_id } },
+            None,
+        )
+        .await
+        .wrap_err("update warden role")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_prison_voice(
+        &self,
+        guild_id: SnowflakeId,
+        prison_voice: Option<&PrisonVoice>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "prison_voice": bson::to_bson(&prison_voice).wrap_err("invalid bson for prison voice")? } },
+            None,
+        )
+        .await
+        .wrap_err("update prison voice")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_judge_channel(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: Option<SnowflakeId>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "judge_channel": channel_id } },
+            None,
+        )
+        .await
+        .wrap_err("update judge channel")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_warden_channel(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: Option<SnowflakeId>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "warden_channel": channel_id } },
+            None,
+        )
+        .await
+        .wrap_err("update warden channel")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_case_ping_role(
+        &self,
+        guild_id: SnowflakeId,
+        role_id: Option<SnowflakeId>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "case_ping_role": role_id } },
+            None,
+        )
+        .await
+        .wrap_err("update case ping role")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_member_leave_action(
+        &self,
+        guild_id: SnowflakeId,
+        action: LeaveAction,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "member_leave_action": bson::to_bson(&action).wrap_err("invalid bson for leave action")? } },
+            None,
+        )
+        .await
+        .wrap_err("update member leave action")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, token_hash))]
+    pub async fn set_api_token_hash(
+        &self,
+        guild_id: SnowflakeId,
+        token_hash: Option<&str>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "api_token_hash": token_hash } },
+            None,
+        )
+        .await
+        .wrap_err("update api token")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_log_channel(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: Option<SnowflakeId>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "log_channel": channel_id } },
+            None,
+        )
+        .await
+        .wrap_err("update log channel")?;
+        Ok(())
+    }
+
+    /// Adds the webhook, replacing an existing one with the same url.
+    #[tracing::instrument(skip(self, webhook), fields(url = %webhook.url))]
+    pub async fn add_webhook(&self, guild_id: SnowflakeId, webhook: &Webhook) -> Result<()> {
+        self.remove_webhook(guild_id, &webhook.url).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$push": { "webhooks": bson::to_bson(webhook).wrap_err("invalid bson for webhook")? } },
+            None,
+        )
+        .await
+        .wrap_err("add webhook")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_webhook(&self, guild_id: SnowflakeId, url: &str) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$pull": { "webhooks": { "url": url } } },
+            None,
+        )
+        .await
+        .wrap_err("remove webhook")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_court_session(
+        &self,
+        guild_id: SnowflakeId,
+        session: &CourtSession,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$push": { "court_sessions": bson::to_bson(session).wrap_err("invalid bson for court session")? } },
+            None,
+        )
+        .await
+        .wrap_err("add court session")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_court_session(&self, guild_id: SnowflakeId, id: Uuid) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$pull": { "court_sessions": { "id": id } } },
+            None,
+        )
+        .await
+        .wrap_err("remove court session")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, task), fields(task_id = %task.id))]
+    pub async fn add_work_task(&self, guild_id: SnowflakeId, task: &WorkTask) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$push": { "work_tasks": bson::to_bson(task).wrap_err("invalid bson for work task")? } },
+            None,
+        )
+        .await
+        .wrap_err("add work task")?;
+        Ok(())
+    }
+
+    /// Gives the task to the prisoner if nobody has it yet.
+    #[tracing::instrument(skip(self))]
+    pub async fn claim_work_task(
+        &self,
+        guild_id: SnowflakeId,
+        id: Uuid,
+        user_id: SnowflakeId,
+    ) -> Result<bool> {
+        let coll = self.state_coll();
+
+        let result = coll
+            .update_one(
+                doc! {
+                    "guild_id": &guild_id,
+                    "work_tasks": { "$elemMatch": { "id": id, "status": "open" } },
+                },
+                doc! { "$set": { "work_tasks.$.status": "claimed", "work_tasks.$.claimed_by": user_id } },
+                None,
+            )
+            .await
+            .wrap_err("claim work task")?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    /// Changes the status of the task if it still has the expected one.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_work_task_status(
+        &self,
+        guild_id: SnowflakeId,
+        id: Uuid,
+        from: WorkTaskStatus,
+        to: WorkTaskStatus,
+    ) -> Result<bool> {
+        let from = bson::to_bson(&from).wrap_err("invalid bson for work task status")?;
+        let to = bson::to_bson(&to).wrap_err("invalid bson for work task status")?;
+        let coll = self.state_coll();
+
+        let result = coll
+            .update_one(
+                doc! {
+                    "guild_id": &guild_id,
+                    "work_tasks": { "$elemMatch": { "id": id, "status": from } },
+                },
+                doc! { "$set": { "work_tasks.$.status": to } },
+                None,
+            )
+            .await
+            .wrap_err("update work task status")?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    /// Removes the task if it has the status, returning it.
+    #[tracing::instrument(skip(self))]
+    pub async fn take_work_task(
+        &self,
+        guild_id: SnowflakeId,
+        id: Uuid,
+        status: WorkTaskStatus,
+    ) -> Result<Option<WorkTask>> {
+        let status = bson::to_bson(&status).wrap_err("invalid bson for work task status")?;
+        let state = self
+            .state_coll()
+            .find_one_and_update(
+                doc! {
+                    "guild_id": &guild_id,
+                    "work_tasks": { "$elemMatch": { "id": id, "status": status } },
+                },
+                doc! { "$pull": { "work_tasks": { "id": id } } },
+                None,
+            )
+            .await
+            .wrap_err("take work task")?;
+
+        Ok(state.and_then(|state| state.work_tasks.into_iter().find(|task| task.id == id)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_timezone(&self, guild_id: SnowflakeId, timezone: &str) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "timezone": timezone } },
+            None,
+        )
+        .await
+        .wrap_err("update timezone")?;
+        Ok(())
+    }
+
+    /// Sets the template for the message, or goes back to the default if there is none.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_message_template(
+        &self,
+        guild_id: SnowflakeId,
+        key: MessageKey,
+        template: Option<&MessageTemplate>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        let key = bson::to_bson(&key).wrap_err("invalid bson for message key")?;
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$pull": { "messages": { "key": key } } },
+            None,
+        )
+        .await
+        .wrap_err("remove old message template")?;
+
+        if let Some(template) = template {
+            coll.update_one(
+                doc! { "guild_id": &guild_id },
+                doc! { "$push": { "messages": bson::to_bson(template).wrap_err("invalid bson for message template")? } },
+                None,
+            )
+            .await
+            .wrap_err("add message template")?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_ephemeral_replies(
+        &self,
+        guild_id: SnowflakeId,
+        ephemeral: Option<bool>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "ephemeral_replies": ephemeral } },
+            None,
+        )
+        .await
+        .wrap_err("update ephemeral replies")?;
+        Ok(())
+    }
+
+    /// Sets whether the replies of the command are ephemeral, or removes the setting.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_reply_visibility(
+        &self,
+        guild_id: SnowflakeId,
+        command: &str,
+        ephemeral: Option<bool>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id },
+            doc! { "$pull": { "reply_visibility": { "command": command } } },
+            None,
+        )
+        .await
+        .wrap_err("remove old reply visibility")?;
+
+        if let Some(ephemeral) = ephemeral {
+            let visibility = ReplyVisibility {
+                command: command.to_string(),
+                ephemeral,
+            };
+            coll.update_one(
+                doc! { "guild_id": &guild_id },
+                doc! { "$push": { "reply_visibility": bson::to_bson(&visibility).wrap_err("invalid bson for reply visibility")? } },
+                None,
+            )
+            .await
+            .wrap_err("add reply visibility")?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_states_with_docket(&self) -> Result<Vec<State>> {
+        let coll = self.state_coll();
+
+        let cursor = coll
+            .find(doc! { "docket": { "$type": "object" } }, None)
+            .await
+            .wrap_err("find states with docket")?;
+
+        cursor.try_collect().await.wrap_err("collect states")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_lawsuit_history(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit_id: Uuid,
+        entry: &HistoryEntry,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "lawsuits.id": lawsuit_id  },
+            doc! { "$push": { "lawsuits.$.history": bson::to_bson(entry).wrap_err("invalid bson for history entry")? } },
+            None,
+        )
+        .await
+        .wrap_err("add lawsuit history")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_states_with_open_fast_track(&self) -> Result<Vec<State>> {
+        let coll = self.state_coll();
+
+        let cursor = coll
+            .find(
+                doc! { "lawsuits": { "$elemMatch": {
+                    "fast_track": true,
+                    "verdict": null,
+                    "status": { "$nin": ["withdrawn", "dismissed", "merged"] },
+                } } },
+                None,
+            )
+            .await
+            .wrap_err("find states with open fast track lawsuits")?;
+
+        cursor.try_collect().await.wrap_err("collect states")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_guild(&self, guild_id: SnowflakeId) -> Result<()> {
+        let coll = self.state_coll();
+
+        coll.delete_one(doc! { "guild_id": &guild_id }, None)
+            .await
+            .wrap_err("delete guild")?;
+        Ok(())
+    }
+
+    /// Returns whether the user wasn't in prison before.
+    #[tracing::instrument(skip(self))]
+    pub async fn add_to_prison(&self, guild_id: SnowflakeId, user_id: SnowflakeId) -> Result<bool> {
+        let coll = self.prison_coll();
+
+        let result = coll
+            .update_one(
+            doc! { "guild_id": guild_id, "user_id": user_id },
+            doc! {
+                "$setOnInsert": {
+                    "guild_id": guild_id, "user_id": user_id, "arrested_at": bson::DateTime::now(),
+                }
+            },
+            UpdateOptions::builder().upsert(true).build(),
+        )
+        .await
+        .wrap_err("add to prison collection")?;
+
+        Ok(result.upserted_id.is_some())
+    }
+
+    /// Returns whether the user was in prison.
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_from_prison(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<bool> {
+        let coll = self.prison_coll();
+
+        let entry = coll
+            .find_one_and_delete(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+            .await
+            .wrap_err("remove from prison")?;
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+
+        let stay = PrisonStay {
+            guild_id,
+            user_id,
+            arrested_at: entry.arrested_at,
+            released_at: bson::DateTime::now(),
+            wrongful: false,
+        };
+        self.prison_history_coll()
+            .insert_one(stay, None)
+            .await
+            .wrap_err("insert prison stay")?;
+
+        Ok(true)
+    }
+
+    /// The members with the most arrests or the longest time in prison, from the finished stays.
+    #[tracing::instrument(skip(self))]
+    pub async fn prison_leaderboard(
+        &self,
+        guild_id: SnowflakeId,
+        ranking: PrisonRanking,
+        hidden: &[SnowflakeId],
+        limit: i64,
+    ) -> Result<Vec<PrisonRecord>> {
+        let sort = match ranking {
+            PrisonRanking::TimeServed => doc! { "served_ms": -1, "arrests": -1 },
+            PrisonRanking::Arrests => doc! { "arrests": -1, "served_ms": -1 },
+        };
+        let cursor = self
+            .prison_history_coll()
+            .aggregate(
+                [
+                    doc! { "$match": { "guild_id": guild_id, "user_id": { "$nin": hidden }, "wrongful": { "$ne": true } } },
+                    doc! { "$group": {
+                        "_id": "$user_id",
+                        "arrests": { "$sum": 1_i64 },
+                        "served_ms": { "$sum": { "$toLong": { "$subtract": ["$released_at", "$arrested_at"] } } },
+                    } },
+                    doc! { "$sort": sort },
+                    doc! { "$limit": limit },
+                ],
+                None,
+            )
+            .await
+            .wrap_err("aggregate prison history")?;
+
+        let documents: Vec<Document> = cursor
+            .try_collect()
+            .await
+            .wrap_err("collect prison history")?;
+        documents
+            .into_iter()
+            .map(|document| bson::from_document(document).wrap_err("invalid prison record"))
+            .collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_leaderboard_opt_out(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        opt_out: bool,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        let update = if opt_out {
+            doc! { "$addToSet": { "leaderboard_opt_outs": user_id } }
+        } else {
+            doc! { "$pull": { "leaderboard_opt_outs": user_id } }
+        };
+        coll.update_one(doc! { "guild_id": &guild_id }, update, None)
+            .await
+            .wrap_err("update leaderboard opt outs")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_federation_partner(
+        &self,
+        guild_id: SnowflakeId,
+        partner_id: SnowflakeId,
+        partner: bool,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        let update = if partner {
+            doc! { "$addToSet": { "federation": partner_id } }
+        } else {
+            doc! { "$pull": { "federation": partner_id } }
+        };
+        coll.update_one(doc! { "guild_id": &guild_id }, update, None)
+            .await
+            .wrap_err("update federation")?;
+        Ok(())
+    }
+
+    /// How often the member was released from prison.
+    #[tracing::instrument(skip(self))]
+    pub async fn count_prison_stays(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<u64> {
+        self.prison_history_coll()
+            .count_documents(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+            .await
+            .wrap_err("count prison stays")
+    }
+
+    #[tracing::instrument(skip(self, petition), fields(petition_id = %petition.id))]
+    pub async fn add_petition(&self, petition: &Petition) -> Result<()> {
+        self.petition_coll()
+            .insert_one(petition, None)
+            .await
+            .wrap_err("insert petition")?;
+        Ok(())
+    }
+
+    /// Marks the last finished stay of the user as wrongful.
+    #[tracing::instrument(skip(self))]
+    pub async fn mark_prison_stay_wrongful(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<()> {
+        self.prison_history_coll()
+            .find_one_and_update(
+                doc! { "guild_id": guild_id, "user_id": user_id },
+                doc! { "$set": { "wrongful": true } },
+                FindOneAndUpdateOptions::builder()
+                    .sort(doc! { "released_at": -1 })
+                    .build(),
+            )
+            .await
+            .wrap_err("mark prison stay wrongful")?;
+        Ok(())
+    }
+
+    /// How often the member was in prison wrongfully.
+    #[tracing::instrument(skip(self))]
+    pub async fn count_wrongful_prison_stays(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<u64> {
+        self.prison_history_coll()
+            .count_documents(
+                doc! { "guild_id": guild_id, "user_id": user_id, "wrongful": true },
+                None,
+            )
+            .await
+            .wrap_err("count wrongful prison stays")
+    }
+
+    #[tracing::instrument(skip(self, appeal), fields(appeal_id = %appeal.id))]
+    pub async fn add_arrest_appeal(&self, appeal: &ArrestAppeal) -> Result<()> {
+        self.arrest_appeal_coll()
+            .insert_one(appeal, None)
+            .await
+            .wrap_err("insert arrest appeal")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_arrest_appeal(&self, appeal_id: Uuid) -> Result<Option<ArrestAppeal>> {
+        self.arrest_appeal_coll()
+            .find_one(doc! { "id": appeal_id }, None)
+            .await
+            .wrap_err("find arrest appeal")
+    }
+
+    /// The appeal against the arrest, if the user filed one already.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_arrest_appeal_for(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        arrested_at: Option<bson::DateTime>,
+    ) -> Result<Option<ArrestAppeal>> {
+        self.arrest_appeal_coll()
+            .find_one(
+                doc! { "guild_id": guild_id, "user_id": user_id, "arrested_at": arrested_at },
+                None,
+            )
+            .await
+            .wrap_err("find arrest appeal")
+    }
+
+    /// Decides the appeal if it is still pending. Returns the decided appeal, or `None` if it was
+    /// decided already.
+    #[tracing::instrument(skip(self))]
+    pub async fn decide_arrest_appeal(
+        &self,
+        appeal_id: Uuid,
+        status: ArrestAppealStatus,
+        decided_by: SnowflakeId,
+    ) -> Result<Option<ArrestAppeal>> {
+        let status = bson::to_bson(&status).wrap_err("invalid bson for arrest appeal status")?;
+        self.arrest_appeal_coll()
+            .find_one_and_update(
+                doc! { "id": appeal_id, "status": "pending" },
+                doc! { "$set": { "status": status, "decided_by": decided_by } },
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .wrap_err("decide arrest appeal")
+    }
+
+    /// The last petition the user filed, for the cooldown.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_last_petition(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<Option<Petition>> {
+        self.petition_coll()
+            .find_one(
+                doc! { "guild_id": guild_id, "user_id": user_id },
+                FindOneOptions::builder()
+                    .sort(doc! { "filed_at": -1 })
+                    .build(),
+            )
+            .await
+            .wrap_err("find last petition")
+    }
+
+    /// Grants or denies the petition if it is still pending. Returns the decided petition, or
+    /// `None` if it was decided already.
+    #[tracing::instrument(skip(self))]
+    pub async fn decide_petition(
+        &self,
+        petition_id: Uuid,
+        status: PetitionStatus,
+        decided_by: SnowflakeId,
+    ) -> Result<Option<Petition>> {
+        let status = bson::to_bson(&status).wrap_err("invalid bson for petition status")?;
+        self.petition_coll()
+            .find_one_and_update(
+                doc! { "id": petition_id, "status": "pending" },
+                doc! { "$set": { "status": status, "decided_by": decided_by } },
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .wrap_err("decide petition")
+    }
+
+    #[tracing::instrument(skip(self, visit), fields(visit_id = %visit.id))]
+    pub async fn add_visit(&self, visit: &Visit) -> Result<()> {
+        self.visit_coll()
+            .insert_one(visit, None)
+            .await
+            .wrap_err("insert visit")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_visit(&self, visit_id: Uuid) -> Result<Option<Visit>> {
+        self.visit_coll()
+            .find_one(doc! { "id": visit_id }, None)
+            .await
+            .wrap_err("find visit")
+    }
+
+    /// Approves or denies the visit if it wasn't decided yet. Returns the decided visit, or `None`
+    /// if it was decided already.
+    #[tracing::instrument(skip(self))]
+    pub async fn decide_visit(
+        &self,
+        visit_id: Uuid,
+        status: VisitStatus,
+        decided_by: SnowflakeId,
+    ) -> Result<Option<Visit>> {
+        let status = bson::to_bson(&status).wrap_err("invalid bson for visit status")?;
+        self.visit_coll()
+            .find_one_and_update(
+                doc! { "id": visit_id, "status": "requested" },
+                doc! { "$set": { "status": status, "decided_by": decided_by } },
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .wrap_err("decide visit")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_warrant(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<Option<Warrant>> {
+        self.warrant_coll()
+            .find_one(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+            .await
+            .wrap_err("find warrant")
+    }
+
+    #[tracing::instrument(skip(self, warrant), fields(user_id = %warrant.user_id))]
+    pub async fn add_warrant(&self, warrant: &Warrant) -> Result<()> {
+        self.warrant_coll()
+            .insert_one(warrant, None)
+            .await
+            .wrap_err("insert warrant")?;
+        Ok(())
+    }
+
+    /// Removes the warrant and returns it, so its notices can be deleted.
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_warrant(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<Option<Warrant>> {
+        self.warrant_coll()
+            .find_one_and_delete(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+            .await
+            .wrap_err("remove warrant")
+    }
+
+    /// Records when the prisoner left the guild, or clears it when they rejoin.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_prisoner_left(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        left_at: Option<bson::DateTime>,
+    ) -> Result<()> {
+        let coll = self.prison_coll();
+
+        coll.update_one(
+            doc! { "guild_id": guild_id, "user_id": user_id },
+            doc! { "$set": { "left_at": left_at } },
+            None,
+        )
+        .await
+        .wrap_err("set prisoner left")?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_prisoners(&self, guild_id: SnowflakeId) -> Result<Vec<PrisonEntry>> {
+        let coll = self.prison_coll();
+
+        let cursor = coll
+            .find(doc! { "guild_id": guild_id }, None)
+            .await
+            .wrap_err("find prisoners")?;
+
+        cursor.try_collect().await.wrap_err("collect prisoners")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_prison_entries(
+        &self,
+        guild_id: SnowflakeId,
+        user_ids: &[SnowflakeId],
+    ) -> Result<()> {
+        if user_ids.is_empty() {
+            return Ok(());
+        }
+        self.prison_coll()
+            .delete_many(
+                doc! { "guild_id": guild_id, "user_id": { "$in": user_ids } },
+                None,
+            )
+            .await
+            .wrap_err("delete prison entries")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_prison_entry(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<Option<PrisonEntry>> {
+        let coll = self.prison_coll();
+
+        coll.find_one(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+            .await
+            .wrap_err("remove from prison")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_filing_fee(&self, guild_id: SnowflakeId, fee: Option<i64>) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "filing_fee": fee } },
+            None,
+        )
+        .await
+        .wrap_err("update filing fee")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_appeal_window(
+        &self,
+        guild_id: SnowflakeId,
+        appeal_window_secs: Option<i64>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "appeal_window_secs": appeal_window_secs } },
+            None,
+        )
+        .await
+        .wrap_err("update appeal window")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_debt_blocks_filings(
+        &self,
+        guild_id: SnowflakeId,
+        debt_blocks_filings: bool,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "debt_blocks_filings": debt_blocks_filings } },
+            None,
+        )
+        .await
+        .wrap_err("update debt blocks filings")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_compensation_rate(
+        &self,
+        guild_id: SnowflakeId,
+        rate: Option<i64>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "compensation_rate": rate } },
+            None,
+        )
+        .await
+        .wrap_err("update compensation rate")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_garnishment_percent(
+        &self,
+        guild_id: SnowflakeId,
+        percent: Option<u8>,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "garnishment_percent": percent.map(i32::from) } },
+            None,
+        )
+        .await
+        .wrap_err("update garnishment percent")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_account(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<Account> {
+        let coll = self.account_coll();
+
+        let account = coll
+            .find_one(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+            .await
+            .wrap_err("find account")?;
+
+        Ok(account.unwrap_or(Account {
+            guild_id,
+            user_id,
+            balance: 0,
+        }))
+    }
+
+    /// Sets the salary for a role, replacing the existing salary of that role.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_salary(&self, guild_id: SnowflakeId, salary: &Salary) -> Result<()> {
+        self.remove_salary(guild_id, salary.role_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$push": { "salaries": bson::to_bson(salary).wrap_err("invalid bson for salary")? } },
+            None,
+        )
+        .await
+        .wrap_err("push salary")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_salary(&self, guild_id: SnowflakeId, role_id: SnowflakeId) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$pull": { "salaries": { "role_id": role_id } } },
+            None,
+        )
+        .await
+        .wrap_err("pull salary")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_salary_paid(
+        &self,
+        guild_id: SnowflakeId,
+        role_id: SnowflakeId,
+        paid_at: bson::DateTime,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id, "salaries.role_id": role_id },
+            doc! { "$set": { "salaries.$.last_paid": paid_at } },
+            None,
+        )
+        .await
+        .wrap_err("set salary paid")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_states_with_salaries(&self) -> Result<Vec<State>> {
+        let coll = self.state_coll();
+
+        let cursor = coll
+            .find(doc! { "salaries.0": { "$exists": true } }, None)
+            .await
+            .wrap_err("find states with salaries")?;
+
+        cursor.try_collect().await.wrap_err("collect states")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_tax(&self, guild_id: SnowflakeId, tax: Option<&Tax>) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "tax": bson::to_bson(&tax).wrap_err("invalid bson for tax")? } },
+            None,
+        )
+        .await
+        .wrap_err("update tax")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_tax_collected(
+        &self,
+        guild_id: SnowflakeId,
+        collected_at: bson::DateTime,
+    ) -> Result<()> {
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "tax.last_collected": collected_at } },
+            None,
+        )
+        .await
+        .wrap_err("set tax collected")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_states_with_tax(&self) -> Result<Vec<State>> {
+        let coll = self.state_coll();
+
+        let cursor = coll
+            .find(doc! { "tax": { "$type": "object" } }, None)
+            .await
+            .wrap_err("find states with tax")?;
+
+        cursor.try_collect().await.wrap_err("collect states")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_accounts(&self, guild_id: SnowflakeId) -> Result<Vec<Account>> {
+        let coll = self.account_coll();
+
+        let cursor = coll
+            .find(doc! { "guild_id": guild_id }, None)
+            .await
+            .wrap_err("find accounts")?;
+
+        cursor.try_collect().await.wrap_err("collect accounts")
+    }
+
+    /// Adds `amount` to the balance of the account and records it in the ledger. `amount` can
+    /// be negative, in which case the balance can go below zero. Returns the new balance.
+    #[tracing::instrument(skip(self))]
+    pub async fn credit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> Result<i64> {
+        let coll = self.account_coll();
+
+        let account = coll
+            .find_one_and_update(
+                doc! { "guild_id": guild_id, "user_id": user_id },
+                doc! { "$inc": { "balance": amount } },
+                FindOneAndUpdateOptions::builder()
+                    .upsert(true)
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .wrap_err("credit account")?
+            .wrap_err("account not found after upsert")?;
+
+        self.record_transaction(guild_id, user_id, amount, kind, description)
+            .await?;
+
+        Ok(account.balance)
+    }
+
+    /// Removes `amount` from the balance of the account if it is sufficient and records it in the ledger.
+    /// Returns the new balance, or the missing amount if the balance is too low.
+    #[tracing::instrument(skip(self))]
+    pub async fn try_debit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> Result<Result<i64, i64>> {
+        let coll = self.account_coll();
+
+        let account = coll
+            .find_one_and_update(
+                doc! { "guild_id": guild_id, "user_id": user_id, "balance": { "$gte": amount } },
+                doc! { "$inc": { "balance": -amount } },
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .wrap_err("debit account")?;
+
+        match account {
+            Some(account) => {
+                self.record_transaction(guild_id, user_id, -amount, kind, description)
+                    .await?;
+                Ok(Ok(account.balance))
+            }
+            None => {
+                let account = self.find_account(guild_id, user_id).await?;
+                Ok(Err(amount - account.balance))
+            }
+        }
+    }
+
+    /// Removes as much of `amount` from the balance of the account as possible without going below zero
+    /// and records it in the ledger. Returns the amount that was actually removed.
+    #[tracing::instrument(skip(self))]
+    pub async fn debit_up_to(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> Result<i64> {
+        loop {
+            let account = self.find_account(guild_id, user_id).await?;
+            let paid = account.balance.clamp(0, amount);
+            if paid == 0 {
+                return Ok(0);
+            }
+
+            // someone else might have changed the balance in the meantime, in which case we just try again
+            if self
+                .try_debit(guild_id, user_id, paid, kind, description)
+                .await?
+                .is_ok()
+            {
+                return Ok(paid);
+            }
+        }
+    }
+
+    async fn record_transaction(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> Result<()> {
+        let transaction = Transaction {
+            id: Uuid::new(),
+            guild_id,
+            user_id,
+            amount,
+            kind,
+            description: description.to_owned(),
+            timestamp: bson::DateTime::now(),
+        };
+
+        self.ledger_coll()
+            .insert_one(&transaction, None)
+            .await
+            .wrap_err("insert transaction")?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_transactions(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        limit: i64,
+    ) -> Result<Vec<Transaction>> {
+        let coll = self.ledger_coll();
+
+        let cursor = coll
+            .find(
+                doc! { "guild_id": guild_id, "user_id": user_id },
+                FindOptions::builder()
+                    .sort(doc! { "timestamp": -1 })
+                    .limit(limit)
+                    .build(),
+            )
+            .await
+            .wrap_err("find transactions")?;
+
+        cursor.try_collect().await.wrap_err("collect transactions")
+    }
+
+    /// Finds the transactions of the user from `from` until before `to`, oldest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_transactions_between(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        from: bson::DateTime,
+        to: bson::DateTime,
+    ) -> Result<Vec<Transaction>> {
+        let coll = self.ledger_coll();
+
+        let cursor = coll
+            .find(
+                doc! {
+                    "guild_id": guild_id,
+                    "user_id": user_id,
+                    "timestamp": { "$gte": from, "$lt": to },
+                },
+                FindOptions::builder().sort(doc! { "timestamp": 1 }).build(),
+            )
+            .await
+            .wrap_err("find transactions")?;
+
+        cursor.try_collect().await.wrap_err("collect transactions")
+    }
+
+    /// Finds the transactions of the kind in the guild, oldest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_transactions_of_kind(
+        &self,
+        guild_id: SnowflakeId,
+        kind: TransactionKind,
+        from: Option<bson::DateTime>,
+        to: Option<bson::DateTime>,
+    ) -> Result<Vec<Transaction>> {
+        let coll = self.ledger_coll();
+
+        let mut filter = doc! {
+            "guild_id": guild_id,
+            "kind": bson::to_bson(&kind).wrap_err("invalid bson for transaction kind")?,
+        };
+        let mut timestamp = Document::new();
+        if let Some(from) = from {
+            timestamp.insert("$gte", from);
+        }
+        if let Some(to) = to {
+            timestamp.insert("$lt", to);
+        }
+        if !timestamp.is_empty() {
+            filter.insert("timestamp", timestamp);
+        }
+
+        let cursor = coll
+            .find(
+                filter,
+                FindOptions::builder().sort(doc! { "timestamp": 1 }).build(),
+            )
+            .await
+            .wrap_err("find transactions")?;
+
+        cursor.try_collect().await.wrap_err("collect transactions")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_debt(&self, debt: &Debt) -> Result<()> {
+        self.debt_coll()
+            .insert_one(debt, None)
+            .await
+            .wrap_err("insert debt")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_debts(
+        &self,
+        guild_id: SnowflakeId,
+        debtor: SnowflakeId,
+    ) -> Result<Vec<Debt>> {
+        let coll = self.debt_coll();
+
+        let cursor = coll
+            .find(
+                doc! { "guild_id": guild_id, "debtor": debtor, "amount": { "$gt": 0_i64 } },
+                FindOptions::builder()
+                    .sort(doc! { "created_at": 1 })
+                    .build(),
+            )
+            .await
+            .wrap_err("find debts")?;
+
+        cursor.try_collect().await.wrap_err("collect debts")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_debt(&self, debt_id: Uuid) -> Result<Option<Debt>> {
+        self.debt_coll()
+            .find_one(doc! { "id": debt_id }, None)
+            .await
+            .wrap_err("find debt")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn reduce_debt(&self, debt_id: Uuid, amount: i64) -> Result<()> {
+        self.debt_coll()
+            .update_one(
+                doc! { "id": debt_id },
+                doc! { "$inc": { "amount": -amount } },
+                None,
+            )
+            .await
+            .wrap_err("reduce debt")?;
+        Ok(())
+    }
+
+    /// Marks the income of the debtor until `until` as garnished for all their outstanding debts.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_garnished_until(
+        &self,
+        guild_id: SnowflakeId,
+        debtor: SnowflakeId,
+        until: bson::DateTime,
+    ) -> Result<()> {
+        self.debt_coll()
+            .update_many(
+                doc! { "guild_id": guild_id, "debtor": debtor, "amount": { "$gt": 0_i64 } },
+                doc! { "$set": { "garnished_until": until } },
+                None,
+            )
+            .await
+            .wrap_err("set garnished until")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_service_obligation(&self, obligation: &ServiceObligation) -> Result<()> {
+        self.service_coll()
+            .insert_one(obligation, None)
+            .await
+            .wrap_err("insert community service")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_service_obligations(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<Vec<ServiceObligation>> {
+        let coll = self.service_coll();
+
+        let cursor = coll
+            .find(
+                doc! { "guild_id": guild_id, "user_id": user_id },
+                FindOptions::builder().sort(doc! { "deadline": 1 }).build(),
+            )
+            .await
+            .wrap_err("find community service")?;
+
+        cursor
+            .try_collect()
+            .await
+            .wrap_err("collect community service")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_moderation_action(&self, action: &ModerationAction) -> Result<()> {
+        self.moderation_coll()
+            .insert_one(action, None)
+            .await
+            .wrap_err("insert moderation action")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, actions), fields(count = actions.len()))]
+    pub async fn add_moderation_actions(&self, actions: &[ModerationAction]) -> Result<()> {
+        if actions.is_empty() {
+            return Ok(());
+        }
+        self.moderation_coll()
+            .insert_many(actions, None)
+            .await
+            .wrap_err("insert moderation actions")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_guild_moderation_actions(
+        &self,
+        guild_id: SnowflakeId,
+    ) -> Result<Vec<ModerationAction>> {
+        let coll = self.moderation_coll();
+
+        let cursor = coll
+            .find(doc! { "guild_id": guild_id }, None)
+            .await
+            .wrap_err("find moderation actions")?;
+
+        cursor
+            .try_collect()
+            .await
+            .wrap_err("collect moderation actions")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_moderation_actions(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        kind: Option<ModerationKind>,
+    ) -> Result<Vec<ModerationAction>> {
+        let coll = self.moderation_coll();
+
+        let mut filter = doc! { "guild_id": guild_id, "user_id": user_id };
+        if let Some(kind) = kind {
+            filter.insert(
+                "kind",
+                bson::to_bson(&kind).wrap_err("invalid bson for moderation kind")?,
+            );
+        }
+        let cursor = coll
+            .find(
+                filter,
+                FindOptions::builder()
+                    .sort(doc! { "timestamp": -1 })
+                    .build(),
+            )
+            .await
+            .wrap_err("find moderation actions")?;
+
+        cursor
+            .try_collect()
+            .await
+            .wrap_err("collect moderation actions")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_verdict_polls(
+        &self,
+        guild_id: SnowflakeId,
+        verdict_polls: bool,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "verdict_polls": verdict_polls } },
+            None,
+        )
+        .await
+        .wrap_err("update verdict polls")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_ordeals(&self, guild_id: SnowflakeId, ordeals: bool) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "ordeals": ordeals } },
+            None,
+        )
+        .await
+        .wrap_err("update ordeals")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_global_list(&self, guild_id: SnowflakeId, global_list: bool) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "global_list": global_list } },
+            None,
+        )
+        .await
+        .wrap_err("update global list")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_arrest_notice(
+        &self,
+        guild_id: SnowflakeId,
+        arrest_notice: bool,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "arrest_notice": arrest_notice } },
+            None,
+        )
+        .await
+        .wrap_err("update arrest notice")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_record_moderation(
+        &self,
+        guild_id: SnowflakeId,
+        record_moderation: bool,
+    ) -> Result<()> {
+        let _ = self.find_or_insert_state(guild_id).await?;
+        let coll = self.state_coll();
+        coll.update_one(
+            doc! { "guild_id": &guild_id  },
+            doc! { "$set": { "record_moderation": record_moderation } },
+            None,
+        )
+        .await
+        .wrap_err("update record moderation")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_overdue_service_obligations(
+        &self,
+        now: bson::DateTime,
+    ) -> Result<Vec<ServiceObligation>> {
+        let coll = self.service_coll();
+
+        let cursor = coll
+            .find(doc! { "deadline": { "$lte": now } }, None)
+            .await
+            .wrap_err("find overdue community service")?;
 
+        cursor
+            .try_collect()
+            .await
+            .wrap_err("collect community service")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_service_hours_done(&self, obligation_id: Uuid, hours_done: u32) -> Result<()> {
+        self.service_coll()
+            .update_one(
+                doc! { "id": obligation_id },
+                doc! { "$set": { "hours_done": hours_done } },
+                None,
+            )
+            .await
+            .wrap_err("set community service hours")?;
         Ok(())
     }
 
     #[tracing::instrument(skip(self))]
-    pub async fn remove_from_prison(
+    pub async fn delete_service_obligation(&self, obligation_id: Uuid) -> Result<()> {
+        self.service_coll()
+            .delete_one(doc! { "id": obligation_id }, None)
+            .await
+            .wrap_err("delete community service")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_probation(&self, probation: &Probation) -> Result<()> {
+        self.probation_coll()
+            .insert_one(probation, None)
+            .await
+            .wrap_err("insert probation")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_probation(&self, probation_id: Uuid) -> Result<Option<Probation>> {
+        self.probation_coll()
+            .find_one(doc! { "id": probation_id }, None)
+            .await
+            .wrap_err("find probation")
+    }
+
+    /// The probations of the user that are neither over nor revoked.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_active_probations(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        now: bson::DateTime,
+    ) -> Result<Vec<Probation>> {
+        let cursor = self
+            .probation_coll()
+            .find(
+                doc! {
+                    "guild_id": guild_id,
+                    "user_id": user_id,
+                    "until": { "$gt": now },
+                    "revoked": false,
+                },
+                None,
+            )
+            .await
+            .wrap_err("find probations")?;
+
+        cursor.try_collect().await.wrap_err("collect probations")
+    }
+
+    /// Marks the probation as revoked. Returns `false` if it was revoked already.
+    #[tracing::instrument(skip(self))]
+    pub async fn revoke_probation(&self, probation_id: Uuid) -> Result<bool> {
+        let result = self
+            .probation_coll()
+            .update_one(
+                doc! { "id": probation_id, "revoked": false },
+                doc! { "$set": { "revoked": true } },
+                None,
+            )
+            .await
+            .wrap_err("revoke probation")?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    /// Deletes the probations from the lawsuit, also revoked ones whose sentence wasn't activated
+    /// yet.
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_lawsuit_probations(&self, lawsuit_id: Uuid) -> Result<()> {
+        self.probation_coll()
+            .delete_many(doc! { "lawsuit_id": lawsuit_id }, None)
+            .await
+            .wrap_err("delete lawsuit probations")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_probation(&self, probation_id: Uuid) -> Result<()> {
+        self.probation_coll()
+            .delete_one(doc! { "id": probation_id }, None)
+            .await
+            .wrap_err("delete probation")?;
+        Ok(())
+    }
+
+    /// How long a round trip to the database takes.
+    #[tracing::instrument(skip(self))]
+    pub async fn ping(&self) -> Result<Duration> {
+        let start = Instant::now();
+        self.db
+            .run_command(doc! { "ping": 1 }, None)
+            .await
+            .wrap_err("ping database")?;
+        Ok(start.elapsed())
+    }
+
+    /// The number of open lawsuits in all guilds.
+    #[tracing::instrument(skip(self))]
+    pub async fn count_open_lawsuits(&self) -> Result<u64> {
+        let mut count = 0;
+        for mongo in self.namespaces().await? {
+            let mut cursor = mongo
+                .state_coll()
+                .aggregate(
+                    [
+                        doc! { "$unwind": "$lawsuits" },
+                        doc! { "$match": {
+                            "lawsuits.verdict": null,
+                            "lawsuits.status": { "$nin": ["no_billed", "withdrawn", "dismissed", "merged"] },
+                        } },
+                        doc! { "$count": "count" },
+                    ],
+                    None,
+                )
+                .await
+                .wrap_err("count open lawsuits")?;
+
+            if let Some(result) = cursor.try_next().await.wrap_err("read lawsuit count")? {
+                count += result.get_i32("count").wrap_err("invalid lawsuit count")? as u64;
+            }
+        }
+        Ok(count)
+    }
+
+    /// The number of prisoners in all guilds.
+    #[tracing::instrument(skip(self))]
+    pub async fn count_prisoners(&self) -> Result<u64> {
+        let mut count = 0;
+        for mongo in self.namespaces().await? {
+            count += mongo
+                .prison_coll()
+                .count_documents(None, None)
+                .await
+                .wrap_err("count prisoners")?;
+        }
+        Ok(count)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_job(&self, job: &Job) -> Result<()> {
+        self.job_coll()
+            .insert_one(job, None)
+            .await
+            .wrap_err("insert job")?;
+        Ok(())
+    }
+
+    /// Locks the next due job that isn't running or whose run was interrupted, except for jobs of
+    /// the skipped guilds.
+    #[tracing::instrument(skip(self))]
+    pub async fn claim_due_job(
+        &self,
+        now: bson::DateTime,
+        locked_until: bson::DateTime,
+        skip_guilds: &[SnowflakeId],
+    ) -> Result<Option<Job>> {
+        self.job_coll()
+            .find_one_and_update(
+                doc! {
+                    "guild_id": { "$nin": skip_guilds },
+                    "due": { "$lte": now },
+                    "failed": false,
+                    "$or": [{ "locked_until": null }, { "locked_until": { "$lte": now } }],
+                },
+                doc! { "$set": { "locked_until": locked_until } },
+                FindOneAndUpdateOptions::builder()
+                    .sort(doc! { "due": 1 })
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .wrap_err("claim due job")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_job(&self, id: Uuid) -> Result<()> {
+        self.job_coll()
+            .delete_one(doc! { "id": id }, None)
+            .await
+            .wrap_err("delete job")?;
+        Ok(())
+    }
+
+    /// Deletes the jobs that would release the prisoner, also failed ones, so that they can't free
+    /// them from a later arrest.
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_release_jobs(
         &self,
         guild_id: SnowflakeId,
         user_id: SnowflakeId,
     ) -> Result<()> {
-        let coll = self.prison_coll();
+        self.job_coll()
+            .delete_many(
+                doc! { "guild_id": guild_id, "kind.type": "release", "kind.user_id": user_id },
+                None,
+            )
+            .await
+            .wrap_err("delete release jobs")?;
+        Ok(())
+    }
 
-        coll.delete_one(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+    /// Records the error and unlocks the job to retry it then, or gives up if there is no retry.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_job_failed(
+        &self,
+        id: Uuid,
+        attempts: u32,
+        error: &str,
+        retry_at: Option<bson::DateTime>,
+    ) -> Result<()> {
+        let mut update = doc! {
+            "attempts": attempts,
+            "last_error": error,
+            "locked_until": Bson::Null,
+            "failed": retry_at.is_none(),
+        };
+        if let Some(retry_at) = retry_at {
+            update.insert("due", retry_at);
+        }
+        self.job_coll()
+            .update_one(doc! { "id": id }, doc! { "$set": update }, None)
             .await
-            .wrap_err("remove from prison")?;
+            .wrap_err("update failed job")?;
+        Ok(())
+    }
 
+    #[tracing::instrument(skip(self))]
+    pub async fn set_job_due(&self, id: Uuid, due: bson::DateTime) -> Result<()> {
+        self.job_coll()
+            .update_one(doc! { "id": id }, doc! { "$set": { "due": due } }, None)
+            .await
+            .wrap_err("update job due")?;
         Ok(())
     }
 
+    /// Finds the jobs of the guild, or of all guilds, by due time.
     #[tracing::instrument(skip(self))]
-    pub async fn find_prison_entry(
+    pub async fn find_jobs(&self, guild_id: Option<SnowflakeId>) -> Result<Vec<Job>> {
+        let filter = match guild_id {
+            Some(guild_id) => doc! { "guild_id": guild_id },
+            None => doc! {},
+        };
+        let cursor = self
+            .job_coll()
+            .find(
+                filter,
+                FindOptions::builder().sort(doc! { "due": 1 }).build(),
+            )
+            .await
+            .wrap_err("find jobs")?;
+
+        cursor.try_collect().await.wrap_err("collect jobs")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn add_command_usage(&self, usage: &CommandUsage) -> Result<()> {
+        self.usage_coll()
+            .insert_one(usage, None)
+            .await
+            .wrap_err("insert command usage")?;
+        Ok(())
+    }
+
+    /// How many lawsuits were opened and got a verdict in each week since the time. Sealed
+    /// lawsuits are only counted if `sealed` is set.
+    #[tracing::instrument(skip(self))]
+    pub async fn lawsuit_activity(
+        &self,
+        guild_id: SnowflakeId,
+        since: bson::DateTime,
+        sealed: bool,
+    ) -> Result<Activity> {
+        let week = |field: &str| {
+            doc! { "$toLong": { "$floor": { "$divide": [
+                { "$subtract": [field, since] },
+                WEEK.as_millis() as i64,
+            ] } } }
+        };
+        let visible = if sealed {
+            doc! {}
+        } else {
+            doc! { "sealed": { "$ne": true } }
+        };
+
+        let cursor = self
+            .state_coll()
+            .aggregate(
+                [
+                    doc! { "$match": { "guild_id": guild_id } },
+                    doc! { "$unwind": "$lawsuits" },
+                    doc! { "$replaceRoot": { "newRoot": "$lawsuits" } },
+                    doc! { "$match": visible },
+                    doc! { "$facet": {
+                        "opened": [
+                            { "$match": { "created_at": { "$gte": since } } },
+                            { "$group": { "_id": week("$created_at"), "count": { "$sum": 1_i64 } } },
+                        ],
+                        "verdicts": [
+                            { "$match": { "closed_at": { "$gte": since }, "verdict": { "$ne": null } } },
+                            { "$group": { "_id": week("$closed_at"), "count": { "$sum": 1_i64 } } },
+                        ],
+                    } },
+                ],
+                None,
+            )
+            .await
+            .wrap_err("aggregate lawsuit activity")?;
+
+        let documents: Vec<Document> = cursor.try_collect().await.wrap_err("collect activity")?;
+        match documents.into_iter().next() {
+            Some(document) => bson::from_document(document).wrap_err("invalid lawsuit activity"),
+            None => Ok(Activity::default()),
+        }
+    }
+
+    /// The usage of every command since the time, most used first.
+    #[tracing::instrument(skip(self))]
+    pub async fn command_usage_stats(&self, since: bson::DateTime) -> Result<Vec<CommandStats>> {
+        let cursor = self
+            .usage_coll()
+            .aggregate(
+                [
+                    doc! { "$match": { "timestamp": { "$gte": since } } },
+                    doc! { "$group": {
+                        "_id": "$command",
+                        "count": { "$sum": 1_i64 },
+                        "failures": { "$sum": { "$cond": ["$success", 0_i64, 1_i64] } },
+                        "avg_latency_ms": { "$avg": "$latency_ms" },
+                    } },
+                    doc! { "$sort": { "count": -1 } },
+                ],
+                None,
+            )
+            .await
+            .wrap_err("aggregate command usage")?;
+
+        let documents: Vec<Document> = cursor.try_collect().await.wrap_err("collect usage")?;
+        documents
+            .into_iter()
+            .map(|document| bson::from_document(document).wrap_err("invalid command stats"))
+            .collect()
+    }
+
+    /// The guilds with the most command invocations since the time.
+    #[tracing::instrument(skip(self))]
+    pub async fn guild_activity(
+        &self,
+        since: bson::DateTime,
+        limit: i64,
+    ) -> Result<Vec<GuildActivity>> {
+        let cursor = self
+            .usage_coll()
+            .aggregate(
+                [
+                    doc! { "$match": { "timestamp": { "$gte": since } } },
+                    doc! { "$group": {
+                        "_id": "$guild_id",
+                        "count": { "$sum": 1_i64 },
+                        "users": { "$addToSet": "$user_id" },
+                    } },
+                    doc! { "$project": { "count": 1, "users": { "$toLong": { "$size": "$users" } } } },
+                    doc! { "$sort": { "count": -1 } },
+                    doc! { "$limit": limit },
+                ],
+                None,
+            )
+            .await
+            .wrap_err("aggregate guild activity")?;
+
+        let documents: Vec<Document> = cursor.try_collect().await.wrap_err("collect activity")?;
+        documents
+            .into_iter()
+            .map(|document| bson::from_document(document).wrap_err("invalid guild activity"))
+            .collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_guild_access(&self) -> Result<Vec<GuildAccess>> {
+        let cursor = self
+            .guild_access_coll()
+            .find(None, None)
+            .await
+            .wrap_err("find guild access")?;
+
+        cursor.try_collect().await.wrap_err("collect guild access")
+    }
+
+    /// Puts the guild on the allowlist or denylist, or removes it from both with `None`.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_guild_access(
         &self,
         guild_id: SnowflakeId,
+        rule: Option<AccessRule>,
+    ) -> Result<()> {
+        let coll = self.guild_access_coll();
+
+        match rule {
+            Some(rule) => {
+                let access = GuildAccess {
+                    guild_id,
+                    rule,
+                    added_at: bson::DateTime::now(),
+                };
+                coll.replace_one(
+                    doc! { "guild_id": guild_id },
+                    access,
+                    ReplaceOptions::builder().upsert(true).build(),
+                )
+                .await
+                .wrap_err("set guild access")?;
+            }
+            None => {
+                coll.delete_one(doc! { "guild_id": guild_id }, None)
+                    .await
+                    .wrap_err("delete guild access")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_global_list(&self) -> Result<Vec<GlobalListEntry>> {
+        let cursor = self
+            .global_list_coll()
+            .find(None, None)
+            .await
+            .wrap_err("find global list")?;
+
+        cursor.try_collect().await.wrap_err("collect global list")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_global_list_entry(
+        &self,
         user_id: SnowflakeId,
-    ) -> Result<Option<PrisonEntry>> {
-        let coll = self.prison_coll();
+    ) -> Result<Option<GlobalListEntry>> {
+        self.global_list_coll()
+            .find_one(doc! { "user_id": user_id }, None)
+            .await
+            .wrap_err("find global list entry")
+    }
 
-        coll.find_one(doc! { "guild_id": guild_id, "user_id": user_id }, None)
+    /// Puts the user on the global list, replacing an earlier entry.
+    #[tracing::instrument(skip(self, entry), fields(user_id = %entry.user_id))]
+    pub async fn add_global_list_entry(&self, entry: &GlobalListEntry) -> Result<()> {
+        self.global_list_coll()
+            .replace_one(
+                doc! { "user_id": entry.user_id },
+                entry,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
             .await
-            .wrap_err("remove from prison")
+            .wrap_err("add global list entry")?;
+        Ok(())
+    }
+
+    /// Removes the user from the global list. Returns whether they were on it.
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_global_list_entry(&self, user_id: SnowflakeId) -> Result<bool> {
+        let result = self
+            .global_list_coll()
+            .delete_one(doc! { "user_id": user_id }, None)
+            .await
+            .wrap_err("remove global list entry")?;
+        Ok(result.deleted_count > 0)
+    }
+
+    #[tracing::instrument(skip(self, event), fields(event_id = %event.id))]
+    pub async fn add_event(&self, event: &Event) -> Result<()> {
+        self.event_coll()
+            .insert_one(event, None)
+            .await
+            .wrap_err("insert event")?;
+        Ok(())
+    }
+
+    /// Inserts the event that undoes another one. Returns `false` if that event was undone already.
+    #[tracing::instrument(skip(self, event), fields(event_id = %event.id))]
+    pub async fn claim_undo(&self, event: &Event) -> Result<bool> {
+        match self.event_coll().insert_one(event, None).await {
+            Ok(_) => Ok(true),
+            Err(err) => match *err.kind {
+                ErrorKind::Write(WriteFailure::WriteError(WriteError { code: 11000, .. })) => {
+                    Ok(false)
+                }
+                _ => Err(err).wrap_err("insert undo event"),
+            },
+        }
+    }
+
+    /// Stores the changes of an undo once it was applied.
+    #[tracing::instrument(skip(self, changes))]
+    pub async fn set_event_changes(&self, id: Uuid, changes: &[Change]) -> Result<()> {
+        self.event_coll()
+            .update_one(
+                doc! { "id": id },
+                doc! { "$set": { "changes": bson::to_bson(changes).wrap_err("invalid bson for changes")? } },
+                None,
+            )
+            .await
+            .wrap_err("set event changes")?;
+        Ok(())
+    }
+
+    /// Removes an undo that couldn't be applied, so that the event can be undone again.
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_event(&self, id: Uuid) -> Result<()> {
+        self.event_coll()
+            .delete_one(doc! { "id": id }, None)
+            .await
+            .wrap_err("delete event")?;
+        Ok(())
+    }
+
+    /// The events of the guild, newest first. Only events that changed the path or something below
+    /// it if one is given.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_events(
+        &self,
+        guild_id: SnowflakeId,
+        path: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Event>> {
+        let mut filter = doc! { "guild_id": guild_id };
+        if let Some(path) = path {
+            let pattern = format!("^{}", regex_escape(path));
+            filter.insert("changes.path", doc! { "$regex": pattern });
+        }
+
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .limit(limit)
+            .build();
+        let cursor = self
+            .event_coll()
+            .find(filter, options)
+            .await
+            .wrap_err("find events")?;
+        cursor.try_collect().await.wrap_err("collect events")
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn find_event(&self, id: Uuid) -> Result<Option<Event>> {
+        self.event_coll()
+            .find_one(doc! { "id": id }, None)
+            .await
+            .wrap_err("find event")
+    }
+
+    /// The events of the guild after the time, newest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_events_since(
+        &self,
+        guild_id: SnowflakeId,
+        since: bson::DateTime,
+    ) -> Result<Vec<Event>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .build();
+        let cursor = self
+            .event_coll()
+            .find(
+                doc! { "guild_id": guild_id, "timestamp": { "$gt": since } },
+                options,
+            )
+            .await
+            .wrap_err("find events")?;
+        cursor.try_collect().await.wrap_err("collect events")
+    }
+
+    /// The events of the user in the guild after the time, newest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_events_by_actor(
+        &self,
+        guild_id: SnowflakeId,
+        actor: SnowflakeId,
+        since: bson::DateTime,
+    ) -> Result<Vec<Event>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .build();
+        let cursor = self
+            .event_coll()
+            .find(
+                doc! { "guild_id": guild_id, "actor": actor, "timestamp": { "$gt": since } },
+                options,
+            )
+            .await
+            .wrap_err("find events of actor")?;
+        cursor.try_collect().await.wrap_err("collect events")
+    }
+
+    /// Sets a field of the state back to an earlier value, or removes it if there was none. Only
+    /// happens if the field still has the `expected` value. Returns whether it was changed.
+    #[tracing::instrument(skip(self))]
+    pub async fn restore_state_field(
+        &self,
+        guild_id: SnowflakeId,
+        field: &str,
+        expected: Option<Bson>,
+        value: Option<Bson>,
+    ) -> Result<bool> {
+        let mut filter = doc! { "guild_id": guild_id };
+        match expected {
+            Some(expected) => filter.insert(field, expected),
+            None => filter.insert(field, doc! { "$exists": false }),
+        };
+        let update = match value {
+            Some(value) => doc! { "$set": { field: value } },
+            None => doc! { "$unset": { field: "" } },
+        };
+
+        let result = self
+            .state_coll()
+            .update_one(filter, update, None)
+            .await
+            .wrap_err("restore state field")?;
+        Ok(result.matched_count > 0)
     }
 
     fn state_coll(&self) -> Collection<State> {
         self.db.collection("state")
     }
 
+    fn arrest_appeal_coll(&self) -> Collection<ArrestAppeal> {
+        self.db.collection("arrest_appeals")
+    }
+
+    fn petition_coll(&self) -> Collection<Petition> {
+        self.db.collection("petitions")
+    }
+
+    fn visit_coll(&self) -> Collection<Visit> {
+        self.db.collection("visits")
+    }
+
+    fn warrant_coll(&self) -> Collection<Warrant> {
+        self.db.collection("warrants")
+    }
+
     fn prison_coll(&self) -> Collection<PrisonEntry> {
         self.db.collection("prison")
     }
+
+    fn prison_history_coll(&self) -> Collection<PrisonStay> {
+        self.db.collection("prison_history")
+    }
+
+    fn account_coll(&self) -> Collection<Account> {
+        self.db.collection("accounts")
+    }
+
+    fn ledger_coll(&self) -> Collection<Transaction> {
+        self.db.collection("ledger")
+    }
+
+    fn debt_coll(&self) -> Collection<Debt> {
+        self.db.collection("debts")
+    }
+
+    fn service_coll(&self) -> Collection<ServiceObligation> {
+        self.db.collection("community_service")
+    }
+
+    fn probation_coll(&self) -> Collection<Probation> {
+        self.db.collection("probations")
+    }
+
+    fn moderation_coll(&self) -> Collection<ModerationAction> {
+        self.db.collection("moderation")
+    }
+
+    fn job_coll(&self) -> Collection<Job> {
+        self.db.collection("jobs")
+    }
+
+    fn usage_coll(&self) -> Collection<CommandUsage> {
+        self.shared_db.collection("command_usage")
+    }
+
+    fn guild_access_coll(&self) -> Collection<GuildAccess> {
+        self.shared_db.collection("guild_access")
+    }
+
+    fn global_list_coll(&self) -> Collection<GlobalListEntry> {
+        self.shared_db.collection("global_list")
+    }
+
+    fn event_coll(&self) -> Collection<Event> {
+        self.db.collection("events")
+    }
+}
+
+/// Escapes the characters that have a meaning in MongoDB regular expressions.
+fn regex_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for char in input.chars() {
+        if "\\^$.|?*+()[]{}".contains(char) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
 }
@@ -0,0 +1,154 @@
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use color_eyre::Result;
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::{AuditLogEntry, GuildId, Http, MemberAction, Timestamp, UserId};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::{model::SnowflakeId, Mongo, WrapErr};
+
+/// How old an audit log entry can be to still belong to an event.
+const AUDIT_LOG_WINDOW: Duration = Duration::from_secs(30);
+
+/// How many audit log entries are searched for the one belonging to an event.
+const AUDIT_LOG_LIMIT: u8 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationKind {
+    Ban,
+    Kick,
+    Timeout,
+    /// Only imported from other bots, Discord has no warnings.
+    Warn,
+}
+
+impl Display for ModerationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Ban => "Bann",
+            Self::Kick => "Kick",
+            Self::Timeout => "Timeout",
+            Self::Warn => "Verwarnung",
+        })
+    }
+}
+
+impl ModerationKind {
+    fn audit_log_action(self) -> Option<MemberAction> {
+        match self {
+            Self::Ban => Some(MemberAction::BanAdd),
+            Self::Kick => Some(MemberAction::Kick),
+            Self::Timeout => Some(MemberAction::Update),
+            Self::Warn => None,
+        }
+    }
+}
+
+/// A moderation action that was taken with Discord itself instead of the bot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationAction {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    pub kind: ModerationKind,
+    /// Only known if the bot can read the audit log.
+    pub moderator: Option<SnowflakeId>,
+    pub reason: Option<String>,
+    /// When a timeout ends.
+    pub until: Option<DateTime>,
+    pub timestamp: DateTime,
+    /// Whether it was imported from another bot with `/admin import_history`.
+    #[serde(default)]
+    pub imported: bool,
+}
+
+impl Display for ModerationAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<t:{}:d> {}",
+            self.timestamp.timestamp_millis() / 1000,
+            self.kind
+        )?;
+        if let Some(until) = self.until {
+            write!(f, " bis <t:{}:f>", until.timestamp_millis() / 1000)?;
+        }
+        if let Some(moderator) = self.moderator {
+            write!(f, " vo <@{moderator}>")?;
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, ": {reason}")?;
+        }
+        if self.imported {
+            write!(f, " (importiert)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the moderation action in the criminal record of the user. Kicks are only recorded if
+/// they are in the audit log, since they can't be told apart from leaving otherwise.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn record(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    kind: ModerationKind,
+    until: Option<Timestamp>,
+) -> Result<()> {
+    let entry = match kind.audit_log_action() {
+        Some(action) => match find_audit_entry(http, guild_id, action, user_id).await {
+            Ok(entry) => entry,
+            Err(err) => {
+                debug!(?err, "Could not read the audit log");
+                None
+            }
+        },
+        None => None,
+    };
+    if entry.is_none() && kind == ModerationKind::Kick {
+        return Ok(());
+    }
+
+    let action = ModerationAction {
+        id: Uuid::new(),
+        guild_id: guild_id.into(),
+        user_id: user_id.into(),
+        kind,
+        moderator: entry.as_ref().map(|entry| entry.user_id.into()),
+        reason: entry.and_then(|entry| entry.reason),
+        until: until.map(|until| DateTime::from_millis(until.unix_timestamp() * 1000)),
+        timestamp: DateTime::now(),
+        imported: false,
+    };
+    mongo.add_moderation_action(&action).await?;
+
+    info!(?action, "Recorded moderation action");
+
+    Ok(())
+}
+
+/// Finds the latest audit log entry of the action on the user, if it happened just now.
+async fn find_audit_entry(
+    http: &Http,
+    guild_id: GuildId,
+    action: MemberAction,
+    target: UserId,
+) -> Result<Option<AuditLogEntry>> {
+    let audit_logs = guild_id
+        .audit_logs(http, Some(action as u8), None, None, Some(AUDIT_LOG_LIMIT))
+        .await
+        .wrap_err("get audit logs")?;
+
+    let now = Timestamp::now().unix_timestamp();
+    Ok(audit_logs.entries.into_iter().find(|entry| {
+        entry.target_id == Some(target.0)
+            && now - entry.id.created_at().unix_timestamp() <= AUDIT_LOG_WINDOW.as_secs() as i64
+    }))
+}
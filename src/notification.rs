@@ -0,0 +1,131 @@
+use color_eyre::Result;
+use mongodb::bson::Uuid;
+use poise::serenity_prelude::{Http, UserId};
+use tracing::warn;
+
+use crate::{lawsuit::Lawsuit, model::SnowflakeId, Mongo, WrapErr};
+
+/// Tells everyone following the lawsuit about a change via DM. Followers that can't be reached
+/// are skipped, and so are followers who aren't part of a sealed or arbitration case.
+#[tracing::instrument(skip(http, lawsuit), fields(lawsuit_id = %lawsuit.id))]
+pub async fn notify_followers(http: &Http, lawsuit: &Lawsuit, update: &str) {
+    for follower in recipients(lawsuit) {
+        let result = notify_follower(http, *follower, lawsuit, update).await;
+        if let Err(err) = result {
+            warn!(?err, %follower, "Could not notify follower");
+        }
+    }
+}
+
+/// The followers allowed to hear about the lawsuit. Sealed and arbitration cases stay between
+/// the people in the room.
+fn recipients(lawsuit: &Lawsuit) -> impl Iterator<Item = &SnowflakeId> {
+    let private = lawsuit.sealed || lawsuit.arbitration;
+    let involved = lawsuit.involved();
+    lawsuit
+        .followers
+        .iter()
+        .filter(move |follower| !private || involved.contains(follower))
+}
+
+async fn notify_follower(
+    http: &Http,
+    follower: SnowflakeId,
+    lawsuit: &Lawsuit,
+    update: &str,
+) -> Result<()> {
+    UserId::from(follower)
+        .create_dm_channel(http)
+        .await
+        .wrap_err("create dm channel")?
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title(format!("Neuigkeite zum Prozess #{}", lawsuit.number))
+                    .description(update)
+                    .field("Grund", &lawsuit.reason, false)
+                    .footer(|footer| footer.text("/lawsuit notify unfollow zum abbestelle"))
+            })
+        })
+        .await
+        .wrap_err("send follower notification")?;
+
+    Ok(())
+}
+
+/// Sends a reminder set with `/lawsuit notify remind` via DM. The reminder is dropped if the lawsuit was
+/// deleted in the meantime or the user can't be reached.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn send_case_reminder(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    user_id: SnowflakeId,
+    note: &str,
+) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let lawsuit = match state.lawsuits.iter().find(|l| l.id == lawsuit_id) {
+        Some(lawsuit) => lawsuit,
+        None => return Ok(()),
+    };
+
+    let sent = UserId::from(user_id)
+        .create_dm_channel(http)
+        .await
+        .wrap_err("create dm channel")?
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title(format!("Erinnerig zum Prozess #{}", lawsuit.number))
+                    .description(note)
+                    .field("Grund", &lawsuit.reason, false)
+            })
+        })
+        .await;
+    if let Err(err) = sent {
+        warn!(?err, "Could not send case reminder");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::recipients;
+    use crate::{lawsuit::Lawsuit, model::SnowflakeId};
+
+    const PLAINTIFF: SnowflakeId = SnowflakeId(1);
+    const ACCUSED: SnowflakeId = SnowflakeId(2);
+    const JUDGE: SnowflakeId = SnowflakeId(3);
+    const BYSTANDER: SnowflakeId = SnowflakeId(4);
+
+    fn followed_lawsuit() -> Lawsuit {
+        let mut lawsuit = Lawsuit::new(PLAINTIFF, ACCUSED, JUDGE, "reason".to_owned());
+        lawsuit.followers = vec![ACCUSED, BYSTANDER];
+        lawsuit
+    }
+
+    fn notified(lawsuit: &Lawsuit) -> Vec<SnowflakeId> {
+        recipients(lawsuit).copied().collect()
+    }
+
+    #[test]
+    fn public_lawsuit_notifies_everyone() {
+        assert_eq!(notified(&followed_lawsuit()), vec![ACCUSED, BYSTANDER]);
+    }
+
+    #[test]
+    fn sealed_lawsuit_notifies_only_participants() {
+        let mut lawsuit = followed_lawsuit();
+        lawsuit.sealed = true;
+        assert_eq!(notified(&lawsuit), vec![ACCUSED]);
+    }
+
+    #[test]
+    fn arbitration_notifies_only_participants() {
+        let mut lawsuit = followed_lawsuit();
+        lawsuit.arbitration = true;
+        assert_eq!(notified(&lawsuit), vec![ACCUSED]);
+    }
+}
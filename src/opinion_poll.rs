@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, CreateComponents, Http, MessageId};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{handler::Response, model::SnowflakeId, scheduler, scheduler::JobKind, Mongo, WrapErr};
+
+/// The prefix of the custom id of the poll buttons, followed by `{lawsuit_id}:{approve}`.
+pub const VOTE_ID_PREFIX: &str = "opinion:";
+
+/// How long spectators can vote on a verdict.
+const POLL_DURATION: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// The public opinion on a verdict, asked with buttons below its announcement. It has no effect
+/// on the verdict.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpinionPoll {
+    pub channel_id: SnowflakeId,
+    pub message_id: SnowflakeId,
+    pub closes_at: DateTime,
+    pub votes: Vec<OpinionVote>,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpinionVote {
+    pub user_id: SnowflakeId,
+    pub approve: bool,
+}
+
+impl OpinionPoll {
+    pub fn tally(&self) -> (usize, usize) {
+        let approve = self.votes.iter().filter(|vote| vote.approve).count();
+        (approve, self.votes.len() - approve)
+    }
+}
+
+/// Parses the `{lawsuit_id}:{approve}` part of a vote custom id.
+pub fn parse_vote_id(id: &str) -> Option<(Uuid, bool)> {
+    let (lawsuit_id, approve) = id.split_once(':')?;
+    Some((Uuid::parse_str(lawsuit_id).ok()?, approve.parse().ok()?))
+}
+
+/// Adds the buttons to vote on the verdict.
+pub fn buttons(components: &mut CreateComponents, lawsuit_id: Uuid) -> &mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(format!("{VOTE_ID_PREFIX}{lawsuit_id}:true"))
+                .label("Gerecht")
+                .emoji('👍')
+                .style(ButtonStyle::Success)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(format!("{VOTE_ID_PREFIX}{lawsuit_id}:false"))
+                .label("Ungerecht")
+                .emoji('👎')
+                .style(ButtonStyle::Danger)
+        })
+    })
+}
+
+/// Records the poll below the verdict announcement and schedules its end.
+#[tracing::instrument(skip(mongo))]
+pub async fn open(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    channel_id: SnowflakeId,
+    message_id: SnowflakeId,
+) -> Result<()> {
+    let closes_at = DateTime::from_millis(
+        DateTime::now().timestamp_millis() + POLL_DURATION.as_millis() as i64,
+    );
+    let poll = OpinionPoll {
+        channel_id,
+        message_id,
+        closes_at,
+        votes: vec![],
+        closed: false,
+    };
+    mongo
+        .set_lawsuit(
+            guild_id,
+            lawsuit_id,
+            doc! { "lawsuits.$.opinion_poll": mongodb::bson::to_bson(&poll).wrap_err("invalid bson for opinion poll")? },
+        )
+        .await?;
+    scheduler::schedule(
+        mongo,
+        guild_id,
+        JobKind::CloseOpinionPoll { lawsuit_id },
+        closes_at,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Records the vote of a spectator, replacing an earlier one. The parties and the judge can't
+/// vote on their own verdict.
+#[tracing::instrument(skip(mongo))]
+pub async fn vote(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    vote: OpinionVote,
+) -> Result<Result<(), Response>> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let lawsuit = match state.lawsuits.iter().find(|l| l.id == lawsuit_id) {
+        Some(lawsuit) => lawsuit,
+        None => return Ok(Err(Response("de Prozess gits nüm".to_string()))),
+    };
+
+    let open = lawsuit.opinion_poll.as_ref().is_some_and(|poll| {
+        !poll.closed && poll.closes_at.timestamp_millis() > DateTime::now().timestamp_millis()
+    });
+    if !open {
+        return Ok(Err(Response("d'Abstimmig isch vorbi".to_string())));
+    }
+    if lawsuit.involved().contains(&vote.user_id) {
+        return Ok(Err(Response(
+            "über dis eigene Urteil chasch nöd abstimme".to_string(),
+        )));
+    }
+
+    mongo.set_opinion_vote(guild_id, lawsuit_id, &vote).await?;
+
+    Ok(Ok(()))
+}
+
+/// Ends the poll and shows the result instead of the buttons.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn close(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let poll = state
+        .lawsuits
+        .iter()
+        .find(|l| l.id == lawsuit_id)
+        .and_then(|l| l.opinion_poll.as_ref());
+    let poll = match poll {
+        Some(poll) if !poll.closed => poll,
+        _ => return Ok(()),
+    };
+
+    let (approve, disapprove) = poll.tally();
+    let edited = ChannelId::from(poll.channel_id)
+        .edit_message(http, MessageId::from(poll.message_id), |msg| {
+            msg.content(format!(
+                "Volksmeinig: 👍 {approve} gerecht, 👎 {disapprove} ungerecht"
+            ))
+            .components(|c| c)
+        })
+        .await;
+    // the announcement may have been deleted
+    if let Err(err) = edited {
+        warn!(?err, "Could not show opinion poll result");
+    }
+
+    mongo
+        .set_lawsuit(
+            guild_id,
+            lawsuit_id,
+            doc! { "lawsuits.$.opinion_poll.closed": true },
+        )
+        .await?;
+
+    info!(%lawsuit_id, approve, disapprove, "Closed opinion poll");
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::fmt::{Display, Formatter};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::lawsuit::Party;
+
+/// The prefix of the custom id of the rock paper scissors buttons, followed by the hand.
+pub const HAND_ID_PREFIX: &str = "ordeal:";
+
+/// How a deadlocked small claim is decided by chance instead of by the judge. Only guilds that
+/// turned on ordeals can use them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum OrdealKind {
+    #[name = "Würfeln"]
+    Dice,
+    #[name = "Schere, Stein, Papier"]
+    RockPaperScissors,
+}
+
+impl Display for OrdealKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Dice => "Würfle",
+            Self::RockPaperScissors => "Schere, Stei, Papier",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl Hand {
+    pub const ALL: [Hand; 3] = [Self::Scissors, Self::Rock, Self::Paper];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Rock => "rock",
+            Self::Paper => "paper",
+            Self::Scissors => "scissors",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|hand| hand.id() == id)
+    }
+
+    pub fn emoji(self) -> char {
+        match self {
+            Self::Rock => '🪨',
+            Self::Paper => '📄',
+            Self::Scissors => '✂',
+        }
+    }
+
+    fn beats(self, other: Hand) -> bool {
+        matches!(
+            (self, other),
+            (Self::Rock, Self::Scissors)
+                | (Self::Paper, Self::Rock)
+                | (Self::Scissors, Self::Paper)
+        )
+    }
+}
+
+impl Display for Hand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Rock => "Stei",
+            Self::Paper => "Papier",
+            Self::Scissors => "Schere",
+        })
+    }
+}
+
+/// Who wins a round of rock paper scissors, nobody on a tie.
+pub fn duel_winner(plaintiff: Hand, accused: Hand) -> Option<Party> {
+    if plaintiff.beats(accused) {
+        Some(Party::Plaintiff)
+    } else if accused.beats(plaintiff) {
+        Some(Party::Accused)
+    } else {
+        None
+    }
+}
+
+/// Rolls a die for both parties until one rolls higher. Returns the winner and the rolls of the
+/// plaintiff and the accused.
+pub fn roll_dice(rng: &mut impl Rng) -> (Party, Vec<(u8, u8)>) {
+    let mut rolls = Vec::new();
+    loop {
+        let roll = (rng.gen_range(1..=6), rng.gen_range(1..=6));
+        rolls.push(roll);
+        if roll.0 != roll.1 {
+            let winner = if roll.0 > roll.1 {
+                Party::Plaintiff
+            } else {
+                Party::Accused
+            };
+            return (winner, rolls);
+        }
+    }
+}
+
+/// The verdict of an ordeal, with how it went.
+pub fn verdict(kind: OrdealKind, winner: Party, course: &str) -> String {
+    format!("Gottesurteil dur {kind}: {course}. Gwünner: {winner}")
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{duel_winner, roll_dice, Hand};
+    use crate::lawsuit::Party;
+
+    #[test]
+    fn duel() {
+        assert_eq!(
+            duel_winner(Hand::Rock, Hand::Scissors),
+            Some(Party::Plaintiff)
+        );
+        assert_eq!(duel_winner(Hand::Rock, Hand::Paper), Some(Party::Accused));
+        assert_eq!(duel_winner(Hand::Paper, Hand::Paper), None);
+        for hand in Hand::ALL {
+            assert_eq!(Hand::from_id(hand.id()), Some(hand));
+        }
+    }
+
+    #[test]
+    fn dice() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let (winner, rolls) = roll_dice(&mut rng);
+            let (last, ties) = rolls.split_last().unwrap();
+            assert!(ties.iter().all(|(plaintiff, accused)| plaintiff == accused));
+            assert_eq!(winner == Party::Plaintiff, last.0 > last.1);
+        }
+    }
+}
@@ -0,0 +1,557 @@
+//! Printable court records, laid out as text and written with `lopdf`. It only uses the
+//! standard Helvetica fonts, so nothing has to be embedded.
+
+use std::collections::HashMap;
+
+use chrono::TimeZone;
+use chrono_tz::Tz;
+use image::DynamicImage;
+use lopdf::{
+    content::{Content, Operation},
+    dictionary, Object, ObjectId, Stream, StringFormat,
+};
+
+use crate::{economy::format_amount, lawsuit::Lawsuit, model::SnowflakeId};
+
+/// A4 in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 64.0;
+
+/// How much higher a line is than its font size.
+const LINE_SPACING: f32 = 1.35;
+
+/// The width of the seal on a verdict, in points.
+const SEAL_SIZE: f32 = 96.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+        }
+    }
+}
+
+/// Text and images that flow from page to page, starting a new page when one is full.
+pub struct Document {
+    pages: Vec<Vec<Operation>>,
+    /// The position of the next line from the bottom of the page.
+    y: f32,
+    /// Entries of the document information.
+    metadata: Vec<(String, String)>,
+    images: Vec<DynamicImage>,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self {
+            pages: vec![Vec::new()],
+            y: PAGE_HEIGHT - MARGIN,
+            metadata: vec![],
+            images: vec![],
+        }
+    }
+}
+
+impl Document {
+    /// Stores the value in the document information, where [`find_metadata`] finds it again.
+    pub fn metadata(&mut self, key: &str, value: &str) {
+        self.metadata.push((key.to_string(), value.to_string()));
+    }
+
+    /// Adds the text, wrapped at the margins. Empty lines are kept.
+    pub fn text(&mut self, text: &str, font: Font, size: f32) {
+        let max_width = PAGE_WIDTH - 2.0 * MARGIN;
+        for paragraph in text.lines() {
+            let lines = wrap(paragraph, font, size, max_width);
+            if lines.is_empty() {
+                self.space(size * LINE_SPACING);
+            }
+            for line in lines {
+                self.line(&line, font, size);
+            }
+        }
+    }
+
+    pub fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Adds the image at the left margin, scaled to the width.
+    pub fn image(&mut self, image: DynamicImage, width: f32) {
+        let height = width * image.height() as f32 / image.width().max(1) as f32;
+        self.reserve(height);
+        self.y -= height;
+
+        let name = format!("Im{}", self.images.len() + 1);
+        self.images.push(image);
+        let y = self.y;
+        self.page().extend([
+            Operation::new("q", vec![]),
+            Operation::new(
+                "cm",
+                vec![
+                    width.into(),
+                    0.into(),
+                    0.into(),
+                    height.into(),
+                    MARGIN.into(),
+                    y.into(),
+                ],
+            ),
+            Operation::new("Do", vec![name.into()]),
+            Operation::new("Q", vec![]),
+        ]);
+    }
+
+    fn line(&mut self, line: &str, font: Font, size: f32) {
+        let height = size * LINE_SPACING;
+        self.reserve(height);
+        self.y -= height;
+
+        let y = self.y;
+        self.page().extend([
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![font.resource().into(), size.into()]),
+            Operation::new("Td", vec![MARGIN.into(), y.into()]),
+            Operation::new("Tj", vec![Object::string_literal(encode(line))]),
+            Operation::new("ET", vec![]),
+        ]);
+    }
+
+    /// Starts a new page if the height doesn't fit on this one anymore.
+    fn reserve(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.pages.push(Vec::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn page(&mut self) -> &mut Vec<Operation> {
+        self.pages.last_mut().expect("there is always a page")
+    }
+
+    /// The finished PDF file.
+    pub fn finish(self) -> Vec<u8> {
+        let mut pdf = lopdf::Document::with_version("1.5");
+        let pages_id = pdf.new_object_id();
+
+        let regular = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let bold = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica-Bold",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let mut images = lopdf::Dictionary::new();
+        for (index, image) in self.images.iter().enumerate() {
+            let id = add_image(&mut pdf, image);
+            images.set(format!("Im{}", index + 1), id);
+        }
+        let resources = pdf.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => regular, "F2" => bold },
+            "XObject" => images,
+        });
+
+        let mut kids = vec![];
+        for operations in self.pages {
+            let content = Content { operations }
+                .encode()
+                .expect("operations only contain encodable objects");
+            let mut stream = Stream::new(dictionary! {}, content);
+            // a failed compression leaves the stream as it is
+            let _ = stream.compress();
+            let content_id = pdf.add_object(stream);
+            let page_id = pdf.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            });
+            kids.push(Object::Reference(page_id));
+        }
+        pdf.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "Resources" => resources,
+                "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+            }),
+        );
+        let catalog_id = pdf.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        pdf.trailer.set("Root", catalog_id);
+
+        if !self.metadata.is_empty() {
+            let mut info = lopdf::Dictionary::new();
+            for (key, value) in self.metadata {
+                info.set(
+                    key,
+                    Object::String(value.into_bytes(), StringFormat::Hexadecimal),
+                );
+            }
+            let info_id = pdf.add_object(info);
+            pdf.trailer.set("Info", info_id);
+        }
+
+        let mut bytes = Vec::new();
+        pdf.save_to(&mut bytes)
+            .expect("writing into a Vec doesn't fail");
+        bytes
+    }
+}
+
+/// Adds the image as an RGB image, with its transparency as a soft mask.
+fn add_image(pdf: &mut lopdf::Document, image: &DynamicImage) -> ObjectId {
+    let image_stream = |color_space: &str, pixels: Vec<u8>| {
+        let mut stream = Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => image.width() as i64,
+                "Height" => image.height() as i64,
+                "ColorSpace" => color_space,
+                "BitsPerComponent" => 8,
+            },
+            pixels,
+        );
+        let _ = stream.compress();
+        stream
+    };
+
+    let mut stream = image_stream("DeviceRGB", image.to_rgb8().into_raw());
+    if image.color().has_alpha() {
+        let alpha = image.to_rgba8().pixels().map(|pixel| pixel.0[3]).collect();
+        let mask = pdf.add_object(image_stream("DeviceGray", alpha));
+        stream.dict.set("SMask", mask);
+    }
+    pdf.add_object(stream)
+}
+
+/// Finds a value that [`Document::metadata`] stored in a PDF file.
+pub fn find_metadata(pdf: &[u8], key: &str) -> Option<String> {
+    let document = lopdf::Document::load_mem(pdf).ok()?;
+    let info = document.trailer.get(b"Info").ok()?;
+    let (_, info) = document.dereference(info).ok()?;
+    let value = info.as_dict().ok()?.get(key.as_bytes()).ok()?;
+    String::from_utf8(value.as_str().ok()?.to_vec()).ok()
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn unhex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Maps the text to the WinAnsi encoding of the standard fonts. Characters it doesn't have become
+/// `?`.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// A rough width of the text, good enough to wrap lines without the font metrics.
+fn width(text: &str, font: Font, size: f32) -> f32 {
+    let em = text
+        .chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '\'' | '!' | '|' | ' ' => 0.28,
+            'f' | 't' | 'r' | '(' | ')' | '-' => 0.36,
+            'm' | 'w' | 'M' | 'W' => 0.85,
+            'A'..='Z' => 0.68,
+            _ => 0.56,
+        })
+        .sum::<f32>();
+    let bold = if font == Font::Bold { 1.06 } else { 1.0 };
+    em * size * bold
+}
+
+/// Splits the text into lines that fit the width, at spaces where possible.
+fn wrap(text: &str, font: Font, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{line} {word}")
+        };
+        if width(&candidate, font, size) <= max_width || line.is_empty() {
+            line = candidate;
+        } else {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        }
+        // a single word that is too long is split anywhere
+        while width(&line, font, size) > max_width && line.chars().count() > 1 {
+            let mut split = line.chars().count() - 1;
+            while split > 1
+                && width(&line.chars().take(split).collect::<String>(), font, size) > max_width
+            {
+                split -= 1;
+            }
+            let rest = line.chars().skip(split).collect::<String>();
+            lines.push(line.chars().take(split).collect());
+            line = rest;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// The verdict of a closed lawsuit as a printable document, headed by the name of the court and
+/// stamped with its seal. Mentions of the people in the lawsuit are replaced by their names, an
+/// anonymous plaintiff stays anonymous.
+pub fn verdict_record(
+    lawsuit: &Lawsuit,
+    court: &str,
+    seal: Option<DynamicImage>,
+    names: &HashMap<SnowflakeId, String>,
+    tz: Tz,
+) -> Document {
+    let name = |id: SnowflakeId| names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+    let resolve = |text: &str| {
+        let mut text = text.to_string();
+        if lawsuit.anonymous {
+            text = text.replace(&format!("<@{}>", lawsuit.plaintiff), "Anonym");
+        }
+        names.iter().fold(text, |text, (id, name)| {
+            text.replace(&format!("<@{id}>"), name)
+                .replace(&format!("<@!{id}>"), name)
+        })
+    };
+    let represented = |party: String, lawyer: Option<SnowflakeId>| match lawyer {
+        Some(lawyer) => format!("{party}, vertreten durch {}", name(lawyer)),
+        None => party,
+    };
+    let plaintiff = if lawsuit.anonymous {
+        "Anonym".to_string()
+    } else {
+        name(lawsuit.plaintiff)
+    };
+
+    let mut document = Document::default();
+    document.text(court, Font::Bold, 14.0);
+    document.text(&format!("Prozess #{}", lawsuit.number), Font::Regular, 11.0);
+    document.space(24.0);
+    document.text("Urteil", Font::Bold, 26.0);
+    document.space(12.0);
+
+    let mut section = |title: &str, text: &str| {
+        document.space(10.0);
+        document.text(title, Font::Bold, 12.0);
+        document.text(text, Font::Regular, 11.0);
+    };
+    section(
+        "Parteien",
+        &[
+            format!(
+                "Kläger: {}",
+                represented(plaintiff, lawsuit.plaintiff_lawyer)
+            ),
+            format!(
+                "Angeklagter: {}",
+                represented(name(lawsuit.accused), lawsuit.accused_lawyer)
+            ),
+            format!("Richter: {}", name(lawsuit.judge)),
+        ]
+        .join("\n"),
+    );
+    section("Klage", &resolve(&lawsuit.reason));
+    section(
+        "Urteil",
+        &resolve(lawsuit.verdict.as_deref().unwrap_or_default()),
+    );
+
+    let mut sentences = vec![];
+    if let Some(winner) = lawsuit.winner {
+        sentences.push(format!("Gewinner: {winner}"));
+    }
+    if let Some(damages) = lawsuit.damages {
+        sentences.push(format!("Schadenersatz: {}", format_amount(damages)));
+    }
+    if let Some(hours) = lawsuit.community_service {
+        sentences.push(format!("Gemeinnützige Arbeit: {hours} Stunden"));
+    }
+    if let Some(prison) = lawsuit.prison_sentence() {
+        sentences.push(format!("Gefängnis: {prison}"));
+    }
+    if !sentences.is_empty() {
+        section("Entscheid", &sentences.join("\n"));
+    }
+
+    document.space(36.0);
+    if let Some(closed_at) = lawsuit.closed_time() {
+        let date = tz.timestamp_millis(closed_at.timestamp_millis());
+        document.text(
+            &format!("Gegeben am {}", date.format("%d.%m.%Y")),
+            Font::Regular,
+            11.0,
+        );
+    }
+    document.text(
+        &format!("Für das Gericht: {}", name(lawsuit.judge)),
+        Font::Regular,
+        11.0,
+    );
+    if let Some(seal) = seal {
+        document.space(12.0);
+        document.image(seal, SEAL_SIZE);
+    }
+
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono_tz::Europe::Zurich;
+    use image::{DynamicImage, RgbaImage};
+
+    use super::{encode, find_metadata, verdict_record, wrap, Document, Font};
+    use crate::{lawsuit::Lawsuit, model::SnowflakeId};
+
+    /// The text of all pages, as drawn by the content streams.
+    fn page_texts(pdf: &[u8]) -> Vec<String> {
+        let document = lopdf::Document::load_mem(pdf).unwrap();
+        document
+            .get_pages()
+            .into_keys()
+            .map(|page| document.extract_text(&[page]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(encode("Prozess – Fr. 50"), b"Prozess \x96 Fr. 50");
+        assert_eq!(encode("Zürich 🎲"), b"Z\xfcrich ?");
+    }
+
+    #[test]
+    fn wrapping() {
+        let text = "wort ".repeat(100);
+        let lines = wrap(&text, Font::Regular, 11.0, 200.0);
+        assert!(lines.len() > 5);
+        assert!(lines
+            .iter()
+            .all(|line| line.starts_with("wort") && line.ends_with("wort")));
+
+        let long = "a".repeat(200);
+        let lines = wrap(&long, Font::Regular, 11.0, 200.0);
+        assert_eq!(lines.concat(), long);
+        assert!(wrap("", Font::Regular, 11.0, 200.0).is_empty());
+    }
+
+    #[test]
+    fn pages() {
+        let mut document = Document::default();
+        // about 44 lines fit on a page
+        for _ in 0..60 {
+            document.text("Zeile", Font::Regular, 12.0);
+        }
+        let pdf = document.finish();
+
+        let pages = page_texts(&pdf);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages.concat().matches("Zeile").count(), 60);
+    }
+
+    #[test]
+    fn metadata() {
+        let mut document = Document::default();
+        document.metadata("CourtRecord", "{\"verdict\": \"schuldig (>_<)\"}");
+        let pdf = document.finish();
+        assert_eq!(
+            find_metadata(&pdf, "CourtRecord").as_deref(),
+            Some("{\"verdict\": \"schuldig (>_<)\"}")
+        );
+        assert_eq!(find_metadata(&pdf, "Signature"), None);
+        assert_eq!(find_metadata(b"kei pdf", "CourtRecord"), None);
+    }
+
+    #[test]
+    fn verdict() {
+        let mut lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "Spam im <#5>".to_string(),
+        );
+        lawsuit.verdict = Some("<@2> muess sich entschuldige".to_string());
+        let names = HashMap::from([
+            (SnowflakeId(1), "Kim".to_string()),
+            (SnowflakeId(2), "Noa".to_string()),
+        ]);
+
+        let pdf = verdict_record(&lawsuit, "Obergericht", None, &names, Zurich).finish();
+        let text = page_texts(&pdf).concat();
+        assert!(text.contains("Obergericht"));
+        assert!(text.contains("Noa muess sich entschuldige"));
+        assert!(text.contains("Richter: 3"));
+    }
+
+    #[test]
+    fn seal() {
+        let lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "Spam".to_string(),
+        );
+        let seal = DynamicImage::ImageRgba8(RgbaImage::new(4, 2));
+
+        let pdf =
+            verdict_record(&lawsuit, "Obergericht", Some(seal), &HashMap::new(), Zurich).finish();
+
+        let document = lopdf::Document::load_mem(&pdf).unwrap();
+        let image = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .find(|stream| {
+                stream.dict.get(b"Subtype").and_then(|s| s.as_name()).ok() == Some(b"Image")
+                    && stream.dict.has(b"SMask")
+            })
+            .expect("the seal is embedded with its transparency");
+        assert_eq!(image.dict.get(b"Width").unwrap().as_i64().unwrap(), 4);
+        assert_eq!(image.dict.get(b"Height").unwrap().as_i64().unwrap(), 2);
+    }
+}
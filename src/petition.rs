@@ -0,0 +1,181 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::Result;
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, Http, UserId};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{model::SnowflakeId, Mongo, WrapErr};
+
+/// The prefix of the custom id of the buttons that decide a petition, followed by
+/// `{petition}:{grant}`.
+pub const PETITION_ID_PREFIX: &str = "petition:";
+
+/// How long a prisoner has to wait after a petition before filing the next one.
+pub const PETITION_COOLDOWN: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// The longest petition, so it fits into the embed with room to spare.
+pub const PETITION_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PetitionStatus {
+    Pending,
+    Granted,
+    Denied,
+}
+
+/// A request or complaint of a prisoner to the wardens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Petition {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    pub text: String,
+    pub filed_at: DateTime,
+    pub status: PetitionStatus,
+    pub decided_by: Option<SnowflakeId>,
+}
+
+/// When the prisoner can file the next petition, or `None` if they can right away.
+pub fn next_petition(last: Option<&Petition>, now: DateTime) -> Option<DateTime> {
+    let next = last?.filed_at.timestamp_millis() + PETITION_COOLDOWN.as_millis() as i64;
+    (next > now.timestamp_millis()).then(|| DateTime::from_millis(next))
+}
+
+/// Parses the `{petition}:{grant}` part of a decision custom id.
+pub fn parse_decision_id(id: &str) -> Option<(Uuid, bool)> {
+    let (petition_id, grant) = id.split_once(':')?;
+    Some((Uuid::parse_str(petition_id).ok()?, grant.parse().ok()?))
+}
+
+/// Stores the petition and posts it in the warden channel to be decided.
+#[tracing::instrument(skip(mongo, http, petition), fields(petition_id = %petition.id))]
+pub async fn file(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    warden_channel: SnowflakeId,
+    petition: &Petition,
+) -> Result<()> {
+    mongo.add_petition(petition).await?;
+
+    ChannelId::from(warden_channel)
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title("Gsuech vomene Gfangene")
+                    .description(&petition.text)
+                    .field("Vo", format!("<@{}>", petition.user_id), true)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(format!("{PETITION_ID_PREFIX}{}:true", petition.id))
+                            .label("Guetheisse")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|button| {
+                        button
+                            .custom_id(format!("{PETITION_ID_PREFIX}{}:false", petition.id))
+                            .label("Ablehne")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await
+        .wrap_err("send petition")?;
+
+    Ok(())
+}
+
+/// Grants or denies a pending petition, tells the prisoner via DM and logs the decision. Returns
+/// `None` if the petition was decided already.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn decide(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    petition_id: Uuid,
+    decided_by: SnowflakeId,
+    grant: bool,
+) -> Result<Option<Petition>> {
+    let status = match grant {
+        true => PetitionStatus::Granted,
+        false => PetitionStatus::Denied,
+    };
+    let petition = match mongo
+        .decide_petition(petition_id, status, decided_by)
+        .await?
+    {
+        Some(petition) => petition,
+        None => return Ok(None),
+    };
+    info!(?petition, "Decided petition");
+
+    let decision = match grant {
+        true => "guetgheisse",
+        false => "abglehnt",
+    };
+    let message = format!("dis Gsuech isch {decision} worde:\n> {}", petition.text);
+    let result = match UserId::from(petition.user_id).create_dm_channel(http).await {
+        Ok(channel) => channel.say(http, message).await.map(drop),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        warn!(?err, user_id = %petition.user_id, "Could not tell prisoner about the decision");
+    }
+
+    let state = mongo.find_or_insert_state(petition.guild_id).await?;
+    if let Some(log_channel) = state.log_channel {
+        let notice = format!(
+            "<@{decided_by}> hät es Gsuech vo <@{}> {decision}",
+            petition.user_id
+        );
+        if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+            warn!(?err, "Failed to send petition notice");
+        }
+    }
+
+    Ok(Some(petition))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{DateTime, Uuid};
+
+    use super::{next_petition, parse_decision_id, Petition, PetitionStatus, PETITION_COOLDOWN};
+    use crate::model::SnowflakeId;
+
+    #[test]
+    fn cooldown() {
+        let petition = Petition {
+            id: Uuid::new(),
+            guild_id: SnowflakeId(1),
+            user_id: SnowflakeId(2),
+            text: "meh Znacht".to_string(),
+            filed_at: DateTime::from_millis(0),
+            status: PetitionStatus::Pending,
+            decided_by: None,
+        };
+        let cooldown = PETITION_COOLDOWN.as_millis() as i64;
+
+        assert_eq!(next_petition(None, DateTime::from_millis(0)), None);
+        assert_eq!(
+            next_petition(Some(&petition), DateTime::from_millis(cooldown - 1)),
+            Some(DateTime::from_millis(cooldown))
+        );
+        assert_eq!(
+            next_petition(Some(&petition), DateTime::from_millis(cooldown)),
+            None
+        );
+    }
+
+    #[test]
+    fn decision_id() {
+        let id = Uuid::new();
+        assert_eq!(parse_decision_id(&format!("{id}:true")), Some((id, true)));
+        assert_eq!(parse_decision_id("kaputt"), None);
+    }
+}
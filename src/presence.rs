@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use poise::serenity_prelude::{Activity, Context};
+use tracing::{debug, error};
+
+use crate::{config, config::TaskInterval, messages, Mongo};
+
+/// How often the status of the bot is updated.
+const PRESENCE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The status if there is no template in the config. `{open_cases}` and `{prisoners}` are replaced
+/// with the counts of all guilds.
+pub const DEFAULT_TEMPLATE: &str = "⚖️ {open_cases} offeni Fäll | /help";
+
+/// The status when it's disabled in the config.
+const STATIC_STATUS: &str = "für Recht und Ordnung sorgen";
+
+/// Periodically shows the number of open lawsuits as the status of the bot. Never returns.
+pub async fn presence_task(ctx: Context, mongo: Mongo) {
+    let mut interval = TaskInterval::new("presence", PRESENCE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = update(&ctx, &mongo).await {
+            error!(?err, "Error updating presence");
+        }
+    }
+}
+
+#[tracing::instrument(skip(ctx, mongo))]
+async fn update(ctx: &Context, mongo: &Mongo) -> Result<()> {
+    let config = config::get();
+    if config.presence_disabled {
+        ctx.set_activity(Activity::playing(STATIC_STATUS)).await;
+        return Ok(());
+    }
+
+    let open_cases = mongo.count_open_lawsuits().await?;
+    let prisoners = mongo.count_prisoners().await?;
+    let status = render(
+        config
+            .presence_template
+            .as_deref()
+            .unwrap_or(DEFAULT_TEMPLATE),
+        open_cases,
+        prisoners,
+    );
+
+    debug!(%status, "Updating presence");
+    ctx.set_activity(Activity::watching(status)).await;
+
+    Ok(())
+}
+
+fn render(template: &str, open_cases: u64, prisoners: u64) -> String {
+    messages::render(
+        template,
+        &[
+            ("open_cases", open_cases.to_string()),
+            ("prisoners", prisoners.to_string()),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, DEFAULT_TEMPLATE};
+
+    #[test]
+    fn render_counts() {
+        assert_eq!(render(DEFAULT_TEMPLATE, 12, 3), "⚖️ 12 offeni Fäll | /help");
+        assert_eq!(
+            render("{prisoners} im Gfängnis, {open_cases} Fäll", 12, 3),
+            "3 im Gfängnis, 12 Fäll"
+        );
+    }
+}
@@ -0,0 +1,770 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::Result;
+use poise::serenity_prelude::{
+    ChannelId, ChannelType, GuildId, Http, PermissionOverwrite, PermissionOverwriteType,
+    Permissions, RoleId, UserId,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+use crate::{
+    bulk,
+    bulk::{BulkOp, BulkReport},
+    discord_api::DiscordApi,
+    duration::format_duration,
+    handler::Response,
+    messages::MessageKey,
+    model::{SnowflakeId, State},
+    probation, sandbox,
+    sandbox::SimulatedDiscord,
+    scheduler::{Job, JobKind},
+    storage::Storage,
+    webhook,
+    webhook::WebhookEvent,
+    Mongo, WrapErr,
+};
+
+/// What a prison role that the bot creates is denied in every channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum PrisonRestriction {
+    #[name = "Schreiben und Sprechen"]
+    Full,
+    #[name = "Nur Schreiben"]
+    Write,
+    #[name = "Keine"]
+    Unrestricted,
+}
+
+impl PrisonRestriction {
+    pub fn denied(self) -> Permissions {
+        let write = Permissions::SEND_MESSAGES
+            | Permissions::SEND_MESSAGES_IN_THREADS
+            | Permissions::CREATE_PUBLIC_THREADS
+            | Permissions::CREATE_PRIVATE_THREADS
+            | Permissions::ADD_REACTIONS;
+        match self {
+            Self::Full => write | Permissions::SPEAK | Permissions::STREAM,
+            Self::Write => write,
+            Self::Unrestricted => Permissions::empty(),
+        }
+    }
+}
+
+/// Keeps prisoners out of the voice channels, which the prison role alone often doesn't because
+/// the voice channels have their own permissions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrisonVoice {
+    /// The only voice channel prisoners can join. Prisoners who are connected are moved there on
+    /// arrest, without a cell they are disconnected.
+    pub cell_channel: Option<SnowflakeId>,
+}
+
+/// The permission changes that keep the prisoner out of the voice channels except the cell, or
+/// that undo them on release.
+fn voice_ops(
+    channels: &[ChannelId],
+    cell: Option<ChannelId>,
+    user_id: UserId,
+    imprisoned: bool,
+) -> Vec<BulkOp> {
+    let kind = PermissionOverwriteType::Member(user_id);
+    channels
+        .iter()
+        .map(|&channel_id| match imprisoned {
+            true => {
+                let (allow, deny) = match Some(channel_id) == cell {
+                    true => (Permissions::CONNECT, Permissions::empty()),
+                    false => (Permissions::empty(), Permissions::CONNECT),
+                };
+                BulkOp::SetPermission {
+                    channel_id,
+                    overwrite: PermissionOverwrite { allow, deny, kind },
+                }
+            }
+            false => BulkOp::RemovePermission { channel_id, kind },
+        })
+        .collect()
+}
+
+/// Denies or restores the voice channels of the prisoner, if the guild keeps prisoners out of
+/// voice. On arrest, a connected prisoner is moved to the cell or disconnected.
+async fn restrict_voice(
+    http: &Http,
+    state: &State,
+    user_id: UserId,
+    imprisoned: bool,
+) -> Result<()> {
+    let voice = match &state.prison_voice {
+        Some(voice) => voice,
+        None => return Ok(()),
+    };
+    let guild_id = GuildId::from(state.guild_id);
+    let cell = voice.cell_channel.map(ChannelId::from);
+
+    let channels = guild_id
+        .channels(http)
+        .await
+        .wrap_err("fetch channels")?
+        .into_values()
+        .filter(|channel| matches!(channel.kind, ChannelType::Voice | ChannelType::Stage))
+        .map(|channel| channel.id)
+        .collect::<Vec<_>>();
+    let report = bulk::run(
+        http,
+        guild_id,
+        voice_ops(&channels, cell, user_id, imprisoned),
+    )
+    .await;
+    report.log_failures("prison voice");
+
+    if !imprisoned {
+        return Ok(());
+    }
+    let change = match cell {
+        Some(cell) => format!("<@{user_id}> i d'Zelle <#{cell}> verschobe"),
+        None => format!("<@{user_id}> us em Sprachkanal gworfe"),
+    };
+    if sandbox::simulate(http, guild_id, change).await {
+        return Ok(());
+    }
+    let result = guild_id
+        .edit_member(http, user_id, |member| match cell {
+            Some(cell) => member.voice_channel(cell),
+            None => member.disconnect_member(),
+        })
+        .await;
+    if let Err(err) = result {
+        // Discord refuses to move members who aren't connected
+        debug!(?err, %user_id, "Did not move prisoner in voice");
+    }
+
+    Ok(())
+}
+
+/// What happened when the prison role was brought into the intended state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoleChange {
+    Changed,
+    /// The member already had the intended roles.
+    Unchanged,
+    /// The member isn't in the guild. The role is given back by the rejoin checks.
+    NotMember,
+}
+
+/// The result of an arrest or release that did something.
+#[derive(Debug, Clone)]
+pub struct PrisonChange {
+    pub state: State,
+    /// Whether the prison entry was added or removed. `false` if only the role was fixed.
+    pub entry_changed: bool,
+}
+
+/// Puts someone into prison and gives them the prison role. Arresting someone who is already in
+/// prison only gives them the role again if it's missing.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn arrest(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Result<(), Response>> {
+    let result = match sandbox::report_channel(guild_id) {
+        Some(_) => imprison(mongo, &SimulatedDiscord::new(http), guild_id, user_id).await?,
+        None => imprison(mongo, http.as_ref(), guild_id, user_id).await?,
+    };
+    let change = match result {
+        Ok(change) => change,
+        Err(response) => return Ok(Err(response)),
+    };
+
+    restrict_voice(http, &change.state, user_id, true).await?;
+
+    if change.entry_changed {
+        let data = json!({ "user": user_id.to_string() });
+        webhook::dispatch(http, &change.state, WebhookEvent::Arrest, data);
+        probation::violated(mongo, guild_id.into(), user_id.into()).await?;
+    }
+
+    Ok(Ok(()))
+}
+
+/// The job that releases the prisoner at the end of their sentence, if they have one.
+pub async fn release_job(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    user_id: SnowflakeId,
+) -> Result<Option<Job>> {
+    let jobs = mongo.find_jobs(Some(guild_id)).await?;
+    Ok(jobs.into_iter().find(|job| {
+        !job.failed && matches!(job.kind, JobKind::Release { user_id: id } if id == user_id)
+    }))
+}
+
+/// The values of the placeholders of the arrest notice.
+pub fn arrest_notice_args(
+    user_id: SnowflakeId,
+    reason: Option<&str>,
+    duration: Option<Duration>,
+    case: Option<u64>,
+) -> [(&'static str, String); 4] {
+    [
+        ("user", format!("<@{user_id}>")),
+        ("reason", reason.unwrap_or("kein Grund aagä").to_string()),
+        (
+            "duration",
+            duration.map_or("unbefristet".to_string(), format_duration),
+        ),
+        (
+            "case",
+            case.map_or("kein Prozess".to_string(), |number| format!("#{number}")),
+        ),
+    ]
+}
+
+/// Tells the arrested member why, for how long and in which case they were arrested, if the guild
+/// turned the arrest notice on. A DM that can't be delivered is only logged, the arrest stands.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn notify_arrest(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: SnowflakeId,
+    user_id: SnowflakeId,
+    reason: Option<&str>,
+    duration: Option<Duration>,
+    case: Option<u64>,
+) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    if !state.arrest_notice {
+        return Ok(());
+    }
+
+    let args = arrest_notice_args(user_id, reason, duration, case);
+    let message = state.message(MessageKey::ArrestNotice, &args);
+    let result = match UserId::from(user_id).create_dm_channel(http).await {
+        Ok(channel) => channel.say(http, message).await.map(drop),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        warn!(?err, %user_id, "Could not send arrest notice");
+        if let Some(log_channel) = state.log_channel {
+            let notice = format!("<@{user_id}> het d'Nachricht zur Verhaftig nöd übercho");
+            if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+                warn!(?err, "Failed to send arrest notice failure");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Releases someone from prison and removes the prison role. Releasing someone who isn't in
+/// prison only removes a leftover prison role.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn release(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Result<(), Response>> {
+    let result = match sandbox::report_channel(guild_id) {
+        Some(_) => set_free(mongo, &SimulatedDiscord::new(http), guild_id, user_id).await?,
+        None => set_free(mongo, http.as_ref(), guild_id, user_id).await?,
+    };
+    let change = match result {
+        Ok(change) => change,
+        Err(response) => return Ok(Err(response)),
+    };
+
+    restrict_voice(http, &change.state, user_id, false).await?;
+
+    if change.entry_changed {
+        let data = json!({ "user": user_id.to_string() });
+        webhook::dispatch(http, &change.state, WebhookEvent::Release, data);
+    }
+
+    Ok(Ok(()))
+}
+
+/// Creates the prison role directly under the highest role of the bot, so that the bot can give
+/// it, denies it the permissions of the restriction in every channel and stores it. The report
+/// contains the channels whose permissions couldn't be set.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn create_role(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    bot_id: UserId,
+    name: String,
+    color: Option<u32>,
+    restriction: PrisonRestriction,
+) -> Result<(RoleId, BulkReport)> {
+    if sandbox::simulate(http, guild_id, format!("Gfängnisrolle `{name}` erstellt")).await {
+        // like for simulated court rooms, the @everyone role stands in for the new role
+        let role_id = RoleId(guild_id.0);
+        mongo
+            .set_prison_role(guild_id.into(), role_id.into())
+            .await?;
+        return Ok((
+            role_id,
+            BulkReport {
+                results: Vec::new(),
+            },
+        ));
+    }
+
+    let role = guild_id
+        .create_role(http, |role| {
+            role.name(name).permissions(Permissions::empty());
+            if let Some(color) = color {
+                role.colour(color.into());
+            }
+            role
+        })
+        .await
+        .wrap_err("create prison role")?;
+
+    // the new role is at the bottom, which moved the role of the bot up
+    if let Some(bot_position) = highest_role_position(http, guild_id, bot_id).await? {
+        guild_id
+            .edit_role_position(http, role.id, position_below(bot_position))
+            .await
+            .wrap_err("move prison role")?;
+    }
+
+    mongo
+        .set_prison_role(guild_id.into(), role.id.into())
+        .await?;
+
+    let denied = restriction.denied();
+    let ops = if denied.is_empty() {
+        Vec::new()
+    } else {
+        guild_id
+            .channels(http)
+            .await
+            .wrap_err("fetch channels")?
+            .into_keys()
+            .map(|channel_id| BulkOp::SetPermission {
+                channel_id,
+                overwrite: PermissionOverwrite {
+                    allow: Permissions::empty(),
+                    deny: denied,
+                    kind: PermissionOverwriteType::Role(role.id),
+                },
+            })
+            .collect()
+    };
+    let report = bulk::run(http, guild_id, ops).await;
+
+    info!(role_id = %role.id, failures = report.failures().count(), "Created prison role");
+
+    Ok((role.id, report))
+}
+
+/// The highest position of the roles of the member, `None` if they have no roles.
+pub async fn highest_role_position(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<i64>> {
+    let member = guild_id
+        .member(http, user_id)
+        .await
+        .wrap_err("fetch member")?;
+    let roles = guild_id.roles(http).await.wrap_err("fetch roles")?;
+    Ok(member
+        .roles
+        .iter()
+        .filter_map(|role_id| roles.get(role_id))
+        .map(|role| role.position)
+        .max())
+}
+
+/// Whether the bot can't give the role, because it isn't below the highest role of the bot.
+pub fn is_out_of_reach(role_position: i64, bot_position: Option<i64>) -> bool {
+    bot_position.is_none_or(|bot_position| role_position >= bot_position)
+}
+
+fn position_below(position: i64) -> u64 {
+    (position - 1).max(1) as u64
+}
+
+/// The part of [`arrest`] that doesn't send webhooks.
+pub async fn imprison(
+    storage: &impl Storage,
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Result<PrisonChange, Response>> {
+    let state = storage.find_or_insert_state(guild_id.into()).await?;
+
+    let role = match state.prison_role {
+        Some(role) => RoleId::from(role),
+        None => {
+            return Ok(Err(Response(
+                "du mosch zerst e rolle setze mit /prison set_role".to_string(),
+            )))
+        }
+    };
+
+    // the entry is the intended state, the role follows it
+    let inserted = storage
+        .add_to_prison(guild_id.into(), user_id.into())
+        .await?;
+    let role_change = set_prison_role(discord, &state, user_id, role, true).await?;
+
+    if !inserted && role_change != RoleChange::Changed {
+        return Ok(Err(Response(format!("<@{user_id}> isch scho igsperrt"))));
+    }
+
+    info!(%guild_id, %user_id, ?role_change, "Arrested user");
+
+    Ok(Ok(PrisonChange {
+        state,
+        entry_changed: inserted,
+    }))
+}
+
+/// The part of [`release`] that doesn't send webhooks.
+pub async fn set_free(
+    storage: &impl Storage,
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Result<PrisonChange, Response>> {
+    let state = storage.find_or_insert_state(guild_id.into()).await?;
+
+    let role = match state.prison_role {
+        Some(role) => RoleId::from(role),
+        None => {
+            return Ok(Err(Response(
+                "du mosch zerst e rolle setze mit /prison set_role".to_string(),
+            )))
+        }
+    };
+
+    let removed = storage
+        .remove_from_prison(guild_id.into(), user_id.into())
+        .await?;
+    if removed {
+        // the end of the sentence must not release them again after a new arrest
+        storage
+            .delete_release_jobs(guild_id.into(), user_id.into())
+            .await?;
+    }
+    let role_change = set_prison_role(discord, &state, user_id, role, false).await?;
+
+    if !removed && role_change != RoleChange::Changed {
+        return Ok(Err(Response(format!("<@{user_id}> isch nöd igsperrt"))));
+    }
+
+    info!(%guild_id, %user_id, ?role_change, "Released user");
+
+    Ok(Ok(PrisonChange {
+        state,
+        entry_changed: removed,
+    }))
+}
+
+/// Gives or removes the prison role if the member doesn't have the intended roles yet, then
+/// checks that the change went through.
+async fn set_prison_role(
+    discord: &impl DiscordApi,
+    state: &State,
+    user_id: UserId,
+    role: RoleId,
+    imprisoned: bool,
+) -> Result<RoleChange> {
+    let guild_id = GuildId::from(state.guild_id);
+
+    let roles = match discord.member_roles(guild_id, user_id).await? {
+        Some(roles) => roles,
+        None => return Ok(RoleChange::NotMember),
+    };
+    if roles.contains(&role) == imprisoned {
+        return Ok(RoleChange::Unchanged);
+    }
+
+    if imprisoned {
+        discord.add_member_role(guild_id, user_id, role).await?;
+    } else {
+        discord.remove_member_role(guild_id, user_id, role).await?;
+    }
+
+    let has_role = discord
+        .member_roles(guild_id, user_id)
+        .await?
+        .is_some_and(|roles| roles.contains(&role));
+    if has_role != imprisoned {
+        report_drift(discord, state, user_id, imprisoned).await;
+    }
+
+    Ok(RoleChange::Changed)
+}
+
+/// Tells the log channel that the prison role doesn't match the prison entry, for example because
+/// someone changed it at the same time or the bot's role is too low.
+async fn report_drift(discord: &impl DiscordApi, state: &State, user_id: UserId, imprisoned: bool) {
+    warn!(guild_id = %state.guild_id, %user_id, imprisoned, "Prison role drifted");
+
+    let log_channel = match state.log_channel {
+        Some(channel) => ChannelId::from(channel),
+        None => return,
+    };
+    let expected = if imprisoned {
+        "isch igsperrt, hät aber d'Gfängnisrolle nöd"
+    } else {
+        "isch freiglah, hät aber no d'Gfängnisrolle"
+    };
+    let notice = format!("<@{user_id}> {expected}, bitte d'Rolle vo Hand aapasse");
+    if let Err(err) = discord.send_message(log_channel, notice).await {
+        warn!(?err, "Failed to send prison drift notice");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{DateTime, Uuid};
+    use poise::serenity_prelude::{ChannelId, GuildId, Permissions, RoleId, UserId};
+
+    use std::time::Duration;
+
+    use super::{
+        arrest_notice_args, imprison, is_out_of_reach, position_below, set_free, voice_ops,
+    };
+    use crate::{
+        bulk::BulkOp,
+        messages::{render, MessageKey},
+        model::{SnowflakeId, State},
+        scheduler::{Job, JobKind},
+        testing::{FakeDiscord, MemoryStorage},
+    };
+
+    const GUILD: GuildId = GuildId(1);
+    const USER: UserId = UserId(2);
+    const PRISON_ROLE: RoleId = RoleId(3);
+    const LOG_CHANNEL: ChannelId = ChannelId(4);
+
+    fn setup() -> (MemoryStorage, FakeDiscord) {
+        let mut state = State::new(GUILD.into());
+        state.prison_role = Some(PRISON_ROLE.into());
+        state.log_channel = Some(LOG_CHANNEL.into());
+
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        (MemoryStorage::with_state(state), discord)
+    }
+
+    #[test]
+    fn role_positions() {
+        assert_eq!(position_below(5), 4);
+        assert_eq!(position_below(1), 1);
+        assert!(!is_out_of_reach(4, Some(5)));
+        assert!(is_out_of_reach(5, Some(5)));
+        assert!(is_out_of_reach(1, None));
+    }
+
+    #[test]
+    fn arrest_notice() {
+        let args = arrest_notice_args(
+            SnowflakeId(2),
+            Some("Diebstahl"),
+            Some(Duration::from_secs(60 * 60 * 36)),
+            Some(7),
+        );
+        assert_eq!(
+            render("{user}: {reason}, {duration}, {case}", &args),
+            "<@2>: Diebstahl, 1d 12h, #7"
+        );
+
+        let args = arrest_notice_args(SnowflakeId(2), None, None, None);
+        let message = render(MessageKey::ArrestNotice.default_template(), &args);
+        assert!(message.contains("Grund: kein Grund aagä\nDauer: unbefristet\nFall: kein Prozess"));
+    }
+
+    #[test]
+    fn voice() {
+        let (lobby, cell) = (ChannelId(10), ChannelId(11));
+
+        let ops = voice_ops(&[lobby, cell], Some(cell), USER, true);
+        let permissions = ops
+            .iter()
+            .map(|op| match op {
+                BulkOp::SetPermission { overwrite, .. } => (overwrite.allow, overwrite.deny),
+                _ => panic!("unexpected op {op:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            permissions,
+            vec![
+                (Permissions::empty(), Permissions::CONNECT),
+                (Permissions::CONNECT, Permissions::empty())
+            ]
+        );
+
+        let ops = voice_ops(&[lobby, cell], Some(cell), USER, false);
+        assert!(ops
+            .iter()
+            .all(|op| matches!(op, BulkOp::RemovePermission { .. })));
+    }
+
+    #[tokio::test]
+    async fn arrest_and_release() {
+        let (storage, discord) = setup();
+
+        let change = imprison(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(change.entry_changed);
+        assert!(storage.prison_entry(GUILD, USER).is_some());
+        assert!(discord.has_role(GUILD, USER, PRISON_ROLE));
+
+        let change = set_free(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(change.entry_changed);
+        assert!(storage.prison_entry(GUILD, USER).is_none());
+        assert!(!discord.has_role(GUILD, USER, PRISON_ROLE));
+    }
+
+    #[tokio::test]
+    async fn release_cancels_the_end_of_the_sentence() {
+        let (storage, discord) = setup();
+        imprison(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+        let release = |user_id: UserId| Job {
+            id: Uuid::new(),
+            guild_id: GUILD.into(),
+            kind: JobKind::Release {
+                user_id: user_id.into(),
+            },
+            due: DateTime::now(),
+            attempts: 0,
+            locked_until: None,
+            last_error: None,
+            failed: false,
+        };
+        let other = release(UserId(99));
+        storage
+            .jobs
+            .lock()
+            .unwrap()
+            .extend([release(USER), other.clone()]);
+
+        set_free(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let jobs = storage.jobs.lock().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, other.id);
+    }
+
+    #[tokio::test]
+    async fn arrest_without_prison_role() {
+        let discord = FakeDiscord::default();
+        let storage = MemoryStorage::with_state(State::new(GUILD.into()));
+
+        let result = imprison(&storage, &discord, GUILD, USER).await.unwrap();
+        assert!(result.is_err());
+        assert!(storage.prison_entry(GUILD, USER).is_none());
+    }
+
+    #[tokio::test]
+    async fn double_arrest() {
+        let (storage, discord) = setup();
+
+        imprison(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+        let result = imprison(&storage, &discord, GUILD, USER).await.unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(discord.role_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn rearrest_gives_missing_role() {
+        let (storage, discord) = setup();
+
+        imprison(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+        discord.add_member(GUILD, USER, &[]);
+
+        let change = imprison(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!change.entry_changed);
+        assert!(discord.has_role(GUILD, USER, PRISON_ROLE));
+    }
+
+    #[tokio::test]
+    async fn release_non_prisoner() {
+        let (storage, discord) = setup();
+
+        let result = set_free(&storage, &discord, GUILD, USER).await.unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(discord.role_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn release_removes_leftover_role() {
+        let (storage, discord) = setup();
+        discord.add_member(GUILD, USER, &[PRISON_ROLE]);
+
+        let change = set_free(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(!change.entry_changed);
+        assert!(!discord.has_role(GUILD, USER, PRISON_ROLE));
+    }
+
+    #[tokio::test]
+    async fn arrest_member_not_in_guild() {
+        let (storage, _) = setup();
+        let discord = FakeDiscord::default();
+
+        let change = imprison(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(change.entry_changed);
+        assert!(storage.prison_entry(GUILD, USER).is_some());
+        assert_eq!(discord.role_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn drift_is_reported() {
+        let (storage, _) = setup();
+        let discord = FakeDiscord {
+            ignore_role_changes: true,
+            ..Default::default()
+        };
+        discord.add_member(GUILD, USER, &[]);
+
+        imprison(&storage, &discord, GUILD, USER)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let messages = discord.messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].0, LOG_CHANNEL);
+    }
+}
@@ -0,0 +1,154 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::Result;
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::{ChannelId, Http};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    duration::format_duration, events, model::SnowflakeId, prison, scheduler, scheduler::JobKind,
+    Mongo,
+};
+
+/// A prison sentence that is suspended on probation. A conviction or arrest before the end of the
+/// probation activates it, otherwise it lapses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Probation {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    /// How long the user is imprisoned once the sentence is activated.
+    pub sentence_secs: u64,
+    pub until: DateTime,
+    /// The lawsuit that handed down the sentence.
+    pub lawsuit_id: Uuid,
+    /// Set once the probation was violated, the activation job then imprisons the user.
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+fn after(time: DateTime, duration: Duration) -> DateTime {
+    DateTime::from_millis(time.timestamp_millis() + duration.as_millis() as i64)
+}
+
+/// Suspends the sentence until the end of the probation and schedules its lapse.
+#[tracing::instrument(skip(mongo))]
+pub async fn start(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    user_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    sentence: Duration,
+    probation: Duration,
+) -> Result<()> {
+    let probation = Probation {
+        id: Uuid::new(),
+        guild_id,
+        user_id,
+        sentence_secs: sentence.as_secs(),
+        until: after(DateTime::now(), probation),
+        lawsuit_id,
+        revoked: false,
+    };
+    mongo.add_probation(&probation).await?;
+
+    let job = JobKind::EndProbation {
+        probation_id: probation.id,
+    };
+    scheduler::schedule(mongo, guild_id, job, probation.until).await?;
+
+    info!(?probation, "Suspended sentence on probation");
+
+    Ok(())
+}
+
+/// Activates the suspended sentences of the user, because they were convicted or arrested during
+/// their probation. The activation itself runs in the scheduler.
+#[tracing::instrument(skip(mongo))]
+pub async fn violated(mongo: &Mongo, guild_id: SnowflakeId, user_id: SnowflakeId) -> Result<()> {
+    let now = DateTime::now();
+
+    for probation in mongo.find_active_probations(guild_id, user_id, now).await? {
+        // another violation might have revoked it in the meantime
+        if !mongo.revoke_probation(probation.id).await? {
+            continue;
+        }
+
+        let job = JobKind::ActivateSentence {
+            probation_id: probation.id,
+        };
+        scheduler::schedule(mongo, guild_id, job, now).await?;
+
+        info!(?probation, "Revoked probation");
+    }
+
+    Ok(())
+}
+
+/// Imprisons the user of a revoked probation and schedules their release after the sentence.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn activate(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: SnowflakeId,
+    probation_id: Uuid,
+) -> Result<()> {
+    let probation = match mongo.find_probation(probation_id).await? {
+        Some(probation) if probation.revoked => probation,
+        _ => return Ok(()),
+    };
+
+    let arrest = prison::arrest(mongo, http, guild_id.into(), probation.user_id.into());
+    let action = format!("Uftrag {}", JobKind::ActivateSentence { probation_id });
+    let sentence = Duration::from_secs(probation.sentence_secs);
+    match events::tracked(mongo, guild_id, None, action, arrest).await? {
+        Ok(()) => {
+            let job = JobKind::Release {
+                user_id: probation.user_id,
+            };
+            let due = after(DateTime::now(), sentence);
+            scheduler::schedule(mongo, guild_id, job, due).await?;
+        }
+        Err(response) => {
+            warn!(%response, ?probation, "Could not activate suspended sentence");
+        }
+    }
+
+    mongo.delete_probation(probation.id).await?;
+
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let number = state
+        .lawsuits
+        .iter()
+        .find(|lawsuit| lawsuit.id == probation.lawsuit_id)
+        .map(|lawsuit| lawsuit.number);
+    if let (Some(log_channel), Some(number)) = (state.log_channel, number) {
+        let notice = format!(
+            "<@{}> hät d'Bewährig vo Prozess #{number} verletzt und mues {} is Gfängnis",
+            probation.user_id,
+            format_duration(sentence)
+        );
+        if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+            warn!(?err, "Failed to send probation notice");
+        }
+    }
+
+    info!(?probation, "Activated suspended sentence");
+
+    Ok(())
+}
+
+/// Lets the sentence lapse at the end of the probation, unless it was activated before.
+#[tracing::instrument(skip(mongo))]
+pub async fn end(mongo: &Mongo, probation_id: Uuid) -> Result<()> {
+    match mongo.find_probation(probation_id).await? {
+        Some(probation) if !probation.revoked => {
+            mongo.delete_probation(probation.id).await?;
+            info!(?probation, "Probation ended");
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
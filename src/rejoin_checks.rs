@@ -0,0 +1,389 @@
+use color_eyre::Result;
+use poise::serenity_prelude::{Context, GuildId, Member, UserId};
+use tracing::{error, info};
+
+use crate::{
+    discord_api::DiscordApi,
+    economy::format_amount,
+    lawsuit::{Lawsuit, LawsuitCtx, LawsuitStatus},
+    model::{SnowflakeId, State},
+    sandbox,
+    sandbox::SimulatedDiscord,
+    storage::Storage,
+    Mongo, WrapErr,
+};
+
+/// Reapplies everything a member lost by leaving the guild. Every check runs even if an earlier
+/// one failed.
+#[tracing::instrument(skip(mongo, ctx, member), fields(user_id = %member.user.id))]
+pub async fn run(mongo: &Mongo, ctx: &Context, member: &Member) -> Result<()> {
+    let state = mongo.find_or_insert_state(member.guild_id.into()).await?;
+
+    match sandbox::report_channel(member.guild_id) {
+        Some(_) => {
+            let discord = SimulatedDiscord::new(&ctx.http);
+            run_checks(mongo, &discord, ctx, member, &state).await;
+        }
+        None => run_checks(mongo, ctx.http.as_ref(), ctx, member, &state).await,
+    }
+
+    Ok(())
+}
+
+async fn run_checks(
+    mongo: &Mongo,
+    discord: &impl DiscordApi,
+    ctx: &Context,
+    member: &Member,
+    state: &State,
+) {
+    let (guild_id, user_id) = (member.guild_id, member.user.id);
+
+    if let Err(err) = check_prison(mongo, discord, guild_id, user_id, state).await {
+        error!(?err, "Failed to check prison on rejoin");
+    }
+
+    match check_lawsuits(discord, guild_id, user_id, state).await {
+        Ok(paused) => {
+            for lawsuit in paused {
+                let mut lawsuit_ctx = LawsuitCtx {
+                    lawsuit,
+                    mongo_client: mongo.clone(),
+                    http: ctx.http.clone(),
+                    guild_id,
+                };
+                let result = lawsuit_ctx.resume(ctx.cache.current_user_id().into()).await;
+                if let Err(err) = result {
+                    error!(?err, "Failed to resume lawsuit on rejoin");
+                }
+            }
+        }
+        Err(err) => error!(?err, "Failed to check lawsuits on rejoin"),
+    }
+
+    match check_debts(mongo, discord, guild_id, user_id, state).await {
+        Ok(Some(total)) => {
+            if let Err(err) = remind_debtor(ctx, member, total).await {
+                error!(?err, "Failed to remind debtor on rejoin");
+            }
+        }
+        Ok(None) => {}
+        Err(err) => error!(?err, "Failed to check debts on rejoin"),
+    }
+}
+
+/// Gives prisoners who tried to escape by leaving the prison role back.
+async fn check_prison(
+    storage: &impl Storage,
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    user_id: UserId,
+    state: &State,
+) -> Result<()> {
+    let prison_entry = match storage
+        .find_prison_entry(guild_id.into(), user_id.into())
+        .await?
+    {
+        Some(prison_entry) => prison_entry,
+        None => return Ok(()),
+    };
+
+    if prison_entry.left_at.is_some() {
+        storage
+            .set_prisoner_left(guild_id.into(), user_id.into(), None)
+            .await?;
+    }
+
+    if let Some(role_id) = state.prison_role {
+        info!("New member was in prison, giving them the prison role");
+
+        discord
+            .add_member_role(guild_id, user_id, role_id.into())
+            .await
+            .wrap_err("add role to member in prison")?;
+    }
+
+    Ok(())
+}
+
+/// Gives back access to the court rooms of open lawsuits. Returns the lawsuits that were paused
+/// because the member left, to be resumed.
+async fn check_lawsuits(
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    user_id: UserId,
+    state: &State,
+) -> Result<Vec<Lawsuit>> {
+    let user_id = SnowflakeId::from(user_id);
+
+    let lawsuits = state.lawsuits.iter().filter(|l| {
+        !l.is_closed() && l.status == LawsuitStatus::Active && l.involved().contains(&user_id)
+    });
+
+    let mut paused = Vec::new();
+    for lawsuit in lawsuits {
+        let room = state
+            .court_rooms
+            .iter()
+            .find(|r| r.channel_id == lawsuit.court_room && r.ongoing_lawsuit);
+
+        // the anonymous plaintiff is involved, but never had access to the court room
+        if let (Some(room), false) = (room, lawsuit.arbitration) {
+            if lawsuit.participants().contains(&user_id) {
+                info!(lawsuit_id = %lawsuit.id, "New member is part of a lawsuit, giving them the court room role");
+
+                discord
+                    .add_member_role(guild_id, user_id.into(), room.role_id.into())
+                    .await
+                    .wrap_err("add court room role to member")?;
+            }
+        }
+
+        if lawsuit.paused && [lawsuit.plaintiff, lawsuit.accused].contains(&user_id) {
+            paused.push(lawsuit.clone());
+        }
+    }
+
+    Ok(paused)
+}
+
+/// Gives members whose unpaid debts are over the threshold the debtor role. Returns their debts if
+/// they are over it.
+async fn check_debts(
+    storage: &impl Storage,
+    discord: &impl DiscordApi,
+    guild_id: GuildId,
+    user_id: UserId,
+    state: &State,
+) -> Result<Option<i64>> {
+    let debtor_role = match &state.debtor_role {
+        Some(debtor_role) => debtor_role,
+        None => return Ok(None),
+    };
+
+    let total = storage
+        .find_debts(guild_id.into(), user_id.into())
+        .await?
+        .iter()
+        .map(|debt| debt.amount)
+        .sum::<i64>();
+
+    if total < debtor_role.threshold {
+        return Ok(None);
+    }
+
+    info!(total, "New member has unpaid debts");
+
+    if let Some(role_id) = debtor_role.role_id {
+        discord
+            .add_member_role(guild_id, user_id, role_id.into())
+            .await
+            .wrap_err("add debtor role to member")?;
+    }
+
+    Ok(Some(total))
+}
+
+async fn remind_debtor(ctx: &Context, member: &Member, total: i64) -> Result<()> {
+    let guild_name = member
+        .guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "em Server".to_string());
+    member
+        .user
+        .dm(&ctx.http, |msg| {
+            msg.content(format!(
+                "Willkomme zrugg uf {guild_name}! Du häsch no {} offeni Schulde",
+                format_amount(total)
+            ))
+        })
+        .await
+        .wrap_err("send debt reminder")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{DateTime, Uuid};
+    use poise::serenity_prelude::{GuildId, RoleId, UserId};
+
+    use super::{check_debts, check_lawsuits, check_prison};
+    use crate::{
+        economy::{Debt, DebtorRole},
+        lawsuit::Lawsuit,
+        model::{CourtRoom, SnowflakeId, State},
+        storage::Storage,
+        testing::{FakeDiscord, MemoryStorage},
+    };
+
+    const GUILD: GuildId = GuildId(1);
+    const USER: UserId = UserId(2);
+    const PRISON_ROLE: RoleId = RoleId(3);
+    const COURT_ROOM_ROLE: RoleId = RoleId(4);
+    const DEBTOR_ROLE: RoleId = RoleId(5);
+    const OTHER: SnowflakeId = SnowflakeId(6);
+    const COURT_ROOM: SnowflakeId = SnowflakeId(7);
+
+    /// A state with an active lawsuit of the user against someone else in a court room.
+    fn state_with_lawsuit(update: impl FnOnce(&mut Lawsuit)) -> State {
+        let mut state = state();
+        let mut lawsuit = Lawsuit::new(USER.into(), OTHER, SnowflakeId(8), "Spam".to_string());
+        lawsuit.court_room = COURT_ROOM;
+        update(&mut lawsuit);
+        state.lawsuits.push(lawsuit);
+        state.court_rooms.push(CourtRoom {
+            channel_id: COURT_ROOM,
+            ongoing_lawsuit: true,
+            role_id: COURT_ROOM_ROLE.into(),
+            category: None,
+        });
+        state
+    }
+
+    fn debt(amount: i64) -> Debt {
+        Debt {
+            id: Uuid::new(),
+            guild_id: GUILD.into(),
+            debtor: USER.into(),
+            creditor: OTHER,
+            amount,
+            reason: "Schadenersatz".to_string(),
+            lawsuit_id: None,
+            created_at: DateTime::now(),
+            garnished_until: None,
+        }
+    }
+
+    fn state() -> State {
+        let mut state = State::new(GUILD.into());
+        state.prison_role = Some(PRISON_ROLE.into());
+        state
+    }
+
+    #[tokio::test]
+    async fn prisoner_gets_role_back() {
+        let state = state();
+        let storage = MemoryStorage::with_state(state.clone());
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        storage
+            .add_to_prison(GUILD.into(), USER.into())
+            .await
+            .unwrap();
+        storage
+            .set_prisoner_left(GUILD.into(), USER.into(), Some(DateTime::now()))
+            .await
+            .unwrap();
+
+        check_prison(&storage, &discord, GUILD, USER, &state)
+            .await
+            .unwrap();
+
+        assert!(discord.has_role(GUILD, USER, PRISON_ROLE));
+        let entry = storage.prison_entry(GUILD, USER).unwrap();
+        assert!(entry.left_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn non_prisoner_is_left_alone() {
+        let state = state();
+        let storage = MemoryStorage::with_state(state.clone());
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        check_prison(&storage, &discord, GUILD, USER, &state)
+            .await
+            .unwrap();
+
+        assert!(!discord.has_role(GUILD, USER, PRISON_ROLE));
+        assert_eq!(discord.role_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn party_gets_court_room_back() {
+        let state = state_with_lawsuit(|_| {});
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        let paused = check_lawsuits(&discord, GUILD, USER, &state).await.unwrap();
+
+        assert!(discord.has_role(GUILD, USER, COURT_ROOM_ROLE));
+        assert!(paused.is_empty());
+    }
+
+    #[tokio::test]
+    async fn paused_lawsuit_is_resumed() {
+        let state = state_with_lawsuit(|lawsuit| lawsuit.paused = true);
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        let paused = check_lawsuits(&discord, GUILD, USER, &state).await.unwrap();
+
+        assert_eq!(paused.len(), 1);
+        assert_eq!(paused[0].id, state.lawsuits[0].id);
+    }
+
+    #[tokio::test]
+    async fn anonymous_plaintiff_gets_no_court_room() {
+        let state = state_with_lawsuit(|lawsuit| lawsuit.anonymous = true);
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        check_lawsuits(&discord, GUILD, USER, &state).await.unwrap();
+
+        assert_eq!(discord.role_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn closed_lawsuit_gives_no_court_room() {
+        let state = state_with_lawsuit(|lawsuit| lawsuit.verdict = Some("schuldig".to_string()));
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        check_lawsuits(&discord, GUILD, USER, &state).await.unwrap();
+
+        assert_eq!(discord.role_requests(), 0);
+    }
+
+    #[tokio::test]
+    async fn debtor_over_threshold_gets_role() {
+        let mut state = state();
+        state.debtor_role = Some(DebtorRole {
+            threshold: 100,
+            role_id: Some(DEBTOR_ROLE.into()),
+        });
+        let storage = MemoryStorage::with_state(state.clone());
+        storage.debts.lock().unwrap().extend([debt(60), debt(50)]);
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        let total = check_debts(&storage, &discord, GUILD, USER, &state)
+            .await
+            .unwrap();
+
+        assert_eq!(total, Some(110));
+        assert!(discord.has_role(GUILD, USER, DEBTOR_ROLE));
+    }
+
+    #[tokio::test]
+    async fn debtor_under_threshold_is_left_alone() {
+        let mut state = state();
+        state.debtor_role = Some(DebtorRole {
+            threshold: 100,
+            role_id: Some(DEBTOR_ROLE.into()),
+        });
+        let storage = MemoryStorage::with_state(state.clone());
+        storage.debts.lock().unwrap().push(debt(99));
+        let discord = FakeDiscord::default();
+        discord.add_member(GUILD, USER, &[]);
+
+        let total = check_debts(&storage, &discord, GUILD, USER, &state)
+            .await
+            .unwrap();
+
+        assert_eq!(total, None);
+        assert_eq!(discord.role_requests(), 0);
+    }
+}
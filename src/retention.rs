@@ -0,0 +1,375 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use color_eyre::Result;
+use mongodb::bson::{doc, DateTime, Uuid};
+use poise::serenity_prelude::{ChannelId, Http};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::TaskInterval,
+    lawsuit::Lawsuit,
+    maintenance::Maintenance,
+    model::{PrisonEntry, SnowflakeId, State},
+    sandbox, Mongo,
+};
+
+/// How often the retention task checks for old data.
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long after the dry run report the first purge happens, so that it can still be changed.
+pub const DRY_RUN_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How many case numbers are listed in a report.
+const REPORT_LIMIT: usize = 20;
+
+/// How long the data of a guild is kept, by kind of data. `None` keeps it forever.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    /// The statements and history of closed lawsuits.
+    pub transcripts_days: Option<u32>,
+    /// The prison entries of prisoners who left the guild.
+    pub prisoners_days: Option<u32>,
+    /// Closed lawsuits as a whole.
+    pub closed_cases_days: Option<u32>,
+    /// When the dry run report was made. Nothing is purged before [`DRY_RUN_GRACE`] after it.
+    #[serde(default)]
+    pub dry_run_at: Option<DateTime>,
+}
+
+impl Retention {
+    pub fn is_enabled(&self) -> bool {
+        self.transcripts_days.is_some()
+            || self.prisoners_days.is_some()
+            || self.closed_cases_days.is_some()
+    }
+
+    /// When the first purge happens, if the dry run report was made already.
+    pub fn first_purge(&self) -> Option<DateTime> {
+        self.dry_run_at
+            .map(|at| DateTime::from_millis(at.timestamp_millis() + millis(DRY_RUN_GRACE)))
+    }
+}
+
+/// The data that is older than the retention allows.
+#[derive(Debug, Default)]
+pub struct Purge<'a> {
+    /// Lawsuits whose statements and history are cleared.
+    pub transcripts: Vec<&'a Lawsuit>,
+    pub cases: Vec<&'a Lawsuit>,
+    pub prisoners: Vec<&'a PrisonEntry>,
+    /// Lawsuits that would be purged but are kept because they are under appeal.
+    pub exempt: Vec<&'a Lawsuit>,
+}
+
+impl<'a> Purge<'a> {
+    pub fn plan(
+        retention: &Retention,
+        lawsuits: &'a [Lawsuit],
+        prisoners: &'a [PrisonEntry],
+        now: DateTime,
+    ) -> Self {
+        let is_older = |time: Option<DateTime>, days: Option<u32>| match (time, days) {
+            (Some(time), Some(days)) => time < cutoff(now, days),
+            _ => false,
+        };
+
+        let mut purge = Self::default();
+
+        for lawsuit in lawsuits {
+            let closed_at = lawsuit.closed_time();
+            let delete = is_older(closed_at, retention.closed_cases_days);
+            let clear = is_older(closed_at, retention.transcripts_days)
+                && !(lawsuit.statements.is_empty() && lawsuit.history.is_empty());
+
+            if (delete || clear) && lawsuit.is_under_appeal(lawsuits) {
+                purge.exempt.push(lawsuit);
+            } else if delete {
+                purge.cases.push(lawsuit);
+            } else if clear {
+                purge.transcripts.push(lawsuit);
+            }
+        }
+
+        purge.prisoners = prisoners
+            .iter()
+            .filter(|entry| is_older(entry.left_at, retention.prisoners_days))
+            .collect();
+
+        purge
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transcripts.is_empty() && self.cases.is_empty() && self.prisoners.is_empty()
+    }
+}
+
+impl Display for Purge<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            writeln!(f, "- nüt")?;
+        }
+        if !self.transcripts.is_empty() {
+            writeln!(f, "- Protokoll vo {}", numbers(&self.transcripts))?;
+        }
+        if !self.cases.is_empty() {
+            writeln!(f, "- Prozess {}", numbers(&self.cases))?;
+        }
+        if !self.prisoners.is_empty() {
+            writeln!(
+                f,
+                "- {} Gfangeni, wo de Server verlah händ",
+                self.prisoners.len()
+            )?;
+        }
+        if !self.exempt.is_empty() {
+            writeln!(f, "Wäg Berufig bhalte: {}", numbers(&self.exempt))?;
+        }
+        Ok(())
+    }
+}
+
+fn numbers(lawsuits: &[&Lawsuit]) -> String {
+    let mut numbers = lawsuits
+        .iter()
+        .take(REPORT_LIMIT)
+        .map(|lawsuit| format!("#{}", lawsuit.number))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if lawsuits.len() > REPORT_LIMIT {
+        numbers.push_str(&format!(" und {} witeri", lawsuits.len() - REPORT_LIMIT));
+    }
+    numbers
+}
+
+fn millis(duration: Duration) -> i64 {
+    duration.as_millis() as i64
+}
+
+fn cutoff(now: DateTime, days: u32) -> DateTime {
+    DateTime::from_millis(now.timestamp_millis() - i64::from(days) * 24 * 60 * 60 * 1000)
+}
+
+/// Periodically purges data that is older than the retention of its guild. Never returns.
+pub async fn retention_task(mongo: Mongo, http: Arc<Http>, maintenance: Maintenance) {
+    let mut interval = TaskInterval::new("retention", RETENTION_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if maintenance.is_active() {
+            continue;
+        }
+
+        if let Err(err) = purge_all(&mongo, &http).await {
+            error!(?err, "Error purging old data");
+        }
+    }
+}
+
+#[tracing::instrument(skip(mongo, http))]
+pub async fn purge_all(mongo: &Mongo, http: &Http) -> Result<()> {
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        let now = DateTime::now();
+
+        for state in mongo.find_states_with_retention().await? {
+            if sandbox::report_channel(state.guild_id.into()).is_some() {
+                continue;
+            }
+            if let Err(err) = purge_guild(mongo, http, &state, now).await {
+                error!(?err, guild_id = %state.guild_id, "Error purging old data of guild");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes the dry run report if there was none yet, or purges the old data once the grace period
+/// after it is over.
+async fn purge_guild(mongo: &Mongo, http: &Http, state: &State, now: DateTime) -> Result<()> {
+    let retention = match &state.retention {
+        Some(retention) if retention.is_enabled() => retention,
+        _ => return Ok(()),
+    };
+    let prisoners = mongo.find_prisoners(state.guild_id).await?;
+    let purge = Purge::plan(retention, &state.lawsuits, &prisoners, now);
+
+    let first_purge = match retention.first_purge() {
+        Some(first_purge) => first_purge,
+        None => {
+            mongo.set_retention_dry_run(state.guild_id, now).await?;
+            let first_purge = DateTime::from_millis(now.timestamp_millis() + millis(DRY_RUN_GRACE));
+            info!(guild_id = %state.guild_id, ?purge, "Made retention dry run");
+            notify(
+                http,
+                state,
+                format!(
+                    "Testlauf vo de Datenufbewahrig, ab <t:{}:f> würd das glöscht:\n{purge}",
+                    first_purge.timestamp_millis() / 1000
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+    };
+
+    if now < first_purge || purge.is_empty() {
+        return Ok(());
+    }
+
+    for lawsuit in &purge.transcripts {
+        mongo
+            .set_lawsuit(
+                state.guild_id,
+                lawsuit.id,
+                doc! { "lawsuits.$.statements": [], "lawsuits.$.history": [] },
+            )
+            .await?;
+    }
+    let case_ids = purge.cases.iter().map(|l| l.id).collect::<Vec<Uuid>>();
+    mongo.delete_lawsuits(state.guild_id, &case_ids).await?;
+    let user_ids = purge
+        .prisoners
+        .iter()
+        .map(|entry| entry.user_id)
+        .collect::<Vec<SnowflakeId>>();
+    mongo
+        .delete_prison_entries(state.guild_id, &user_ids)
+        .await?;
+
+    info!(
+        guild_id = %state.guild_id,
+        transcripts = purge.transcripts.len(),
+        cases = purge.cases.len(),
+        prisoners = purge.prisoners.len(),
+        "Purged old data"
+    );
+    notify(
+        http,
+        state,
+        format!("Alti Date sind glöscht worde:\n{purge}"),
+    )
+    .await;
+
+    Ok(())
+}
+
+async fn notify(http: &Http, state: &State, notice: String) {
+    if let Some(log_channel) = state.log_channel {
+        if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+            warn!(?err, "Failed to send retention notice");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::DateTime;
+
+    use super::{cutoff, Purge, Retention};
+    use crate::{
+        lawsuit::{Lawsuit, LawsuitStatus},
+        model::{PrisonEntry, SnowflakeId},
+    };
+
+    fn lawsuit(number: u64, closed_days_ago: Option<u32>, now: DateTime) -> Lawsuit {
+        let lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "test".to_string(),
+        );
+        Lawsuit {
+            number,
+            status: match closed_days_ago {
+                Some(_) => LawsuitStatus::Withdrawn,
+                None => LawsuitStatus::Active,
+            },
+            closed_at: closed_days_ago.map(|days| cutoff(now, days)),
+            ..lawsuit
+        }
+    }
+
+    fn numbers(lawsuits: &[&Lawsuit]) -> Vec<u64> {
+        lawsuits.iter().map(|lawsuit| lawsuit.number).collect()
+    }
+
+    #[test]
+    fn closed_cases() {
+        let now = DateTime::now();
+        let retention = Retention {
+            closed_cases_days: Some(30),
+            ..Default::default()
+        };
+        let lawsuits = [
+            lawsuit(1, Some(40), now),
+            lawsuit(2, Some(10), now),
+            lawsuit(3, None, now),
+        ];
+
+        let purge = Purge::plan(&retention, &lawsuits, &[], now);
+        assert_eq!(numbers(&purge.cases), [1]);
+        assert!(purge.transcripts.is_empty());
+    }
+
+    #[test]
+    fn appeals_are_exempt() {
+        let now = DateTime::now();
+        let retention = Retention {
+            closed_cases_days: Some(30),
+            ..Default::default()
+        };
+        let appeal = Lawsuit {
+            appeal_of: Some(1),
+            ..lawsuit(3, None, now)
+        };
+        let closed_appeal = Lawsuit {
+            appeal_of: Some(2),
+            ..lawsuit(4, Some(5), now)
+        };
+        let lawsuits = [
+            lawsuit(1, Some(40), now),
+            lawsuit(2, Some(40), now),
+            appeal,
+            closed_appeal,
+        ];
+
+        let purge = Purge::plan(&retention, &lawsuits, &[], now);
+        assert_eq!(numbers(&purge.cases), [2]);
+        assert_eq!(numbers(&purge.exempt), [1]);
+    }
+
+    #[test]
+    fn prisoners_who_left() {
+        let now = DateTime::now();
+        let retention = Retention {
+            prisoners_days: Some(7),
+            ..Default::default()
+        };
+        let entry = |user_id, left_at| PrisonEntry {
+            guild_id: SnowflakeId(1),
+            user_id: SnowflakeId(user_id),
+            arrested_at: None,
+            left_at,
+        };
+        let prisoners = [
+            entry(2, Some(cutoff(now, 8))),
+            entry(3, Some(cutoff(now, 1))),
+            entry(4, None),
+        ];
+
+        let purge = Purge::plan(&retention, &[], &prisoners, now);
+        let purged = purge
+            .prisoners
+            .iter()
+            .map(|entry| entry.user_id)
+            .collect::<Vec<_>>();
+        assert_eq!(purged, [SnowflakeId(2)]);
+    }
+}
@@ -0,0 +1,133 @@
+use poise::serenity_prelude::{
+    GuildId, PermissionOverwrite, PermissionOverwriteType, Permissions, RoleId, UserId,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::model::SnowflakeId;
+
+/// Who can see and use the court rooms the bot creates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomPermissions {
+    /// The roles that can read the rooms besides the participants. Everyone can if it's empty.
+    pub readers: Vec<SnowflakeId>,
+    /// Whether members who aren't part of the lawsuit can react to messages.
+    pub spectator_reactions: bool,
+    /// Whether the participants can open threads.
+    pub threads: bool,
+}
+
+impl Default for RoomPermissions {
+    fn default() -> Self {
+        Self {
+            readers: Vec::new(),
+            spectator_reactions: true,
+            threads: true,
+        }
+    }
+}
+
+impl RoomPermissions {
+    /// The permission overwrites of a room whose participants have the role. The default only
+    /// lets the participants write.
+    pub fn overwrites(
+        &self,
+        guild_id: GuildId,
+        room_role: RoleId,
+        bot_id: UserId,
+    ) -> Vec<PermissionOverwrite> {
+        let threads = Permissions::CREATE_PUBLIC_THREADS
+            | Permissions::CREATE_PRIVATE_THREADS
+            | Permissions::SEND_MESSAGES_IN_THREADS;
+
+        let mut participants = Permissions::SEND_MESSAGES;
+        let mut participants_deny = Permissions::empty();
+        let mut everyone_deny = Permissions::empty();
+        if !self.readers.is_empty() {
+            participants |= Permissions::VIEW_CHANNEL;
+            everyone_deny |= Permissions::VIEW_CHANNEL;
+        }
+        if !self.spectator_reactions {
+            participants |= Permissions::ADD_REACTIONS;
+            everyone_deny |= Permissions::ADD_REACTIONS;
+        }
+        if !self.threads {
+            participants_deny |= threads;
+            everyone_deny |= threads;
+        }
+
+        let mut overwrites = vec![PermissionOverwrite {
+            allow: participants,
+            deny: participants_deny,
+            kind: PermissionOverwriteType::Role(room_role),
+        }];
+        if !everyone_deny.is_empty() {
+            // the @everyone role has the id of the guild
+            overwrites.push(PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: everyone_deny,
+                kind: PermissionOverwriteType::Role(RoleId(guild_id.0)),
+            });
+        }
+        if !self.readers.is_empty() {
+            overwrites.push(PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(bot_id),
+            });
+        }
+        overwrites.extend(self.readers.iter().map(|&role| PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(role.into()),
+        }));
+
+        overwrites
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poise::serenity_prelude::{GuildId, PermissionOverwriteType, Permissions, RoleId, UserId};
+
+    use super::RoomPermissions;
+    use crate::model::SnowflakeId;
+
+    const GUILD: GuildId = GuildId(1);
+    const ROOM_ROLE: RoleId = RoleId(2);
+    const BOT: UserId = UserId(3);
+
+    #[test]
+    fn default_only_lets_participants_write() {
+        let overwrites = RoomPermissions::default().overwrites(GUILD, ROOM_ROLE, BOT);
+        assert_eq!(overwrites.len(), 1);
+        assert_eq!(overwrites[0].allow, Permissions::SEND_MESSAGES);
+        assert_eq!(overwrites[0].kind, PermissionOverwriteType::Role(ROOM_ROLE));
+    }
+
+    #[test]
+    fn restricted_rooms() {
+        let permissions = RoomPermissions {
+            readers: vec![SnowflakeId(4)],
+            spectator_reactions: false,
+            threads: false,
+        };
+        let overwrites = permissions.overwrites(GUILD, ROOM_ROLE, BOT);
+
+        let everyone = overwrites
+            .iter()
+            .find(|o| o.kind == PermissionOverwriteType::Role(RoleId(GUILD.0)))
+            .unwrap();
+        assert!(everyone.deny.contains(
+            Permissions::VIEW_CHANNEL
+                | Permissions::ADD_REACTIONS
+                | Permissions::CREATE_PUBLIC_THREADS
+        ));
+        assert!(overwrites
+            .iter()
+            .any(|o| o.kind == PermissionOverwriteType::Member(BOT)
+                && o.allow.contains(Permissions::VIEW_CHANNEL)));
+        assert!(overwrites
+            .iter()
+            .any(|o| o.kind == PermissionOverwriteType::Role(RoleId(4))));
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{LazyLock, Mutex, RwLock},
+};
+
+use color_eyre::Result;
+use poise::serenity_prelude::{ChannelId, GuildId, Http, RoleId, UserId};
+use tracing::{info, warn};
+
+use crate::{discord_api::DiscordApi, Mongo, WrapErr};
+
+/// The report channel of every guild in sandbox mode. Loaded on startup and kept in sync by
+/// `/config sandbox`, so that code without access to the handler data can check it.
+static SANDBOXES: LazyLock<RwLock<HashMap<GuildId, ChannelId>>> = LazyLock::new(Default::default);
+
+/// The channel that simulated changes are reported to, if the guild is in sandbox mode.
+pub fn report_channel(guild_id: GuildId) -> Option<ChannelId> {
+    SANDBOXES
+        .read()
+        .expect("sandboxes lock poisoned")
+        .get(&guild_id)
+        .copied()
+}
+
+/// The guilds in sandbox mode. Background tasks leave them alone, since their real data must not
+/// change and their shadow data isn't read by the tasks.
+pub fn guilds() -> Vec<GuildId> {
+    SANDBOXES
+        .read()
+        .expect("sandboxes lock poisoned")
+        .keys()
+        .copied()
+        .collect()
+}
+
+pub fn set(guild_id: GuildId, report_channel: Option<ChannelId>) {
+    let mut sandboxes = SANDBOXES.write().expect("sandboxes lock poisoned");
+    match report_channel {
+        Some(channel) => sandboxes.insert(guild_id, channel),
+        None => sandboxes.remove(&guild_id),
+    };
+}
+
+/// Reads the guilds in sandbox mode from the database.
+pub async fn load(mongo: &Mongo) -> Result<()> {
+    let mut sandboxes = Vec::new();
+    for mongo in mongo.namespaces().await? {
+        sandboxes.extend(mongo.find_sandboxes().await?);
+    }
+    info!(count = sandboxes.len(), "Loaded sandboxes");
+
+    *SANDBOXES.write().expect("sandboxes lock poisoned") = sandboxes
+        .into_iter()
+        .map(|(guild_id, channel)| (guild_id.into(), channel.into()))
+        .collect();
+    Ok(())
+}
+
+/// Reports the change as an embed instead of making it, if the guild is in sandbox mode. Returns
+/// whether it was simulated.
+pub async fn simulate(http: &Http, guild_id: GuildId, change: impl Display) -> bool {
+    let channel = match report_channel(guild_id) {
+        Some(channel) => channel,
+        None => return false,
+    };
+
+    info!(%guild_id, %change, "Simulated change");
+
+    let result = channel
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title("Simuliert")
+                    .description(change.to_string())
+                    .footer(|footer| footer.text("Sandbox-Modus, nüt isch würklich passiert"))
+            })
+        })
+        .await;
+    if let Err(err) = result {
+        warn!(?err, "Failed to report simulated change");
+    }
+
+    true
+}
+
+/// Gives the member the role, or only reports it in sandbox mode.
+pub async fn add_member_role(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: impl Into<UserId>,
+    role_id: impl Into<RoleId>,
+) -> Result<()> {
+    let (user_id, role_id) = (user_id.into(), role_id.into());
+    if simulate(http, guild_id, format!("<@&{role_id}> für <@{user_id}>")).await {
+        return Ok(());
+    }
+    http.add_member_role(guild_id.0, user_id.0, role_id.0, None)
+        .await
+        .wrap_err("add role to member")
+}
+
+/// Removes the role from the member, or only reports it in sandbox mode.
+pub async fn remove_member_role(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: impl Into<UserId>,
+    role_id: impl Into<RoleId>,
+) -> Result<()> {
+    let (user_id, role_id) = (user_id.into(), role_id.into());
+    if simulate(http, guild_id, format!("<@&{role_id}> weg vo <@{user_id}>")).await {
+        return Ok(());
+    }
+    http.remove_member_role(guild_id.0, user_id.0, role_id.0, None)
+        .await
+        .wrap_err("remove role from member")
+}
+
+/// Reads from Discord but only reports role changes. Remembers the simulated changes, so that
+/// reading the roles afterwards sees them.
+pub struct SimulatedDiscord<'a> {
+    pub http: &'a Http,
+    changes: Mutex<Vec<(UserId, RoleId, bool)>>,
+}
+
+impl<'a> SimulatedDiscord<'a> {
+    pub fn new(http: &'a Http) -> Self {
+        Self {
+            http,
+            changes: Mutex::default(),
+        }
+    }
+}
+
+impl DiscordApi for SimulatedDiscord<'_> {
+    async fn member_roles(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Option<Vec<RoleId>>> {
+        let mut roles = match DiscordApi::member_roles(self.http, guild_id, user_id).await? {
+            Some(roles) => roles,
+            None => return Ok(None),
+        };
+        let changes = self
+            .changes
+            .lock()
+            .expect("simulated changes lock poisoned");
+        for &(_, role_id, added) in changes.iter().filter(|change| change.0 == user_id) {
+            roles.retain(|role| *role != role_id);
+            if added {
+                roles.push(role_id);
+            }
+        }
+        Ok(Some(roles))
+    }
+
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        simulate(
+            self.http,
+            guild_id,
+            format!("<@&{role_id}> für <@{user_id}>"),
+        )
+        .await;
+        self.changes
+            .lock()
+            .expect("simulated changes lock poisoned")
+            .push((user_id, role_id, true));
+        Ok(())
+    }
+
+    async fn remove_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        simulate(
+            self.http,
+            guild_id,
+            format!("<@&{role_id}> weg vo <@{user_id}>"),
+        )
+        .await;
+        self.changes
+            .lock()
+            .expect("simulated changes lock poisoned")
+            .push((user_id, role_id, false));
+        Ok(())
+    }
+
+    async fn send_message(&self, channel_id: ChannelId, content: String) -> Result<()> {
+        DiscordApi::send_message(self.http, channel_id, content).await
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use color_eyre::Result;
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::Http;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::TaskInterval, court_session, economy, events, lawsuit, lawsuit::Phase,
+    maintenance::Maintenance, model::SnowflakeId, notification, opinion_poll, prison, probation,
+    sandbox, subpoena, visitation, Mongo,
+};
+
+/// How often the scheduler checks for due jobs.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a claimed job is reserved for the run. If the bot stops before the job is done, it is
+/// run again once the lock expired.
+const LOCK_DURATION: Duration = Duration::from_secs(5 * 60);
+
+/// How often a job is attempted before it is kept as failed.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// How long to wait before the first retry. Doubles after every attempt.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// What a job does, together with its payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobKind {
+    /// Releases someone from prison at the end of their sentence.
+    Release { user_id: SnowflakeId },
+    /// Reminds the parties of a lawsuit that the deadline of a phase is near.
+    DeadlineReminder {
+        lawsuit_id: Uuid,
+        phase: Phase,
+        /// The deadline when the job was scheduled, the job does nothing if it was changed.
+        due: DateTime,
+    },
+    /// Tells the court room that the deadline of a phase is over.
+    DeadlineExpired {
+        lawsuit_id: Uuid,
+        phase: Phase,
+        due: DateTime,
+    },
+    /// Executes a verdict once it can't be appealed anymore.
+    FinalizeVerdict { lawsuit_id: Uuid },
+    /// Imprisons someone whose probation was violated.
+    ActivateSentence { probation_id: Uuid },
+    /// Lets a suspended sentence lapse at the end of its probation.
+    EndProbation { probation_id: Uuid },
+    /// Reminds a witness of the hearing they were summoned to.
+    SubpoenaReminder { lawsuit_id: Uuid, subpoena_id: Uuid },
+    /// Tells the judge if a summoned witness didn't show up.
+    SubpoenaNoShow { lawsuit_id: Uuid, subpoena_id: Uuid },
+    /// Reminds the judge to review the pre-trial detention of the accused.
+    DetentionReview {
+        lawsuit_id: Uuid,
+        /// The review when the job was scheduled, the job does nothing if it was changed.
+        due: DateTime,
+    },
+    /// Sends a personal reminder about a lawsuit that was set with `/lawsuit notify remind`.
+    CaseReminder {
+        lawsuit_id: Uuid,
+        user_id: SnowflakeId,
+        note: String,
+    },
+    /// Announces a recurring court day on its morning.
+    CourtSession { session_id: Uuid },
+    /// Shows the result of the spectator vote on a verdict.
+    CloseOpinionPoll { lawsuit_id: Uuid },
+    /// Lets the visitor of an approved visit into the prison channel.
+    VisitStart { visit_id: Uuid },
+    /// Takes the access of the visitor away again.
+    VisitEnd { visit_id: Uuid },
+    /// Garnishes the income of a debtor for their outstanding debts.
+    Garnishment { user_id: SnowflakeId },
+}
+
+impl Display for JobKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Release { user_id } => write!(f, "Freilassung vo <@{user_id}>"),
+            Self::DeadlineReminder { phase, .. } => write!(f, "Erinnerig a d'Frist für {phase}"),
+            Self::DeadlineExpired { phase, .. } => write!(f, "Ablauf vo de Frist für {phase}"),
+            Self::FinalizeVerdict { .. } => write!(f, "Rechtskraft vomene Urteil"),
+            Self::ActivateSentence { .. } => write!(f, "Vollzug vomene Urteil uf Bewährig"),
+            Self::EndProbation { .. } => write!(f, "Ablauf vonere Bewährigsfrist"),
+            Self::DetentionReview { .. } => write!(f, "Haftprüefig"),
+            Self::SubpoenaReminder { .. } => write!(f, "Erinnerig a e Vorladig"),
+            Self::SubpoenaNoShow { .. } => write!(f, "Kontrolle vonere Vorladig"),
+            Self::CaseReminder { user_id, .. } => write!(f, "Erinnerig für <@{user_id}>"),
+            Self::CourtSession { .. } => write!(f, "Aakündigung vomene Gerichtstag"),
+            Self::CloseOpinionPoll { .. } => write!(f, "Ändi vonere Volksabstimmig"),
+            Self::VisitStart { .. } => write!(f, "Afang vomene Bsuech"),
+            Self::VisitEnd { .. } => write!(f, "Ändi vomene Bsuech"),
+            Self::Garnishment { user_id } => write!(f, "Lohnpfändung vo <@{user_id}>"),
+        }
+    }
+}
+
+/// A job that runs at the due time. Jobs are deleted once they succeeded, so a job may run more
+/// than once if the bot stops in between and must be safe to repeat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub kind: JobKind,
+    pub due: DateTime,
+    pub attempts: u32,
+    /// Set while the job is running.
+    pub locked_until: Option<DateTime>,
+    pub last_error: Option<String>,
+    /// Whether the job gave up after too many attempts. Failed jobs are kept for `/debug jobs`.
+    pub failed: bool,
+}
+
+/// Schedules the job to run at the due time.
+#[tracing::instrument(skip(mongo))]
+pub async fn schedule(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    kind: JobKind,
+    due: DateTime,
+) -> Result<Job> {
+    let job = Job {
+        id: Uuid::new(),
+        guild_id,
+        kind,
+        due,
+        attempts: 0,
+        locked_until: None,
+        last_error: None,
+        failed: false,
+    };
+    mongo.add_job(&job).await?;
+
+    info!(job_id = %job.id, "Scheduled job");
+
+    Ok(job)
+}
+
+/// Periodically runs the due jobs. Never returns.
+pub async fn scheduler_task(mongo: Mongo, http: Arc<Http>, maintenance: Maintenance) {
+    let mut interval = TaskInterval::new("scheduler", SCHEDULER_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if maintenance.is_active() {
+            continue;
+        }
+
+        if let Err(err) = run_due_jobs(&mongo, &http).await {
+            error!(?err, "Error running due jobs");
+        }
+    }
+}
+
+#[tracing::instrument(skip(mongo, http))]
+pub async fn run_due_jobs(mongo: &Mongo, http: &Arc<Http>) -> Result<()> {
+    // the jobs of guilds in sandbox mode wait until it's turned off
+    let sandboxes = sandbox::guilds()
+        .into_iter()
+        .map(SnowflakeId::from)
+        .collect::<Vec<_>>();
+
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        loop {
+            let now = DateTime::now();
+            let locked_until =
+                DateTime::from_millis(now.timestamp_millis() + LOCK_DURATION.as_millis() as i64);
+
+            let job = match mongo.claim_due_job(now, locked_until, &sandboxes).await? {
+                Some(job) => job,
+                None => break,
+            };
+
+            match run(mongo, http, &job).await {
+                Ok(()) => {
+                    mongo.delete_job(job.id).await?;
+                    info!(job_id = %job.id, kind = ?job.kind, "Ran job");
+                }
+                Err(err) => {
+                    let attempts = job.attempts + 1;
+                    let retry_at = (attempts < MAX_ATTEMPTS).then(|| {
+                        let delay = RETRY_DELAY * 2_u32.pow(attempts - 1);
+                        DateTime::from_millis(now.timestamp_millis() + delay.as_millis() as i64)
+                    });
+                    warn!(?err, job_id = %job.id, attempts, "Job failed");
+                    mongo
+                        .set_job_failed(job.id, attempts, &format!("{err:#}"), retry_at)
+                        .await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run(mongo: &Mongo, http: &Arc<Http>, job: &Job) -> Result<()> {
+    match job.kind {
+        JobKind::Release { user_id } => {
+            // released by hand in the meantime
+            if mongo
+                .find_prison_entry(job.guild_id, user_id)
+                .await?
+                .is_none()
+            {
+                return Ok(());
+            }
+            let release = prison::release(mongo, http, job.guild_id.into(), user_id.into());
+            let action = format!("Uftrag {}", job.kind);
+            if let Err(response) =
+                events::tracked(mongo, job.guild_id, None, action, release).await?
+            {
+                warn!(%response, job_id = %job.id, "Could not release prisoner");
+            }
+            Ok(())
+        }
+        JobKind::DeadlineReminder {
+            lawsuit_id,
+            phase,
+            due,
+        } => lawsuit::remind_deadline(mongo, http, job.guild_id, lawsuit_id, phase, due).await,
+        JobKind::DeadlineExpired {
+            lawsuit_id,
+            phase,
+            due,
+        } => lawsuit::expire_deadline(mongo, http, job.guild_id, lawsuit_id, phase, due).await,
+        JobKind::FinalizeVerdict { lawsuit_id } => {
+            lawsuit::finalize_verdict(mongo, http, job.guild_id, lawsuit_id).await
+        }
+        JobKind::ActivateSentence { probation_id } => {
+            probation::activate(mongo, http, job.guild_id, probation_id).await
+        }
+        JobKind::EndProbation { probation_id } => probation::end(mongo, probation_id).await,
+        JobKind::SubpoenaReminder {
+            lawsuit_id,
+            subpoena_id,
+        } => subpoena::remind(mongo, http, job.guild_id, lawsuit_id, subpoena_id).await,
+        JobKind::SubpoenaNoShow {
+            lawsuit_id,
+            subpoena_id,
+        } => subpoena::check_no_show(mongo, http, job.guild_id, lawsuit_id, subpoena_id).await,
+        JobKind::DetentionReview { lawsuit_id, due } => {
+            lawsuit::review_detention(mongo, http, job.guild_id, lawsuit_id, due).await
+        }
+        JobKind::CaseReminder {
+            lawsuit_id,
+            user_id,
+            ref note,
+        } => {
+            notification::send_case_reminder(mongo, http, job.guild_id, lawsuit_id, user_id, note)
+                .await
+        }
+        JobKind::CourtSession { session_id } => {
+            court_session::announce(mongo, http, job.guild_id, session_id, job.due).await
+        }
+        JobKind::CloseOpinionPoll { lawsuit_id } => {
+            opinion_poll::close(mongo, http, job.guild_id, lawsuit_id).await
+        }
+        JobKind::VisitStart { visit_id } => visitation::start(mongo, http, visit_id).await,
+        JobKind::VisitEnd { visit_id } => visitation::end(mongo, http, visit_id).await,
+        JobKind::Garnishment { user_id } => {
+            economy::garnish(mongo, job.guild_id, user_id, job.due).await
+        }
+    }
+}
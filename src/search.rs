@@ -0,0 +1,188 @@
+use crate::lawsuit::Lawsuit;
+
+/// How many lawsuits a search shows.
+pub const MAX_RESULTS: usize = 10;
+
+/// How many characters a snippet shows before and after the match.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A lawsuit that matched a search, with the part of its text that matched.
+#[derive(Debug)]
+pub struct SearchHit<'a> {
+    pub lawsuit: &'a Lawsuit,
+    /// The text around the first match, with the words of the query in bold.
+    pub snippet: String,
+    /// How often the words of the query appear in the lawsuit.
+    matches: usize,
+}
+
+/// Lowercases char by char, so that the positions still match the original text.
+fn lowercase(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// The positions where the word starts in the text.
+fn positions(text: &[char], word: &[char]) -> Vec<usize> {
+    if word.is_empty() || word.len() > text.len() {
+        return vec![];
+    }
+    (0..=text.len() - word.len())
+        .filter(|&start| &text[start..start + word.len()] == word)
+        .collect()
+}
+
+/// The text around the position, with every word in bold.
+fn snippet(text: &str, position: usize, words: &[Vec<char>]) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let lower = lowercase(text);
+    let start = position.saturating_sub(SNIPPET_CONTEXT);
+    let longest = words.iter().map(Vec::len).max().unwrap_or(0);
+    let end = (position + longest + SNIPPET_CONTEXT).min(chars.len());
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut i = start;
+    while i < end {
+        let word = words
+            .iter()
+            .filter(|word| lower[i..].starts_with(word))
+            .max_by_key(|word| word.len());
+        match word {
+            Some(word) => {
+                let matched = chars[i..(i + word.len()).min(chars.len())]
+                    .iter()
+                    .collect::<String>();
+                snippet.push_str(&format!("**{matched}**"));
+                i += word.len();
+            }
+            None => {
+                snippet.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    if i < chars.len() {
+        snippet.push('…');
+    }
+    snippet.replace('\n', " ")
+}
+
+/// The texts of a lawsuit that are searched: the reason, the verdict and the statements.
+fn texts(lawsuit: &Lawsuit) -> impl Iterator<Item = &str> {
+    std::iter::once(lawsuit.reason.as_str())
+        .chain(lawsuit.verdict.as_deref())
+        .chain(lawsuit.statements.iter().map(|s| s.text.as_str()))
+}
+
+/// Finds the lawsuits whose texts contain every word of the query, ignoring case. The lawsuits
+/// where the words appear most often come first.
+pub fn search<'a>(
+    lawsuits: impl IntoIterator<Item = &'a Lawsuit>,
+    query: &str,
+) -> Vec<SearchHit<'a>> {
+    let words = query.split_whitespace().map(lowercase).collect::<Vec<_>>();
+    if words.is_empty() {
+        return vec![];
+    }
+
+    let mut hits = lawsuits
+        .into_iter()
+        .filter_map(|lawsuit| {
+            let texts = texts(lawsuit).collect::<Vec<_>>();
+            let lowered = texts.iter().map(|text| lowercase(text)).collect::<Vec<_>>();
+
+            let mut matches = 0;
+            for word in &words {
+                let count = lowered
+                    .iter()
+                    .map(|text| positions(text, word).len())
+                    .sum::<usize>();
+                if count == 0 {
+                    return None;
+                }
+                matches += count;
+            }
+
+            let (text, position) = texts
+                .iter()
+                .zip(&lowered)
+                .find_map(|(text, lower)| Some((text, *positions(lower, &words[0]).first()?)))?;
+            Some(SearchHit {
+                lawsuit,
+                snippet: snippet(text, position, &words),
+                matches,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // newer lawsuits first among equally good ones
+    hits.sort_by_key(|hit| {
+        (
+            std::cmp::Reverse(hit.matches),
+            std::cmp::Reverse(hit.lawsuit.number),
+        )
+    });
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::search;
+    use crate::{
+        lawsuit::{Lawsuit, Party, Statement},
+        model::SnowflakeId,
+    };
+
+    fn lawsuit(number: u64, reason: &str) -> Lawsuit {
+        let mut lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            reason.to_string(),
+        );
+        lawsuit.number = number;
+        lawsuit
+    }
+
+    #[test]
+    fn all_words() {
+        let mut stolen = lawsuit(1, "Er hät mis Velo gstohle");
+        stolen.statements.push(Statement {
+            party: Party::Accused,
+            text: "S'Velo isch scho kaputt gsi, ich han keis Velo gno".to_string(),
+        });
+        let insult = lawsuit(2, "Beleidigung im Voice-Chat");
+        let lawsuits = [stolen, insult];
+
+        let hits = search(&lawsuits, "velo");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].snippet, "Er hät mis **Velo** gstohle");
+
+        assert_eq!(search(&lawsuits, "VELO kaputt").len(), 1);
+        assert!(search(&lawsuits, "velo beleidigung").is_empty());
+        assert!(search(&lawsuits, "  ").is_empty());
+    }
+
+    #[test]
+    fn ranking_and_snippet() {
+        let once = lawsuit(1, "Spam");
+        let twice = lawsuit(2, "Spam und no meh Spam");
+        let long = lawsuit(3, &format!("{} Spam {}", "a".repeat(50), "b".repeat(50)));
+        let lawsuits = [once, twice, long];
+
+        let hits = search(&lawsuits, "spam");
+        let numbers = hits
+            .iter()
+            .map(|hit| hit.lawsuit.number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec![2, 3, 1]);
+        assert_eq!(
+            hits[1].snippet,
+            format!("…{} **Spam** {}…", "a".repeat(39), "b".repeat(39))
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::Result;
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::Http;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::TaskInterval, maintenance::Maintenance, model::SnowflakeId, prison, sandbox, Mongo,
+};
+
+/// How often the service task checks for overdue community service.
+const SERVICE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Community service someone was sentenced to. The entry is deleted once all hours are done.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceObligation {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    pub hours_required: u32,
+    pub hours_done: u32,
+    pub deadline: DateTime,
+    pub reason: String,
+    pub lawsuit_id: Option<Uuid>,
+}
+
+/// Periodically arrests everyone who didn't finish their community service in time. Never returns.
+pub async fn service_task(mongo: Mongo, http: Arc<Http>, maintenance: Maintenance) {
+    let mut interval = TaskInterval::new("service", SERVICE_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if maintenance.is_active() {
+            continue;
+        }
+
+        if let Err(err) = escalate_overdue_service(&mongo, &http).await {
+            error!(?err, "Error escalating overdue community service");
+        }
+    }
+}
+
+#[tracing::instrument(skip(mongo, http))]
+pub async fn escalate_overdue_service(mongo: &Mongo, http: &Arc<Http>) -> Result<()> {
+    for namespace in mongo.namespaces().await? {
+        let mongo = &namespace;
+        for obligation in mongo
+            .find_overdue_service_obligations(DateTime::now())
+            .await?
+        {
+            if sandbox::report_channel(obligation.guild_id.into()).is_some() {
+                continue;
+            }
+            mongo.delete_service_obligation(obligation.id).await?;
+
+            let result = prison::arrest(
+                mongo,
+                http,
+                obligation.guild_id.into(),
+                obligation.user_id.into(),
+            )
+            .await;
+
+            match result {
+                Ok(Ok(())) => {
+                    info!(
+                        ?obligation,
+                        "Arrested user for not finishing community service"
+                    );
+                }
+                Ok(Err(response)) => {
+                    warn!(?obligation, %response, "Could not arrest user for overdue community service");
+                }
+                Err(err) => {
+                    error!(
+                        ?err,
+                        ?obligation,
+                        "Error arresting user for overdue community service"
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
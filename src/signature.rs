@@ -0,0 +1,200 @@
+use std::env;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    lawsuit::{Lawsuit, Party},
+    model::SnowflakeId,
+    pdf,
+    pdf::{Document, Font},
+};
+
+/// The document information entries that hold the signed record and its signature.
+const RECORD_KEY: &str = "CourtRecord";
+const SIGNATURE_KEY: &str = "CourtSignature";
+
+/// The key verdict records are signed with, from `VERDICT_SIGNING_KEY`. Without it, records aren't
+/// signed and can't be verified.
+fn key() -> Option<String> {
+    env::var("VERDICT_SIGNING_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+}
+
+/// What a signed verdict record certifies. It's signed as JSON with the fields in this order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedVerdict {
+    pub guild_id: SnowflakeId,
+    pub number: u64,
+    /// `None` if the plaintiff is anonymous.
+    pub plaintiff: Option<SnowflakeId>,
+    pub accused: SnowflakeId,
+    pub judge: SnowflakeId,
+    pub reason: String,
+    pub verdict: String,
+    pub winner: Option<Party>,
+    pub damages: Option<i64>,
+    pub community_service: Option<u32>,
+    pub prison_secs: Option<u64>,
+    /// Milliseconds since the epoch.
+    pub closed_at: Option<i64>,
+}
+
+impl SignedVerdict {
+    fn new(guild_id: SnowflakeId, lawsuit: &Lawsuit) -> Option<Self> {
+        Some(Self {
+            guild_id,
+            number: lawsuit.number,
+            plaintiff: (!lawsuit.anonymous).then_some(lawsuit.plaintiff),
+            accused: lawsuit.accused,
+            judge: lawsuit.judge,
+            reason: lawsuit.reason.clone(),
+            verdict: lawsuit.verdict.clone()?,
+            winner: lawsuit.winner,
+            damages: lawsuit.damages,
+            community_service: lawsuit.community_service,
+            prison_secs: lawsuit.prison_secs,
+            closed_at: lawsuit.closed_time().map(|time| time.timestamp_millis()),
+        })
+    }
+}
+
+/// A verdict record and the hex encoded HMAC-SHA256 of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signed {
+    pub record: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    Valid(SignedVerdict),
+    /// The record or the signature was changed.
+    Forged,
+    /// The document doesn't contain a signed record.
+    Unsigned,
+    /// There is no key to verify with.
+    NoKey,
+}
+
+fn mac(key: &str) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("hmac accepts any key length")
+}
+
+fn sign_with(key: &str, guild_id: SnowflakeId, lawsuit: &Lawsuit) -> Option<Signed> {
+    let record = serde_json::to_string(&SignedVerdict::new(guild_id, lawsuit)?).ok()?;
+    let mut mac = mac(key);
+    mac.update(record.as_bytes());
+    Some(Signed {
+        signature: pdf::hex(&mac.finalize().into_bytes()),
+        record,
+    })
+}
+
+fn verify_with(key: &str, signed: &Signed) -> Verification {
+    let signature = match pdf::unhex(&signed.signature) {
+        Some(signature) => signature,
+        None => return Verification::Forged,
+    };
+    let mut mac = mac(key);
+    mac.update(signed.record.as_bytes());
+    if mac.verify_slice(&signature).is_err() {
+        return Verification::Forged;
+    }
+    match serde_json::from_str(&signed.record) {
+        Ok(verdict) => Verification::Valid(verdict),
+        Err(_) => Verification::Forged,
+    }
+}
+
+/// Signs the verdict of the lawsuit, if the bot has a key and the lawsuit a verdict.
+pub fn sign(guild_id: SnowflakeId, lawsuit: &Lawsuit) -> Option<Signed> {
+    sign_with(&key()?, guild_id, lawsuit)
+}
+
+/// Checks that the record in the PDF file was signed by the bot and not changed since.
+pub fn verify(pdf: &[u8]) -> Verification {
+    let key = match key() {
+        Some(key) => key,
+        None => return Verification::NoKey,
+    };
+    match Signed::extract(pdf) {
+        Some(signed) => verify_with(&key, &signed),
+        None => Verification::Unsigned,
+    }
+}
+
+impl Signed {
+    /// Stores the record in the document and prints the start of the signature at its end.
+    pub fn embed(&self, document: &mut Document) {
+        document.metadata(RECORD_KEY, &self.record);
+        document.metadata(SIGNATURE_KEY, &self.signature);
+        document.space(12.0);
+        document.text(
+            &format!(
+                "Signatur: {}… (prüfen mit /lawsuit verdict verify)",
+                &self.signature[..16]
+            ),
+            Font::Regular,
+            8.0,
+        );
+    }
+
+    fn extract(pdf: &[u8]) -> Option<Self> {
+        Some(Self {
+            record: pdf::find_metadata(pdf, RECORD_KEY)?,
+            signature: pdf::find_metadata(pdf, SIGNATURE_KEY)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sign_with, verify_with, Signed, Verification};
+    use crate::{lawsuit::Lawsuit, model::SnowflakeId, pdf::Document};
+
+    fn lawsuit() -> Lawsuit {
+        let mut lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "Spam".to_string(),
+        );
+        lawsuit.verdict = Some("schuldig".to_string());
+        lawsuit
+    }
+
+    #[test]
+    fn roundtrip() {
+        let signed = sign_with("geheim", SnowflakeId(9), &lawsuit()).unwrap();
+        let mut document = Document::default();
+        signed.embed(&mut document);
+        let extracted = Signed::extract(&document.finish()).unwrap();
+
+        assert_eq!(extracted, signed);
+        match verify_with("geheim", &extracted) {
+            Verification::Valid(verdict) => {
+                assert_eq!(verdict.guild_id, SnowflakeId(9));
+                assert_eq!(verdict.verdict, "schuldig");
+            }
+            other => panic!("expected a valid signature, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn forged() {
+        let signed = sign_with("geheim", SnowflakeId(9), &lawsuit()).unwrap();
+        let edited = Signed {
+            record: signed.record.replace("schuldig", "unschuldig"),
+            ..signed.clone()
+        };
+        assert_eq!(verify_with("geheim", &edited), Verification::Forged);
+        assert_eq!(verify_with("anders", &signed), Verification::Forged);
+
+        let mut open = lawsuit();
+        open.verdict = None;
+        assert_eq!(sign_with("geheim", SnowflakeId(9), &open), None);
+    }
+}
@@ -0,0 +1,220 @@
+use std::future::Future;
+
+use color_eyre::Result;
+use mongodb::bson::DateTime;
+
+use crate::{
+    economy::{Debt, TransactionKind},
+    lawsuit::Lawsuit,
+    model::{PrisonEntry, SnowflakeId, State},
+    Mongo,
+};
+
+/// The database operations of the logic that can run without Mongo, implemented by [`Mongo`] and
+/// an in-memory store in tests. Methods are added here as more logic is moved behind it, the
+/// documentation is on the methods of [`Mongo`].
+pub trait Storage: Send + Sync {
+    fn find_or_insert_state(
+        &self,
+        guild_id: SnowflakeId,
+    ) -> impl Future<Output = Result<State>> + Send;
+
+    fn add_to_prison(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn remove_from_prison(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn find_prison_entry(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<Option<PrisonEntry>>> + Send;
+
+    fn set_prisoner_left(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        left_at: Option<DateTime>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn delete_release_jobs(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn next_case_number(&self, guild_id: SnowflakeId) -> impl Future<Output = Result<u64>> + Send;
+
+    fn save_lawsuit(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit: &Lawsuit,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn set_verdict(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit: &Lawsuit,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn claim_court_room(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn set_court_room_ongoing(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+        ongoing: bool,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn credit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> impl Future<Output = Result<i64>> + Send;
+
+    fn try_debit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> impl Future<Output = Result<Result<i64, i64>>> + Send;
+
+    fn find_debts(
+        &self,
+        guild_id: SnowflakeId,
+        debtor: SnowflakeId,
+    ) -> impl Future<Output = Result<Vec<Debt>>> + Send;
+}
+
+impl Storage for Mongo {
+    fn find_or_insert_state(
+        &self,
+        guild_id: SnowflakeId,
+    ) -> impl Future<Output = Result<State>> + Send {
+        Mongo::find_or_insert_state(self, guild_id)
+    }
+
+    fn add_to_prison(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<bool>> + Send {
+        Mongo::add_to_prison(self, guild_id, user_id)
+    }
+
+    fn remove_from_prison(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<bool>> + Send {
+        Mongo::remove_from_prison(self, guild_id, user_id)
+    }
+
+    fn find_prison_entry(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<Option<PrisonEntry>>> + Send {
+        Mongo::find_prison_entry(self, guild_id, user_id)
+    }
+
+    fn set_prisoner_left(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        left_at: Option<DateTime>,
+    ) -> impl Future<Output = Result<()>> + Send {
+        Mongo::set_prisoner_left(self, guild_id, user_id, left_at)
+    }
+
+    fn delete_release_jobs(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> impl Future<Output = Result<()>> + Send {
+        Mongo::delete_release_jobs(self, guild_id, user_id)
+    }
+
+    fn next_case_number(&self, guild_id: SnowflakeId) -> impl Future<Output = Result<u64>> + Send {
+        Mongo::next_case_number(self, guild_id)
+    }
+
+    fn save_lawsuit(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit: &Lawsuit,
+    ) -> impl Future<Output = Result<()>> + Send {
+        Mongo::save_lawsuit(self, guild_id, lawsuit)
+    }
+
+    fn set_verdict(
+        &self,
+        guild_id: SnowflakeId,
+        lawsuit: &Lawsuit,
+    ) -> impl Future<Output = Result<()>> + Send {
+        Mongo::set_verdict(self, guild_id, lawsuit)
+    }
+
+    fn claim_court_room(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+    ) -> impl Future<Output = Result<bool>> + Send {
+        Mongo::claim_court_room(self, guild_id, channel_id)
+    }
+
+    fn set_court_room_ongoing(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+        ongoing: bool,
+    ) -> impl Future<Output = Result<()>> + Send {
+        Mongo::set_court_room_ongoing(self, guild_id, channel_id, ongoing)
+    }
+
+    fn credit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> impl Future<Output = Result<i64>> + Send {
+        Mongo::credit(self, guild_id, user_id, amount, kind, description)
+    }
+
+    fn try_debit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        description: &str,
+    ) -> impl Future<Output = Result<Result<i64, i64>>> + Send {
+        Mongo::try_debit(self, guild_id, user_id, amount, kind, description)
+    }
+
+    fn find_debts(
+        &self,
+        guild_id: SnowflakeId,
+        debtor: SnowflakeId,
+    ) -> impl Future<Output = Result<Vec<Debt>>> + Send {
+        Mongo::find_debts(self, guild_id, debtor)
+    }
+}
@@ -0,0 +1,327 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use mongodb::{
+    bson,
+    bson::{doc, DateTime, Uuid},
+};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, GuildId, Http, MessageId, UserId};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    handler::Response, lawsuit::Lawsuit, model::SnowflakeId, scheduler, scheduler::JobKind, Mongo,
+    WrapErr,
+};
+
+/// The prefix of the custom id of the buttons a witness uses to answer a subpoena,
+/// followed by `{guild_id}:{lawsuit_id}:{subpoena_id}:{accept}`.
+pub const SUBPOENA_ID_PREFIX: &str = "subpoena:";
+
+/// How long before the hearing the witness is reminded.
+const SUBPOENA_REMINDER: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// How long after the start of the hearing the witness has to show up in the court room.
+const NO_SHOW_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// The first second of 2015, where Discord snowflakes start.
+const DISCORD_EPOCH: i64 = 1_420_070_400_000;
+
+/// A summons for a witness to appear at a hearing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subpoena {
+    pub id: Uuid,
+    pub user_id: SnowflakeId,
+    pub hearing: DateTime,
+    /// Whether the witness accepted, `None` until they answered.
+    pub accepted: Option<bool>,
+}
+
+impl Subpoena {
+    pub fn answer(&self) -> &'static str {
+        match self.accepted {
+            Some(true) => "agnoh",
+            Some(false) => "abglehnt",
+            None => "kei Antwort",
+        }
+    }
+}
+
+/// Parses the rest of a subpoena custom id.
+pub fn parse_id(id: &str) -> Option<(GuildId, Uuid, Uuid, bool)> {
+    let mut parts = id.split(':');
+    let guild_id = parts.next()?.parse::<u64>().ok()?;
+    let lawsuit_id = Uuid::parse_str(parts.next()?).ok()?;
+    let subpoena_id = Uuid::parse_str(parts.next()?).ok()?;
+    let accept = parts.next()?.parse::<bool>().ok()?;
+    Some((GuildId(guild_id), lawsuit_id, subpoena_id, accept))
+}
+
+fn custom_id(guild_id: GuildId, lawsuit_id: Uuid, subpoena_id: Uuid, accept: bool) -> String {
+    format!("{SUBPOENA_ID_PREFIX}{guild_id}:{lawsuit_id}:{subpoena_id}:{accept}")
+}
+
+/// Sends the witness a summons to the hearing and schedules the reminder and the no-show check.
+#[tracing::instrument(skip(mongo, http, lawsuit), fields(lawsuit_id = %lawsuit.id))]
+pub async fn summon(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    lawsuit: &mut Lawsuit,
+    user_id: UserId,
+    hearing: DateTime,
+) -> Result<Result<(), Response>> {
+    let subpoena = Subpoena {
+        id: Uuid::new(),
+        user_id: user_id.into(),
+        hearing,
+        accepted: None,
+    };
+
+    let hearing_secs = hearing.timestamp_millis() / 1000;
+    let sent = user_id
+        .create_dm_channel(http)
+        .await
+        .wrap_err("create dm channel")?
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title(format!("Vorladig zum Prozess #{}", lawsuit.number))
+                    .description(
+                        "Du wirsch als Zeug vor Gricht glade. Bitte bestätig, ob du chunnsch.",
+                    )
+                    .field("Grund", &lawsuit.reason, false)
+                    .field("Verhandlig", format!("<t:{hearing_secs}:F>"), true)
+                    .field("Richter", format!("<@{}>", lawsuit.judge), true)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(custom_id(guild_id, lawsuit.id, subpoena.id, true))
+                            .label("Ich chume")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|button| {
+                        button
+                            .custom_id(custom_id(guild_id, lawsuit.id, subpoena.id, false))
+                            .label("Ich chume nöd")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await;
+    if let Err(err) = sent {
+        warn!(?err, %user_id, "Could not send subpoena");
+        return Ok(Err(Response(format!(
+            "ich ha <@{user_id}> kei Vorladig chöne schicke, vilicht sind d'DMs zue"
+        ))));
+    }
+
+    lawsuit.subpoenas.push(subpoena.clone());
+    save(mongo, guild_id, lawsuit).await?;
+
+    let (lawsuit_id, subpoena_id) = (lawsuit.id, subpoena.id);
+    let remind_at = hearing.timestamp_millis() - SUBPOENA_REMINDER.as_millis() as i64;
+    if remind_at > DateTime::now().timestamp_millis() {
+        let job = JobKind::SubpoenaReminder {
+            lawsuit_id,
+            subpoena_id,
+        };
+        let remind_at = DateTime::from_millis(remind_at);
+        scheduler::schedule(mongo, guild_id.into(), job, remind_at).await?;
+    }
+    let job = JobKind::SubpoenaNoShow {
+        lawsuit_id,
+        subpoena_id,
+    };
+    let check_at =
+        DateTime::from_millis(hearing.timestamp_millis() + NO_SHOW_GRACE.as_millis() as i64);
+    scheduler::schedule(mongo, guild_id.into(), job, check_at).await?;
+
+    info!(?subpoena, "Sent subpoena");
+
+    Ok(Ok(()))
+}
+
+/// Records the answer of the witness and tells the court room about it.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn answer(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: GuildId,
+    lawsuit_id: Uuid,
+    subpoena_id: Uuid,
+    user_id: UserId,
+    accept: bool,
+) -> Result<Result<(), Response>> {
+    let mut lawsuit = match find(mongo, guild_id.into(), lawsuit_id).await? {
+        Some(lawsuit) => lawsuit,
+        None => return Ok(Err(Response("de Prozess isch scho verbi".to_string()))),
+    };
+    let subpoena = match lawsuit.subpoenas.iter_mut().find(|s| s.id == subpoena_id) {
+        Some(subpoena) if subpoena.user_id == user_id.into() => subpoena,
+        _ => return Ok(Err(Response("die Vorladig gits nöd".to_string()))),
+    };
+    subpoena.accepted = Some(accept);
+    let answer = subpoena.answer();
+    save(mongo, guild_id, &lawsuit).await?;
+
+    if lawsuit.court_room != SnowflakeId(0) {
+        let message = format!("<@{user_id}> hät d'Vorladig {answer}");
+        if let Err(err) = ChannelId::from(lawsuit.court_room).say(http, message).await {
+            warn!(?err, "Failed to send subpoena answer");
+        }
+    }
+
+    info!(accept, "Witness answered subpoena");
+
+    Ok(Ok(()))
+}
+
+/// Reminds the witness of the hearing, unless they declined.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn remind(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    subpoena_id: Uuid,
+) -> Result<()> {
+    let lawsuit = match find(mongo, guild_id, lawsuit_id).await? {
+        Some(lawsuit) => lawsuit,
+        None => return Ok(()),
+    };
+    let subpoena = match lawsuit.subpoenas.iter().find(|s| s.id == subpoena_id) {
+        Some(subpoena) if subpoena.accepted != Some(false) => subpoena,
+        _ => return Ok(()),
+    };
+
+    let mut message = format!(
+        "Erinnerig: du bisch als Zeug zum Prozess #{} <t:{}:R> vorglade",
+        lawsuit.number,
+        subpoena.hearing.timestamp_millis() / 1000
+    );
+    if subpoena.accepted.is_none() {
+        message.push_str(", du häsch no nöd gantwortet");
+    }
+    let sent = UserId::from(subpoena.user_id)
+        .create_dm_channel(http)
+        .await
+        .wrap_err("create dm channel")?
+        .say(http, message)
+        .await;
+    if let Err(err) = sent {
+        warn!(?err, "Could not send subpoena reminder");
+    }
+
+    Ok(())
+}
+
+/// Tells the judge if the witness didn't write in the court room since the start of the hearing,
+/// so they can decide about contempt of court.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn check_no_show(
+    mongo: &Mongo,
+    http: &Http,
+    guild_id: SnowflakeId,
+    lawsuit_id: Uuid,
+    subpoena_id: Uuid,
+) -> Result<()> {
+    let lawsuit = match find(mongo, guild_id, lawsuit_id).await? {
+        Some(lawsuit) if lawsuit.court_room != SnowflakeId(0) => lawsuit,
+        _ => return Ok(()),
+    };
+    let subpoena = match lawsuit.subpoenas.iter().find(|s| s.id == subpoena_id) {
+        Some(subpoena) => subpoena,
+        None => return Ok(()),
+    };
+
+    let room = ChannelId::from(lawsuit.court_room);
+    let messages = room
+        .messages(http, |retriever| {
+            retriever
+                .after(first_message_id(subpoena.hearing))
+                .limit(100)
+        })
+        .await
+        .wrap_err("fetch court room messages")?;
+    if messages
+        .iter()
+        .any(|message| SnowflakeId::from(message.author.id) == subpoena.user_id)
+    {
+        return Ok(());
+    }
+
+    room.say(
+        http,
+        format!(
+            "<@{}>, de Zeug <@{}> isch nöd zur Verhandlig cho (Vorladig: {}). \
+             Das chönnt e Missachtig vom Gricht sii",
+            lawsuit.judge,
+            subpoena.user_id,
+            subpoena.answer()
+        ),
+    )
+    .await
+    .wrap_err("send no-show report")?;
+
+    info!(?subpoena, "Reported witness no-show");
+
+    Ok(())
+}
+
+/// The smallest message id of a message sent at the time.
+fn first_message_id(time: DateTime) -> MessageId {
+    MessageId(((time.timestamp_millis() - DISCORD_EPOCH).max(0) as u64) << 22)
+}
+
+/// The lawsuit if it's still running.
+async fn find(mongo: &Mongo, guild_id: SnowflakeId, lawsuit_id: Uuid) -> Result<Option<Lawsuit>> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    Ok(state
+        .lawsuits
+        .into_iter()
+        .find(|lawsuit| lawsuit.id == lawsuit_id && !lawsuit.is_closed()))
+}
+
+async fn save(mongo: &Mongo, guild_id: GuildId, lawsuit: &Lawsuit) -> Result<()> {
+    mongo
+        .set_lawsuit(
+            guild_id.into(),
+            lawsuit.id,
+            doc! { "lawsuits.$.subpoenas": bson::to_bson(&lawsuit.subpoenas).wrap_err("invalid bson for subpoenas")? },
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{DateTime, Uuid};
+    use poise::serenity_prelude::{GuildId, MessageId};
+
+    use super::{custom_id, first_message_id, parse_id, SUBPOENA_ID_PREFIX};
+
+    #[test]
+    fn custom_ids() {
+        let (lawsuit_id, subpoena_id) = (Uuid::new(), Uuid::new());
+        let id = custom_id(GuildId(42), lawsuit_id, subpoena_id, false);
+        let id = id.strip_prefix(SUBPOENA_ID_PREFIX).unwrap();
+        assert_eq!(
+            parse_id(id),
+            Some((GuildId(42), lawsuit_id, subpoena_id, false))
+        );
+        assert_eq!(parse_id("42:nöd"), None);
+    }
+
+    #[test]
+    fn message_ids() {
+        // the example from the Discord documentation
+        let time = DateTime::from_millis(1_462_015_105_796);
+        assert_eq!(
+            first_message_id(time),
+            MessageId(175928847299117056 >> 22 << 22)
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! In-memory implementations of [`Storage`] and [`DiscordApi`] for tests.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use color_eyre::Result;
+use mongodb::bson::DateTime;
+use poise::serenity_prelude::{ChannelId, GuildId, RoleId, UserId};
+
+use crate::{
+    discord_api::DiscordApi,
+    economy::{Debt, TransactionKind},
+    lawsuit::Lawsuit,
+    model::{PrisonEntry, SnowflakeId, State},
+    scheduler::{Job, JobKind},
+    storage::Storage,
+};
+
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    pub states: Mutex<HashMap<SnowflakeId, State>>,
+    pub prison: Mutex<Vec<PrisonEntry>>,
+    /// The balance of every account, by guild and user.
+    pub accounts: Mutex<HashMap<(SnowflakeId, SnowflakeId), i64>>,
+    /// The ledger, as the account, the amount and the kind of every transaction.
+    pub transactions: Mutex<Vec<(SnowflakeId, i64, TransactionKind)>>,
+    pub debts: Mutex<Vec<Debt>>,
+    pub jobs: Mutex<Vec<Job>>,
+}
+
+impl MemoryStorage {
+    pub fn with_state(state: State) -> Self {
+        let storage = Self::default();
+        storage.states.lock().unwrap().insert(state.guild_id, state);
+        storage
+    }
+
+    pub fn state(&self, guild_id: GuildId) -> State {
+        self.states.lock().unwrap()[&guild_id.into()].clone()
+    }
+
+    pub fn balance(&self, guild_id: GuildId, user_id: SnowflakeId) -> i64 {
+        self.accounts
+            .lock()
+            .unwrap()
+            .get(&(guild_id.into(), user_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn update_state(&self, guild_id: SnowflakeId, update: impl FnOnce(&mut State)) {
+        if let Some(state) = self.states.lock().unwrap().get_mut(&guild_id) {
+            update(state);
+        }
+    }
+
+    pub fn prison_entry(&self, guild_id: GuildId, user_id: UserId) -> Option<PrisonEntry> {
+        let (guild_id, user_id) = (guild_id.into(), user_id.into());
+        self.prison
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|entry| entry.guild_id == guild_id && entry.user_id == user_id)
+            .cloned()
+    }
+}
+
+impl Storage for MemoryStorage {
+    async fn find_or_insert_state(&self, guild_id: SnowflakeId) -> Result<State> {
+        Ok(self
+            .states
+            .lock()
+            .unwrap()
+            .entry(guild_id)
+            .or_insert_with(|| State::new(guild_id))
+            .clone())
+    }
+
+    async fn add_to_prison(&self, guild_id: SnowflakeId, user_id: SnowflakeId) -> Result<bool> {
+        let mut prison = self.prison.lock().unwrap();
+        if prison
+            .iter()
+            .any(|entry| entry.guild_id == guild_id && entry.user_id == user_id)
+        {
+            return Ok(false);
+        }
+        prison.push(PrisonEntry {
+            guild_id,
+            user_id,
+            arrested_at: Some(DateTime::now()),
+            left_at: None,
+        });
+        Ok(true)
+    }
+
+    async fn remove_from_prison(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<bool> {
+        let mut prison = self.prison.lock().unwrap();
+        let len = prison.len();
+        prison.retain(|entry| entry.guild_id != guild_id || entry.user_id != user_id);
+        Ok(prison.len() < len)
+    }
+
+    async fn find_prison_entry(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+    ) -> Result<Option<PrisonEntry>> {
+        Ok(self.prison_entry(guild_id.into(), user_id.into()))
+    }
+
+    async fn set_prisoner_left(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        left_at: Option<DateTime>,
+    ) -> Result<()> {
+        let mut prison = self.prison.lock().unwrap();
+        for entry in prison
+            .iter_mut()
+            .filter(|entry| entry.guild_id == guild_id && entry.user_id == user_id)
+        {
+            entry.left_at = left_at;
+        }
+        Ok(())
+    }
+
+    async fn delete_release_jobs(&self, guild_id: SnowflakeId, user_id: SnowflakeId) -> Result<()> {
+        self.jobs.lock().unwrap().retain(|job| {
+            job.guild_id != guild_id
+                || !matches!(job.kind, JobKind::Release { user_id: id } if id == user_id)
+        });
+        Ok(())
+    }
+
+    async fn next_case_number(&self, guild_id: SnowflakeId) -> Result<u64> {
+        let mut states = self.states.lock().unwrap();
+        let state = states
+            .entry(guild_id)
+            .or_insert_with(|| State::new(guild_id));
+        state.case_counter += 1;
+        Ok(state.case_counter)
+    }
+
+    async fn save_lawsuit(&self, guild_id: SnowflakeId, lawsuit: &Lawsuit) -> Result<()> {
+        self.update_state(guild_id, |state| {
+            state.lawsuits.retain(|l| l.id != lawsuit.id);
+            state.lawsuits.push(lawsuit.clone());
+        });
+        Ok(())
+    }
+
+    async fn set_verdict(&self, guild_id: SnowflakeId, lawsuit: &Lawsuit) -> Result<()> {
+        self.save_lawsuit(guild_id, lawsuit).await
+    }
+
+    async fn claim_court_room(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+    ) -> Result<bool> {
+        let mut claimed = false;
+        self.update_state(guild_id, |state| {
+            if let Some(room) = state
+                .court_rooms
+                .iter_mut()
+                .find(|room| room.channel_id == channel_id && !room.ongoing_lawsuit)
+            {
+                room.ongoing_lawsuit = true;
+                claimed = true;
+            }
+        });
+        Ok(claimed)
+    }
+
+    async fn set_court_room_ongoing(
+        &self,
+        guild_id: SnowflakeId,
+        channel_id: SnowflakeId,
+        ongoing: bool,
+    ) -> Result<()> {
+        self.update_state(guild_id, |state| {
+            for room in state
+                .court_rooms
+                .iter_mut()
+                .filter(|room| room.channel_id == channel_id)
+            {
+                room.ongoing_lawsuit = ongoing;
+            }
+        });
+        Ok(())
+    }
+
+    async fn credit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        _: &str,
+    ) -> Result<i64> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let balance = accounts.entry((guild_id, user_id)).or_default();
+        *balance += amount;
+        self.transactions
+            .lock()
+            .unwrap()
+            .push((user_id, amount, kind));
+        Ok(*balance)
+    }
+
+    async fn try_debit(
+        &self,
+        guild_id: SnowflakeId,
+        user_id: SnowflakeId,
+        amount: i64,
+        kind: TransactionKind,
+        _: &str,
+    ) -> Result<Result<i64, i64>> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let balance = accounts.entry((guild_id, user_id)).or_default();
+        if *balance < amount {
+            return Ok(Err(amount - *balance));
+        }
+        *balance -= amount;
+        self.transactions
+            .lock()
+            .unwrap()
+            .push((user_id, -amount, kind));
+        Ok(Ok(*balance))
+    }
+
+    async fn find_debts(&self, guild_id: SnowflakeId, debtor: SnowflakeId) -> Result<Vec<Debt>> {
+        Ok(self
+            .debts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|debt| debt.guild_id == guild_id && debt.debtor == debtor && debt.amount > 0)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Records the requests instead of sending them. Only members that were added are in the guild.
+#[derive(Debug, Default)]
+pub struct FakeDiscord {
+    pub members: Mutex<HashMap<(GuildId, UserId), HashSet<RoleId>>>,
+    pub messages: Mutex<Vec<(ChannelId, String)>>,
+    pub role_requests: Mutex<usize>,
+    /// Accepts role changes without applying them, like when someone reverts them right away.
+    pub ignore_role_changes: bool,
+}
+
+impl FakeDiscord {
+    pub fn add_member(&self, guild_id: GuildId, user_id: UserId, roles: &[RoleId]) {
+        self.members
+            .lock()
+            .unwrap()
+            .insert((guild_id, user_id), roles.iter().copied().collect());
+    }
+
+    pub fn has_role(&self, guild_id: GuildId, user_id: UserId, role_id: RoleId) -> bool {
+        self.members
+            .lock()
+            .unwrap()
+            .get(&(guild_id, user_id))
+            .is_some_and(|roles| roles.contains(&role_id))
+    }
+
+    pub fn role_requests(&self) -> usize {
+        *self.role_requests.lock().unwrap()
+    }
+
+    fn change_role(&self, guild_id: GuildId, user_id: UserId, role_id: RoleId, add: bool) {
+        *self.role_requests.lock().unwrap() += 1;
+        if self.ignore_role_changes {
+            return;
+        }
+        if let Some(roles) = self.members.lock().unwrap().get_mut(&(guild_id, user_id)) {
+            if add {
+                roles.insert(role_id);
+            } else {
+                roles.remove(&role_id);
+            }
+        }
+    }
+}
+
+impl DiscordApi for FakeDiscord {
+    async fn member_roles(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Option<Vec<RoleId>>> {
+        Ok(self
+            .members
+            .lock()
+            .unwrap()
+            .get(&(guild_id, user_id))
+            .map(|roles| roles.iter().copied().collect()))
+    }
+
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        self.change_role(guild_id, user_id, role_id, true);
+        Ok(())
+    }
+
+    async fn remove_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<()> {
+        self.change_role(guild_id, user_id, role_id, false);
+        Ok(())
+    }
+
+    async fn send_message(&self, channel_id: ChannelId, content: String) -> Result<()> {
+        self.messages.lock().unwrap().push((channel_id, content));
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use color_eyre::Result;
+use mongodb::bson::{self, Bson, DateTime, Uuid};
+use poise::serenity_prelude::{GuildId, Http};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    economy::{treasury_account, TransactionKind},
+    events,
+    events::{Change, Event},
+    handler::Response,
+    model::SnowflakeId,
+    prison, Mongo, WrapErr,
+};
+
+/// How long after an action it can still be undone.
+pub const UNDO_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// The fields of the state with roles that can be set back.
+const ROLE_FIELDS: [&str; 3] = ["prison_role", "judge_role", "case_ping_role"];
+
+/// A fine as it's stored in its event, since the accounts aren't part of the snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFine {
+    pub id: Uuid,
+    pub user_id: SnowflakeId,
+    pub amount: i64,
+    /// The debt for the part that couldn't be paid right away.
+    pub debt_id: Option<Uuid>,
+}
+
+/// Records the fine as an event, so that it can be undone.
+pub async fn record_fine(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    actor: SnowflakeId,
+    fine: &RecordedFine,
+) -> Result<()> {
+    let change = Change {
+        path: format!("fines[{}]", fine.id),
+        before: None,
+        after: Some(bson::to_bson(fine).wrap_err("serialize fine")?),
+    };
+    events::record_changes(mongo, guild_id, Some(actor), "/economy fine", vec![change]).await;
+    Ok(())
+}
+
+/// How an event can be undone.
+#[derive(Debug, Clone)]
+pub enum Reversal {
+    Arrest { user_id: SnowflakeId },
+    Release { user_id: SnowflakeId },
+    Fine(RecordedFine),
+    RoleConfig { changes: Vec<Change> },
+}
+
+impl Display for Reversal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Arrest { user_id } => write!(f, "d'Verhaftig vo <@{user_id}>"),
+            Self::Release { user_id } => write!(f, "d'Freilassig vo <@{user_id}>"),
+            Self::Fine(fine) => write!(f, "d'Busse vo {} Fr. für <@{}>", fine.amount, fine.user_id),
+            Self::RoleConfig { changes } => {
+                let roles = changes
+                    .iter()
+                    .map(|change| match &change.after {
+                        Some(Bson::String(role)) => format!("<@&{role}>"),
+                        _ => "kei Rolle".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "d'Rolle-Iistellig ({roles})")
+            }
+        }
+    }
+}
+
+impl Reversal {
+    /// How the event can be undone, if it's an arrest, release, fine or change of role settings
+    /// and nothing else.
+    pub fn of(event: &Event) -> Option<Self> {
+        if event.undoes.is_some() {
+            return None;
+        }
+
+        if let [change] = event.changes.as_slice() {
+            if let Some(user_id) = change.path.strip_prefix("prison[") {
+                let user_id = user_id.trim_end_matches(']').parse().ok()?;
+                return match (&change.before, &change.after) {
+                    (None, Some(_)) => Some(Self::Arrest { user_id }),
+                    (Some(_), None) => Some(Self::Release { user_id }),
+                    _ => None,
+                };
+            }
+            if change.path.starts_with("fines[") {
+                let fine = bson::from_bson(change.after.clone()?).ok()?;
+                return Some(Self::Fine(fine));
+            }
+        }
+
+        let only_roles = !event.changes.is_empty()
+            && event.changes.iter().all(|change| {
+                change
+                    .path
+                    .strip_prefix("state.")
+                    .is_some_and(|field| ROLE_FIELDS.contains(&field))
+            });
+        only_roles.then(|| Self::RoleConfig {
+            changes: event.changes.clone(),
+        })
+    }
+
+    /// Reverses the action. Returns a response if it can't be reversed anymore, like when someone
+    /// changed the same thing in the meantime.
+    #[tracing::instrument(skip(mongo, http))]
+    pub async fn apply(
+        &self,
+        mongo: &Mongo,
+        http: &Arc<Http>,
+        guild_id: GuildId,
+    ) -> Result<Result<Vec<Change>, Response>> {
+        match self {
+            Self::Arrest { user_id } => {
+                let before = events::snapshot(mongo, guild_id.into()).await?;
+                if let Err(response) =
+                    prison::release(mongo, http, guild_id, (*user_id).into()).await?
+                {
+                    return Ok(Err(response));
+                }
+                let after = events::snapshot(mongo, guild_id.into()).await?;
+                Ok(Ok(events::diff(&before, &after)))
+            }
+            Self::Release { user_id } => {
+                let before = events::snapshot(mongo, guild_id.into()).await?;
+                if let Err(response) =
+                    prison::arrest(mongo, http, guild_id, (*user_id).into()).await?
+                {
+                    return Ok(Err(response));
+                }
+                let after = events::snapshot(mongo, guild_id.into()).await?;
+                Ok(Ok(events::diff(&before, &after)))
+            }
+            Self::Fine(fine) => refund_fine(mongo, guild_id.into(), fine).await.map(Ok),
+            Self::RoleConfig { changes } => {
+                restore_roles(mongo, guild_id.into(), changes).await.map(Ok)
+            }
+        }
+    }
+}
+
+/// Undoes the event, unless someone else is undoing it or did already. The undo event is stored
+/// first, which only succeeds once per event, and gets the changes once the reversal is applied.
+/// Returns the changes, which are empty if there was nothing left to undo.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn undo(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    actor: SnowflakeId,
+    event: &Event,
+    reversal: &Reversal,
+) -> Result<Result<Vec<Change>, Response>> {
+    let undo = Event {
+        id: Uuid::new(),
+        guild_id: guild_id.into(),
+        actor: Some(actor),
+        action: "/admin undo".to_string(),
+        changes: vec![],
+        timestamp: DateTime::now(),
+        undoes: Some(event.id),
+    };
+    if !mongo.claim_undo(&undo).await? {
+        return Ok(Err(Response(
+            "das isch scho rückgängig gmacht worde".to_string(),
+        )));
+    }
+
+    let changes = match reversal.apply(mongo, http, guild_id).await {
+        Ok(Ok(changes)) if !changes.is_empty() => changes,
+        result => {
+            mongo.delete_event(undo.id).await?;
+            return result;
+        }
+    };
+    mongo.set_event_changes(undo.id, &changes).await?;
+
+    info!(%guild_id, event_id = %event.id, ?reversal, "Undid action");
+
+    Ok(Ok(changes))
+}
+
+/// Pays back everything that was paid of the fine and cancels the rest of the debt.
+async fn refund_fine(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    fine: &RecordedFine,
+) -> Result<Vec<Change>> {
+    let remaining = match fine.debt_id {
+        Some(debt_id) => match mongo.find_debt(debt_id).await? {
+            Some(debt) => {
+                mongo.reduce_debt(debt_id, debt.amount).await?;
+                debt.amount
+            }
+            None => 0,
+        },
+        None => 0,
+    };
+    let paid = fine.amount - remaining;
+
+    if paid > 0 {
+        let description = "Busse rückgängig gmacht";
+        mongo
+            .credit(
+                guild_id,
+                fine.user_id,
+                paid,
+                TransactionKind::FineRefund,
+                description,
+            )
+            .await?;
+        mongo
+            .credit(
+                guild_id,
+                treasury_account(guild_id),
+                -paid,
+                TransactionKind::FineRefund,
+                description,
+            )
+            .await?;
+    }
+
+    info!(fine_id = %fine.id, paid, remaining, "Refunded fine");
+
+    Ok(vec![Change {
+        path: format!("fines[{}]", fine.id),
+        before: Some(bson::to_bson(fine).wrap_err("serialize fine")?),
+        after: None,
+    }])
+}
+
+/// Sets the roles back, unless someone changed them again in the meantime.
+async fn restore_roles(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    changes: &[Change],
+) -> Result<Vec<Change>> {
+    let mut restored = Vec::new();
+    for change in changes {
+        let field = change.path.trim_start_matches("state.");
+        if mongo
+            .restore_state_field(guild_id, field, change.after.clone(), change.before.clone())
+            .await?
+        {
+            restored.push(Change {
+                path: change.path.clone(),
+                before: change.after.clone(),
+                after: change.before.clone(),
+            });
+        }
+    }
+    Ok(restored)
+}
+
+/// The latest action of the user in the guild that can still be undone.
+#[tracing::instrument(skip(mongo))]
+pub async fn find_last(
+    mongo: &Mongo,
+    guild_id: SnowflakeId,
+    actor: SnowflakeId,
+) -> Result<Option<(Event, Reversal)>> {
+    let since =
+        DateTime::from_millis(DateTime::now().timestamp_millis() - UNDO_WINDOW.as_millis() as i64);
+    let events = mongo.find_events_by_actor(guild_id, actor, since).await?;
+
+    Ok(last_reversible(events))
+}
+
+fn last_reversible(events: Vec<Event>) -> Option<(Event, Reversal)> {
+    let undone = events
+        .iter()
+        .filter_map(|event| event.undoes)
+        .collect::<Vec<_>>();
+
+    events
+        .into_iter()
+        .filter(|event| !undone.contains(&event.id))
+        .find_map(|event| Reversal::of(&event).map(|reversal| (event, reversal)))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{doc, Bson, DateTime, Uuid};
+
+    use super::{last_reversible, Reversal};
+    use crate::{
+        events::{diff, Change, Event},
+        model::SnowflakeId,
+    };
+
+    fn event(changes: Vec<Change>) -> Event {
+        Event {
+            id: Uuid::new(),
+            guild_id: SnowflakeId(1),
+            actor: Some(SnowflakeId(2)),
+            action: "test".to_string(),
+            changes,
+            timestamp: DateTime::now(),
+            undoes: None,
+        }
+    }
+
+    #[test]
+    fn arrest_and_release() {
+        let free = doc! { "state": {}, "prison": [] };
+        let imprisoned = doc! { "state": {}, "prison": [{ "user_id": "3" }] };
+
+        let arrest = Reversal::of(&event(diff(&free, &imprisoned)));
+        assert!(matches!(
+            arrest,
+            Some(Reversal::Arrest {
+                user_id: SnowflakeId(3)
+            })
+        ));
+
+        let release = Reversal::of(&event(diff(&imprisoned, &free)));
+        assert!(matches!(
+            release,
+            Some(Reversal::Release {
+                user_id: SnowflakeId(3)
+            })
+        ));
+    }
+
+    #[test]
+    fn role_config() {
+        let before = doc! { "state": { "judge_role": Bson::Null, "case_counter": 1 } };
+        let roles = doc! { "state": { "judge_role": "4", "case_counter": 1 } };
+        let other = doc! { "state": { "judge_role": "4", "case_counter": 2 } };
+
+        let reversal = Reversal::of(&event(diff(&before, &roles)));
+        assert!(matches!(reversal, Some(Reversal::RoleConfig { .. })));
+
+        assert!(Reversal::of(&event(diff(&before, &other))).is_none());
+        assert!(Reversal::of(&event(vec![])).is_none());
+    }
+
+    #[test]
+    fn skips_undone_events() {
+        let free = doc! { "state": {}, "prison": [] };
+        let imprisoned = doc! { "state": {}, "prison": [{ "user_id": "3" }] };
+        let other = doc! { "state": { "case_counter": 1 }, "prison": [] };
+
+        let first = event(diff(&free, &imprisoned));
+        let second = event(diff(&free, &imprisoned));
+        let undo = Event {
+            undoes: Some(second.id),
+            ..event(diff(&imprisoned, &free))
+        };
+        let unrelated = event(diff(&free, &other));
+
+        // newest first
+        let (found, _) = last_reversible(vec![unrelated, undo, second, first.clone()]).unwrap();
+        assert_eq!(found.id, first.id);
+    }
+}
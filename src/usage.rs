@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{logging::Invocation, model::SnowflakeId, Context};
+
+/// How long command invocations are kept, enforced by a TTL index.
+pub const USAGE_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// A single command invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandUsage {
+    pub command: String,
+    pub guild_id: Option<SnowflakeId>,
+    pub user_id: SnowflakeId,
+    pub success: bool,
+    pub latency_ms: i64,
+    pub timestamp: DateTime,
+}
+
+/// How often a command was used in a time range.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandStats {
+    #[serde(rename = "_id")]
+    pub command: String,
+    pub count: i64,
+    pub failures: i64,
+    pub avg_latency_ms: f64,
+}
+
+/// How many commands were used in a guild in a time range.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GuildActivity {
+    #[serde(rename = "_id")]
+    pub guild_id: Option<SnowflakeId>,
+    pub count: i64,
+    pub users: i64,
+}
+
+impl CommandStats {
+    /// The share of failed invocations in percent.
+    pub fn error_rate(&self) -> f64 {
+        self.failures as f64 / self.count as f64 * 100.0
+    }
+}
+
+/// Stores the invocation. The latency is measured from the pre-command hook. Failures are only
+/// logged, the command already ran.
+pub async fn record(ctx: Context<'_>, success: bool) {
+    let latency = match ctx.invocation_data::<Invocation>().await {
+        Some(invocation) => invocation.started_at.elapsed(),
+        None => return,
+    };
+
+    let usage = CommandUsage {
+        command: ctx.command().qualified_name.clone(),
+        guild_id: ctx.guild_id().map(Into::into),
+        user_id: ctx.author().id.into(),
+        success,
+        latency_ms: latency.as_millis() as i64,
+        timestamp: DateTime::now(),
+    };
+
+    if let Err(err) = ctx.data().mongo.add_command_usage(&usage).await {
+        warn!(?err, "Failed to record command usage");
+    }
+}
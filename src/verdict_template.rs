@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{lawsuit::Lawsuit, messages};
+
+/// The placeholders that are replaced in a verdict template, written as `{name}`.
+pub const PLACEHOLDERS: [&str; 4] = ["accused", "plaintiff", "law", "number"];
+
+/// Autocomplete can't offer more choices.
+const MAX_CHOICES: usize = 25;
+
+/// A prepared verdict judges can pick when closing a lawsuit with `/lawsuit close`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerdictTemplate {
+    pub name: String,
+    pub text: String,
+    /// The law the verdict is based on, filled into `{law}`.
+    #[serde(default)]
+    pub law: Option<String>,
+}
+
+impl VerdictTemplate {
+    /// The verdict for the lawsuit, with the placeholders replaced.
+    pub fn fill(&self, lawsuit: &Lawsuit) -> String {
+        messages::render(
+            &self.text,
+            &[
+                ("accused", format!("<@{}>", lawsuit.accused)),
+                ("plaintiff", lawsuit.plaintiff_mention()),
+                ("law", self.law.clone().unwrap_or_default()),
+                ("number", lawsuit.number.to_string()),
+            ],
+        )
+    }
+}
+
+/// The names of the templates that contain what was typed so far, for autocomplete.
+pub fn matching(templates: &[VerdictTemplate], partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+    templates
+        .iter()
+        .filter(|template| template.name.to_lowercase().contains(&partial))
+        .map(|template| template.name.clone())
+        .take(MAX_CHOICES)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matching, VerdictTemplate};
+    use crate::{lawsuit::Lawsuit, model::SnowflakeId};
+
+    fn template(name: &str) -> VerdictTemplate {
+        VerdictTemplate {
+            name: name.to_string(),
+            text: "{accused} isch schuldig wäge {law} (Prozess #{number})".to_string(),
+            law: Some("§3".to_string()),
+        }
+    }
+
+    #[test]
+    fn fill() {
+        let mut lawsuit = Lawsuit::new(
+            SnowflakeId(1),
+            SnowflakeId(2),
+            SnowflakeId(3),
+            "gmein".to_string(),
+        );
+        lawsuit.number = 7;
+        assert_eq!(
+            template("Schuldig").fill(&lawsuit),
+            "<@2> isch schuldig wäge §3 (Prozess #7)"
+        );
+    }
+
+    #[test]
+    fn autocomplete() {
+        let templates = [
+            template("Schuldig – Beleidigung §3"),
+            template("Freispruch"),
+        ];
+        assert_eq!(
+            matching(&templates, "beleid"),
+            vec!["Schuldig – Beleidigung §3".to_string()]
+        );
+        assert_eq!(matching(&templates, "").len(), 2);
+    }
+}
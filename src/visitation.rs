@@ -0,0 +1,261 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::Result;
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::{
+    ButtonStyle, ChannelId, Http, PermissionOverwrite, PermissionOverwriteType, Permissions, UserId,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    duration::format_date_time, model::SnowflakeId, sandbox, scheduler, scheduler::JobKind, Mongo,
+    WrapErr,
+};
+
+/// The prefix of the custom id of the buttons that decide a visit, followed by `{visit}:{approve}`.
+pub const VISIT_ID_PREFIX: &str = "visit:";
+
+/// How long a visit lasts if the guild didn't configure it.
+pub const DEFAULT_VISIT_MINUTES: u32 = 30;
+
+/// Where and for how long prisoners can be visited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisitationConfig {
+    /// The prison channel that visitors get access to.
+    pub channel_id: SnowflakeId,
+    pub minutes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisitStatus {
+    Requested,
+    Approved,
+    Denied,
+}
+
+/// A visit to a prisoner. The visitor can see and write in the prison channel from the start of
+/// the visit until its end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    pub id: Uuid,
+    pub guild_id: SnowflakeId,
+    pub prisoner: SnowflakeId,
+    pub visitor: SnowflakeId,
+    /// The channel at the time of the request, so a later change of the configuration doesn't keep
+    /// the visitor in the old one.
+    pub channel_id: SnowflakeId,
+    pub start: DateTime,
+    pub minutes: u32,
+    pub status: VisitStatus,
+    pub decided_by: Option<SnowflakeId>,
+}
+
+impl Visit {
+    pub fn end(&self) -> DateTime {
+        let duration = Duration::from_secs(u64::from(self.minutes) * 60);
+        DateTime::from_millis(self.start.timestamp_millis() + duration.as_millis() as i64)
+    }
+}
+
+/// Parses the `{visit}:{approve}` part of a decision custom id.
+pub fn parse_decision_id(id: &str) -> Option<(Uuid, bool)> {
+    let (visit_id, approve) = id.split_once(':')?;
+    Some((Uuid::parse_str(visit_id).ok()?, approve.parse().ok()?))
+}
+
+/// Stores the request and asks for a decision in the log channel.
+#[tracing::instrument(skip(mongo, http, visit), fields(visit_id = %visit.id))]
+pub async fn request(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    log_channel: SnowflakeId,
+    visit: &Visit,
+    tz: chrono_tz::Tz,
+) -> Result<()> {
+    mongo.add_visit(visit).await?;
+
+    let content = format!(
+        "<@{}> möcht <@{}> am {} für {} Minute bsueche",
+        visit.visitor,
+        visit.prisoner,
+        format_date_time(visit.start, tz),
+        visit.minutes
+    );
+    ChannelId::from(log_channel)
+        .send_message(http, |msg| {
+            msg.content(content).components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(format!("{VISIT_ID_PREFIX}{}:true", visit.id))
+                            .label("Erlaube")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|button| {
+                        button
+                            .custom_id(format!("{VISIT_ID_PREFIX}{}:false", visit.id))
+                            .label("Ablehne")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await
+        .wrap_err("send visit request")?;
+
+    Ok(())
+}
+
+/// Approves or denies a requested visit, schedules the access of an approved one and tells the
+/// visitor. Returns `None` if the visit was decided already.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn decide(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    visit_id: Uuid,
+    decided_by: SnowflakeId,
+    approve: bool,
+) -> Result<Option<Visit>> {
+    let status = match approve {
+        true => VisitStatus::Approved,
+        false => VisitStatus::Denied,
+    };
+    let visit = match mongo.decide_visit(visit_id, status, decided_by).await? {
+        Some(visit) => visit,
+        None => return Ok(None),
+    };
+
+    if approve {
+        let start = JobKind::VisitStart { visit_id };
+        scheduler::schedule(mongo, visit.guild_id, start, visit.start).await?;
+        let end = JobKind::VisitEnd { visit_id };
+        scheduler::schedule(mongo, visit.guild_id, end, visit.end()).await?;
+    }
+    info!(?visit, "Decided visit");
+
+    let answer = match approve {
+        true => "isch erlaubt worde",
+        false => "isch abglehnt worde",
+    };
+    let message = format!("din Bsuech bi <@{}> {answer}", visit.prisoner);
+    let result = match UserId::from(visit.visitor).create_dm_channel(http).await {
+        Ok(channel) => channel.say(http, message).await.map(drop),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        warn!(?err, visitor = %visit.visitor, "Could not tell visitor about the decision");
+    }
+
+    Ok(Some(visit))
+}
+
+/// Lets the visitor into the prison channel, or out of it again.
+async fn set_access(http: &Arc<Http>, visit: &Visit, access: bool) -> Result<()> {
+    let (channel_id, visitor) = (
+        ChannelId::from(visit.channel_id),
+        UserId::from(visit.visitor),
+    );
+    let change = match access {
+        true => format!("<@{visitor}> dörf i <#{channel_id}>"),
+        false => format!("<@{visitor}> dörf nüm i <#{channel_id}>"),
+    };
+    if sandbox::simulate(http, visit.guild_id.into(), change).await {
+        return Ok(());
+    }
+
+    if access {
+        let overwrite = PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL
+                | Permissions::SEND_MESSAGES
+                | Permissions::READ_MESSAGE_HISTORY,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(visitor),
+        };
+        channel_id
+            .create_permission(http, &overwrite)
+            .await
+            .wrap_err("grant visit access")
+    } else {
+        channel_id
+            .delete_permission(http, PermissionOverwriteType::Member(visitor))
+            .await
+            .wrap_err("revoke visit access")
+    }
+}
+
+/// Posts the notice in the log channel of the guild, if it has one.
+async fn log(mongo: &Mongo, http: &Arc<Http>, guild_id: SnowflakeId, notice: String) -> Result<()> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    if let Some(log_channel) = state.log_channel {
+        if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+            warn!(?err, "Failed to send visit notice");
+        }
+    }
+    Ok(())
+}
+
+/// Starts an approved visit.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn start(mongo: &Mongo, http: &Arc<Http>, visit_id: Uuid) -> Result<()> {
+    let visit = match mongo.find_visit(visit_id).await? {
+        Some(visit) if visit.status == VisitStatus::Approved => visit,
+        _ => return Ok(()),
+    };
+
+    set_access(http, &visit, true).await?;
+    let notice = format!(
+        "de Bsuech vo <@{}> bi <@{}> het agfange",
+        visit.visitor, visit.prisoner
+    );
+    log(mongo, http, visit.guild_id, notice).await
+}
+
+/// Ends an approved visit.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn end(mongo: &Mongo, http: &Arc<Http>, visit_id: Uuid) -> Result<()> {
+    let visit = match mongo.find_visit(visit_id).await? {
+        Some(visit) if visit.status == VisitStatus::Approved => visit,
+        _ => return Ok(()),
+    };
+
+    set_access(http, &visit, false).await?;
+    let notice = format!(
+        "de Bsuech vo <@{}> bi <@{}> isch verbii",
+        visit.visitor, visit.prisoner
+    );
+    log(mongo, http, visit.guild_id, notice).await
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{DateTime, Uuid};
+
+    use super::{parse_decision_id, Visit, VisitStatus};
+    use crate::model::SnowflakeId;
+
+    #[test]
+    fn decision_id() {
+        let id = Uuid::new();
+        assert_eq!(parse_decision_id(&format!("{id}:true")), Some((id, true)));
+        assert_eq!(parse_decision_id(&format!("{id}:false")), Some((id, false)));
+        assert_eq!(parse_decision_id("kaputt:true"), None);
+    }
+
+    #[test]
+    fn end() {
+        let visit = Visit {
+            id: Uuid::new(),
+            guild_id: SnowflakeId(1),
+            prisoner: SnowflakeId(2),
+            visitor: SnowflakeId(3),
+            channel_id: SnowflakeId(4),
+            start: DateTime::from_millis(1_000),
+            minutes: 30,
+            status: VisitStatus::Requested,
+            decided_by: None,
+        };
+        assert_eq!(visit.end(), DateTime::from_millis(1_000 + 30 * 60 * 1000));
+    }
+}
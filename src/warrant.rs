@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use mongodb::bson::DateTime;
+use poise::serenity_prelude::{ChannelId, Http, MessageId};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::model::SnowflakeId;
+
+/// A wanted notice ("Fahndung") for a member, posted to the log channels of the issuing guild and
+/// the guilds it shares prison records with. Stored in the database of the issuing guild.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warrant {
+    pub guild_id: SnowflakeId,
+    pub user_id: SnowflakeId,
+    pub reason: String,
+    pub issued_by: SnowflakeId,
+    pub issued_at: DateTime,
+    /// The posted notices, deleted again when the warrant is resolved.
+    pub notices: Vec<WarrantNotice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarrantNotice {
+    pub channel_id: SnowflakeId,
+    pub message_id: SnowflakeId,
+}
+
+/// The text of the notice in the log channels.
+pub fn notice_text(user_id: SnowflakeId, reason: &str, issuing_guild: &str) -> String {
+    format!("🚨 **Fahndig** noch <@{user_id}>\nGrund: {reason}\nUsgschriebe vo: {issuing_guild}")
+}
+
+/// Posts the notice in the channels. Channels where posting fails are skipped, so one broken log
+/// channel doesn't stop the others.
+pub async fn post(http: &Arc<Http>, text: &str, channels: &[SnowflakeId]) -> Vec<WarrantNotice> {
+    let mut notices = vec![];
+    for &channel_id in channels {
+        match ChannelId::from(channel_id).say(http, text).await {
+            Ok(message) => notices.push(WarrantNotice {
+                channel_id,
+                message_id: message.id.into(),
+            }),
+            Err(err) => warn!(?err, %channel_id, "Failed to post warrant notice"),
+        }
+    }
+    notices
+}
+
+/// Deletes the notices of a resolved warrant. Notices that were deleted by hand already are
+/// skipped.
+pub async fn clear(http: &Arc<Http>, warrant: &Warrant) {
+    for notice in &warrant.notices {
+        let result = ChannelId::from(notice.channel_id)
+            .delete_message(http, MessageId::from(notice.message_id))
+            .await;
+        if let Err(err) = result {
+            warn!(?err, channel_id = %notice.channel_id, "Failed to delete warrant notice");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::notice_text;
+    use crate::model::SnowflakeId;
+
+    #[test]
+    fn notice() {
+        assert_eq!(
+            notice_text(SnowflakeId(1), "Flucht us em Gfängnis", "Bezirksgricht"),
+            "🚨 **Fahndig** noch <@1>\nGrund: Flucht us em Gfängnis\nUsgschriebe vo: Bezirksgricht"
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use color_eyre::{eyre::eyre, Result};
+use hmac::{Hmac, Mac};
+use mongodb::bson::{DateTime, Uuid};
+use poise::serenity_prelude::{ChannelId, GuildId, Http};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::{redirect, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use tracing::{info, warn};
+
+use crate::{lawsuit::Lawsuit, model::State, WrapErr};
+
+/// The header that contains the hex encoded HMAC-SHA256 of the body, keyed with the secret.
+const SIGNATURE_HEADER: &str = "X-Court-Signature";
+
+/// How often a delivery is attempted before giving up.
+const DELIVERY_ATTEMPTS: u32 = 4;
+
+/// How long to wait before the first retry. Doubles after every attempt.
+const RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    LawsuitCreated,
+    Verdict,
+    Arrest,
+    Release,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 4] = [
+        Self::LawsuitCreated,
+        Self::Verdict,
+        Self::Arrest,
+        Self::Release,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::LawsuitCreated => "lawsuit_created",
+            Self::Verdict => "verdict",
+            Self::Arrest => "arrest",
+            Self::Release => "release",
+        }
+    }
+
+    /// Parses a comma separated list of event names. No names means all events.
+    pub fn parse_list(input: &str) -> Option<Vec<Self>> {
+        let events = input
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| Self::ALL.into_iter().find(|event| event.name() == name))
+            .collect::<Option<Vec<_>>>()?;
+
+        if events.is_empty() {
+            Some(Self::ALL.to_vec())
+        } else {
+            Some(events)
+        }
+    }
+}
+
+/// An external endpoint that gets a signed JSON payload for court events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+/// The payload data of a lawsuit event. Anonymous plaintiffs are not revealed.
+pub fn lawsuit_data(lawsuit: &Lawsuit) -> serde_json::Value {
+    json!({
+        "number": lawsuit.number,
+        "reason": lawsuit.reason,
+        "plaintiff": (!lawsuit.anonymous).then(|| lawsuit.plaintiff.to_string()),
+        "accused": lawsuit.accused.to_string(),
+        "judge": lawsuit.judge.to_string(),
+        "case_type": lawsuit.case_type,
+        "verdict": lawsuit.verdict,
+        "winner": lawsuit.winner,
+        "damages": lawsuit.damages,
+        "community_service": lawsuit.community_service,
+        "prison_secs": lawsuit.prison_secs,
+        "probation_secs": lawsuit.probation_secs,
+    })
+}
+
+/// Sends the event to every webhook of the guild that subscribed to it. The deliveries run in the
+/// background and are retried with backoff. If a delivery fails for good, a notice is posted in
+/// the log channel.
+pub fn dispatch(http: &Arc<Http>, state: &State, event: WebhookEvent, data: serde_json::Value) {
+    let payload = json!({
+        "event": event.name(),
+        "guild_id": state.guild_id.to_string(),
+        "timestamp": DateTime::now().try_to_rfc3339_string().ok(),
+        "data": data,
+    });
+    let body = payload.to_string();
+
+    for webhook in state.webhooks.iter().filter(|w| w.events.contains(&event)) {
+        let http = http.clone();
+        let webhook = webhook.clone();
+        let body = body.clone();
+        let guild_id = GuildId::from(state.guild_id);
+        let log_channel = state.log_channel;
+
+        tokio::spawn(async move {
+            if let Err(err) = deliver(&webhook, &body).await {
+                warn!(?err, %guild_id, url = %webhook.url, "Failed to deliver webhook");
+                if let Some(log_channel) = log_channel {
+                    let notice = format!(
+                        "de Webhook `{}` hät s'Ereignis `{}` nöd chöne empfange: {err}",
+                        webhook.url,
+                        event.name()
+                    );
+                    if let Err(err) = ChannelId::from(log_channel).say(&http, notice).await {
+                        warn!(?err, "Failed to send webhook failure notice");
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// The hex encoded HMAC-SHA256 of the body.
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Checks that the URL is a public https endpoint and resolves its host. Webhooks must not reach
+/// the network of the bot, so loopback, private, link-local and unspecified addresses are
+/// rejected. Returns the host with the address to connect to, or why the URL can't be used.
+pub async fn resolve(url: &str) -> Result<(String, SocketAddr), &'static str> {
+    let url = Url::parse(url).map_err(|_| "das isch kei gültigi URL")?;
+    if url.scheme() != "https" {
+        return Err("de Webhook muess `https` bruche");
+    }
+    let host = url.host_str().ok_or("d'URL hät kein Host")?.to_owned();
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    // ipv6 hosts are in brackets
+    let ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>();
+    let addrs = match ip {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host.as_str(), port))
+            .await
+            .map_err(|_| "de Host vo de URL gits nöd")?
+            .collect(),
+    };
+
+    if addrs.is_empty() {
+        return Err("de Host vo de URL gits nöd");
+    }
+    if !addrs.iter().all(|addr| is_public(addr.ip())) {
+        return Err("de Webhook dörf kei interni Adrässe ha");
+    }
+    Ok((host, addrs[0]))
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    || ip.is_unspecified())
+            }
+        },
+    }
+}
+
+#[tracing::instrument(skip(webhook, body), fields(url = %webhook.url))]
+async fn deliver(webhook: &Webhook, body: &str) -> Result<()> {
+    // the url is checked again, its host could resolve somewhere else since it was added
+    let (host, addr) = resolve(&webhook.url)
+        .await
+        .map_err(|reason| eyre!("rejected webhook url: {reason}"))?;
+    // the checked address is used for the connection and redirects aren't followed, so that the
+    // request can't end up at an internal address after all
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .resolve(&host, addr)
+        .redirect(redirect::Policy::none())
+        .build()
+        .wrap_err("build http client")?;
+    let signature = format!("sha256={}", sign(&webhook.secret, body));
+
+    let mut backoff = RETRY_BACKOFF;
+    let mut last_error = eyre!("no delivery attempted");
+
+    for attempt in 1..=DELIVERY_ATTEMPTS {
+        let result = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.to_string())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match result {
+            Ok(_) => {
+                info!(attempt, "Delivered webhook");
+                return Ok(());
+            }
+            Err(err) => {
+                warn!(?err, attempt, "Webhook delivery attempt failed");
+                last_error = eyre!(err);
+            }
+        }
+
+        if attempt < DELIVERY_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    Err(last_error)
+}
+
+/// A random secret for signing the payloads of a new webhook.
+pub fn generate_secret() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve;
+
+    #[tokio::test]
+    async fn only_public_https_urls() {
+        assert!(resolve("https://93.184.216.34/hook").await.is_ok());
+        assert!(resolve("https://[2606:2800:220:1::1]/hook").await.is_ok());
+
+        for url in [
+            "http://93.184.216.34/hook",
+            "https://127.0.0.1/hook",
+            "https://10.0.0.1/hook",
+            "https://192.168.1.1:8080/hook",
+            "https://169.254.169.254/latest",
+            "https://0.0.0.0/hook",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+            "kei url",
+        ] {
+            assert!(resolve(url).await.is_err(), "{url} was accepted");
+        }
+    }
+}
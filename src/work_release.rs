@@ -0,0 +1,235 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::{eyre::ContextCompat, Result};
+use mongodb::bson::{Bson, DateTime, Uuid};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, Http, UserId};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    duration::format_date_time, events, events::Change, model::SnowflakeId, prison, Mongo, WrapErr,
+};
+
+/// The prefix of the custom id of the buttons that confirm a finished task, followed by
+/// `{task}:{confirm}`.
+pub const TASK_ID_PREFIX: &str = "task:";
+
+/// The longest description of a task, so the list of tasks stays readable.
+pub const TASK_DESCRIPTION_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkTaskStatus {
+    Open,
+    Claimed,
+    /// Done according to the prisoner, waiting for a warden to confirm it.
+    Submitted,
+}
+
+/// Work that a prisoner can do to be released earlier. The task is removed once a warden
+/// confirmed it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkTask {
+    pub id: Uuid,
+    pub description: String,
+    /// By how many hours the sentence of the prisoner is reduced.
+    pub hours_credit: u32,
+    pub created_by: SnowflakeId,
+    pub claimed_by: Option<SnowflakeId>,
+    pub status: WorkTaskStatus,
+}
+
+/// The release time after the credit for a task, but not before now.
+pub fn reduced_release(due: DateTime, hours_credit: u32, now: DateTime) -> DateTime {
+    let credit = Duration::from_secs(u64::from(hours_credit) * 60 * 60);
+    let due = due.timestamp_millis() - credit.as_millis() as i64;
+    DateTime::from_millis(due.max(now.timestamp_millis()))
+}
+
+/// Parses the `{task}:{confirm}` part of a confirmation custom id.
+pub fn parse_decision_id(id: &str) -> Option<(Uuid, bool)> {
+    let (task_id, confirm) = id.split_once(':')?;
+    Some((Uuid::parse_str(task_id).ok()?, confirm.parse().ok()?))
+}
+
+/// Asks the wardens in their channel to confirm the task that the prisoner finished.
+#[tracing::instrument(skip(http, task), fields(task_id = %task.id))]
+pub async fn submit(
+    http: &Arc<Http>,
+    warden_channel: SnowflakeId,
+    task: &WorkTask,
+    prisoner: SnowflakeId,
+) -> Result<()> {
+    ChannelId::from(warden_channel)
+        .send_message(http, |msg| {
+            msg.embed(|embed| {
+                embed
+                    .title("Arbeit erledigt")
+                    .description(&task.description)
+                    .field("Vo", format!("<@{prisoner}>"), true)
+                    .field("Gutschrift", format!("{} Stunde", task.hours_credit), true)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .custom_id(format!("{TASK_ID_PREFIX}{}:true", task.id))
+                            .label("Bestätige")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|button| {
+                        button
+                            .custom_id(format!("{TASK_ID_PREFIX}{}:false", task.id))
+                            .label("Ablehne")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await
+        .wrap_err("send finished task")?;
+
+    Ok(())
+}
+
+/// Confirms a submitted task and shortens the sentence of the prisoner by its credit, or gives
+/// it back to the prisoner to do it again. Tells the prisoner via DM and logs the decision.
+/// Returns `None` if the task was decided already.
+#[tracing::instrument(skip(mongo, http))]
+pub async fn decide(
+    mongo: &Mongo,
+    http: &Arc<Http>,
+    guild_id: SnowflakeId,
+    task_id: Uuid,
+    decided_by: SnowflakeId,
+    confirm: bool,
+) -> Result<Option<WorkTask>> {
+    let state = mongo.find_or_insert_state(guild_id).await?;
+    let task = match state.work_tasks.iter().find(|task| task.id == task_id) {
+        Some(task) => task.clone(),
+        None => return Ok(None),
+    };
+    let prisoner = task
+        .claimed_by
+        .wrap_err("submitted task without prisoner")?;
+
+    let (message, notice) = if confirm {
+        let before = events::snapshot(mongo, guild_id).await?;
+        if mongo
+            .take_work_task(guild_id, task_id, WorkTaskStatus::Submitted)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let mut changes = events::diff(&before, &events::snapshot(mongo, guild_id).await?);
+
+        let release = match prison::release_job(mongo, guild_id, prisoner).await? {
+            Some(job) => {
+                let due = reduced_release(job.due, task.hours_credit, DateTime::now());
+                mongo.set_job_due(job.id, due).await?;
+                // the jobs aren't part of the snapshot
+                changes.push(Change {
+                    path: format!("jobs[{}].due", job.id),
+                    before: Some(Bson::DateTime(job.due)),
+                    after: Some(Bson::DateTime(due)),
+                });
+                Some(due)
+            }
+            None => None,
+        };
+        events::record_changes(
+            mongo,
+            guild_id,
+            Some(decided_by),
+            "Arbeit bestätigt",
+            changes,
+        )
+        .await;
+
+        let message = match release {
+            Some(due) => format!(
+                "dini Arbeit isch bestätigt worde, du wirsch jetzt am {} freiglah:\n> {}",
+                format_date_time(due, state.timezone()),
+                task.description
+            ),
+            None => format!(
+                "dini Arbeit isch bestätigt worde, aber es git kei Freilassigsziit wo chönnt verchürzt werde:\n> {}",
+                task.description
+            ),
+        };
+        let notice = format!(
+            "<@{decided_by}> hät d'Arbeit vo <@{prisoner}> bestätigt, {} Stunde weniger",
+            task.hours_credit
+        );
+        (message, notice)
+    } else {
+        if !mongo
+            .set_work_task_status(
+                guild_id,
+                task_id,
+                WorkTaskStatus::Submitted,
+                WorkTaskStatus::Claimed,
+            )
+            .await?
+        {
+            return Ok(None);
+        }
+        let message = format!(
+            "dini Arbeit isch nöd aagnoh worde, du chasch sie nomal mache:\n> {}",
+            task.description
+        );
+        let notice = format!("<@{decided_by}> hät d'Arbeit vo <@{prisoner}> abglehnt");
+        (message, notice)
+    };
+    info!(?task, confirm, "Decided work task");
+
+    let result = match UserId::from(prisoner).create_dm_channel(http).await {
+        Ok(channel) => channel.say(http, message).await.map(drop),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        warn!(?err, user_id = %prisoner, "Could not tell prisoner about the decision");
+    }
+
+    if let Some(log_channel) = state.log_channel {
+        if let Err(err) = ChannelId::from(log_channel).say(http, notice).await {
+            warn!(?err, "Failed to send work task notice");
+        }
+    }
+
+    Ok(Some(task))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{DateTime, Uuid};
+
+    use super::{parse_decision_id, reduced_release};
+
+    #[test]
+    fn reduction() {
+        let hour = 60 * 60 * 1000;
+        let due = DateTime::from_millis(10 * hour);
+
+        assert_eq!(
+            reduced_release(due, 3, DateTime::from_millis(0)),
+            DateTime::from_millis(7 * hour)
+        );
+        assert_eq!(
+            reduced_release(due, 3, DateTime::from_millis(8 * hour)),
+            DateTime::from_millis(8 * hour)
+        );
+        assert_eq!(
+            reduced_release(due, 0, DateTime::from_millis(0)),
+            DateTime::from_millis(10 * hour)
+        );
+    }
+
+    #[test]
+    fn decision_id() {
+        let id = Uuid::new();
+        assert_eq!(parse_decision_id(&format!("{id}:true")), Some((id, true)));
+        assert_eq!(parse_decision_id("kaputt"), None);
+    }
+}